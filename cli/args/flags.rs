@@ -22,6 +22,7 @@ use deno_core::url::Url;
 use deno_graph::GraphKind;
 use deno_runtime::deno_permissions::parse_sys_kind;
 use deno_runtime::deno_permissions::PermissionsOptions;
+use deno_runtime::ops::process::ChildShutdownPolicy;
 use log::debug;
 use log::Level;
 use serde::Deserialize;
@@ -36,6 +37,7 @@ use std::num::NonZeroUsize;
 use std::path::Path;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 
 use crate::args::resolve_no_prompt;
 use crate::util::fs::canonicalize_path;
@@ -584,6 +586,8 @@ pub struct Flags {
   /// the language server is configured with an explicit cache option.
   pub cache_path: Option<PathBuf>,
   pub cached_only: bool,
+  pub broadcast_channel_ipc: Option<String>,
+  pub child_shutdown: ChildShutdownFlags,
   pub type_check_mode: TypeCheckMode,
   pub config_flag: ConfigFlag,
   pub node_modules_dir: Option<NodeModulesDirMode>,
@@ -614,6 +618,28 @@ pub struct Flags {
   pub allow_scripts: PackagesAllowedScripts,
 }
 
+/// How the children that are still running are shut down on exit, see
+/// [`ChildShutdownPolicy`].
+#[derive(Clone, Debug, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub struct ChildShutdownFlags {
+  pub signal: Option<String>,
+  /// The grace period in milliseconds.
+  pub timeout: Option<u64>,
+}
+
+impl ChildShutdownFlags {
+  pub fn to_policy(&self) -> ChildShutdownPolicy {
+    let default = ChildShutdownPolicy::default();
+    ChildShutdownPolicy {
+      signal: self.signal.clone().unwrap_or(default.signal),
+      grace_period: self
+        .timeout
+        .map(Duration::from_millis)
+        .unwrap_or(default.grace_period),
+    }
+  }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct PermissionFlags {
   pub allow_all: bool,
@@ -632,6 +658,7 @@ pub struct PermissionFlags {
   pub allow_write: Option<Vec<String>>,
   pub deny_write: Option<Vec<String>>,
   pub no_prompt: bool,
+  pub permission_audit: Option<String>,
 }
 
 impl PermissionFlags {
@@ -3066,18 +3093,19 @@ Docs: <c>https://docs.deno.com/go/permissions</>
   <g>-A, --allow-all</>                        Allow all permissions.
   <g>--no-prompt</>                        Always throw if required permission wasn't passed.
                                            <p(245)>Can also be set via the DENO_NO_PROMPT environment variable.</>
+      <g>--permission-audit=<<PATH>></>          Append a JSON line describing every permission check and its result to a file.
   <g>-R, --allow-read[=<<PATH>...]</>           Allow file system read access. Optionally specify allowed paths.
                                            <p(245)>--allow-read  |  --allow-read="/etc,/var/log.txt"</>
   <g>-W, --allow-write[=<<PATH>...]</>          Allow file system write access. Optionally specify allowed paths.
                                            <p(245)>--allow-write  |  --allow-write="/etc,/var/log.txt"</>
   <g>-N, --allow-net[=<<IP_OR_HOSTNAME>...]</>  Allow network access. Optionally specify allowed IP addresses and host names, with ports as necessary.
                                            <p(245)>--allow-net  |  --allow-net="localhost:8080,deno.land"</>
-  <g>-E, --allow-env[=<<VARIABLE_NAME>...]</>   Allow access to environment variables. Optionally specify accessible environment variables.
-                                           <p(245)>--allow-env  |  --allow-env="PORT,HOME,PATH"</>
+  <g>-E, --allow-env[=<<VARIABLE_NAME>...]</>   Allow access to environment variables. Optionally specify accessible environment variables, or patterns of them.
+                                           <p(245)>--allow-env  |  --allow-env="PORT,HOME,PATH"  |  --allow-env="AWS_*"</>
   <g>-S, --allow-sys[=<<API_NAME>...]</>        Allow access to OS information. Optionally allow specific APIs by function name.
                                            <p(245)>--allow-sys  |  --allow-sys="systemMemoryInfo,osRelease"</>
-      <g>--allow-run[=<<PROGRAM_NAME>...]</>    Allow running subprocesses. Optionally specify allowed runnable program names.
-                                           <p(245)>--allow-run  |  --allow-run="whoami,ps"</>
+      <g>--allow-run[=<<PROGRAM_NAME>...]</>    Allow running subprocesses. Optionally specify allowed runnable program names, and restrict their first argument.
+                                           <p(245)>--allow-run  |  --allow-run="whoami,ps"  |  --allow-run="curl,git:status,git:log"</>
       <g>--allow-ffi[=<<PATH>...]</>            (Unstable) Allow loading dynamic libraries. Optionally specify allowed directories or files.
                                            <p(245)>--allow-ffi  |  --allow-ffi="./libfoo.so"</>
   <g>    --deny-read[=<<PATH>...]</>            Deny file system read access. Optionally specify denied paths.
@@ -3090,7 +3118,7 @@ Docs: <c>https://docs.deno.com/go/permissions</>
                                            <p(245)>--deny-env  |  --deny-env="PORT,HOME,PATH"</>
   <g>-S, --deny-sys[=<<API_NAME>...]</>         Deny access to OS information. Optionally deny specific APIs by function name.
                                            <p(245)>--deny-sys  |  --deny-sys="systemMemoryInfo,osRelease"</>
-      <g>--deny-run[=<<PROGRAM_NAME>...]</>     Deny running subprocesses. Optionally specify denied runnable program names.
+      <g>--deny-run[=<<PROGRAM_NAME>...]</>     Deny running subprocesses. Optionally specify denied runnable program names, which take precedence over --allow-run.
                                            <p(245)>--deny-run  |  --deny-run="whoami,ps"</>
       <g>--deny-ffi[=<<PATH>...]</>             (Unstable) Deny loading dynamic libraries. Optionally specify denied directories or files.
                                            <p(245)>--deny-ffi  |  --deny-ffi="./libfoo.so"</>
@@ -3259,7 +3287,7 @@ Docs: <c>https://docs.deno.com/go/permissions</>
         .use_value_delimiter(true)
         .require_equals(true)
         .value_name("PROGRAM_NAME")
-        .help("Deny running subprocesses. Optionally specify denied runnable program names")
+        .help("Deny running subprocesses. Optionally specify denied runnable program names, which take precedence over --allow-run")
         .hide(true),
     )
     .arg(
@@ -3307,6 +3335,15 @@ Docs: <c>https://docs.deno.com/go/permissions</>
         .hide(true)
         .help("Always throw if required permission wasn't passed"),
     )
+    .arg(
+      Arg::new("permission-audit")
+        .long("permission-audit")
+        .require_equals(true)
+        .value_name("PATH")
+        .value_hint(ValueHint::FilePath)
+        .hide(true)
+        .help("Append a JSON line describing every permission check and its result to a file"),
+    )
 }
 
 fn runtime_args(
@@ -3329,6 +3366,9 @@ fn runtime_args(
     .arg(frozen_lockfile_arg())
     .arg(cached_only_arg())
     .arg(location_arg())
+    .arg(broadcast_channel_ipc_arg())
+    .arg(child_shutdown_signal_arg())
+    .arg(child_shutdown_timeout_arg())
     .arg(v8_flags_arg())
     .arg(seed_arg())
     .arg(enable_testing_features_arg())
@@ -3486,6 +3526,42 @@ fn location_arg() -> Arg {
     .value_hint(ValueHint::Url)
 }
 
+fn broadcast_channel_ipc_arg() -> Arg {
+  Arg::new("broadcast-channel-ipc")
+    .long("broadcast-channel-ipc")
+    .value_name("PATH")
+    .help("Share BroadcastChannel messages with other processes using the same Unix socket path, or named pipe on Windows")
+    .value_hint(ValueHint::FilePath)
+}
+
+fn child_shutdown_signal_arg() -> Arg {
+  Arg::new("child-shutdown-signal")
+    .long("child-shutdown-signal")
+    .value_name("SIGNAL")
+    .help(cstr!("Signal sent to the running subprocesses when exiting <p(245)>[default: SIGTERM]</>"))
+    .value_parser(|signal: &str| -> Result<String, String> {
+      #[cfg(unix)]
+      let valid =
+        deno_runtime::ops::signal::signal_str_to_int(signal).is_ok();
+      #[cfg(windows)]
+      let valid =
+        matches!(signal, "SIGKILL" | "SIGTERM" | "SIGBREAK");
+      if valid {
+        Ok(signal.to_string())
+      } else {
+        Err(format!("Invalid signal: {signal}"))
+      }
+    })
+}
+
+fn child_shutdown_timeout_arg() -> Arg {
+  Arg::new("child-shutdown-timeout")
+    .long("child-shutdown-timeout")
+    .value_name("MILLISECONDS")
+    .help(cstr!("Time to wait for the running subprocesses to exit before killing them when exiting <p(245)>[default: 1000]</>"))
+    .value_parser(value_parser!(u64))
+}
+
 fn enable_testing_features_arg() -> Arg {
   Arg::new("enable-testing-features-do-not-use")
     .long("enable-testing-features-do-not-use")
@@ -4791,6 +4867,9 @@ fn permission_args_parse(flags: &mut Flags, matches: &mut ArgMatches) {
   if matches.get_flag("no-prompt") {
     flags.permissions.no_prompt = true;
   }
+
+  flags.permissions.permission_audit =
+    matches.remove_one::<String>("permission-audit");
 }
 
 fn unsafely_ignore_certificate_errors_parse(
@@ -4822,6 +4901,8 @@ fn runtime_args_parse(
     inspect_arg_parse(flags, matches);
   }
   location_arg_parse(flags, matches);
+  broadcast_channel_ipc_arg_parse(flags, matches);
+  child_shutdown_arg_parse(flags, matches);
   v8_flags_arg_parse(flags, matches);
   seed_arg_parse(flags, matches);
   enable_testing_features_arg_parse(flags, matches);
@@ -4901,6 +4982,21 @@ fn v8_flags_arg_parse(flags: &mut Flags, matches: &mut ArgMatches) {
   }
 }
 
+fn broadcast_channel_ipc_arg_parse(
+  flags: &mut Flags,
+  matches: &mut ArgMatches,
+) {
+  flags.broadcast_channel_ipc =
+    matches.remove_one::<String>("broadcast-channel-ipc");
+}
+
+fn child_shutdown_arg_parse(flags: &mut Flags, matches: &mut ArgMatches) {
+  flags.child_shutdown = ChildShutdownFlags {
+    signal: matches.remove_one::<String>("child-shutdown-signal"),
+    timeout: matches.remove_one::<u64>("child-shutdown-timeout"),
+  };
+}
+
 fn seed_arg_parse(flags: &mut Flags, matches: &mut ArgMatches) {
   if let Some(seed) = matches.remove_one::<u64>("seed") {
     flags.seed = Some(seed);
@@ -7119,6 +7215,54 @@ mod tests {
     );
   }
 
+  #[test]
+  fn permission_audit() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--permission-audit=audit.jsonl",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags::new_default(
+          "script.ts".to_string(),
+        )),
+        permissions: PermissionFlags {
+          permission_audit: Some("audit.jsonl".to_string()),
+          ..Default::default()
+        },
+        code_cache_enabled: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn allow_run_constraints() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--allow-run=git:status,npm,git:log",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags::new_default(
+          "script.ts".to_string(),
+        )),
+        permissions: PermissionFlags {
+          allow_run: Some(svec!["git:status", "npm", "git:log"]),
+          ..Default::default()
+        },
+        code_cache_enabled: true,
+        ..Flags::default()
+      }
+    );
+  }
+
   #[test]
   fn allow_env_allowlist_multiple() {
     let r = flags_from_vec(svec![
@@ -7638,6 +7782,68 @@ mod tests {
     );
   }
 
+  #[test]
+  fn run_broadcast_channel_ipc() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--broadcast-channel-ipc",
+      "/tmp/deno.sock",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags::new_default(
+          "script.ts".to_string(),
+        )),
+        broadcast_channel_ipc: Some("/tmp/deno.sock".to_string()),
+        code_cache_enabled: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_child_shutdown() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--child-shutdown-signal",
+      "SIGINT",
+      "--child-shutdown-timeout",
+      "5000",
+      "script.ts"
+    ]);
+    let flags = r.unwrap();
+    assert_eq!(
+      flags,
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags::new_default(
+          "script.ts".to_string(),
+        )),
+        child_shutdown: ChildShutdownFlags {
+          signal: Some("SIGINT".to_string()),
+          timeout: Some(5000),
+        },
+        code_cache_enabled: true,
+        ..Flags::default()
+      }
+    );
+    let policy = flags.child_shutdown.to_policy();
+    assert_eq!(policy.signal, "SIGINT");
+    assert_eq!(policy.grace_period, Duration::from_secs(5));
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--child-shutdown-signal",
+      "SIGFOO",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
+  }
+
   #[test]
   fn run_seed_with_v8_flags() {
     let r = flags_from_vec(svec![
@@ -59,6 +59,7 @@ use deno_runtime::deno_tls::rustls::RootCertStore;
 use deno_runtime::deno_tls::rustls_pemfile;
 use deno_runtime::deno_tls::webpki_roots;
 use deno_runtime::inspector_server::InspectorServer;
+use deno_runtime::ops::process::ParentIpcChannel;
 use deno_terminal::colors;
 use dotenvy::from_filename;
 use once_cell::sync::Lazy;
@@ -1103,6 +1104,10 @@ impl CliOptions {
     }
   }
 
+  pub fn deno_ipc(&self) -> Option<ParentIpcChannel> {
+    ParentIpcChannel::from_env()
+  }
+
   pub fn serve_port(&self) -> Option<u16> {
     if let DenoSubcommand::Serve(flags) = self.sub_command() {
       Some(flags.port)
@@ -1488,6 +1493,14 @@ impl CliOptions {
     &self.flags.location
   }
 
+  pub fn broadcast_channel_ipc(&self) -> Option<&String> {
+    self.flags.broadcast_channel_ipc.as_ref()
+  }
+
+  pub fn child_shutdown_flags(&self) -> &ChildShutdownFlags {
+    &self.flags.child_shutdown
+  }
+
   pub fn no_remote(&self) -> bool {
     self.flags.no_remote
   }
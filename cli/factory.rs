@@ -849,6 +849,7 @@ impl CliFactory {
       is_inspecting: cli_options.is_inspecting(),
       is_npm_main: cli_options.is_npm_main(),
       location: cli_options.location_flag().clone(),
+      broadcast_channel_ipc: cli_options.broadcast_channel_ipc().cloned(),
       // if the user ran a binary command, we'll need to set process.argv[0]
       // to be the name of the binary command instead of deno
       argv0: cli_options
@@ -864,6 +865,7 @@ impl CliFactory {
       create_hmr_runner,
       create_coverage_collector,
       node_ipc: cli_options.node_ipc_fd(),
+      deno_ipc: cli_options.deno_ipc(),
       serve_port: cli_options.serve_port(),
       serve_host: cli_options.serve_host(),
     })
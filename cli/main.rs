@@ -56,6 +56,7 @@ use standalone::UNSUPPORTED_SCHEME;
 use std::env;
 use std::future::Future;
 use std::ops::Deref;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -357,6 +358,7 @@ fn exit_for_error(error: AnyError) -> ! {
     error_code = 10;
   }
 
+  deno_runtime::ops::process::shutdown_children();
   exit_with_message(&error_string, error_code);
 }
 
@@ -463,6 +465,16 @@ fn resolve_flags_and_init(
     None, /* import assertions enabled */ false,
   );
   util::logger::init(flags.log_level);
+  deno_runtime::ops::process::set_child_shutdown_policy(
+    flags.child_shutdown.to_policy(),
+  );
+
+  if let Some(path) = &flags.permissions.permission_audit {
+    deno_runtime::deno_permissions::open_audit_log(Path::new(path))
+      .with_context(|| {
+        format!("Failed to open the permission audit log '{path}'")
+      })?;
+  }
 
   Ok(flags)
 }
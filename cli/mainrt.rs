@@ -69,6 +69,7 @@ fn unwrap_or_exit<T>(result: Result<T, AnyError>) -> T {
         error_string = format_js_error(e);
       }
 
+      deno_runtime::ops::process::shutdown_children();
       exit_with_message(&error_string, 1);
     }
   }
@@ -91,6 +92,9 @@ fn main() {
       Ok(Some(future)) => {
         let (metadata, eszip) = future.await?;
         util::logger::init(metadata.log_level);
+        deno_runtime::ops::process::set_child_shutdown_policy(
+          metadata.child_shutdown.to_policy(),
+        );
         load_env_vars(&metadata.env_vars_from_env_file);
         let exit_code = standalone::run(eszip, metadata).await?;
         std::process::exit(exit_code);
@@ -43,6 +43,7 @@ use serde::Deserialize;
 use serde::Serialize;
 
 use crate::args::CaData;
+use crate::args::ChildShutdownFlags;
 use crate::args::CliOptions;
 use crate::args::CompileFlags;
 use crate::args::PackageJsonInstallDepsProvider;
@@ -108,6 +109,8 @@ pub struct Metadata {
   pub seed: Option<u64>,
   pub permissions: PermissionFlags,
   pub location: Option<Url>,
+  pub broadcast_channel_ipc: Option<String>,
+  pub child_shutdown: ChildShutdownFlags,
   pub v8_flags: Vec<String>,
   pub log_level: Option<Level>,
   pub ca_stores: Option<Vec<String>>,
@@ -576,6 +579,8 @@ impl<'a> DenoCompileBinaryWriter<'a> {
       argv: compile_flags.args.clone(),
       seed: cli_options.seed(),
       location: cli_options.location_flag().clone(),
+      broadcast_channel_ipc: cli_options.broadcast_channel_ipc().cloned(),
+      child_shutdown: cli_options.child_shutdown_flags().clone(),
       permissions: cli_options.permission_flags().clone(),
       v8_flags: cli_options.v8_flags().clone(),
       unsafely_ignore_certificate_errors: cli_options
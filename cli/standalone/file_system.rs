@@ -7,10 +7,14 @@ use std::sync::Arc;
 
 use deno_runtime::deno_fs::AccessCheckCb;
 use deno_runtime::deno_fs::FileSystem;
+use deno_runtime::deno_fs::FsDirEntries;
 use deno_runtime::deno_fs::FsDirEntry;
+use deno_runtime::deno_fs::FsDirReader;
+use deno_runtime::deno_fs::FsDirStatEntry;
 use deno_runtime::deno_fs::FsFileType;
 use deno_runtime::deno_fs::OpenOptions;
 use deno_runtime::deno_fs::RealFs;
+use deno_runtime::deno_fs::Reflink;
 use deno_runtime::deno_io::fs::File;
 use deno_runtime::deno_io::fs::FsError;
 use deno_runtime::deno_io::fs::FsResult;
@@ -34,6 +38,18 @@ impl DenoCompileFileSystem {
     }
   }
 
+  fn vfs_dir_entries(&self, path: &Path) -> FsResult<FsDirEntries> {
+    let mut entries = vec![];
+    for entry in self.0.read_dir(path)? {
+      let stat = self.0.lstat(&path.join(&entry.name))?;
+      entries.push(FsDirStatEntry {
+        name: entry.name,
+        stat,
+      });
+    }
+    Ok(FsDirEntries::new(entries))
+  }
+
   fn copy_to_real_path(&self, oldpath: &Path, newpath: &Path) -> FsResult<()> {
     let old_file = self.0.file_entry(oldpath)?;
     let old_file_bytes = self.0.read_file_all(old_file)?;
@@ -98,6 +114,18 @@ impl FileSystem for DenoCompileFileSystem {
     }
   }
 
+  fn create_unnamed_file_sync(&self, path: &Path) -> FsResult<Rc<dyn File>> {
+    self.error_if_in_vfs(path)?;
+    RealFs.create_unnamed_file_sync(path)
+  }
+  async fn create_unnamed_file_async(
+    &self,
+    path: PathBuf,
+  ) -> FsResult<Rc<dyn File>> {
+    self.error_if_in_vfs(&path)?;
+    RealFs.create_unnamed_file_async(path).await
+  }
+
   fn mkdir_sync(
     &self,
     path: &Path,
@@ -174,29 +202,118 @@ impl FileSystem for DenoCompileFileSystem {
     RealFs.remove_async(path, recursive).await
   }
 
-  fn copy_file_sync(&self, oldpath: &Path, newpath: &Path) -> FsResult<()> {
+  fn copy_file_sync(
+    &self,
+    oldpath: &Path,
+    newpath: &Path,
+    reflink: Reflink,
+  ) -> FsResult<()> {
     self.error_if_in_vfs(newpath)?;
     if self.0.is_path_within(oldpath) {
+      // files in the vfs can't be cloned
+      if reflink == Reflink::Always {
+        return Err(FsError::NotSupported);
+      }
       self.copy_to_real_path(oldpath, newpath)
     } else {
-      RealFs.copy_file_sync(oldpath, newpath)
+      RealFs.copy_file_sync(oldpath, newpath, reflink)
     }
   }
   async fn copy_file_async(
     &self,
     oldpath: PathBuf,
     newpath: PathBuf,
+    reflink: Reflink,
   ) -> FsResult<()> {
     self.error_if_in_vfs(&newpath)?;
     if self.0.is_path_within(&oldpath) {
+      if reflink == Reflink::Always {
+        return Err(FsError::NotSupported);
+      }
       let fs = self.clone();
       tokio::task::spawn_blocking(move || {
         fs.copy_to_real_path(&oldpath, &newpath)
       })
       .await?
     } else {
-      RealFs.copy_file_async(oldpath, newpath).await
+      RealFs.copy_file_async(oldpath, newpath, reflink).await
+    }
+  }
+
+  fn get_xattr_sync(
+    &self,
+    path: &Path,
+    name: &str,
+  ) -> FsResult<Option<Vec<u8>>> {
+    if self.0.is_path_within(path) {
+      return Err(FsError::NotSupported);
+    }
+    RealFs.get_xattr_sync(path, name)
+  }
+  async fn get_xattr_async(
+    &self,
+    path: PathBuf,
+    name: String,
+  ) -> FsResult<Option<Vec<u8>>> {
+    if self.0.is_path_within(&path) {
+      return Err(FsError::NotSupported);
+    }
+    RealFs.get_xattr_async(path, name).await
+  }
+
+  fn set_xattr_sync(
+    &self,
+    path: &Path,
+    name: &str,
+    value: &[u8],
+  ) -> FsResult<()> {
+    self.error_if_in_vfs(path)?;
+    RealFs.set_xattr_sync(path, name, value)
+  }
+  async fn set_xattr_async(
+    &self,
+    path: PathBuf,
+    name: String,
+    value: Vec<u8>,
+  ) -> FsResult<()> {
+    self.error_if_in_vfs(&path)?;
+    RealFs.set_xattr_async(path, name, value).await
+  }
+
+  fn list_xattrs_sync(&self, path: &Path) -> FsResult<Vec<String>> {
+    if self.0.is_path_within(path) {
+      return Err(FsError::NotSupported);
     }
+    RealFs.list_xattrs_sync(path)
+  }
+  async fn list_xattrs_async(&self, path: PathBuf) -> FsResult<Vec<String>> {
+    if self.0.is_path_within(&path) {
+      return Err(FsError::NotSupported);
+    }
+    RealFs.list_xattrs_async(path).await
+  }
+
+  fn write_file_atomic_sync(
+    &self,
+    path: &Path,
+    mode: Option<u32>,
+    sync_dir: bool,
+    data: &[u8],
+  ) -> FsResult<()> {
+    self.error_if_in_vfs(path)?;
+    RealFs.write_file_atomic_sync(path, mode, sync_dir, data)
+  }
+  async fn write_file_atomic_async(
+    &self,
+    path: PathBuf,
+    mode: Option<u32>,
+    sync_dir: bool,
+    data: Vec<u8>,
+  ) -> FsResult<()> {
+    self.error_if_in_vfs(&path)?;
+    RealFs
+      .write_file_atomic_async(path, mode, sync_dir, data)
+      .await
   }
 
   fn cp_sync(&self, from: &Path, to: &Path) -> FsResult<()> {
@@ -270,6 +387,24 @@ impl FileSystem for DenoCompileFileSystem {
     }
   }
 
+  fn open_dir_sync(&self, path: &Path) -> FsResult<Rc<dyn FsDirReader>> {
+    if self.0.is_path_within(path) {
+      Ok(Rc::new(self.vfs_dir_entries(path)?))
+    } else {
+      RealFs.open_dir_sync(path)
+    }
+  }
+  async fn open_dir_async(
+    &self,
+    path: PathBuf,
+  ) -> FsResult<Rc<dyn FsDirReader>> {
+    if self.0.is_path_within(&path) {
+      Ok(Rc::new(self.vfs_dir_entries(&path)?))
+    } else {
+      RealFs.open_dir_async(path).await
+    }
+  }
+
   fn rename_sync(&self, oldpath: &Path, newpath: &Path) -> FsResult<()> {
     self.error_if_in_vfs(oldpath)?;
     self.error_if_in_vfs(newpath)?;
@@ -722,6 +722,7 @@ pub async fn run(
       is_npm_main: main_module.scheme() == "npm",
       skip_op_registration: true,
       location: metadata.location,
+      broadcast_channel_ipc: metadata.broadcast_channel_ipc,
       argv0: NpmPackageReqReference::from_specifier(&main_module)
         .ok()
         .map(|req_ref| npm_pkg_req_ref_to_binary_command(&req_ref))
@@ -735,6 +736,7 @@ pub async fn run(
       create_hmr_runner: None,
       create_coverage_collector: None,
       node_ipc: None,
+      deno_ipc: None,
       serve_port: None,
       serve_host: None,
     },
@@ -21,6 +21,7 @@ use deno_core::BufView;
 use deno_core::ResourceHandleFd;
 use deno_runtime::deno_fs::FsDirEntry;
 use deno_runtime::deno_io;
+use deno_runtime::deno_io::fs::AllocateMode;
 use deno_runtime::deno_io::fs::FsError;
 use deno_runtime::deno_io::fs::FsResult;
 use deno_runtime::deno_io::fs::FsStat;
@@ -704,6 +705,13 @@ impl deno_io::fs::File for FileBackedVfsFile {
     Err(FsError::NotSupported)
   }
 
+  fn try_lock_sync(self: Rc<Self>, _exclusive: bool) -> FsResult<bool> {
+    Err(FsError::NotSupported)
+  }
+  async fn try_lock_async(self: Rc<Self>, _exclusive: bool) -> FsResult<bool> {
+    Err(FsError::NotSupported)
+  }
+
   fn unlock_sync(self: Rc<Self>) -> FsResult<()> {
     Err(FsError::NotSupported)
   }
@@ -718,6 +726,23 @@ impl deno_io::fs::File for FileBackedVfsFile {
     Err(FsError::NotSupported)
   }
 
+  fn allocate_sync(
+    self: Rc<Self>,
+    _offset: u64,
+    _len: u64,
+    _mode: AllocateMode,
+  ) -> FsResult<()> {
+    Err(FsError::NotSupported)
+  }
+  async fn allocate_async(
+    self: Rc<Self>,
+    _offset: u64,
+    _len: u64,
+    _mode: AllocateMode,
+  ) -> FsResult<()> {
+    Err(FsError::NotSupported)
+  }
+
   fn utime_sync(
     self: Rc<Self>,
     _atime_secs: i64,
@@ -281,6 +281,8 @@ fn resource_close_hint(name: &str) -> &'static str {
     "unixDatagram" => "Close the Unix datagram socket by calling `unixDatagram.close()`.",
     "tcpListener" => "Close the TCP listener by calling `tcpListener.close()`.",
     "udpSocket" => "Close the UDP socket by calling `udpSocket.close()`.",
+    "quicListener" => "Close the QUIC listener by calling `quicListener.close()`.",
+    "quicConnection" => "Close the QUIC connection by calling `quicConn.close()`.",
     "timer" => "Clear the timer by calling `clearInterval` or `clearTimeout`.",
     "textDecoder" => "Close the text decoder by calling `textDecoder.decode('')` or `await textDecoderStream.readable.cancel()`.",
     "messagePort" => "Close the message port by calling `messagePort.close()`.",
@@ -300,6 +302,8 @@ fn resource_close_hint(name: &str) -> &'static str {
 }
 
 pub const OP_DETAILS: phf::Map<&'static str, [&'static str; 2]> = phf_map! {
+  "op_archive_unpack_file" => ["unpack an archive", "awaiting the result of a `Deno.extractArchive` call"],
+  "op_archive_unpack_finish" => ["unpack an archive", "awaiting the result of a `Deno.extractArchive` call"],
   "op_blob_read_part" => ["read from a Blob or File", "awaiting the result of a Blob or File read"],
   "op_broadcast_recv" => ["receive a message from a BroadcastChannel", "closing the BroadcastChannel"],
   "op_broadcast_send" => ["send a message to a BroadcastChannel", "closing the BroadcastChannel"],
@@ -311,6 +315,8 @@ pub const OP_DETAILS: phf::Map<&'static str, [&'static str; 2]> = phf_map! {
   "op_crypto_subtle_digest" => ["digest data", "awaiting the result of a `crypto.subtle.digest` call"],
   "op_crypto_verify_key" => ["verify data", "awaiting the result of a `crypto.subtle.verify` call"],
   "op_dns_resolve" => ["resolve a DNS name", "awaiting the result of a `Deno.resolveDns` call"],
+  "op_fetch_progress" => ["report the progress of a HTTP request", "awaiting the `onUploadProgress` or `onDownloadProgress` hooks of a `fetch` call"],
+  "op_fetch_response_trailers" => ["receive the trailers of a HTTP response", "awaiting the `trailers` of a `fetch` response"],
   "op_fetch_send" => ["send a HTTP request", "awaiting the result of a `fetch` call"],
   "op_ffi_call_nonblocking" => ["do a non blocking ffi call", "awaiting the returned promise"],
   "op_ffi_call_ptr_nonblocking" => ["do a non blocking ffi call", "awaiting the returned promise"],
@@ -318,6 +324,7 @@ pub const OP_DETAILS: phf::Map<&'static str, [&'static str; 2]> = phf_map! {
   "op_fs_chown_async" => ["change the owner of a file", "awaiting the result of a `Deno.chown` call"],
   "op_fs_copy_file_async" => ["copy a file", "awaiting the result of a `Deno.copyFile` call"],
   "op_fs_events_poll" => ["get the next file system event", "breaking out of a for await loop looping over `Deno.FsEvents`"],
+  "op_fs_fallocate_async" => ["allocate space for a file", "awaiting the result of a `Deno.FsFile.allocate` call"],
   "op_fs_fdatasync_async" => ["flush pending data operations for a file to disk", "awaiting the result of a `Deno.fdatasync` or `Deno.FsFile.syncData` call"],
   "op_fs_file_stat_async" => ["get file metadata", "awaiting the result of a `Deno.fstat` or `Deno.FsFile.stat` call"],
   "op_fs_flock_async" => ["lock a file", "awaiting the result of a `Deno.FsFile.lock` call"],
@@ -325,13 +332,19 @@ pub const OP_DETAILS: phf::Map<&'static str, [&'static str; 2]> = phf_map! {
   "op_fs_ftruncate_async" => ["truncate a file", "awaiting the result of a `Deno.ftruncate` or `Deno.FsFile.truncate` call"],
   "op_fs_funlock_async_unstable" => ["unlock a file", "awaiting the result of a `Deno.funlock` call"],
   "op_fs_funlock_async" => ["unlock a file", "awaiting the result of a `Deno.FsFile.unlock` call"],
+  "op_fs_get_xattr_async" => ["get an extended attribute", "awaiting the result of a `Deno.getXattr` call"],
+  "op_fs_glob_next_async" => ["walk a directory tree", "awaiting the next paths of a `Deno.glob` call"],
   "op_fs_link_async" => ["create a hard link", "awaiting the result of a `Deno.link` call"],
+  "op_fs_list_xattrs_async" => ["list extended attributes", "awaiting the result of a `Deno.listXattrs` call"],
   "op_fs_lstat_async" => ["get file metadata", "awaiting the result of a `Deno.lstat` call"],
   "op_fs_make_temp_dir_async" => ["create a temporary directory", "awaiting the result of a `Deno.makeTempDir` call"],
   "op_fs_make_temp_file_async" => ["create a temporary file", "awaiting the result of a `Deno.makeTempFile` call"],
+  "op_fs_make_temp_file_unnamed_async" => ["create an unnamed temporary file", "awaiting the result of a `Deno.makeTempFile` call"],
   "op_fs_mkdir_async" => ["create a directory", "awaiting the result of a `Deno.mkdir` call"],
   "op_fs_open_async" => ["open a file", "awaiting the result of a `Deno.open` call"],
+  "op_fs_open_dir_async" => ["open a directory", "awaiting the result of a `Deno.readDir` call"],
   "op_fs_read_dir_async" => ["read a directory", "collecting all items in the async iterable returned from a `Deno.readDir` call"],
+  "op_fs_read_dir_batch_async" => ["read a directory", "awaiting the next entry of a `Deno.readDir` call"],
   "op_fs_read_file_async" => ["read a file", "awaiting the result of a `Deno.readFile` call"],
   "op_fs_read_file_text_async" => ["read a text file", "awaiting the result of a `Deno.readTextFile` call"],
   "op_fs_read_link_async" => ["read a symlink", "awaiting the result of a `Deno.readLink` call"],
@@ -339,11 +352,14 @@ pub const OP_DETAILS: phf::Map<&'static str, [&'static str; 2]> = phf_map! {
   "op_fs_remove_async" => ["remove a file or directory", "awaiting the result of a `Deno.remove` call"],
   "op_fs_rename_async" => ["rename a file or directory", "awaiting the result of a `Deno.rename` call"],
   "op_fs_seek_async" => ["seek in a file", "awaiting the result of a `Deno.seek` or `Deno.FsFile.seek` call"],
+  "op_fs_set_xattr_async" => ["set an extended attribute", "awaiting the result of a `Deno.setXattr` call"],
   "op_fs_stat_async" => ["get file metadata", "awaiting the result of a `Deno.stat` call"],
   "op_fs_symlink_async" => ["create a symlink", "awaiting the result of a `Deno.symlink` call"],
   "op_fs_truncate_async" => ["truncate a file", "awaiting the result of a `Deno.truncate` call"],
+  "op_fs_try_flock_async" => ["try to lock a file", "awaiting the result of a `Deno.FsFile.tryLock` call"],
   "op_fs_utime_async" => ["change file timestamps", "awaiting the result of a `Deno.utime` call"],
   "op_fs_write_file_async" => ["write a file", "awaiting the result of a `Deno.writeFile` call"],
+  "op_fs_write_file_atomic_async" => ["write a file atomically", "awaiting the result of a `Deno.writeFileAtomic` call"],
   "op_host_recv_ctrl" => ["receive a message from a web worker", "terminating a `Worker`"],
   "op_host_recv_message" => ["receive a message from a web worker", "terminating a `Worker`"],
   "op_http_accept" => ["accept a HTTP request", "closing a `Deno.HttpConn`"],
@@ -362,15 +378,26 @@ pub const OP_DETAILS: phf::Map<&'static str, [&'static str; 2]> = phf_map! {
   "op_net_recv_unixpacket" => ["receive a datagram message via Unixpacket", "awaiting the result of `Deno.DatagramConn#receive` call, or not breaking out of a for await loop looping over a `Deno.DatagramConn`"],
   "op_net_send_udp" => ["send a datagram message via UDP", "awaiting the result of `Deno.DatagramConn#send` call"],
   "op_net_send_unixpacket" => ["send a datagram message via Unixpacket", "awaiting the result of `Deno.DatagramConn#send` call"],
+  "op_net_sendfile" => ["send a file via TCP", "awaiting the result of a `Deno.TcpConn#sendFile` call"],
+  "op_quic_accept" => ["accept a QUIC connection", "closing a `Deno.QuicListener`"],
+  "op_quic_connect" => ["connect to a QUIC server", "awaiting a `Deno.connectQuic` call"],
+  "op_quic_connection_accept_bi" => ["accept a bidirectional QUIC stream", "closing a `Deno.QuicConn`"],
+  "op_quic_connection_accept_uni" => ["accept a unidirectional QUIC stream", "closing a `Deno.QuicConn`"],
+  "op_quic_connection_closed" => ["wait for a QUIC connection to close", "closing a `Deno.QuicConn`"],
+  "op_quic_connection_open_bi" => ["open a bidirectional QUIC stream", "awaiting a `Deno.QuicConn#createBidirectionalStream` call"],
+  "op_quic_connection_open_uni" => ["open a unidirectional QUIC stream", "awaiting a `Deno.QuicConn#createUnidirectionalStream` call"],
+  "op_quic_connection_read_datagram" => ["read a QUIC datagram", "awaiting the result of a `Deno.QuicConn#readDatagram` call, or closing the `Deno.QuicConn`"],
   "op_run_status" => ["get the status of a subprocess", "awaiting the result of a `Deno.Process#status` call"],
   "op_signal_poll" => ["get the next signal", "un-registering a OS signal handler"],
   "op_spawn_wait" => ["wait for a subprocess to exit", "awaiting the result of a `Deno.Process#status` call"],
+  "op_tls_export_keying_material" => ["export keying material of a TLS connection", "awaiting the result of a `Deno.TlsConn#exportKeyingMaterial` call"],
   "op_tls_handshake" => ["perform a TLS handshake", "awaiting a `Deno.TlsConn#handshake` call"],
   "op_tls_start" => ["start a TLS connection", "awaiting a `Deno.startTls` call"],
   "op_utime_async" => ["change file timestamps", "awaiting the result of a `Deno.utime` call"],
   "op_webgpu_buffer_get_map_async" => ["map a WebGPU buffer", "awaiting the result of a `GPUBuffer#mapAsync` call"],
   "op_webgpu_request_adapter" => ["request a WebGPU adapter", "awaiting the result of a `navigator.gpu.requestAdapter` call"],
   "op_webgpu_request_device" => ["request a WebGPU device", "awaiting the result of a `GPUAdapter#requestDevice` call"],
+  "op_worker_pool_run" => ["run a task on a worker pool", "awaiting the result of `Deno.WorkerPool#run`, or terminating the `WorkerPool`"],
   "op_ws_close" => ["close a WebSocket", "awaiting until the `close` event is emitted on a `WebSocket`, or the `WebSocketStream#closed` promise resolves"],
   "op_ws_create" => ["create a WebSocket", "awaiting until the `open` event is emitted on a `WebSocket`, or the result of a `WebSocketStream#connection` promise"],
   "op_ws_next_event" => ["receive the next message on a WebSocket", "closing a `WebSocket` or `WebSocketStream`"],
@@ -378,6 +405,8 @@ pub const OP_DETAILS: phf::Map<&'static str, [&'static str; 2]> = phf_map! {
   "op_ws_send_binary_ab" => ["send a message on a WebSocket", "closing a `WebSocket` or `WebSocketStream`"],
   "op_ws_send_ping" => ["send a message on a WebSocket", "closing a `WebSocket` or `WebSocketStream`"],
   "op_ws_send_text" => ["send a message on a WebSocket", "closing a `WebSocket` or `WebSocketStream`"],
+  "op_ws_stream_drain" => ["wait for a WebSocketStream to drain", "closing a `WebSocketStream`"],
+  "op_ws_stream_next" => ["receive the next message on a WebSocketStream", "closing a `WebSocketStream`"],
 };
 
 #[cfg(test)]
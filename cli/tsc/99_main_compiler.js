@@ -31,27 +31,74 @@ delete Object.prototype.__proto__;
 
   /** @type {ReadonlySet<string>} */
   const unstableDenoProps = new Set([
+    "AllocateOptions",
     "AtomicOperation",
+    "ChildProcessPty",
+    "CopyFileOptions",
     "CreateHttpClientOptions",
     "DatagramConn",
+    "DirEntryWithInfo",
+    "DiskUsage",
+    "DnsResolver",
+    "EnvChangeEvent",
+    "EnvDiff",
     "HttpClient",
+    "IpcChannel",
     "Kv",
     "KvListIterator",
     "KvU64",
+    "PermissionSnapshot",
+    "PermissionsSnapshot",
+    "ProcessMetrics",
+    "PtySize",
+    "QuicBidirectionalStream",
+    "QuicConn",
+    "QuicListener",
+    "ReadDirOptions",
+    "SharedMemory",
+    "StdinInfo",
+    "TlsSessionCache",
+    "TtyMode",
     "UnsafeCallback",
     "UnsafePointer",
     "UnsafePointerView",
     "UnsafeFnPointer",
     "UnixConnectOptions",
     "UnixListenOptions",
+    "WorkerPool",
+    "WriteFileAtomicOptions",
+    "connectDatagram",
+    "connectQuic",
+    "createArchive",
+    "createDnsResolver",
     "createHttpClient",
+    "createSharedMemory",
+    "createTlsSessionCache",
+    "diskUsage",
     "dlopen",
+    "extractArchive",
     "funlock",
     "funlockSync",
+    "getXattr",
+    "getXattrSync",
+    "glob",
+    "globSync",
+    "ipc",
+    "listXattrs",
+    "listXattrsSync",
     "listen",
     "listenDatagram",
+    "listenQuic",
     "openKv",
+    "openSharedMemory",
+    "processMetrics",
+    "setXattr",
+    "setXattrSync",
+    "signal",
+    "systemCpuUsage",
     "umask",
+    "writeFileAtomic",
+    "writeFileAtomicSync",
   ]);
   const unstableMsgSuggestion =
     "If not, try changing the 'lib' compiler option to include 'deno.unstable' " +
@@ -314,6 +314,25 @@ declare namespace Deno {
      *
      * @category Errors */
     export class NotADirectory extends Error {}
+    /**
+     * Raised when the output of a subprocess exceeds the limit set with
+     * {@linkcode Deno.CommandOptions.maxBuffer}.
+     *
+     * @category Errors */
+    export class MaxBufferExceeded extends Error {}
+    /**
+     * Raised when spawning a subprocess whose
+     * {@linkcode Deno.CommandOptions.cwd} does not exist.
+     *
+     * @category Errors */
+    export class CwdNotFound extends NotFound {}
+    /**
+     * Raised when spawning a subprocess whose
+     * {@linkcode Deno.CommandOptions.cwd} is not a directory. It extends
+     * {@linkcode Deno.errors.NotFound}, which was raised in this case before.
+     *
+     * @category Errors */
+    export class CwdNotDirectory extends NotFound {}
   }
 
   /** The current process ID of this instance of the Deno CLI.
@@ -2701,7 +2720,12 @@ declare namespace Deno {
    *
    * @category I/O
    */
-  export const stdin: Reader & ReaderSync & Closer & {
+  export const stdin: Stdin;
+
+  /** The type of {@linkcode Deno.stdin}.
+   *
+   * @category I/O */
+  export interface Stdin extends Reader, ReaderSync, Closer {
     /**
      * The resource ID assigned to `stdin`. This can be used with the discrete
      * I/O functions in the `Deno` namespace.
@@ -2738,7 +2762,8 @@ declare namespace Deno {
      * @category I/O
      */
     isTerminal(): boolean;
-  };
+  }
+
   /** A reference to `stdout` which can be used to write directly to `stdout`.
    * It implements the Deno specific {@linkcode Writer}, {@linkcode WriterSync},
    * and {@linkcode Closer} interfaces as well as provides a
@@ -3656,6 +3681,10 @@ declare namespace Deno {
    * await Deno.copyFile("from.txt", "to.txt");
    * ```
    *
+   * On file systems which support it, e.g. Btrfs, XFS, APFS and ReFS, the
+   * copy is a clone sharing its data with the original until either is
+   * changed, which is much faster for large files.
+   *
    * Requires `allow-read` permission on `fromPath`.
    *
    * Requires `allow-write` permission on `toPath`.
@@ -3676,6 +3705,8 @@ declare namespace Deno {
    * Deno.copyFileSync("from.txt", "to.txt");
    * ```
    *
+   * See {@linkcode Deno.copyFile} on cloning files.
+   *
    * Requires `allow-read` permission on `fromPath`.
    *
    * Requires `allow-write` permission on `toPath`.
@@ -3983,10 +4014,13 @@ declare namespace Deno {
    *    An application that keeps an in-memory representation of the filesystem
    *    will need to care, and will need to refresh that representation directly
    *    from the filesystem.
+   * - `"overflow"`: events were dropped because more of them happened than
+   *    the watcher could buffer until they were read. Like `"rescan"`, events
+   *    received so far can no longer be relied on.
    *
    * @category File System
    */
-  export type FsEventFlag = "rescan";
+  export type FsEventFlag = "rescan" | "overflow";
 
   /**
    * Represents a unique file system event yielded by a
@@ -4047,6 +4081,15 @@ declare namespace Deno {
    * The recursive option is `true` by default and, for directories, will watch
    * the specified directory and all sub directories.
    *
+   * If `correlateRenames` is `true`, the two halves of a rename which some
+   * platforms report separately are combined into one `"rename"` event with
+   * the old and the new path, in this order. A rename from or to outside of
+   * the watched paths is reported with only the path inside of them.
+   *
+   * If `debounce` is set to a number of milliseconds, identical events which
+   * happen within that window after the first one are reported once, at the
+   * end of the window.
+   *
    * Note that the exact ordering of the events can vary between operating
    * systems.
    *
@@ -4079,7 +4122,11 @@ declare namespace Deno {
    */
   export function watchFs(
     paths: string | string[],
-    options?: { recursive: boolean },
+    options?: {
+      recursive?: boolean;
+      correlateRenames?: boolean;
+      debounce?: number;
+    },
   ): FsWatcher;
 
   /**
@@ -4254,6 +4301,15 @@ declare namespace Deno {
   /** Operating signals which can be listened for or sent to sub-processes. What
    * signals and what their standard behaviors are OS dependent.
    *
+   * On Linux the realtime signals are available too, named like `kill -l`
+   * prints them, e.g. `"SIGRTMIN+1"` or `"SIGRTMAX-2"`.
+   *
+   * On Windows only `"SIGINT"` (ctrl-c), `"SIGBREAK"` (ctrl-break) and
+   * `"SIGHUP"` (the console is closed) can be listened for. Windows terminates
+   * the process after `"SIGHUP"`, which is delayed until its listeners
+   * returned, but only for a few seconds at most. Delivering it is best-effort,
+   * so work the listeners start asynchronously may not finish.
+   *
    * @category Runtime */
   export type Signal =
     | "SIGABRT"
@@ -4276,6 +4332,10 @@ declare namespace Deno {
     | "SIGPROF"
     | "SIGPWR"
     | "SIGQUIT"
+    | "SIGRTMIN"
+    | `SIGRTMIN+${number}`
+    | "SIGRTMAX"
+    | `SIGRTMAX-${number}`
     | "SIGSEGV"
     | "SIGSTKFLT"
     | "SIGSTOP"
@@ -4304,12 +4364,46 @@ declare namespace Deno {
    * );
    * ```
    *
-   * _Note_: On Windows only `"SIGINT"` (CTRL+C) and `"SIGBREAK"` (CTRL+Break)
-   * are supported.
+   * Listeners of `"SIGCHLD"` and `"SIGWINCH"` receive a
+   * {@linkcode SignalInfo} with details about the signal:
+   *
+   * ```ts
+   * Deno.addSignalListener("SIGWINCH", (info) => {
+   *   console.log(`Resized to ${info?.columns}x${info?.rows}`);
+   * });
+   * ```
+   *
+   * _Note_: On Windows only `"SIGINT"` (CTRL+C), `"SIGBREAK"` (CTRL+Break)
+   * and `"SIGHUP"` (the console is closed) are supported.
+   *
+   * @category Runtime
+   */
+  export function addSignalListener(
+    signal: Signal,
+    handler: (info?: SignalInfo) => void,
+  ): void;
+
+  /** Details about a received signal, which are passed to the listeners of
+   * `"SIGCHLD"` and `"SIGWINCH"`.
    *
    * @category Runtime
    */
-  export function addSignalListener(signal: Signal, handler: () => void): void;
+  export interface SignalInfo {
+    signal: Signal;
+    /** For `"SIGCHLD"`, the children spawned with {@linkcode Deno.Command}
+     * which exited since the previous `"SIGCHLD"`. Signals which arrive in
+     * quick succession are coalesced, so this may list several children, or
+     * none if they were already listed before. */
+    children?: {
+      rid: number;
+      pid: number;
+      status: CommandStatus;
+    }[];
+    /** For `"SIGWINCH"`, the new number of columns of the terminal. */
+    columns?: number;
+    /** For `"SIGWINCH"`, the new number of rows of the terminal. */
+    rows?: number;
+  }
 
   /** Removes the given signal listener that has been registered with
    * {@linkcode Deno.addSignalListener}.
@@ -4322,14 +4416,14 @@ declare namespace Deno {
    * Deno.removeSignalListener("SIGTERM", listener);
    * ```
    *
-   * _Note_: On Windows only `"SIGINT"` (CTRL+C) and `"SIGBREAK"` (CTRL+Break)
-   * are supported.
+   * _Note_: On Windows only `"SIGINT"` (CTRL+C), `"SIGBREAK"` (CTRL+Break)
+   * and `"SIGHUP"` (the console is closed) are supported.
    *
    * @category Runtime
    */
   export function removeSignalListener(
     signal: Signal,
-    handler: () => void,
+    handler: (info?: SignalInfo) => void,
   ): void;
 
   /**
@@ -4492,9 +4586,25 @@ declare namespace Deno {
     get stdin(): WritableStream<Uint8Array>;
     get stdout(): ReadableStream<Uint8Array>;
     get stderr(): ReadableStream<Uint8Array>;
+    /** The process id of the child. */
     readonly pid: number;
+    /** When the child was spawned, in milliseconds since the Unix epoch like
+     * {@linkcode Date.now}. Together with {@linkcode ChildProcess.pid} this
+     * identifies the process even if its pid is reused later. */
+    readonly startTime: number;
+    /** The resource id of a pidfd referring to the child, which makes it
+     * possible to refer to the child without racing on pid reuse, e.g. by
+     * passing it on to another process. It is closed once the status of the
+     * child was collected.
+     *
+     * Only available on Linux 5.3 and later, `null` otherwise. */
+    readonly pidfd: number | null;
     /** Get the status of the child. */
     readonly status: Promise<CommandStatus>;
+    /** Get the status of the child if it has already exited, or `null` if it
+     * is still running. Unlike {@linkcode ChildProcess.status}, this doesn't
+     * wait for the child to exit. */
+    tryStatus(): CommandStatus | null;
 
     /** Waits for the child to exit completely, returning all its output and
      * status. */
@@ -4503,9 +4613,15 @@ declare namespace Deno {
      *
      * Defaults to `SIGTERM` if no signal is provided.
      *
+     * If `options.tree` is `true`, the signal is sent to the whole process
+     * group of the child instead, which requires the child to have been
+     * spawned with {@linkcode CommandOptions.processGroup}. On Windows, only
+     * `SIGKILL` and `SIGTERM` are supported and terminate the job object the
+     * child was assigned to.
+     *
      * @param [signo="SIGTERM"]
      */
-    kill(signo?: Signal): void;
+    kill(signo?: Signal, options?: ChildProcessKillOptions): void;
 
     /** Ensure that the status of the child process prevents the Deno process
      * from exiting. */
@@ -4517,6 +4633,20 @@ declare namespace Deno {
     [Symbol.asyncDispose](): Promise<void>;
   }
 
+  /**
+   * Options which can be passed to {@linkcode Deno.ChildProcess.kill}.
+   *
+   * @category Subprocess
+   */
+  export interface ChildProcessKillOptions {
+    /** Signal the child's entire process group (or job object on Windows)
+     * rather than just the child itself, so that grandchildren spawned by it
+     * are terminated as well.
+     *
+     * @default {false} */
+    tree?: boolean;
+  }
+
   /**
    * Options which can be set when calling {@linkcode Deno.Command}.
    *
@@ -4525,6 +4655,12 @@ declare namespace Deno {
   export interface CommandOptions {
     /** Arguments to pass to the process. */
     args?: string[];
+    /** The value of `argv[0]` in the process, which defaults to the command.
+     * Useful for multi-call binaries like `busybox`, which pick the program
+     * to run based on it.
+     *
+     * Ignored on Windows. */
+    argv0?: string;
     /**
      * The working directory of the process.
      *
@@ -4542,6 +4678,15 @@ declare namespace Deno {
     clearEnv?: boolean;
     /** Environmental variables to pass to the subprocess. */
     env?: Record<string, string>;
+    /**
+     * Only inherit the environmental variables of the parent process whose
+     * name is listed or matches one of the regular expressions, e.g.
+     * `["PATH", "HOME", /^LC_/]`. `env` is applied on top of them.
+     *
+     * Names are compared case insensitively on Windows. Can't be combined
+     * with `clearEnv`.
+     */
+    envInherit?: (string | RegExp)[];
     /**
      * Sets the child process’s user ID. This translates to a setuid call in the
      * child process. Failure in the set uid call will cause the spawn to fail.
@@ -4549,6 +4694,14 @@ declare namespace Deno {
     uid?: number;
     /** Similar to `uid`, but sets the group ID of the child process. */
     gid?: number;
+    /** The file mode creation mask of the child process, e.g. `0o077` to
+     * make the files it creates only accessible to their owner. Unlike
+     * {@linkcode Deno.umask}, this leaves the mask of the current process
+     * untouched.
+     *
+     * Ignored on Windows. By default the child inherits the mask of the
+     * current process. */
+    umask?: number;
     /**
      * An {@linkcode AbortSignal} that allows closing the process using the
      * corresponding {@linkcode AbortController} by sending the process a
@@ -4557,12 +4710,25 @@ declare namespace Deno {
      * Not supported in {@linkcode Deno.Command.outputSync}.
      */
     signal?: AbortSignal;
+    /**
+     * If set, a process that is still running this many milliseconds after
+     * {@linkcode CommandOptions.signal} was aborted is forcefully killed with
+     * `SIGKILL`. This gives the process a chance to shut down gracefully on
+     * `SIGTERM` while still guaranteeing that it terminates.
+     *
+     * On Windows the process is always terminated immediately.
+     */
+    abortGracePeriod?: number;
 
     /** How `stdin` of the spawned process should be handled.
+     *
+     * With `{ bytes }` the given bytes are written to `stdin` of the process
+     * in the background, after which it is closed. Unlike with `"piped"`,
+     * this is supported by `output` & `outputSync` too.
      *
      * Defaults to `"inherit"` for `output` & `outputSync`,
      * and `"inherit"` for `spawn`. */
-    stdin?: "piped" | "inherit" | "null";
+    stdin?: "piped" | "inherit" | "null" | { bytes: Uint8Array };
     /** How `stdout` of the spawned process should be handled.
      *
      * Defaults to `"piped"` for `output` & `outputSync`,
@@ -4579,6 +4745,65 @@ declare namespace Deno {
      *
      * @default {false} */
     windowsRawArguments?: boolean;
+
+    /** The [process creation flags](https://learn.microsoft.com/en-us/windows/win32/procthread/process-creation-flags)
+     * to spawn the process with on windows, e.g. `0x08000000`
+     * (`CREATE_NO_WINDOW`) to not open a console window for it. This option
+     * is ignored on non-windows platforms. */
+    creationFlags?: number;
+
+    /** Spawn the process as the leader of a new process group (`setpgid` on
+     * unix, a new job object on Windows). This allows killing the process
+     * together with all of its descendants via
+     * `child.kill(signo, { tree: true })`.
+     *
+     * @default {false} */
+    processGroup?: boolean;
+
+    /** The scheduling priority (niceness) of the process, ranging from `-20`
+     * (highest priority) to `19` (lowest priority). On windows it is mapped to
+     * the closest priority class. Raising the priority above the one of the
+     * current process usually requires elevated privileges.
+     *
+     * By default the process inherits the priority of the current process. */
+    priority?: number;
+
+    /** The indices of the CPUs the process is allowed to run on, starting at
+     * `0`. On Windows only the first 64 CPUs can be selected.
+     *
+     * Only supported on Linux and Windows. By default the process inherits
+     * the affinity of the current process. */
+    cpuAffinity?: number[];
+
+    /** The largest amount of data in bytes allowed on `stdout` or `stderr`.
+     * If exceeded, the process is killed and
+     * {@linkcode Deno.errors.MaxBufferExceeded} is thrown.
+     *
+     * Currently only supported in {@linkcode Deno.Command.outputSync}. */
+    maxBuffer?: number;
+
+    /** The maximum amount of time in milliseconds the process is allowed to
+     * run. Once exceeded, the process is sent `SIGTERM` (the whole process
+     * group if {@linkcode CommandOptions.processGroup} is set) and the
+     * resulting {@linkcode CommandStatus.timedOut} is `true`. If it is still
+     * running 5 seconds later, it is killed with `SIGKILL`.
+     *
+     * On Windows the process is always terminated immediately.
+     *
+     * Not supported in {@linkcode Deno.Command.outputSync}. */
+    timeout?: number;
+
+    /** Relay `SIGINT` and `SIGTERM` received by the Deno process to the child
+     * until it exits, before any listeners added with
+     * {@linkcode Deno.addSignalListener} run. Meanwhile these signals don't
+     * terminate the Deno process, so that it can wait for the child to exit.
+     * Children on Windows receive ctrl-c and ctrl-break from their console
+     * already.
+     *
+     * Not supported in {@linkcode Deno.Command.outputSync}.
+     *
+     * @default {false} */
+    forwardSignals?: boolean;
   }
 
   /**
@@ -4592,6 +4817,27 @@ declare namespace Deno {
     code: number;
     /** The signal associated with the child process. */
     signal: Signal | null;
+    /** Whether the child process was terminated because it exceeded
+     * {@linkcode CommandOptions.timeout}. */
+    timedOut: boolean;
+    /** The resources used by the child process, including its waited-for
+     * descendants. Only available on Linux and Windows, `null` elsewhere. */
+    usage: CommandResourceUsage | null;
+  }
+
+  /**
+   * Resource usage of a child process, reported in
+   * {@linkcode CommandStatus.usage}.
+   *
+   * @category Subprocess
+   */
+  export interface CommandResourceUsage {
+    /** CPU time spent in user mode, in microseconds. */
+    userCpuTime: number;
+    /** CPU time spent in kernel mode, in microseconds. */
+    systemCpuTime: number;
+    /** Peak resident set size, in bytes. */
+    maxRss: number;
   }
 
   /**
@@ -4740,6 +4986,15 @@ declare namespace Deno {
     /** An `allow-run` or `deny-run` permission can be scoped to a specific executable,
      * which would be relative to the start-up CWD of the Deno CLI. */
     command?: string | URL;
+    /** When querying, the arguments `command` would be run with. Together
+     * with `env` this checks whether spawning a specific invocation would be
+     * allowed, including restrictions of `--allow-run` to certain first
+     * arguments and env vars like `LD_PRELOAD` that require all run
+     * permissions. */
+    args?: string[];
+    /** When querying, the env vars `command` would be run with, in addition
+     * to the inherited ones. See `args`. */
+    env?: Record<string, string>;
   }
 
   /** The permission descriptor for the `allow-read` and `deny-read` permissions, which controls
@@ -4859,6 +5114,17 @@ declare namespace Deno {
     | SysPermissionDescriptor
     | FfiPermissionDescriptor;
 
+  /** Options for {@linkcode Permissions.revoke}.
+   *
+   * @category Permissions */
+  export interface PermissionRevokeOptions {
+    /** Also revoke the permission in all descendant workers which were
+     * created with their own set of permissions.
+     *
+     * @default {false} */
+    workers?: boolean;
+  }
+
   /** The interface which defines what event types are supported by
    * {@linkcode PermissionStatus} instances.
    *
@@ -4968,8 +5234,15 @@ declare namespace Deno {
      * const status = await Deno.permissions.revoke({ name: "run" });
      * assert(status.state !== "granted")
      * ```
+     *
+     * Pass `{ workers: true }` to also revoke the permission in all workers
+     * that were created by this thread with their own set of permissions,
+     * and in the workers they created in turn.
      */
-    revoke(desc: PermissionDescriptor): Promise<PermissionStatus>;
+    revoke(
+      desc: PermissionDescriptor,
+      options?: PermissionRevokeOptions,
+    ): Promise<PermissionStatus>;
 
     /** Revokes a permission, and returns the state of the permission.
      *
@@ -4980,7 +5253,10 @@ declare namespace Deno {
      * assert(status.state !== "granted")
      * ```
      */
-    revokeSync(desc: PermissionDescriptor): PermissionStatus;
+    revokeSync(
+      desc: PermissionDescriptor,
+      options?: PermissionRevokeOptions,
+    ): PermissionStatus;
 
     /** Requests the permission, and resolves to the state of the permission.
      *
@@ -5591,8 +5867,15 @@ declare namespace Deno {
    * which also signals processes.
    *
    * If `pid` is negative, the signal will be sent to the process group
-   * identified by `pid`. An error will be thrown if a negative `pid` is used on
-   * Windows.
+   * identified by `-pid`. As there are no process groups on Windows, the
+   * process `-pid` is terminated together with all of its descendants there
+   * instead. A `pid` of `-1`, which would signal every process, is rejected.
+   *
+   * On Windows, only `"SIGKILL"` and `"SIGTERM"`, which terminate the process,
+   * as well as `"SIGBREAK"` are supported. `"SIGBREAK"` is sent as a
+   * ctrl-break event, which gives the process a chance to exit gracefully. It
+   * can only be sent to a child spawned with `processGroup: true` that shares
+   * the console with Deno, and only reaches the process group of that child.
    *
    * ```ts
    * const p = Deno.run({
@@ -5622,12 +5905,14 @@ declare namespace Deno {
     | "ANAME"
     | "CAA"
     | "CNAME"
+    | "HTTPS"
     | "MX"
     | "NAPTR"
     | "NS"
     | "PTR"
     | "SOA"
     | "SRV"
+    | "SVCB"
     | "TXT";
 
   /**
@@ -5727,6 +6012,38 @@ declare namespace Deno {
     target: string;
   }
 
+  /** If {@linkcode Deno.resolveDns} is called with `"SVCB"` or `"HTTPS"`
+   * record type specified, it will return an array of objects with this
+   * interface.
+   *
+   * @category Network
+   */
+  export interface SvcbRecord {
+    /** `0` for an alias of `target`, otherwise the priority of the service
+     * endpoint compared to the other records, lower values first. */
+    priority: number;
+    /** The name of the service endpoint, `"."` for the name which was
+     * queried. */
+    target: string;
+    /** The known parameters of the service endpoint. */
+    params: {
+      /** The keys of the parameters which clients must understand. */
+      mandatory?: string[];
+      /** The ALPN protocol ids which are supported. */
+      alpn?: string[];
+      /** If `true`, the default protocol of the scheme isn't supported. */
+      noDefaultAlpn?: boolean;
+      /** The port of the service endpoint. */
+      port?: number;
+      /** IPv4 addresses of the service endpoint. */
+      ipv4Hint?: string[];
+      /** The ECH config list, base64 encoded. */
+      ech?: string;
+      /** IPv6 addresses of the service endpoint. */
+      ipv6Hint?: string[];
+    };
+  }
+
   /**
    * Performs DNS resolution against the given query, returning resolved
    * records.
@@ -5907,6 +6224,36 @@ declare namespace Deno {
     options?: ResolveDnsOptions,
   ): Promise<SrvRecord[]>;
 
+  /**
+   * Performs DNS resolution against the given query, returning resolved
+   * records.
+   *
+   * Fails in the cases such as:
+   *
+   * - the query is in invalid format.
+   * - the options have an invalid parameter. For example `nameServer.port` is
+   *   beyond the range of 16-bit unsigned integer.
+   * - the request timed out.
+   *
+   * ```ts
+   * const a = await Deno.resolveDns("example.com", "A");
+   *
+   * const aaaa = await Deno.resolveDns("example.com", "AAAA", {
+   *   nameServer: { ipAddr: "8.8.8.8", port: 53 },
+   * });
+   * ```
+   *
+   * Requires `allow-net` permission.
+   *
+   * @tags allow-net
+   * @category Network
+   */
+  export function resolveDns(
+    query: string,
+    recordType: "HTTPS" | "SVCB",
+    options?: ResolveDnsOptions,
+  ): Promise<SvcbRecord[]>;
+
   /**
    * Performs DNS resolution against the given query, returning resolved
    * records.
@@ -5972,6 +6319,7 @@ declare namespace Deno {
     | NaptrRecord[]
     | SoaRecord[]
     | SrvRecord[]
+    | SvcbRecord[]
     | string[][]
   >;
 
@@ -29,6 +29,7 @@ use deno_runtime::deno_tls::RootCertStoreProvider;
 use deno_runtime::deno_web::BlobStore;
 use deno_runtime::fmt_errors::format_js_error;
 use deno_runtime::inspector_server::InspectorServer;
+use deno_runtime::ops::process::ParentIpcChannel;
 use deno_runtime::ops::worker_host::CreateWebWorkerCb;
 use deno_runtime::web_worker::WebWorker;
 use deno_runtime::web_worker::WebWorkerOptions;
@@ -107,6 +108,7 @@ pub struct CliMainWorkerOptions {
   pub is_inspecting: bool,
   pub is_npm_main: bool,
   pub location: Option<Url>,
+  pub broadcast_channel_ipc: Option<String>,
   pub argv0: Option<String>,
   pub node_debug: Option<String>,
   pub origin_data_folder_path: Option<PathBuf>,
@@ -117,6 +119,7 @@ pub struct CliMainWorkerOptions {
   pub create_hmr_runner: Option<CreateHmrRunnerCb>,
   pub create_coverage_collector: Option<CreateCoverageCollectorCb>,
   pub node_ipc: Option<i64>,
+  pub deno_ipc: Option<ParentIpcChannel>,
   pub serve_port: Option<u16>,
   pub serve_host: Option<String>,
 }
@@ -435,6 +438,10 @@ impl CliMainWorkerFactory {
     code_cache: Option<Arc<dyn code_cache::CodeCache>>,
     options: CliMainWorkerOptions,
   ) -> Self {
+    let broadcast_channel = match &options.broadcast_channel_ipc {
+      Some(path) => InMemoryBroadcastChannel::with_ipc(path.clone()),
+      None => Default::default(),
+    };
     Self {
       shared: Arc::new(SharedWorkerState {
         options,
@@ -443,7 +450,7 @@ impl CliMainWorkerFactory {
         npm_resolver,
         node_resolver,
         blob_store,
-        broadcast_channel: Default::default(),
+        broadcast_channel,
         shared_array_buffer_store: Default::default(),
         compiled_wasm_module_store: Default::default(),
         module_loader_factory,
@@ -591,6 +598,7 @@ impl CliMainWorkerFactory {
         argv0: shared.options.argv0.clone(),
         node_debug: shared.options.node_debug.clone(),
         node_ipc_fd: shared.options.node_ipc,
+        deno_ipc: shared.options.deno_ipc.clone(),
         future: shared.enable_future_features,
         mode,
         serve_port: shared.options.serve_port,
@@ -738,6 +746,14 @@ fn create_web_worker_callback(
         args.parent_permissions.clone(),
         args.permissions.clone(),
       );
+    // Workers that the worker spawns write to the same stdio as the worker.
+    let mut stdio = stdio.clone();
+    if let Some(stdout) = args.stdout {
+      stdio.stdout = stdout;
+    }
+    if let Some(stderr) = args.stderr {
+      stdio.stderr = stderr;
+    }
     let create_web_worker_cb =
       create_web_worker_callback(shared.clone(), stdio.clone());
 
@@ -787,6 +803,7 @@ fn create_web_worker_callback(
         argv0: shared.options.argv0.clone(),
         node_debug: shared.options.node_debug.clone(),
         node_ipc_fd: None,
+        deno_ipc: None,
         future: shared.enable_future_features,
         mode: WorkerExecutionMode::Worker,
         serve_port: shared.options.serve_port,
@@ -814,12 +831,13 @@ fn create_web_worker_callback(
       compiled_wasm_module_store: Some(
         shared.compiled_wasm_module_store.clone(),
       ),
-      stdio: stdio.clone(),
+      stdio,
       cache_storage_dir,
       feature_checker,
       strace_ops: shared.options.strace_ops.clone(),
       close_on_idle: args.close_on_idle,
       maybe_worker_metadata: args.maybe_worker_metadata,
+      limits: args.limits,
     };
 
     WebWorker::bootstrap_from_options(
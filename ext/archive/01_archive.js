@@ -0,0 +1,43 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+import { core, primordials } from "ext:core/mod.js";
+import {
+  op_archive_pack,
+  op_archive_unpack,
+  op_archive_unpack_file,
+  op_archive_unpack_finish,
+} from "ext:core/ops";
+const {
+  ObjectPrototypeIsPrototypeOf,
+} = primordials;
+
+import { pathFromURL } from "ext:deno_web/00_infra.js";
+import {
+  readableStreamForRid,
+  ReadableStreamPrototype,
+  writableStreamForRid,
+} from "ext:deno_web/06_streams.js";
+
+function createArchive(root, options = {}) {
+  const rid = op_archive_pack(pathFromURL(root), options);
+  return readableStreamForRid(rid);
+}
+
+async function extractArchive(source, dest, options = {}) {
+  dest = pathFromURL(dest);
+  if (!ObjectPrototypeIsPrototypeOf(ReadableStreamPrototype, source)) {
+    await op_archive_unpack_file(pathFromURL(source), dest, options);
+    return;
+  }
+
+  const rid = op_archive_unpack(dest, options);
+  try {
+    await source.pipeTo(writableStreamForRid(rid, false));
+  } catch (error) {
+    core.tryClose(rid);
+    throw error;
+  }
+  await op_archive_unpack_finish(rid);
+}
+
+export { createArchive, extractArchive };
@@ -0,0 +1,308 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+mod pack;
+mod unpack;
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use deno_core::error::AnyError;
+use deno_core::op2;
+use deno_core::unsync::spawn_blocking;
+use deno_core::unsync::JoinHandle;
+use deno_core::AsyncRefCell;
+use deno_core::AsyncResult;
+use deno_core::BufView;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_core::WriteOutcome;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+pub use crate::pack::PackOptions;
+pub use crate::unpack::UnpackOptions;
+
+pub const UNSTABLE_FEATURE_NAME: &str = "archive";
+
+/// How many chunks can be in flight between JavaScript and the task which
+/// packs or unpacks the archive.
+const CHANNEL_CAPACITY: usize = 4;
+
+fn check_unstable(state: &OpState, api_name: &str) {
+  // TODO(bartlomieju): replace with `state.feature_checker.check_or_exit`
+  // once we phase out `check_or_exit_with_legacy_fallback`
+  state
+    .feature_checker
+    .check_or_exit_with_legacy_fallback(UNSTABLE_FEATURE_NAME, api_name)
+}
+
+pub trait ArchivePermissions {
+  fn check_read(&mut self, path: &Path, api_name: &str)
+    -> Result<(), AnyError>;
+  fn check_write(
+    &mut self,
+    path: &Path,
+    api_name: &str,
+  ) -> Result<(), AnyError>;
+}
+
+impl ArchivePermissions for deno_permissions::PermissionsContainer {
+  #[inline(always)]
+  fn check_read(
+    &mut self,
+    path: &Path,
+    api_name: &str,
+  ) -> Result<(), AnyError> {
+    deno_permissions::PermissionsContainer::check_read(self, path, api_name)
+  }
+
+  #[inline(always)]
+  fn check_write(
+    &mut self,
+    path: &Path,
+    api_name: &str,
+  ) -> Result<(), AnyError> {
+    deno_permissions::PermissionsContainer::check_write(self, path, api_name)
+  }
+}
+
+deno_core::extension!(deno_archive,
+  deps = [ deno_web ],
+  parameters = [P: ArchivePermissions],
+  ops = [
+    op_archive_pack<P>,
+    op_archive_unpack<P>,
+    op_archive_unpack_finish,
+    op_archive_unpack_file<P>,
+  ],
+  esm = [ "01_archive.js" ],
+);
+
+#[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+  #[default]
+  None,
+  Gzip,
+  Zstd,
+}
+
+struct PackReader {
+  rx: mpsc::Receiver<Vec<u8>>,
+  /// What is left of the last chunk, if it was larger than the read.
+  pending: Option<BufView>,
+}
+
+/// The archive of a directory, which is packed by a blocking task while it's
+/// read.
+struct PackResource {
+  reader: AsyncRefCell<PackReader>,
+  task: RefCell<Option<JoinHandle<io::Result<()>>>>,
+  cancel: CancelHandle,
+}
+
+impl PackResource {
+  async fn read_chunk(
+    self: Rc<Self>,
+    limit: usize,
+  ) -> Result<BufView, AnyError> {
+    let mut reader = RcRef::map(&self, |r| &r.reader).borrow_mut().await;
+    let mut chunk = match reader.pending.take() {
+      Some(chunk) => chunk,
+      None => {
+        let cancel = RcRef::map(&self, |r| &r.cancel);
+        match reader.rx.recv().or_cancel(cancel).await? {
+          Some(chunk) => BufView::from(chunk),
+          None => {
+            // the archive is complete, unless packing it failed
+            let task = self.task.borrow_mut().take();
+            if let Some(task) = task {
+              task.await??;
+            }
+            return Ok(BufView::empty());
+          }
+        }
+      }
+    };
+    if chunk.len() > limit {
+      reader.pending = Some(chunk.split_off(limit));
+    }
+    Ok(chunk)
+  }
+}
+
+impl Resource for PackResource {
+  fn name(&self) -> Cow<str> {
+    "archivePack".into()
+  }
+
+  fn read(self: Rc<Self>, limit: usize) -> AsyncResult<BufView> {
+    Box::pin(self.read_chunk(limit))
+  }
+
+  fn close(self: Rc<Self>) {
+    // the task stops once it can't send any more chunks
+    self.cancel.cancel();
+  }
+}
+
+/// An archive which is unpacked by a blocking task while it's written.
+struct UnpackResource {
+  tx: RefCell<Option<mpsc::Sender<Vec<u8>>>>,
+  task: RefCell<Option<JoinHandle<io::Result<()>>>>,
+}
+
+impl UnpackResource {
+  async fn write_chunk(
+    self: Rc<Self>,
+    buf: BufView,
+  ) -> Result<usize, AnyError> {
+    let tx = self.tx.borrow().clone();
+    let Some(tx) = tx else {
+      return Err(closed_error());
+    };
+    let nwritten = buf.len();
+    if tx.send(buf.to_vec()).await.is_err() {
+      // unpacking stopped early, because it failed
+      self.finish().await?;
+      return Err(closed_error());
+    }
+    Ok(nwritten)
+  }
+
+  /// Ends the archive, and waits for it to be unpacked.
+  async fn finish(&self) -> Result<(), AnyError> {
+    self.tx.borrow_mut().take();
+    let task = self.task.borrow_mut().take();
+    if let Some(task) = task {
+      task.await??;
+    }
+    Ok(())
+  }
+}
+
+impl Resource for UnpackResource {
+  fn name(&self) -> Cow<str> {
+    "archiveUnpack".into()
+  }
+
+  fn write(self: Rc<Self>, buf: BufView) -> AsyncResult<WriteOutcome> {
+    Box::pin(async move {
+      let nwritten = self.write_chunk(buf).await?;
+      Ok(WriteOutcome::Full { nwritten })
+    })
+  }
+}
+
+fn closed_error() -> AnyError {
+  io::Error::new(io::ErrorKind::BrokenPipe, "The archive was finished").into()
+}
+
+#[op2]
+#[smi]
+pub fn op_archive_pack<P>(
+  state: &mut OpState,
+  #[string] root: String,
+  #[serde] options: PackOptions,
+) -> Result<ResourceId, AnyError>
+where
+  P: ArchivePermissions + 'static,
+{
+  check_unstable(state, "Deno.createArchive");
+  let root = PathBuf::from(root);
+  state
+    .borrow_mut::<P>()
+    .check_read(&root, "Deno.createArchive()")?;
+
+  let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+  let task = spawn_blocking(move || {
+    pack::pack(&root, &options, pack::ChunkWriter::new(tx))
+  });
+  let rid = state.resource_table.add(PackResource {
+    reader: AsyncRefCell::new(PackReader { rx, pending: None }),
+    task: RefCell::new(Some(task)),
+    cancel: CancelHandle::new(),
+  });
+  Ok(rid)
+}
+
+#[op2]
+#[smi]
+pub fn op_archive_unpack<P>(
+  state: &mut OpState,
+  #[string] dest: String,
+  #[serde] options: UnpackOptions,
+) -> Result<ResourceId, AnyError>
+where
+  P: ArchivePermissions + 'static,
+{
+  check_unstable(state, "Deno.extractArchive");
+  let dest = PathBuf::from(dest);
+  state
+    .borrow_mut::<P>()
+    .check_write(&dest, "Deno.extractArchive()")?;
+
+  let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+  let task = spawn_blocking(move || {
+    let mut reader = unpack::ChunkReader::new(rx);
+    unpack::unpack(&mut reader, &dest, &options)?;
+    // what comes after the end of the archive, e.g. padding, is left out
+    io::copy(&mut reader, &mut io::sink())?;
+    Ok(())
+  });
+  let rid = state.resource_table.add(UnpackResource {
+    tx: RefCell::new(Some(tx)),
+    task: RefCell::new(Some(task)),
+  });
+  Ok(rid)
+}
+
+#[op2(async)]
+pub async fn op_archive_unpack_finish(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<(), AnyError> {
+  let resource = state
+    .borrow_mut()
+    .resource_table
+    .take::<UnpackResource>(rid)?;
+  resource.finish().await
+}
+
+#[op2(async)]
+pub async fn op_archive_unpack_file<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] archive: String,
+  #[string] dest: String,
+  #[serde] options: UnpackOptions,
+) -> Result<(), AnyError>
+where
+  P: ArchivePermissions + 'static,
+{
+  let archive = PathBuf::from(archive);
+  let dest = PathBuf::from(dest);
+  {
+    let mut state = state.borrow_mut();
+    check_unstable(&state, "Deno.extractArchive");
+    let permissions = state.borrow_mut::<P>();
+    permissions.check_read(&archive, "Deno.extractArchive()")?;
+    permissions.check_write(&dest, "Deno.extractArchive()")?;
+  }
+
+  spawn_blocking(move || {
+    let file = File::open(archive)?;
+    unpack::unpack(BufReader::new(file), &dest, &options)
+  })
+  .await??;
+  Ok(())
+}
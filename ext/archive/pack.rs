@@ -0,0 +1,102 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+
+use flate2::write::GzEncoder;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use crate::Compression;
+
+/// The size of the chunks which the archive is sent in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PackOptions {
+  pub compression: Compression,
+  /// The directory which the entries are put in, e.g. `package`.
+  pub prefix: Option<String>,
+}
+
+/// Writes the archive of `root` to `writer`. Symlinks are stored as they
+/// are, rather than followed.
+pub fn pack(
+  root: &Path,
+  options: &PackOptions,
+  writer: impl Write,
+) -> io::Result<()> {
+  match options.compression {
+    Compression::None => append_all(root, options, writer)?.flush(),
+    Compression::Gzip => {
+      let encoder = GzEncoder::new(writer, flate2::Compression::default());
+      append_all(root, options, encoder)?.finish()?.flush()
+    }
+    Compression::Zstd => {
+      let encoder = zstd::Encoder::new(writer, 0)?;
+      append_all(root, options, encoder)?.finish()?.flush()
+    }
+  }
+}
+
+fn append_all<W: Write>(
+  root: &Path,
+  options: &PackOptions,
+  writer: W,
+) -> io::Result<W> {
+  let prefix = Path::new(options.prefix.as_deref().unwrap_or(""));
+  let mut builder = tar::Builder::new(writer);
+  builder.follow_symlinks(false);
+  if fs::metadata(root)?.is_dir() {
+    builder.append_dir_all(prefix, root)?;
+  } else {
+    // a single file is stored under its name
+    let name = root.file_name().ok_or_else(|| {
+      io::Error::new(io::ErrorKind::InvalidInput, "Invalid archive root")
+    })?;
+    builder.append_path_with_name(root, prefix.join(name))?;
+  }
+  builder.into_inner()
+}
+
+/// Sends what is written to it to the resource which the archive is read
+/// from, in chunks of [`CHUNK_SIZE`]. Writing blocks while the resource has
+/// enough chunks to read, and fails once it was closed.
+pub struct ChunkWriter {
+  chunk: Vec<u8>,
+  tx: mpsc::Sender<Vec<u8>>,
+}
+
+impl ChunkWriter {
+  pub fn new(tx: mpsc::Sender<Vec<u8>>) -> Self {
+    Self {
+      chunk: Vec::with_capacity(CHUNK_SIZE),
+      tx,
+    }
+  }
+}
+
+impl Write for ChunkWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let len = buf.len().min(CHUNK_SIZE - self.chunk.len());
+    self.chunk.extend_from_slice(&buf[..len]);
+    if self.chunk.len() == CHUNK_SIZE {
+      self.flush()?;
+    }
+    Ok(len)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    if self.chunk.is_empty() {
+      return Ok(());
+    }
+    let chunk =
+      std::mem::replace(&mut self.chunk, Vec::with_capacity(CHUNK_SIZE));
+    self.tx.blocking_send(chunk).map_err(|_| {
+      io::Error::new(io::ErrorKind::BrokenPipe, "The archive was closed")
+    })
+  }
+}
@@ -0,0 +1,273 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use flate2::read::MultiGzDecoder;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use crate::Compression;
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "camelCase")]
+pub struct UnpackOptions {
+  /// Detected from the first bytes of the archive if not given.
+  pub compression: Option<Compression>,
+  /// How many leading components of the paths to leave out, e.g. 1 for the
+  /// `package` directory of npm tarballs.
+  pub strip_components: usize,
+}
+
+/// Unpacks the archive read from `reader` into `dest`, which is created if
+/// it doesn't exist. Like `tar`, entries whose paths contain `..` are left
+/// out, and nothing is written outside of `dest`, also not through symlinks
+/// in the archive.
+pub fn unpack(
+  mut reader: impl Read,
+  dest: &Path,
+  options: &UnpackOptions,
+) -> io::Result<()> {
+  let mut magic = Vec::with_capacity(4);
+  (&mut reader).take(4).read_to_end(&mut magic)?;
+  let compression = options.compression.unwrap_or(match magic.as_slice() {
+    [0x1f, 0x8b, ..] => Compression::Gzip,
+    [0x28, 0xb5, 0x2f, 0xfd] => Compression::Zstd,
+    _ => Compression::None,
+  });
+  let reader = io::Cursor::new(magic).chain(reader);
+  let strip = options.strip_components;
+  match compression {
+    Compression::None => unpack_entries(reader, dest, strip),
+    Compression::Gzip => {
+      unpack_entries(MultiGzDecoder::new(reader), dest, strip)
+    }
+    Compression::Zstd => {
+      unpack_entries(zstd::Decoder::new(reader)?, dest, strip)
+    }
+  }
+}
+
+fn unpack_entries(
+  reader: impl Read,
+  dest: &Path,
+  strip_components: usize,
+) -> io::Result<()> {
+  fs::create_dir_all(dest)?;
+  let dest = dest.canonicalize()?;
+  let mut archive = tar::Archive::new(reader);
+  // the modes of directories are set last, so that read-only ones can be
+  // unpacked into
+  let mut dirs = vec![];
+  for entry in archive.entries()? {
+    let mut entry = entry?;
+    let Some(path) = entry_path(&dest, &entry.path()?, strip_components) else {
+      continue;
+    };
+    create_parent(&dest, &path)?;
+    let entry_type = entry.header().entry_type();
+    if entry_type.is_dir() {
+      fs::create_dir_all(&path)?;
+      if !path.canonicalize()?.starts_with(&dest) {
+        return Err(outside_dest(&path));
+      }
+      dirs.push((path, entry.header().mode()?));
+    } else if entry_type.is_hard_link() {
+      // the target is in the archive as well, so its path is stripped too
+      let target = entry
+        .link_name()?
+        .and_then(|target| entry_path(&dest, &target, strip_components))
+        .ok_or_else(|| outside_dest(&path))?;
+      create_parent(&dest, &target)?;
+      match fs::remove_file(&path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+      }
+      fs::hard_link(target, &path)?;
+    } else {
+      entry.unpack(&path)?;
+    }
+  }
+  for (path, mode) in dirs.into_iter().rev() {
+    set_dir_mode(&path, mode)?;
+  }
+  Ok(())
+}
+
+#[cfg(unix)]
+fn set_dir_mode(path: &Path, mode: u32) -> io::Result<()> {
+  use std::os::unix::fs::PermissionsExt;
+  fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))
+}
+
+#[cfg(not(unix))]
+fn set_dir_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+  Ok(())
+}
+
+/// Returns where an entry at `path` in the archive is unpacked to, or `None`
+/// if it's left out.
+fn entry_path(
+  dest: &Path,
+  path: &Path,
+  strip_components: usize,
+) -> Option<PathBuf> {
+  let mut relative = PathBuf::new();
+  let mut stripped = 0;
+  for component in path.components() {
+    match component {
+      Component::Normal(_) if stripped < strip_components => stripped += 1,
+      Component::Normal(part) => relative.push(part),
+      // leading slashes are ignored, like `tar` does
+      Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+      Component::ParentDir => return None,
+    }
+  }
+  if relative.as_os_str().is_empty() {
+    return None;
+  }
+  Some(dest.join(relative))
+}
+
+/// Creates the parent directory of `path`, and makes sure that it's inside
+/// of `dest` after following symlinks.
+fn create_parent(dest: &Path, path: &Path) -> io::Result<()> {
+  let parent = path.parent().unwrap_or(dest);
+  fs::create_dir_all(parent)?;
+  if !parent.canonicalize()?.starts_with(dest) {
+    return Err(outside_dest(path));
+  }
+  Ok(())
+}
+
+fn outside_dest(path: &Path) -> io::Error {
+  io::Error::new(
+    io::ErrorKind::InvalidData,
+    format!(
+      "Archive entry {} is outside of the destination",
+      path.display()
+    ),
+  )
+}
+
+/// Reads the chunks which are written to the resource that the archive is
+/// unpacked from. Reading blocks until the next chunk was written, and ends
+/// once the resource was closed.
+pub struct ChunkReader {
+  chunk: Vec<u8>,
+  pos: usize,
+  rx: mpsc::Receiver<Vec<u8>>,
+}
+
+impl ChunkReader {
+  pub fn new(rx: mpsc::Receiver<Vec<u8>>) -> Self {
+    Self {
+      chunk: vec![],
+      pos: 0,
+      rx,
+    }
+  }
+}
+
+impl Read for ChunkReader {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    while self.pos == self.chunk.len() {
+      match self.rx.blocking_recv() {
+        Some(chunk) => {
+          self.chunk = chunk;
+          self.pos = 0;
+        }
+        None => return Ok(0),
+      }
+    }
+    let len = buf.len().min(self.chunk.len() - self.pos);
+    buf[..len].copy_from_slice(&self.chunk[self.pos..self.pos + len]);
+    self.pos += len;
+    Ok(len)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::pack::pack;
+  use crate::pack::PackOptions;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+      .join(format!("deno_archive_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn pack_and_unpack() {
+    let root = temp_dir("root");
+    fs::create_dir_all(root.join("src/sub")).unwrap();
+    fs::write(root.join("mod.ts"), "mod").unwrap();
+    fs::write(root.join("src/sub/a.ts"), "a").unwrap();
+
+    for compression in [Compression::None, Compression::Gzip, Compression::Zstd]
+    {
+      let options = PackOptions {
+        compression,
+        prefix: Some("package".to_string()),
+      };
+      let mut archive = vec![];
+      pack(&root, &options, &mut archive).unwrap();
+
+      let dest = temp_dir("dest");
+      let options = UnpackOptions {
+        compression: None,
+        strip_components: 1,
+      };
+      unpack(archive.as_slice(), &dest, &options).unwrap();
+      assert_eq!(fs::read_to_string(dest.join("mod.ts")).unwrap(), "mod");
+      assert_eq!(fs::read_to_string(dest.join("src/sub/a.ts")).unwrap(), "a");
+      fs::remove_dir_all(dest).unwrap();
+    }
+    fs::remove_dir_all(root).unwrap();
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn no_writes_through_symlinks() {
+    let outside = temp_dir("outside");
+    let mut builder = tar::Builder::new(vec![]);
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Symlink);
+    header.set_size(0);
+    builder.append_link(&mut header, "link", &outside).unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_size(1);
+    builder
+      .append_data(&mut header, "link/file", &b"x"[..])
+      .unwrap();
+    let archive = builder.into_inner().unwrap();
+
+    let dest = temp_dir("symlink_dest");
+    let err =
+      unpack(archive.as_slice(), &dest, &Default::default()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(!outside.join("file").exists());
+    fs::remove_dir_all(dest).unwrap();
+    fs::remove_dir_all(outside).unwrap();
+  }
+
+  #[test]
+  fn entry_paths() {
+    let dest = Path::new("/dest");
+    let path = |p: &str, strip| entry_path(dest, Path::new(p), strip);
+    assert_eq!(path("a/b", 0), Some(dest.join("a/b")));
+    assert_eq!(path("/a/./b", 0), Some(dest.join("a/b")));
+    assert_eq!(path("package/a", 1), Some(dest.join("a")));
+    assert_eq!(path("package/", 1), None);
+    assert_eq!(path("a/../../b", 0), None);
+    assert_eq!(path(".", 0), None);
+  }
+}
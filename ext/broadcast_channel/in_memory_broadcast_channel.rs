@@ -9,10 +9,16 @@ use tokio::sync::broadcast;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
+use crate::ipc_relay;
 use crate::BroadcastChannel;
 
 #[derive(Clone)]
-pub struct InMemoryBroadcastChannel(Arc<Mutex<broadcast::Sender<Message>>>);
+pub struct InMemoryBroadcastChannel {
+  tx: Arc<Mutex<broadcast::Sender<Message>>>,
+  /// Relays messages to other processes, see
+  /// [`InMemoryBroadcastChannel::with_ipc`].
+  ipc: Option<mpsc::UnboundedSender<Message>>,
+}
 
 pub struct InMemoryBroadcastChannelResource {
   rx: tokio::sync::Mutex<(
@@ -24,16 +30,33 @@ pub struct InMemoryBroadcastChannelResource {
 }
 
 #[derive(Clone, Debug)]
-struct Message {
-  name: Arc<String>,
-  data: Arc<Vec<u8>>,
-  uuid: Uuid,
+pub(crate) struct Message {
+  pub name: Arc<String>,
+  pub data: Arc<Vec<u8>>,
+  pub uuid: Uuid,
 }
 
 impl Default for InMemoryBroadcastChannel {
   fn default() -> Self {
     let (tx, _) = broadcast::channel(256);
-    Self(Arc::new(Mutex::new(tx)))
+    Self {
+      tx: Arc::new(Mutex::new(tx)),
+      ipc: None,
+    }
+  }
+}
+
+impl InMemoryBroadcastChannel {
+  /// A channel that also exchanges messages with the other processes that
+  /// use the same `path`, which is the path of a Unix socket, or the name of
+  /// a named pipe on Windows.
+  pub fn with_ipc(path: String) -> Self {
+    let channel = Self::default();
+    let ipc = ipc_relay::start(path, channel.tx.clone());
+    Self {
+      ipc: Some(ipc),
+      ..channel
+    }
   }
 }
 
@@ -43,7 +66,7 @@ impl BroadcastChannel for InMemoryBroadcastChannel {
 
   fn subscribe(&self) -> Result<Self::Resource, AnyError> {
     let (cancel_tx, cancel_rx) = mpsc::unbounded_channel();
-    let broadcast_rx = self.0.lock().subscribe();
+    let broadcast_rx = self.tx.lock().subscribe();
     let rx = tokio::sync::Mutex::new((broadcast_rx, cancel_rx));
     let uuid = Uuid::new_v4();
     Ok(Self::Resource {
@@ -65,8 +88,15 @@ impl BroadcastChannel for InMemoryBroadcastChannel {
   ) -> Result<(), AnyError> {
     let name = Arc::new(name);
     let data = Arc::new(data);
-    let uuid = resource.uuid;
-    self.0.lock().send(Message { name, data, uuid })?;
+    let message = Message {
+      name,
+      data,
+      uuid: resource.uuid,
+    };
+    if let Some(ipc) = &self.ipc {
+      _ = ipc.send(message.clone());
+    }
+    self.tx.lock().send(message)?;
     Ok(())
   }
 
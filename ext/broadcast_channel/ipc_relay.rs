@@ -0,0 +1,366 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Relays the messages of an [`InMemoryBroadcastChannel`] between the
+//! processes that use the same IPC path: a Unix socket, or a named pipe on
+//! Windows.
+//!
+//! The first process to claim the path becomes the hub, which listens on it
+//! and forwards the messages of every process to all others. The other
+//! processes connect to the hub. When the hub exits, the remaining processes
+//! claim the path again, and one of them becomes the new hub. Messages that
+//! are sent while a process is not connected are not delivered to other
+//! processes.
+//!
+//! [`InMemoryBroadcastChannel`]: crate::InMemoryBroadcastChannel
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use deno_core::parking_lot::Mutex;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::in_memory_broadcast_channel::Message;
+
+/// Frames larger than this are considered corrupt.
+const MAX_FRAME_SIZE: usize = 64 << 20;
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The uuid of messages from other processes, which no local resource has.
+const REMOTE_UUID: Uuid = Uuid::nil();
+
+/// An encoded message: its length, the length of its name, the name and the
+/// data, with lengths as big endian `u32`s.
+type Frame = Arc<Vec<u8>>;
+
+type LocalSender = Arc<Mutex<broadcast::Sender<Message>>>;
+
+/// Starts relaying messages on a thread of its own, as the channel is shared
+/// by all workers. Returns the sender for messages sent in this process.
+pub(crate) fn start(
+  path: String,
+  local: LocalSender,
+) -> mpsc::UnboundedSender<Message> {
+  let (tx, rx) = mpsc::unbounded_channel();
+  std::thread::Builder::new()
+    .name("broadcast-channel-ipc".to_string())
+    .spawn(move || {
+      let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+      rt.block_on(run(path, local, rx));
+    })
+    .expect("Failed to spawn BroadcastChannel IPC thread");
+  tx
+}
+
+/// How the connection to the other processes ended.
+enum End {
+  /// All channels of this process were dropped.
+  Closed,
+  /// The hub went away, or the path could not be claimed.
+  Retry,
+}
+
+async fn run(
+  path: String,
+  local: LocalSender,
+  mut outgoing: mpsc::UnboundedReceiver<Message>,
+) {
+  loop {
+    let end = match sys::connect(&path).await {
+      Ok(conn) => run_peer(conn, &local, &mut outgoing).await,
+      Err(_) => match sys::Listener::claim(&path) {
+        Ok(Some(listener)) => run_hub(listener, &local, &mut outgoing).await,
+        // Another process claimed the path, but may not listen yet.
+        Ok(None) => End::Retry,
+        Err(err) => {
+          log::debug!(
+            "Failed to claim BroadcastChannel IPC path {path}: {err}"
+          );
+          End::Retry
+        }
+      },
+    };
+    if let End::Closed = end {
+      return;
+    }
+
+    let sleep = tokio::time::sleep(RETRY_INTERVAL);
+    tokio::pin!(sleep);
+    loop {
+      tokio::select! {
+        _ = &mut sleep => break,
+        message = outgoing.recv() => {
+          if message.is_none() {
+            return;
+          }
+        }
+      }
+    }
+  }
+}
+
+async fn run_peer<S>(
+  conn: S,
+  local: &LocalSender,
+  outgoing: &mut mpsc::UnboundedReceiver<Message>,
+) -> End
+where
+  S: AsyncRead + AsyncWrite + Send + 'static,
+{
+  let (events_tx, mut events) = mpsc::unbounded_channel();
+  let hub = Connection::spawn(0, conn, events_tx);
+  loop {
+    tokio::select! {
+      message = outgoing.recv() => {
+        let Some(message) = message else {
+          return End::Closed;
+        };
+        hub.send(encode(&message));
+      }
+      event = events.recv() => match event {
+        Some(Event::Message { message, .. }) => deliver(local, message),
+        _ => return End::Retry,
+      },
+    }
+  }
+}
+
+async fn run_hub(
+  mut listener: sys::Listener,
+  local: &LocalSender,
+  outgoing: &mut mpsc::UnboundedReceiver<Message>,
+) -> End {
+  let (events_tx, mut events) = mpsc::unbounded_channel();
+  let mut peers = HashMap::new();
+  let mut next_id = 0;
+  loop {
+    tokio::select! {
+      conn = listener.accept() => match conn {
+        Ok(conn) => {
+          let peer = Connection::spawn(next_id, conn, events_tx.clone());
+          peers.insert(next_id, peer);
+          next_id += 1;
+        }
+        Err(err) => {
+          log::debug!("Failed to accept BroadcastChannel IPC peer: {err}");
+          return End::Retry;
+        }
+      },
+      message = outgoing.recv() => {
+        let Some(message) = message else {
+          return End::Closed;
+        };
+        let frame = encode(&message);
+        for peer in peers.values() {
+          peer.send(frame.clone());
+        }
+      }
+      Some(event) = events.recv() => match event {
+        Event::Message { from, frame, message } => {
+          deliver(local, message);
+          for (id, peer) in &peers {
+            if *id != from {
+              peer.send(frame.clone());
+            }
+          }
+        }
+        Event::Closed(id) => {
+          peers.remove(&id);
+        }
+      },
+    }
+  }
+}
+
+fn deliver(local: &LocalSender, message: Message) {
+  // There are no receivers if no channel is open in this process.
+  _ = local.lock().send(message);
+}
+
+enum Event {
+  Message {
+    from: usize,
+    frame: Frame,
+    message: Message,
+  },
+  Closed(usize),
+}
+
+/// A connection to another process, with tasks that write and read its
+/// frames. Dropping it stops writing.
+struct Connection(mpsc::UnboundedSender<Frame>);
+
+impl Connection {
+  fn spawn<S>(id: usize, conn: S, events: mpsc::UnboundedSender<Event>) -> Self
+  where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+  {
+    let (mut reader, mut writer) = tokio::io::split(conn);
+    let (tx, mut rx) = mpsc::unbounded_channel::<Frame>();
+    tokio::spawn(async move {
+      while let Some(frame) = rx.recv().await {
+        if writer.write_all(&frame).await.is_err() {
+          return;
+        }
+      }
+    });
+    tokio::spawn(async move {
+      while let Ok((frame, message)) = read_frame(&mut reader).await {
+        let event = Event::Message {
+          from: id,
+          frame,
+          message,
+        };
+        if events.send(event).is_err() {
+          return;
+        }
+      }
+      _ = events.send(Event::Closed(id));
+    });
+    Self(tx)
+  }
+
+  fn send(&self, frame: Frame) {
+    _ = self.0.send(frame);
+  }
+}
+
+fn encode(message: &Message) -> Frame {
+  let len = 4 + message.name.len() + message.data.len();
+  let mut frame = Vec::with_capacity(4 + len);
+  frame.extend_from_slice(&(len as u32).to_be_bytes());
+  frame.extend_from_slice(&(message.name.len() as u32).to_be_bytes());
+  frame.extend_from_slice(message.name.as_bytes());
+  frame.extend_from_slice(&message.data);
+  Arc::new(frame)
+}
+
+async fn read_frame<R: AsyncRead + Unpin>(
+  reader: &mut R,
+) -> io::Result<(Frame, Message)> {
+  let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid frame");
+  let len = reader.read_u32().await? as usize;
+  if !(4..=MAX_FRAME_SIZE).contains(&len) {
+    return Err(invalid());
+  }
+  let mut frame = vec![0; 4 + len];
+  frame[..4].copy_from_slice(&(len as u32).to_be_bytes());
+  reader.read_exact(&mut frame[4..]).await?;
+  let name_len = u32::from_be_bytes(frame[4..8].try_into().unwrap()) as usize;
+  let Some(name) = frame.get(8..8 + name_len) else {
+    return Err(invalid());
+  };
+  let name = String::from_utf8(name.to_vec()).map_err(|_| invalid())?;
+  let message = Message {
+    name: Arc::new(name),
+    data: Arc::new(frame[8 + name_len..].to_vec()),
+    uuid: REMOTE_UUID,
+  };
+  Ok((Arc::new(frame), message))
+}
+
+#[cfg(unix)]
+mod sys {
+  use std::fs::OpenOptions;
+  use std::io;
+
+  use fs3::FileExt;
+  use tokio::net::UnixListener;
+  use tokio::net::UnixStream;
+
+  pub async fn connect(path: &str) -> io::Result<UnixStream> {
+    UnixStream::connect(path).await
+  }
+
+  pub struct Listener {
+    inner: UnixListener,
+    /// Held while the process is the hub, so that only one process replaces
+    /// the socket of a hub that exited.
+    _lock: std::fs::File,
+  }
+
+  impl Listener {
+    /// Listens on `path`, unless another process claimed it.
+    pub fn claim(path: &str) -> io::Result<Option<Self>> {
+      let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(format!("{path}.lock"))?;
+      if let Err(err) = lock.try_lock_exclusive() {
+        return match err.kind() {
+          io::ErrorKind::WouldBlock => Ok(None),
+          _ => Err(err),
+        };
+      }
+      match std::fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+      }
+      let inner = UnixListener::bind(path)?;
+      Ok(Some(Self { inner, _lock: lock }))
+    }
+
+    pub async fn accept(&mut self) -> io::Result<UnixStream> {
+      Ok(self.inner.accept().await?.0)
+    }
+  }
+}
+
+#[cfg(windows)]
+mod sys {
+  use std::io;
+
+  use tokio::net::windows::named_pipe::ClientOptions;
+  use tokio::net::windows::named_pipe::NamedPipeClient;
+  use tokio::net::windows::named_pipe::NamedPipeServer;
+  use tokio::net::windows::named_pipe::ServerOptions;
+
+  const PIPE_PREFIX: &str = r"\\.\pipe\";
+
+  fn pipe_name(path: &str) -> String {
+    if path.starts_with(PIPE_PREFIX) {
+      path.to_string()
+    } else {
+      // Pipe names can't contain backslashes.
+      format!("{PIPE_PREFIX}{}", path.replace('\\', "/"))
+    }
+  }
+
+  pub async fn connect(path: &str) -> io::Result<NamedPipeClient> {
+    ClientOptions::new().open(pipe_name(path))
+  }
+
+  pub struct Listener {
+    name: String,
+    next: NamedPipeServer,
+  }
+
+  impl Listener {
+    /// Listens on `path`, unless another process claimed it.
+    pub fn claim(path: &str) -> io::Result<Option<Self>> {
+      let name = pipe_name(path);
+      match ServerOptions::new().first_pipe_instance(true).create(&name) {
+        Ok(next) => Ok(Some(Self { name, next })),
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => Ok(None),
+        Err(err) => Err(err),
+      }
+    }
+
+    pub async fn accept(&mut self) -> io::Result<NamedPipeServer> {
+      self.next.connect().await?;
+      let next = ServerOptions::new().create(&self.name)?;
+      Ok(std::mem::replace(&mut self.next, next))
+    }
+  }
+}
@@ -4,10 +4,15 @@ import { core, internals, primordials } from "ext:core/mod.js";
 const {
   isPromise,
 } = core;
-import { op_cron_create, op_cron_next } from "ext:core/ops";
+import {
+  op_cron_create,
+  op_cron_killed,
+  op_cron_next,
+} from "ext:core/ops";
 const {
   ArrayPrototypeJoin,
   NumberPrototypeToString,
+  SafePromiseRace,
   TypeError,
 } = primordials;
 
@@ -59,6 +64,7 @@ export function parseScheduleToString(
   if (typeof schedule === "string") {
     return schedule;
   } else {
+    const { second } = schedule;
     let {
       minute,
       hour,
@@ -86,7 +92,12 @@ export function parseScheduleToString(
       dayOfMonth = 1;
     }
 
-    return formatToCronSchedule(minute) +
+    // The seconds field is only added when specified, so that schedules
+    // without it keep running at the start of the minute.
+    const seconds = second !== undefined
+      ? formatToCronSchedule(second) + " "
+      : "";
+    return seconds + formatToCronSchedule(minute) +
       " " + formatToCronSchedule(hour) +
       " " + formatToCronSchedule(dayOfMonth) +
       " " + formatToCronSchedule(month) +
@@ -94,12 +105,19 @@ export function parseScheduleToString(
   }
 }
 
+interface CronOptions {
+  backoffSchedule?: number[];
+  signal?: AbortSignal;
+  jitter?: number;
+  overlap?: "skip" | "queue" | "kill";
+}
+
 function cron(
   name: string,
   schedule: string | Deno.CronSchedule,
   handlerOrOptions1:
     | (() => Promise<void> | void)
-    | ({ backoffSchedule?: number[]; signal?: AbortSignal }),
+    | CronOptions,
   handler2?: () => Promise<void> | void,
 ) {
   if (name === undefined) {
@@ -112,9 +130,7 @@ function cron(
   schedule = parseScheduleToString(schedule);
 
   let handler: () => Promise<void> | void;
-  let options:
-    | { backoffSchedule?: number[]; signal?: AbortSignal }
-    | undefined = undefined;
+  let options: CronOptions | undefined = undefined;
 
   if (typeof handlerOrOptions1 === "function") {
     handler = handlerOrOptions1;
@@ -132,7 +148,10 @@ function cron(
     name,
     schedule,
     options?.backoffSchedule,
+    options?.jitter,
+    options?.overlap,
   );
+  const overlap = options?.overlap;
 
   if (options?.signal) {
     const signal = options?.signal;
@@ -154,7 +173,12 @@ function cron(
       }
       try {
         const result = handler();
-        const _res = isPromise(result) ? (await result) : result;
+        if (isPromise(result) && overlap === "kill") {
+          // A killed execution is abandoned, so that the next one can start.
+          await SafePromiseRace([result, op_cron_killed(rid)]);
+        } else {
+          const _res = isPromise(result) ? (await result) : result;
+        }
         success = true;
       } catch (error) {
         // deno-lint-ignore no-console
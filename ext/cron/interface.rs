@@ -12,6 +12,9 @@ pub trait CronHandler {
 #[async_trait(?Send)]
 pub trait CronHandle {
   async fn next(&self, prev_success: bool) -> Result<bool, AnyError>;
+  /// Resolves to `true` if the running invocation is killed by the overlap
+  /// policy, or to `false` once the next invocation is requested.
+  async fn killed(&self) -> Result<bool, AnyError>;
   fn close(&self);
 }
 
@@ -20,4 +23,20 @@ pub struct CronSpec {
   pub name: String,
   pub cron_schedule: String,
   pub backoff_schedule: Option<Vec<u32>>,
+  /// The maximum random delay of invocations, in milliseconds.
+  pub jitter: Option<u32>,
+  pub overlap_policy: OverlapPolicy,
+}
+
+/// What happens when an invocation is due while the previous one still runs.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverlapPolicy {
+  /// The invocation is skipped.
+  #[default]
+  Skip,
+  /// The invocation runs once the previous one finished. Invocations that are
+  /// due while one is queued already are skipped.
+  Queue,
+  /// The previous invocation is killed, and the invocation runs right away.
+  Kill,
 }
@@ -2,6 +2,7 @@
 
 mod interface;
 pub mod local;
+mod schedule;
 
 use std::borrow::Cow;
 use std::cell::RefCell;
@@ -25,6 +26,7 @@ deno_core::extension!(deno_cron,
   ops = [
     op_cron_create<C>,
     op_cron_next<C>,
+    op_cron_killed<C>,
   ],
   esm = [ "01_cron.ts" ],
   options = {
@@ -56,6 +58,8 @@ fn op_cron_create<C>(
   #[string] name: String,
   #[string] cron_schedule: String,
   #[serde] backoff_schedule: Option<Vec<u32>>,
+  jitter: Option<u32>,
+  #[string] overlap: Option<String>,
 ) -> Result<ResourceId, AnyError>
 where
   C: CronHandler + 'static,
@@ -71,11 +75,19 @@ where
   };
 
   validate_cron_name(&name)?;
+  let overlap_policy = match overlap.as_deref() {
+    None | Some("skip") => OverlapPolicy::Skip,
+    Some("queue") => OverlapPolicy::Queue,
+    Some("kill") => OverlapPolicy::Kill,
+    Some(_) => return Err(type_error("Invalid overlap policy")),
+  };
 
   let handle = cron_handler.create(CronSpec {
     name,
     cron_schedule,
     backoff_schedule,
+    jitter,
+    overlap_policy,
   })?;
 
   let handle_rid = {
@@ -114,6 +126,32 @@ where
   cron_handler.next(prev_success).await
 }
 
+#[op2(async)]
+async fn op_cron_killed<C>(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<bool, AnyError>
+where
+  C: CronHandler + 'static,
+{
+  let cron_handler = {
+    let state = state.borrow();
+    let resource = match state.resource_table.get::<CronResource<C::EH>>(rid) {
+      Ok(resource) => resource,
+      Err(err) => {
+        if get_custom_error_class(&err) == Some("BadResource") {
+          return Ok(false);
+        } else {
+          return Err(err);
+        }
+      }
+    };
+    resource.handle.clone()
+  };
+
+  cron_handler.killed().await
+}
+
 fn validate_cron_name(name: &str) -> Result<(), AnyError> {
   if name.len() > 64 {
     return Err(type_error("Cron name is too long"));
@@ -16,19 +16,27 @@ use deno_core::futures;
 use deno_core::futures::FutureExt;
 use deno_core::unsync::spawn;
 use deno_core::unsync::JoinHandle;
+use rand::Rng;
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::WeakSender;
+use tokio::sync::oneshot;
 use tokio::sync::OwnedSemaphorePermit;
 use tokio::sync::Semaphore;
 
+use crate::schedule::Schedule;
 use crate::CronHandle;
 use crate::CronHandler;
 use crate::CronSpec;
+use crate::OverlapPolicy;
 
 const MAX_CRONS: usize = 100;
 const DISPATCH_CONCURRENCY_LIMIT: usize = 50;
 const MAX_BACKOFF_MS: u32 = 60 * 60 * 1_000; // 1 hour
 const MAX_BACKOFF_COUNT: usize = 5;
+const MAX_JITTER_MS: u32 = 60 * 60 * 1_000; // 1 hour
+
+/// Resolves when the execution it was dispatched with is killed.
+type KillReceiver = oneshot::Receiver<()>;
 const DEFAULT_BACKOFF_SCHEDULE: [u32; 5] = [100, 1_000, 5_000, 30_000, 60_000];
 
 pub struct LocalCronHandler {
@@ -45,8 +53,17 @@ struct RuntimeState {
 
 struct Cron {
   spec: CronSpec,
-  next_tx: mpsc::WeakSender<()>,
+  /// Dispatches executions, each with a receiver that kills it.
+  next_tx: mpsc::WeakSender<KillReceiver>,
   current_execution_retries: u32,
+  /// The deadline of the next execution, if one is scheduled. Entries of
+  /// `scheduled_deadlines` for other deadlines are stale.
+  deadline: Option<u64>,
+  /// Whether an execution is running.
+  running: bool,
+  /// Whether an execution starts as soon as the running one finishes.
+  pending: bool,
+  kill_tx: Option<oneshot::Sender<()>>,
 }
 
 impl Cron {
@@ -57,6 +74,15 @@ impl Cron {
       .as_deref()
       .unwrap_or(&DEFAULT_BACKOFF_SCHEDULE)
   }
+
+  fn next_deadline(&self) -> Result<u64, AnyError> {
+    let deadline = compute_next_deadline(&self.spec.cron_schedule)?;
+    let jitter = match self.spec.jitter {
+      Some(jitter) if jitter > 0 => rand::thread_rng().gen_range(0..=jitter),
+      _ => 0,
+    };
+    Ok(deadline + jitter as u64)
+  }
 }
 
 impl Default for LocalCronHandler {
@@ -113,28 +139,7 @@ impl LocalCronHandler {
 
       // Schedule next execution of the cron if needed.
       if let Some((name, prev_success)) = cron_to_schedule {
-        let mut runtime_state = runtime_state.borrow_mut();
-        if let Some(cron) = runtime_state.crons.get_mut(&name) {
-          let backoff_schedule = cron.backoff_schedule();
-          let next_deadline = if !prev_success
-            && cron.current_execution_retries < backoff_schedule.len() as u32
-          {
-            let backoff_ms =
-              backoff_schedule[cron.current_execution_retries as usize];
-            let now = chrono::Utc::now().timestamp_millis() as u64;
-            cron.current_execution_retries += 1;
-            now + backoff_ms as u64
-          } else {
-            let next_ts = compute_next_deadline(&cron.spec.cron_schedule)?;
-            cron.current_execution_retries = 0;
-            next_ts
-          };
-          runtime_state
-            .scheduled_deadlines
-            .entry(next_deadline)
-            .or_default()
-            .push(name.to_string());
-        }
+        runtime_state.borrow_mut().finished(&name, prev_success)?;
       }
 
       // Dispatch ready to execute crons.
@@ -142,9 +147,9 @@ impl LocalCronHandler {
         let mut runtime_state = runtime_state.borrow_mut();
         runtime_state.get_ready_crons()?
       };
-      for (_, tx) in crons_to_execute {
+      for (tx, kill_rx) in crons_to_execute {
         if let Some(tx) = tx.upgrade() {
-          let _ = tx.send(()).await;
+          let _ = tx.send(kill_rx).await;
         }
       }
     }
@@ -152,36 +157,94 @@ impl LocalCronHandler {
 }
 
 impl RuntimeState {
+  fn schedule(&mut self, name: &str, deadline: u64) {
+    if let Some(cron) = self.crons.get_mut(name) {
+      cron.deadline = Some(deadline);
+      self
+        .scheduled_deadlines
+        .entry(deadline)
+        .or_default()
+        .push(name.to_string());
+    }
+  }
+
+  /// Called when an execution of a cron finished, and once when the cron is
+  /// created.
+  fn finished(
+    &mut self,
+    name: &str,
+    prev_success: bool,
+  ) -> Result<(), AnyError> {
+    let Some(cron) = self.crons.get_mut(name) else {
+      return Ok(());
+    };
+    cron.running = false;
+    cron.kill_tx = None;
+    let now = chrono::Utc::now().timestamp_millis() as u64;
+    let backoff_schedule = cron.backoff_schedule();
+    let next_deadline = if cron.pending {
+      cron.pending = false;
+      cron.current_execution_retries = 0;
+      now
+    } else if !prev_success
+      && cron.current_execution_retries < backoff_schedule.len() as u32
+    {
+      let backoff_ms =
+        backoff_schedule[cron.current_execution_retries as usize];
+      cron.current_execution_retries += 1;
+      now + backoff_ms as u64
+    } else {
+      cron.current_execution_retries = 0;
+      if cron.deadline.is_some() {
+        return Ok(());
+      }
+      cron.next_deadline()?
+    };
+    self.schedule(name, next_deadline);
+    Ok(())
+  }
+
   fn get_ready_crons(
     &mut self,
-  ) -> Result<Vec<(String, WeakSender<()>)>, AnyError> {
+  ) -> Result<Vec<(WeakSender<KillReceiver>, KillReceiver)>, AnyError> {
     let now = chrono::Utc::now().timestamp_millis() as u64;
 
-    let ready = {
-      let to_remove = self
-        .scheduled_deadlines
-        .range(..=now)
-        .map(|(ts, _)| *ts)
-        .collect::<Vec<_>>();
-      to_remove
-        .iter()
-        .flat_map(|ts| {
-          self
-            .scheduled_deadlines
-            .remove(ts)
-            .unwrap()
-            .iter()
-            .map(move |name| (*ts, name.clone()))
-            .collect::<Vec<_>>()
-        })
-        .filter_map(|(_, name)| {
-          self
-            .crons
-            .get(&name)
-            .map(|c| (name.clone(), c.next_tx.clone()))
-        })
-        .collect::<Vec<_>>()
-    };
+    let deadlines = self
+      .scheduled_deadlines
+      .range(..=now)
+      .map(|(ts, _)| *ts)
+      .collect::<Vec<_>>();
+    let mut ready = vec![];
+    for ts in deadlines {
+      for name in self.scheduled_deadlines.remove(&ts).unwrap() {
+        let Some(cron) = self.crons.get_mut(&name) else {
+          continue;
+        };
+        if cron.deadline != Some(ts) {
+          continue;
+        }
+        cron.deadline = None;
+        if !cron.running {
+          let (kill_tx, kill_rx) = oneshot::channel();
+          cron.running = true;
+          cron.kill_tx = Some(kill_tx);
+          ready.push((cron.next_tx.clone(), kill_rx));
+        } else {
+          match cron.spec.overlap_policy {
+            OverlapPolicy::Skip => {}
+            OverlapPolicy::Queue => cron.pending = true,
+            OverlapPolicy::Kill => {
+              cron.pending = true;
+              if let Some(kill_tx) = cron.kill_tx.take() {
+                let _ = kill_tx.send(());
+              }
+            }
+          }
+        }
+        let next_deadline = cron.next_deadline()?;
+        self.schedule(&name, next_deadline);
+      }
+    }
 
     Ok(ready)
   }
@@ -215,21 +278,28 @@ impl CronHandler for LocalCronHandler {
     }
 
     // Validate schedule expression.
-    spec
-      .cron_schedule
-      .parse::<saffron::Cron>()
-      .map_err(|_| type_error("Invalid cron schedule"))?;
+    if Schedule::parse(&spec.cron_schedule).is_none() {
+      return Err(type_error("Invalid cron schedule"));
+    }
 
     // Validate backoff_schedule.
     if let Some(backoff_schedule) = &spec.backoff_schedule {
       validate_backoff_schedule(backoff_schedule)?;
     }
 
-    let (next_tx, next_rx) = mpsc::channel::<()>(1);
+    if spec.jitter.is_some_and(|jitter| jitter > MAX_JITTER_MS) {
+      return Err(type_error("Invalid jitter"));
+    }
+
+    let (next_tx, next_rx) = mpsc::channel::<KillReceiver>(1);
     let cron = Cron {
       spec: spec.clone(),
       next_tx: next_tx.downgrade(),
       current_execution_retries: 0,
+      deadline: None,
+      running: false,
+      pending: false,
+      kill_tx: None,
     };
     runtime_state.crons.insert(spec.name.clone(), cron);
 
@@ -241,6 +311,7 @@ impl CronHandler for LocalCronHandler {
       inner: RefCell::new(Inner {
         next_rx: Some(next_rx),
         shutdown_tx: Some(next_tx),
+        kill_rx: None,
         permit: None,
       }),
     })
@@ -256,8 +327,9 @@ pub struct CronExecutionHandle {
 }
 
 struct Inner {
-  next_rx: Option<mpsc::Receiver<()>>,
-  shutdown_tx: Option<mpsc::Sender<()>>,
+  next_rx: Option<mpsc::Receiver<KillReceiver>>,
+  shutdown_tx: Option<mpsc::Sender<KillReceiver>>,
+  kill_rx: Option<KillReceiver>,
   permit: Option<OwnedSemaphorePermit>,
 }
 
@@ -278,17 +350,25 @@ impl CronHandle for CronExecutionHandle {
     let Some(mut next_rx) = self.inner.borrow_mut().next_rx.take() else {
       return Ok(false);
     };
-    if next_rx.recv().await.is_none() {
+    let Some(kill_rx) = next_rx.recv().await else {
       return Ok(false);
     };
 
     let permit = self.concurrency_limiter.clone().acquire_owned().await?;
     let mut inner = self.inner.borrow_mut();
     inner.next_rx = Some(next_rx);
+    inner.kill_rx = Some(kill_rx);
     inner.permit = Some(permit);
     Ok(true)
   }
 
+  async fn killed(&self) -> Result<bool, AnyError> {
+    let Some(kill_rx) = self.inner.borrow_mut().kill_rx.take() else {
+      return Ok(false);
+    };
+    Ok(kill_rx.await.is_ok())
+  }
+
   fn close(&self) {
     if let Some(tx) = self.inner.borrow_mut().shutdown_tx.take() {
       drop(tx)
@@ -309,10 +389,9 @@ fn compute_next_deadline(cron_expression: &str) -> Result<u64, AnyError> {
     }
   }
 
-  let cron = cron_expression
-    .parse::<saffron::Cron>()
-    .map_err(|_| anyhow::anyhow!("invalid cron expression"))?;
-  let Some(next_deadline) = cron.next_after(now) else {
+  let schedule = Schedule::parse(cron_expression)
+    .ok_or_else(|| anyhow::anyhow!("invalid cron expression"))?;
+  let Some(next_deadline) = schedule.next_after(now) else {
     return Err(anyhow::anyhow!("invalid cron expression"));
   };
   Ok(next_deadline.timestamp_millis() as u64)
@@ -337,8 +416,9 @@ mod tests {
     let now = chrono::Utc::now().timestamp_millis() as u64;
     assert!(compute_next_deadline("*/1 * * * *").unwrap() > now);
     assert!(compute_next_deadline("* * * * *").unwrap() > now);
+    assert!(compute_next_deadline("* * * * * *").unwrap() > now);
     assert!(compute_next_deadline("bogus").is_err());
-    assert!(compute_next_deadline("* * * * * *").is_err());
+    assert!(compute_next_deadline("* * * * * * *").is_err());
     assert!(compute_next_deadline("* * *").is_err());
   }
 }
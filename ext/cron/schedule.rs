@@ -0,0 +1,129 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Cron schedules: the five fields of a Unix cron expression, optionally
+//! preceded by a sixth field for the seconds of the minute.
+
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::SubsecRound;
+use chrono::Timelike;
+use chrono::Utc;
+
+pub(crate) struct Schedule {
+  /// The seconds of a minute that the schedule matches, as bits.
+  seconds: u64,
+  cron: saffron::Cron,
+}
+
+impl Schedule {
+  pub fn parse(expression: &str) -> Option<Self> {
+    let fields = expression.split_whitespace().collect::<Vec<_>>();
+    let (seconds, cron) = match fields.len() {
+      5 => (1, expression.parse().ok()?),
+      6 => (
+        parse_seconds(fields[0])?,
+        fields[1..].join(" ").parse().ok()?,
+      ),
+      _ => return None,
+    };
+    Some(Self { seconds, cron })
+  }
+
+  /// The first time after `date` that the schedule matches.
+  pub fn next_after(&self, date: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let current_minute = date.trunc_subsecs(0).with_second(0)?;
+    let mut minute = self.cron.next_from(date)?;
+    loop {
+      let first_second = if minute == current_minute {
+        date.second() + 1
+      } else {
+        0
+      };
+      let seconds = self.seconds >> first_second << first_second;
+      if seconds != 0 {
+        return Some(
+          minute + Duration::seconds(seconds.trailing_zeros() as i64),
+        );
+      }
+      minute = self.cron.next_after(minute)?;
+    }
+  }
+}
+
+/// Parses a field of seconds, with the syntax of the minutes field: a list of
+/// seconds, ranges of seconds or `*`, each optionally followed by a step.
+fn parse_seconds(field: &str) -> Option<u64> {
+  let mut seconds = 0u64;
+  for part in field.split(',') {
+    let (range, step) = match part.split_once('/') {
+      Some((range, step)) => (range, step.parse().ok().filter(|s| *s > 0)?),
+      None => (part, 1),
+    };
+    let (start, end) = match range.split_once('-') {
+      _ if range == "*" => (0, 59),
+      Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+      // A single second with a step repeats until the end of the minute.
+      None if part.contains('/') => (range.parse().ok()?, 59),
+      None => {
+        let second = range.parse().ok()?;
+        (second, second)
+      }
+    };
+    if start > end || end > 59 {
+      return None;
+    }
+    for second in (start..=end).step_by(step) {
+      seconds |= 1 << second;
+    }
+  }
+  Some(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeZone;
+
+  use super::*;
+
+  #[test]
+  fn test_parse_seconds() {
+    assert_eq!(parse_seconds("*"), Some((1 << 60) - 1));
+    assert_eq!(parse_seconds("5"), Some(1 << 5));
+    assert_eq!(parse_seconds("1,3-4"), Some(0b11010));
+    assert_eq!(parse_seconds("*/20"), Some(1 | 1 << 20 | 1 << 40));
+    assert_eq!(parse_seconds("50/5"), Some(1 << 50 | 1 << 55));
+    assert_eq!(parse_seconds("10-30/10"), Some(1 << 10 | 1 << 20 | 1 << 30));
+    assert_eq!(parse_seconds("60"), None);
+    assert_eq!(parse_seconds("5-1"), None);
+    assert_eq!(parse_seconds("*/0"), None);
+    assert_eq!(parse_seconds("a"), None);
+  }
+
+  #[test]
+  fn test_next_after() {
+    let date = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 10).unwrap();
+    let next = |expression: &str| {
+      Schedule::parse(expression)
+        .unwrap()
+        .next_after(date)
+        .unwrap()
+    };
+    assert_eq!(
+      next("* * * * *"),
+      date.with_minute(1).unwrap().with_second(0).unwrap()
+    );
+    assert_eq!(next("* * * * * *"), date.with_second(11).unwrap());
+    assert_eq!(next("*/15 * * * * *"), date.with_second(15).unwrap());
+    assert_eq!(
+      next("5 * * * * *"),
+      date.with_minute(1).unwrap().with_second(5).unwrap()
+    );
+    assert_eq!(
+      next("30 0 1 * * *"),
+      Utc.with_ymd_and_hms(2024, 1, 1, 1, 0, 30).unwrap()
+    );
+    assert!(Schedule::parse("* * * *").is_none());
+    assert!(Schedule::parse("* * * * * * *").is_none());
+    assert!(Schedule::parse("60 * * * * *").is_none());
+  }
+}
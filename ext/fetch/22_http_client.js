@@ -13,11 +13,19 @@
 import { core, primordials } from "ext:core/mod.js";
 
 import { SymbolDispose } from "ext:deno_web/00_infra.js";
-import { op_fetch_custom_client } from "ext:core/ops";
+import {
+  op_fetch_client_connections,
+  op_fetch_custom_client,
+} from "ext:core/ops";
 import { loadTlsKeyPair } from "ext:deno_net/02_tls.js";
 
 const { internalRidSymbol } = core;
-const { ObjectDefineProperty } = primordials;
+const { ArrayPrototypeMap, ObjectDefineProperty } = primordials;
+
+function netAddr(hostname, port) {
+  if (hostname === null) return null;
+  return { transport: "tcp", hostname, port };
+}
 
 /**
  * @param {Deno.CreateHttpClientOptions} options
@@ -48,6 +56,23 @@ class HttpClient {
     this.#rid = rid;
   }
 
+  /**
+   * @returns {Deno.HttpClientConnection[]}
+   */
+  connections() {
+    return ArrayPrototypeMap(
+      op_fetch_client_connections(this.#rid),
+      (conn) => ({
+        origin: conn.origin,
+        protocol: conn.protocol,
+        proxied: conn.proxied,
+        localAddr: netAddr(conn.localAddrIp, conn.localAddrPort),
+        remoteAddr: netAddr(conn.remoteAddrIp, conn.remoteAddrPort),
+        openedAt: conn.openedAt,
+      }),
+    );
+  }
+
   close() {
     core.close(this.#rid);
   }
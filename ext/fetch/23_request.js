@@ -18,6 +18,7 @@ const {
   ObjectKeys,
   ObjectPrototypeIsPrototypeOf,
   RegExpPrototypeExec,
+  SafeArrayIterator,
   StringPrototypeStartsWith,
   Symbol,
   SymbolFor,
@@ -55,6 +56,8 @@ const _request = Symbol("request");
 const _headers = Symbol("headers");
 const _getHeaders = Symbol("get headers");
 const _headersCache = Symbol("headers cache");
+const PROGRESS_HOOKS = ["onUploadProgress", "onDownloadProgress"];
+
 const _signal = Symbol("signal");
 const _signalCache = Symbol("signalCache");
 const _mimeType = Symbol("mime type");
@@ -87,7 +90,11 @@ function processUrlList(urlList, urlListProcessed) {
  * @property {number} redirectCount
  * @property {(() => string)[]} urlList
  * @property {string[]} urlListProcessed
+ * @property {"half" | "full"} duplex NOTE: "full" is a non standard extension.
  * @property {number | null} clientRid NOTE: non standard extension for `Deno.HttpClient`.
+ * @property {{ proxy: Deno.Proxy | null, noProxy: string[] | null } | null} proxyOptions NOTE: non standard extension.
+ * @property {((progress: { loaded: number, total: number | null }) => void) | null} onUploadProgress NOTE: non standard extension.
+ * @property {((progress: { loaded: number, total: number | null }) => void) | null} onDownloadProgress NOTE: non standard extension.
  * @property {Blob | null} blobUrlEntry
  */
 
@@ -135,7 +142,11 @@ function newInnerRequest(method, url, headerList, body, maybeBlob) {
     redirectCount: 0,
     urlList: [typeof url === "string" ? () => url : url],
     urlListProcessed: [],
+    duplex: "half",
     clientRid: null,
+    proxyOptions: null,
+    onUploadProgress: null,
+    onDownloadProgress: null,
     blobUrlEntry,
     url() {
       if (this.urlListProcessed[0] === undefined) {
@@ -186,7 +197,11 @@ function cloneInnerRequest(request, skipBody = false) {
     redirectCount: request.redirectCount,
     urlList: [() => request.url()],
     urlListProcessed: [request.url()],
+    duplex: request.duplex,
     clientRid: request.clientRid,
+    proxyOptions: request.proxyOptions,
+    onUploadProgress: request.onUploadProgress,
+    onDownloadProgress: request.onDownloadProgress,
     blobUrlEntry: request.blobUrlEntry,
     url() {
       if (this.urlListProcessed[0] === undefined) {
@@ -365,6 +380,12 @@ class Request {
       signal = init.signal;
     }
 
+    // NOTE: "full" is a non standard extension. Request bodies are always
+    // streamed concurrently with the response, so this is only recorded.
+    if (init.duplex !== undefined) {
+      request.duplex = init.duplex;
+    }
+
     // NOTE: non standard extension. This handles Deno.HttpClient parameter
     if (init.client !== undefined) {
       if (
@@ -381,6 +402,39 @@ class Request {
       request.clientRid = init.client?.[internalRidSymbol] ?? null;
     }
 
+    // NOTE: non standard extension. Proxy settings of this request, which
+    // replace those of the default client.
+    if (init.proxy !== undefined || init.noProxy !== undefined) {
+      request.proxyOptions = {
+        proxy: init.proxy ?? request.proxyOptions?.proxy ?? null,
+        noProxy: init.noProxy ?? request.proxyOptions?.noProxy ?? null,
+      };
+    }
+    if (request.clientRid !== null && request.proxyOptions !== null) {
+      throw webidl.makeException(
+        TypeError,
+        "`proxy` and `noProxy` can't be used together with `client`",
+        prefix,
+        "Argument 2",
+      );
+    }
+
+    // NOTE: non standard extension. Progress of the request, reported by the
+    // fetch ops while the bodies are transferred.
+    for (const key of new SafeArrayIterator(PROGRESS_HOOKS)) {
+      if (init[key] !== undefined) {
+        if (init[key] !== null && typeof init[key] !== "function") {
+          throw webidl.makeException(
+            TypeError,
+            `\`${key}\` must be a function`,
+            prefix,
+            "Argument 2",
+          );
+        }
+        request[key] = init[key];
+      }
+    }
+
     // 28.
     this[_request] = request;
 
@@ -485,6 +539,11 @@ class Request {
     return this[_signal];
   }
 
+  get duplex() {
+    webidl.assertBranded(this, RequestPrototype);
+    return this[_request].duplex ?? "half";
+  }
+
   clone() {
     const prefix = "Failed to execute 'Request.clone'";
     webidl.assertBranded(this, RequestPrototype);
@@ -554,6 +613,35 @@ webidl.converters["RequestRedirect"] = webidl.createEnumConverter(
     "manual",
   ],
 );
+webidl.converters["RequestDuplex"] = webidl.createEnumConverter(
+  "RequestDuplex",
+  [
+    "half",
+    "full",
+  ],
+);
+webidl.converters["ProxyBasicAuth"] = webidl.createDictionaryConverter(
+  "ProxyBasicAuth",
+  [
+    {
+      key: "username",
+      converter: webidl.converters["DOMString"],
+      required: true,
+    },
+    {
+      key: "password",
+      converter: webidl.converters["DOMString"],
+      required: true,
+    },
+  ],
+);
+webidl.converters["Proxy"] = webidl.createDictionaryConverter(
+  "Proxy",
+  [
+    { key: "url", converter: webidl.converters["USVString"], required: true },
+    { key: "basicAuth", converter: webidl.converters["ProxyBasicAuth"] },
+  ],
+);
 webidl.converters["RequestInit"] = webidl.createDictionaryConverter(
   "RequestInit",
   [
@@ -572,7 +660,12 @@ webidl.converters["RequestInit"] = webidl.createDictionaryConverter(
         webidl.converters["AbortSignal"],
       ),
     },
+    { key: "duplex", converter: webidl.converters["RequestDuplex"] },
     { key: "client", converter: webidl.converters.any },
+    { key: "proxy", converter: webidl.converters["Proxy"] },
+    { key: "noProxy", converter: webidl.converters["sequence<DOMString>"] },
+    { key: "onUploadProgress", converter: webidl.converters.any },
+    { key: "onDownloadProgress", converter: webidl.converters.any },
   ],
 );
 
@@ -11,6 +11,7 @@
 /// <reference lib="esnext" />
 
 import { core, primordials } from "ext:core/mod.js";
+import { op_fetch_response_trailers } from "ext:core/ops";
 import * as webidl from "ext:deno_webidl/00_webidl.js";
 import { createFilteredInspectProxy } from "ext:deno_console/01_console.js";
 import {
@@ -59,6 +60,7 @@ const _response = Symbol("response");
 const _headers = Symbol("headers");
 const _mimeType = Symbol("mime type");
 const _body = Symbol("body");
+const _trailers = Symbol("trailers");
 const _brand = webidl.brand;
 
 /**
@@ -72,6 +74,7 @@ const _brand = webidl.brand;
  * @property {null | typeof __window.bootstrap.fetchBody.InnerBody} body
  * @property {boolean} aborted
  * @property {string} [error]
+ * @property {number | null} [trailersRid] NOTE: non standard extension for response trailers.
  */
 
 /**
@@ -162,6 +165,22 @@ function abortedNetworkError() {
   return resp;
 }
 
+/**
+ * @param {InnerResponse} response
+ * @returns {Promise<Headers>}
+ */
+async function trailersForResponse(response) {
+  const rid = response.trailersRid ?? null;
+  if (rid === null) {
+    return headersFromHeaderList([], "immutable");
+  }
+  const promise = op_fetch_response_trailers(rid);
+  // Waiting for the trailers must not keep the event loop alive on its own,
+  // they only arrive when the body is read.
+  core.unrefOpPromise(promise);
+  return headersFromHeaderList(await promise, "immutable");
+}
+
 /**
  * https://fetch.spec.whatwg.org#initialize-a-response
  * @param {Response} response
@@ -389,6 +408,18 @@ class Response {
     return this[_headers];
   }
 
+  /**
+   * NOTE: non standard extension. Must be accessed before the body is read.
+   * @returns {Promise<Headers>}
+   */
+  get trailers() {
+    webidl.assertBranded(this, ResponsePrototype);
+    if (this[_trailers] === undefined) {
+      this[_trailers] = trailersForResponse(this[_response]);
+    }
+    return this[_trailers];
+  }
+
   /**
    * @returns {Response}
    */
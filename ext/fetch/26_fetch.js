@@ -13,6 +13,7 @@
 import { core, primordials } from "ext:core/mod.js";
 import {
   op_fetch,
+  op_fetch_progress,
   op_fetch_send,
   op_wasm_streaming_feed,
   op_wasm_streaming_set_url,
@@ -55,6 +56,7 @@ import {
   toInnerResponse,
 } from "ext:deno_fetch/23_response.js";
 import * as abortSignal from "ext:deno_web/03_abort_signal.js";
+import { reportException } from "ext:deno_web/02_event.js";
 
 const REQUEST_BODY_HEADER_NAMES = [
   "content-encoding",
@@ -71,6 +73,58 @@ function opFetchSend(rid) {
   return op_fetch_send(rid);
 }
 
+/**
+ * Calls the progress hooks of a request whenever the fetch ops report that
+ * more bytes were sent or received, until both bodies are finished.
+ * @param {number} rid
+ * @param {InnerRequest} req
+ */
+async function reportProgress(rid, req) {
+  let uploaded = 0;
+  let uploadTotal = null;
+  let downloaded = 0;
+  let downloadTotal = null;
+  try {
+    while (true) {
+      const promise = op_fetch_progress(rid);
+      // The request itself keeps the event loop alive while it is in flight.
+      core.unrefOpPromise(promise);
+      const progress = await promise;
+      if (progress === null) break;
+      if (
+        req.onUploadProgress !== null &&
+        (progress.uploaded !== uploaded || progress.uploadTotal !== uploadTotal)
+      ) {
+        callProgressHook(req.onUploadProgress, {
+          loaded: progress.uploaded,
+          total: progress.uploadTotal,
+        });
+      }
+      if (
+        req.onDownloadProgress !== null &&
+        (progress.downloaded !== downloaded ||
+          progress.downloadTotal !== downloadTotal)
+      ) {
+        callProgressHook(req.onDownloadProgress, {
+          loaded: progress.downloaded,
+          total: progress.downloadTotal,
+        });
+      }
+      ({ uploaded, uploadTotal, downloaded, downloadTotal } = progress);
+    }
+  } finally {
+    core.tryClose(rid);
+  }
+}
+
+function callProgressHook(hook, progress) {
+  try {
+    hook(progress);
+  } catch (err) {
+    reportException(err);
+  }
+}
+
 /**
  * @param {number} responseBodyRid
  * @param {AbortSignal} [terminator]
@@ -149,7 +203,7 @@ async function mainFetch(req, recursive, terminator) {
     }
   }
 
-  const { requestRid, cancelHandleRid } = op_fetch(
+  const { requestRid, cancelHandleRid, progressRid } = op_fetch(
     req.method,
     req.currentUrl(),
     req.headerList,
@@ -157,7 +211,12 @@ async function mainFetch(req, recursive, terminator) {
     reqBody !== null || reqRid !== null,
     reqBody,
     reqRid,
+    req.onUploadProgress !== null || req.onDownloadProgress !== null,
+    req.proxyOptions,
   );
+  if (progressRid !== null) {
+    reportProgress(progressRid, req);
+  }
 
   function onAbort() {
     if (cancelHandleRid !== null) {
@@ -223,6 +282,7 @@ async function mainFetch(req, recursive, terminator) {
       response.body = new InnerBody(
         createResponseBodyStream(resp.responseRid, terminator),
       );
+      response.trailersRid = resp.responseRid;
     }
   }
 
@@ -142,6 +142,8 @@ declare type RequestCache =
 /** @category Fetch */
 declare type RequestCredentials = "include" | "omit" | "same-origin";
 /** @category Fetch */
+declare type RequestDuplex = "half" | "full";
+/** @category Fetch */
 declare type RequestMode = "cors" | "navigate" | "no-cors" | "same-origin";
 /** @category Fetch */
 declare type RequestRedirect = "error" | "follow" | "manual";
@@ -202,6 +204,13 @@ declare interface RequestInit {
    * credentials.
    */
   credentials?: RequestCredentials;
+  /**
+   * A string indicating whether the request body is sent before the response
+   * is read ("half") or while the response is streamed back ("full"). Deno
+   * always streams both directions concurrently; "full" is a non standard
+   * extension. Sets request's duplex.
+   */
+  duplex?: RequestDuplex;
   /**
    * A Headers object, an object literal, or an array of two-item arrays to set
    * request's headers.
@@ -271,6 +280,11 @@ declare interface Request extends Body {
    * Returns the kind of resource requested by request, e.g., "document" or "script".
    */
   readonly destination: RequestDestination;
+  /**
+   * Returns the duplex mode associated with request, which is "half" unless
+   * "full" was set in init.
+   */
+  readonly duplex: RequestDuplex;
   /**
    * Returns a Headers object consisting of the headers associated with request.
    * Note that headers added in the network layer by the user agent will not be
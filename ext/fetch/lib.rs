@@ -1,6 +1,8 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
 mod fs_fetch_handler;
+mod pool;
+mod progress;
 mod proxy;
 #[cfg(test)]
 mod tests;
@@ -8,6 +10,7 @@ mod tests;
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::cmp::min;
+use std::collections::VecDeque;
 use std::convert::From;
 use std::path::Path;
 use std::path::PathBuf;
@@ -21,6 +24,7 @@ use deno_core::anyhow::anyhow;
 use deno_core::anyhow::Error;
 use deno_core::error::type_error;
 use deno_core::error::AnyError;
+use deno_core::futures::channel::oneshot;
 use deno_core::futures::stream::Peekable;
 use deno_core::futures::Future;
 use deno_core::futures::FutureExt;
@@ -63,6 +67,7 @@ use http::header::PROXY_AUTHORIZATION;
 use http::header::RANGE;
 use http::header::USER_AGENT;
 use http::Extensions;
+use http::HeaderMap;
 use http::Method;
 use http::Uri;
 use http_body_util::BodyExt;
@@ -74,6 +79,8 @@ use hyper_util::rt::TokioIo;
 use hyper_util::rt::TokioTimer;
 use serde::Deserialize;
 use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
 use tokio::io::AsyncReadExt;
 use tokio::io::AsyncWriteExt;
 use tower::ServiceExt;
@@ -84,6 +91,11 @@ pub use data_url;
 pub use proxy::basic_auth;
 
 pub use fs_fetch_handler::FsFetchHandler;
+pub use pool::PoolConnections;
+pub use pool::PooledConnectionInfo;
+pub use progress::FetchProgress;
+pub use progress::FetchProgressResource;
+pub use progress::ProgressBody;
 
 #[derive(Clone)]
 pub struct Options {
@@ -128,8 +140,11 @@ deno_core::extension!(deno_fetch,
     op_fetch<FP>,
     op_fetch_send,
     op_fetch_response_upgrade,
+    op_fetch_response_trailers,
+    progress::op_fetch_progress,
     op_utf8_to_byte_string,
     op_fetch_custom_client<FP>,
+    op_fetch_client_connections,
   ],
   esm = [
     "20_headers.js",
@@ -192,6 +207,7 @@ pub fn get_declaration() -> PathBuf {
 pub struct FetchReturn {
   pub request_rid: ResourceId,
   pub cancel_handle_rid: Option<ResourceId>,
+  pub progress_rid: Option<ResourceId>,
 }
 
 pub fn get_or_create_client_from_state(
@@ -207,29 +223,130 @@ pub fn get_or_create_client_from_state(
   }
 }
 
+/// Proxy settings of a single request, used instead of those of the default
+/// client.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestProxyOptions {
+  proxy: Option<RequestProxy>,
+  no_proxy: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct RequestProxy {
+  url: String,
+  basic_auth: Option<RequestProxyAuth>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+struct RequestProxyAuth {
+  username: String,
+  password: String,
+}
+
+impl RequestProxyOptions {
+  /// Identifies the settings without holding on to the proxy password.
+  fn digest(&self) -> [u8; 32] {
+    let json = serde_json::to_vec(self).unwrap();
+    Sha256::digest(json).into()
+  }
+}
+
+/// How many of the clients created for per-request proxy settings are kept.
+const MAX_REQUEST_PROXY_CLIENTS: usize = 16;
+
+/// The most recently used clients for the proxy settings that requests were
+/// made with, so that requests going through the same proxy share a
+/// connection pool. The least recently used client is dropped once there are
+/// more than `MAX_REQUEST_PROXY_CLIENTS`, which closes its idle connections.
+#[derive(Default)]
+struct RequestProxyClients(VecDeque<([u8; 32], Client)>);
+
+impl RequestProxyClients {
+  fn get(&mut self, digest: &[u8; 32]) -> Option<Client> {
+    let index = self.0.iter().position(|(key, _)| key == digest)?;
+    let entry = self.0.remove(index)?;
+    let client = entry.1.clone();
+    self.0.push_front(entry);
+    Some(client)
+  }
+
+  fn insert(&mut self, digest: [u8; 32], client: Client) {
+    self.0.push_front((digest, client));
+    self.0.truncate(MAX_REQUEST_PROXY_CLIENTS);
+  }
+}
+
+fn get_or_create_proxy_client<FP>(
+  state: &mut OpState,
+  proxy_options: RequestProxyOptions,
+) -> Result<Client, AnyError>
+where
+  FP: FetchPermissions + 'static,
+{
+  if let Some(proxy) = &proxy_options.proxy {
+    let url = Url::parse(&proxy.url)?;
+    state.borrow_mut::<FP>().check_net_url(&url, "fetch()")?;
+  }
+  let digest = proxy_options.digest();
+  if let Some(clients) = state.try_borrow_mut::<RequestProxyClients>() {
+    if let Some(client) = clients.get(&digest) {
+      return Ok(client);
+    }
+  }
+
+  let options = state.borrow::<Options>();
+  let mut client_options = client_options_from_options(options)?;
+  if let Some(proxy) = proxy_options.proxy {
+    client_options.proxy = Some(Proxy {
+      url: proxy.url,
+      basic_auth: proxy.basic_auth.map(|auth| deno_tls::BasicAuth {
+        username: auth.username,
+        password: auth.password,
+      }),
+    });
+  }
+  client_options.no_proxy = proxy_options.no_proxy;
+  let client = create_http_client(&options.user_agent, client_options)?;
+
+  if !state.has::<RequestProxyClients>() {
+    state.put(RequestProxyClients::default());
+  }
+  state
+    .borrow_mut::<RequestProxyClients>()
+    .insert(digest, client.clone());
+  Ok(client)
+}
+
 pub fn create_client_from_options(
   options: &Options,
 ) -> Result<Client, AnyError> {
-  create_http_client(
-    &options.user_agent,
-    CreateHttpClientOptions {
-      root_cert_store: options.root_cert_store()?,
-      ca_certs: vec![],
-      proxy: options.proxy.clone(),
-      unsafely_ignore_certificate_errors: options
-        .unsafely_ignore_certificate_errors
-        .clone(),
-      client_cert_chain_and_key: options
-        .client_cert_chain_and_key
-        .clone()
-        .try_into()
-        .unwrap_or_default(),
-      pool_max_idle_per_host: None,
-      pool_idle_timeout: None,
-      http1: true,
-      http2: true,
-    },
-  )
+  create_http_client(&options.user_agent, client_options_from_options(options)?)
+}
+
+fn client_options_from_options(
+  options: &Options,
+) -> Result<CreateHttpClientOptions, AnyError> {
+  Ok(CreateHttpClientOptions {
+    root_cert_store: options.root_cert_store()?,
+    ca_certs: vec![],
+    proxy: options.proxy.clone(),
+    no_proxy: None,
+    unsafely_ignore_certificate_errors: options
+      .unsafely_ignore_certificate_errors
+      .clone(),
+    client_cert_chain_and_key: options
+      .client_cert_chain_and_key
+      .clone()
+      .try_into()
+      .unwrap_or_default(),
+    pool_max_idle_per_host: None,
+    pool_idle_timeout: None,
+    pool_max_connections_per_host: None,
+    http1: true,
+    http2: true,
+  })
 }
 
 #[allow(clippy::type_complexity)]
@@ -342,13 +459,25 @@ pub fn op_fetch<FP>(
   has_body: bool,
   #[buffer] data: Option<JsBuffer>,
   #[smi] resource: Option<ResourceId>,
+  has_progress: bool,
+  #[serde] proxy_options: Option<RequestProxyOptions>,
 ) -> Result<FetchReturn, AnyError>
 where
   FP: FetchPermissions + 'static,
 {
   let (client, allow_host) = if let Some(rid) = client_rid {
+    if proxy_options.is_some() {
+      return Err(type_error(
+        "`proxy` and `noProxy` can't be used together with `client`",
+      ));
+    }
     let r = state.resource_table.get::<HttpClientResource>(rid)?;
     (r.client.clone(), r.allow_host)
+  } else if let Some(proxy_options) = proxy_options {
+    (
+      get_or_create_proxy_client::<FP>(state, proxy_options)?,
+      false,
+    )
   } else {
     (get_or_create_client_from_state(state)?, false)
   };
@@ -358,6 +487,7 @@ where
 
   // Check scheme before asking for net permission
   let scheme = url.scheme();
+  let mut progress_rid = None;
   let (request_rid, cancel_handle_rid) = match scheme {
     "file" => {
       let path = url.to_file_path().map_err(|_| {
@@ -430,6 +560,20 @@ where
           .boxed()
       };
 
+      // Progress is only reported for network requests.
+      let progress = has_progress.then(|| Arc::new(FetchProgress::default()));
+      let (body, download_progress) = match &progress {
+        Some(progress) => {
+          let upload = progress.upload();
+          upload.set_total(con_len.unwrap_or(0));
+          (
+            ProgressBody::new(body, upload).boxed(),
+            Some(progress.download()),
+          )
+        }
+        None => (body, None),
+      };
+
       let mut request = http::Request::new(body);
       *request.method_mut() = method.clone();
       *request.uri_mut() = uri.clone();
@@ -474,11 +618,24 @@ where
 
       let fut = {
         async move {
-          client
+          let res = client
             .send(request)
             .map_err(Into::into)
             .or_cancel(cancel_handle_)
-            .await
+            .await;
+          match (res, download_progress) {
+            (Ok(Ok(res)), Some(download)) => {
+              if let Some(total) =
+                hyper::body::Body::size_hint(res.body()).exact()
+              {
+                download.set_total(total);
+              }
+              Ok(Ok(
+                res.map(|body| ProgressBody::new(body, download).boxed()),
+              ))
+            }
+            (res, _) => res,
+          }
         }
       };
 
@@ -489,6 +646,11 @@ where
 
       let cancel_handle_rid =
         state.resource_table.add(FetchCancelHandle(cancel_handle));
+      progress_rid = progress.map(|progress| {
+        state
+          .resource_table
+          .add(FetchProgressResource::new(progress))
+      });
 
       (request_rid, Some(cancel_handle_rid))
     }
@@ -528,6 +690,7 @@ where
   Ok(FetchReturn {
     request_rid,
     cancel_handle_rid,
+    progress_rid,
   })
 }
 
@@ -679,6 +842,43 @@ pub async fn op_fetch_response_upgrade(
   )
 }
 
+/// Resolves with the trailers of a response once its body has been read to
+/// the end. Resolves with an empty list if the response had no trailers or
+/// the body was closed before they arrived.
+#[op2(async)]
+#[serde]
+pub fn op_fetch_response_trailers(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<
+  impl Future<Output = Result<Vec<(ByteString, ByteString)>, AnyError>>,
+  AnyError,
+> {
+  let resource = state
+    .resource_table
+    .get::<FetchResponseResource>(rid)
+    .map_err(|_| {
+      type_error("Response trailers must be requested before the body is read")
+    })?;
+  let trailers_rx = resource
+    .trailers_rx
+    .borrow_mut()
+    .take()
+    .ok_or_else(|| type_error("Response trailers were already requested"))?;
+
+  Ok(async move {
+    let Ok(trailers) = trailers_rx.await else {
+      return Ok(vec![]);
+    };
+    Ok(
+      trailers
+        .iter()
+        .map(|(key, value)| (key.as_str().into(), value.as_bytes().into()))
+        .collect(),
+    )
+  })
+}
+
 struct UpgradeStream {
   read: AsyncRefCell<tokio::io::ReadHalf<tokio::io::DuplexStream>>,
   write: AsyncRefCell<tokio::io::WriteHalf<tokio::io::DuplexStream>>,
@@ -778,14 +978,19 @@ pub struct FetchResponseResource {
   pub response_reader: AsyncRefCell<FetchResponseReader>,
   pub cancel: CancelHandle,
   pub size: Option<u64>,
+  trailers_tx: RefCell<Option<oneshot::Sender<HeaderMap>>>,
+  trailers_rx: RefCell<Option<oneshot::Receiver<HeaderMap>>>,
 }
 
 impl FetchResponseResource {
   pub fn new(response: http::Response<ResBody>, size: Option<u64>) -> Self {
+    let (trailers_tx, trailers_rx) = oneshot::channel();
     Self {
       response_reader: AsyncRefCell::new(FetchResponseReader::Start(response)),
       cancel: CancelHandle::default(),
       size,
+      trailers_tx: RefCell::new(Some(trailers_tx)),
+      trailers_rx: RefCell::new(Some(trailers_rx)),
     }
   }
 
@@ -816,12 +1021,33 @@ impl Resource for FetchResponseResource {
 
         match std::mem::take(&mut *reader) {
           FetchResponseReader::Start(resp) => {
-            let stream: BytesStream =
-              Box::pin(resp.into_body().into_data_stream().map(|r| {
-                r.map_err(|err| {
-                  std::io::Error::new(std::io::ErrorKind::Other, err)
-                })
-              }));
+            // Trailers arrive as the last frame of the body, hand them over
+            // to `op_fetch_response_trailers` instead of dropping them.
+            let mut trailers_tx = self.trailers_tx.borrow_mut().take();
+            let stream: BytesStream = Box::pin(
+              http_body_util::BodyStream::new(resp.into_body()).filter_map(
+                move |frame| {
+                  let chunk = match frame {
+                    Ok(frame) => match frame.into_data() {
+                      Ok(data) => Some(Ok(data)),
+                      Err(frame) => {
+                        if let (Ok(trailers), Some(tx)) =
+                          (frame.into_trailers(), trailers_tx.take())
+                        {
+                          let _ = tx.send(trailers);
+                        }
+                        None
+                      }
+                    },
+                    Err(err) => Some(Err(std::io::Error::new(
+                      std::io::ErrorKind::Other,
+                      err,
+                    ))),
+                  };
+                  std::future::ready(chunk)
+                },
+              ),
+            );
             *reader = FetchResponseReader::BodyReader(stream.peekable());
           }
           FetchResponseReader::BodyReader(_) => unreachable!(),
@@ -887,8 +1113,10 @@ impl HttpClientResource {
 pub struct CreateHttpClientArgs {
   ca_certs: Vec<String>,
   proxy: Option<Proxy>,
+  no_proxy: Option<Vec<String>>,
   pool_max_idle_per_host: Option<usize>,
   pool_idle_timeout: Option<serde_json::Value>,
+  pool_max_connections_per_host: Option<usize>,
   #[serde(default = "default_true")]
   http1: bool,
   #[serde(default = "default_true")]
@@ -930,6 +1158,7 @@ where
       root_cert_store: options.root_cert_store()?,
       ca_certs,
       proxy: args.proxy,
+      no_proxy: args.no_proxy,
       unsafely_ignore_certificate_errors: options
         .unsafely_ignore_certificate_errors
         .clone(),
@@ -945,6 +1174,7 @@ where
           _ => Some(None),
         },
       ),
+      pool_max_connections_per_host: args.pool_max_connections_per_host,
       http1: args.http1,
       http2: args.http2,
     },
@@ -961,10 +1191,16 @@ pub struct CreateHttpClientOptions {
   pub root_cert_store: Option<RootCertStore>,
   pub ca_certs: Vec<Vec<u8>>,
   pub proxy: Option<Proxy>,
+  /// Hosts to connect to directly, in the format of the `NO_PROXY`
+  /// environment variable, which is ignored if this is set.
+  pub no_proxy: Option<Vec<String>>,
   pub unsafely_ignore_certificate_errors: Option<Vec<String>>,
   pub client_cert_chain_and_key: Option<TlsKey>,
   pub pool_max_idle_per_host: Option<usize>,
   pub pool_idle_timeout: Option<Option<u64>>,
+  /// Limits the number of open connections per origin, requests wait for
+  /// a connection to be released once the limit is reached.
+  pub pool_max_connections_per_host: Option<usize>,
   pub http1: bool,
   pub http2: bool,
 }
@@ -975,10 +1211,12 @@ impl Default for CreateHttpClientOptions {
       root_cert_store: None,
       ca_certs: vec![],
       proxy: None,
+      no_proxy: None,
       unsafely_ignore_certificate_errors: None,
       client_cert_chain_and_key: None,
       pool_max_idle_per_host: None,
       pool_idle_timeout: None,
+      pool_max_connections_per_host: None,
       http1: true,
       http2: true,
     }
@@ -997,6 +1235,7 @@ pub fn create_http_client(
     options.unsafely_ignore_certificate_errors,
     options.client_cert_chain_and_key.into(),
     deno_tls::SocketUse::Http,
+    vec![],
   )?;
 
   // Proxy TLS should not send ALPN
@@ -1026,6 +1265,9 @@ pub fn create_http_client(
   builder.pool_timer(TokioTimer::new());
 
   let mut proxies = proxy::from_env();
+  if let Some(no_proxy) = &options.no_proxy {
+    proxies.set_no_proxy(no_proxy);
+  }
   if let Some(proxy) = options.proxy {
     let mut intercept = proxy::Intercept::all(&proxy.url)
       .ok_or_else(|| type_error("invalid proxy url"))?;
@@ -1035,14 +1277,27 @@ pub fn create_http_client(
     proxies.prepend(intercept);
   }
   let proxies = Arc::new(proxies);
-  let connector = proxy::ProxyConnector {
-    http: http_connector,
-    proxies: proxies.clone(),
-    tls: tls_config,
-    tls_proxy: proxy_tls_config,
-    user_agent: Some(user_agent.clone()),
+  let connections = Arc::new(PoolConnections::new(
+    options.pool_max_connections_per_host,
+    !options.http1 && options.http2,
+  ));
+  let connector = pool::PoolConnector {
+    inner: proxy::ProxyConnector {
+      http: http_connector,
+      proxies: proxies.clone(),
+      tls: tls_config,
+      tls_proxy: proxy_tls_config,
+      user_agent: Some(user_agent.clone()),
+    },
+    connections: connections.clone(),
   };
 
+  if options.pool_max_connections_per_host == Some(0) {
+    return Err(type_error(
+      "`poolMaxConnectionsPerHost` must be greater than 0",
+    ));
+  }
+
   if let Some(pool_max_idle_per_host) = options.pool_max_idle_per_host {
     builder.pool_max_idle_per_host(pool_max_idle_per_host);
   }
@@ -1071,9 +1326,20 @@ pub fn create_http_client(
     inner: decompress,
     proxies,
     user_agent,
+    connections,
   })
 }
 
+#[op2]
+#[serde]
+pub fn op_fetch_client_connections(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<Vec<PooledConnectionInfo>, AnyError> {
+  let resource = state.resource_table.get::<HttpClientResource>(rid)?;
+  Ok(resource.client.connections().list())
+}
+
 #[op2]
 #[serde]
 pub fn op_utf8_to_byte_string(
@@ -1088,9 +1354,10 @@ pub struct Client {
   // Used to check whether to include a proxy-authorization header
   proxies: Arc<proxy::Proxies>,
   user_agent: HeaderValue,
+  connections: Arc<PoolConnections>,
 }
 
-type Connector = proxy::ProxyConnector<HttpConnector>;
+type Connector = pool::PoolConnector<proxy::ProxyConnector<HttpConnector>>;
 
 // clippy is wrong here
 #[allow(clippy::declare_interior_mutable_const)]
@@ -1149,6 +1416,11 @@ impl std::error::Error for ClientSendError {
 }
 
 impl Client {
+  /// The connections currently held by the pool of this client.
+  pub fn connections(&self) -> &PoolConnections {
+    &self.connections
+  }
+
   pub async fn send(
     self,
     mut req: http::Request<ReqBody>,
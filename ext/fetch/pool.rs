@@ -0,0 +1,279 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Bookkeeping for the connections of a client's pool. The pool of the
+//! hyper-util client is opaque, so connections are tracked as the connector
+//! hands them out, and released once hyper drops them.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use http::Extensions;
+use http::Uri;
+use hyper_util::client::legacy::connect::Connected;
+use hyper_util::client::legacy::connect::Connection;
+use hyper_util::client::legacy::connect::HttpInfo;
+use serde::Serialize;
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::sync::Semaphore;
+use tower_service::Service;
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PooledConnectionInfo {
+  pub origin: String,
+  pub protocol: &'static str,
+  pub proxied: bool,
+  pub local_addr_ip: Option<String>,
+  pub local_addr_port: Option<u16>,
+  pub remote_addr_ip: Option<String>,
+  pub remote_addr_port: Option<u16>,
+  /// Milliseconds since the UNIX epoch.
+  pub opened_at: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct PoolConnections {
+  max_per_host: Option<usize>,
+  http2_only: bool,
+  next_id: AtomicU64,
+  limits: Mutex<HashMap<String, Arc<Semaphore>>>,
+  live: Mutex<HashMap<u64, PooledConnectionInfo>>,
+}
+
+impl PoolConnections {
+  pub fn new(max_per_host: Option<usize>, http2_only: bool) -> Self {
+    Self {
+      max_per_host,
+      http2_only,
+      ..Default::default()
+    }
+  }
+
+  /// Live connections, ordered by the time they were opened.
+  pub fn list(&self) -> Vec<PooledConnectionInfo> {
+    let live = self.live.lock().unwrap();
+    let mut connections = live.iter().collect::<Vec<_>>();
+    connections.sort_by_key(|(id, _)| **id);
+    connections
+      .into_iter()
+      .map(|(_, info)| info.clone())
+      .collect()
+  }
+
+  async fn acquire(&self, origin: &str) -> Option<OwnedSemaphorePermit> {
+    let max = self.max_per_host?;
+    let semaphore = self
+      .limits
+      .lock()
+      .unwrap()
+      .entry(origin.to_string())
+      .or_insert_with(|| Arc::new(Semaphore::new(max)))
+      .clone();
+    // The semaphore is never closed.
+    semaphore.acquire_owned().await.ok()
+  }
+
+  fn release(
+    &self,
+    id: u64,
+    origin: &str,
+    permit: Option<OwnedSemaphorePermit>,
+  ) {
+    self.live.lock().unwrap().remove(&id);
+    let Some(permit) = permit else {
+      return;
+    };
+    drop(permit);
+    // Forget origins without connections, so that the map does not grow with
+    // every host the client ever talked to.
+    let mut limits = self.limits.lock().unwrap();
+    if let Some(semaphore) = limits.get(origin) {
+      if Arc::strong_count(semaphore) == 1
+        && Some(semaphore.available_permits()) == self.max_per_host
+      {
+        limits.remove(origin);
+      }
+    }
+  }
+}
+
+fn origin_of(dst: &Uri) -> String {
+  let scheme = dst.scheme_str().unwrap_or("http");
+  let host = dst.host().unwrap_or("");
+  let port = dst
+    .port_u16()
+    .unwrap_or(if scheme == "https" { 443 } else { 80 });
+  format!("{scheme}://{host}:{port}")
+}
+
+fn now_millis() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or_default()
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PoolConnector<C> {
+  pub(crate) inner: C,
+  pub(crate) connections: Arc<PoolConnections>,
+}
+
+impl<C> Service<Uri> for PoolConnector<C>
+where
+  C: Service<Uri> + Clone + Send + 'static,
+  C::Response: Connection + Send + 'static,
+  C::Future: Send + 'static,
+  C::Error: Into<BoxError> + 'static,
+{
+  type Response = PooledConnection<C::Response>;
+  type Error = BoxError;
+  type Future = BoxFuture<Result<Self::Response, Self::Error>>;
+
+  fn poll_ready(
+    &mut self,
+    cx: &mut Context<'_>,
+  ) -> Poll<Result<(), Self::Error>> {
+    self.inner.poll_ready(cx).map_err(Into::into)
+  }
+
+  fn call(&mut self, dst: Uri) -> Self::Future {
+    let connections = self.connections.clone();
+    // The readiness checked in `poll_ready` belongs to `self.inner`.
+    let clone = self.inner.clone();
+    let mut inner = std::mem::replace(&mut self.inner, clone);
+    Box::pin(async move {
+      let origin = origin_of(&dst);
+      let permit = connections.acquire(&origin).await;
+      let stream = inner.call(dst).await.map_err(Into::into)?;
+
+      let connected = stream.connected();
+      let mut extensions = Extensions::new();
+      connected.get_extras(&mut extensions);
+      let http_info = extensions.remove::<HttpInfo>();
+      let info = PooledConnectionInfo {
+        origin: origin.clone(),
+        protocol: if connected.is_negotiated_h2() || connections.http2_only {
+          "h2"
+        } else {
+          "http/1.1"
+        },
+        proxied: connected.is_proxied(),
+        local_addr_ip: http_info
+          .as_ref()
+          .map(|i| i.local_addr().ip().to_string()),
+        local_addr_port: http_info.as_ref().map(|i| i.local_addr().port()),
+        remote_addr_ip: http_info
+          .as_ref()
+          .map(|i| i.remote_addr().ip().to_string()),
+        remote_addr_port: http_info.as_ref().map(|i| i.remote_addr().port()),
+        opened_at: now_millis(),
+      };
+      let id = connections.next_id.fetch_add(1, Ordering::Relaxed);
+      connections.live.lock().unwrap().insert(id, info);
+
+      Ok(PooledConnection {
+        inner: stream,
+        _guard: ConnectionGuard {
+          id,
+          origin,
+          permit,
+          connections,
+        },
+      })
+    })
+  }
+}
+
+struct ConnectionGuard {
+  id: u64,
+  origin: String,
+  permit: Option<OwnedSemaphorePermit>,
+  connections: Arc<PoolConnections>,
+}
+
+impl Drop for ConnectionGuard {
+  fn drop(&mut self) {
+    self
+      .connections
+      .release(self.id, &self.origin, self.permit.take());
+  }
+}
+
+pub(crate) struct PooledConnection<T> {
+  inner: T,
+  _guard: ConnectionGuard,
+}
+
+impl<T> hyper::rt::Read for PooledConnection<T>
+where
+  T: hyper::rt::Read + Unpin,
+{
+  fn poll_read(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: hyper::rt::ReadBufCursor<'_>,
+  ) -> Poll<Result<(), std::io::Error>> {
+    Pin::new(&mut self.inner).poll_read(cx, buf)
+  }
+}
+
+impl<T> hyper::rt::Write for PooledConnection<T>
+where
+  T: hyper::rt::Write + Unpin,
+{
+  fn poll_write(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: &[u8],
+  ) -> Poll<Result<usize, std::io::Error>> {
+    Pin::new(&mut self.inner).poll_write(cx, buf)
+  }
+
+  fn poll_flush(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Result<(), std::io::Error>> {
+    Pin::new(&mut self.inner).poll_flush(cx)
+  }
+
+  fn poll_shutdown(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Result<(), std::io::Error>> {
+    Pin::new(&mut self.inner).poll_shutdown(cx)
+  }
+
+  fn is_write_vectored(&self) -> bool {
+    self.inner.is_write_vectored()
+  }
+
+  fn poll_write_vectored(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    bufs: &[std::io::IoSlice<'_>],
+  ) -> Poll<Result<usize, std::io::Error>> {
+    Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+  }
+}
+
+impl<T> Connection for PooledConnection<T>
+where
+  T: Connection,
+{
+  fn connected(&self) -> Connected {
+    self.inner.connected()
+  }
+}
@@ -0,0 +1,247 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Byte counters for the `onUploadProgress` and `onDownloadProgress` hooks of
+//! `fetch`. The request and response bodies are wrapped so that every frame
+//! that passes through them is counted, and `op_fetch_progress` reports the
+//! counters to JS whenever they changed.
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::task::Context;
+use std::task::Poll;
+
+use deno_core::error::AnyError;
+use deno_core::futures::future::poll_fn;
+use deno_core::futures::task::AtomicWaker;
+use deno_core::op2;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use hyper::body::Body;
+use hyper::body::Frame;
+use hyper::body::SizeHint;
+use serde::Serialize;
+
+const UNKNOWN_TOTAL: u64 = u64::MAX;
+
+#[derive(Debug)]
+struct Counter {
+  loaded: AtomicU64,
+  total: AtomicU64,
+  done: AtomicBool,
+}
+
+impl Default for Counter {
+  fn default() -> Self {
+    Self {
+      loaded: AtomicU64::new(0),
+      total: AtomicU64::new(UNKNOWN_TOTAL),
+      done: AtomicBool::new(false),
+    }
+  }
+}
+
+impl Counter {
+  fn total(&self) -> Option<u64> {
+    match self.total.load(Ordering::Relaxed) {
+      UNKNOWN_TOTAL => None,
+      total => Some(total),
+    }
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct FetchProgress {
+  upload: Counter,
+  download: Counter,
+  waker: AtomicWaker,
+}
+
+impl FetchProgress {
+  pub fn upload(self: &Arc<Self>) -> ProgressHandle {
+    ProgressHandle {
+      progress: self.clone(),
+      direction: Direction::Upload,
+    }
+  }
+
+  pub fn download(self: &Arc<Self>) -> ProgressHandle {
+    ProgressHandle {
+      progress: self.clone(),
+      direction: Direction::Download,
+    }
+  }
+
+  fn snapshot(&self) -> FetchProgressEvent {
+    FetchProgressEvent {
+      uploaded: self.upload.loaded.load(Ordering::Relaxed),
+      upload_total: self.upload.total(),
+      downloaded: self.download.loaded.load(Ordering::Relaxed),
+      download_total: self.download.total(),
+    }
+  }
+
+  fn done(&self) -> bool {
+    self.upload.done.load(Ordering::Relaxed)
+      && self.download.done.load(Ordering::Relaxed)
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+  Upload,
+  Download,
+}
+
+/// Counts the bytes of one direction of a request. The direction is marked
+/// as finished when the handle is dropped, e.g. because the request failed
+/// before a response arrived.
+#[derive(Debug)]
+pub struct ProgressHandle {
+  progress: Arc<FetchProgress>,
+  direction: Direction,
+}
+
+impl ProgressHandle {
+  pub fn set_total(&self, total: u64) {
+    self.counter().total.store(total, Ordering::Relaxed);
+    self.progress.waker.wake();
+  }
+
+  fn counter(&self) -> &Counter {
+    match self.direction {
+      Direction::Upload => &self.progress.upload,
+      Direction::Download => &self.progress.download,
+    }
+  }
+
+  fn add(&self, len: usize) {
+    if len > 0 {
+      self
+        .counter()
+        .loaded
+        .fetch_add(len as u64, Ordering::Relaxed);
+      self.progress.waker.wake();
+    }
+  }
+}
+
+impl Drop for ProgressHandle {
+  fn drop(&mut self) {
+    self.counter().done.store(true, Ordering::Relaxed);
+    self.progress.waker.wake();
+  }
+}
+
+/// A body that reports the size of every data frame to a [`ProgressHandle`].
+pub struct ProgressBody<B> {
+  inner: B,
+  handle: Option<ProgressHandle>,
+}
+
+impl<B> ProgressBody<B> {
+  pub fn new(inner: B, handle: ProgressHandle) -> Self {
+    Self {
+      inner,
+      handle: Some(handle),
+    }
+  }
+}
+
+impl<B> Body for ProgressBody<B>
+where
+  B: Body<Data = bytes::Bytes> + Unpin,
+{
+  type Data = bytes::Bytes;
+  type Error = B::Error;
+
+  fn poll_frame(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+    let res = Pin::new(&mut self.inner).poll_frame(cx);
+    match &res {
+      Poll::Ready(Some(Ok(frame))) => {
+        if let (Some(data), Some(handle)) = (frame.data_ref(), &self.handle) {
+          handle.add(data.len());
+        }
+      }
+      // Finish as soon as the body ends, it may not be dropped right away.
+      Poll::Ready(None) => self.handle = None,
+      _ => {}
+    }
+    res
+  }
+
+  fn is_end_stream(&self) -> bool {
+    self.inner.is_end_stream()
+  }
+
+  fn size_hint(&self) -> SizeHint {
+    self.inner.size_hint()
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchProgressEvent {
+  pub uploaded: u64,
+  pub upload_total: Option<u64>,
+  pub downloaded: u64,
+  pub download_total: Option<u64>,
+}
+
+pub struct FetchProgressResource {
+  progress: Arc<FetchProgress>,
+  last: Cell<FetchProgressEvent>,
+}
+
+impl FetchProgressResource {
+  pub fn new(progress: Arc<FetchProgress>) -> Self {
+    let last = Cell::new(progress.snapshot());
+    Self { progress, last }
+  }
+}
+
+impl Resource for FetchProgressResource {
+  fn name(&self) -> Cow<str> {
+    "fetchProgress".into()
+  }
+}
+
+/// Resolves with the byte counters of a request as soon as they changed since
+/// the last call, or with `null` once both bodies are finished.
+#[op2(async)]
+#[serde]
+pub async fn op_fetch_progress(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<FetchProgressEvent>, AnyError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<FetchProgressResource>(rid)?;
+  let event = poll_fn(|cx| {
+    resource.progress.waker.register(cx.waker());
+    // Check `done` first, so that the final counters are always reported.
+    let done = resource.progress.done();
+    let snapshot = resource.progress.snapshot();
+    if resource.last.get() != snapshot {
+      resource.last.set(snapshot);
+      Poll::Ready(Some(snapshot))
+    } else if done {
+      Poll::Ready(None)
+    } else {
+      Poll::Pending
+    }
+  })
+  .await;
+  Ok(event)
+}
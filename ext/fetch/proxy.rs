@@ -383,6 +383,10 @@ impl Proxies {
     self.intercepts.insert(0, intercept);
   }
 
+  pub(crate) fn set_no_proxy(&mut self, no_proxy: &[String]) {
+    self.no = NoProxy::from_string(&no_proxy.join(","));
+  }
+
   pub(crate) fn http_forward_auth(&self, dst: &Uri) -> Option<&HeaderValue> {
     let intercept = self.intercept(dst)?;
     match intercept.target {
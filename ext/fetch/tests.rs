@@ -11,7 +11,13 @@ use tokio::io::AsyncReadExt;
 use tokio::io::AsyncWriteExt;
 
 use super::create_http_client;
+use super::Client;
 use super::CreateHttpClientOptions;
+use super::RequestProxy;
+use super::RequestProxyAuth;
+use super::RequestProxyClients;
+use super::RequestProxyOptions;
+use super::MAX_REQUEST_PROXY_CLIENTS;
 
 static EXAMPLE_CRT: &[u8] = include_bytes!("../tls/testdata/example1_cert.der");
 static EXAMPLE_KEY: &[u8] =
@@ -52,6 +58,108 @@ async fn test_socks_proxy_h2() {
   run_test_client(prx_addr, src_addr, "socks5", http::Version::HTTP_2).await;
 }
 
+#[tokio::test]
+async fn test_pool_max_connections_per_host() {
+  let src_addr = create_https_server(false).await;
+  let client = create_http_client(
+    "fetch/test",
+    CreateHttpClientOptions {
+      unsafely_ignore_certificate_errors: Some(vec![]),
+      pool_max_connections_per_host: Some(1),
+      ..Default::default()
+    },
+  )
+  .unwrap();
+
+  let send = |client: Client| async move {
+    let req = http::Request::builder()
+      .uri(format!("https://{}/foo", src_addr))
+      .body(
+        http_body_util::Empty::new()
+          .map_err(|err| match err {})
+          .boxed(),
+      )
+      .unwrap();
+    let resp = client.send(req).await.unwrap();
+    resp.collect().await.unwrap().to_bytes()
+  };
+  let (a, b) = tokio::join!(send(client.clone()), send(client.clone()));
+  assert_eq!(a, "hello from server");
+  assert_eq!(b, "hello from server");
+
+  let connections = client.connections().list();
+  assert_eq!(connections.len(), 1);
+  assert_eq!(connections[0].origin, format!("https://{}", src_addr));
+  assert_eq!(connections[0].protocol, "http/1.1");
+  assert!(!connections[0].proxied);
+  assert_eq!(connections[0].remote_addr_port, Some(src_addr.port()));
+}
+
+#[tokio::test]
+async fn test_no_proxy_bypasses_proxy() {
+  let src_addr = create_https_server(false).await;
+  // Nothing listens on port 1, so the request only succeeds if the proxy is
+  // not used.
+  let client = create_http_client(
+    "fetch/test",
+    CreateHttpClientOptions {
+      proxy: Some(deno_tls::Proxy {
+        url: "http://127.0.0.1:1".to_string(),
+        basic_auth: None,
+      }),
+      no_proxy: Some(vec!["example.com".to_string(), "127.0.0.1".to_string()]),
+      unsafely_ignore_certificate_errors: Some(vec![]),
+      ..Default::default()
+    },
+  )
+  .unwrap();
+
+  let req = http::Request::builder()
+    .uri(format!("https://{}/foo", src_addr))
+    .body(
+      http_body_util::Empty::new()
+        .map_err(|err| match err {})
+        .boxed(),
+    )
+    .unwrap();
+  let resp = client.clone().send(req).await.unwrap();
+  assert_eq!(resp.status(), http::StatusCode::OK);
+  let hello = resp.collect().await.unwrap().to_bytes();
+  assert_eq!(hello, "hello from server");
+  assert!(!client.connections().list()[0].proxied);
+}
+
+#[tokio::test]
+async fn test_request_proxy_clients_evict_least_recently_used() {
+  let digest = |password: usize| {
+    RequestProxyOptions {
+      proxy: Some(RequestProxy {
+        url: "http://127.0.0.1:1".to_string(),
+        basic_auth: Some(RequestProxyAuth {
+          username: "deno".to_string(),
+          password: format!("secret{password}"),
+        }),
+      }),
+      no_proxy: None,
+    }
+    .digest()
+  };
+  let client = create_http_client("fetch/test", Default::default()).unwrap();
+
+  let mut clients = RequestProxyClients::default();
+  for password in 0..MAX_REQUEST_PROXY_CLIENTS {
+    clients.insert(digest(password), client.clone());
+  }
+  // makes the first client the most recently used one
+  assert!(clients.get(&digest(0)).is_some());
+  clients.insert(digest(MAX_REQUEST_PROXY_CLIENTS), client.clone());
+
+  assert_eq!(clients.0.len(), MAX_REQUEST_PROXY_CLIENTS);
+  assert!(clients.get(&digest(0)).is_some());
+  assert!(clients.get(&digest(1)).is_none());
+  assert!(clients.get(&digest(MAX_REQUEST_PROXY_CLIENTS)).is_some());
+}
+
 async fn run_test_client(
   prx_addr: SocketAddr,
   src_addr: SocketAddr,
@@ -67,10 +175,12 @@ async fn run_test_client(
         url: format!("{}://{}", proto, prx_addr),
         basic_auth: None,
       }),
+      no_proxy: None,
       unsafely_ignore_certificate_errors: Some(vec![]),
       client_cert_chain_and_key: None,
       pool_max_idle_per_host: None,
       pool_idle_timeout: None,
+      pool_max_connections_per_host: None,
       http1: true,
       http2: true,
     },
@@ -15,6 +15,8 @@ import {
   op_fs_copy_file_async,
   op_fs_copy_file_sync,
   op_fs_cwd,
+  op_fs_fallocate_async,
+  op_fs_fallocate_sync,
   op_fs_fdatasync_async,
   op_fs_fdatasync_sync,
   op_fs_file_stat_async,
@@ -31,19 +33,32 @@ import {
   op_fs_funlock_sync_unstable,
   op_fs_futime_async,
   op_fs_futime_sync,
+  op_fs_get_xattr_async,
+  op_fs_get_xattr_sync,
+  op_fs_glob_next_async,
+  op_fs_glob_next_sync,
+  op_fs_glob_open,
   op_fs_link_async,
   op_fs_link_sync,
+  op_fs_list_xattrs_async,
+  op_fs_list_xattrs_sync,
   op_fs_lstat_async,
   op_fs_lstat_sync,
   op_fs_make_temp_dir_async,
   op_fs_make_temp_dir_sync,
   op_fs_make_temp_file_async,
   op_fs_make_temp_file_sync,
+  op_fs_make_temp_file_unnamed_async,
+  op_fs_make_temp_file_unnamed_sync,
   op_fs_mkdir_async,
   op_fs_mkdir_sync,
   op_fs_open_async,
+  op_fs_open_dir_async,
+  op_fs_open_dir_sync,
   op_fs_open_sync,
   op_fs_read_dir_async,
+  op_fs_read_dir_batch_async,
+  op_fs_read_dir_batch_sync,
   op_fs_read_dir_sync,
   op_fs_read_file_async,
   op_fs_read_file_sync,
@@ -59,16 +74,23 @@ import {
   op_fs_rename_sync,
   op_fs_seek_async,
   op_fs_seek_sync,
+  op_fs_set_xattr_async,
+  op_fs_set_xattr_sync,
   op_fs_stat_async,
   op_fs_stat_sync,
   op_fs_symlink_async,
   op_fs_symlink_sync,
   op_fs_truncate_async,
   op_fs_truncate_sync,
+  op_fs_try_flock_async,
+  op_fs_try_flock_sync,
   op_fs_umask,
+  op_fs_umask_scoped,
   op_fs_utime_async,
   op_fs_utime_sync,
   op_fs_write_file_async,
+  op_fs_write_file_atomic_async,
+  op_fs_write_file_atomic_sync,
   op_fs_write_file_sync,
   op_set_raw,
 } from "ext:core/ops";
@@ -132,20 +154,24 @@ async function chown(
 function copyFileSync(
   fromPath,
   toPath,
+  options = { __proto__: null },
 ) {
   op_fs_copy_file_sync(
     pathFromURL(fromPath),
     pathFromURL(toPath),
+    options.reflink,
   );
 }
 
 async function copyFile(
   fromPath,
   toPath,
+  options = { __proto__: null },
 ) {
   await op_fs_copy_file_async(
     pathFromURL(fromPath),
     pathFromURL(toPath),
+    options.reflink,
   );
 }
 
@@ -174,6 +200,14 @@ function makeTempDir(options = { __proto__: null }) {
 }
 
 function makeTempFileSync(options = { __proto__: null }) {
+  if (options.unnamed) {
+    const rid = op_fs_make_temp_file_unnamed_sync(
+      options.dir,
+      options.prefix,
+      options.suffix,
+    );
+    return new FsFile(rid, SymbolFor("Deno.internal.FsFile"));
+  }
   return op_fs_make_temp_file_sync(
     options.dir,
     options.prefix,
@@ -181,8 +215,16 @@ function makeTempFileSync(options = { __proto__: null }) {
   );
 }
 
-function makeTempFile(options = { __proto__: null }) {
-  return op_fs_make_temp_file_async(
+async function makeTempFile(options = { __proto__: null }) {
+  if (options.unnamed) {
+    const rid = await op_fs_make_temp_file_unnamed_async(
+      options.dir,
+      options.prefix,
+      options.suffix,
+    );
+    return new FsFile(rid, SymbolFor("Deno.internal.FsFile"));
+  }
+  return await op_fs_make_temp_file_async(
     options.dir,
     options.prefix,
     options.suffix,
@@ -205,13 +247,73 @@ async function mkdir(path, options) {
   );
 }
 
-function readDirSync(path) {
+// how many entries each op call returns when reading with `stat: true`
+const READ_DIR_BATCH_SIZE = 256;
+
+function toDirEntryWithInfo(entry) {
+  return {
+    name: entry.name,
+    isFile: entry.isFile,
+    isDirectory: entry.isDirectory,
+    isSymlink: entry.isSymlink,
+    info: parseFileInfo(entry.info),
+  };
+}
+
+function* readDirWithInfoSync(rid) {
+  try {
+    while (true) {
+      const batch = op_fs_read_dir_batch_sync(rid, READ_DIR_BATCH_SIZE);
+      if (batch.length === 0) {
+        return;
+      }
+      for (let i = 0; i < batch.length; ++i) {
+        yield toDirEntryWithInfo(batch[i]);
+      }
+    }
+  } finally {
+    core.tryClose(rid);
+  }
+}
+
+async function* readDirWithInfo(path) {
+  const rid = await op_fs_open_dir_async(pathFromURL(path));
+  try {
+    while (true) {
+      const batch = await op_fs_read_dir_batch_async(
+        rid,
+        READ_DIR_BATCH_SIZE,
+      );
+      if (batch.length === 0) {
+        return;
+      }
+      for (let i = 0; i < batch.length; ++i) {
+        yield toDirEntryWithInfo(batch[i]);
+      }
+    }
+  } finally {
+    core.tryClose(rid);
+  }
+}
+
+function readDirSync(path, options) {
+  if (options?.stat) {
+    // open the directory right away, like without `stat`
+    return readDirWithInfoSync(op_fs_open_dir_sync(pathFromURL(path)));
+  }
   return op_fs_read_dir_sync(pathFromURL(path))[
     SymbolIterator
   ]();
 }
 
-function readDir(path) {
+function readDir(path, options) {
+  if (options?.stat) {
+    return {
+      [SymbolAsyncIterator]() {
+        return readDirWithInfo(path);
+      },
+    };
+  }
   const array = op_fs_read_dir_async(
     pathFromURL(path),
   );
@@ -225,6 +327,59 @@ function readDir(path) {
   };
 }
 
+// how many paths each op call returns at most
+const GLOB_BATCH_SIZE = 256;
+
+function openGlob(patterns, options) {
+  if (typeof patterns === "string") {
+    patterns = [patterns];
+  }
+  return op_fs_glob_open(
+    pathFromURL(options?.root ?? "."),
+    patterns,
+    {
+      exclude: options?.exclude ?? [],
+      gitignore: options?.gitignore ?? false,
+      followSymlinks: options?.followSymlinks ?? false,
+      includeDirs: options?.includeDirs ?? false,
+    },
+  );
+}
+
+function* globSync(patterns, options) {
+  const rid = openGlob(patterns, options);
+  try {
+    while (true) {
+      const paths = op_fs_glob_next_sync(rid, GLOB_BATCH_SIZE);
+      if (paths.length === 0) {
+        return;
+      }
+      for (let i = 0; i < paths.length; ++i) {
+        yield paths[i];
+      }
+    }
+  } finally {
+    core.tryClose(rid);
+  }
+}
+
+async function* glob(patterns, options) {
+  const rid = openGlob(patterns, options);
+  try {
+    while (true) {
+      const paths = await op_fs_glob_next_async(rid, GLOB_BATCH_SIZE);
+      if (paths.length === 0) {
+        return;
+      }
+      for (let i = 0; i < paths.length; ++i) {
+        yield paths[i];
+      }
+    }
+  } finally {
+    core.tryClose(rid);
+  }
+}
+
 function readLinkSync(path) {
   return op_fs_read_link_sync(pathFromURL(path));
 }
@@ -447,8 +602,30 @@ async function truncate(path, len) {
   await op_fs_truncate_async(path, coerceLen(len));
 }
 
-function umask(mask) {
-  return op_fs_umask(mask);
+function umask(mask, callback) {
+  if (callback === undefined) {
+    return op_fs_umask(mask);
+  }
+  const previous = op_fs_umask_scoped(mask);
+  let result;
+  try {
+    result = callback();
+  } finally {
+    op_fs_umask_scoped(previous);
+  }
+  // Overlapping asynchronous callbacks would restore each other's masks in
+  // the wrong order. Asynchronous operations started by the callback capture
+  // the mask when they are started, so they don't need it to be kept.
+  if (
+    result !== null &&
+    (typeof result === "object" || typeof result === "function") &&
+    typeof result.then === "function"
+  ) {
+    throw new TypeError(
+      "The callback of Deno.umask() must not return a promise",
+    );
+  }
+  return result;
 }
 
 function linkSync(oldpath, newpath) {
@@ -671,6 +848,24 @@ class FsFile {
     return ftruncateSync(this.#rid, len);
   }
 
+  async allocate(len, options = {}) {
+    await op_fs_fallocate_async(
+      this.#rid,
+      coerceLen(options.offset),
+      coerceLen(len),
+      options.mode ?? "zero",
+    );
+  }
+
+  allocateSync(len, options = {}) {
+    op_fs_fallocate_sync(
+      this.#rid,
+      coerceLen(options.offset),
+      coerceLen(len),
+      options.mode ?? "zero",
+    );
+  }
+
   read(p) {
     return read(this.#rid, p);
   }
@@ -764,6 +959,14 @@ class FsFile {
     await op_fs_flock_async(this.#rid, exclusive);
   }
 
+  tryLockSync(exclusive = false) {
+    return op_fs_try_flock_sync(this.#rid, exclusive);
+  }
+
+  tryLock(exclusive = false) {
+    return op_fs_try_flock_async(this.#rid, exclusive);
+  }
+
   unlockSync() {
     op_fs_funlock_sync(this.#rid);
   }
@@ -927,6 +1130,70 @@ async function writeFile(
   }
 }
 
+function writeFileAtomicSync(
+  path,
+  data,
+  options = { __proto__: null },
+) {
+  if (typeof data === "string") {
+    data = new TextEncoder().encode(data);
+  }
+  op_fs_write_file_atomic_sync(
+    pathFromURL(path),
+    options.mode,
+    options.syncDir ?? false,
+    data,
+  );
+}
+
+async function writeFileAtomic(
+  path,
+  data,
+  options = { __proto__: null },
+) {
+  if (typeof data === "string") {
+    data = new TextEncoder().encode(data);
+  }
+  await op_fs_write_file_atomic_async(
+    pathFromURL(path),
+    options.mode,
+    options.syncDir ?? false,
+    data,
+  );
+}
+
+function getXattrSync(path, name) {
+  const value = op_fs_get_xattr_sync(pathFromURL(path), name);
+  return value === null ? null : new Uint8Array(value);
+}
+
+async function getXattr(path, name) {
+  const value = await op_fs_get_xattr_async(pathFromURL(path), name);
+  return value === null ? null : new Uint8Array(value);
+}
+
+function setXattrSync(path, name, value) {
+  if (typeof value === "string") {
+    value = new TextEncoder().encode(value);
+  }
+  op_fs_set_xattr_sync(pathFromURL(path), name, value);
+}
+
+async function setXattr(path, name, value) {
+  if (typeof value === "string") {
+    value = new TextEncoder().encode(value);
+  }
+  await op_fs_set_xattr_async(pathFromURL(path), name, value);
+}
+
+function listXattrsSync(path) {
+  return op_fs_list_xattrs_sync(pathFromURL(path));
+}
+
+function listXattrs(path) {
+  return op_fs_list_xattrs_async(pathFromURL(path));
+}
+
 function writeTextFileSync(
   path,
   data,
@@ -976,8 +1243,14 @@ export {
   ftruncateSync,
   funlock,
   funlockSync,
+  getXattr,
+  getXattrSync,
+  glob,
+  globSync,
   link,
   linkSync,
+  listXattrs,
+  listXattrsSync,
   lstat,
   lstatSync,
   makeTempDir,
@@ -1004,6 +1277,8 @@ export {
   renameSync,
   seek,
   seekSync,
+  setXattr,
+  setXattrSync,
   stat,
   statSync,
   symlink,
@@ -1014,6 +1289,8 @@ export {
   utime,
   utimeSync,
   writeFile,
+  writeFileAtomic,
+  writeFileAtomicSync,
   writeFileSync,
   writeTextFile,
   writeTextFileSync,
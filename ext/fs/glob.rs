@@ -0,0 +1,301 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Walking a directory tree for the paths which match gitignore-style
+//! patterns, e.g. `**/*.ts` and `!**/*.d.ts`. The tree is read through the
+//! [`FileSystem`], with the metadata of each entry read along with it, see
+//! [`FileSystem::open_dir_sync`], and directories which are excluded aren't
+//! read at all.
+
+use std::collections::HashSet;
+use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use deno_io::fs::FsError;
+use deno_io::fs::FsResult;
+use ignore::gitignore::Gitignore;
+use ignore::gitignore::GitignoreBuilder;
+use serde::Deserialize;
+
+use crate::interface::FsDirEntries;
+use crate::interface::FsDirReader;
+use crate::interface::FsDirStatEntry;
+use crate::FileSystem;
+
+/// How many entries are read from a directory at once.
+const DIR_BATCH_SIZE: usize = 256;
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GlobOptions {
+  /// Patterns of paths to leave out, along with everything below them.
+  pub exclude: Vec<String>,
+  /// Leave out what the `.gitignore` files in the tree ignore, and `.git`.
+  pub gitignore: bool,
+  /// Walk into symlinks to directories, each directory only once.
+  pub follow_symlinks: bool,
+  /// Also return the directories which match.
+  pub include_dirs: bool,
+}
+
+/// A directory which is being read, and the `.gitignore` files which apply
+/// to its entries, from the innermost.
+struct GlobDir {
+  path: PathBuf,
+  relative: PathBuf,
+  reader: Option<Rc<dyn FsDirReader>>,
+  ignores: Vec<Rc<Gitignore>>,
+}
+
+pub struct GlobWalker {
+  include: Gitignore,
+  exclude: Gitignore,
+  options: GlobOptions,
+  stack: Vec<GlobDir>,
+  /// The real paths of the directories which were walked into, when
+  /// following symlinks.
+  visited: HashSet<PathBuf>,
+}
+
+impl GlobWalker {
+  pub fn new(
+    root: PathBuf,
+    patterns: &[String],
+    options: GlobOptions,
+  ) -> Result<Self, AnyError> {
+    let include = build_matcher(patterns)?;
+    let exclude = build_matcher(&options.exclude)?;
+    Ok(Self {
+      include,
+      exclude,
+      options,
+      stack: vec![GlobDir {
+        path: root,
+        relative: PathBuf::new(),
+        reader: None,
+        ignores: vec![],
+      }],
+      visited: HashSet::new(),
+    })
+  }
+
+  /// Returns up to `max` matching paths, relative to the root, or none once
+  /// the whole tree was walked.
+  pub fn next_sync(
+    &mut self,
+    fs: &dyn FileSystem,
+    max: usize,
+  ) -> FsResult<Vec<PathBuf>> {
+    let mut paths = vec![];
+    while paths.len() < max {
+      let Some(dir) = self.stack.last_mut() else {
+        break;
+      };
+      let reader = match &dir.reader {
+        Some(reader) => reader.clone(),
+        None => {
+          let is_root = self.stack.len() == 1;
+          match self.open_sync(fs) {
+            Ok(reader) => reader,
+            Err(err) if !is_root && is_vanished(&err) => {
+              self.stack.pop();
+              continue;
+            }
+            Err(err) => return Err(err),
+          }
+        }
+      };
+      let batch = reader.next_batch_sync(DIR_BATCH_SIZE)?;
+      if batch.is_empty() {
+        self.stack.pop();
+        continue;
+      }
+      // the directories in the batch are pushed on top of this one
+      let index = self.stack.len() - 1;
+      for mut entry in batch {
+        if entry.stat.is_symlink && self.options.follow_symlinks {
+          let path = self.stack[index].path.join(&entry.name);
+          // dangling symlinks are returned as they are
+          if let Ok(stat) = fs.stat_sync(&path) {
+            entry.stat = stat;
+          }
+        }
+        self.visit(index, entry, &mut paths);
+      }
+    }
+    Ok(paths)
+  }
+
+  pub async fn next_async(
+    &mut self,
+    fs: &dyn FileSystem,
+    max: usize,
+  ) -> FsResult<Vec<PathBuf>> {
+    let mut paths = vec![];
+    while paths.len() < max {
+      let Some(dir) = self.stack.last_mut() else {
+        break;
+      };
+      let reader = match &dir.reader {
+        Some(reader) => reader.clone(),
+        None => {
+          let is_root = self.stack.len() == 1;
+          match self.open_async(fs).await {
+            Ok(reader) => reader,
+            Err(err) if !is_root && is_vanished(&err) => {
+              self.stack.pop();
+              continue;
+            }
+            Err(err) => return Err(err),
+          }
+        }
+      };
+      let batch = reader.next_batch_async(DIR_BATCH_SIZE).await?;
+      if batch.is_empty() {
+        self.stack.pop();
+        continue;
+      }
+      let index = self.stack.len() - 1;
+      for mut entry in batch {
+        if entry.stat.is_symlink && self.options.follow_symlinks {
+          let path = self.stack[index].path.join(&entry.name);
+          if let Ok(stat) = fs.stat_async(path).await {
+            entry.stat = stat;
+          }
+        }
+        self.visit(index, entry, &mut paths);
+      }
+    }
+    Ok(paths)
+  }
+
+  /// Opens the innermost directory, and reads its `.gitignore`.
+  fn open_sync(
+    &mut self,
+    fs: &dyn FileSystem,
+  ) -> FsResult<Rc<dyn FsDirReader>> {
+    let dir = self.stack.last_mut().unwrap();
+    if self.options.follow_symlinks {
+      let real_path = fs.realpath_sync(&dir.path)?;
+      if !self.visited.insert(real_path) {
+        return Ok(Rc::new(FsDirEntries::new(vec![])));
+      }
+    }
+    let reader = fs.open_dir_sync(&dir.path)?;
+    if self.options.gitignore {
+      let path = dir.path.join(".gitignore");
+      match fs.read_file_sync(&path, None) {
+        Ok(data) => push_gitignore(dir, &data),
+        Err(err) if is_vanished(&err) => {}
+        Err(err) => return Err(err),
+      }
+    }
+    dir.reader = Some(reader.clone());
+    Ok(reader)
+  }
+
+  async fn open_async(
+    &mut self,
+    fs: &dyn FileSystem,
+  ) -> FsResult<Rc<dyn FsDirReader>> {
+    let dir = self.stack.last_mut().unwrap();
+    if self.options.follow_symlinks {
+      let real_path = fs.realpath_async(dir.path.clone()).await?;
+      if !self.visited.insert(real_path) {
+        return Ok(Rc::new(FsDirEntries::new(vec![])));
+      }
+    }
+    let reader = fs.open_dir_async(dir.path.clone()).await?;
+    if self.options.gitignore {
+      let path = dir.path.join(".gitignore");
+      match fs.read_file_async(path, None).await {
+        Ok(data) => push_gitignore(dir, &data),
+        Err(err) if is_vanished(&err) => {}
+        Err(err) => return Err(err),
+      }
+    }
+    dir.reader = Some(reader.clone());
+    Ok(reader)
+  }
+
+  /// Adds the entry of the directory at `index` to `paths` if it matches,
+  /// and walks into it later if it's a directory which isn't excluded.
+  fn visit(
+    &mut self,
+    index: usize,
+    entry: FsDirStatEntry,
+    paths: &mut Vec<PathBuf>,
+  ) {
+    let dir = &self.stack[index];
+    let is_dir = entry.stat.is_directory;
+    let path = dir.path.join(&entry.name);
+    let relative = dir.relative.join(&entry.name);
+
+    if self.exclude.matched(&relative, is_dir).is_ignore() {
+      return;
+    }
+    if self.options.gitignore {
+      if is_dir && entry.name == ".git" {
+        return;
+      }
+      let ignored = dir
+        .ignores
+        .iter()
+        .map(|ignore| ignore.matched(&path, is_dir))
+        .find(|m| !m.is_none())
+        .is_some_and(|m| m.is_ignore());
+      if ignored {
+        return;
+      }
+    }
+
+    if (!is_dir || self.options.include_dirs)
+      && self.include.matched(&relative, is_dir).is_ignore()
+    {
+      paths.push(relative.clone());
+    }
+    if is_dir {
+      let ignores = dir.ignores.clone();
+      self.stack.push(GlobDir {
+        path,
+        relative,
+        reader: None,
+        ignores,
+      });
+    }
+  }
+}
+
+fn build_matcher(patterns: &[String]) -> Result<Gitignore, AnyError> {
+  let mut builder = GitignoreBuilder::new(".");
+  for pattern in patterns {
+    builder
+      .add_line(None, pattern)
+      .map_err(|err| type_error(format!("Invalid glob pattern: {err}")))?;
+  }
+  builder
+    .build()
+    .map_err(|err| type_error(format!("Invalid glob pattern: {err}")))
+}
+
+/// Applies the `.gitignore` of `dir`, leaving out lines which aren't valid,
+/// like git does.
+fn push_gitignore(dir: &mut GlobDir, data: &[u8]) {
+  let mut builder = GitignoreBuilder::new(&dir.path);
+  for line in String::from_utf8_lossy(data).lines() {
+    let _ = builder.add_line(None, line);
+  }
+  if let Ok(gitignore) = builder.build() {
+    if !gitignore.is_empty() {
+      dir.ignores.insert(0, Rc::new(gitignore));
+    }
+  }
+}
+
+/// Whether `err` means that a path doesn't exist (anymore), e.g. a directory
+/// which was removed during the walk.
+fn is_vanished(err: &FsError) -> bool {
+  matches!(err, FsError::Io(err) if err.kind() == io::ErrorKind::NotFound)
+}
@@ -24,6 +24,7 @@ use crate::interface::FsDirEntry;
 use crate::interface::FsFileType;
 use crate::FileSystem;
 use crate::OpenOptions;
+use crate::Reflink;
 
 #[derive(Debug)]
 enum PathEntry {
@@ -97,6 +98,16 @@ impl FileSystem for InMemoryFs {
     self.open_sync(&path, options, access_check)
   }
 
+  fn create_unnamed_file_sync(&self, _path: &Path) -> FsResult<Rc<dyn File>> {
+    Err(FsError::NotSupported)
+  }
+  async fn create_unnamed_file_async(
+    &self,
+    path: PathBuf,
+  ) -> FsResult<Rc<dyn File>> {
+    self.create_unnamed_file_sync(&path)
+  }
+
   fn mkdir_sync(
     &self,
     path: &Path,
@@ -203,11 +214,21 @@ impl FileSystem for InMemoryFs {
     self.remove_sync(&path, recursive)
   }
 
-  fn copy_file_sync(&self, _from: &Path, _to: &Path) -> FsResult<()> {
+  fn copy_file_sync(
+    &self,
+    _from: &Path,
+    _to: &Path,
+    _reflink: Reflink,
+  ) -> FsResult<()> {
     Err(FsError::NotSupported)
   }
-  async fn copy_file_async(&self, from: PathBuf, to: PathBuf) -> FsResult<()> {
-    self.copy_file_sync(&from, &to)
+  async fn copy_file_async(
+    &self,
+    from: PathBuf,
+    to: PathBuf,
+    reflink: Reflink,
+  ) -> FsResult<()> {
+    self.copy_file_sync(&from, &to, reflink)
   }
 
   fn cp_sync(&self, _from: &Path, _to: &Path) -> FsResult<()> {
@@ -389,6 +410,45 @@ impl FileSystem for InMemoryFs {
     self.lutime_sync(&path, atime_secs, atime_nanos, mtime_secs, mtime_nanos)
   }
 
+  fn get_xattr_sync(
+    &self,
+    _path: &Path,
+    _name: &str,
+  ) -> FsResult<Option<Vec<u8>>> {
+    Err(FsError::NotSupported)
+  }
+  async fn get_xattr_async(
+    &self,
+    path: PathBuf,
+    name: String,
+  ) -> FsResult<Option<Vec<u8>>> {
+    self.get_xattr_sync(&path, &name)
+  }
+
+  fn set_xattr_sync(
+    &self,
+    _path: &Path,
+    _name: &str,
+    _value: &[u8],
+  ) -> FsResult<()> {
+    Err(FsError::NotSupported)
+  }
+  async fn set_xattr_async(
+    &self,
+    path: PathBuf,
+    name: String,
+    value: Vec<u8>,
+  ) -> FsResult<()> {
+    self.set_xattr_sync(&path, &name, &value)
+  }
+
+  fn list_xattrs_sync(&self, _path: &Path) -> FsResult<Vec<String>> {
+    Err(FsError::NotSupported)
+  }
+  async fn list_xattrs_async(&self, path: PathBuf) -> FsResult<Vec<String>> {
+    self.list_xattrs_sync(&path)
+  }
+
   fn write_file_sync(
     &self,
     path: &Path,
@@ -451,6 +511,28 @@ impl FileSystem for InMemoryFs {
     self.write_file_sync(&path, options, access_check, &data)
   }
 
+  fn write_file_atomic_sync(
+    &self,
+    path: &Path,
+    mode: Option<u32>,
+    _sync_dir: bool,
+    data: &[u8],
+  ) -> FsResult<()> {
+    // writes replace the entry as a whole anyway
+    let options = OpenOptions::write(true, false, false, mode);
+    self.write_file_sync(path, options, None, data)
+  }
+
+  async fn write_file_atomic_async(
+    &self,
+    path: PathBuf,
+    mode: Option<u32>,
+    sync_dir: bool,
+    data: Vec<u8>,
+  ) -> FsResult<()> {
+    self.write_file_atomic_sync(&path, mode, sync_dir, &data)
+  }
+
   fn read_file_sync(
     &self,
     path: &Path,
@@ -1,6 +1,8 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
 use std::borrow::Cow;
+use std::cell::RefCell;
+use std::io;
 use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
@@ -9,6 +11,7 @@ use serde::Deserialize;
 use serde::Serialize;
 
 use deno_io::fs::File;
+use deno_io::fs::FsError;
 use deno_io::fs::FsResult;
 use deno_io::fs::FsStat;
 
@@ -59,6 +62,21 @@ impl OpenOptions {
   }
 }
 
+/// Whether a copied file is a clone of the original, which shares its data
+/// until either of them is changed. Only some file systems support clones,
+/// e.g. Btrfs, XFS, APFS and ReFS.
+#[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Reflink {
+  /// Clone the file if possible, else copy it.
+  #[default]
+  Auto,
+  /// Clone the file, or fail with `FsError::NotSupported`.
+  Always,
+  /// Copy the file.
+  Never,
+}
+
 #[derive(Deserialize)]
 pub enum FsFileType {
   #[serde(rename = "file")]
@@ -79,6 +97,52 @@ pub struct FsDirEntry {
   pub is_symlink: bool,
 }
 
+/// A directory entry along with its metadata. Symlinks aren't followed.
+pub struct FsDirStatEntry {
+  pub name: String,
+  pub stat: FsStat,
+}
+
+/// Reads the entries of a directory in batches, see
+/// [`FileSystem::open_dir_sync`].
+#[async_trait::async_trait(?Send)]
+pub trait FsDirReader {
+  /// Returns up to `max` entries, or none once all were read.
+  fn next_batch_sync(
+    self: Rc<Self>,
+    max: usize,
+  ) -> FsResult<Vec<FsDirStatEntry>>;
+  async fn next_batch_async(
+    self: Rc<Self>,
+    max: usize,
+  ) -> FsResult<Vec<FsDirStatEntry>>;
+}
+
+/// A [`FsDirReader`] over entries which were all read up front.
+pub struct FsDirEntries(RefCell<std::vec::IntoIter<FsDirStatEntry>>);
+
+impl FsDirEntries {
+  pub fn new(entries: Vec<FsDirStatEntry>) -> Self {
+    Self(RefCell::new(entries.into_iter()))
+  }
+}
+
+#[async_trait::async_trait(?Send)]
+impl FsDirReader for FsDirEntries {
+  fn next_batch_sync(
+    self: Rc<Self>,
+    max: usize,
+  ) -> FsResult<Vec<FsDirStatEntry>> {
+    Ok(self.0.borrow_mut().by_ref().take(max).collect())
+  }
+  async fn next_batch_async(
+    self: Rc<Self>,
+    max: usize,
+  ) -> FsResult<Vec<FsDirStatEntry>> {
+    self.next_batch_sync(max)
+  }
+}
+
 #[allow(clippy::disallowed_types)]
 pub type FileSystemRc = crate::sync::MaybeArc<dyn FileSystem>;
 
@@ -121,6 +185,15 @@ pub trait FileSystem: std::fmt::Debug + MaybeSend + MaybeSync {
     access_check: Option<AccessCheckCb<'a>>,
   ) -> FsResult<Rc<dyn File>>;
 
+  /// Creates a file for reading and writing which is gone once it's closed.
+  /// Where supported it never has a name, e.g. with `O_TMPFILE` in the
+  /// directory of `path`, else it's created at `path`, which must not exist.
+  fn create_unnamed_file_sync(&self, path: &Path) -> FsResult<Rc<dyn File>>;
+  async fn create_unnamed_file_async(
+    &self,
+    path: PathBuf,
+  ) -> FsResult<Rc<dyn File>>;
+
   fn mkdir_sync(&self, path: &Path, recursive: bool, mode: u32)
     -> FsResult<()>;
   async fn mkdir_async(
@@ -162,11 +235,17 @@ pub trait FileSystem: std::fmt::Debug + MaybeSend + MaybeSync {
   fn remove_sync(&self, path: &Path, recursive: bool) -> FsResult<()>;
   async fn remove_async(&self, path: PathBuf, recursive: bool) -> FsResult<()>;
 
-  fn copy_file_sync(&self, oldpath: &Path, newpath: &Path) -> FsResult<()>;
+  fn copy_file_sync(
+    &self,
+    oldpath: &Path,
+    newpath: &Path,
+    reflink: Reflink,
+  ) -> FsResult<()>;
   async fn copy_file_async(
     &self,
     oldpath: PathBuf,
     newpath: PathBuf,
+    reflink: Reflink,
   ) -> FsResult<()>;
 
   fn cp_sync(&self, path: &Path, new_path: &Path) -> FsResult<()>;
@@ -251,6 +330,35 @@ pub trait FileSystem: std::fmt::Debug + MaybeSend + MaybeSync {
     mtime_nanos: u32,
   ) -> FsResult<()>;
 
+  /// Returns the value of the extended attribute `name` of `path`, or `None`
+  /// if it has no such attribute.
+  fn get_xattr_sync(
+    &self,
+    path: &Path,
+    name: &str,
+  ) -> FsResult<Option<Vec<u8>>>;
+  async fn get_xattr_async(
+    &self,
+    path: PathBuf,
+    name: String,
+  ) -> FsResult<Option<Vec<u8>>>;
+
+  fn set_xattr_sync(
+    &self,
+    path: &Path,
+    name: &str,
+    value: &[u8],
+  ) -> FsResult<()>;
+  async fn set_xattr_async(
+    &self,
+    path: PathBuf,
+    name: String,
+    value: Vec<u8>,
+  ) -> FsResult<()>;
+
+  fn list_xattrs_sync(&self, path: &Path) -> FsResult<Vec<String>>;
+  async fn list_xattrs_async(&self, path: PathBuf) -> FsResult<Vec<String>>;
+
   fn write_file_sync(
     &self,
     path: &Path,
@@ -280,6 +388,23 @@ pub trait FileSystem: std::fmt::Debug + MaybeSend + MaybeSync {
     Ok(())
   }
 
+  /// Replaces the contents of `path` with `data`, so that it either has the
+  /// old or the new contents at any time, even if the process crashes.
+  fn write_file_atomic_sync(
+    &self,
+    path: &Path,
+    mode: Option<u32>,
+    sync_dir: bool,
+    data: &[u8],
+  ) -> FsResult<()>;
+  async fn write_file_atomic_async(
+    &self,
+    path: PathBuf,
+    mode: Option<u32>,
+    sync_dir: bool,
+    data: Vec<u8>,
+  ) -> FsResult<()>;
+
   fn read_file_sync(
     &self,
     path: &Path,
@@ -301,6 +426,42 @@ pub trait FileSystem: std::fmt::Debug + MaybeSend + MaybeSync {
     Ok(buf)
   }
 
+  /// Opens the directory `path` to read its entries along with their
+  /// metadata, without a separate stat call for each entry where the platform
+  /// allows it.
+  fn open_dir_sync(&self, path: &Path) -> FsResult<Rc<dyn FsDirReader>> {
+    let mut entries = vec![];
+    for entry in self.read_dir_sync(path)? {
+      match self.lstat_sync(&path.join(&entry.name)) {
+        Ok(stat) => entries.push(FsDirStatEntry {
+          name: entry.name,
+          stat,
+        }),
+        // removed since it was listed
+        Err(FsError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+      }
+    }
+    Ok(Rc::new(FsDirEntries::new(entries)))
+  }
+  async fn open_dir_async(
+    &self,
+    path: PathBuf,
+  ) -> FsResult<Rc<dyn FsDirReader>> {
+    let mut entries = vec![];
+    for entry in self.read_dir_async(path.clone()).await? {
+      match self.lstat_async(path.join(&entry.name)).await {
+        Ok(stat) => entries.push(FsDirStatEntry {
+          name: entry.name,
+          stat,
+        }),
+        Err(FsError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+      }
+    }
+    Ok(Rc::new(FsDirEntries::new(entries)))
+  }
+
   fn is_file_sync(&self, path: &Path) -> bool {
     self.stat_sync(path).map(|m| m.is_file).unwrap_or(false)
   }
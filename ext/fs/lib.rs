@@ -1,19 +1,26 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+mod glob;
 mod in_memory_fs;
 mod interface;
 mod ops;
 mod std_fs;
 pub mod sync;
+pub mod umask;
+mod xattr;
 
 pub use crate::in_memory_fs::InMemoryFs;
 pub use crate::interface::AccessCheckCb;
 pub use crate::interface::AccessCheckFn;
 pub use crate::interface::FileSystem;
 pub use crate::interface::FileSystemRc;
+pub use crate::interface::FsDirEntries;
 pub use crate::interface::FsDirEntry;
+pub use crate::interface::FsDirReader;
+pub use crate::interface::FsDirStatEntry;
 pub use crate::interface::FsFileType;
 pub use crate::interface::OpenOptions;
+pub use crate::interface::Reflink;
 pub use crate::std_fs::RealFs;
 pub use crate::sync::MaybeSend;
 pub use crate::sync::MaybeSync;
@@ -182,6 +189,7 @@ deno_core::extension!(deno_fs,
   ops = [
     op_fs_cwd<P>,
     op_fs_umask,
+    op_fs_umask_scoped,
     op_fs_chdir<P>,
 
     op_fs_open_sync<P>,
@@ -204,6 +212,13 @@ deno_core::extension!(deno_fs,
     op_fs_realpath_async<P>,
     op_fs_read_dir_sync<P>,
     op_fs_read_dir_async<P>,
+    op_fs_open_dir_sync<P>,
+    op_fs_open_dir_async<P>,
+    op_fs_read_dir_batch_sync,
+    op_fs_read_dir_batch_async,
+    op_fs_glob_open<P>,
+    op_fs_glob_next_sync,
+    op_fs_glob_next_async,
     op_fs_rename_sync<P>,
     op_fs_rename_async<P>,
     op_fs_link_sync<P>,
@@ -220,12 +235,22 @@ deno_core::extension!(deno_fs,
     op_fs_make_temp_dir_async<P>,
     op_fs_make_temp_file_sync<P>,
     op_fs_make_temp_file_async<P>,
+    op_fs_make_temp_file_unnamed_sync<P>,
+    op_fs_make_temp_file_unnamed_async<P>,
     op_fs_write_file_sync<P>,
     op_fs_write_file_async<P>,
+    op_fs_write_file_atomic_sync<P>,
+    op_fs_write_file_atomic_async<P>,
     op_fs_read_file_sync<P>,
     op_fs_read_file_async<P>,
     op_fs_read_file_text_sync<P>,
     op_fs_read_file_text_async<P>,
+    op_fs_get_xattr_sync<P>,
+    op_fs_get_xattr_async<P>,
+    op_fs_set_xattr_sync<P>,
+    op_fs_set_xattr_async<P>,
+    op_fs_list_xattrs_sync<P>,
+    op_fs_list_xattrs_async<P>,
 
     op_fs_seek_sync,
     op_fs_seek_async,
@@ -239,10 +264,14 @@ deno_core::extension!(deno_fs,
     op_fs_funlock_async_unstable,
     op_fs_flock_async,
     op_fs_flock_sync,
+    op_fs_try_flock_async,
+    op_fs_try_flock_sync,
     op_fs_funlock_async,
     op_fs_funlock_sync,
     op_fs_ftruncate_sync,
     op_fs_ftruncate_async,
+    op_fs_fallocate_sync,
+    op_fs_fallocate_async,
     op_fs_futime_sync,
     op_fs_futime_async,
 
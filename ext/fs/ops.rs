@@ -1,5 +1,6 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::io;
 use std::io::SeekFrom;
@@ -16,8 +17,10 @@ use deno_core::CancelFuture;
 use deno_core::CancelHandle;
 use deno_core::JsBuffer;
 use deno_core::OpState;
+use deno_core::Resource;
 use deno_core::ResourceId;
 use deno_core::ToJsBuffer;
+use deno_io::fs::AllocateMode;
 use deno_io::fs::FileResource;
 use deno_io::fs::FsError;
 use deno_io::fs::FsStat;
@@ -27,12 +30,17 @@ use rand::Rng;
 use serde::Serialize;
 
 use crate::check_unstable;
+use crate::glob::GlobOptions;
+use crate::glob::GlobWalker;
 use crate::interface::AccessCheckFn;
 use crate::interface::FileSystemRc;
 use crate::interface::FsDirEntry;
+use crate::interface::FsDirReader;
+use crate::interface::FsDirStatEntry;
 use crate::interface::FsFileType;
 use crate::FsPermissions;
 use crate::OpenOptions;
+use crate::Reflink;
 
 fn sync_permission_check<'a, P: FsPermissions + 'static>(
   permissions: &'a mut P,
@@ -122,9 +130,30 @@ pub fn op_fs_umask(
 where
 {
   check_unstable(state, "Deno.umask");
+  // within `Deno.umask(mask, callback)`, that's the mask files get
+  if let (None, Some(scoped)) = (mask, crate::umask::scoped()) {
+    return Ok(scoped);
+  }
   state.borrow::<FileSystemRc>().umask(mask).context("umask")
 }
 
+/// Sets the scoped umask of the isolate, see `umask::set_scoped`, and
+/// returns the previous one.
+#[op2]
+pub fn op_fs_umask_scoped(
+  state: &mut OpState,
+  mask: Option<u32>,
+) -> Result<Option<u32>, AnyError> {
+  check_unstable(state, "Deno.umask");
+  if cfg!(not(unix)) {
+    return Err(FsError::NotSupported).context("umask");
+  }
+  if let Some(mask) = mask.filter(|mask| *mask > 0o777) {
+    return Err(type_error(format!("Invalid umask: {mask:#o}")));
+  }
+  Ok(crate::umask::set_scoped(mask))
+}
+
 #[op2]
 #[smi]
 pub fn op_fs_open_sync<P>(
@@ -368,15 +397,19 @@ where
   Ok(())
 }
 
-#[op2(fast)]
+#[op2]
 pub fn op_fs_copy_file_sync<P>(
   state: &mut OpState,
   #[string] from: &str,
   #[string] to: &str,
+  #[serde] reflink: Option<Reflink>,
 ) -> Result<(), AnyError>
 where
   P: FsPermissions + 'static,
 {
+  if reflink.is_some() {
+    check_unstable(state, "Deno.copyFileSync.reflink");
+  }
   let from = PathBuf::from(from);
   let to = PathBuf::from(to);
 
@@ -385,7 +418,7 @@ where
   permissions.check_write(&to, "Deno.copyFileSync()")?;
 
   let fs = state.borrow::<FileSystemRc>();
-  fs.copy_file_sync(&from, &to)
+  fs.copy_file_sync(&from, &to, reflink.unwrap_or_default())
     .context_two_path("copy", &from, &to)?;
 
   Ok(())
@@ -396,6 +429,7 @@ pub async fn op_fs_copy_file_async<P>(
   state: Rc<RefCell<OpState>>,
   #[string] from: String,
   #[string] to: String,
+  #[serde] reflink: Option<Reflink>,
 ) -> Result<(), AnyError>
 where
   P: FsPermissions + 'static,
@@ -405,13 +439,16 @@ where
 
   let fs = {
     let mut state = state.borrow_mut();
+    if reflink.is_some() {
+      check_unstable(&state, "Deno.copyFile.reflink");
+    }
     let permissions = state.borrow_mut::<P>();
     permissions.check_read(&from, "Deno.copyFile()")?;
     permissions.check_write(&to, "Deno.copyFile()")?;
     state.borrow::<FileSystemRc>().clone()
   };
 
-  fs.copy_file_async(from.clone(), to.clone())
+  fs.copy_file_async(from.clone(), to.clone(), reflink.unwrap_or_default())
     .await
     .context_two_path("copy", &from, &to)?;
 
@@ -607,6 +644,200 @@ where
   Ok(entries)
 }
 
+/// A directory opened by `Deno.readDir` with `stat: true`.
+struct FsDirResource(Rc<dyn FsDirReader>);
+
+impl Resource for FsDirResource {
+  fn name(&self) -> Cow<str> {
+    "fsDir".into()
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializableDirStatEntry {
+  name: String,
+  is_file: bool,
+  is_directory: bool,
+  is_symlink: bool,
+  info: SerializableStat,
+}
+
+impl From<FsDirStatEntry> for SerializableDirStatEntry {
+  fn from(entry: FsDirStatEntry) -> Self {
+    SerializableDirStatEntry {
+      name: entry.name,
+      is_file: entry.stat.is_file,
+      is_directory: entry.stat.is_directory,
+      is_symlink: entry.stat.is_symlink,
+      info: entry.stat.into(),
+    }
+  }
+}
+
+#[op2(fast)]
+#[smi]
+pub fn op_fs_open_dir_sync<P>(
+  state: &mut OpState,
+  #[string] path: String,
+) -> Result<ResourceId, AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  check_unstable(state, "Deno.readDirSync.stat");
+  let path = PathBuf::from(path);
+
+  state
+    .borrow_mut::<P>()
+    .check_read(&path, "Deno.readDirSync()")?;
+
+  let fs = state.borrow::<FileSystemRc>();
+  let dir = fs.open_dir_sync(&path).context_path("readdir", &path)?;
+  let rid = state.resource_table.add(FsDirResource(dir));
+
+  Ok(rid)
+}
+
+#[op2(async)]
+#[smi]
+pub async fn op_fs_open_dir_async<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] path: String,
+) -> Result<ResourceId, AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  let path = PathBuf::from(path);
+
+  let fs = {
+    let mut state = state.borrow_mut();
+    check_unstable(&state, "Deno.readDir.stat");
+    state
+      .borrow_mut::<P>()
+      .check_read(&path, "Deno.readDir()")?;
+    state.borrow::<FileSystemRc>().clone()
+  };
+
+  let dir = fs
+    .open_dir_async(path.clone())
+    .await
+    .context_path("readdir", &path)?;
+  let rid = state.borrow_mut().resource_table.add(FsDirResource(dir));
+
+  Ok(rid)
+}
+
+#[op2]
+#[serde]
+pub fn op_fs_read_dir_batch_sync(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[smi] max: u32,
+) -> Result<Vec<SerializableDirStatEntry>, AnyError> {
+  let dir = state.resource_table.get::<FsDirResource>(rid)?.0.clone();
+  let entries = dir.next_batch_sync(max as usize)?;
+  Ok(entries.into_iter().map(Into::into).collect())
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_fs_read_dir_batch_async(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  #[smi] max: u32,
+) -> Result<Vec<SerializableDirStatEntry>, AnyError> {
+  let dir = state
+    .borrow()
+    .resource_table
+    .get::<FsDirResource>(rid)?
+    .0
+    .clone();
+  let entries = dir.next_batch_async(max as usize).await?;
+  Ok(entries.into_iter().map(Into::into).collect())
+}
+
+/// A walk started by `Deno.glob`. It's taken out while an async op walks.
+struct GlobResource(RefCell<Option<GlobWalker>>);
+
+impl Resource for GlobResource {
+  fn name(&self) -> Cow<str> {
+    "fsGlob".into()
+  }
+}
+
+fn glob_busy() -> AnyError {
+  custom_error("Busy", "Another glob operation is pending")
+}
+
+#[op2]
+#[smi]
+pub fn op_fs_glob_open<P>(
+  state: &mut OpState,
+  #[string] root: String,
+  #[serde] patterns: Vec<String>,
+  #[serde] options: GlobOptions,
+) -> Result<ResourceId, AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  check_unstable(state, "Deno.glob");
+  let root = PathBuf::from(root);
+
+  state.borrow_mut::<P>().check_read(&root, "Deno.glob()")?;
+
+  let walker = GlobWalker::new(root, &patterns, options)?;
+  let rid = state
+    .resource_table
+    .add(GlobResource(RefCell::new(Some(walker))));
+
+  Ok(rid)
+}
+
+#[op2]
+#[serde]
+pub fn op_fs_glob_next_sync(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[smi] max: u32,
+) -> Result<Vec<String>, AnyError> {
+  let resource = state.resource_table.get::<GlobResource>(rid)?;
+  let mut walker = resource.0.try_borrow_mut().map_err(|_| glob_busy())?;
+  let walker = walker.as_mut().ok_or_else(glob_busy)?;
+
+  let fs = state.borrow::<FileSystemRc>();
+  let paths = walker.next_sync(&**fs, max as usize)?;
+
+  paths
+    .into_iter()
+    .map(|path| path_into_string(path.into_os_string()))
+    .collect()
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_fs_glob_next_async(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  #[smi] max: u32,
+) -> Result<Vec<String>, AnyError> {
+  let (resource, fs) = {
+    let state = state.borrow();
+    (
+      state.resource_table.get::<GlobResource>(rid)?,
+      state.borrow::<FileSystemRc>().clone(),
+    )
+  };
+
+  let mut walker = resource.0.borrow_mut().take().ok_or_else(glob_busy)?;
+  let res = walker.next_async(&*fs, max as usize).await;
+  *resource.0.borrow_mut() = Some(walker);
+
+  res?
+    .into_iter()
+    .map(|path| path_into_string(path.into_os_string()))
+    .collect()
+}
+
 #[op2(fast)]
 pub fn op_fs_rename_sync<P>(
   state: &mut OpState,
@@ -1069,6 +1300,87 @@ where
   .context("tmpfile")
 }
 
+#[op2]
+#[smi]
+pub fn op_fs_make_temp_file_unnamed_sync<P>(
+  state: &mut OpState,
+  #[string] dir: Option<String>,
+  #[string] prefix: Option<String>,
+  #[string] suffix: Option<String>,
+) -> Result<ResourceId, AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  check_unstable(state, "Deno.makeTempFileSync.unnamed");
+  let (dir, fs) =
+    make_temp_check_sync::<P>(state, dir, "Deno.makeTempFileSync()")?;
+
+  let mut rng = thread_rng();
+  const MAX_TRIES: u32 = 10;
+  for _ in 0..MAX_TRIES {
+    let path = tmp_name(&mut rng, &dir, prefix.as_deref(), suffix.as_deref())?;
+    match fs.create_unnamed_file_sync(&path) {
+      Ok(file) => {
+        let rid = state
+          .resource_table
+          .add(FileResource::new(file, "fsFile".to_string()));
+        return Ok(rid);
+      }
+      Err(FsError::Io(ref e)) if e.kind() == io::ErrorKind::AlreadyExists => {
+        continue;
+      }
+      Err(e) => return Err(e).context("tmpfile"),
+    }
+  }
+
+  Err(FsError::Io(io::Error::new(
+    io::ErrorKind::AlreadyExists,
+    "too many temp files exist",
+  )))
+  .context("tmpfile")
+}
+
+#[op2(async)]
+#[smi]
+pub async fn op_fs_make_temp_file_unnamed_async<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] dir: Option<String>,
+  #[string] prefix: Option<String>,
+  #[string] suffix: Option<String>,
+) -> Result<ResourceId, AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  check_unstable(&state.borrow(), "Deno.makeTempFile.unnamed");
+  let (dir, fs) =
+    make_temp_check_async::<P>(state.clone(), dir, "Deno.makeTempFile()")?;
+
+  let mut rng = thread_rng();
+  const MAX_TRIES: u32 = 10;
+  for _ in 0..MAX_TRIES {
+    let path = tmp_name(&mut rng, &dir, prefix.as_deref(), suffix.as_deref())?;
+    match fs.create_unnamed_file_async(path).await {
+      Ok(file) => {
+        let rid = state
+          .borrow_mut()
+          .resource_table
+          .add(FileResource::new(file, "fsFile".to_string()));
+        return Ok(rid);
+      }
+      Err(FsError::Io(ref e)) if e.kind() == io::ErrorKind::AlreadyExists => {
+        continue;
+      }
+      Err(e) => return Err(e).context("tmpfile"),
+    }
+  }
+
+  Err(FsError::Io(io::Error::new(
+    io::ErrorKind::AlreadyExists,
+    "too many temp files exist",
+  )))
+  .context("tmpfile")
+}
+
 fn make_temp_check_sync<P>(
   state: &mut OpState,
   dir: Option<String>,
@@ -1262,6 +1574,84 @@ where
   Ok(())
 }
 
+/// Resolves symlinks, so that the file they point to is replaced rather than
+/// the symlink, unless `path` doesn't exist yet.
+fn atomic_write_target<P>(
+  fs: &FileSystemRc,
+  permissions: &mut P,
+  path: PathBuf,
+  api_name: &str,
+) -> Result<PathBuf, AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  permissions.check_write(&path, api_name)?;
+  match fs.realpath_sync(&path) {
+    Ok(target) => {
+      permissions.check_write(&target, api_name)?;
+      Ok(target)
+    }
+    Err(FsError::Io(err)) if err.kind() == io::ErrorKind::NotFound => Ok(path),
+    Err(err) => Err(err).context_path("writefile", &path),
+  }
+}
+#[op2]
+pub fn op_fs_write_file_atomic_sync<P>(
+  state: &mut OpState,
+  #[string] path: String,
+  mode: Option<u32>,
+  sync_dir: bool,
+  #[buffer] data: JsBuffer,
+) -> Result<(), AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  check_unstable(state, "Deno.writeFileAtomicSync");
+  let fs = state.borrow::<FileSystemRc>().clone();
+  let path = atomic_write_target(
+    &fs,
+    state.borrow_mut::<P>(),
+    PathBuf::from(path),
+    "Deno.writeFileAtomicSync()",
+  )?;
+
+  fs.write_file_atomic_sync(&path, mode, sync_dir, &data)
+    .context_path("writefile", &path)?;
+
+  Ok(())
+}
+
+#[op2(async)]
+pub async fn op_fs_write_file_atomic_async<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] path: String,
+  #[smi] mode: Option<u32>,
+  sync_dir: bool,
+  #[buffer] data: JsBuffer,
+) -> Result<(), AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  let (fs, path) = {
+    let mut state = state.borrow_mut();
+    check_unstable(&state, "Deno.writeFileAtomic");
+    let fs = state.borrow::<FileSystemRc>().clone();
+    let path = atomic_write_target(
+      &fs,
+      state.borrow_mut::<P>(),
+      PathBuf::from(path),
+      "Deno.writeFileAtomic()",
+    )?;
+    (fs, path)
+  };
+
+  fs.write_file_atomic_async(path.clone(), mode, sync_dir, data.to_vec())
+    .await
+    .context_path("writefile", &path)?;
+
+  Ok(())
+}
+
 #[op2]
 #[serde]
 pub fn op_fs_read_file_sync<P>(
@@ -1389,6 +1779,158 @@ where
   Ok(str)
 }
 
+#[op2]
+#[serde]
+pub fn op_fs_get_xattr_sync<P>(
+  state: &mut OpState,
+  #[string] path: String,
+  #[string] name: String,
+) -> Result<Option<ToJsBuffer>, AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  check_unstable(state, "Deno.getXattrSync");
+  let path = PathBuf::from(path);
+
+  let permissions = state.borrow_mut::<P>();
+  permissions.check_read(&path, "Deno.getXattrSync()")?;
+
+  let fs = state.borrow::<FileSystemRc>();
+  let value = fs
+    .get_xattr_sync(&path, &name)
+    .context_path("getxattr", &path)?;
+
+  Ok(value.map(Into::into))
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_fs_get_xattr_async<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] path: String,
+  #[string] name: String,
+) -> Result<Option<ToJsBuffer>, AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  check_unstable(&state.borrow(), "Deno.getXattr");
+  let path = PathBuf::from(path);
+
+  let fs = {
+    let mut state = state.borrow_mut();
+    let permissions = state.borrow_mut::<P>();
+    permissions.check_read(&path, "Deno.getXattr()")?;
+    state.borrow::<FileSystemRc>().clone()
+  };
+
+  let value = fs
+    .get_xattr_async(path.clone(), name)
+    .await
+    .context_path("getxattr", &path)?;
+
+  Ok(value.map(Into::into))
+}
+
+#[op2]
+pub fn op_fs_set_xattr_sync<P>(
+  state: &mut OpState,
+  #[string] path: String,
+  #[string] name: String,
+  #[buffer] value: JsBuffer,
+) -> Result<(), AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  check_unstable(state, "Deno.setXattrSync");
+  let path = PathBuf::from(path);
+
+  let permissions = state.borrow_mut::<P>();
+  permissions.check_write(&path, "Deno.setXattrSync()")?;
+
+  let fs = state.borrow::<FileSystemRc>();
+  fs.set_xattr_sync(&path, &name, &value)
+    .context_path("setxattr", &path)?;
+
+  Ok(())
+}
+
+#[op2(async)]
+pub async fn op_fs_set_xattr_async<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] path: String,
+  #[string] name: String,
+  #[buffer] value: JsBuffer,
+) -> Result<(), AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  check_unstable(&state.borrow(), "Deno.setXattr");
+  let path = PathBuf::from(path);
+
+  let fs = {
+    let mut state = state.borrow_mut();
+    let permissions = state.borrow_mut::<P>();
+    permissions.check_write(&path, "Deno.setXattr()")?;
+    state.borrow::<FileSystemRc>().clone()
+  };
+
+  fs.set_xattr_async(path.clone(), name, value.to_vec())
+    .await
+    .context_path("setxattr", &path)?;
+
+  Ok(())
+}
+
+#[op2]
+#[serde]
+pub fn op_fs_list_xattrs_sync<P>(
+  state: &mut OpState,
+  #[string] path: String,
+) -> Result<Vec<String>, AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  check_unstable(state, "Deno.listXattrsSync");
+  let path = PathBuf::from(path);
+
+  let permissions = state.borrow_mut::<P>();
+  permissions.check_read(&path, "Deno.listXattrsSync()")?;
+
+  let fs = state.borrow::<FileSystemRc>();
+  let names = fs
+    .list_xattrs_sync(&path)
+    .context_path("listxattr", &path)?;
+
+  Ok(names)
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_fs_list_xattrs_async<P>(
+  state: Rc<RefCell<OpState>>,
+  #[string] path: String,
+) -> Result<Vec<String>, AnyError>
+where
+  P: FsPermissions + 'static,
+{
+  check_unstable(&state.borrow(), "Deno.listXattrs");
+  let path = PathBuf::from(path);
+
+  let fs = {
+    let mut state = state.borrow_mut();
+    let permissions = state.borrow_mut::<P>();
+    permissions.check_read(&path, "Deno.listXattrs()")?;
+    state.borrow::<FileSystemRc>().clone()
+  };
+
+  let names = fs
+    .list_xattrs_async(path.clone())
+    .await
+    .context_path("listxattr", &path)?;
+
+  Ok(names)
+}
+
 fn to_seek_from(offset: i64, whence: i32) -> Result<SeekFrom, AnyError> {
   let seek_from = match whence {
     0 => SeekFrom::Start(offset as u64),
@@ -1537,6 +2079,30 @@ pub async fn op_fs_flock_async(
   Ok(())
 }
 
+#[op2(fast)]
+pub fn op_fs_try_flock_sync(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  exclusive: bool,
+) -> Result<bool, AnyError> {
+  check_unstable(state, "Deno.FsFile.tryLockSync");
+  let file = FileResource::get_file(state, rid)?;
+  let locked = file.try_lock_sync(exclusive)?;
+  Ok(locked)
+}
+
+#[op2(async)]
+pub async fn op_fs_try_flock_async(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  exclusive: bool,
+) -> Result<bool, AnyError> {
+  check_unstable(&state.borrow(), "Deno.FsFile.tryLock");
+  let file = FileResource::get_file(&state.borrow(), rid)?;
+  let locked = file.try_lock_async(exclusive).await?;
+  Ok(locked)
+}
+
 #[op2(fast)]
 pub fn op_fs_funlock_sync(
   state: &mut OpState,
@@ -1579,6 +2145,48 @@ pub async fn op_fs_ftruncate_async(
   Ok(())
 }
 
+fn to_allocate_mode(mode: &str) -> Result<AllocateMode, AnyError> {
+  let mode = match mode {
+    "zero" => AllocateMode::Zero,
+    "sparse" => AllocateMode::Sparse,
+    "punch-hole" => AllocateMode::PunchHole,
+    _ => {
+      return Err(type_error(format!("Invalid allocate mode: {mode}")));
+    }
+  };
+  Ok(mode)
+}
+
+#[op2(fast)]
+pub fn op_fs_fallocate_sync(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[number] offset: u64,
+  #[number] len: u64,
+  #[string] mode: &str,
+) -> Result<(), AnyError> {
+  check_unstable(state, "Deno.FsFile.allocateSync");
+  let mode = to_allocate_mode(mode)?;
+  let file = FileResource::get_file(state, rid)?;
+  file.allocate_sync(offset, len, mode)?;
+  Ok(())
+}
+
+#[op2(async)]
+pub async fn op_fs_fallocate_async(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  #[number] offset: u64,
+  #[number] len: u64,
+  #[string] mode: String,
+) -> Result<(), AnyError> {
+  check_unstable(&state.borrow(), "Deno.FsFile.allocate");
+  let mode = to_allocate_mode(&mode)?;
+  let file = FileResource::get_file(&state.borrow(), rid)?;
+  file.allocate_async(offset, len, mode).await?;
+  Ok(())
+}
+
 #[op2(fast)]
 pub fn op_fs_futime_sync(
   state: &mut OpState,
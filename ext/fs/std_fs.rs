@@ -2,6 +2,7 @@
 
 #![allow(clippy::disallowed_methods)]
 
+use std::cell::RefCell;
 use std::env::current_dir;
 use std::fs;
 use std::io;
@@ -21,9 +22,14 @@ use deno_io::StdFileResourceInner;
 
 use crate::interface::AccessCheckCb;
 use crate::interface::FsDirEntry;
+use crate::interface::FsDirReader;
+use crate::interface::FsDirStatEntry;
 use crate::interface::FsFileType;
+use crate::umask;
+use crate::xattr;
 use crate::FileSystem;
 use crate::OpenOptions;
+use crate::Reflink;
 
 #[derive(Debug, Clone)]
 pub struct RealFs;
@@ -52,30 +58,11 @@ impl FileSystem for RealFs {
 
   #[cfg(unix)]
   fn umask(&self, mask: Option<u32>) -> FsResult<u32> {
-    use nix::sys::stat::mode_t;
-    use nix::sys::stat::umask;
-    use nix::sys::stat::Mode;
-    let r = if let Some(mask) = mask {
-      // If mask provided, return previous.
-      umask(Mode::from_bits_truncate(mask as mode_t))
-    } else {
-      // If no mask provided, we query the current. Requires two syscalls.
-      let prev = umask(Mode::from_bits_truncate(0o777));
-      let _ = umask(prev);
-      prev
-    };
-    #[cfg(any(target_os = "android", target_os = "linux"))]
-    {
-      Ok(r.bits())
-    }
-    #[cfg(any(
-      target_os = "macos",
-      target_os = "openbsd",
-      target_os = "freebsd"
-    ))]
-    {
-      Ok(r.bits() as u32)
-    }
+    // If mask provided, return previous.
+    Ok(match mask {
+      Some(mask) => umask::replace(mask),
+      None => umask::get(),
+    })
   }
 
   fn open_sync(
@@ -97,13 +84,30 @@ impl FileSystem for RealFs {
     Ok(Rc::new(StdFileResourceInner::file(std_file)))
   }
 
+  fn create_unnamed_file_sync(&self, path: &Path) -> FsResult<Rc<dyn File>> {
+    let mask = umask::scoped();
+    let std_file = umask::creating(mask, || create_unnamed_file(path))?;
+    Ok(Rc::new(StdFileResourceInner::file(std_file)))
+  }
+  async fn create_unnamed_file_async(
+    &self,
+    path: PathBuf,
+  ) -> FsResult<Rc<dyn File>> {
+    let mask = umask::scoped();
+    let std_file = spawn_blocking(move || {
+      umask::creating(mask, || create_unnamed_file(&path))
+    })
+    .await??;
+    Ok(Rc::new(StdFileResourceInner::file(std_file)))
+  }
+
   fn mkdir_sync(
     &self,
     path: &Path,
     recursive: bool,
     mode: u32,
   ) -> FsResult<()> {
-    mkdir(path, recursive, mode)
+    umask::creating(umask::scoped(), || mkdir(path, recursive, mode))
   }
   async fn mkdir_async(
     &self,
@@ -111,7 +115,11 @@ impl FileSystem for RealFs {
     recursive: bool,
     mode: u32,
   ) -> FsResult<()> {
-    spawn_blocking(move || mkdir(&path, recursive, mode)).await?
+    let mask = umask::scoped();
+    spawn_blocking(move || {
+      umask::creating(mask, || mkdir(&path, recursive, mode))
+    })
+    .await?
   }
 
   fn chmod_sync(&self, path: &Path, mode: u32) -> FsResult<()> {
@@ -145,11 +153,21 @@ impl FileSystem for RealFs {
     spawn_blocking(move || remove(&path, recursive)).await?
   }
 
-  fn copy_file_sync(&self, from: &Path, to: &Path) -> FsResult<()> {
-    copy_file(from, to)
+  fn copy_file_sync(
+    &self,
+    from: &Path,
+    to: &Path,
+    reflink: Reflink,
+  ) -> FsResult<()> {
+    copy_file(from, to, reflink)
   }
-  async fn copy_file_async(&self, from: PathBuf, to: PathBuf) -> FsResult<()> {
-    spawn_blocking(move || copy_file(&from, &to)).await?
+  async fn copy_file_async(
+    &self,
+    from: PathBuf,
+    to: PathBuf,
+    reflink: Reflink,
+  ) -> FsResult<()> {
+    spawn_blocking(move || copy_file(&from, &to, reflink)).await?
   }
 
   fn cp_sync(&self, fro: &Path, to: &Path) -> FsResult<()> {
@@ -196,6 +214,18 @@ impl FileSystem for RealFs {
     spawn_blocking(move || read_dir(&path)).await?
   }
 
+  fn open_dir_sync(&self, path: &Path) -> FsResult<Rc<dyn FsDirReader>> {
+    let dir = fs::read_dir(path)?;
+    Ok(Rc::new(RealDirReader(RefCell::new(Some(dir)))))
+  }
+  async fn open_dir_async(
+    &self,
+    path: PathBuf,
+  ) -> FsResult<Rc<dyn FsDirReader>> {
+    let dir = spawn_blocking(move || fs::read_dir(path)).await??;
+    Ok(Rc::new(RealDirReader(RefCell::new(Some(dir)))))
+  }
+
   fn rename_sync(&self, oldpath: &Path, newpath: &Path) -> FsResult<()> {
     fs::rename(oldpath, newpath).map_err(Into::into)
   }
@@ -330,6 +360,45 @@ impl FileSystem for RealFs {
     spawn_blocking(move || lchown(&path, uid, gid)).await?
   }
 
+  fn get_xattr_sync(
+    &self,
+    path: &Path,
+    name: &str,
+  ) -> FsResult<Option<Vec<u8>>> {
+    xattr::get(path, name)
+  }
+  async fn get_xattr_async(
+    &self,
+    path: PathBuf,
+    name: String,
+  ) -> FsResult<Option<Vec<u8>>> {
+    spawn_blocking(move || xattr::get(&path, &name)).await?
+  }
+
+  fn set_xattr_sync(
+    &self,
+    path: &Path,
+    name: &str,
+    value: &[u8],
+  ) -> FsResult<()> {
+    xattr::set(path, name, value)
+  }
+  async fn set_xattr_async(
+    &self,
+    path: PathBuf,
+    name: String,
+    value: Vec<u8>,
+  ) -> FsResult<()> {
+    spawn_blocking(move || xattr::set(&path, &name, &value)).await?
+  }
+
+  fn list_xattrs_sync(&self, path: &Path) -> FsResult<Vec<String>> {
+    xattr::list(path)
+  }
+  async fn list_xattrs_async(&self, path: PathBuf) -> FsResult<Vec<String>> {
+    spawn_blocking(move || xattr::list(&path)).await?
+  }
+
   fn write_file_sync(
     &self,
     path: &Path,
@@ -367,6 +436,30 @@ impl FileSystem for RealFs {
     .await?
   }
 
+  fn write_file_atomic_sync(
+    &self,
+    path: &Path,
+    mode: Option<u32>,
+    sync_dir: bool,
+    data: &[u8],
+  ) -> FsResult<()> {
+    write_file_atomic(path, mode, sync_dir, data, umask::scoped())
+  }
+
+  async fn write_file_atomic_async(
+    &self,
+    path: PathBuf,
+    mode: Option<u32>,
+    sync_dir: bool,
+    data: Vec<u8>,
+  ) -> FsResult<()> {
+    let mask = umask::scoped();
+    spawn_blocking(move || {
+      write_file_atomic(&path, mode, sync_dir, &data, mask)
+    })
+    .await?
+  }
+
   fn read_file_sync(
     &self,
     path: &Path,
@@ -516,77 +609,474 @@ fn remove(path: &Path, recursive: bool) -> FsResult<()> {
   res.map_err(Into::into)
 }
 
-fn copy_file(from: &Path, to: &Path) -> FsResult<()> {
+fn copy_file(from: &Path, to: &Path, reflink: Reflink) -> FsResult<()> {
+  match reflink {
+    Reflink::Auto => {}
+    Reflink::Always => return clone_file(from, to),
+    Reflink::Never => return copy_file_contents(from, to),
+  }
+
   #[cfg(target_os = "macos")]
   {
-    use libc::clonefile;
-    use libc::stat;
-    use libc::unlink;
-    use std::ffi::CString;
+    // std::fs::copy does open() + fcopyfile() on macOS. We try to use
+    // clonefile() instead, which is more efficient. fcopyfile() is an
+    // overkill for < 128KB files, so those are copied directly.
+    if fs::metadata(from)?.len() <= 128 * 1024 {
+      return copy_file_contents(from, to);
+    }
+    if clone_file(from, to).is_ok() {
+      return Ok(());
+    }
+    // clonefile() failed, fall back to std::fs::copy().
+  }
+
+  #[cfg(any(target_os = "android", target_os = "linux", windows))]
+  if clone_file(from, to).is_ok() {
+    return Ok(());
+  }
+
+  fs::copy(from, to)?;
+
+  Ok(())
+}
+
+/// Copies the contents and permissions of `from` to `to`, without ever
+/// cloning it, which `std::fs::copy` may do.
+fn copy_file_contents(from: &Path, to: &Path) -> FsResult<()> {
+  let mut from_file = fs::File::open(from)?;
+  let perm = from_file.metadata()?.permissions();
+
+  let mut options = fs::OpenOptions::new();
+  options.write(true).create(true).truncate(true);
+  #[cfg(unix)]
+  {
     use std::os::unix::fs::OpenOptionsExt;
     use std::os::unix::fs::PermissionsExt;
+    // create the file with the correct mode right away
+    options.mode(perm.mode());
+  }
+  let mut to_file = options.open(to)?;
+  let writer_metadata = to_file.metadata()?;
+  if writer_metadata.is_file() {
+    // Set the correct file permissions, in case the file already existed.
+    // Don't set the permissions on already existing non-files like
+    // pipes/FIFOs or device nodes.
+    to_file.set_permissions(perm)?;
+  }
+  let mut buf = vec![0u8; 128 * 1024];
+  loop {
+    let nread = from_file.read(&mut buf)?;
+    if nread == 0 {
+      break;
+    }
+    to_file.write_all(&buf[..nread])?;
+  }
+  Ok(())
+}
 
-    let from_str = CString::new(from.as_os_str().as_encoded_bytes())
-      .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
-    let to_str = CString::new(to.as_os_str().as_encoded_bytes())
-      .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+/// Makes `to` a clone of `from`. Fails with `FsError::NotSupported` if the
+/// file system doesn't support that, or `from` and `to` are on different ones.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn clone_file(from: &Path, to: &Path) -> FsResult<()> {
+  use nix::errno::Errno;
+  use std::os::fd::AsRawFd;
+  use std::os::unix::fs::OpenOptionsExt;
+  use std::os::unix::fs::PermissionsExt;
 
-    // SAFETY: `from` and `to` are valid C strings.
-    // std::fs::copy does open() + fcopyfile() on macOS. We try to use
-    // clonefile() instead, which is more efficient.
-    unsafe {
-      let mut st = std::mem::zeroed();
-      let ret = stat(from_str.as_ptr(), &mut st);
-      if ret != 0 {
-        return Err(io::Error::last_os_error().into());
+  nix::ioctl_write_int!(ficlone, 0x94, 9);
+
+  let from_file = fs::File::open(from)?;
+  let perm = from_file.metadata()?.permissions();
+  let existed = fs::symlink_metadata(to).is_ok();
+  let to_file = fs::OpenOptions::new()
+    .mode(perm.mode())
+    .write(true)
+    .create(true)
+    .truncate(true)
+    .open(to)?;
+  // SAFETY: both fds are valid for the duration of the call
+  let res = unsafe {
+    ficlone(
+      to_file.as_raw_fd(),
+      from_file.as_raw_fd() as nix::sys::ioctl::ioctl_param_type,
+    )
+  };
+  match res {
+    Ok(_) => {
+      to_file.set_permissions(perm)?;
+      Ok(())
+    }
+    Err(errno) => {
+      drop(to_file);
+      if !existed {
+        let _ = fs::remove_file(to);
+      }
+      match errno {
+        Errno::EOPNOTSUPP
+        | Errno::EXDEV
+        | Errno::EINVAL
+        | Errno::ENOTTY
+        | Errno::ENOSYS => Err(FsError::NotSupported),
+        errno => Err(io::Error::from(errno).into()),
       }
+    }
+  }
+}
 
-      if st.st_size > 128 * 1024 {
-        // Try unlink. If it fails, we are going to try clonefile() anyway.
-        let _ = unlink(to_str.as_ptr());
-        // Matches rust stdlib behavior for io::copy.
-        // https://github.com/rust-lang/rust/blob/3fdd578d72a24d4efc2fe2ad18eec3b6ba72271e/library/std/src/sys/unix/fs.rs#L1613-L1616
-        if clonefile(from_str.as_ptr(), to_str.as_ptr(), 0) == 0 {
-          return Ok(());
-        }
-      } else {
-        // Do a regular copy. fcopyfile() is an overkill for < 128KB
-        // files.
-        let mut buf = [0u8; 128 * 1024];
-        let mut from_file = fs::File::open(from)?;
-        let perm = from_file.metadata()?.permissions();
-
-        let mut to_file = fs::OpenOptions::new()
-          // create the file with the correct mode right away
-          .mode(perm.mode())
-          .write(true)
-          .create(true)
-          .truncate(true)
-          .open(to)?;
-        let writer_metadata = to_file.metadata()?;
-        if writer_metadata.is_file() {
-          // Set the correct file permissions, in case the file already existed.
-          // Don't set the permissions on already existing non-files like
-          // pipes/FIFOs or device nodes.
-          to_file.set_permissions(perm)?;
-        }
-        loop {
-          let nread = from_file.read(&mut buf)?;
-          if nread == 0 {
-            break;
-          }
-          to_file.write_all(&buf[..nread])?;
-        }
-        return Ok(());
+/// Makes `to` a clone of `from`. Fails with `FsError::NotSupported` if the
+/// file system doesn't support that, or `from` and `to` are on different ones.
+#[cfg(target_os = "macos")]
+fn clone_file(from: &Path, to: &Path) -> FsResult<()> {
+  use libc::clonefile;
+  use std::ffi::CString;
+
+  let from_str = CString::new(from.as_os_str().as_encoded_bytes())
+    .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+  let to_str = CString::new(to.as_os_str().as_encoded_bytes())
+    .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+  // clonefile() doesn't overwrite existing files. If removing it fails, we
+  // are going to try clonefile() anyway.
+  let _ = fs::remove_file(to);
+  // SAFETY: `from` and `to` are valid C strings.
+  if unsafe { clonefile(from_str.as_ptr(), to_str.as_ptr(), 0) } == 0 {
+    return Ok(());
+  }
+  let err = io::Error::last_os_error();
+  match err.raw_os_error() {
+    Some(libc::ENOTSUP | libc::EXDEV) => Err(FsError::NotSupported),
+    _ => Err(err.into()),
+  }
+}
+
+/// Makes `to` a clone of `from` with block cloning, which ReFS supports.
+/// Fails with `FsError::NotSupported` if the file system doesn't support it,
+/// or `from` and `to` are on different volumes.
+#[cfg(windows)]
+fn clone_file(from: &Path, to: &Path) -> FsResult<()> {
+  use std::os::windows::fs::MetadataExt;
+  use std::os::windows::io::AsRawHandle;
+  use windows_sys::Win32::Foundation::ERROR_BLOCK_TOO_MANY_REFERENCES;
+  use windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION;
+  use windows_sys::Win32::Foundation::ERROR_NOT_SAME_DEVICE;
+  use windows_sys::Win32::Foundation::ERROR_NOT_SUPPORTED;
+  use windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_SPARSE_FILE;
+  use windows_sys::Win32::System::Ioctl::DUPLICATE_EXTENTS_DATA;
+  use windows_sys::Win32::System::Ioctl::FSCTL_DUPLICATE_EXTENTS_TO_FILE;
+  use windows_sys::Win32::System::Ioctl::FSCTL_SET_SPARSE;
+  use windows_sys::Win32::System::IO::DeviceIoControl;
+
+  fn control(
+    file: &fs::File,
+    code: u32,
+    input: Option<&DUPLICATE_EXTENTS_DATA>,
+  ) -> io::Result<()> {
+    let (input, input_len) = match input {
+      Some(input) => (
+        input as *const DUPLICATE_EXTENTS_DATA as *const std::ffi::c_void,
+        std::mem::size_of::<DUPLICATE_EXTENTS_DATA>() as u32,
+      ),
+      None => (std::ptr::null(), 0),
+    };
+    let mut returned = 0;
+    // SAFETY: winapi call, `input` is valid for `input_len` bytes
+    let ok = unsafe {
+      DeviceIoControl(
+        file.as_raw_handle() as _,
+        code,
+        input,
+        input_len,
+        std::ptr::null_mut(),
+        0,
+        &mut returned,
+        std::ptr::null_mut(),
+      )
+    };
+    if ok == 0 {
+      return Err(io::Error::last_os_error());
+    }
+    Ok(())
+  }
+
+  let from_file = fs::File::open(from)?;
+  let metadata = from_file.metadata()?;
+  let len = metadata.len();
+  let cluster_size = cluster_size(from)?;
+  let existed = fs::symlink_metadata(to).is_ok();
+  let to_file = fs::OpenOptions::new()
+    .write(true)
+    .create(true)
+    .truncate(true)
+    .open(to)?;
+  let res = (|| {
+    if metadata.file_attributes() & FILE_ATTRIBUTE_SPARSE_FILE != 0 {
+      control(&to_file, FSCTL_SET_SPARSE, None)?;
+    }
+    to_file.set_len(len)?;
+    // every region must be less than 4GB, and a multiple of the cluster size,
+    // even at the end of the file
+    let max_region = (u32::MAX as u64 / cluster_size) * cluster_size;
+    let mut offset = 0;
+    while offset < len {
+      let count =
+        (len - offset).min(max_region).div_ceil(cluster_size) * cluster_size;
+      let data = DUPLICATE_EXTENTS_DATA {
+        FileHandle: from_file.as_raw_handle() as _,
+        SourceFileOffset: offset as i64,
+        TargetFileOffset: offset as i64,
+        ByteCount: count as i64,
+      };
+      control(&to_file, FSCTL_DUPLICATE_EXTENTS_TO_FILE, Some(&data))?;
+      offset += count;
+    }
+    to_file.set_permissions(metadata.permissions())
+  })();
+  match res {
+    Ok(()) => Ok(()),
+    Err(err) => {
+      drop(to_file);
+      if !existed {
+        let _ = fs::remove_file(to);
+      }
+      match err.raw_os_error().map(|code| code as u32) {
+        Some(
+          ERROR_INVALID_FUNCTION
+          | ERROR_NOT_SUPPORTED
+          | ERROR_NOT_SAME_DEVICE
+          | ERROR_BLOCK_TOO_MANY_REFERENCES,
+        ) => Err(FsError::NotSupported),
+        _ => Err(err.into()),
       }
     }
+  }
+}
 
-    // clonefile() failed, fall back to std::fs::copy().
+/// The cluster size of the volume `path` is on.
+#[cfg(windows)]
+fn cluster_size(path: &Path) -> io::Result<u64> {
+  use std::os::windows::ffi::OsStrExt;
+  use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceW;
+  use windows_sys::Win32::Storage::FileSystem::GetVolumePathNameW;
+
+  let path = path
+    .as_os_str()
+    .encode_wide()
+    .chain(Some(0))
+    .collect::<Vec<_>>();
+  let mut volume = vec![0u16; 1024];
+  let mut sectors_per_cluster = 0;
+  let mut bytes_per_sector = 0;
+  let mut free_clusters = 0;
+  let mut total_clusters = 0;
+  // SAFETY: winapi calls, the buffers are valid and null terminated
+  let ok = unsafe {
+    GetVolumePathNameW(path.as_ptr(), volume.as_mut_ptr(), volume.len() as u32)
+      != 0
+      && GetDiskFreeSpaceW(
+        volume.as_ptr(),
+        &mut sectors_per_cluster,
+        &mut bytes_per_sector,
+        &mut free_clusters,
+        &mut total_clusters,
+      ) != 0
+  };
+  if !ok {
+    return Err(io::Error::last_os_error());
   }
+  Ok(sectors_per_cluster as u64 * bytes_per_sector as u64)
+}
 
-  fs::copy(from, to)?;
+/// Cloning files isn't supported on this platform.
+#[cfg(not(any(
+  target_os = "android",
+  target_os = "linux",
+  target_os = "macos",
+  windows
+)))]
+fn clone_file(_from: &Path, _to: &Path) -> FsResult<()> {
+  Err(FsError::NotSupported)
+}
 
-  Ok(())
+/// Opens a file with `O_TMPFILE` in the directory of `path`, unless the
+/// kernel or the file system doesn't support it, and otherwise removes the
+/// file at `path` right after creating it.
+#[cfg(unix)]
+fn create_unnamed_file(path: &Path) -> io::Result<fs::File> {
+  use std::os::unix::fs::OpenOptionsExt;
+
+  #[cfg(any(target_os = "android", target_os = "linux"))]
+  {
+    let dir = match path.parent() {
+      Some(parent) if !parent.as_os_str().is_empty() => parent,
+      _ => Path::new("."),
+    };
+    let res = fs::OpenOptions::new()
+      .read(true)
+      .write(true)
+      .mode(0o600)
+      .custom_flags(libc::O_TMPFILE)
+      .open(dir);
+    match res {
+      Ok(file) => return Ok(file),
+      // kernels before 3.11 ignore the flag apart from its `O_DIRECTORY`
+      Err(err)
+        if matches!(
+          err.raw_os_error(),
+          Some(libc::EOPNOTSUPP | libc::EISDIR | libc::EINVAL)
+        ) => {}
+      Err(err) => return Err(err),
+    }
+  }
+
+  let file = fs::OpenOptions::new()
+    .read(true)
+    .write(true)
+    .create_new(true)
+    .mode(0o600)
+    .open(path)?;
+  fs::remove_file(path)?;
+  Ok(file)
+}
+
+/// Creates the file at `path` to be deleted once its last handle is closed.
+#[cfg(windows)]
+fn create_unnamed_file(path: &Path) -> io::Result<fs::File> {
+  use std::os::windows::fs::OpenOptionsExt;
+  use winapi::um::winbase::FILE_FLAG_DELETE_ON_CLOSE;
+  use winapi::um::winnt::FILE_ATTRIBUTE_TEMPORARY;
+  use winapi::um::winnt::FILE_SHARE_DELETE;
+  use winapi::um::winnt::FILE_SHARE_READ;
+  use winapi::um::winnt::FILE_SHARE_WRITE;
+
+  fs::OpenOptions::new()
+    .read(true)
+    .write(true)
+    .create_new(true)
+    .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE)
+    .attributes(FILE_ATTRIBUTE_TEMPORARY)
+    .custom_flags(FILE_FLAG_DELETE_ON_CLOSE)
+    .open(path)
+}
+
+/// Writes `data` to a new file next to `path`, which is renamed over `path`
+/// once it is flushed to disk. Unless `mode` is given, an existing file keeps
+/// its permissions. The new file is created with `mask` as the umask, if
+/// given.
+fn write_file_atomic(
+  path: &Path,
+  mode: Option<u32>,
+  sync_dir: bool,
+  data: &[u8],
+  mask: Option<u32>,
+) -> FsResult<()> {
+  let file_name = path.file_name().ok_or_else(|| {
+    io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name")
+  })?;
+  let dir = match path.parent() {
+    Some(parent) if !parent.as_os_str().is_empty() => parent,
+    _ => Path::new("."),
+  };
+
+  #[cfg(unix)]
+  let permissions = {
+    use std::os::unix::fs::PermissionsExt;
+    match mode {
+      Some(mode) => Some(fs::Permissions::from_mode(mode)),
+      None => fs::metadata(path).ok().map(|m| m.permissions()),
+    }
+  };
+  #[cfg(not(unix))]
+  let _ = mode;
+
+  let (temp_path, mut file) = create_sibling_temp_file(dir, file_name, mask)?;
+  let res = (|| -> io::Result<()> {
+    #[cfg(unix)]
+    if let Some(permissions) = permissions {
+      file.set_permissions(permissions)?;
+    }
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+    rename_replacing(&temp_path, path)?;
+    #[cfg(unix)]
+    if sync_dir {
+      // makes the rename itself durable
+      fs::File::open(dir)?.sync_all()?;
+    }
+    // the rename is written through on Windows
+    #[cfg(not(unix))]
+    let _ = sync_dir;
+    Ok(())
+  })();
+  if res.is_err() {
+    let _ = fs::remove_file(&temp_path);
+  }
+  res.map_err(Into::into)
+}
+
+fn create_sibling_temp_file(
+  dir: &Path,
+  file_name: &std::ffi::OsStr,
+  mask: Option<u32>,
+) -> io::Result<(PathBuf, fs::File)> {
+  let mut options = fs::OpenOptions::new();
+  options.write(true).create_new(true);
+  loop {
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{:08x}.tmp", rand::random::<u32>()));
+    let temp_path = dir.join(temp_name);
+    match umask::creating(mask, || options.open(&temp_path)) {
+      Ok(file) => return Ok((temp_path, file)),
+      Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+      Err(err) => return Err(err),
+    }
+  }
+}
+
+#[cfg(not(windows))]
+fn rename_replacing(from: &Path, to: &Path) -> io::Result<()> {
+  fs::rename(from, to)
+}
+
+/// Like `std::fs::rename`, but the rename is written to disk before this
+/// returns, and files which are briefly opened without sharing, e.g. by virus
+/// scanners or the search indexer, don't make it fail.
+#[cfg(windows)]
+fn rename_replacing(from: &Path, to: &Path) -> io::Result<()> {
+  use std::os::windows::ffi::OsStrExt;
+  use windows_sys::Win32::Foundation::ERROR_ACCESS_DENIED;
+  use windows_sys::Win32::Foundation::ERROR_SHARING_VIOLATION;
+  use windows_sys::Win32::Storage::FileSystem::MoveFileExW;
+  use windows_sys::Win32::Storage::FileSystem::MOVEFILE_REPLACE_EXISTING;
+  use windows_sys::Win32::Storage::FileSystem::MOVEFILE_WRITE_THROUGH;
+
+  fn wide(path: &Path) -> Vec<u16> {
+    path.as_os_str().encode_wide().chain(Some(0)).collect()
+  }
+  let (from, to) = (wide(from), wide(to));
+  let mut attempt = 0;
+  loop {
+    // SAFETY: winapi call, both paths are null terminated
+    let ok = unsafe {
+      MoveFileExW(
+        from.as_ptr(),
+        to.as_ptr(),
+        MOVEFILE_REPLACE_EXISTING | MOVEFILE_WRITE_THROUGH,
+      )
+    };
+    if ok != 0 {
+      return Ok(());
+    }
+    let err = io::Error::last_os_error();
+    match err.raw_os_error().map(|code| code as u32) {
+      Some(ERROR_ACCESS_DENIED | ERROR_SHARING_VIOLATION) if attempt < 10 => {
+        attempt += 1;
+        std::thread::sleep(std::time::Duration::from_millis(10 * attempt));
+      }
+      _ => return Err(err),
+    }
+  }
 }
 
 fn cp(from: &Path, to: &Path) -> FsResult<()> {
@@ -663,7 +1153,7 @@ fn cp(from: &Path, to: &Path) -> FsResult<()> {
       fs::create_dir_all(parent)?;
     }
 
-    copy_file(from, to)
+    copy_file(from, to, Reflink::Auto)
   }
 
   #[cfg(target_os = "macos")]
@@ -932,6 +1422,67 @@ fn realpath(path: &Path) -> FsResult<PathBuf> {
   Ok(deno_core::strip_unc_prefix(path.canonicalize()?))
 }
 
+/// Reads the metadata of each entry along with it. `std` gets it with `statx`
+/// relative to the directory on Linux, and from the data `FindNextFileW`
+/// returns for the entry on Windows, which lacks `dev` though.
+struct RealDirReader(RefCell<Option<fs::ReadDir>>);
+
+#[async_trait::async_trait(?Send)]
+impl FsDirReader for RealDirReader {
+  fn next_batch_sync(
+    self: Rc<Self>,
+    max: usize,
+  ) -> FsResult<Vec<FsDirStatEntry>> {
+    let mut dir = self.0.borrow_mut();
+    let dir = dir.as_mut().ok_or(FsError::FileBusy)?;
+    read_dir_batch(dir, max)
+  }
+  async fn next_batch_async(
+    self: Rc<Self>,
+    max: usize,
+  ) -> FsResult<Vec<FsDirStatEntry>> {
+    // the directory is handed to the blocking task, and back afterwards
+    let mut dir = self.0.borrow_mut().take().ok_or(FsError::FileBusy)?;
+    let (dir, res) = spawn_blocking(move || {
+      let res = read_dir_batch(&mut dir, max);
+      (dir, res)
+    })
+    .await?;
+    *self.0.borrow_mut() = Some(dir);
+    res
+  }
+}
+
+fn read_dir_batch(
+  dir: &mut fs::ReadDir,
+  max: usize,
+) -> FsResult<Vec<FsDirStatEntry>> {
+  let mut entries = vec![];
+  while entries.len() < max {
+    let Some(entry) = dir.next() else {
+      break;
+    };
+    // like `read_dir`, which skips unreadable and non UTF-8 entries
+    let Ok(entry) = entry else {
+      continue;
+    };
+    let Ok(name) = entry.file_name().into_string() else {
+      continue;
+    };
+    let metadata = match entry.metadata() {
+      Ok(metadata) => metadata,
+      // removed since it was listed
+      Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+      Err(err) => return Err(err.into()),
+    };
+    entries.push(FsDirStatEntry {
+      name,
+      stat: FsStat::from_std(metadata),
+    });
+  }
+  Ok(entries)
+}
+
 fn read_dir(path: &Path) -> FsResult<Vec<FsDirEntry>> {
   let entries = fs::read_dir(path)?
     .filter_map(|entry| {
@@ -1108,7 +1659,7 @@ fn open_with_access_check(
       }
     }
 
-    Ok(opts.open(&path)?)
+    Ok(open(&opts, &path, &options)?)
   } else {
     // for unix
     #[allow(unused_mut)]
@@ -1119,6 +1670,19 @@ fn open_with_access_check(
       use std::os::windows::fs::OpenOptionsExt;
       opts.custom_flags(winapi::um::winbase::FILE_FLAG_BACKUP_SEMANTICS);
     }
-    Ok(opts.open(path)?)
+    Ok(open(&opts, path, &options)?)
+  }
+}
+
+fn open(
+  opts: &fs::OpenOptions,
+  path: &Path,
+  options: &OpenOptions,
+) -> io::Result<fs::File> {
+  if options.create || options.create_new {
+    umask::creating(umask::scoped(), || opts.open(path))
+  } else {
+    // opening e.g. a FIFO may block, which mustn't hold up umask changes
+    opts.open(path)
   }
 }
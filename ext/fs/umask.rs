@@ -0,0 +1,90 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! The umask is process wide, so changing it for a while, to query it or to
+//! create files with another mask, races with files being created elsewhere,
+//! e.g. by a worker. Operations which create files or spawn processes hold
+//! `UMASK_LOCK` for reading while they do, and changes hold it for writing.
+//!
+//! A scoped mask, see [`set_scoped`], only applies to the files created by the
+//! current thread, i.e. by the isolate running on it, and to the processes it
+//! spawns.
+
+use std::cell::Cell;
+use std::sync::PoisonError;
+use std::sync::RwLock;
+
+static UMASK_LOCK: RwLock<()> = RwLock::new(());
+
+thread_local! {
+  static SCOPED_UMASK: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+/// The scoped mask of the current thread.
+pub fn scoped() -> Option<u32> {
+  SCOPED_UMASK.with(Cell::get)
+}
+
+/// Sets the scoped mask of the current thread, and returns the previous one.
+pub fn set_scoped(mask: Option<u32>) -> Option<u32> {
+  SCOPED_UMASK.with(|scoped| scoped.replace(mask))
+}
+
+/// Runs `f`, which creates files or spawns processes, with `mask` as the
+/// umask if given, usually [`scoped`]. Nothing else can change the umask in
+/// the meantime.
+pub fn creating<R>(mask: Option<u32>, f: impl FnOnce() -> R) -> R {
+  match mask {
+    #[cfg(unix)]
+    Some(mask) => {
+      let _guard = UMASK_LOCK.write().unwrap_or_else(PoisonError::into_inner);
+      let previous = set(mask);
+      let result = f();
+      set(previous);
+      result
+    }
+    _ => {
+      let _guard = UMASK_LOCK.read().unwrap_or_else(PoisonError::into_inner);
+      f()
+    }
+  }
+}
+
+/// Returns the umask of the process.
+#[cfg(unix)]
+pub fn get() -> u32 {
+  // Linux reports it without having to change it
+  #[cfg(any(target_os = "android", target_os = "linux"))]
+  {
+    #[allow(clippy::disallowed_methods)]
+    let status = std::fs::read_to_string("/proc/self/status");
+    let mask = status.ok().and_then(|status| {
+      let mask = status.lines().find_map(|l| l.strip_prefix("Umask:"))?;
+      u32::from_str_radix(mask.trim(), 8).ok()
+    });
+    if let Some(mask) = mask {
+      return mask;
+    }
+  }
+  let _guard = UMASK_LOCK.write().unwrap_or_else(PoisonError::into_inner);
+  let previous = set(0o777);
+  set(previous);
+  previous
+}
+
+/// Sets the umask of the process, and returns the previous one.
+#[cfg(unix)]
+pub fn replace(mask: u32) -> u32 {
+  let _guard = UMASK_LOCK.write().unwrap_or_else(PoisonError::into_inner);
+  set(mask)
+}
+
+#[cfg(unix)]
+// `mode_t` is smaller than `u32` on some platforms
+#[allow(clippy::useless_conversion)]
+fn set(mask: u32) -> u32 {
+  use nix::sys::stat::mode_t;
+  use nix::sys::stat::umask;
+  use nix::sys::stat::Mode;
+
+  u32::from(umask(Mode::from_bits_truncate(mask as mode_t)).bits())
+}
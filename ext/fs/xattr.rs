@@ -0,0 +1,289 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Extended attributes of files. On Linux and macOS these are xattrs, whose
+//! names on Linux need a namespace, e.g. `user.`. On Windows they are
+//! alternate data streams, i.e. `name` is stored in the stream `path:name`.
+
+use std::path::Path;
+
+use deno_io::fs::FsResult;
+
+#[cfg(any(target_os = "android", target_os = "linux", target_os = "macos"))]
+mod unix {
+  use std::ffi::CString;
+  use std::io;
+  use std::path::Path;
+
+  use deno_io::fs::FsResult;
+
+  fn c_string(s: &[u8]) -> io::Result<CString> {
+    CString::new(s)
+      .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+  }
+
+  /// Calls `f` with a buffer of the size it reports when called without one,
+  /// until the value doesn't grow in between.
+  fn read_sized(
+    mut f: impl FnMut(*mut libc::c_void, usize) -> libc::ssize_t,
+  ) -> io::Result<Vec<u8>> {
+    loop {
+      let size = f(std::ptr::null_mut(), 0);
+      if size < 0 {
+        return Err(io::Error::last_os_error());
+      }
+      let mut buf = vec![0u8; size as usize];
+      let len = f(buf.as_mut_ptr() as *mut libc::c_void, buf.len());
+      if len >= 0 {
+        buf.truncate(len as usize);
+        return Ok(buf);
+      }
+      let err = io::Error::last_os_error();
+      if err.raw_os_error() != Some(libc::ERANGE) {
+        return Err(err);
+      }
+    }
+  }
+
+  pub fn get(path: &Path, name: &str) -> FsResult<Option<Vec<u8>>> {
+    let path = c_string(path.as_os_str().as_encoded_bytes())?;
+    let name = c_string(name.as_bytes())?;
+    let res = read_sized(|value, size| {
+      // SAFETY: libc call, `value` is null or valid for `size` bytes
+      unsafe {
+        #[cfg(target_os = "macos")]
+        return libc::getxattr(path.as_ptr(), name.as_ptr(), value, size, 0, 0);
+        #[cfg(not(target_os = "macos"))]
+        return libc::getxattr(path.as_ptr(), name.as_ptr(), value, size);
+      }
+    });
+    #[cfg(target_os = "macos")]
+    const NO_ATTR: i32 = libc::ENOATTR;
+    #[cfg(not(target_os = "macos"))]
+    const NO_ATTR: i32 = libc::ENODATA;
+    match res {
+      Ok(value) => Ok(Some(value)),
+      Err(err) if err.raw_os_error() == Some(NO_ATTR) => Ok(None),
+      Err(err) => Err(err.into()),
+    }
+  }
+
+  pub fn set(path: &Path, name: &str, value: &[u8]) -> FsResult<()> {
+    let path = c_string(path.as_os_str().as_encoded_bytes())?;
+    let name = c_string(name.as_bytes())?;
+    let value_ptr = value.as_ptr() as *const libc::c_void;
+    // SAFETY: libc call, `value` is valid for its length
+    let res = unsafe {
+      #[cfg(target_os = "macos")]
+      {
+        libc::setxattr(
+          path.as_ptr(),
+          name.as_ptr(),
+          value_ptr,
+          value.len(),
+          0,
+          0,
+        )
+      }
+      #[cfg(not(target_os = "macos"))]
+      {
+        libc::setxattr(path.as_ptr(), name.as_ptr(), value_ptr, value.len(), 0)
+      }
+    };
+    if res != 0 {
+      return Err(io::Error::last_os_error().into());
+    }
+    Ok(())
+  }
+
+  pub fn list(path: &Path) -> FsResult<Vec<String>> {
+    let path = c_string(path.as_os_str().as_encoded_bytes())?;
+    let names = read_sized(|list, size| {
+      let list = list as *mut libc::c_char;
+      // SAFETY: libc call, `list` is null or valid for `size` bytes
+      unsafe {
+        #[cfg(target_os = "macos")]
+        return libc::listxattr(path.as_ptr(), list, size, 0);
+        #[cfg(not(target_os = "macos"))]
+        return libc::listxattr(path.as_ptr(), list, size);
+      }
+    })?;
+    Ok(
+      names
+        .split(|b| *b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect(),
+    )
+  }
+}
+
+#[cfg(windows)]
+mod windows {
+  use std::fs;
+  use std::io;
+  use std::io::Read;
+  use std::io::Write;
+  use std::os::windows::ffi::OsStrExt;
+  use std::path::Path;
+  use std::path::PathBuf;
+
+  use deno_io::fs::FsResult;
+
+  fn stream_path(path: &Path, name: &str) -> io::Result<PathBuf> {
+    if name.is_empty() || name.contains([':', '\\', '/', '\0']) {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "Invalid extended attribute name",
+      ));
+    }
+    let mut stream = path.as_os_str().to_owned();
+    stream.push(":");
+    stream.push(name);
+    Ok(stream.into())
+  }
+
+  pub fn get(path: &Path, name: &str) -> FsResult<Option<Vec<u8>>> {
+    let stream = stream_path(path, name)?;
+    let mut file = match fs::File::open(stream) {
+      Ok(file) => file,
+      // the file itself must exist
+      Err(err) if err.kind() == io::ErrorKind::NotFound => {
+        #[allow(clippy::disallowed_methods)]
+        fs::symlink_metadata(path)?;
+        return Ok(None);
+      }
+      Err(err) => return Err(err.into()),
+    };
+    let mut value = vec![];
+    file.read_to_end(&mut value)?;
+    Ok(Some(value))
+  }
+
+  pub fn set(path: &Path, name: &str, value: &[u8]) -> FsResult<()> {
+    // don't create the file itself
+    #[allow(clippy::disallowed_methods)]
+    fs::symlink_metadata(path)?;
+    let stream = stream_path(path, name)?;
+    let mut file = fs::File::create(stream)?;
+    file.write_all(value)?;
+    Ok(())
+  }
+
+  pub fn list(path: &Path) -> FsResult<Vec<String>> {
+    use windows_sys::Win32::Foundation::GetLastError;
+    use windows_sys::Win32::Foundation::ERROR_HANDLE_EOF;
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::Storage::FileSystem::FindClose;
+    use windows_sys::Win32::Storage::FileSystem::FindFirstStreamW;
+    use windows_sys::Win32::Storage::FileSystem::FindNextStreamW;
+    use windows_sys::Win32::Storage::FileSystem::FindStreamInfoStandard;
+    use windows_sys::Win32::Storage::FileSystem::WIN32_FIND_STREAM_DATA;
+
+    let path = path
+      .as_os_str()
+      .encode_wide()
+      .chain(Some(0))
+      .collect::<Vec<_>>();
+    let mut names = vec![];
+    // SAFETY: winapi calls, `data` is a valid WIN32_FIND_STREAM_DATA
+    unsafe {
+      let mut data = std::mem::zeroed::<WIN32_FIND_STREAM_DATA>();
+      let handle = FindFirstStreamW(
+        path.as_ptr(),
+        FindStreamInfoStandard,
+        &mut data as *mut _ as *mut std::ffi::c_void,
+        0,
+      );
+      if handle == INVALID_HANDLE_VALUE {
+        // files without any stream, e.g. directories without named streams
+        if GetLastError() == ERROR_HANDLE_EOF {
+          return Ok(names);
+        }
+        return Err(io::Error::last_os_error().into());
+      }
+      loop {
+        let len = data
+          .cStreamName
+          .iter()
+          .position(|c| *c == 0)
+          .unwrap_or(data.cStreamName.len());
+        let stream = String::from_utf16_lossy(&data.cStreamName[..len]);
+        // streams are named ":name:$DATA", the unnamed one is "::$DATA"
+        if let Some(name) = stream
+          .strip_prefix(':')
+          .and_then(|s| s.strip_suffix(":$DATA"))
+          .filter(|name| !name.is_empty())
+        {
+          names.push(name.to_string());
+        }
+        if FindNextStreamW(handle, &mut data as *mut _ as *mut std::ffi::c_void)
+          == 0
+        {
+          let err = io::Error::last_os_error();
+          FindClose(handle);
+          if err.raw_os_error() == Some(ERROR_HANDLE_EOF as i32) {
+            return Ok(names);
+          }
+          return Err(err.into());
+        }
+      }
+    }
+  }
+}
+
+#[cfg(not(any(
+  target_os = "android",
+  target_os = "linux",
+  target_os = "macos",
+  windows
+)))]
+mod unsupported {
+  use std::path::Path;
+
+  use deno_io::fs::FsError;
+  use deno_io::fs::FsResult;
+
+  pub fn get(_path: &Path, _name: &str) -> FsResult<Option<Vec<u8>>> {
+    Err(FsError::NotSupported)
+  }
+
+  pub fn set(_path: &Path, _name: &str, _value: &[u8]) -> FsResult<()> {
+    Err(FsError::NotSupported)
+  }
+
+  pub fn list(_path: &Path) -> FsResult<Vec<String>> {
+    Err(FsError::NotSupported)
+  }
+}
+
+#[cfg(any(
+  target_os = "android",
+  target_os = "linux",
+  target_os = "macos"
+))]
+use unix as imp;
+#[cfg(not(any(
+  target_os = "android",
+  target_os = "linux",
+  target_os = "macos",
+  windows
+)))]
+use unsupported as imp;
+#[cfg(windows)]
+use windows as imp;
+
+/// Returns the value of the attribute `name` of `path`, or `None` if it has
+/// no such attribute.
+pub fn get(path: &Path, name: &str) -> FsResult<Option<Vec<u8>>> {
+  imp::get(path, name)
+}
+
+/// Sets the attribute `name` of `path` to `value`.
+pub fn set(path: &Path, name: &str, value: &[u8]) -> FsResult<()> {
+  imp::set(path, name, value)
+}
+
+/// Returns the names of the attributes of `path`.
+pub fn list(path: &Path) -> FsResult<Vec<String>> {
+  imp::list(path)
+}
@@ -6,16 +6,21 @@ const {
   InterruptedPrototype,
   Interrupted,
   internalRidSymbol,
+  getAsyncContext,
+  setAsyncContext,
 } = core;
 import {
   op_http_cancel,
   op_http_close,
   op_http_close_after_finish,
+  op_http_get_request_client_certificate,
   op_http_get_request_headers,
   op_http_get_request_method_and_url,
   op_http_read_request_body,
   op_http_serve,
+  op_http_serve_add_compression_dictionary,
   op_http_serve_on,
+  op_http_serve_stats,
   op_http_set_promise_complete,
   op_http_set_response_body_bytes,
   op_http_set_response_body_resource,
@@ -30,6 +35,8 @@ import {
 } from "ext:core/ops";
 const {
   ArrayPrototypePush,
+  MathCeil,
+  NumberIsFinite,
   ObjectHasOwn,
   ObjectPrototypeIsPrototypeOf,
   PromisePrototypeCatch,
@@ -66,6 +73,7 @@ import {
   _role,
   _server,
   _serverHandleIdleTimeout,
+  _upgradeOptions,
   SERVER,
   WebSocket,
 } from "ext:deno_websocket/01_websocket.js";
@@ -76,7 +84,12 @@ import {
   ReadableStreamPrototype,
   resourceForReadableStream,
 } from "ext:deno_web/06_streams.js";
-import { listen, listenOptionApiName, TcpConn } from "ext:deno_net/01_net.js";
+import {
+  listen,
+  listenFd,
+  listenOptionApiName,
+  TcpConn,
+} from "ext:deno_net/01_net.js";
 import { hasTlsKeyPairOptions, listenTls } from "ext:deno_net/02_tls.js";
 import { SymbolAsyncDispose } from "ext:deno_web/00_infra.js";
 
@@ -140,6 +153,7 @@ class InnerRequest {
   #upgraded;
   #urlValue;
   #completed;
+  #clientCertificate;
   request;
 
   constructor(external, context) {
@@ -216,6 +230,7 @@ class InnerRequest {
       const wsPromise = op_http_upgrade_websocket_next(
         external,
         response.headerList,
+        ws[_upgradeOptions],
       );
 
       // Start the upgrade in the background.
@@ -336,6 +351,18 @@ class InnerRequest {
     return this.#methodAndUri[0];
   }
 
+  get clientCertificate() {
+    if (this.#clientCertificate === undefined) {
+      if (this.#external === null) {
+        throw new TypeError("request closed");
+      }
+      this.#clientCertificate = op_http_get_request_client_certificate(
+        this.#external,
+      );
+    }
+    return this.#clientCertificate;
+  }
+
   get body() {
     if (this.#external === null) {
       throw new TypeError("request closed");
@@ -419,6 +446,9 @@ class ServeHandlerInfo {
   get completed() {
     return this.#inner.completed;
   }
+  get clientCertificate() {
+    return this.#inner.clientCertificate;
+  }
 }
 
 function fastSyncResponseOrStream(
@@ -490,7 +520,11 @@ function fastSyncResponseOrStream(
  * This function returns a promise that will only reject in the case of abnormal exit.
  */
 function mapToCallback(context, callback, onError) {
-  return async function (req) {
+  // Every request is handled in the async context the server was started in,
+  // so that a handler entering a context cannot leak it into the requests
+  // that are accepted after it.
+  const asyncContext = getAsyncContext();
+  const handleRequest = async function (req) {
     // Get the response from the user-provided callback. If that fails, use onError. If that fails, return a fallback
     // 500 error.
     let innerRequest;
@@ -562,6 +596,15 @@ function mapToCallback(context, callback, onError) {
 
     fastSyncResponseOrStream(req, inner.body, status, innerRequest);
   };
+  return function (req) {
+    const oldContext = getAsyncContext();
+    try {
+      setAsyncContext(asyncContext);
+      return handleRequest(req);
+    } finally {
+      setAsyncContext(oldContext);
+    }
+  };
 }
 
 type RawHandler = (
@@ -579,6 +622,11 @@ type RawServeOptions = {
   onError?: (error: unknown) => Response | Promise<Response>;
   onListen?: (params: { hostname: string; port: number }) => void;
   handler?: RawHandler;
+  compressionDictionaries?: Uint8Array[];
+  listener?: Deno.Listener;
+  fd?: number;
+  clientCaCerts?: string[];
+  requireClientCert?: boolean;
 };
 
 const kLoadBalanced = Symbol("kLoadBalanced");
@@ -619,6 +667,37 @@ function serve(arg1, arg2) {
     return internalServerError();
   };
 
+  if (options.listener !== undefined || options.fd !== undefined) {
+    if (options.listener !== undefined && options.fd !== undefined) {
+      throw new TypeError(
+        "Only one of 'listener' and 'fd' may be provided.",
+      );
+    }
+    const listener = options.listener ?? listenFd(options.fd, "Deno.serve");
+    const addr = listener.addr;
+    return serveHttpOnListener(
+      listener,
+      signal,
+      handler,
+      onError,
+      (scheme) => {
+        if (options.onListen) {
+          options.onListen(addr);
+        } else if (addr.transport === "unix") {
+          // deno-lint-ignore no-console
+          console.log(`Listening on ${addr.path}`);
+        } else {
+          const host = StringPrototypeIncludes(addr.hostname, ":")
+            ? `[${addr.hostname}]`
+            : addr.hostname;
+          // deno-lint-ignore no-console
+          console.log(`Listening on ${scheme}${host}:${addr.port}/`);
+        }
+      },
+      options.compressionDictionaries,
+    );
+  }
+
   if (wantsUnix) {
     const listener = listen({
       transport: "unix",
@@ -626,14 +705,21 @@ function serve(arg1, arg2) {
       [listenOptionApiName]: "Deno.serve",
     });
     const path = listener.addr.path;
-    return serveHttpOnListener(listener, signal, handler, onError, () => {
-      if (options.onListen) {
-        options.onListen(listener.addr);
-      } else {
-        // deno-lint-ignore no-console
-        console.log(`Listening on ${path}`);
-      }
-    });
+    return serveHttpOnListener(
+      listener,
+      signal,
+      handler,
+      onError,
+      () => {
+        if (options.onListen) {
+          options.onListen(listener.addr);
+        } else {
+          // deno-lint-ignore no-console
+          console.log(`Listening on ${path}`);
+        }
+      },
+      options.compressionDictionaries,
+    );
   }
 
   const listenOpts = {
@@ -664,6 +750,8 @@ function serve(arg1, arg2) {
     listenOpts.cert = options.cert;
     listenOpts.key = options.key;
     listenOpts.alpnProtocols = ["h2", "http/1.1"];
+    listenOpts.clientCaCerts = options.clientCaCerts;
+    listenOpts.requireClientCert = options.requireClientCert;
     listener = listenTls(listenOpts);
     listenOpts.port = listener.addr.port;
   } else {
@@ -693,18 +781,40 @@ function serve(arg1, arg2) {
     }
   };
 
-  return serveHttpOnListener(listener, signal, handler, onError, onListen);
+  return serveHttpOnListener(
+    listener,
+    signal,
+    handler,
+    onError,
+    onListen,
+    options.compressionDictionaries,
+  );
 }
 
 /**
  * Serve HTTP/1.1 and/or HTTP/2 on an arbitrary listener.
  */
-function serveHttpOnListener(listener, signal, handler, onError, onListen) {
+function serveHttpOnListener(
+  listener,
+  signal,
+  handler,
+  onError,
+  onListen,
+  compressionDictionaries = undefined,
+) {
   const context = new CallbackContext(
     signal,
     op_http_serve(listener[internalRidSymbol]),
     listener,
   );
+  if (compressionDictionaries !== undefined) {
+    for (let i = 0; i < compressionDictionaries.length; i++) {
+      op_http_serve_add_compression_dictionary(
+        context.serverRid,
+        compressionDictionaries[i],
+      );
+    }
+  }
   const callback = mapToCallback(context, handler, onError);
 
   onListen(context.scheme);
@@ -799,11 +909,28 @@ function serveHttpOn(context, addr, callback) {
   return {
     addr,
     finished,
-    async shutdown() {
+    stats() {
+      if (context.closed) {
+        return { connections: 0, requests: 0 };
+      }
+      const { 0: connections, 1: requests } = op_http_serve_stats(
+        context.serverRid,
+      );
+      return { connections, requests };
+    },
+    async shutdown(options = undefined) {
+      let timeout = options?.timeout;
+      if (timeout !== undefined) {
+        if (typeof timeout !== "number" || !NumberIsFinite(timeout)) {
+          throw new TypeError("The timeout must be a finite number.");
+        }
+        timeout = timeout > 0 ? MathCeil(timeout) : 0;
+      }
       try {
         if (!context.closing && !context.closed) {
-          // Shut this HTTP server down gracefully
-          context.closing = op_http_close(context.serverRid, true);
+          // Shut this HTTP server down gracefully, and forcefully once the
+          // timeout passes
+          context.closing = op_http_close(context.serverRid, true, timeout);
         }
 
         await context.closing;
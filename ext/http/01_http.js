@@ -52,6 +52,7 @@ import {
   _role,
   _server,
   _serverHandleIdleTimeout,
+  _upgradeOptions,
   SERVER,
   WebSocket,
 } from "ext:deno_websocket/01_websocket.js";
@@ -361,6 +362,7 @@ function createRespondWith(
       if (ws) {
         const wsRid = await op_http_upgrade_websocket(
           readStreamRid,
+          ws[_upgradeOptions],
         );
         ws[_rid] = wsRid;
         ws[_protocol] = resp.headers.get("sec-websocket-protocol");
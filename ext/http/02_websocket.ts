@@ -1,6 +1,9 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 import { internals, primordials } from "ext:core/mod.js";
-import { op_http_websocket_accept_header } from "ext:core/ops";
+import {
+  op_http_websocket_accept_header,
+  op_ws_server_negotiate_deflate,
+} from "ext:core/ops";
 const {
   ArrayPrototypeIncludes,
   ArrayPrototypeMap,
@@ -20,6 +23,7 @@ import {
 import { setEventTargetData } from "ext:deno_web/02_event.js";
 import {
   _eventLoop,
+  _extensions,
   _idleTimeoutDuration,
   _idleTimeoutTimeout,
   _protocol,
@@ -28,8 +32,10 @@ import {
   _role,
   _server,
   _serverHandleIdleTimeout,
+  _upgradeOptions,
   createWebSocketBranded,
   WebSocket,
+  webSocketExtensionOptions,
 } from "ext:deno_websocket/01_websocket.js";
 
 const _ws = Symbol("[[associated_ws]]");
@@ -38,6 +44,11 @@ const websocketCvf = buildCaseInsensitiveCommaValueFinder("websocket");
 const upgradeCvf = buildCaseInsensitiveCommaValueFinder("upgrade");
 
 function upgradeWebSocket(request, options = { __proto__: null }) {
+  const extensionOptions = webSocketExtensionOptions(
+    options,
+    "Failed to execute 'Deno.upgradeWebSocket'",
+    true,
+  );
   const inner = toInnerRequest(request);
   const upgrade = request.headers.get("upgrade");
   const upgradeHasWebSocketOption = upgrade !== null &&
@@ -88,9 +99,31 @@ function upgradeWebSocket(request, options = { __proto__: null }) {
     }
   }
 
+  // NOTE: non standard extension. The extension is only used if the client
+  // offered it.
+  let extensions = null;
+  if (extensionOptions.deflate !== null) {
+    extensions = op_ws_server_negotiate_deflate(
+      request.headers.get("sec-websocket-extensions") ?? "",
+      extensionOptions.deflate,
+    );
+    if (extensions !== null) {
+      ArrayPrototypePush(r.headerList, [
+        "sec-websocket-extensions",
+        extensions,
+      ]);
+    }
+  }
+
   const socket = createWebSocketBranded(WebSocket);
   setEventTargetData(socket);
   socket[_server] = true;
+  socket[_extensions] = extensions ?? "";
+  socket[_upgradeOptions] = {
+    extensions,
+    maxFrameSize: extensionOptions.maxFrameSize,
+    maxMessageSize: extensionOptions.maxMessageSize,
+  };
   // Nginx timeout is 60s, so default to a lower number: https://github.com/denoland/deno/pull/23985
   socket[_idleTimeoutDuration] = options.idleTimeout ?? 30;
   socket[_idleTimeoutTimeout] = null;
@@ -34,6 +34,10 @@ pub enum Encoding {
   Brotli,
   /// The Zstd encoding.
   Zstd,
+  /// The Brotli encoding, using a shared dictionary.
+  DictionaryBrotli,
+  /// The Zstd encoding, using a shared dictionary.
+  DictionaryZstd,
   /// No encoding.
   Identity,
 }
@@ -46,6 +50,8 @@ impl Encoding {
       "deflate" => Ok(Some(Encoding::Deflate)),
       "br" => Ok(Some(Encoding::Brotli)),
       "zstd" => Ok(Some(Encoding::Zstd)),
+      "dcb" => Ok(Some(Encoding::DictionaryBrotli)),
+      "dcz" => Ok(Some(Encoding::DictionaryZstd)),
       "identity" => Ok(Some(Encoding::Identity)),
       "*" => Ok(None),
       _ => Err(EncodingError::UnknownEncoding),
@@ -234,4 +240,17 @@ mod tests {
     assert_eq!(encodings[0], (Some(Encoding::Zstd), 1.0));
     assert_eq!(encodings[1], (Some(Encoding::Brotli), 0.9));
   }
+
+  #[test]
+  fn list_dictionary_encodings() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+      ACCEPT_ENCODING,
+      HeaderValue::from_str("gzip, br, zstd, dcb, dcz").unwrap(),
+    );
+
+    let encodings = encodings(&headers).unwrap();
+    assert_eq!(encodings[3], (Some(Encoding::DictionaryBrotli), 1.0));
+    assert_eq!(encodings[4], (Some(Encoding::DictionaryZstd), 1.0));
+  }
 }
@@ -7,10 +7,12 @@ use crate::request_properties::HttpConnectionProperties;
 use crate::request_properties::HttpListenProperties;
 use crate::request_properties::HttpPropertyExtractor;
 use crate::response_body::Compression;
+use crate::response_body::CompressionDictionary;
 use crate::response_body::ResponseBytesInner;
 use crate::service::handle_request;
 use crate::service::http_general_trace;
 use crate::service::http_trace;
+use crate::service::ActiveConnection;
 use crate::service::HttpRecord;
 use crate::service::HttpRecordResponse;
 use crate::service::HttpRequestBodyAutocloser;
@@ -18,6 +20,8 @@ use crate::service::HttpServerState;
 use crate::service::SignallingRc;
 use crate::websocket_upgrade::WebSocketUpgrade;
 use crate::LocalExecutor;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
 use cache_control::CacheControl;
 use deno_core::error::AnyError;
 use deno_core::external;
@@ -44,6 +48,7 @@ use deno_core::ResourceId;
 use deno_net::ops_tls::TlsStream;
 use deno_net::raw::NetworkStream;
 use deno_websocket::ws_create_server_stream;
+use deno_websocket::WebSocketServerOptions;
 use hyper::body::Incoming;
 use hyper::header::HeaderMap;
 use hyper::header::ACCEPT_ENCODING;
@@ -62,6 +67,7 @@ use hyper::service::HttpService;
 use hyper::StatusCode;
 use hyper_util::rt::TokioIo;
 use once_cell::sync::Lazy;
+use serde::Serialize;
 use smallvec::SmallVec;
 use std::borrow::Cow;
 use std::cell::RefCell;
@@ -71,6 +77,7 @@ use std::io;
 use std::pin::Pin;
 use std::ptr::null;
 use std::rc::Rc;
+use std::time::Duration;
 
 use super::fly_accept_encoding;
 use fly_accept_encoding::Encoding;
@@ -90,13 +97,21 @@ static USE_WRITEV: Lazy<bool> = Lazy::new(|| {
   false
 });
 
-// NOTE(bartlomieju): currently we don't have any unstable HTTP features,
-// but let's keep this const here, because:
-//   a) we still need to support `--unstable-http` flag to not break user's CLI;
-//   b) we might add more unstable features in the future.
-#[allow(dead_code)]
 pub const UNSTABLE_FEATURE_NAME: &str = "http";
 
+fn check_unstable(state: &OpState, api_name: &str) {
+  // TODO(bartlomieju): replace with `state.feature_checker.check_or_exit`
+  // once we phase out `check_or_exit_with_legacy_fallback`
+  state
+    .feature_checker
+    .check_or_exit_with_legacy_fallback(UNSTABLE_FEATURE_NAME, api_name);
+}
+
+/// The request header in which clients advertise the hash of a shared
+/// compression dictionary they hold.
+static AVAILABLE_DICTIONARY: HeaderName =
+  HeaderName::from_static("available-dictionary");
+
 /// All HTTP/2 connections start with this byte string.
 ///
 /// In HTTP/2, each endpoint is required to send a connection preface as a final confirmation
@@ -230,6 +245,7 @@ pub async fn op_http_upgrade_websocket_next(
   state: Rc<RefCell<OpState>>,
   external: *const c_void,
   #[serde] headers: Vec<(ByteString, ByteString)>,
+  #[serde] options: Option<WebSocketServerOptions>,
 ) -> Result<ResourceId, AnyError> {
   let http =
     // SAFETY: external is deleted before calling this op.
@@ -253,7 +269,12 @@ pub async fn op_http_upgrade_websocket_next(
 
   // Stage 3: take the extracted raw network stream and upgrade it to a websocket, then return it
   let (stream, bytes) = extract_network_stream(upgraded);
-  ws_create_server_stream(&mut state.borrow_mut(), stream, bytes)
+  ws_create_server_stream(
+    &mut state.borrow_mut(),
+    stream,
+    bytes,
+    options.unwrap_or_default(),
+  )
 }
 
 #[op2(fast)]
@@ -339,6 +360,54 @@ where
   v8::Array::new_with_elements(scope, vec.as_slice())
 }
 
+/// The certificate that the client presented during the TLS handshake, for
+/// listeners that request client certificates.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientCertificateInfo {
+  /// PEM encoded, starting with the end-entity certificate.
+  chain: Vec<String>,
+  verified: bool,
+  error: Option<String>,
+  /// Base64 encoded, like `pinnedPublicKeys` of `Deno.connectTls()`.
+  spki_sha256: Option<String>,
+}
+
+#[op2]
+#[serde]
+pub fn op_http_get_request_client_certificate(
+  external: *const c_void,
+) -> Option<ClientCertificateInfo> {
+  let http =
+    // SAFETY: op is called with external.
+    unsafe { clone_external!(external, "op_http_get_request_client_certificate") };
+  let certificate = http.request_info().client_certificate.as_ref()?.get()?;
+  Some(ClientCertificateInfo {
+    chain: certificate
+      .chain
+      .iter()
+      .map(|cert| pem_certificate(cert))
+      .collect(),
+    verified: certificate.error.is_none(),
+    spki_sha256: certificate
+      .public_key_hash()
+      .map(|hash| BASE64_STANDARD.encode(hash)),
+    error: certificate.error,
+  })
+}
+
+fn pem_certificate(der: &[u8]) -> String {
+  let base64 = BASE64_STANDARD.encode(der);
+  let mut pem = String::from("-----BEGIN CERTIFICATE-----\n");
+  for line in base64.as_bytes().chunks(64) {
+    // base64 is ASCII
+    pem.push_str(std::str::from_utf8(line).unwrap());
+    pem.push('\n');
+  }
+  pem.push_str("-----END CERTIFICATE-----\n");
+  pem
+}
+
 #[op2]
 #[serde]
 pub fn op_http_get_request_header(
@@ -522,6 +591,7 @@ pub fn op_http_set_response_trailers(
 fn is_request_compressible(
   length: Option<usize>,
   headers: &HeaderMap,
+  find_dictionary: impl FnOnce(&[u8]) -> Option<Rc<CompressionDictionary>>,
 ) -> Compression {
   if let Some(length) = length {
     // By the time we add compression headers and Accept-Encoding, it probably doesn't make sense
@@ -535,11 +605,17 @@ fn is_request_compressible(
     return Compression::None;
   };
 
+  if let Some(compression) = dictionary_compression(headers, find_dictionary) {
+    return compression;
+  }
+
   match accept_encoding.to_str() {
-    // Firefox and Chrome send this -- no need to parse
+    // Firefox and Chrome send these -- no need to parse
     Ok("gzip, deflate, br") => return Compression::Brotli,
+    Ok("gzip, deflate, br, zstd") => return Compression::Zstd,
     Ok("gzip") => return Compression::GZip,
     Ok("br") => return Compression::Brotli,
+    Ok("zstd") => return Compression::Zstd,
     _ => (),
   }
 
@@ -549,7 +625,12 @@ fn is_request_compressible(
       matches!(
         r,
         Ok((
-          Some(Encoding::Identity | Encoding::Gzip | Encoding::Brotli),
+          Some(
+            Encoding::Identity
+              | Encoding::Gzip
+              | Encoding::Brotli
+              | Encoding::Zstd
+          ),
           _
         ))
       )
@@ -557,10 +638,38 @@ fn is_request_compressible(
   match fly_accept_encoding::preferred(accepted) {
     Ok(Some(fly_accept_encoding::Encoding::Gzip)) => Compression::GZip,
     Ok(Some(fly_accept_encoding::Encoding::Brotli)) => Compression::Brotli,
+    Ok(Some(fly_accept_encoding::Encoding::Zstd)) => Compression::Zstd,
     _ => Compression::None,
   }
 }
 
+/// If the client holds one of the server's compression dictionaries and
+/// accepts `dcz` or `dcb`, compress against it. A dictionary beats any other
+/// encoding, and `dcz` is preferred over `dcb`.
+fn dictionary_compression(
+  headers: &HeaderMap,
+  find_dictionary: impl FnOnce(&[u8]) -> Option<Rc<CompressionDictionary>>,
+) -> Option<Compression> {
+  // The hash is sent as a structured field byte sequence: `:<base64>:`
+  let hash = headers.get(&AVAILABLE_DICTIONARY)?.to_str().ok()?.trim();
+  let hash = hash.strip_prefix(':')?.strip_suffix(':')?;
+  let dictionary = find_dictionary(&BASE64_STANDARD.decode(hash).ok()?)?;
+
+  let mut accepts_brotli = false;
+  for encoding in fly_accept_encoding::encodings_iter_http_1(headers) {
+    match encoding {
+      Ok((Some(Encoding::DictionaryZstd), qval)) if qval > 0.0 => {
+        return Some(Compression::ZstdDictionary(dictionary));
+      }
+      Ok((Some(Encoding::DictionaryBrotli), qval)) if qval > 0.0 => {
+        accepts_brotli = true;
+      }
+      _ => {}
+    }
+  }
+  accepts_brotli.then_some(Compression::BrotliDictionary(dictionary))
+}
+
 fn is_response_compressible(headers: &HeaderMap) -> bool {
   if let Some(content_type) = headers.get(CONTENT_TYPE) {
     if !is_content_compressible(content_type) {
@@ -601,8 +710,17 @@ fn modify_compressibility_from_response(
   let encoding = match compression {
     Compression::Brotli => "br",
     Compression::GZip => "gzip",
+    Compression::Zstd => "zstd",
+    Compression::BrotliDictionary(..) => "dcb",
+    Compression::ZstdDictionary(..) => "dcz",
     _ => unreachable!(),
   };
+  if matches!(
+    compression,
+    Compression::BrotliDictionary(..) | Compression::ZstdDictionary(..)
+  ) {
+    ensure_vary_available_dictionary(headers);
+  }
   weaken_etag(headers);
   headers.remove(CONTENT_LENGTH);
   headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding));
@@ -641,6 +759,20 @@ fn ensure_vary_accept_encoding(hmap: &mut HeaderMap) {
   );
 }
 
+// Responses compressed against a dictionary may only be served from caches to
+// clients that advertise the same dictionary.
+fn ensure_vary_available_dictionary(hmap: &mut HeaderMap) {
+  // `ensure_vary_accept_encoding` has always set the header by this point
+  let Some(v) = hmap.get_mut(hyper::header::VARY) else {
+    return;
+  };
+  if let Ok(s) = v.to_str() {
+    if !s.to_lowercase().contains("available-dictionary") {
+      *v = format!("{s}, Available-Dictionary").try_into().unwrap()
+    }
+  }
+}
+
 /// Sets the appropriate response body. Use `force_instantiate_body` if you need
 /// to ensure that the response is cleaned up correctly (eg: for resources).
 fn set_response(
@@ -654,7 +786,9 @@ fn set_response(
   // do all of this work to send the response.
   if !http.cancelled() {
     let compression =
-      is_request_compressible(length, &http.request_parts().headers);
+      is_request_compressible(length, &http.request_parts().headers, |hash| {
+        http.compression_dictionary(hash)
+      });
     let mut response_headers =
       std::cell::RefMut::map(http.response_parts(), |this| &mut this.headers);
     let compression =
@@ -809,7 +943,11 @@ async fn serve_http2_autodetect(
   cancel: Rc<CancelHandle>,
 ) -> Result<(), AnyError> {
   let prefix = NetworkStreamPrefixCheck::new(io, HTTP2_PREFIX);
-  let (matches, io) = prefix.match_prefix().await?;
+  let Ok(prefix) = prefix.match_prefix().or_cancel(cancel.clone()).await else {
+    // The server is shutting down, and this connection is still idle
+    return Ok(());
+  };
+  let (matches, io) = prefix?;
   if matches {
     serve_http2_unconditional(io, svc, cancel)
       .await
@@ -833,12 +971,24 @@ fn serve_https(
     listen_cancel_handle,
   } = lifetime;
 
+  let connection = ActiveConnection::new(server_state);
   let svc = service_fn(move |req: Request| {
-    handle_request(req, request_info.clone(), server_state.clone(), tx.clone())
+    handle_request(
+      req,
+      request_info.clone(),
+      connection.server_state(),
+      tx.clone(),
+    )
   });
   spawn(
     async {
-      let handshake = io.handshake().await?;
+      let Ok(handshake) =
+        io.handshake().or_cancel(listen_cancel_handle.clone()).await
+      else {
+        // The server is shutting down before the handshake completed
+        return Ok(());
+      };
+      let handshake = handshake?;
       // If the client specifically negotiates a protocol, we will use it. If not, we'll auto-detect
       // based on the prefix bytes
       let handshake = handshake.alpn;
@@ -870,8 +1020,14 @@ fn serve_http(
     listen_cancel_handle,
   } = lifetime;
 
+  let connection = ActiveConnection::new(server_state);
   let svc = service_fn(move |req: Request| {
-    handle_request(req, request_info.clone(), server_state.clone(), tx.clone())
+    handle_request(
+      req,
+      request_info.clone(),
+      connection.server_state(),
+      tx.clone(),
+    )
   });
   spawn(
     serve_http2_autodetect(io, svc, listen_cancel_handle)
@@ -1056,6 +1212,39 @@ where
   ))
 }
 
+/// Registers a shared dictionary that responses of the server may be compressed
+/// against, when clients advertise it in `Available-Dictionary`.
+#[op2(fast)]
+pub fn op_http_serve_add_compression_dictionary(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[buffer] dictionary: &[u8],
+) -> Result<(), AnyError> {
+  check_unstable(state, "Deno.serve.compressionDictionaries");
+  let join_handle = state.resource_table.get::<HttpJoinHandle>(rid)?;
+  let dictionary = CompressionDictionary::new(dictionary.to_vec());
+  join_handle
+    .server_state
+    .borrow_mut()
+    .compression_dictionaries
+    .push(Rc::new(dictionary));
+  Ok(())
+}
+
+/// Returns the number of open connections and of requests in flight, which a
+/// graceful shutdown waits on.
+#[op2]
+#[serde]
+pub fn op_http_serve_stats(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<(usize, usize), AnyError> {
+  check_unstable(state, "Deno.HttpServer.stats");
+  let join_handle = state.resource_table.get::<HttpJoinHandle>(rid)?;
+  let server_state = &join_handle.server_state;
+  Ok((server_state.connections(), server_state.requests()))
+}
+
 /// Synchronous, non-blocking call to see if there are any further HTTP requests. If anything
 /// goes wrong in this method we return null and let the async handler pick up the real error.
 #[op2(fast)]
@@ -1159,17 +1348,43 @@ pub async fn op_http_close(
   state: Rc<RefCell<OpState>>,
   #[smi] rid: ResourceId,
   graceful: bool,
+  #[serde] timeout: Option<u64>,
 ) -> Result<(), AnyError> {
+  if timeout.is_some() {
+    check_unstable(&state.borrow(), "Deno.HttpServer.shutdown.timeout");
+  }
+  // The resource stays in the table while draining, so that the server can
+  // still report its connections and requests, or be closed forcefully.
   let join_handle = state
     .borrow_mut()
     .resource_table
-    .take::<HttpJoinHandle>(rid)?;
+    .get::<HttpJoinHandle>(rid)?;
 
   if graceful {
     http_general_trace!("graceful shutdown");
-    // In a graceful shutdown, we close the listener and allow all the remaining connections to drain
+    // In a graceful shutdown, we close the listener and allow all the remaining connections to drain.
+    // Idle connections are closed right away, and busy ones once their requests finish: HTTP/1.1
+    // responses are sent with `Connection: close`, and HTTP/2 connections receive a GOAWAY.
     join_handle.listen_cancel_handle().cancel();
-    poll_fn(|cx| join_handle.server_state.poll_complete(cx)).await;
+    let drain = poll_fn(|cx| join_handle.server_state.poll_complete(cx));
+    let drained = match timeout {
+      Some(timeout) => {
+        tokio::time::timeout(Duration::from_millis(timeout), drain)
+          .await
+          .is_ok()
+      }
+      None => {
+        drain.await;
+        true
+      }
+    };
+    if !drained {
+      http_general_trace!("graceful shutdown timed out");
+      // Past the deadline, we close everything that's left
+      join_handle.connection_cancel_handle().cancel();
+      // Give streaming responses a tick to close
+      tokio::task::yield_now().await;
+    }
   } else {
     http_general_trace!("forceful shutdown");
     // In a forceful shutdown, we close everything
@@ -1179,6 +1394,12 @@ pub async fn op_http_close(
     tokio::task::yield_now().await;
   }
 
+  // The server may have been closed while draining
+  _ = state
+    .borrow_mut()
+    .resource_table
+    .take::<HttpJoinHandle>(rid);
+
   http_general_trace!("awaiting shutdown");
 
   let mut join_handle = RcRef::map(&join_handle, |this| &this.join_handle)
@@ -39,6 +39,7 @@ use deno_core::ResourceId;
 use deno_core::StringOrBuffer;
 use deno_net::raw::NetworkStream;
 use deno_websocket::ws_create_server_stream;
+use deno_websocket::WebSocketServerOptions;
 use flate2::write::GzEncoder;
 use flate2::Compression;
 use hyper_util::rt::TokioIo;
@@ -109,12 +110,15 @@ deno_core::extension!(
     op_http_write_resource,
     op_http_write,
     http_next::op_http_close_after_finish,
+    http_next::op_http_get_request_client_certificate,
     http_next::op_http_get_request_header,
     http_next::op_http_get_request_headers,
     http_next::op_http_get_request_method_and_url<HTTP>,
     http_next::op_http_read_request_body,
     http_next::op_http_serve_on<HTTP>,
     http_next::op_http_serve<HTTP>,
+    http_next::op_http_serve_add_compression_dictionary,
+    http_next::op_http_serve_stats,
     http_next::op_http_set_promise_complete,
     http_next::op_http_set_response_body_bytes,
     http_next::op_http_set_response_body_resource,
@@ -1037,6 +1041,7 @@ fn op_http_websocket_accept_header(
 async fn op_http_upgrade_websocket(
   state: Rc<RefCell<OpState>>,
   #[smi] rid: ResourceId,
+  #[serde] options: Option<WebSocketServerOptions>,
 ) -> Result<ResourceId, AnyError> {
   let stream = state
     .borrow_mut()
@@ -1053,8 +1058,12 @@ async fn op_http_upgrade_websocket(
 
   let (transport, bytes) =
     extract_network_stream(hyper_v014::upgrade::on(request).await?);
-  let ws_rid =
-    ws_create_server_stream(&mut state.borrow_mut(), transport, bytes)?;
+  let ws_rid = ws_create_server_stream(
+    &mut state.borrow_mut(),
+    transport,
+    bytes,
+    options.unwrap_or_default(),
+  )?;
   Ok(ws_rid)
 }
 
@@ -8,6 +8,7 @@ use deno_net::raw::NetworkStream;
 use deno_net::raw::NetworkStreamAddress;
 use deno_net::raw::NetworkStreamListener;
 use deno_net::raw::NetworkStreamType;
+use deno_tls::ClientCertificate;
 use hyper::header::HOST;
 use hyper::HeaderMap;
 use hyper::Uri;
@@ -16,6 +17,7 @@ use std::net::Ipv4Addr;
 use std::net::SocketAddr;
 use std::net::SocketAddrV4;
 use std::rc::Rc;
+use std::sync::Arc;
 
 // TODO(mmastrac): I don't like that we have to clone this, but it's one-time setup
 #[derive(Clone)]
@@ -32,6 +34,9 @@ pub struct HttpConnectionProperties {
   pub peer_port: Option<u16>,
   pub local_port: Option<u16>,
   pub stream_type: NetworkStreamType,
+  /// The certificate that the client presented, for TLS listeners that
+  /// request one.
+  pub client_certificate: Option<Arc<ClientCertificate>>,
 }
 
 pub struct HttpRequestProperties {
@@ -97,7 +102,8 @@ pub struct DefaultHttpPropertyExtractor {}
 impl HttpPropertyExtractor for DefaultHttpPropertyExtractor {
   type Listener = NetworkStreamListener;
 
-  type Connection = NetworkStream;
+  /// The stream, and the certificate that its client presents.
+  type Connection = (NetworkStream, Option<Arc<ClientCertificate>>);
 
   fn get_listener_for_rid(
     state: &mut OpState,
@@ -112,18 +118,20 @@ impl HttpPropertyExtractor for DefaultHttpPropertyExtractor {
   fn get_connection_for_rid(
     state: &mut OpState,
     stream_rid: ResourceId,
-  ) -> Result<NetworkStream, AnyError> {
-    take_network_stream_resource(&mut state.resource_table, stream_rid)
+  ) -> Result<Self::Connection, AnyError> {
+    let stream =
+      take_network_stream_resource(&mut state.resource_table, stream_rid)?;
+    Ok((stream, None))
   }
 
   async fn accept_connection_from_listener(
     listener: &NetworkStreamListener,
-  ) -> Result<NetworkStream, AnyError> {
+  ) -> Result<Self::Connection, AnyError> {
     listener
-      .accept()
+      .accept_with_client_certificate()
       .await
       .map_err(Into::into)
-      .map(|(stm, _)| stm)
+      .map(|(stm, _, certificate)| (stm, certificate))
   }
 
   fn listen_properties_from_listener(
@@ -135,7 +143,7 @@ impl HttpPropertyExtractor for DefaultHttpPropertyExtractor {
   }
 
   fn listen_properties_from_connection(
-    connection: &Self::Connection,
+    (connection, _): &Self::Connection,
   ) -> Result<HttpListenProperties, std::io::Error> {
     let stream_type = connection.stream();
     let local_address = connection.local_address()?;
@@ -143,14 +151,14 @@ impl HttpPropertyExtractor for DefaultHttpPropertyExtractor {
   }
 
   fn to_network_stream_from_connection(
-    connection: Self::Connection,
+    (connection, _): Self::Connection,
   ) -> NetworkStream {
     connection
   }
 
   fn connection_properties(
     listen_properties: &HttpListenProperties,
-    connection: &NetworkStream,
+    (connection, client_certificate): &Self::Connection,
   ) -> HttpConnectionProperties {
     // We always want some sort of peer address. If we can't get one, just make up one.
     let peer_address = connection.peer_address().unwrap_or_else(|_| {
@@ -177,6 +185,7 @@ impl HttpPropertyExtractor for DefaultHttpPropertyExtractor {
       peer_port,
       local_port,
       stream_type,
+      client_certificate: client_certificate.clone(),
     }
   }
 
@@ -20,6 +20,10 @@ use hyper::body::Frame;
 use hyper::body::SizeHint;
 use pin_project::pin_project;
 
+/// Zstandard's own default level, which is fast enough for on-the-fly
+/// compression.
+const ZSTD_LEVEL: i32 = 3;
+
 /// Simplification for nested types we use for our streams. We provide a way to convert from
 /// this type into Hyper's body [`Frame`].
 pub enum ResponseStreamResult {
@@ -61,6 +65,47 @@ pub enum Compression {
   None,
   GZip,
   Brotli,
+  Zstd,
+  /// Brotli, compressed against a shared dictionary (`dcb`).
+  BrotliDictionary(Rc<CompressionDictionary>),
+  /// Zstandard, compressed against a shared dictionary (`dcz`).
+  ZstdDictionary(Rc<CompressionDictionary>),
+}
+
+/// A shared dictionary that responses may be compressed against. Clients
+/// advertise the dictionaries they hold by SHA-256 hash in the
+/// `Available-Dictionary` request header.
+#[derive(PartialEq, Eq)]
+pub struct CompressionDictionary {
+  pub hash: [u8; 32],
+  pub bytes: Vec<u8>,
+}
+
+impl CompressionDictionary {
+  pub fn new(bytes: Vec<u8>) -> Self {
+    let digest = ring::digest::digest(&ring::digest::SHA256, &bytes);
+    let mut hash = [0; 32];
+    hash.copy_from_slice(digest.as_ref());
+    Self { hash, bytes }
+  }
+
+  /// The header that starts a `dcb` response: a magic number followed by the
+  /// hash of the dictionary.
+  fn dcb_header(&self) -> Vec<u8> {
+    let mut header = Vec::with_capacity(4 + 32);
+    header.extend([0xff, 0x44, 0x43, 0x42]);
+    header.extend(self.hash);
+    header
+  }
+
+  /// The header that starts a `dcz` response: a zstd skippable frame holding
+  /// the hash of the dictionary.
+  fn dcz_header(&self) -> Vec<u8> {
+    let mut header = Vec::with_capacity(8 + 32);
+    header.extend([0x5e, 0x2a, 0x4d, 0x18, 0x20, 0x00, 0x00, 0x00]);
+    header.extend(self.hash);
+    header
+  }
 }
 
 pub enum ResponseStream {
@@ -95,6 +140,8 @@ pub enum ResponseBytesInner {
   GZipStream(Box<GZipResponseStream>),
   /// A Brotli stream.
   BrotliStream(Box<BrotliResponseStream>),
+  /// A Zstandard stream.
+  ZstdStream(Box<ZstdResponseStream>),
 }
 
 impl std::fmt::Debug for ResponseBytesInner {
@@ -106,6 +153,7 @@ impl std::fmt::Debug for ResponseBytesInner {
       Self::UncompressedStream(..) => f.write_str("Uncompressed"),
       Self::GZipStream(..) => f.write_str("GZip"),
       Self::BrotliStream(..) => f.write_str("Brotli"),
+      Self::ZstdStream(..) => f.write_str("Zstd"),
     }
   }
 }
@@ -116,6 +164,7 @@ impl ResponseBytesInner {
       Self::Done | Self::Empty | Self::Bytes(..) => {}
       Self::BrotliStream(stm) => stm.abort(),
       Self::GZipStream(stm) => stm.abort(),
+      Self::ZstdStream(stm) => stm.abort(),
       Self::UncompressedStream(stm) => stm.abort(),
     }
   }
@@ -128,6 +177,7 @@ impl ResponseBytesInner {
       Self::UncompressedStream(res) => res.size_hint(),
       Self::GZipStream(..) => SizeHint::default(),
       Self::BrotliStream(..) => SizeHint::default(),
+      Self::ZstdStream(..) => SizeHint::default(),
     }
   }
 
@@ -139,6 +189,15 @@ impl ResponseBytesInner {
       Compression::Brotli => {
         Self::BrotliStream(Box::new(BrotliResponseStream::new(stream)))
       }
+      Compression::BrotliDictionary(dictionary) => Self::BrotliStream(
+        Box::new(BrotliResponseStream::with_dictionary(stream, &dictionary)),
+      ),
+      Compression::Zstd => {
+        Self::ZstdStream(Box::new(ZstdResponseStream::new(stream, None)))
+      }
+      Compression::ZstdDictionary(dictionary) => Self::ZstdStream(Box::new(
+        ZstdResponseStream::new(stream, Some(&dictionary)),
+      )),
       _ => Self::UncompressedStream(stream),
     }
   }
@@ -174,6 +233,15 @@ impl ResponseBytesInner {
         writer.flush().unwrap();
         Self::Bytes(BufView::from(writer.into_inner()))
       }
+      Compression::BrotliDictionary(dictionary) => {
+        Self::Bytes(BufView::from(brotli_compress(&dictionary, &buf)))
+      }
+      Compression::Zstd => {
+        Self::Bytes(BufView::from(zstd_compress(None, &buf)))
+      }
+      Compression::ZstdDictionary(dictionary) => {
+        Self::Bytes(BufView::from(zstd_compress(Some(&dictionary), &buf)))
+      }
       _ => Self::Bytes(buf),
     }
   }
@@ -193,6 +261,15 @@ impl ResponseBytesInner {
         writer.flush().unwrap();
         Self::Bytes(BufView::from(writer.into_inner()))
       }
+      Compression::BrotliDictionary(dictionary) => {
+        Self::Bytes(BufView::from(brotli_compress(&dictionary, &vec)))
+      }
+      Compression::Zstd => {
+        Self::Bytes(BufView::from(zstd_compress(None, &vec)))
+      }
+      Compression::ZstdDictionary(dictionary) => {
+        Self::Bytes(BufView::from(zstd_compress(Some(&dictionary), &vec)))
+      }
       _ => Self::Bytes(BufView::from(vec)),
     }
   }
@@ -456,25 +533,92 @@ enum BrotliState {
 pub struct BrotliResponseStream {
   state: BrotliState,
   stm: BrotliEncoderStateStruct<StandardAlloc>,
+  /// The `dcb` header, sent ahead of the first compressed bytes.
+  header: Option<Vec<u8>>,
   #[pin]
   underlying: ResponseStream,
 }
 
+fn brotli_encoder(
+  dictionary: Option<&CompressionDictionary>,
+) -> BrotliEncoderStateStruct<StandardAlloc> {
+  let mut stm = BrotliEncoderStateStruct::new(StandardAlloc::default());
+  // Quality level 6 is based on google's nginx default value for on-the-fly compression
+  // https://github.com/google/ngx_brotli#brotli_comp_level
+  // lgwin 22 is equivalent to brotli window size of (2**22)-16 bytes (~4MB)
+  stm.set_parameter(BrotliEncoderParameter::BROTLI_PARAM_QUALITY, 6);
+  stm.set_parameter(BrotliEncoderParameter::BROTLI_PARAM_LGWIN, 22);
+  if let Some(dictionary) = dictionary {
+    stm.set_custom_dictionary(dictionary.bytes.len(), &dictionary.bytes);
+  }
+  stm
+}
+
+/// Compresses a buffer in one go against a shared dictionary, producing a
+/// `dcb` response body.
+fn brotli_compress(dictionary: &CompressionDictionary, buf: &[u8]) -> Vec<u8> {
+  let mut stm = brotli_encoder(Some(dictionary));
+  let mut output = dictionary.dcb_header();
+  let mut output_offset = output.len();
+  output.resize(output_offset + max_compressed_size(buf.len()), 0);
+  let mut available_in = buf.len();
+  let mut input_offset = 0;
+  while !stm.is_finished() {
+    if output_offset == output.len() {
+      output.resize(output.len() * 2, 0);
+    }
+    let ok = stm.compress_stream(
+      BrotliEncoderOperation::BROTLI_OPERATION_FINISH,
+      &mut available_in,
+      buf,
+      &mut input_offset,
+      &mut (output.len() - output_offset),
+      &mut output,
+      &mut output_offset,
+      &mut None,
+      &mut |_, _, _, _| (),
+    );
+    if !ok {
+      break;
+    }
+  }
+  output.truncate(output_offset);
+  output
+}
+
 impl BrotliResponseStream {
   pub fn new(underlying: ResponseStream) -> Self {
-    let mut stm = BrotliEncoderStateStruct::new(StandardAlloc::default());
-    // Quality level 6 is based on google's nginx default value for on-the-fly compression
-    // https://github.com/google/ngx_brotli#brotli_comp_level
-    // lgwin 22 is equivalent to brotli window size of (2**22)-16 bytes (~4MB)
-    stm.set_parameter(BrotliEncoderParameter::BROTLI_PARAM_QUALITY, 6);
-    stm.set_parameter(BrotliEncoderParameter::BROTLI_PARAM_LGWIN, 22);
     Self {
-      stm,
+      stm: brotli_encoder(None),
+      state: BrotliState::Streaming,
+      header: None,
+      underlying,
+    }
+  }
+
+  pub fn with_dictionary(
+    underlying: ResponseStream,
+    dictionary: &CompressionDictionary,
+  ) -> Self {
+    Self {
+      stm: brotli_encoder(Some(dictionary)),
       state: BrotliState::Streaming,
+      header: Some(dictionary.dcb_header()),
       underlying,
     }
   }
 
+  /// Prefixes the first compressed bytes with the pending header, if any.
+  fn prefix_header(&mut self, output: Vec<u8>) -> Vec<u8> {
+    match self.header.take() {
+      Some(mut header) => {
+        header.extend(output);
+        header
+      }
+      None => output,
+    }
+  }
+
   pub fn abort(self) {
     self.underlying.abort()
   }
@@ -532,6 +676,7 @@ impl PollFrame for BrotliResponseStream {
         );
 
         output_buffer.truncate(output_offset);
+        let output_buffer = this.prefix_header(output_buffer);
         ResponseStreamResult::NonEmptyBuf(BufView::from(output_buffer))
       }
       ResponseStreamResult::EndOfStream => {
@@ -550,12 +695,13 @@ impl PollFrame for BrotliResponseStream {
           &mut |_, _, _, _| (),
         );
 
-        if output_offset == 0 {
+        output_buffer.truncate(output_offset);
+        let output_buffer = this.prefix_header(output_buffer);
+        if output_buffer.is_empty() {
           this.state = BrotliState::EndOfStream;
           ResponseStreamResult::EndOfStream
         } else {
           this.state = BrotliState::Flushing;
-          output_buffer.truncate(output_offset);
           ResponseStreamResult::NonEmptyBuf(BufView::from(output_buffer))
         }
       }
@@ -570,6 +716,111 @@ impl PollFrame for BrotliResponseStream {
   }
 }
 
+type ZstdEncoder = zstd::stream::write::Encoder<'static, Vec<u8>>;
+
+/// Creates an encoder writing into a buffer that, for `dcz`, already holds
+/// the header.
+fn zstd_encoder(dictionary: Option<&CompressionDictionary>) -> ZstdEncoder {
+  match dictionary {
+    Some(dictionary) => ZstdEncoder::with_dictionary(
+      dictionary.dcz_header(),
+      ZSTD_LEVEL,
+      &dictionary.bytes,
+    ),
+    None => ZstdEncoder::new(Vec::new(), ZSTD_LEVEL),
+  }
+  .unwrap()
+}
+
+fn zstd_compress(
+  dictionary: Option<&CompressionDictionary>,
+  buf: &[u8],
+) -> Vec<u8> {
+  let mut writer = zstd_encoder(dictionary);
+  writer.write_all(buf).unwrap();
+  writer.finish().unwrap()
+}
+
+#[derive(Copy, Clone, Debug)]
+enum ZstdState {
+  Streaming,
+  EndOfStream,
+}
+
+#[pin_project]
+pub struct ZstdResponseStream {
+  state: ZstdState,
+  stm: ZstdEncoder,
+  #[pin]
+  underlying: ResponseStream,
+}
+
+impl ZstdResponseStream {
+  pub fn new(
+    underlying: ResponseStream,
+    dictionary: Option<&CompressionDictionary>,
+  ) -> Self {
+    Self {
+      state: ZstdState::Streaming,
+      stm: zstd_encoder(dictionary),
+      underlying,
+    }
+  }
+
+  pub fn abort(self) {
+    self.underlying.abort()
+  }
+}
+
+impl PollFrame for ZstdResponseStream {
+  fn poll_frame(
+    self: Pin<&mut Self>,
+    cx: &mut std::task::Context<'_>,
+  ) -> std::task::Poll<ResponseStreamResult> {
+    let this = self.get_mut();
+    let frame = match this.state {
+      ZstdState::Streaming => {
+        ready!(Pin::new(&mut this.underlying).poll_frame(cx))
+      }
+      ZstdState::EndOfStream => {
+        return std::task::Poll::Ready(ResponseStreamResult::EndOfStream);
+      }
+    };
+
+    let res = match frame {
+      // Flush after every chunk so that streamed responses are not held back
+      ResponseStreamResult::NonEmptyBuf(buf) => {
+        this.stm.write_all(&buf).and_then(|_| this.stm.flush())
+      }
+      ResponseStreamResult::EndOfStream => {
+        this.state = ZstdState::EndOfStream;
+        this.stm.do_finish()
+      }
+      _ => return std::task::Poll::Ready(frame),
+    };
+
+    let res = match res {
+      Err(err) => ResponseStreamResult::Error(err.into()),
+      Ok(()) => {
+        let output = std::mem::take(this.stm.get_mut());
+        if !output.is_empty() {
+          ResponseStreamResult::NonEmptyBuf(BufView::from(output))
+        } else if matches!(this.state, ZstdState::EndOfStream) {
+          ResponseStreamResult::EndOfStream
+        } else {
+          ResponseStreamResult::NoData
+        }
+      }
+    };
+
+    std::task::Poll::Ready(res)
+  }
+
+  fn size_hint(&self) -> SizeHint {
+    SizeHint::default()
+  }
+}
+
 #[allow(clippy::print_stderr)]
 #[cfg(test)]
 mod tests {
@@ -735,22 +986,165 @@ mod tests {
     handle.await.unwrap();
   }
 
+  /// Feeds the chunks through a compression stream, returning the
+  /// uncompressed and the compressed data.
+  async fn compress_stream<S: PollFrame>(
+    i: impl Iterator<Item = Vec<u8>> + Send + 'static,
+    new: impl FnOnce(ResponseStream) -> S,
+  ) -> (Vec<u8>, Vec<u8>) {
+    let v = i.collect::<Vec<_>>();
+    let mut expected: Vec<u8> = vec![];
+    for v in &v {
+      expected.extend(v);
+    }
+    let (tx, rx) = tokio::sync::mpsc::channel(1);
+    let mut resp = new(ResponseStream::TestChannel(rx));
+    let handle = tokio::task::spawn(async move {
+      for chunk in v {
+        tx.send(chunk.into()).await.ok().unwrap();
+      }
+    });
+    // Limit how many times we'll loop
+    const LIMIT: usize = 1000;
+    let mut v: Vec<u8> = vec![];
+    for i in 0..=LIMIT {
+      assert_ne!(i, LIMIT);
+      let frame = poll_fn(|cx| Pin::new(&mut resp).poll_frame(cx)).await;
+      if matches!(frame, ResponseStreamResult::EndOfStream) {
+        break;
+      }
+      if matches!(frame, ResponseStreamResult::NoData) {
+        continue;
+      }
+      let ResponseStreamResult::NonEmptyBuf(buf) = frame else {
+        panic!("Unexpected stream type");
+      };
+      assert_ne!(buf.len(), 0);
+      v.extend(&*buf);
+    }
+    handle.await.unwrap();
+    (expected, v)
+  }
+
+  async fn test_zstd(i: impl Iterator<Item = Vec<u8>> + Send + 'static) {
+    let (expected, v) =
+      compress_stream(i, |stm| ZstdResponseStream::new(stm, None)).await;
+    assert_eq!(zstd::stream::decode_all(&*v).unwrap(), expected);
+  }
+
+  fn records(count: usize) -> Vec<u8> {
+    let mut v = vec![];
+    for i in 0..count {
+      v.extend(
+        format!(
+          r#"{{"id":{i},"name":"user{i}","email":"user{i}@example.com"}}"#
+        )
+        .as_bytes(),
+      );
+    }
+    v
+  }
+
+  fn dictionary() -> CompressionDictionary {
+    CompressionDictionary::new(records(100))
+  }
+
+  fn decode_dcz(dictionary: &CompressionDictionary, v: &[u8]) -> Vec<u8> {
+    let header = dictionary.dcz_header();
+    assert_eq!(&v[..header.len()], header);
+    let mut decoder = zstd::stream::read::Decoder::with_dictionary(
+      &v[header.len()..],
+      &dictionary.bytes,
+    )
+    .unwrap();
+    let mut v = vec![];
+    decoder.read_to_end(&mut v).unwrap();
+    v
+  }
+
+  fn decode_dcb(dictionary: &CompressionDictionary, v: &[u8]) -> Vec<u8> {
+    let header = dictionary.dcb_header();
+    assert_eq!(&v[..header.len()], header);
+    let mut decoder = brotli::Decompressor::new_with_custom_dict(
+      &v[header.len()..],
+      4096,
+      dictionary.bytes.clone().into(),
+    );
+    let mut v = vec![];
+    decoder.read_to_end(&mut v).unwrap();
+    v
+  }
+
   #[tokio::test]
   async fn test_simple() {
     test_brotli(vec![b"hello world".to_vec()].into_iter()).await;
     test_gzip(vec![b"hello world".to_vec()].into_iter()).await;
+    test_zstd(vec![b"hello world".to_vec()].into_iter()).await;
   }
 
   #[tokio::test]
   async fn test_empty() {
     test_brotli(vec![].into_iter()).await;
     test_gzip(vec![].into_iter()).await;
+    test_zstd(vec![].into_iter()).await;
   }
 
   #[tokio::test]
   async fn test_simple_zeros() {
     test_brotli(vec![vec![0; 0x10000]].into_iter()).await;
     test_gzip(vec![vec![0; 0x10000]].into_iter()).await;
+    test_zstd(vec![vec![0; 0x10000]].into_iter()).await;
+  }
+
+  #[tokio::test]
+  async fn test_dictionary_streams() {
+    let dictionary = dictionary();
+    let (expected, v) = compress_stream(chunk(records(1000)), |stm| {
+      ZstdResponseStream::new(stm, Some(&dictionary))
+    })
+    .await;
+    assert_eq!(decode_dcz(&dictionary, &v), expected);
+
+    let (expected, v) = compress_stream(chunk(records(1000)), |stm| {
+      BrotliResponseStream::with_dictionary(stm, &dictionary)
+    })
+    .await;
+    assert_eq!(decode_dcb(&dictionary, &v), expected);
+
+    // The header is sent even when there's no body
+    let (_, v) = compress_stream(vec![].into_iter(), |stm| {
+      ZstdResponseStream::new(stm, Some(&dictionary))
+    })
+    .await;
+    assert_eq!(decode_dcz(&dictionary, &v), b"");
+    let (_, v) = compress_stream(vec![].into_iter(), |stm| {
+      BrotliResponseStream::with_dictionary(stm, &dictionary)
+    })
+    .await;
+    assert_eq!(decode_dcb(&dictionary, &v), b"");
+  }
+
+  #[test]
+  fn test_dictionary_bytes() {
+    let dictionary = Rc::new(dictionary());
+    // A new version of the dictionary itself, as with versioned assets
+    let data = records(101);
+    let compressed = |compression| {
+      let ResponseBytesInner::Bytes(buf) =
+        ResponseBytesInner::from_vec(compression, data.clone())
+      else {
+        panic!("Unexpected response type");
+      };
+      buf.to_vec()
+    };
+
+    let zstd = compressed(Compression::Zstd);
+    assert_eq!(zstd::stream::decode_all(&*zstd).unwrap(), data);
+    let dcz = compressed(Compression::ZstdDictionary(dictionary.clone()));
+    assert_eq!(decode_dcz(&dictionary, &dcz), data);
+    let dcb = compressed(Compression::BrotliDictionary(dictionary.clone()));
+    assert_eq!(decode_dcb(&dictionary, &dcb), data);
+    assert!(dcz.len() < zstd.len() / 4);
   }
 
   macro_rules! test {
@@ -762,6 +1156,8 @@ mod tests {
           super::test_gzip(iter).await;
           let br_iter = super::chunk(super::$vec());
           super::test_brotli(br_iter).await;
+          let zstd_iter = super::chunk(super::$vec());
+          super::test_zstd(zstd_iter).await;
         }
 
         #[tokio::test]
@@ -770,6 +1166,8 @@ mod tests {
           super::test_gzip(iter).await;
           let br_iter = super::front_load(super::$vec());
           super::test_brotli(br_iter).await;
+          let zstd_iter = super::front_load(super::$vec());
+          super::test_zstd(zstd_iter).await;
         }
 
         #[tokio::test]
@@ -778,6 +1176,8 @@ mod tests {
           super::test_gzip(iter).await;
           let br_iter = super::front_load_but_one(super::$vec());
           super::test_brotli(br_iter).await;
+          let zstd_iter = super::front_load_but_one(super::$vec());
+          super::test_zstd(zstd_iter).await;
         }
 
         #[tokio::test]
@@ -786,6 +1186,8 @@ mod tests {
           super::test_gzip(iter).await;
           let br_iter = super::back_load(super::$vec());
           super::test_brotli(br_iter).await;
+          let zstd_iter = super::back_load(super::$vec());
+          super::test_zstd(zstd_iter).await;
         }
 
         #[tokio::test]
@@ -794,6 +1196,8 @@ mod tests {
           super::test_gzip(iter).await;
           let br_iter = super::random(super::$vec());
           super::test_brotli(br_iter).await;
+          let zstd_iter = super::random(super::$vec());
+          super::test_zstd(zstd_iter).await;
         }
       }
     };
@@ -1,5 +1,6 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 use crate::request_properties::HttpConnectionProperties;
+use crate::response_body::CompressionDictionary;
 use crate::response_body::ResponseBytesInner;
 use crate::response_body::ResponseStreamResult;
 use deno_core::error::AnyError;
@@ -73,6 +74,8 @@ pub(crate) use http_trace;
 
 pub(crate) struct HttpServerStateInner {
   pool: Vec<(Rc<HttpRecord>, HeaderMap)>,
+  /// Shared dictionaries that responses may be compressed against.
+  pub(crate) compression_dictionaries: Vec<Rc<CompressionDictionary>>,
 }
 
 /// A signalling version of `Rc` that allows one to poll for when all other references
@@ -130,24 +133,91 @@ impl<T> std::ops::Deref for SignallingRc<T> {
   }
 }
 
-pub(crate) struct HttpServerState(RefCell<HttpServerStateInner>);
+pub(crate) struct HttpServerState {
+  inner: RefCell<HttpServerStateInner>,
+  connections: Cell<usize>,
+  requests: Cell<usize>,
+}
 
 impl HttpServerState {
   pub fn new() -> SignallingRc<Self> {
-    SignallingRc::new(Self(RefCell::new(HttpServerStateInner {
-      pool: Vec::new(),
-    })))
+    SignallingRc::new(Self {
+      inner: RefCell::new(HttpServerStateInner {
+        pool: Vec::new(),
+        compression_dictionaries: Vec::new(),
+      }),
+      connections: Cell::new(0),
+      requests: Cell::new(0),
+    })
+  }
+
+  /// The number of open connections.
+  pub fn connections(&self) -> usize {
+    self.connections.get()
+  }
+
+  /// The number of requests that have been received and whose response has
+  /// not finished yet.
+  pub fn requests(&self) -> usize {
+    self.requests.get()
   }
 }
 
 impl std::ops::Deref for HttpServerState {
   type Target = RefCell<HttpServerStateInner>;
 
+  fn deref(&self) -> &Self::Target {
+    &self.inner
+  }
+}
+
+/// Counts a connection against its server for as long as it is open.
+pub(crate) struct ActiveConnection(SignallingRc<HttpServerState>);
+
+impl ActiveConnection {
+  pub fn new(server_state: SignallingRc<HttpServerState>) -> Self {
+    server_state
+      .connections
+      .set(server_state.connections.get() + 1);
+    Self(server_state)
+  }
+
+  pub fn server_state(&self) -> SignallingRc<HttpServerState> {
+    self.0.clone()
+  }
+}
+
+impl Drop for ActiveConnection {
+  fn drop(&mut self) {
+    self.0.connections.set(self.0.connections.get() - 1);
+  }
+}
+
+/// Counts a request against its server until its record is recycled or
+/// dropped.
+struct ActiveRequest(SignallingRc<HttpServerState>);
+
+impl ActiveRequest {
+  fn new(server_state: SignallingRc<HttpServerState>) -> Self {
+    server_state.requests.set(server_state.requests.get() + 1);
+    Self(server_state)
+  }
+}
+
+impl std::ops::Deref for ActiveRequest {
+  type Target = SignallingRc<HttpServerState>;
+
   fn deref(&self) -> &Self::Target {
     &self.0
   }
 }
 
+impl Drop for ActiveRequest {
+  fn drop(&mut self) {
+    self.0.requests.set(self.0.requests.get() - 1);
+  }
+}
+
 enum RequestBodyState {
   Incoming(Incoming),
   Resource(#[allow(dead_code)] HttpRequestBodyAutocloser),
@@ -207,7 +277,7 @@ pub(crate) async fn handle_request(
 }
 
 struct HttpRecordInner {
-  server_state: SignallingRc<HttpServerState>,
+  server_state: ActiveRequest,
   request_info: HttpConnectionProperties,
   request_parts: http::request::Parts,
   request_body: Option<RequestBodyState>,
@@ -262,7 +332,7 @@ impl HttpRecord {
         record
       };
     *record.0.borrow_mut() = Some(HttpRecordInner {
-      server_state,
+      server_state: ActiveRequest::new(server_state),
       request_info,
       request_parts,
       request_body,
@@ -459,6 +529,20 @@ impl HttpRecord {
     Ref::map(self.self_ref(), |inner| &inner.request_parts)
   }
 
+  /// Find the server's compression dictionary with the given SHA-256 hash.
+  pub fn compression_dictionary(
+    &self,
+    hash: &[u8],
+  ) -> Option<Rc<CompressionDictionary>> {
+    let inner = self.self_ref();
+    let server_state = inner.server_state.borrow();
+    server_state
+      .compression_dictionaries
+      .iter()
+      .find(|dictionary| dictionary.hash[..] == *hash)
+      .cloned()
+  }
+
   /// Resolves when response head is ready.
   fn response_ready(&self) -> impl Future<Output = ()> + '_ {
     struct HttpRecordReady<'a>(&'a HttpRecord);
@@ -550,6 +634,9 @@ impl Body for HttpRecordResponse {
         ResponseBytesInner::BrotliStream(stm) => {
           ready!(Pin::new(stm.as_mut()).poll_frame(cx))
         }
+        ResponseBytesInner::ZstdStream(stm) => {
+          ready!(Pin::new(stm.as_mut()).poll_frame(cx))
+        }
       };
       // This is where we retry the NoData response
       if matches!(res, ResponseStreamResult::NoData) {
@@ -649,6 +736,7 @@ mod tests {
       peer_port: None,
       local_port: None,
       stream_type: NetworkStreamType::Tcp,
+      client_certificate: None,
     };
     let svc = service_fn(move |req: hyper::Request<Incoming>| {
       handle_request(
@@ -689,4 +777,56 @@ mod tests {
     assert_eq!(server_state_check.strong_count(), 1);
     Ok(())
   }
+
+  #[tokio::test]
+  async fn test_active_connections_and_requests() -> Result<(), AnyError> {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+    let server_state = HttpServerState::new();
+    let server_state_check = server_state.clone();
+    let handler_server_state = server_state.clone();
+    let request_info = HttpConnectionProperties {
+      peer_address: "".into(),
+      peer_port: None,
+      local_port: None,
+      stream_type: NetworkStreamType::Tcp,
+      client_certificate: None,
+    };
+    let connection = ActiveConnection::new(server_state);
+    assert_eq!(server_state_check.connections(), 1);
+    assert_eq!(server_state_check.requests(), 0);
+    let svc = service_fn(move |req: hyper::Request<Incoming>| {
+      handle_request(
+        req,
+        request_info.clone(),
+        connection.server_state(),
+        tx.clone(),
+      )
+    });
+
+    let client_req = http::Request::builder().uri("/").body("".to_string())?;
+
+    tokio::try_join!(
+      async move {
+        let record = rx.recv().await.unwrap();
+        assert_eq!(handler_server_state.requests(), 1);
+        record.set_response_body(ResponseBytesInner::from_vec(
+          Compression::None,
+          b"hello world".to_vec(),
+        ));
+        record.complete();
+        Ok(())
+      },
+      async move {
+        serve_request(client_req, svc, |res| async {
+          use http_body_util::BodyExt;
+          res.collect().await?;
+          Ok(())
+        })
+        .await
+      },
+    )?;
+    assert_eq!(server_state_check.requests(), 0);
+    assert_eq!(server_state_check.connections(), 0);
+    Ok(())
+  }
 }
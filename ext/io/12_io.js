@@ -5,7 +5,12 @@
 // Thank you! We love Go! <3
 
 import { core, internals, primordials } from "ext:core/mod.js";
-import { op_set_raw } from "ext:core/ops";
+import {
+  op_set_raw,
+  op_stdin_info,
+  op_tty_get_mode,
+  op_tty_set_mode,
+} from "ext:core/ops";
 const {
   Uint8Array,
   ArrayPrototypePush,
@@ -159,6 +164,18 @@ class Stdin {
     op_set_raw(this.#rid, mode, cbreak);
   }
 
+  getMode() {
+    return op_tty_get_mode(this.#rid);
+  }
+
+  setMode(mode) {
+    op_tty_set_mode(this.#rid, mode);
+  }
+
+  info() {
+    return op_stdin_info(this.#rid);
+  }
+
   isTerminal() {
     return core.isTerminal(this.#rid);
   }
@@ -0,0 +1,341 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Allocating and deallocating the disk space of a range of a file, see
+//! [`AllocateMode`]. Linux uses `fallocate(2)`, macOS `F_PREALLOCATE` and
+//! `F_PUNCHHOLE`, and Windows the allocation size and sparse files.
+
+use std::fs::File as StdFile;
+use std::io;
+
+use crate::fs::AllocateMode;
+use crate::fs::FsError;
+use crate::fs::FsResult;
+
+pub fn allocate(
+  file: &StdFile,
+  offset: u64,
+  len: u64,
+  mode: AllocateMode,
+) -> FsResult<()> {
+  let end = offset.checked_add(len).ok_or_else(|| {
+    io::Error::new(io::ErrorKind::InvalidInput, "Range is too large")
+  })?;
+  if len == 0 {
+    return Ok(());
+  }
+  match mode {
+    AllocateMode::Zero => preallocate(file, offset, len, end),
+    AllocateMode::Sparse => {
+      if end > file.metadata()?.len() {
+        set_sparse(file)?;
+        file.set_len(end)?;
+      }
+      Ok(())
+    }
+    AllocateMode::PunchHole => punch_hole(file, offset, len),
+  }
+}
+
+#[cfg(any(
+  target_os = "android",
+  target_os = "freebsd",
+  target_os = "linux",
+  target_os = "macos"
+))]
+fn not_supported_or(err: io::Error) -> FsError {
+  if err.raw_os_error() == Some(libc::EOPNOTSUPP) {
+    return FsError::NotSupported;
+  }
+  err.into()
+}
+
+#[cfg(any(target_os = "android", target_os = "freebsd", target_os = "linux"))]
+fn preallocate(
+  file: &StdFile,
+  offset: u64,
+  len: u64,
+  _end: u64,
+) -> FsResult<()> {
+  use std::os::fd::AsRawFd;
+
+  // SAFETY: libc call, the fd is valid
+  let res = unsafe {
+    libc::posix_fallocate(
+      file.as_raw_fd(),
+      offset as libc::off_t,
+      len as libc::off_t,
+    )
+  };
+  match res {
+    0 => Ok(()),
+    // `posix_fallocate` returns the error rather than setting `errno`
+    errno => Err(not_supported_or(io::Error::from_raw_os_error(errno))),
+  }
+}
+
+#[cfg(target_os = "macos")]
+fn preallocate(
+  file: &StdFile,
+  _offset: u64,
+  _len: u64,
+  end: u64,
+) -> FsResult<()> {
+  use std::os::fd::AsRawFd;
+
+  let size = file.metadata()?.len();
+  if end <= size {
+    // the range is allocated already, unless the file is sparse
+    return Ok(());
+  }
+  let mut store = libc::fstore_t {
+    fst_flags: libc::F_ALLOCATECONTIG | libc::F_ALLOCATEALL,
+    fst_posmode: libc::F_PEOFPOSMODE,
+    fst_offset: 0,
+    fst_length: (end - size) as libc::off_t,
+    fst_bytesalloc: 0,
+  };
+  // SAFETY: libc call, the fd is valid and `store` lives long enough
+  let mut res =
+    unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PREALLOCATE, &store) };
+  if res == -1 {
+    // there may be enough space, just not in one piece
+    store.fst_flags = libc::F_ALLOCATEALL;
+    // SAFETY: see above
+    res = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PREALLOCATE, &store) };
+  }
+  if res == -1 {
+    return Err(not_supported_or(io::Error::last_os_error()));
+  }
+  // the space is allocated past the end of the file until it grows
+  file.set_len(end)?;
+  Ok(())
+}
+
+#[cfg(windows)]
+fn preallocate(
+  file: &StdFile,
+  _offset: u64,
+  _len: u64,
+  end: u64,
+) -> FsResult<()> {
+  use std::os::windows::io::AsRawHandle;
+  use windows_sys::Win32::Storage::FileSystem::FileAllocationInfo;
+  use windows_sys::Win32::Storage::FileSystem::SetFileInformationByHandle;
+  use windows_sys::Win32::Storage::FileSystem::FILE_ALLOCATION_INFO;
+
+  if end <= file.metadata()?.len() {
+    return Ok(());
+  }
+  let info = FILE_ALLOCATION_INFO {
+    AllocationSize: end as i64,
+  };
+  // SAFETY: winapi call, the handle is valid and `info` lives long enough
+  let ok = unsafe {
+    SetFileInformationByHandle(
+      file.as_raw_handle() as _,
+      FileAllocationInfo,
+      &info as *const _ as *const std::ffi::c_void,
+      std::mem::size_of::<FILE_ALLOCATION_INFO>() as u32,
+    )
+  };
+  if ok == 0 {
+    return Err(io::Error::last_os_error().into());
+  }
+  // the clusters past the valid data length read as zeros
+  file.set_len(end)?;
+  Ok(())
+}
+
+#[cfg(not(any(
+  target_os = "android",
+  target_os = "freebsd",
+  target_os = "linux",
+  target_os = "macos",
+  windows
+)))]
+fn preallocate(
+  _file: &StdFile,
+  _offset: u64,
+  _len: u64,
+  _end: u64,
+) -> FsResult<()> {
+  Err(FsError::NotSupported)
+}
+
+#[cfg(target_os = "linux")]
+fn punch_hole(file: &StdFile, offset: u64, len: u64) -> FsResult<()> {
+  use std::os::fd::AsRawFd;
+
+  // SAFETY: libc call, the fd is valid
+  let res = unsafe {
+    libc::fallocate(
+      file.as_raw_fd(),
+      libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE,
+      offset as libc::off_t,
+      len as libc::off_t,
+    )
+  };
+  if res != 0 {
+    return Err(not_supported_or(io::Error::last_os_error()));
+  }
+  Ok(())
+}
+
+/// `F_PUNCHHOLE` only takes whole blocks, so the partial blocks at either end
+/// of the range are overwritten with zeros instead.
+#[cfg(target_os = "macos")]
+fn punch_hole(file: &StdFile, offset: u64, len: u64) -> FsResult<()> {
+  use std::os::fd::AsRawFd;
+  use std::os::unix::fs::FileExt;
+  use std::os::unix::fs::MetadataExt;
+
+  let metadata = file.metadata()?;
+  // like Linux, the file doesn't grow
+  let end = (offset + len).min(metadata.len());
+  if offset >= end {
+    return Ok(());
+  }
+  let block_size = metadata.blksize().max(1);
+  let hole_start = offset.div_ceil(block_size) * block_size;
+  let hole_end = end / block_size * block_size;
+
+  let write_zeros = |from: u64, to: u64| -> io::Result<()> {
+    if from < to {
+      file.write_all_at(&vec![0; (to - from) as usize], from)?;
+    }
+    Ok(())
+  };
+  if hole_start >= hole_end {
+    write_zeros(offset, end)?;
+    return Ok(());
+  }
+  write_zeros(offset, hole_start)?;
+  write_zeros(hole_end, end)?;
+
+  let hole = libc::fpunchhole_t {
+    fp_flags: 0,
+    reserved: 0,
+    fp_offset: hole_start as libc::off_t,
+    fp_length: (hole_end - hole_start) as libc::off_t,
+  };
+  // SAFETY: libc call, the fd is valid and `hole` lives long enough
+  let res = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PUNCHHOLE, &hole) };
+  if res == -1 {
+    return Err(not_supported_or(io::Error::last_os_error()));
+  }
+  Ok(())
+}
+
+#[cfg(windows)]
+fn punch_hole(file: &StdFile, offset: u64, len: u64) -> FsResult<()> {
+  use std::os::windows::io::AsRawHandle;
+  use windows_sys::Win32::System::Ioctl::FILE_ZERO_DATA_INFORMATION;
+  use windows_sys::Win32::System::Ioctl::FSCTL_SET_ZERO_DATA;
+  use windows_sys::Win32::System::IO::DeviceIoControl;
+
+  set_sparse(file)?;
+  let info = FILE_ZERO_DATA_INFORMATION {
+    FileOffset: offset as i64,
+    BeyondFinalZero: (offset + len) as i64,
+  };
+  let mut returned = 0;
+  // SAFETY: winapi call, the handle is valid and `info` lives long enough
+  let ok = unsafe {
+    DeviceIoControl(
+      file.as_raw_handle() as _,
+      FSCTL_SET_ZERO_DATA,
+      &info as *const _ as *const std::ffi::c_void,
+      std::mem::size_of::<FILE_ZERO_DATA_INFORMATION>() as u32,
+      std::ptr::null_mut(),
+      0,
+      &mut returned,
+      std::ptr::null_mut(),
+    )
+  };
+  if ok == 0 {
+    return Err(io::Error::last_os_error().into());
+  }
+  Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn punch_hole(_file: &StdFile, _offset: u64, _len: u64) -> FsResult<()> {
+  Err(FsError::NotSupported)
+}
+
+/// Marks the file as sparse, so that the space which is never written, or
+/// which is zeroed, isn't allocated. Files are sparse by default elsewhere.
+#[cfg(windows)]
+fn set_sparse(file: &StdFile) -> FsResult<()> {
+  use std::os::windows::io::AsRawHandle;
+  use windows_sys::Win32::System::Ioctl::FSCTL_SET_SPARSE;
+  use windows_sys::Win32::System::IO::DeviceIoControl;
+
+  let mut returned = 0;
+  // SAFETY: winapi call, the handle is valid
+  let ok = unsafe {
+    DeviceIoControl(
+      file.as_raw_handle() as _,
+      FSCTL_SET_SPARSE,
+      std::ptr::null(),
+      0,
+      std::ptr::null_mut(),
+      0,
+      &mut returned,
+      std::ptr::null_mut(),
+    )
+  };
+  if ok == 0 {
+    // e.g. FAT32
+    return Err(FsError::NotSupported);
+  }
+  Ok(())
+}
+
+#[cfg(not(windows))]
+fn set_sparse(_file: &StdFile) -> FsResult<()> {
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::Read;
+  use std::io::Write;
+
+  #[test]
+  fn allocate_modes() {
+    let path = std::env::temp_dir()
+      .join(format!("deno_io_allocate_{}.bin", std::process::id()));
+    let mut file = StdFile::options()
+      .create(true)
+      .truncate(true)
+      .read(true)
+      .write(true)
+      .open(&path)
+      .unwrap();
+    file.write_all(&[1; 8192]).unwrap();
+
+    allocate(&file, 0, 16384, AllocateMode::Zero).unwrap();
+    assert_eq!(file.metadata().unwrap().len(), 16384);
+    allocate(&file, 16384, 4096, AllocateMode::Sparse).unwrap();
+    assert_eq!(file.metadata().unwrap().len(), 20480);
+    match allocate(&file, 1024, 4096, AllocateMode::PunchHole) {
+      Ok(()) | Err(FsError::NotSupported) => {}
+      Err(err) => panic!("{err:?}"),
+    }
+    assert_eq!(file.metadata().unwrap().len(), 20480);
+    assert!(allocate(&file, u64::MAX, 1, AllocateMode::Zero).is_err());
+
+    let mut data = vec![];
+    StdFile::open(&path)
+      .unwrap()
+      .read_to_end(&mut data)
+      .unwrap();
+    assert!(data[..1024].iter().all(|b| *b == 1));
+    assert!(data[5120..8192].iter().all(|b| *b == 1));
+    assert!(data[8192..].iter().all(|b| *b == 0));
+    drop(file);
+    std::fs::remove_file(path).unwrap();
+  }
+}
@@ -1,15 +1,23 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::io::IoSlice;
 use std::rc::Rc;
 
 use deno_core::error::AnyError;
+use deno_core::op2;
 use deno_core::AsyncRefCell;
 use deno_core::AsyncResult;
 use deno_core::CancelHandle;
 use deno_core::CancelTryFuture;
+use deno_core::JsBuffer;
+use deno_core::OpState;
 use deno_core::RcRef;
+use deno_core::ResourceId;
 use tokio::io::AsyncReadExt;
 use tokio::io::AsyncWriteExt;
+use tokio::sync::Notify;
 
 #[cfg(unix)]
 pub type RawBiPipeHandle = std::os::fd::RawFd;
@@ -17,13 +25,26 @@ pub type RawBiPipeHandle = std::os::fd::RawFd;
 #[cfg(windows)]
 pub type RawBiPipeHandle = std::os::windows::io::RawHandle;
 
+/// The default number of bytes that may be queued for writing to a
+/// [`BiPipeResource`] before it signals backpressure.
+pub const DEFAULT_HIGH_WATER_MARK: usize = 64 * 1024;
+
 /// One end of a bidirectional pipe. This implements the
 /// `Resource` trait.
+///
+/// Writes are performed one at a time, so concurrent writes queue up behind
+/// each other. The number of queued bytes is tracked, and once it exceeds the
+/// high water mark, writers should wait for [`BiPipeResource::drained`] before
+/// writing more, instead of buffering without bounds while the other end is
+/// slow to read.
 pub struct BiPipeResource {
   read_half: AsyncRefCell<BiPipeRead>,
   write_half: AsyncRefCell<BiPipeWrite>,
   cancel: CancelHandle,
   raw_handle: RawBiPipeHandle,
+  write_queue_size: Cell<usize>,
+  high_water_mark: Cell<usize>,
+  drained: Notify,
 }
 
 #[cfg(windows)]
@@ -69,9 +90,53 @@ impl BiPipeResource {
       read_half: AsyncRefCell::new(read),
       write_half: AsyncRefCell::new(write),
       cancel: Default::default(),
+      write_queue_size: Cell::new(0),
+      high_water_mark: Cell::new(DEFAULT_HIGH_WATER_MARK),
+      drained: Notify::new(),
     })
   }
 
+  /// The number of bytes passed to writes that didn't complete yet.
+  pub fn write_queue_size(&self) -> usize {
+    self.write_queue_size.get()
+  }
+
+  pub fn high_water_mark(&self) -> usize {
+    self.high_water_mark.get()
+  }
+
+  pub fn set_high_water_mark(&self, high_water_mark: usize) {
+    self.high_water_mark.set(high_water_mark);
+    self.notify_if_drained();
+  }
+
+  /// Whether more bytes than the high water mark are queued for writing.
+  pub fn needs_drain(&self) -> bool {
+    self.write_queue_size() > self.high_water_mark()
+  }
+
+  /// Resolves once no more bytes than the high water mark are queued for
+  /// writing.
+  pub async fn drained(&self) {
+    while self.needs_drain() {
+      self.drained.notified().await;
+    }
+  }
+
+  fn notify_if_drained(&self) {
+    if !self.needs_drain() {
+      self.drained.notify_waiters();
+    }
+  }
+
+  fn queue_write(&self, len: usize) -> QueuedWrite<'_> {
+    self.write_queue_size.set(self.write_queue_size.get() + len);
+    QueuedWrite {
+      resource: self,
+      len,
+    }
+  }
+
   pub async fn read(
     self: Rc<Self>,
     data: &mut [u8],
@@ -82,11 +147,92 @@ impl BiPipeResource {
   }
 
   pub async fn write(self: Rc<Self>, data: &[u8]) -> Result<usize, AnyError> {
-    let mut wr = RcRef::map(self, |r| &r.write_half).borrow_mut().await;
+    let _queued = self.queue_write(data.len());
+    let mut wr = RcRef::map(&self, |r| &r.write_half).borrow_mut().await;
     let nwritten = wr.write(data).await?;
     wr.flush().await?;
     Ok(nwritten)
   }
+
+  /// Writes all of `bufs`, using vectored writes if the pipe supports them.
+  /// Returns the total number of bytes written.
+  pub async fn write_all_vectored(
+    self: Rc<Self>,
+    bufs: &[&[u8]],
+  ) -> Result<usize, AnyError> {
+    let total = bufs.iter().map(|buf| buf.len()).sum();
+    let _queued = self.queue_write(total);
+    let mut wr = RcRef::map(&self, |r| &r.write_half).borrow_mut().await;
+
+    let mut bufs = bufs
+      .iter()
+      .copied()
+      .filter(|buf| !buf.is_empty())
+      .collect::<Vec<_>>();
+    let mut start = 0;
+    while start < bufs.len() {
+      let slices = bufs[start..]
+        .iter()
+        .map(|buf| IoSlice::new(buf))
+        .collect::<Vec<_>>();
+      let mut nwritten = wr.write_vectored(&slices).await?;
+      if nwritten == 0 {
+        return Err(std::io::Error::from(std::io::ErrorKind::WriteZero).into());
+      }
+      // skip the buffers that were written completely, and the written part
+      // of the first one that wasn't
+      while nwritten > 0 {
+        let len = bufs[start].len();
+        if nwritten >= len {
+          nwritten -= len;
+          start += 1;
+        } else {
+          bufs[start] = &bufs[start][nwritten..];
+          nwritten = 0;
+        }
+      }
+    }
+    wr.flush().await?;
+    Ok(total)
+  }
+}
+
+/// Accounts for the bytes of a write in the write queue of a
+/// [`BiPipeResource`] until it completes.
+struct QueuedWrite<'a> {
+  resource: &'a BiPipeResource,
+  len: usize,
+}
+
+impl Drop for QueuedWrite<'_> {
+  fn drop(&mut self) {
+    let queue_size = &self.resource.write_queue_size;
+    queue_size.set(queue_size.get() - self.len);
+    self.resource.notify_if_drained();
+  }
+}
+
+#[op2(fast)]
+pub fn op_bi_pipe_can_write_vectored(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> bool {
+  state.resource_table.get::<BiPipeResource>(rid).is_ok()
+}
+
+/// Writes all of `bufs` to the pipe, after waiting for the write queue to
+/// drain if too many bytes are queued already.
+#[op2(async)]
+#[number]
+pub async fn op_bi_pipe_write_vectored(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  #[serde] bufs: Vec<JsBuffer>,
+) -> Result<usize, AnyError> {
+  let resource = state.borrow().resource_table.get::<BiPipeResource>(rid)?;
+  resource.drained().await;
+  let bufs = bufs.iter().map(|buf| &buf[..]).collect::<Vec<_>>();
+  resource.write_all_vectored(&bufs).await
 }
 
 /// One end of a bidirectional pipe
@@ -226,6 +372,160 @@ impl tokio::io::AsyncRead for BiPipe {
   }
 }
 
+/// The maximum number of file descriptors received with a single read.
+#[cfg(unix)]
+const MAX_RECEIVED_FDS: usize = 16;
+
+#[cfg(unix)]
+impl BiPipeRead {
+  /// Like `poll_read`, but also receives the file descriptors that the other
+  /// end sent along with the data, for passing sockets between processes.
+  pub fn poll_read_with_fds(
+    &mut self,
+    cx: &mut std::task::Context<'_>,
+    buf: &mut tokio::io::ReadBuf<'_>,
+    fds: &mut impl Extend<std::os::fd::OwnedFd>,
+  ) -> std::task::Poll<std::io::Result<()>> {
+    use std::os::fd::AsRawFd;
+    let stream: &tokio::net::UnixStream = self.inner.as_ref();
+    loop {
+      std::task::ready!(stream.poll_read_ready(cx))?;
+      let unfilled = buf.initialize_unfilled();
+      match stream.try_io(tokio::io::Interest::READABLE, || {
+        recv_with_fds(stream.as_raw_fd(), unfilled, fds)
+      }) {
+        Ok(n) => {
+          buf.advance(n);
+          return std::task::Poll::Ready(Ok(()));
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => continue,
+        Err(err) => return std::task::Poll::Ready(Err(err)),
+      }
+    }
+  }
+}
+
+#[cfg(unix)]
+impl BiPipeWrite {
+  /// Writes all of `buf`, sending `fd` to the other end along with its first
+  /// bytes.
+  pub async fn write_all_with_fd(
+    &mut self,
+    buf: &[u8],
+    fd: std::os::fd::BorrowedFd<'_>,
+  ) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+    let stream: &tokio::net::UnixStream = self.inner.as_ref();
+    let written = loop {
+      stream.writable().await?;
+      match stream.try_io(tokio::io::Interest::WRITABLE, || {
+        send_with_fd(stream.as_raw_fd(), buf, fd.as_raw_fd())
+      }) {
+        Ok(n) => break n,
+        Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => continue,
+        Err(err) => return Err(err),
+      }
+    };
+    self.write_all(&buf[written..]).await
+  }
+}
+
+#[cfg(unix)]
+fn recv_with_fds(
+  socket: std::os::fd::RawFd,
+  buf: &mut [u8],
+  fds: &mut impl Extend<std::os::fd::OwnedFd>,
+) -> std::io::Result<usize> {
+  use std::os::fd::FromRawFd;
+  const FD_SIZE: usize = std::mem::size_of::<libc::c_int>();
+  // SAFETY: computes the size of a control message
+  let space = unsafe { libc::CMSG_SPACE((FD_SIZE * MAX_RECEIVED_FDS) as _) };
+  // u64s so that the buffer is aligned for `cmsghdr`
+  let mut control = vec![0u64; (space as usize).div_ceil(8)];
+  let mut iov = libc::iovec {
+    iov_base: buf.as_mut_ptr() as _,
+    iov_len: buf.len(),
+  };
+  // SAFETY: all zeroes is a valid `msghdr`
+  let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+  msg.msg_iov = &mut iov;
+  msg.msg_iovlen = 1;
+  msg.msg_control = control.as_mut_ptr() as _;
+  msg.msg_controllen = space as _;
+  #[cfg(any(target_os = "android", target_os = "linux"))]
+  let flags = libc::MSG_CMSG_CLOEXEC;
+  #[cfg(not(any(target_os = "android", target_os = "linux")))]
+  let flags = 0;
+  // SAFETY: libc call, the buffers outlive it
+  let n = unsafe { libc::recvmsg(socket, &mut msg, flags) };
+  if n < 0 {
+    return Err(std::io::Error::last_os_error());
+  }
+  // SAFETY: the control messages were written by `recvmsg`, and the file
+  // descriptors in them are owned by this process now.
+  unsafe {
+    let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+    while !cmsg.is_null() {
+      if (*cmsg).cmsg_level == libc::SOL_SOCKET
+        && (*cmsg).cmsg_type == libc::SCM_RIGHTS
+      {
+        let data = libc::CMSG_DATA(cmsg) as *const libc::c_int;
+        let len =
+          ((*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize) / FD_SIZE;
+        fds.extend((0..len).map(|i| {
+          let fd = data.add(i).read_unaligned();
+          #[cfg(not(any(target_os = "android", target_os = "linux")))]
+          libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+          std::os::fd::OwnedFd::from_raw_fd(fd)
+        }));
+      }
+      cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+    }
+  }
+  Ok(n as usize)
+}
+
+#[cfg(unix)]
+fn send_with_fd(
+  socket: std::os::fd::RawFd,
+  buf: &[u8],
+  fd: std::os::fd::RawFd,
+) -> std::io::Result<usize> {
+  const FD_SIZE: usize = std::mem::size_of::<libc::c_int>();
+  // SAFETY: computes the size of a control message
+  let space = unsafe { libc::CMSG_SPACE(FD_SIZE as _) };
+  // u64s so that the buffer is aligned for `cmsghdr`
+  let mut control = vec![0u64; (space as usize).div_ceil(8)];
+  let mut iov = libc::iovec {
+    iov_base: buf.as_ptr() as *mut _,
+    iov_len: buf.len(),
+  };
+  // SAFETY: all zeroes is a valid `msghdr`
+  let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+  msg.msg_iov = &mut iov;
+  msg.msg_iovlen = 1;
+  msg.msg_control = control.as_mut_ptr() as _;
+  msg.msg_controllen = space as _;
+  // SAFETY: the control buffer has room for a message with one fd
+  unsafe {
+    let cmsg = libc::CMSG_FIRSTHDR(&msg);
+    (*cmsg).cmsg_level = libc::SOL_SOCKET;
+    (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+    (*cmsg).cmsg_len = libc::CMSG_LEN(FD_SIZE as _) as _;
+    (libc::CMSG_DATA(cmsg) as *mut libc::c_int).write_unaligned(fd);
+  }
+  #[cfg(any(target_os = "android", target_os = "linux"))]
+  let flags = libc::MSG_NOSIGNAL;
+  #[cfg(not(any(target_os = "android", target_os = "linux")))]
+  let flags = 0;
+  // SAFETY: libc call, the buffers outlive it
+  let n = unsafe { libc::sendmsg(socket, &msg, flags) };
+  if n < 0 {
+    return Err(std::io::Error::last_os_error());
+  }
+  Ok(n as usize)
+}
+
 // implement `AsyncWrite` for `$name`, delegating
 // the impl to `$field`. `$name` must have a `project` method
 // with a projected `$field` (e.g. with `pin_project::pin_project`)
@@ -273,7 +573,8 @@ impl_async_write!(for BiPipeWrite -> self.inner);
 impl_async_write!(for BiPipe -> self.write_end);
 
 /// Creates both sides of a bidirectional pipe, returning the raw
-/// handles to the underlying OS resources.
+/// handles to the underlying OS resources. Neither is inherited by child
+/// processes implicitly, so one end has to be passed to a child explicitly.
 pub fn bi_pipe_pair_raw() -> Result<(RawBiPipeHandle, RawBiPipeHandle), AnyError>
 {
   #[cfg(unix)]
@@ -403,7 +704,7 @@ pub fn bi_pipe_pair_raw() -> Result<(RawBiPipeHandle, RawBiPipeHandle), AnyError
       let s = SECURITY_ATTRIBUTES {
         nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
         lpSecurityDescriptor: ptr::null_mut(),
-        bInheritHandle: 1,
+        bInheritHandle: 0,
       };
       let hd2 = CreateFileW(
         path.as_ptr(),
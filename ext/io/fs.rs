@@ -47,6 +47,20 @@ impl FsError {
   }
 }
 
+/// How [`File::allocate_sync`] changes the disk space of a range of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocateMode {
+  /// Allocate the range, which reads as zeros where it had no data yet, so
+  /// that writing to it can't run out of space. The file grows to include
+  /// the range.
+  Zero,
+  /// Grow the file to include the range without allocating it.
+  Sparse,
+  /// Deallocate the range, which then reads as zeros. The file keeps its
+  /// size.
+  PunchHole,
+}
+
 impl From<io::Error> for FsError {
   fn from(err: io::Error) -> Self {
     Self::Io(err)
@@ -224,6 +238,10 @@ pub trait File {
 
   fn lock_sync(self: Rc<Self>, exclusive: bool) -> FsResult<()>;
   async fn lock_async(self: Rc<Self>, exclusive: bool) -> FsResult<()>;
+  /// Like `lock_sync`, but returns `false` instead of waiting if the file is
+  /// locked by someone else in a conflicting mode.
+  fn try_lock_sync(self: Rc<Self>, exclusive: bool) -> FsResult<bool>;
+  async fn try_lock_async(self: Rc<Self>, exclusive: bool) -> FsResult<bool>;
 
   fn unlock_sync(self: Rc<Self>) -> FsResult<()>;
   async fn unlock_async(self: Rc<Self>) -> FsResult<()>;
@@ -231,6 +249,19 @@ pub trait File {
   fn truncate_sync(self: Rc<Self>, len: u64) -> FsResult<()>;
   async fn truncate_async(self: Rc<Self>, len: u64) -> FsResult<()>;
 
+  fn allocate_sync(
+    self: Rc<Self>,
+    offset: u64,
+    len: u64,
+    mode: AllocateMode,
+  ) -> FsResult<()>;
+  async fn allocate_async(
+    self: Rc<Self>,
+    offset: u64,
+    len: u64,
+    mode: AllocateMode,
+  ) -> FsResult<()>;
+
   fn utime_sync(
     self: Rc<Self>,
     atime_secs: i64,
@@ -16,6 +16,7 @@ use deno_core::RcRef;
 use deno_core::Resource;
 use deno_core::ResourceHandle;
 use deno_core::ResourceHandleFd;
+use fs::AllocateMode;
 use fs::FileResource;
 use fs::FsError;
 use fs::FsResult;
@@ -55,8 +56,12 @@ use parking_lot::Mutex;
 #[cfg(windows)]
 use std::sync::Arc;
 
+mod allocate;
 pub mod fs;
+mod lines;
 mod pipe;
+mod splice;
+mod tee;
 #[cfg(windows)]
 mod winpipe;
 
@@ -74,6 +79,18 @@ pub use bi_pipe::BiPipeRead;
 pub use bi_pipe::BiPipeResource;
 pub use bi_pipe::BiPipeWrite;
 pub use bi_pipe::RawBiPipeHandle;
+pub use bi_pipe::DEFAULT_HIGH_WATER_MARK;
+
+pub use lines::LineReaderResource;
+
+pub use splice::splice;
+
+pub use tee::tee;
+pub use tee::TeeReaderResource;
+pub use tee::TEE_BUFFERED_CHUNKS;
+
+#[cfg(windows)]
+pub use winpipe::create_named_pipe_with_sync_client;
 
 // Store the stdio fd/handles in global statics in order to keep them
 // alive for the duration of the application since the last handle/fd
@@ -110,8 +127,72 @@ pub static STDERR_HANDLE: Lazy<StdFile> = Lazy::new(|| {
   unsafe { StdFile::from_raw_handle(GetStdHandle(winbase::STD_ERROR_HANDLE)) }
 });
 
+/// The variable a parent Deno process lists the handles of the extra stdio
+/// entries of a child in on Windows, which has no fds beyond stdio. It holds
+/// `fd:handle` pairs separated by commas. The child inherits the handles under
+/// the same values.
+#[cfg(windows)]
+pub const EXTRA_STDIO_HANDLES_ENV_VAR: &str = "DENO_EXTRA_STDIO_HANDLES";
+
+/// The extra stdio handles this process inherited from its parent, sorted by
+/// fd. The variable they are read from is removed, so that children of this
+/// process don't see it. Like stdio, every worker gets duplicates of them, as
+/// the resources with their fds as rids, so that e.g. `fs.writeSync(3, data)`
+/// writes to fd 3.
+#[cfg(windows)]
+pub static EXTRA_STDIO_HANDLES: Lazy<Vec<(u32, StdFile)>> = Lazy::new(|| {
+  let Ok(value) = std::env::var(EXTRA_STDIO_HANDLES_ENV_VAR) else {
+    return Vec::new();
+  };
+  std::env::remove_var(EXTRA_STDIO_HANDLES_ENV_VAR);
+  let mut handles: Vec<(u32, StdFile)> = Vec::new();
+  for entry in value.split(',') {
+    let Some((fd, handle)) = entry.split_once(':') else {
+      continue;
+    };
+    let (Ok(fd), Ok(handle)) = (fd.parse::<u32>(), handle.parse::<usize>())
+    else {
+      continue;
+    };
+    if fd > 2 && handles.last().map_or(true, |(last, _)| *last < fd) {
+      // SAFETY: the handle was inherited for this fd, and isn't used
+      // otherwise
+      handles.push((fd, unsafe { StdFile::from_raw_handle(handle as _) }));
+    }
+  }
+  handles
+});
+
+/// Takes up the rid of an fd between the extra stdio handles that the parent
+/// didn't pass. It is closed right away.
+#[cfg(windows)]
+struct SkippedFd;
+
+#[cfg(windows)]
+impl Resource for SkippedFd {}
+
+/// The unstable child stdio APIs are enabled with `--unstable-process`, like
+/// the other unstable subprocess APIs.
+const UNSTABLE_PROCESS_FEATURE_NAME: &str = "process";
+
+fn check_unstable(state: &OpState, feature: &str, api_name: &str) {
+  // TODO(bartlomieju): replace with `state.feature_checker.check_or_exit`
+  // once we phase out `check_or_exit_with_legacy_fallback`
+  state
+    .feature_checker
+    .check_or_exit_with_legacy_fallback(feature, api_name);
+}
+
 deno_core::extension!(deno_io,
   deps = [ deno_web ],
+  ops = [
+    bi_pipe::op_bi_pipe_can_write_vectored,
+    bi_pipe::op_bi_pipe_write_vectored,
+    tee::op_tee_child_stdout,
+    lines::op_child_stdio_lines,
+    lines::op_read_line,
+    splice::op_splice,
+  ],
   esm = [ "12_io.js" ],
   options = {
     stdio: Option<Stdio>,
@@ -168,6 +249,23 @@ deno_core::extension!(deno_io,
         "stderr".to_string(),
       ));
       assert_eq!(rid, 2, "stderr must have ResourceId 2");
+
+      #[cfg(windows)]
+      {
+        let mut next_rid = 3;
+        for (fd, handle) in EXTRA_STDIO_HANDLES.iter() {
+          for _ in next_rid..*fd {
+            let rid = t.add(SkippedFd);
+            let _ = t.take_any(rid);
+          }
+          let rid = t.add(FileResource::new(
+            Rc::new(StdFileResourceInner::file(handle.try_clone().unwrap())),
+            "extraStdio".to_string(),
+          ));
+          assert_eq!(rid, *fd, "extra stdio must have its fd as ResourceId");
+          next_rid = fd + 1;
+        }
+      }
     }
   },
 );
@@ -323,6 +421,12 @@ impl Resource for ChildStdoutResource {
     "childStdout".into()
   }
 
+  fn backing_handle(self: Rc<Self>) -> Option<ResourceHandle> {
+    // not available while a read is pending
+    let stream = RcRef::map(&self, |r| &r.stream).try_borrow()?;
+    Some(ResourceHandle::from_fd_like(&*stream))
+  }
+
   fn close(self: Rc<Self>) {
     self.cancel_read_ops();
   }
@@ -342,6 +446,34 @@ impl Resource for ChildStderrResource {
   }
 }
 
+pub type PipeReadResource = ReadOnlyResource<AsyncPipeRead>;
+
+impl Resource for PipeReadResource {
+  deno_core::impl_readable_byob!();
+
+  fn name(&self) -> Cow<str> {
+    "pipeRead".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel_read_ops();
+  }
+}
+
+pub type PipeWriteResource = WriteOnlyResource<AsyncPipeWrite>;
+
+impl Resource for PipeWriteResource {
+  fn name(&self) -> Cow<str> {
+    "pipeWrite".into()
+  }
+
+  deno_core::impl_writable!();
+
+  fn shutdown(self: Rc<Self>) -> AsyncResult<()> {
+    Box::pin(self.shutdown())
+  }
+}
+
 #[cfg(windows)]
 #[derive(Default)]
 pub struct WinTtyState {
@@ -540,6 +672,23 @@ impl StdFileResourceInner {
   }
 }
 
+fn try_lock(file: &StdFile, exclusive: bool) -> FsResult<bool> {
+  let res = if exclusive {
+    file.try_lock_exclusive()
+  } else {
+    file.try_lock_shared()
+  };
+  match res {
+    Ok(()) => Ok(true),
+    Err(err)
+      if err.raw_os_error() == fs3::lock_contended_error().raw_os_error() =>
+    {
+      Ok(false)
+    }
+    Err(err) => Err(err.into()),
+  }
+}
+
 #[async_trait::async_trait(?Send)]
 impl crate::fs::File for StdFileResourceInner {
   fn write_sync(self: Rc<Self>, buf: &[u8]) -> FsResult<usize> {
@@ -797,6 +946,15 @@ impl crate::fs::File for StdFileResourceInner {
       .await
   }
 
+  fn try_lock_sync(self: Rc<Self>, exclusive: bool) -> FsResult<bool> {
+    self.with_sync(|file| try_lock(file, exclusive))
+  }
+  async fn try_lock_async(self: Rc<Self>, exclusive: bool) -> FsResult<bool> {
+    self
+      .with_inner_blocking_task(move |file| try_lock(file, exclusive))
+      .await
+  }
+
   fn unlock_sync(self: Rc<Self>) -> FsResult<()> {
     self.with_sync(|file| Ok(file.unlock()?))
   }
@@ -815,6 +973,27 @@ impl crate::fs::File for StdFileResourceInner {
       .await
   }
 
+  fn allocate_sync(
+    self: Rc<Self>,
+    offset: u64,
+    len: u64,
+    mode: AllocateMode,
+  ) -> FsResult<()> {
+    self.with_sync(|file| allocate::allocate(file, offset, len, mode))
+  }
+  async fn allocate_async(
+    self: Rc<Self>,
+    offset: u64,
+    len: u64,
+    mode: AllocateMode,
+  ) -> FsResult<()> {
+    self
+      .with_inner_blocking_task(move |file| {
+        allocate::allocate(file, offset, len, mode)
+      })
+      .await
+  }
+
   fn utime_sync(
     self: Rc<Self>,
     atime_secs: i64,
@@ -909,3 +1088,23 @@ pub fn op_print(
     Ok(file.write_all_sync(msg.as_bytes())?)
   })
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn try_lock_conflicts() {
+    let path = std::env::temp_dir()
+      .join(format!("deno_io_try_lock_{}.txt", std::process::id()));
+    let first = StdFile::create(&path).unwrap();
+    let second = StdFile::open(&path).unwrap();
+    assert!(try_lock(&first, true).unwrap());
+    assert!(!try_lock(&second, false).unwrap());
+    first.unlock().unwrap();
+    assert!(try_lock(&first, false).unwrap());
+    assert!(try_lock(&second, false).unwrap());
+    drop((first, second));
+    std::fs::remove_file(path).unwrap();
+  }
+}
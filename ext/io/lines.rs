@@ -0,0 +1,144 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Reading the stdout or stderr of a child line by line. The output is split
+//! into lines and decoded here, which is much cheaper than doing so in JS for
+//! children that log a lot.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use deno_core::op2;
+use deno_core::AsyncRefCell;
+use deno_core::CancelHandle;
+use deno_core::CancelTryFuture;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncRead;
+use tokio::io::BufReader;
+use tokio::process;
+
+use crate::ChildStdoutResource;
+use crate::ReadOnlyResource;
+
+pub struct LineReaderResource {
+  reader: AsyncRefCell<BufReader<Box<dyn AsyncRead + Unpin>>>,
+  cancel: CancelHandle,
+}
+
+impl LineReaderResource {
+  pub fn new(reader: impl AsyncRead + Unpin + 'static) -> Self {
+    Self {
+      reader: AsyncRefCell::new(BufReader::new(Box::new(reader))),
+      cancel: Default::default(),
+    }
+  }
+
+  /// Reads the next line without its line ending, which may be `\n` or
+  /// `\r\n`. Returns `None` at the end of the stream. Invalid UTF-8 is
+  /// replaced like `TextDecoder` does.
+  pub async fn read_line(self: Rc<Self>) -> Result<Option<String>, AnyError> {
+    let mut reader = RcRef::map(&self, |r| &r.reader).borrow_mut().await;
+    let cancel = RcRef::map(&self, |r| &r.cancel);
+    let mut line = vec![];
+    let nread = reader
+      .read_until(b'\n', &mut line)
+      .try_or_cancel(cancel)
+      .await?;
+    if nread == 0 {
+      return Ok(None);
+    }
+    if line.ends_with(b"\n") {
+      line.pop();
+      if line.ends_with(b"\r") {
+        line.pop();
+      }
+    }
+    Ok(Some(match String::from_utf8(line) {
+      Ok(line) => line,
+      Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+    }))
+  }
+}
+
+impl Resource for LineReaderResource {
+  fn name(&self) -> Cow<str> {
+    "lineReader".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+  }
+}
+
+fn take_stream<S>(state: &mut OpState, rid: ResourceId) -> Result<S, AnyError>
+where
+  S: AsyncRead + Unpin + 'static,
+  ReadOnlyResource<S>: Resource,
+{
+  let resource = state.resource_table.get::<ReadOnlyResource<S>>(rid)?;
+  // besides the resource table, pending reads hold on to the resource
+  if Rc::strong_count(&resource) > 2 {
+    return Err(type_error("The stream is being read from"));
+  }
+  drop(resource);
+  let resource = state.resource_table.take::<ReadOnlyResource<S>>(rid)?;
+  Rc::try_unwrap(resource)
+    .map(ReadOnlyResource::into_inner)
+    .map_err(|_| type_error("The stream is being read from"))
+}
+
+/// Replaces the stdout or stderr resource `rid` of a child with a resource
+/// for `op_read_line`.
+#[op2(fast)]
+#[smi]
+pub fn op_child_stdio_lines(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<ResourceId, AnyError> {
+  super::check_unstable(
+    state,
+    super::UNSTABLE_PROCESS_FEATURE_NAME,
+    "Deno.ChildProcess.lines",
+  );
+  let reader = if state.resource_table.get::<ChildStdoutResource>(rid).is_ok() {
+    LineReaderResource::new(take_stream::<process::ChildStdout>(state, rid)?)
+  } else {
+    LineReaderResource::new(take_stream::<process::ChildStderr>(state, rid)?)
+  };
+  Ok(state.resource_table.add(reader))
+}
+
+#[op2(async)]
+#[string]
+pub async fn op_read_line(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<String>, AnyError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<LineReaderResource>(rid)?;
+  resource.read_line().await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn read_lines() {
+    let input: &[u8] = b"first\nsecond\r\n\n\xffinvalid\nlast";
+    let reader = Rc::new(LineReaderResource::new(input));
+    let mut lines = vec![];
+    while let Some(line) = reader.clone().read_line().await.unwrap() {
+      lines.push(line);
+    }
+    assert_eq!(lines, ["first", "second", "", "\u{FFFD}invalid", "last"]);
+  }
+}
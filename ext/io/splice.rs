@@ -0,0 +1,243 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Forwarding all bytes of one resource to another, e.g. the output of a
+//! child to a socket. On Linux this uses `splice(2)` if either side is a pipe,
+//! so the bytes are never copied into this process. Otherwise, and on other
+//! platforms, they are copied through the generic resource reads and writes.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use deno_core::error::AnyError;
+use deno_core::op2;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceId;
+
+const SPLICE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Forwards `src` to `dst` until `src` reaches its end, and returns the number
+/// of bytes that were forwarded. `dst` isn't shut down afterwards.
+pub async fn splice(
+  src: Rc<dyn Resource>,
+  dst: Rc<dyn Resource>,
+) -> Result<u64, AnyError> {
+  #[cfg(target_os = "linux")]
+  {
+    let handles = src
+      .clone()
+      .backing_handle()
+      .zip(dst.clone().backing_handle());
+    if let Some((src_handle, dst_handle)) = handles {
+      let src_fd = raw_fd(src_handle);
+      let dst_fd = raw_fd(dst_handle);
+      // the resources are kept alive, and with them the fds
+      if let Some(nwritten) = linux::splice(src_fd, dst_fd).await? {
+        return Ok(nwritten);
+      }
+    }
+  }
+
+  let mut nwritten = 0;
+  loop {
+    let view = src.clone().read(SPLICE_CHUNK_SIZE).await?;
+    if view.is_empty() {
+      return Ok(nwritten);
+    }
+    nwritten += view.len() as u64;
+    dst.clone().write_all(view).await?;
+  }
+}
+
+#[cfg(target_os = "linux")]
+fn raw_fd(handle: deno_core::ResourceHandle) -> std::os::fd::RawFd {
+  match handle {
+    deno_core::ResourceHandle::Fd(fd) => fd,
+    deno_core::ResourceHandle::Socket(fd) => fd,
+  }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+  use std::io;
+  use std::os::fd::AsRawFd;
+  use std::os::fd::BorrowedFd;
+  use std::os::fd::OwnedFd;
+  use std::os::fd::RawFd;
+
+  use deno_core::error::AnyError;
+  use tokio::io::unix::AsyncFd;
+  use tokio::io::Interest;
+
+  use super::SPLICE_CHUNK_SIZE;
+
+  /// Waits for readiness on duplicates of the fds, as the originals are
+  /// already registered with the reactor by their resources. Returns `None`
+  /// if the fds can't be spliced before anything was forwarded.
+  pub(super) async fn splice(
+    src: RawFd,
+    dst: RawFd,
+  ) -> Result<Option<u64>, AnyError> {
+    let src = PollableFd::new(src, Interest::READABLE)?;
+    let dst = PollableFd::new(dst, Interest::WRITABLE)?;
+
+    let mut nwritten = 0;
+    loop {
+      let mut src_guard = match &src {
+        PollableFd::Polled(fd) => Some(fd.readable().await?),
+        PollableFd::Always(_) => None,
+      };
+      let mut dst_guard = match &dst {
+        PollableFd::Polled(fd) => Some(fd.writable().await?),
+        PollableFd::Always(_) => None,
+      };
+      // SAFETY: libc call, both fds are valid
+      let res = unsafe {
+        libc::splice(
+          src.as_raw_fd(),
+          std::ptr::null_mut(),
+          dst.as_raw_fd(),
+          std::ptr::null_mut(),
+          SPLICE_CHUNK_SIZE,
+          libc::SPLICE_F_MOVE | libc::SPLICE_F_NONBLOCK,
+        )
+      };
+      match res {
+        0 => return Ok(Some(nwritten)),
+        n if n > 0 => nwritten += n as u64,
+        _ => {
+          let err = io::Error::last_os_error();
+          match err.raw_os_error() {
+            // either side would block, so wait again for the one that does
+            Some(libc::EAGAIN) => {
+              let (src_ready, dst_ready) =
+                poll_now(src.as_raw_fd(), dst.as_raw_fd());
+              if let (false, Some(guard)) = (src_ready, src_guard.as_mut()) {
+                guard.clear_ready();
+              }
+              if let (false, Some(guard)) = (dst_ready, dst_guard.as_mut()) {
+                guard.clear_ready();
+              }
+            }
+            Some(libc::EINTR) => {}
+            // neither side is a pipe, or the other side doesn't support it
+            Some(libc::EINVAL) if nwritten == 0 => return Ok(None),
+            _ => return Err(err.into()),
+          }
+        }
+      }
+    }
+  }
+
+  /// A duplicate of an fd, which is polled for readiness unless it refers to
+  /// a regular file. Those can't be polled, but are always ready.
+  enum PollableFd {
+    Polled(AsyncFd<OwnedFd>),
+    Always(OwnedFd),
+  }
+
+  impl PollableFd {
+    fn new(fd: RawFd, interest: Interest) -> io::Result<Self> {
+      // SAFETY: the resource owning `fd` outlives the duplicate
+      let fd = unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()?;
+      // SAFETY: libc call, `stat` is written by `fstat`
+      let is_file = unsafe {
+        let mut stat = std::mem::zeroed::<libc::stat>();
+        libc::fstat(fd.as_raw_fd(), &mut stat) == 0
+          && stat.st_mode & libc::S_IFMT == libc::S_IFREG
+      };
+      if is_file {
+        Ok(PollableFd::Always(fd))
+      } else {
+        Ok(PollableFd::Polled(AsyncFd::with_interest(fd, interest)?))
+      }
+    }
+  }
+
+  impl AsRawFd for PollableFd {
+    fn as_raw_fd(&self) -> RawFd {
+      match self {
+        PollableFd::Polled(fd) => fd.as_raw_fd(),
+        PollableFd::Always(fd) => fd.as_raw_fd(),
+      }
+    }
+  }
+
+  /// Whether `src` is readable and `dst` is writable right now.
+  fn poll_now(src: RawFd, dst: RawFd) -> (bool, bool) {
+    let mut fds = [
+      libc::pollfd {
+        fd: src,
+        events: libc::POLLIN,
+        revents: 0,
+      },
+      libc::pollfd {
+        fd: dst,
+        events: libc::POLLOUT,
+        revents: 0,
+      },
+    ];
+    // SAFETY: libc call, `fds` has two entries
+    unsafe { libc::poll(fds.as_mut_ptr(), 2, 0) };
+    // errors and hangups count as ready, so that `splice` reports them
+    let ready = |fd: &libc::pollfd| fd.revents != 0;
+    (ready(&fds[0]), ready(&fds[1]))
+  }
+}
+
+/// Forwards the resource `src_rid` to `dst_rid`, see [`splice`].
+#[op2(async)]
+#[number]
+pub async fn op_splice(
+  state: Rc<RefCell<OpState>>,
+  #[smi] src_rid: ResourceId,
+  #[smi] dst_rid: ResourceId,
+) -> Result<u64, AnyError> {
+  let (src, dst) = {
+    let state = state.borrow();
+    super::check_unstable(
+      &state,
+      super::UNSTABLE_PROCESS_FEATURE_NAME,
+      "Deno.ChildProcess.spliceStdout",
+    );
+    (
+      state.resource_table.get_any(src_rid)?,
+      state.resource_table.get_any(dst_rid)?,
+    )
+  };
+  splice(src, dst).await
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+  use std::io::Read;
+  use std::io::Write;
+  use std::os::fd::AsRawFd;
+
+  #[tokio::test]
+  async fn splice_pipes() {
+    let (src_read, mut src_write) = os_pipe::pipe().unwrap();
+    let (mut dst_read, dst_write) = os_pipe::pipe().unwrap();
+    let data = vec![7u8; 200_000];
+    let writer = {
+      let data = data.clone();
+      std::thread::spawn(move || {
+        src_write.write_all(&data).unwrap();
+      })
+    };
+    let reader = std::thread::spawn(move || {
+      let mut output = vec![];
+      dst_read.read_to_end(&mut output).unwrap();
+      output
+    });
+
+    let nwritten =
+      super::linux::splice(src_read.as_raw_fd(), dst_write.as_raw_fd())
+        .await
+        .unwrap();
+    assert_eq!(nwritten, Some(data.len() as u64));
+    drop(dst_write);
+    writer.join().unwrap();
+    assert_eq!(reader.join().unwrap(), data);
+  }
+}
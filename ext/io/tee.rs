@@ -0,0 +1,195 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Splitting the stdout of a child into several readers, e.g. to show the
+//! output on the console and collect it at the same time. Every reader buffers
+//! up to [`TEE_BUFFERED_CHUNKS`] chunks, so a slow reader only holds back the
+//! others once its buffer is full.
+
+use std::borrow::Cow;
+use std::io;
+use std::rc::Rc;
+
+use deno_core::error::AnyError;
+use deno_core::op2;
+use deno_core::AsyncRefCell;
+use deno_core::AsyncResult;
+use deno_core::CancelHandle;
+use deno_core::CancelTryFuture;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use tokio::io::AsyncRead;
+use tokio::sync::mpsc;
+
+use crate::ChildStdoutResource;
+use crate::ReadOnlyResource;
+
+/// The number of chunks a reader may lag behind the fastest reader.
+pub const TEE_BUFFERED_CHUNKS: usize = 16;
+
+const TEE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Read errors of the source are passed on to every reader. `io::Error` can't
+/// be cloned, so only its kind and message are.
+type TeeChunk = Result<Rc<[u8]>, (io::ErrorKind, String)>;
+
+struct TeeReaderState {
+  rx: mpsc::Receiver<TeeChunk>,
+  /// The rest of a chunk which didn't fit into the buffer of the last read.
+  pending: Option<(Rc<[u8]>, usize)>,
+}
+
+/// One of the readers created by `op_tee_child_stdout`.
+pub struct TeeReaderResource {
+  state: AsyncRefCell<TeeReaderState>,
+  cancel: CancelHandle,
+}
+
+impl TeeReaderResource {
+  async fn read(self: Rc<Self>, data: &mut [u8]) -> Result<usize, AnyError> {
+    let mut state = RcRef::map(&self, |r| &r.state).borrow_mut().await;
+    let (chunk, offset) = match state.pending.take() {
+      Some(pending) => pending,
+      None => {
+        let cancel = RcRef::map(&self, |r| &r.cancel);
+        let recv = async { Ok::<_, AnyError>(state.rx.recv().await) };
+        match recv.try_or_cancel(cancel).await? {
+          Some(Ok(chunk)) => (chunk, 0),
+          Some(Err((kind, msg))) => {
+            return Err(io::Error::new(kind, msg).into())
+          }
+          // the source reached its end
+          None => return Ok(0),
+        }
+      }
+    };
+    let nread = data.len().min(chunk.len() - offset);
+    data[..nread].copy_from_slice(&chunk[offset..offset + nread]);
+    if offset + nread < chunk.len() {
+      state.pending = Some((chunk, offset + nread));
+    }
+    Ok(nread)
+  }
+}
+
+impl Resource for TeeReaderResource {
+  deno_core::impl_readable_byob!();
+
+  fn name(&self) -> Cow<str> {
+    "teeReader".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+  }
+}
+
+/// Reads `source` to its end in the background, and hands every chunk to all
+/// of the `count` returned readers. Readers which are closed are skipped, and
+/// `source` is dropped once all of them are.
+pub fn tee<S>(
+  source: Rc<ReadOnlyResource<S>>,
+  count: usize,
+) -> Vec<TeeReaderResource>
+where
+  S: AsyncRead + Unpin + 'static,
+{
+  let (txs, readers): (Vec<_>, Vec<_>) = (0..count)
+    .map(|_| {
+      let (tx, rx) = mpsc::channel(TEE_BUFFERED_CHUNKS);
+      let reader = TeeReaderResource {
+        state: AsyncRefCell::new(TeeReaderState { rx, pending: None }),
+        cancel: Default::default(),
+      };
+      (tx, reader)
+    })
+    .unzip();
+
+  deno_core::unsync::spawn(async move {
+    let mut txs = txs;
+    let mut buf = vec![0; TEE_CHUNK_SIZE];
+    while !txs.is_empty() {
+      let chunk = match source.clone().read(&mut buf).await {
+        Ok(0) => break,
+        Ok(nread) => Ok(Rc::from(&buf[..nread])),
+        Err(err) => {
+          let err = match err.downcast::<io::Error>() {
+            Ok(err) => (err.kind(), err.to_string()),
+            Err(err) => (io::ErrorKind::Other, err.to_string()),
+          };
+          Err(err)
+        }
+      };
+      let failed = chunk.is_err();
+      let mut open = Vec::with_capacity(txs.len());
+      for tx in txs {
+        if tx.send(chunk.clone()).await.is_ok() {
+          open.push(tx);
+        }
+      }
+      txs = open;
+      if failed {
+        break;
+      }
+    }
+  });
+
+  readers
+}
+
+/// Replaces the stdout resource `rid` of a child with `count` readers, which
+/// each receive all of its output.
+#[op2]
+#[serde]
+pub fn op_tee_child_stdout(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[smi] count: u32,
+) -> Result<Vec<ResourceId>, AnyError> {
+  super::check_unstable(
+    state,
+    super::UNSTABLE_PROCESS_FEATURE_NAME,
+    "Deno.ChildProcess.teeStdout",
+  );
+  if count == 0 {
+    return Err(deno_core::error::type_error(
+      "The number of readers must be greater than 0",
+    ));
+  }
+  let source = state.resource_table.take::<ChildStdoutResource>(rid)?;
+  let rids = tee(source, count as usize)
+    .into_iter()
+    .map(|reader| state.resource_table.add(reader))
+    .collect();
+  Ok(rids)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn readers_receive_everything() {
+    let data = vec![7u8; TEE_CHUNK_SIZE * 3 + 10];
+    let source = ReadOnlyResource::from(std::io::Cursor::new(data.clone()));
+    let mut readers = tee(Rc::new(source), 3).into_iter().map(Rc::new);
+    let first = readers.next().unwrap();
+    let second = readers.next().unwrap();
+    // a reader that is dropped right away doesn't hold back the others
+    drop(readers);
+
+    for reader in [first, second] {
+      let mut output = vec![];
+      let mut buf = [0; 1000];
+      loop {
+        let nread = reader.clone().read(&mut buf).await.unwrap();
+        if nread == 0 {
+          break;
+        }
+        output.extend_from_slice(&buf[..nread]);
+      }
+      assert_eq!(output, data);
+    }
+  }
+}
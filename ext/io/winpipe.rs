@@ -30,10 +30,20 @@ use winapi::um::winnt::GENERIC_WRITE;
 /// well as offering a complex NTAPI solution if we decide to try to make these pipes truely
 /// anonymous: https://stackoverflow.com/questions/60645/overlapped-i-o-on-anonymous-pipe
 pub fn create_named_pipe() -> io::Result<(RawHandle, RawHandle)> {
-  create_named_pipe_inner()
+  create_named_pipe_inner(FILE_FLAG_OVERLAPPED)
 }
 
-fn create_named_pipe_inner() -> io::Result<(RawHandle, RawHandle)> {
+/// Like [`create_named_pipe`], but the client handle is opened for synchronous
+/// I/O. It is meant to be handed to a child process, which doesn't expect an
+/// overlapped handle.
+pub fn create_named_pipe_with_sync_client() -> io::Result<(RawHandle, RawHandle)>
+{
+  create_named_pipe_inner(0)
+}
+
+fn create_named_pipe_inner(
+  client_flags: DWORD,
+) -> io::Result<(RawHandle, RawHandle)> {
   static NEXT_ID: AtomicU32 = AtomicU32::new(0);
   // Create an extremely-likely-unique pipe name from randomness, identity and a serial counter.
   let pipe_name = format!(
@@ -90,7 +100,7 @@ fn create_named_pipe_inner() -> io::Result<(RawHandle, RawHandle)> {
       0,
       &mut security_attributes,
       OPEN_EXISTING,
-      FILE_FLAG_OVERLAPPED,
+      client_flags,
       std::ptr::null_mut(),
     )
   };
@@ -139,6 +149,21 @@ mod tests {
     assert_eq!(&buf, b"hello");
   }
 
+  #[test]
+  fn make_named_pipe_with_sync_client() {
+    let (server, client) = create_named_pipe_with_sync_client().unwrap();
+    // SAFETY: For testing
+    let mut server = unsafe { File::from_raw_handle(server) };
+    // SAFETY: For testing
+    let mut client = unsafe { File::from_raw_handle(client) };
+
+    // The pipe is duplex, and either end can be written to
+    client.write_all(b"hello").unwrap();
+    let mut buf: [u8; 5] = Default::default();
+    server.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello");
+  }
+
   #[test]
   fn make_many_named_pipes_serial() {
     let mut handles = vec![];
@@ -9,13 +9,21 @@ import {
   op_kv_database_open,
   op_kv_dequeue_next_message,
   op_kv_encode_cursor,
+  op_kv_export,
+  op_kv_export_next,
   op_kv_finish_dequeued_message,
+  op_kv_import,
+  op_kv_import_finish,
+  op_kv_import_write,
   op_kv_snapshot_read,
   op_kv_watch,
   op_kv_watch_next,
+  op_kv_watch_range,
+  op_kv_watch_range_next,
 } from "ext:core/ops";
 const {
   ArrayFrom,
+  ArrayIsArray,
   ArrayPrototypeMap,
   ArrayPrototypePush,
   ArrayPrototypeReverse,
@@ -24,6 +32,7 @@ const {
   BigInt,
   BigIntPrototypeToString,
   Error,
+  NumberIsInteger,
   NumberIsNaN,
   Object,
   ObjectFreeze,
@@ -88,6 +97,97 @@ function validateBackoffSchedule(backoffSchedule: number[]) {
   }
 }
 
+const maxQueuePriority = 9;
+const maxQueueVisibilityTimeout = 60 * 60 * 1000;
+const maxQueueDeliveries = 100;
+
+interface EnqueueOptions {
+  delay?: number;
+  keysIfUndelivered?: Deno.KvKey[];
+  backoffSchedule?: number[];
+  priority?: number;
+  visibilityTimeout?: number;
+  maxDeliveries?: number;
+  deadLetterPrefix?: Deno.KvKey;
+}
+
+// [priority, visibilityTimeout, maxDeliveries, deadLetterPrefix]
+type RawDeliveryOptions = [
+  number,
+  number | null,
+  number | null,
+  Deno.KvKey | null,
+];
+
+// [payload, delay, keysIfUndelivered, backoffSchedule, deliveryOptions]
+type RawEnqueue = [
+  Uint8Array,
+  number,
+  Deno.KvKey[],
+  number[] | null,
+  RawDeliveryOptions | null,
+];
+
+function isIntegerInRange(value: number, min: number, max: number) {
+  return NumberIsInteger(value) && value >= min && value <= max;
+}
+
+function enqueueFromOptions(
+  message: unknown,
+  opts: EnqueueOptions | undefined,
+): RawEnqueue {
+  if (opts?.delay !== undefined) {
+    validateQueueDelay(opts?.delay);
+  }
+  if (opts?.backoffSchedule !== undefined) {
+    validateBackoffSchedule(opts?.backoffSchedule);
+  }
+  let deliveryOptions: RawDeliveryOptions | null = null;
+  const { priority, visibilityTimeout, maxDeliveries, deadLetterPrefix } =
+    opts ?? {};
+  if (
+    priority !== undefined || visibilityTimeout !== undefined ||
+    maxDeliveries !== undefined || deadLetterPrefix !== undefined
+  ) {
+    if (
+      priority !== undefined &&
+      !isIntegerInRange(priority, 0, maxQueuePriority)
+    ) {
+      throw new TypeError("priority must be an integer between 0 and 9");
+    }
+    if (priority && opts?.delay) {
+      throw new TypeError("priority cannot be combined with delay");
+    }
+    if (
+      visibilityTimeout !== undefined &&
+      !isIntegerInRange(visibilityTimeout, 1, maxQueueVisibilityTimeout)
+    ) {
+      throw new TypeError(
+        "visibilityTimeout must be an integer between 1 and 3600000",
+      );
+    }
+    if (
+      maxDeliveries !== undefined &&
+      !isIntegerInRange(maxDeliveries, 1, maxQueueDeliveries)
+    ) {
+      throw new TypeError("maxDeliveries must be an integer between 1 and 100");
+    }
+    deliveryOptions = [
+      priority ?? 0,
+      visibilityTimeout ?? null,
+      maxDeliveries ?? null,
+      deadLetterPrefix ?? null,
+    ];
+  }
+  return [
+    core.serialize(message, { forStorage: true }),
+    opts?.delay ?? 0,
+    opts?.keysIfUndelivered ?? [],
+    opts?.backoffSchedule ?? null,
+    deliveryOptions,
+  ];
+}
+
 interface RawKvEntry {
   key: Deno.KvKey;
   value: RawValue;
@@ -105,6 +205,15 @@ type RawValue = {
   value: bigint;
 };
 
+// [key, type, value, expireIn, indexes]
+type RawMutation = [
+  Deno.KvKey,
+  string,
+  RawValue | null,
+  number | undefined,
+  Deno.KvKey[] | null,
+];
+
 const kvSymbol = Symbol("KvRid");
 const commitVersionstampSymbol = Symbol("KvCommitVersionstamp");
 
@@ -181,22 +290,32 @@ class Kv {
     });
   }
 
-  async set(key: Deno.KvKey, value: unknown, options?: { expireIn?: number }) {
+  async set(
+    key: Deno.KvKey,
+    value: unknown,
+    options?: { expireIn?: number; indexes?: Deno.KvKey[] },
+  ) {
     const versionstamp = await doAtomicWriteInPlace(
       this.#rid,
       [],
-      [[key, "set", serializeValue(value), options?.expireIn]],
+      [[
+        key,
+        "set",
+        serializeValue(value),
+        options?.expireIn,
+        options?.indexes ?? null,
+      ]],
       [],
     );
     if (versionstamp === null) throw new TypeError("Failed to set value");
     return { ok: true, versionstamp };
   }
 
-  async delete(key: Deno.KvKey) {
+  async delete(key: Deno.KvKey, options?: { indexes?: boolean }) {
     const result = await doAtomicWriteInPlace(
       this.#rid,
       [],
-      [[key, "delete", null, undefined]],
+      [[key, "delete", null, undefined, options?.indexes ? [] : null]],
       [],
     );
     if (!result) throw new TypeError("Failed to set value");
@@ -255,33 +374,12 @@ class Kv {
     };
   }
 
-  async enqueue(
-    message: unknown,
-    opts?: {
-      delay?: number;
-      keysIfUndelivered?: Deno.KvKey[];
-      backoffSchedule?: number[];
-    },
-  ) {
-    if (opts?.delay !== undefined) {
-      validateQueueDelay(opts?.delay);
-    }
-    if (opts?.backoffSchedule !== undefined) {
-      validateBackoffSchedule(opts?.backoffSchedule);
-    }
-
+  async enqueue(message: unknown, opts?: EnqueueOptions) {
     const versionstamp = await doAtomicWriteInPlace(
       this.#rid,
       [],
       [],
-      [
-        [
-          core.serialize(message, { forStorage: true }),
-          opts?.delay ?? 0,
-          opts?.keysIfUndelivered ?? [],
-          opts?.backoffSchedule ?? null,
-        ],
-      ],
+      [enqueueFromOptions(message, opts)],
     );
     if (versionstamp === null) throw new TypeError("Failed to enqueue value");
     return { ok: true, versionstamp };
@@ -341,7 +439,13 @@ class Kv {
     finishMessageOps.clear();
   }
 
-  watch(keys: Deno.KvKey[], options = { __proto__: null }) {
+  watch(
+    keys: Deno.KvKey[] | Deno.KvListSelector,
+    options = { __proto__: null },
+  ) {
+    if (!ArrayIsArray(keys)) {
+      return this.#watchRange(keys, options);
+    }
     const raw = options.raw ?? false;
     const rid = op_kv_watch(this.#rid, keys);
     const lastEntries: (Deno.KvEntryMaybe<unknown> | undefined)[] = ArrayFrom(
@@ -409,6 +513,98 @@ class Kv {
     });
   }
 
+  #watchRange(
+    selector: Deno.KvListSelector,
+    options: { raw?: boolean; limit?: number },
+  ) {
+    const raw = options.raw ?? false;
+    const limit = options.limit ?? 100;
+    if (limit <= 0) throw new Error("limit must be positive");
+    const rid = op_kv_watch_range(
+      this.#rid,
+      [
+        ObjectHasOwn(selector, "prefix") ? selector.prefix : null,
+        ObjectHasOwn(selector, "start") ? selector.start : null,
+        ObjectHasOwn(selector, "end") ? selector.end : null,
+      ],
+      limit,
+      raw,
+    );
+    return new ReadableStream({
+      async pull(controller) {
+        let entries;
+        try {
+          entries = await op_kv_watch_range_next(rid);
+        } catch (err) {
+          core.tryClose(rid);
+          controller.error(err);
+          return;
+        }
+        if (entries === null) {
+          core.tryClose(rid);
+          controller.close();
+          return;
+        }
+        controller.enqueue(ArrayPrototypeMap(entries, deserializeValue));
+      },
+      cancel() {
+        core.tryClose(rid);
+      },
+    });
+  }
+
+  export(): ReadableStream<Uint8Array> {
+    const dbRid = this.#rid;
+    let rid: number | undefined;
+    return new ReadableStream({
+      async start() {
+        rid = await op_kv_export(dbRid);
+      },
+      pull(controller) {
+        let chunk;
+        try {
+          chunk = op_kv_export_next(rid);
+        } catch (err) {
+          core.tryClose(rid);
+          controller.error(err);
+          return;
+        }
+        if (chunk === null) {
+          core.tryClose(rid);
+          controller.close();
+          return;
+        }
+        controller.enqueue(chunk);
+      },
+      cancel() {
+        if (rid !== undefined) core.tryClose(rid);
+      },
+    });
+  }
+
+  async import(
+    source: ReadableStream<Uint8Array> | Uint8Array,
+  ): Promise<number> {
+    const rid = op_kv_import(this.#rid);
+    let count = 0;
+    try {
+      if (TypedArrayPrototypeGetSymbolToStringTag(source) === "Uint8Array") {
+        count += await op_kv_import_write(rid, source as Uint8Array);
+      } else {
+        const reader = (source as ReadableStream<Uint8Array>).getReader();
+        while (true) {
+          const { value, done } = await reader.read();
+          if (done) break;
+          count += await op_kv_import_write(rid, value);
+        }
+      }
+      op_kv_import_finish(rid);
+    } finally {
+      core.tryClose(rid);
+    }
+    return count;
+  }
+
   close() {
     core.close(this.#rid);
     this.#isClosed = true;
@@ -423,8 +619,8 @@ class AtomicOperation {
   #rid: number;
 
   #checks: [Deno.KvKey, string | null][] = [];
-  #mutations: [Deno.KvKey, string, RawValue | null, number | undefined][] = [];
-  #enqueues: [Uint8Array, number, Deno.KvKey[], number[] | null][] = [];
+  #mutations: RawMutation[] = [];
+  #enqueues: RawEnqueue[] = [];
 
   constructor(rid: number) {
     this.#rid = rid;
@@ -445,17 +641,20 @@ class AtomicOperation {
       let type: string;
       let value: RawValue | null;
       let expireIn: number | undefined = undefined;
+      let indexes: Deno.KvKey[] | null = null;
       switch (mutation.type) {
         case "delete":
           type = "delete";
           if (mutation.value) {
             throw new TypeError("invalid mutation 'delete' with value");
           }
+          if (mutation.indexes) indexes = [];
           break;
         case "set":
           if (typeof mutation.expireIn === "number") {
             expireIn = mutation.expireIn;
           }
+          indexes = mutation.indexes ?? null;
           /* falls through */
         case "sum":
         case "min":
@@ -469,7 +668,13 @@ class AtomicOperation {
         default:
           throw new TypeError("Invalid mutation type");
       }
-      ArrayPrototypePush(this.#mutations, [key, type, value, expireIn]);
+      ArrayPrototypePush(this.#mutations, [
+        key,
+        type,
+        value,
+        expireIn,
+        indexes,
+      ]);
     }
     return this;
   }
@@ -480,6 +685,7 @@ class AtomicOperation {
       "sum",
       serializeValue(new KvU64(n)),
       undefined,
+      null,
     ]);
     return this;
   }
@@ -490,6 +696,7 @@ class AtomicOperation {
       "min",
       serializeValue(new KvU64(n)),
       undefined,
+      null,
     ]);
     return this;
   }
@@ -500,6 +707,7 @@ class AtomicOperation {
       "max",
       serializeValue(new KvU64(n)),
       undefined,
+      null,
     ]);
     return this;
   }
@@ -507,42 +715,31 @@ class AtomicOperation {
   set(
     key: Deno.KvKey,
     value: unknown,
-    options?: { expireIn?: number },
+    options?: { expireIn?: number; indexes?: Deno.KvKey[] },
   ): this {
     ArrayPrototypePush(this.#mutations, [
       key,
       "set",
       serializeValue(value),
       options?.expireIn,
+      options?.indexes ?? null,
     ]);
     return this;
   }
 
-  delete(key: Deno.KvKey): this {
-    ArrayPrototypePush(this.#mutations, [key, "delete", null, undefined]);
+  delete(key: Deno.KvKey, options?: { indexes?: boolean }): this {
+    ArrayPrototypePush(this.#mutations, [
+      key,
+      "delete",
+      null,
+      undefined,
+      options?.indexes ? [] : null,
+    ]);
     return this;
   }
 
-  enqueue(
-    message: unknown,
-    opts?: {
-      delay?: number;
-      keysIfUndelivered?: Deno.KvKey[];
-      backoffSchedule?: number[];
-    },
-  ): this {
-    if (opts?.delay !== undefined) {
-      validateQueueDelay(opts?.delay);
-    }
-    if (opts?.backoffSchedule !== undefined) {
-      validateBackoffSchedule(opts?.backoffSchedule);
-    }
-    ArrayPrototypePush(this.#enqueues, [
-      core.serialize(message, { forStorage: true }),
-      opts?.delay ?? 0,
-      opts?.keysIfUndelivered ?? [],
-      opts?.backoffSchedule ?? null,
-    ]);
+  enqueue(message: unknown, opts?: EnqueueOptions): this {
+    ArrayPrototypePush(this.#enqueues, enqueueFromOptions(message, opts));
     return this;
   }
 
@@ -803,8 +1000,8 @@ class KvListIterator extends AsyncIterator
 async function doAtomicWriteInPlace(
   rid: number,
   checks: [Deno.KvKey, string | null][],
-  mutations: [Deno.KvKey, string, RawValue | null, number | undefined][],
-  enqueues: [Uint8Array, number, Deno.KvKey[], number[] | null][],
+  mutations: RawMutation[],
+  enqueues: RawEnqueue[],
 ): Promise<string | null> {
   for (let i = 0; i < mutations.length; ++i) {
     const mutation = mutations[i];
@@ -0,0 +1,137 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! The format of the snapshots that `Deno.Kv.prototype.export` produces and
+//! `Deno.Kv.prototype.import` restores: a magic number, followed by the
+//! entries of the database in key order, followed by its index records.
+//! Every entry is encoded as
+//!
+//! - the length of the key, as a big endian `u32`, and the key,
+//! - the versionstamp of the entry (10 bytes),
+//! - the encoding of the value (1 byte, as stored by denokv),
+//! - the length of the value, as a big endian `u32`, and the value.
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use denokv_proto::decode_key;
+use denokv_proto::decode_value;
+use denokv_proto::encode_value;
+use denokv_proto::KvEntry;
+use denokv_proto::VALUE_ENCODING_LE64;
+
+use crate::index;
+
+const MAGIC: &[u8; 8] = b"DENOKVB1";
+
+/// The size after which an encoded chunk of entries is flushed.
+pub(crate) const CHUNK_SIZE: usize = 64 * 1024;
+
+pub(crate) fn encode_header(out: &mut Vec<u8>) {
+  out.extend_from_slice(MAGIC);
+}
+
+pub(crate) fn encode_entry(entry: &KvEntry, out: &mut Vec<u8>) {
+  let (value, encoding) = encode_value(&entry.value);
+  out.extend_from_slice(&(entry.key.len() as u32).to_be_bytes());
+  out.extend_from_slice(&entry.key);
+  out.extend_from_slice(&entry.versionstamp);
+  out.push(encoding as u8);
+  out.extend_from_slice(&(value.len() as u32).to_be_bytes());
+  out.extend_from_slice(&value);
+}
+
+fn invalid_backup() -> AnyError {
+  type_error("Invalid Deno KV backup")
+}
+
+/// Decodes the entries of a backup from the chunks it is read in.
+#[derive(Default)]
+pub(crate) struct BackupDecoder {
+  buf: Vec<u8>,
+  header_read: bool,
+}
+
+impl BackupDecoder {
+  /// Returns the entries that are complete once `chunk` was read.
+  pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<KvEntry>, AnyError> {
+    self.buf.extend_from_slice(chunk);
+    let mut pos = 0;
+    if !self.header_read {
+      if self.buf.len() < MAGIC.len() {
+        return Ok(vec![]);
+      }
+      if &self.buf[..MAGIC.len()] != MAGIC {
+        return Err(invalid_backup());
+      }
+      self.header_read = true;
+      pos = MAGIC.len();
+    }
+
+    let mut entries = vec![];
+    while let Some((entry, len)) = decode_entry(&self.buf[pos..])? {
+      entries.push(entry);
+      pos += len;
+    }
+    self.buf.drain(..pos);
+    Ok(entries)
+  }
+
+  /// Fails if the backup ended in the middle of an entry.
+  pub fn finish(&self) -> Result<(), AnyError> {
+    if self.header_read && self.buf.is_empty() {
+      Ok(())
+    } else {
+      Err(invalid_backup())
+    }
+  }
+}
+
+/// Decodes the entry at the start of `buf`, and returns it with its encoded
+/// length, unless `buf` ends before the entry does.
+fn decode_entry(buf: &[u8]) -> Result<Option<(KvEntry, usize)>, AnyError> {
+  fn take<'a>(buf: &'a [u8], pos: &mut usize, len: usize) -> Option<&'a [u8]> {
+    let bytes = buf.get(*pos..*pos + len)?;
+    *pos += len;
+    Some(bytes)
+  }
+  fn take_u32(buf: &[u8], pos: &mut usize) -> Option<usize> {
+    let bytes = take(buf, pos, 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().unwrap()) as usize)
+  }
+
+  let mut pos = 0;
+  let Some(key_len) = take_u32(buf, &mut pos) else {
+    return Ok(None);
+  };
+  let Some(key) = take(buf, &mut pos, key_len) else {
+    return Ok(None);
+  };
+  let Some(versionstamp) = take(buf, &mut pos, 10) else {
+    return Ok(None);
+  };
+  let Some(&[encoding]) = take(buf, &mut pos, 1) else {
+    return Ok(None);
+  };
+  let Some(value_len) = take_u32(buf, &mut pos) else {
+    return Ok(None);
+  };
+  let Some(value) = take(buf, &mut pos, value_len) else {
+    return Ok(None);
+  };
+
+  if !index::is_record_key(key) && (key.is_empty() || decode_key(key).is_err())
+  {
+    return Err(invalid_backup());
+  }
+  let encoding = encoding as i64;
+  if encoding == VALUE_ENCODING_LE64 && value.len() != 8 {
+    return Err(invalid_backup());
+  }
+  let value =
+    decode_value(value.to_vec(), encoding).ok_or_else(invalid_backup)?;
+  let entry = KvEntry {
+    key: key.to_vec(),
+    value,
+    versionstamp: versionstamp.try_into().unwrap(),
+  };
+  Ok(Some((entry, pos)))
+}
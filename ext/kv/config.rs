@@ -1,5 +1,7 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+use std::time::Duration;
+
 pub struct KvConfig {
   pub(crate) max_write_key_size_bytes: usize,
   pub(crate) max_read_key_size_bytes: usize,
@@ -11,6 +13,7 @@ pub struct KvConfig {
   pub(crate) max_watched_keys: usize,
   pub(crate) max_total_mutation_size_bytes: usize,
   pub(crate) max_total_key_size_bytes: usize,
+  pub(crate) range_watch_poll_interval: Duration,
 }
 
 impl KvConfig {
@@ -30,6 +33,7 @@ pub struct KvConfigBuilder {
   max_watched_keys: Option<usize>,
   max_total_mutation_size_bytes: Option<usize>,
   max_total_key_size_bytes: Option<usize>,
+  range_watch_poll_interval: Option<Duration>,
 }
 
 impl KvConfigBuilder {
@@ -94,6 +98,16 @@ impl KvConfigBuilder {
     self
   }
 
+  /// How often range watchers re-read their range, to pick up the writes of
+  /// other processes.
+  pub fn range_watch_poll_interval(
+    &mut self,
+    range_watch_poll_interval: Duration,
+  ) -> &mut Self {
+    self.range_watch_poll_interval = Some(range_watch_poll_interval);
+    self
+  }
+
   pub fn build(&self) -> KvConfig {
     const MAX_WRITE_KEY_SIZE_BYTES: usize = 2048;
     // range selectors can contain 0x00 or 0xff suffixes
@@ -106,6 +120,7 @@ impl KvConfigBuilder {
     const MAX_WATCHED_KEYS: usize = 10;
     const MAX_TOTAL_MUTATION_SIZE_BYTES: usize = 800 * 1024;
     const MAX_TOTAL_KEY_SIZE_BYTES: usize = 80 * 1024;
+    const RANGE_WATCH_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
     KvConfig {
       max_write_key_size_bytes: self
@@ -131,6 +146,9 @@ impl KvConfigBuilder {
       max_total_key_size_bytes: self
         .max_total_key_size_bytes
         .unwrap_or(MAX_TOTAL_KEY_SIZE_BYTES),
+      range_watch_poll_interval: self
+        .range_watch_poll_interval
+        .unwrap_or(RANGE_WATCH_POLL_INTERVAL),
     }
   }
 }
@@ -0,0 +1,281 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Secondary indexes that are maintained in the commit path.
+//!
+//! A set mutation can declare the index keys of its entry, which are set to
+//! the same value as the entry. The index keys of every indexed entry are
+//! stored in an index record of its own, under a key that no user key starts
+//! with, so that index keys that an entry no longer declares, or all of them
+//! when the entry is deleted, are deleted in the same atomic write. The index
+//! records are checked in that write, and the write is retried when another
+//! write changed them in the meantime.
+
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+
+use anyhow::Context;
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use denokv_proto::AtomicWrite;
+use denokv_proto::Check;
+use denokv_proto::CommitResult;
+use denokv_proto::Consistency;
+use denokv_proto::Database;
+use denokv_proto::Enqueue;
+use denokv_proto::KvValue;
+use denokv_proto::Mutation;
+use denokv_proto::MutationKind;
+use denokv_proto::ReadRange;
+use denokv_proto::SnapshotReadOptions;
+use denokv_proto::Versionstamp;
+
+/// The prefix of the keys of index records. Encoded user keys never start
+/// with 0xff.
+pub(crate) const RECORD_PREFIX: &[u8] = b"\xffindex\x00";
+
+pub(crate) fn is_record_key(key: &[u8]) -> bool {
+  key.starts_with(RECORD_PREFIX)
+}
+
+fn record_key(key: &[u8]) -> Vec<u8> {
+  [RECORD_PREFIX, key].concat()
+}
+
+/// The index keys of an entry, each prefixed with its length as a big endian
+/// `u32`.
+fn encode_record(index_keys: &[Vec<u8>]) -> KvValue {
+  let mut buf = vec![];
+  for key in index_keys {
+    buf.extend_from_slice(&(key.len() as u32).to_be_bytes());
+    buf.extend_from_slice(key);
+  }
+  KvValue::Bytes(buf)
+}
+
+fn decode_record(value: &KvValue) -> Result<IndexKeys, AnyError> {
+  let KvValue::Bytes(buf) = value else {
+    return Err(type_error("invalid index record"));
+  };
+  let mut buf = buf.as_slice();
+  let mut keys = vec![];
+  while !buf.is_empty() {
+    let len = buf
+      .get(..4)
+      .map(|len| u32::from_be_bytes(len.try_into().unwrap()) as usize)
+      .filter(|len| buf.len() >= 4 + len)
+      .context("invalid index record")?;
+    keys.push(buf[4..4 + len].to_vec());
+    buf = &buf[4 + len..];
+  }
+  Ok(keys)
+}
+
+/// The encoded index keys of an entry.
+pub(crate) type IndexKeys = Vec<Vec<u8>>;
+
+/// The index keys a set or delete mutation declares for its entry.
+pub(crate) struct IndexedMutation {
+  /// The position of the mutation in the atomic write.
+  pub position: usize,
+  /// Empty for deletes.
+  pub index_keys: IndexKeys,
+}
+
+/// The index keys that the index records store, with their versionstamps.
+type Records = HashMap<Vec<u8>, Option<(IndexKeys, Versionstamp)>>;
+
+async fn read_records<DB: Database>(
+  db: &DB,
+  keys: impl Iterator<Item = &[u8]>,
+) -> Result<Records, AnyError> {
+  let keys = keys.map(<[u8]>::to_vec).collect::<Vec<_>>();
+  let ranges = keys
+    .iter()
+    .map(|key| {
+      let start = record_key(key);
+      let end = start.iter().copied().chain(Some(0)).collect();
+      ReadRange {
+        start,
+        end,
+        limit: NonZeroU32::new(1).unwrap(),
+        reverse: false,
+      }
+    })
+    .collect();
+  let opts = SnapshotReadOptions {
+    consistency: Consistency::Strong,
+  };
+  let outputs = db.snapshot_read(ranges, opts).await?;
+  let mut records = HashMap::new();
+  for (key, output) in keys.into_iter().zip(outputs) {
+    let record = match output.entries.first() {
+      Some(entry) => Some((decode_record(&entry.value)?, entry.versionstamp)),
+      None => None,
+    };
+    records.insert(key, record);
+  }
+  Ok(records)
+}
+
+fn clone_value(value: &KvValue) -> KvValue {
+  match value {
+    KvValue::V8(buf) => KvValue::V8(buf.clone()),
+    KvValue::Bytes(buf) => KvValue::Bytes(buf.clone()),
+    KvValue::U64(n) => KvValue::U64(*n),
+  }
+}
+
+fn clone_mutation(mutation: &Mutation) -> Mutation {
+  let kind = match &mutation.kind {
+    MutationKind::Set(value) => MutationKind::Set(clone_value(value)),
+    MutationKind::Delete => MutationKind::Delete,
+    MutationKind::Sum {
+      value,
+      min_v8,
+      max_v8,
+      clamp,
+    } => MutationKind::Sum {
+      value: clone_value(value),
+      min_v8: min_v8.clone(),
+      max_v8: max_v8.clone(),
+      clamp: *clamp,
+    },
+    MutationKind::Min(value) => MutationKind::Min(clone_value(value)),
+    MutationKind::Max(value) => MutationKind::Max(clone_value(value)),
+    MutationKind::SetSuffixVersionstampedKey(value) => {
+      MutationKind::SetSuffixVersionstampedKey(clone_value(value))
+    }
+  };
+  Mutation {
+    key: mutation.key.clone(),
+    kind,
+    expire_at: mutation.expire_at,
+  }
+}
+
+/// Adds the checks of the index records, and the mutations of the index keys
+/// and records, to a copy of `write`.
+fn expand(
+  write: &AtomicWrite,
+  indexed: &[IndexedMutation],
+  records: &Records,
+) -> AtomicWrite {
+  let mut checks = write
+    .checks
+    .iter()
+    .map(|check| Check {
+      key: check.key.clone(),
+      versionstamp: check.versionstamp,
+    })
+    .collect::<Vec<_>>();
+  let mut mutations = write
+    .mutations
+    .iter()
+    .map(clone_mutation)
+    .collect::<Vec<_>>();
+  for (key, record) in records {
+    checks.push(Check {
+      key: record_key(key),
+      versionstamp: record.as_ref().map(|(_, versionstamp)| *versionstamp),
+    });
+  }
+
+  // An entry may be mutated more than once in the same write.
+  let mut current = records
+    .iter()
+    .map(|(key, record)| {
+      let index_keys = record.as_ref().map(|(keys, _)| keys.clone());
+      (key.clone(), index_keys.unwrap_or_default())
+    })
+    .collect::<HashMap<_, _>>();
+  for IndexedMutation {
+    position,
+    index_keys,
+  } in indexed
+  {
+    let mutation = &write.mutations[*position];
+    let previous = current.insert(mutation.key.clone(), index_keys.clone());
+    for key in previous.unwrap_or_default() {
+      if !index_keys.contains(&key) {
+        mutations.push(Mutation {
+          key,
+          kind: MutationKind::Delete,
+          expire_at: None,
+        });
+      }
+    }
+    if let MutationKind::Set(value) = &mutation.kind {
+      for key in index_keys {
+        mutations.push(Mutation {
+          key: key.clone(),
+          kind: MutationKind::Set(clone_value(value)),
+          expire_at: mutation.expire_at,
+        });
+      }
+    }
+    mutations.push(Mutation {
+      key: record_key(&mutation.key),
+      kind: if index_keys.is_empty() {
+        MutationKind::Delete
+      } else {
+        MutationKind::Set(encode_record(index_keys))
+      },
+      expire_at: mutation.expire_at,
+    });
+  }
+
+  let enqueues = write
+    .enqueues
+    .iter()
+    .map(|enqueue| Enqueue {
+      payload: enqueue.payload.clone(),
+      deadline: enqueue.deadline,
+      keys_if_undelivered: enqueue.keys_if_undelivered.clone(),
+      backoff_schedule: enqueue.backoff_schedule.clone(),
+    })
+    .collect();
+  AtomicWrite {
+    checks,
+    mutations,
+    enqueues,
+  }
+}
+
+/// Runs an atomic write that maintains the indexes of `indexed`, retrying it
+/// when the index records changed between reading and writing them. Calls
+/// `prepare` with every write, including its index mutations, before it is
+/// committed, and returns the result of the call for the write that was
+/// committed last.
+pub(crate) async fn atomic_write<DB: Database, T>(
+  db: &DB,
+  write: AtomicWrite,
+  indexed: Vec<IndexedMutation>,
+  prepare: impl Fn(&AtomicWrite) -> Result<T, AnyError>,
+) -> Result<(Option<CommitResult>, T), AnyError> {
+  if indexed.is_empty() {
+    let prepared = prepare(&write)?;
+    return Ok((db.atomic_write(write).await?, prepared));
+  }
+
+  let keys = || {
+    indexed
+      .iter()
+      .map(|mutation| write.mutations[mutation.position].key.as_slice())
+  };
+  let mut records = read_records(db, keys()).await?;
+  loop {
+    let expanded = expand(&write, &indexed, &records);
+    let prepared = prepare(&expanded)?;
+    let result = db.atomic_write(expanded).await?;
+    if result.is_some() {
+      return Ok((result, prepared));
+    }
+    // The write failed because of a check of the caller, unless the index
+    // records changed.
+    let current = read_records(db, keys()).await?;
+    if current == records {
+      return Ok((None, prepared));
+    }
+    records = current;
+  }
+}
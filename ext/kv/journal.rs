@@ -0,0 +1,136 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! A journal of the keys changed by the atomic writes of this process, per
+//! database. Range watchers use it to re-read their range only once a write
+//! touched it, instead of re-reading it after every write. Writes of other
+//! processes, and keys that expire, are not journaled, so range watchers also
+//! poll their range.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::sync::Weak;
+
+use tokio::sync::watch;
+
+/// The number of changes a journal keeps. Cursors that fall behind further
+/// than that treat their range as changed.
+const CAPACITY: usize = 1024;
+
+/// A range of keys, with an inclusive start and an exclusive end.
+pub(crate) type KeyRange = (Vec<u8>, Vec<u8>);
+
+#[derive(Default)]
+struct Changes {
+  /// The sequence number of the first change in `ranges`.
+  first: u64,
+  ranges: VecDeque<KeyRange>,
+}
+
+impl Changes {
+  fn end(&self) -> u64 {
+    self.first + self.ranges.len() as u64
+  }
+}
+
+pub(crate) struct Journal {
+  changes: Mutex<Changes>,
+  /// The sequence number after the last change.
+  end: watch::Sender<u64>,
+}
+
+impl Default for Journal {
+  fn default() -> Self {
+    Self {
+      changes: Default::default(),
+      end: watch::Sender::new(0),
+    }
+  }
+}
+
+impl Journal {
+  /// The journal of the database at `path`, which is shared by all workers
+  /// that open the same path.
+  pub fn open(path: Option<&str>) -> Arc<Self> {
+    static JOURNALS: OnceLock<Mutex<HashMap<String, Weak<Journal>>>> =
+      OnceLock::new();
+
+    // Every in-memory database is a database of its own.
+    if path == Some(":memory:") {
+      return Arc::default();
+    }
+    let mut journals = JOURNALS.get_or_init(Default::default).lock().unwrap();
+    journals.retain(|_, journal| journal.strong_count() > 0);
+    let key = path.unwrap_or_default().to_string();
+    if let Some(journal) = journals.get(&key).and_then(Weak::upgrade) {
+      return journal;
+    }
+    let journal = Arc::<Self>::default();
+    journals.insert(key, Arc::downgrade(&journal));
+    journal
+  }
+
+  pub fn record(&self, ranges: impl IntoIterator<Item = KeyRange>) {
+    let mut changes = self.changes.lock().unwrap();
+    for range in ranges {
+      changes.ranges.push_back(range);
+      if changes.ranges.len() > CAPACITY {
+        changes.ranges.pop_front();
+        changes.first += 1;
+      }
+    }
+    let end = changes.end();
+    drop(changes);
+    self.end.send_replace(end);
+  }
+
+  /// A cursor at the end of the journal.
+  pub fn cursor(self: &Arc<Self>) -> JournalCursor {
+    let mut receiver = self.end.subscribe();
+    let position = *receiver.borrow_and_update();
+    JournalCursor {
+      journal: self.clone(),
+      position,
+      receiver,
+    }
+  }
+}
+
+pub(crate) struct JournalCursor {
+  journal: Arc<Journal>,
+  position: u64,
+  receiver: watch::Receiver<u64>,
+}
+
+impl JournalCursor {
+  /// Waits for a change after the cursor that overlaps `range`, and moves the
+  /// cursor past it.
+  pub async fn changed(&mut self, (start, end): (&[u8], &[u8])) {
+    loop {
+      // Marked as seen before the journal is read, so that a change that is
+      // recorded after the read wakes the cursor.
+      self.receiver.borrow_and_update();
+      {
+        let changes = self.journal.changes.lock().unwrap();
+        if self.position < changes.first {
+          self.position = changes.end();
+          return;
+        }
+        let skip = (self.position - changes.first) as usize;
+        let overlaps = changes
+          .ranges
+          .iter()
+          .skip(skip)
+          .any(|(s, e)| s.as_slice() < end && start < e.as_slice());
+        self.position = changes.end();
+        if overlaps {
+          return;
+        }
+      }
+      // The sender is kept alive by `self.journal`.
+      _ = self.receiver.changed().await;
+    }
+  }
+}
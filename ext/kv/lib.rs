@@ -1,15 +1,21 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+mod backup;
 pub mod config;
 pub mod dynamic;
+mod index;
 mod interface;
+mod journal;
+mod queue;
 pub mod remote;
 pub mod sqlite;
 
 use std::borrow::Cow;
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::num::NonZeroU32;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::bail;
@@ -51,14 +57,19 @@ use denokv_proto::MutationKind;
 use denokv_proto::QueueMessageHandle;
 use denokv_proto::ReadRange;
 use denokv_proto::SnapshotReadOptions;
+use denokv_proto::Versionstamp;
 use denokv_proto::WatchKeyOutput;
 use denokv_proto::WatchStream;
 use log::debug;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::backup::BackupDecoder;
 pub use crate::config::*;
 pub use crate::interface::*;
+use crate::journal::Journal;
+use crate::journal::JournalCursor;
+use crate::journal::KeyRange;
 
 pub const UNSTABLE_FEATURE_NAME: &str = "kv";
 
@@ -74,6 +85,13 @@ deno_core::extension!(deno_kv,
     op_kv_finish_dequeued_message<DBH>,
     op_kv_watch<DBH>,
     op_kv_watch_next,
+    op_kv_watch_range<DBH>,
+    op_kv_watch_range_next<DBH>,
+    op_kv_export<DBH>,
+    op_kv_export_next,
+    op_kv_import<DBH>,
+    op_kv_import_write<DBH>,
+    op_kv_import_finish<DBH>,
   ],
   esm = [ "01_db.ts" ],
   options = {
@@ -88,6 +106,7 @@ deno_core::extension!(deno_kv,
 
 struct DatabaseResource<DB: Database + 'static> {
   db: DB,
+  journal: Arc<Journal>,
   cancel_handle: Rc<CancelHandle>,
 }
 
@@ -136,9 +155,11 @@ where
       .check_or_exit_with_legacy_fallback(UNSTABLE_FEATURE_NAME, "Deno.openKv");
     state.borrow::<Rc<DBH>>().clone()
   };
+  let journal = Journal::open(path.as_deref());
   let db = handler.open(state.clone(), path).await?;
   let rid = state.borrow_mut().resource_table.add(DatabaseResource {
     db,
+    journal,
     cancel_handle: CancelHandle::new_rc(),
   });
   Ok(rid)
@@ -332,11 +353,15 @@ where
   Ok(output_ranges)
 }
 
-struct QueueMessageResource<QPH: QueueMessageHandle + 'static> {
-  handle: QPH,
+struct QueueMessageResource<DB: Database + 'static> {
+  db: DB,
+  journal: Arc<Journal>,
+  /// Taken once the delivery is finished, or failed by the visibility timeout.
+  message: RefCell<Option<queue::DequeuedMessage<DB::QMH>>>,
+  cancel_handle: Rc<CancelHandle>,
 }
 
-impl<QMH: QueueMessageHandle + 'static> Resource for QueueMessageResource<QMH> {
+impl<DB: Database + 'static> Resource for QueueMessageResource<DB> {
   fn name(&self) -> Cow<str> {
     "queueMessage".into()
   }
@@ -351,7 +376,7 @@ async fn op_kv_dequeue_next_message<DBH>(
 where
   DBH: DatabaseHandler + 'static,
 {
-  let db = {
+  let (db, journal) = {
     let state = state.borrow();
     let resource =
       match state.resource_table.get::<DatabaseResource<DBH::DB>>(rid) {
@@ -364,17 +389,57 @@ where
           }
         }
       };
-    resource.db.clone()
+    (resource.db.clone(), resource.journal.clone())
   };
 
   let Some(mut handle) = db.dequeue_next_message().await? else {
     return Ok(None);
   };
-  let payload = handle.take_payload().await?.into();
+  let (options, payload) = queue::decode_message(handle.take_payload().await?)?;
+  let message = queue::DequeuedMessage {
+    handle,
+    options,
+    payload,
+  };
+  let payload = message.payload.clone().into();
+  let visibility_timeout = message.options.visibility_timeout;
+  let cancel_handle = CancelHandle::new_rc();
   let handle_rid = {
     let mut state = state.borrow_mut();
-    state.resource_table.add(QueueMessageResource { handle })
+    state.resource_table.add(QueueMessageResource {
+      db,
+      journal,
+      message: RefCell::new(Some(message)),
+      cancel_handle: cancel_handle.clone(),
+    })
   };
+
+  // Fail the delivery if it is not finished in time.
+  if let Some(timeout) = visibility_timeout {
+    let state = state.clone();
+    deno_core::unsync::spawn(async move {
+      let sleep = tokio::time::sleep(Duration::from_millis(timeout as u64));
+      if sleep.or_cancel(cancel_handle).await.is_err() {
+        return;
+      }
+      let Ok(resource) = state
+        .borrow()
+        .resource_table
+        .get::<QueueMessageResource<DBH::DB>>(handle_rid)
+      else {
+        return;
+      };
+      let Some(message) = resource.message.borrow_mut().take() else {
+        return;
+      };
+      if let Err(err) =
+        queue::finish(&resource.db, &resource.journal, message, false).await
+      {
+        debug!("Failed to finish timed out message: {}", err);
+      }
+    });
+  }
+
   Ok(Some((payload, handle_rid)))
 }
 
@@ -475,6 +540,380 @@ async fn op_kv_watch_next(
   Ok(Some(entries))
 }
 
+struct DatabaseRangeWatcherResource<DB: Database + 'static> {
+  db: DB,
+  start: Vec<u8>,
+  end: Vec<u8>,
+  limit: NonZeroU32,
+  raw: bool,
+  state: AsyncRefCell<RangeWatchState>,
+  db_cancel_handle: Rc<CancelHandle>,
+  cancel_handle: Rc<CancelHandle>,
+}
+
+struct RangeWatchState {
+  cursor: JournalCursor,
+  /// The keys and versionstamps of the entries that were returned last.
+  last: Option<Vec<(Vec<u8>, Versionstamp)>>,
+}
+
+impl<DB: Database + 'static> Resource for DatabaseRangeWatcherResource<DB> {
+  fn name(&self) -> Cow<str> {
+    "databaseRangeWatcher".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel_handle.cancel()
+  }
+}
+
+/// The keys a mutation may change. Keys with a versionstamp suffix are only
+/// known once the write was committed, so all keys that start with the key
+/// of the mutation are included.
+fn changed_key_range(mutation: &Mutation) -> KeyRange {
+  let suffix = match mutation.kind {
+    MutationKind::SetSuffixVersionstampedKey(_) => 0xff,
+    _ => 0,
+  };
+  let end = mutation.key.iter().copied().chain(Some(suffix)).collect();
+  (mutation.key.clone(), end)
+}
+
+#[op2]
+#[smi]
+fn op_kv_watch_range<DBH>(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[serde] (prefix, start, end): EncodeCursorRangeSelector,
+  #[smi] limit: u32,
+  raw: bool,
+) -> Result<ResourceId, AnyError>
+where
+  DBH: DatabaseHandler + 'static,
+{
+  let resource = state.resource_table.get::<DatabaseResource<DBH::DB>>(rid)?;
+  let config = state.borrow::<Rc<KvConfig>>().clone();
+
+  if limit as usize > config.max_read_entries {
+    return Err(type_error(format!(
+      "too many entries (max {})",
+      config.max_read_entries
+    )));
+  }
+  let limit = NonZeroU32::new(limit)
+    .ok_or_else(|| type_error("limit must be greater than 0"))?;
+
+  let selector = RawSelector::from_tuple(prefix, start, end)?;
+  let start = selector.range_start_key();
+  let end = selector.range_end_key();
+  check_read_key_size(&start, &config)?;
+  check_read_key_size(&end, &config)?;
+
+  let rid = state.resource_table.add(DatabaseRangeWatcherResource {
+    db: resource.db.clone(),
+    start,
+    end,
+    limit,
+    raw,
+    state: AsyncRefCell::new(RangeWatchState {
+      cursor: resource.journal.cursor(),
+      last: None,
+    }),
+    db_cancel_handle: resource.cancel_handle.clone(),
+    cancel_handle: CancelHandle::new_rc(),
+  });
+
+  Ok(rid)
+}
+
+/// Reads the range of a watcher once it may have changed, until its entries
+/// differ from the ones that were returned last.
+async fn next_range_entries<DB: Database + 'static>(
+  resource: Rc<DatabaseRangeWatcherResource<DB>>,
+  poll_interval: Duration,
+) -> Result<Vec<KvEntry>, AnyError> {
+  let mut state = RcRef::map(&resource, |r| &r.state).borrow_mut().await;
+  let range = (resource.start.as_slice(), resource.end.as_slice());
+  loop {
+    let mut journaled = false;
+    if state.last.is_some() {
+      journaled = tokio::select! {
+        _ = state.cursor.changed(range) => true,
+        _ = tokio::time::sleep(poll_interval) => false,
+      };
+    }
+
+    let read_range = ReadRange {
+      start: resource.start.clone(),
+      end: resource.end.clone(),
+      limit: resource.limit,
+      reverse: false,
+    };
+    let opts = SnapshotReadOptions {
+      consistency: Consistency::Strong,
+    };
+    let entries = resource
+      .db
+      .snapshot_read(vec![read_range], opts)
+      .await?
+      .pop()
+      .map(|output| output.entries)
+      .unwrap_or_default();
+    let keys = entries
+      .iter()
+      .map(|entry| (entry.key.clone(), entry.versionstamp))
+      .collect::<Vec<_>>();
+    if (resource.raw && journaled) || state.last.as_ref() != Some(&keys) {
+      state.last = Some(keys);
+      return Ok(entries);
+    }
+  }
+}
+
+#[op2(async)]
+#[serde]
+async fn op_kv_watch_range_next<DBH>(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<Vec<ToV8KvEntry>>, AnyError>
+where
+  DBH: DatabaseHandler + 'static,
+{
+  let (resource, poll_interval) = {
+    let state = state.borrow();
+    let resource = state
+      .resource_table
+      .get::<DatabaseRangeWatcherResource<DBH::DB>>(rid)?;
+    let config = state.borrow::<Rc<KvConfig>>();
+    (resource, config.range_watch_poll_interval)
+  };
+
+  let db_cancel_handle = resource.db_cancel_handle.clone();
+  let cancel_handle = resource.cancel_handle.clone();
+  let Ok(Ok(entries)) = next_range_entries(resource, poll_interval)
+    .or_cancel(db_cancel_handle)
+    .or_cancel(cancel_handle)
+    .await
+  else {
+    return Ok(None);
+  };
+
+  let entries = entries?
+    .into_iter()
+    .map(TryInto::try_into)
+    .collect::<Result<_, AnyError>>()?;
+  Ok(Some(entries))
+}
+
+struct DatabaseExportResource {
+  entries: RefCell<std::vec::IntoIter<KvEntry>>,
+  header_written: Cell<bool>,
+}
+
+impl Resource for DatabaseExportResource {
+  fn name(&self) -> Cow<str> {
+    "databaseExport".into()
+  }
+}
+
+/// Reads a snapshot of all entries of a database, to be encoded as a backup
+/// by `op_kv_export_next`. The snapshot is read in one go, so that it is
+/// consistent.
+#[op2(async)]
+#[smi]
+async fn op_kv_export<DBH>(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<ResourceId, AnyError>
+where
+  DBH: DatabaseHandler + 'static,
+{
+  let db = {
+    let state = state.borrow();
+    let resource =
+      state.resource_table.get::<DatabaseResource<DBH::DB>>(rid)?;
+    resource.db.clone()
+  };
+
+  // Encoded keys never start with 0xff, while index records do.
+  let range = |start: &[u8], end: &[u8]| ReadRange {
+    start: start.to_vec(),
+    end: end.to_vec(),
+    limit: NonZeroU32::MAX,
+    reverse: false,
+  };
+  let record_end = [index::RECORD_PREFIX, &[0xff]].concat();
+  let read_ranges = vec![
+    range(&[], &[0xff]),
+    range(index::RECORD_PREFIX, &record_end),
+  ];
+  let opts = SnapshotReadOptions {
+    consistency: Consistency::Strong,
+  };
+  let entries = db
+    .snapshot_read(read_ranges, opts)
+    .await?
+    .into_iter()
+    .flat_map(|output| output.entries)
+    .collect::<Vec<_>>();
+
+  let rid = state
+    .borrow_mut()
+    .resource_table
+    .add(DatabaseExportResource {
+      entries: RefCell::new(entries.into_iter()),
+      header_written: Cell::new(false),
+    });
+  Ok(rid)
+}
+
+/// Encodes the next chunk of a backup, or returns `None` once all entries
+/// were encoded.
+#[op2]
+#[serde]
+fn op_kv_export_next(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<Option<ToJsBuffer>, AnyError> {
+  let resource = state.resource_table.get::<DatabaseExportResource>(rid)?;
+  let mut chunk = vec![];
+  if !resource.header_written.replace(true) {
+    backup::encode_header(&mut chunk);
+  }
+  let mut entries = resource.entries.borrow_mut();
+  while chunk.len() < backup::CHUNK_SIZE {
+    let Some(entry) = entries.next() else {
+      break;
+    };
+    backup::encode_entry(&entry, &mut chunk);
+  }
+  if chunk.is_empty() {
+    return Ok(None);
+  }
+  Ok(Some(chunk.into()))
+}
+
+struct DatabaseImportResource<DB: Database + 'static> {
+  db: DB,
+  journal: Arc<Journal>,
+  decoder: RefCell<BackupDecoder>,
+}
+
+impl<DB: Database + 'static> Resource for DatabaseImportResource<DB> {
+  fn name(&self) -> Cow<str> {
+    "databaseImport".into()
+  }
+}
+
+#[op2(fast)]
+#[smi]
+fn op_kv_import<DBH>(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<ResourceId, AnyError>
+where
+  DBH: DatabaseHandler + 'static,
+{
+  let resource = state.resource_table.get::<DatabaseResource<DBH::DB>>(rid)?;
+  let rid = state.resource_table.add(DatabaseImportResource {
+    db: resource.db.clone(),
+    journal: resource.journal.clone(),
+    decoder: Default::default(),
+  });
+  Ok(rid)
+}
+
+/// Writes the entries of a backup that are complete once `chunk` was read,
+/// in as few atomic writes as the limits of the database allow, and returns
+/// how many entries were written.
+#[op2(async)]
+#[number]
+async fn op_kv_import_write<DBH>(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  #[buffer] chunk: JsBuffer,
+) -> Result<u64, AnyError>
+where
+  DBH: DatabaseHandler + 'static,
+{
+  let (resource, config) = {
+    let state = state.borrow();
+    let resource = state
+      .resource_table
+      .get::<DatabaseImportResource<DBH::DB>>(rid)?;
+    (resource, state.borrow::<Rc<KvConfig>>().clone())
+  };
+  let entries = resource.decoder.borrow_mut().push(&chunk)?;
+  let count = entries.len() as u64;
+
+  let mut batches = vec![vec![]];
+  let mut total_payload_size = 0usize;
+  let mut total_key_size = 0usize;
+  for entry in entries {
+    let key_size = if index::is_record_key(&entry.key) {
+      entry.key.len()
+    } else {
+      check_write_key_size(&entry.key, &config)?
+    };
+    // Keys count twice towards the payload, as in `op_kv_atomic_write`.
+    let payload_size = check_value_size(&entry.value, &config)? + 2 * key_size;
+    let batch = batches.last_mut().unwrap();
+    if batch.len() == config.max_mutations
+      || total_payload_size + payload_size
+        > config.max_total_mutation_size_bytes
+      || total_key_size + key_size > config.max_total_key_size_bytes
+    {
+      batches.push(vec![]);
+      total_payload_size = 0;
+      total_key_size = 0;
+    }
+    total_payload_size += payload_size;
+    total_key_size += key_size;
+    batches.last_mut().unwrap().push(Mutation {
+      key: entry.key,
+      kind: MutationKind::Set(entry.value),
+      expire_at: None,
+    });
+  }
+
+  for mutations in batches {
+    if mutations.is_empty() {
+      continue;
+    }
+    let changed_keys =
+      mutations.iter().map(changed_key_range).collect::<Vec<_>>();
+    let atomic_write = AtomicWrite {
+      checks: vec![],
+      mutations,
+      enqueues: vec![],
+    };
+    resource
+      .db
+      .atomic_write(atomic_write)
+      .await?
+      .context("failed to import entries")?;
+    resource.journal.record(changed_keys);
+  }
+
+  Ok(count)
+}
+
+/// Closes an import, failing if the backup ended in the middle of an entry.
+#[op2(fast)]
+fn op_kv_import_finish<DBH>(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<(), AnyError>
+where
+  DBH: DatabaseHandler + 'static,
+{
+  let resource = state
+    .resource_table
+    .take::<DatabaseImportResource<DBH::DB>>(rid)?;
+  let result = resource.decoder.borrow().finish();
+  result
+}
+
 #[op2(async)]
 async fn op_kv_finish_dequeued_message<DBH>(
   state: Rc<RefCell<OpState>>,
@@ -484,19 +923,21 @@ async fn op_kv_finish_dequeued_message<DBH>(
 where
   DBH: DatabaseHandler + 'static,
 {
-  let handle = {
-    let mut state = state.borrow_mut();
-    let handle = state
-      .resource_table
-      .take::<QueueMessageResource<<<DBH>::DB as Database>::QMH>>(handle_rid)
-      .map_err(|_| type_error("Queue message not found"))?;
-    Rc::try_unwrap(handle)
-      .map_err(|_| type_error("Queue message not found"))?
-      .handle
+  let resource = state
+    .borrow_mut()
+    .resource_table
+    .take::<QueueMessageResource<DBH::DB>>(handle_rid)
+    .map_err(|_| type_error("Queue message not found"))?;
+  resource.cancel_handle.cancel();
+  // the delivery already failed if the visibility timeout expired
+  let Some(message) = resource.message.borrow_mut().take() else {
+    return Ok(());
   };
   // if we fail to finish the message, there is not much we can do and the
   // message will be retried anyway, so we just ignore the error
-  if let Err(err) = handle.finish(success).await {
+  if let Err(err) =
+    queue::finish(&resource.db, &resource.journal, message, success).await
+  {
     debug!("Failed to finish dequeued message: {}", err);
   };
   Ok(())
@@ -523,12 +964,24 @@ fn check_from_v8(value: V8KvCheck) -> Result<Check, AnyError> {
   })
 }
 
-type V8KvMutation = (KvKey, String, Option<FromV8Value>, Option<u64>);
+// (key, type, value, expireIn, indexes)
+type V8KvMutation = (
+  KvKey,
+  String,
+  Option<FromV8Value>,
+  Option<u64>,
+  Option<Vec<KvKey>>,
+);
 
+/// Returns the mutation, and the index keys it declares for its entry.
 fn mutation_from_v8(
   (value, current_timstamp): (V8KvMutation, DateTime<Utc>),
-) -> Result<Mutation, AnyError> {
+) -> Result<(Mutation, Option<index::IndexKeys>), AnyError> {
   let key = encode_v8_key(value.0)?;
+  let index_keys: Option<index::IndexKeys> = value
+    .4
+    .map(|keys| keys.into_iter().map(encode_v8_key).collect())
+    .transpose()?;
   let kind = match (value.1.as_str(), value.2) {
     ("set", Some(value)) => MutationKind::Set(value.try_into()?),
     ("delete", None) => MutationKind::Delete,
@@ -550,31 +1003,103 @@ fn mutation_from_v8(
       return Err(type_error(format!("invalid mutation '{op}' without value")))
     }
   };
-  Ok(Mutation {
+  match (&kind, &index_keys) {
+    (_, None) | (MutationKind::Set(_), _) => {}
+    (MutationKind::Delete, Some(keys)) if keys.is_empty() => {}
+    _ => {
+      return Err(type_error(format!(
+        "invalid mutation '{}' with indexes",
+        value.1
+      )))
+    }
+  }
+  let mutation = Mutation {
     key,
     kind,
     expire_at: value
       .3
       .map(|expire_in| current_timstamp + Duration::from_millis(expire_in)),
-  })
+  };
+  Ok((mutation, index_keys))
 }
 
-type V8Enqueue = (JsBuffer, u64, Vec<KvKey>, Option<Vec<u32>>);
+// (priority, visibilityTimeout, maxDeliveries, deadLetterPrefix)
+type V8DeliveryOptions = (u8, Option<u32>, Option<u32>, Option<KvKey>);
+
+// (payload, delay, keysIfUndelivered, backoffSchedule, deliveryOptions)
+type V8Enqueue = (
+  JsBuffer,
+  u64,
+  Vec<KvKey>,
+  Option<Vec<u32>>,
+  Option<V8DeliveryOptions>,
+);
 
 fn enqueue_from_v8(
   value: V8Enqueue,
   current_timestamp: DateTime<Utc>,
 ) -> Result<Enqueue, AnyError> {
+  let (payload, delay, keys_if_undelivered, backoff_schedule, options) = value;
+  let keys_if_undelivered = keys_if_undelivered
+    .into_iter()
+    .map(encode_v8_key)
+    .collect::<std::io::Result<Vec<_>>>()?;
+  let mut deadline =
+    current_timestamp + chrono::Duration::milliseconds(delay as i64);
+  let Some((priority, visibility_timeout, max_deliveries, dead_letter_prefix)) =
+    options
+  else {
+    return Ok(Enqueue {
+      payload: payload.to_vec(),
+      deadline,
+      keys_if_undelivered,
+      backoff_schedule,
+    });
+  };
+
+  if priority > queue::MAX_PRIORITY || (priority > 0 && delay > 0) {
+    return Err(type_error("invalid priority"));
+  }
+  if visibility_timeout.is_some_and(|timeout| {
+    timeout == 0 || timeout > queue::MAX_VISIBILITY_TIMEOUT_MS
+  }) {
+    return Err(type_error("invalid visibilityTimeout"));
+  }
+  if max_deliveries.is_some_and(|max| max == 0 || max > queue::MAX_DELIVERIES) {
+    return Err(type_error("invalid maxDeliveries"));
+  }
+  deadline -=
+    chrono::Duration::milliseconds(priority as i64 * queue::PRIORITY_STEP_MS);
+
+  let dead_letter_prefix = dead_letter_prefix.map(encode_v8_key).transpose()?;
+  // Messages with a dead-letter prefix are delivered as many times as the
+  // database would deliver them.
+  let max_deliveries = max_deliveries.or_else(|| {
+    let schedule = backoff_schedule
+      .as_deref()
+      .unwrap_or(&queue::DEFAULT_BACKOFF_SCHEDULE);
+    dead_letter_prefix
+      .as_ref()
+      .map(|_| schedule.len() as u32 + 1)
+  });
+  let payload = if visibility_timeout.is_some() || max_deliveries.is_some() {
+    let options = queue::DeliveryOptions {
+      failed_deliveries: 0,
+      visibility_timeout,
+      max_deliveries,
+      dead_letter_prefix,
+      keys_if_undelivered: keys_if_undelivered.clone(),
+      backoff_schedule: backoff_schedule.clone(),
+    };
+    queue::encode_message(&options, &payload)
+  } else {
+    payload.to_vec()
+  };
   Ok(Enqueue {
-    payload: value.0.to_vec(),
-    deadline: current_timestamp
-      + chrono::Duration::milliseconds(value.1 as i64),
-    keys_if_undelivered: value
-      .2
-      .into_iter()
-      .map(encode_v8_key)
-      .collect::<std::io::Result<_>>()?,
-    backoff_schedule: value.3,
+    payload,
+    deadline,
+    keys_if_undelivered,
+    backoff_schedule,
   })
 }
 
@@ -774,11 +1299,11 @@ where
   DBH: DatabaseHandler + 'static,
 {
   let current_timestamp = chrono::Utc::now();
-  let db = {
+  let (db, journal) = {
     let state = state.borrow();
     let resource =
       state.resource_table.get::<DatabaseResource<DBH::DB>>(rid)?;
-    resource.db.clone()
+    (resource.db.clone(), resource.journal.clone())
   };
 
   let config = {
@@ -805,44 +1330,101 @@ where
     .map(check_from_v8)
     .collect::<Result<Vec<Check>, AnyError>>()
     .with_context(|| "invalid check")?;
-  let mutations = mutations
+  let (mutations, index_keys): (Vec<_>, Vec<_>) = mutations
     .into_iter()
     .map(|mutation| mutation_from_v8((mutation, current_timestamp)))
-    .collect::<Result<Vec<Mutation>, AnyError>>()
-    .with_context(|| "invalid mutation")?;
+    .collect::<Result<Vec<_>, AnyError>>()
+    .with_context(|| "invalid mutation")?
+    .into_iter()
+    .unzip();
   let enqueues = enqueues
     .into_iter()
     .map(|e| enqueue_from_v8(e, current_timestamp))
     .collect::<Result<Vec<Enqueue>, AnyError>>()
     .with_context(|| "invalid enqueue")?;
 
+  let indexed = index_keys
+    .into_iter()
+    .enumerate()
+    .filter_map(|(position, index_keys)| {
+      Some(index::IndexedMutation {
+        position,
+        index_keys: index_keys?,
+      })
+    })
+    .collect();
+
+  let atomic_write = AtomicWrite {
+    checks,
+    mutations,
+    enqueues,
+  };
+
+  let (result, changed_keys) =
+    index::atomic_write(&db, atomic_write, indexed, |write| {
+      check_atomic_write(write, &config)?;
+      Ok(
+        write
+          .mutations
+          .iter()
+          .map(changed_key_range)
+          .collect::<Vec<_>>(),
+      )
+    })
+    .await?;
+  if result.is_some() {
+    journal.record(changed_keys);
+  }
+
+  Ok(result.map(|res| faster_hex::hex_string(&res.versionstamp)))
+}
+
+/// Checks the limits of the database against a write, including the index
+/// mutations it maintains.
+fn check_atomic_write(
+  write: &AtomicWrite,
+  config: &KvConfig,
+) -> Result<(), AnyError> {
+  if write.mutations.len() + write.enqueues.len() > config.max_mutations {
+    return Err(type_error(format!(
+      "too many mutations (max {})",
+      config.max_mutations
+    )));
+  }
+
   let mut total_payload_size = 0usize;
   let mut total_key_size = 0usize;
 
-  for key in checks
+  // Index records are keyed by the keys of their entries, which were checked.
+  let is_user_key = |key: &&Vec<u8>| !index::is_record_key(key);
+
+  for key in write
+    .checks
     .iter()
     .map(|c| &c.key)
-    .chain(mutations.iter().map(|m| &m.key))
+    .chain(write.mutations.iter().map(|m| &m.key))
+    .filter(is_user_key)
   {
     if key.is_empty() {
       return Err(type_error("key cannot be empty"));
     }
 
-    total_payload_size += check_write_key_size(key, &config)?;
+    total_payload_size += check_write_key_size(key, config)?;
   }
 
-  for (key, value) in mutations
+  for (key, value) in write
+    .mutations
     .iter()
     .flat_map(|m| m.kind.value().map(|x| (&m.key, x)))
+    .filter(|(key, _)| is_user_key(key))
   {
-    let key_size = check_write_key_size(key, &config)?;
-    total_payload_size += check_value_size(value, &config)? + key_size;
+    let key_size = check_write_key_size(key, config)?;
+    total_payload_size += check_value_size(value, config)? + key_size;
     total_key_size += key_size;
   }
 
-  for enqueue in &enqueues {
-    total_payload_size +=
-      check_enqueue_payload_size(&enqueue.payload, &config)?;
+  for enqueue in &write.enqueues {
+    total_payload_size += check_enqueue_payload_size(&enqueue.payload, config)?;
     if let Some(schedule) = enqueue.backoff_schedule.as_ref() {
       total_payload_size += 4 * schedule.len();
     }
@@ -862,15 +1444,7 @@ where
     )));
   }
 
-  let atomic_write = AtomicWrite {
-    checks,
-    mutations,
-    enqueues,
-  };
-
-  let result = db.atomic_write(atomic_write).await?;
-
-  Ok(result.map(|res| faster_hex::hex_string(&res.versionstamp)))
+  Ok(())
 }
 
 // (prefix, start, end)
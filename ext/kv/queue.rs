@@ -0,0 +1,265 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Delivery options of queue messages that are implemented on top of the
+//! queue of the database: priorities, visibility timeouts, and dead-letter
+//! keys for messages that failed too many deliveries.
+//!
+//! The database delivers the messages that are ready in the order of their
+//! deadlines, so the deadline of a message with a priority is moved back by
+//! `PRIORITY_STEP_MS` per level.
+//!
+//! The other options of a message are stored in an envelope around its
+//! payload. Payloads are serialized by V8, so they start with 0xff, while
+//! envelopes start with `ENVELOPE_TAG`. An envelope is encoded as
+//!
+//! - `ENVELOPE_TAG` (1 byte),
+//! - the number of failed deliveries, the visibility timeout and the maximum
+//!   number of deliveries, as big endian `u32`s, where 0 means none,
+//! - the dead-letter prefix, the keys to set if the message is undelivered
+//!   and the backoff schedule, each as a list,
+//! - the payload.
+//!
+//! Lists are prefixed with a flag whether they are present (for optional
+//! lists) and their length as a big endian `u32`, and keys with their length
+//! as a big endian `u32`.
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use denokv_proto::AtomicWrite;
+use denokv_proto::Database;
+use denokv_proto::Enqueue;
+use denokv_proto::KvValue;
+use denokv_proto::Mutation;
+use denokv_proto::MutationKind;
+use denokv_proto::QueueMessageHandle;
+
+use crate::changed_key_range;
+use crate::journal::Journal;
+
+const ENVELOPE_TAG: u8 = 0x01;
+
+pub(crate) const MAX_PRIORITY: u8 = 9;
+pub(crate) const MAX_VISIBILITY_TIMEOUT_MS: u32 = 60 * 60 * 1000; // 1 hour
+pub(crate) const MAX_DELIVERIES: u32 = 100;
+
+/// The time in milliseconds that the deadline of a message is moved back by
+/// per level of priority. It is longer than the maximum delay of messages, so
+/// that a message is delivered before the messages of lower priorities that
+/// are ready.
+pub(crate) const PRIORITY_STEP_MS: i64 = 30 * 24 * 60 * 60 * 1000; // 30 days
+
+/// The backoff schedule of the database for messages that don't have one.
+pub(crate) const DEFAULT_BACKOFF_SCHEDULE: [u32; 5] =
+  [100, 1000, 5000, 30000, 60000];
+
+#[derive(Default)]
+pub(crate) struct DeliveryOptions {
+  /// The number of failed deliveries so far.
+  pub failed_deliveries: u32,
+  /// The time in milliseconds after which an unfinished delivery fails.
+  pub visibility_timeout: Option<u32>,
+  /// Set for messages whose retries are handled here, rather than by the
+  /// database.
+  pub max_deliveries: Option<u32>,
+  /// The prefix of the key that a message is set at once its last delivery
+  /// failed, followed by a versionstamp.
+  pub dead_letter_prefix: Option<Vec<u8>>,
+  pub keys_if_undelivered: Vec<Vec<u8>>,
+  pub backoff_schedule: Option<Vec<u32>>,
+}
+
+pub(crate) fn encode_message(
+  options: &DeliveryOptions,
+  payload: &[u8],
+) -> Vec<u8> {
+  let mut out = vec![ENVELOPE_TAG];
+  out.extend_from_slice(&options.failed_deliveries.to_be_bytes());
+  for value in [options.visibility_timeout, options.max_deliveries] {
+    out.extend_from_slice(&value.unwrap_or(0).to_be_bytes());
+  }
+  let encode_key = |out: &mut Vec<u8>, key: &[u8]| {
+    out.extend_from_slice(&(key.len() as u32).to_be_bytes());
+    out.extend_from_slice(key);
+  };
+  out.push(options.dead_letter_prefix.is_some() as u8);
+  if let Some(prefix) = &options.dead_letter_prefix {
+    encode_key(&mut out, prefix);
+  }
+  out.extend_from_slice(
+    &(options.keys_if_undelivered.len() as u32).to_be_bytes(),
+  );
+  for key in &options.keys_if_undelivered {
+    encode_key(&mut out, key);
+  }
+  out.push(options.backoff_schedule.is_some() as u8);
+  if let Some(schedule) = &options.backoff_schedule {
+    out.extend_from_slice(&(schedule.len() as u32).to_be_bytes());
+    for interval in schedule {
+      out.extend_from_slice(&interval.to_be_bytes());
+    }
+  }
+  out.extend_from_slice(payload);
+  out
+}
+
+fn invalid_message() -> AnyError {
+  type_error("Invalid queue message")
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+  fn take(&mut self, len: usize) -> Result<&'a [u8], AnyError> {
+    if self.0.len() < len {
+      return Err(invalid_message());
+    }
+    let (taken, rest) = self.0.split_at(len);
+    self.0 = rest;
+    Ok(taken)
+  }
+
+  fn u8(&mut self) -> Result<u8, AnyError> {
+    Ok(self.take(1)?[0])
+  }
+
+  fn u32(&mut self) -> Result<u32, AnyError> {
+    Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+  }
+
+  fn flag(&mut self) -> Result<bool, AnyError> {
+    match self.u8()? {
+      0 => Ok(false),
+      1 => Ok(true),
+      _ => Err(invalid_message()),
+    }
+  }
+
+  fn key(&mut self) -> Result<Vec<u8>, AnyError> {
+    let len = self.u32()? as usize;
+    Ok(self.take(len)?.to_vec())
+  }
+}
+
+/// Returns the options and the payload of a message, which has the default
+/// options unless it was enqueued with an envelope.
+pub(crate) fn decode_message(
+  data: Vec<u8>,
+) -> Result<(DeliveryOptions, Vec<u8>), AnyError> {
+  if data.first() != Some(&ENVELOPE_TAG) {
+    return Ok((DeliveryOptions::default(), data));
+  }
+  let mut reader = Reader(&data[1..]);
+  let failed_deliveries = reader.u32()?;
+  let visibility_timeout = Some(reader.u32()?).filter(|v| *v != 0);
+  let max_deliveries = Some(reader.u32()?).filter(|v| *v != 0);
+  let dead_letter_prefix = match reader.flag()? {
+    true => Some(reader.key()?),
+    false => None,
+  };
+  let keys_if_undelivered = (0..reader.u32()?)
+    .map(|_| reader.key())
+    .collect::<Result<_, _>>()?;
+  let backoff_schedule = match reader.flag()? {
+    true => Some(
+      (0..reader.u32()?)
+        .map(|_| reader.u32())
+        .collect::<Result<_, _>>()?,
+    ),
+    false => None,
+  };
+  let options = DeliveryOptions {
+    failed_deliveries,
+    visibility_timeout,
+    max_deliveries,
+    dead_letter_prefix,
+    keys_if_undelivered,
+    backoff_schedule,
+  };
+  Ok((options, reader.0.to_vec()))
+}
+
+pub(crate) struct DequeuedMessage<QMH: QueueMessageHandle> {
+  pub handle: QMH,
+  pub options: DeliveryOptions,
+  pub payload: Vec<u8>,
+}
+
+/// Finishes the delivery of a message. A failed delivery of a message whose
+/// retries are handled here enqueues the message again, or sets it at its
+/// dead-letter and undelivered keys once it was delivered the maximum number
+/// of times. Either way, the message is finished successfully in the
+/// database afterwards, so that the database only retries it if this process
+/// dies before.
+pub(crate) async fn finish<DB: Database>(
+  db: &DB,
+  journal: &Journal,
+  message: DequeuedMessage<DB::QMH>,
+  success: bool,
+) -> Result<(), AnyError> {
+  let DequeuedMessage {
+    handle,
+    options,
+    payload,
+  } = message;
+  let Some(max_deliveries) = options.max_deliveries.filter(|_| !success) else {
+    return handle.finish(success).await;
+  };
+
+  let failed_deliveries = options.failed_deliveries + 1;
+  let write = if failed_deliveries < max_deliveries {
+    let backoff_schedule = options
+      .backoff_schedule
+      .as_deref()
+      .unwrap_or(&DEFAULT_BACKOFF_SCHEDULE);
+    let backoff_ms = backoff_schedule
+      .get(options.failed_deliveries as usize)
+      .or(backoff_schedule.last())
+      .copied()
+      .unwrap_or(0);
+    let deadline =
+      chrono::Utc::now() + chrono::Duration::milliseconds(backoff_ms as i64);
+    let options = DeliveryOptions {
+      failed_deliveries,
+      ..options
+    };
+    AtomicWrite {
+      checks: vec![],
+      mutations: vec![],
+      enqueues: vec![Enqueue {
+        payload: encode_message(&options, &payload),
+        deadline,
+        keys_if_undelivered: options.keys_if_undelivered.clone(),
+        backoff_schedule: options.backoff_schedule.clone(),
+      }],
+    }
+  } else {
+    let dead_letter = options.dead_letter_prefix.map(|prefix| Mutation {
+      key: prefix,
+      kind: MutationKind::SetSuffixVersionstampedKey(KvValue::V8(
+        payload.clone(),
+      )),
+      expire_at: None,
+    });
+    let undelivered =
+      options.keys_if_undelivered.into_iter().map(|key| Mutation {
+        key,
+        kind: MutationKind::Set(KvValue::V8(payload.clone())),
+        expire_at: None,
+      });
+    AtomicWrite {
+      checks: vec![],
+      mutations: dead_letter.into_iter().chain(undelivered).collect(),
+      enqueues: vec![],
+    }
+  };
+
+  let changed_keys = write
+    .mutations
+    .iter()
+    .map(changed_key_range)
+    .collect::<Vec<_>>();
+  if db.atomic_write(write).await?.is_some() {
+    journal.record(changed_keys);
+  }
+  handle.finish(true).await
+}
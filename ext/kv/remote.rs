@@ -194,6 +194,7 @@ impl<P: RemoteDbHandlerPermissions + 'static> DatabaseHandler
         root_cert_store: options.root_cert_store()?,
         ca_certs: vec![],
         proxy: options.proxy.clone(),
+        no_proxy: None,
         unsafely_ignore_certificate_errors: options
           .unsafely_ignore_certificate_errors
           .clone(),
@@ -204,6 +205,7 @@ impl<P: RemoteDbHandlerPermissions + 'static> DatabaseHandler
           .unwrap(),
         pool_max_idle_per_host: None,
         pool_idle_timeout: None,
+        pool_max_connections_per_host: None,
         http1: false,
         http2: true,
       },
@@ -9,20 +9,27 @@ const {
 } = core;
 import {
   op_dns_resolve,
+  op_dns_resolver_clear_cache,
+  op_dns_resolver_create,
   op_net_accept_tcp,
   op_net_accept_unix,
   op_net_connect_tcp,
   op_net_connect_unix,
+  op_net_connect_unixpacket,
+  op_net_get_socket_option,
   op_net_join_multi_v4_udp,
   op_net_join_multi_v6_udp,
   op_net_leave_multi_v4_udp,
   op_net_leave_multi_v6_udp,
+  op_net_listen_fd,
   op_net_listen_tcp,
   op_net_listen_unix,
   op_net_recv_udp,
   op_net_recv_unixpacket,
   op_net_send_udp,
   op_net_send_unixpacket,
+  op_net_sendfile,
+  op_net_set_socket_option,
   op_net_set_multi_loopback_udp,
   op_net_set_multi_ttl_udp,
   op_set_keepalive,
@@ -43,6 +50,7 @@ const {
   SetPrototypeAdd,
   SetPrototypeDelete,
   SetPrototypeForEach,
+  SetPrototypeHas,
   SymbolAsyncIterator,
   Symbol,
   TypeError,
@@ -63,6 +71,26 @@ async function write(rid, data) {
   return await core.write(rid, data);
 }
 
+function setSocketOption(rid, name, value) {
+  if (typeof value === "boolean") {
+    value = value ? 1 : 0;
+  } else if (!NumberIsInteger(value) || value < 0) {
+    throw new TypeError(`Invalid socket option value: ${value}`);
+  }
+  op_net_set_socket_option(rid, name, value);
+}
+
+const booleanSocketOptions = new SafeSet([
+  "reusePort",
+  "broadcast",
+  "multicastLoopback",
+]);
+
+function getSocketOption(rid, name) {
+  const value = op_net_get_socket_option(rid, name);
+  return SetPrototypeHas(booleanSocketOptions, name) ? value !== 0 : value;
+}
+
 async function resolveDns(query, recordType, options) {
   let cancelRid;
   let abortHandler;
@@ -79,6 +107,7 @@ async function resolveDns(query, recordType, options) {
       query,
       recordType,
       options,
+      resolverRid: options?.resolver?.[internalRidSymbol],
     });
   } finally {
     if (options?.signal) {
@@ -90,6 +119,40 @@ async function resolveDns(query, recordType, options) {
   }
 }
 
+class DnsResolver {
+  #rid = 0;
+
+  constructor(rid) {
+    ObjectDefineProperty(this, internalRidSymbol, {
+      enumerable: false,
+      value: rid,
+    });
+    this.#rid = rid;
+  }
+
+  clearCache() {
+    op_dns_resolver_clear_cache(this.#rid);
+  }
+
+  close() {
+    core.close(this.#rid);
+  }
+
+  [SymbolDispose]() {
+    core.tryClose(this.#rid);
+  }
+}
+
+function createDnsResolver(options = { __proto__: null }) {
+  const rid = op_dns_resolver_create({
+    nameServers: options.nameServers,
+    caCerts: options.caCerts ?? [],
+    cacheSize: options.cacheSize ?? 1024,
+    timeout: options.timeout,
+  });
+  return new DnsResolver(rid);
+}
+
 class Conn {
   #rid = 0;
   #remoteAddr = null;
@@ -236,6 +299,23 @@ class TcpConn extends Conn {
   setKeepAlive(keepAlive = true) {
     return op_set_keepalive(this.#rid, keepAlive);
   }
+
+  async sendFile(file, options = {}) {
+    return await op_net_sendfile(
+      this.#rid,
+      file[internalRidSymbol],
+      options.offset ?? 0,
+      options.length ?? null,
+    );
+  }
+
+  setSocketOption(name, value) {
+    setSocketOption(this.#rid, name, value);
+  }
+
+  getSocketOption(name) {
+    return getSocketOption(this.#rid, name);
+  }
 }
 
 class UnixConn extends Conn {
@@ -294,6 +374,14 @@ class Listener {
     return this.#addr;
   }
 
+  setSocketOption(name, value) {
+    setSocketOption(this.#rid, name, value);
+  }
+
+  getSocketOption(name) {
+    return getSocketOption(this.#rid, name);
+  }
+
   async accept() {
     let promise;
     switch (this.addr.transport) {
@@ -380,6 +468,10 @@ class DatagramConn {
   #promise = null;
 
   constructor(rid, addr, bufSize = UDP_DGRAM_MAXSIZE) {
+    ObjectDefineProperty(this, internalRidSymbol, {
+      enumerable: false,
+      value: rid,
+    });
     this.#rid = rid;
     this.#addr = addr;
     this.bufSize = bufSize;
@@ -389,6 +481,14 @@ class DatagramConn {
     return this.#addr;
   }
 
+  setSocketOption(name, value) {
+    setSocketOption(this.#rid, name, value);
+  }
+
+  getSocketOption(name) {
+    return getSocketOption(this.#rid, name);
+  }
+
   async joinMulticastV4(addr, multiInterface) {
     await op_net_join_multi_v4_udp(
       this.#rid,
@@ -412,6 +512,7 @@ class DatagramConn {
       setTTL: (ttl) =>
         op_net_set_multi_ttl_udp(
           this.#rid,
+          true,
           ttl,
         ),
     };
@@ -437,6 +538,12 @@ class DatagramConn {
           false,
           loopback,
         ),
+      setTTL: (ttl) =>
+        op_net_set_multi_ttl_udp(
+          this.#rid,
+          false,
+          ttl,
+        ),
     };
   }
 
@@ -482,7 +589,7 @@ class DatagramConn {
       case "unixpacket":
         return await op_net_send_unixpacket(
           this.#rid,
-          opts.path,
+          opts?.path ?? null,
           p,
         );
       default:
@@ -558,6 +665,14 @@ function listen(args) {
   }
 }
 
+function listenFd(fd, apiName = "Deno.listen") {
+  if (!NumberIsInteger(fd) || fd < 0) {
+    throw new TypeError(`Invalid file descriptor: ${fd}`);
+  }
+  const { 0: rid, 1: addr } = op_net_listen_fd(fd, apiName);
+  return new Listener(rid, addr);
+}
+
 function validatePort(maybePort) {
   if (typeof maybePort !== "number" && typeof maybePort !== "string") {
     throw new TypeError(`Invalid port (expected number): ${maybePort}`);
@@ -588,6 +703,7 @@ function createListenDatagram(udpOpFn, unixOpFn) {
           },
           args.reuseAddress ?? false,
           args.loopback ?? false,
+          args.broadcast ?? true,
         );
         addr.transport = "udp";
         return new DatagramConn(rid, addr);
@@ -606,6 +722,18 @@ function createListenDatagram(udpOpFn, unixOpFn) {
   };
 }
 
+// deno-lint-ignore require-await
+async function connectDatagram(args) {
+  switch (args.transport) {
+    case "unixpacket": {
+      const { 0: rid, 1: path } = op_net_connect_unixpacket(args.path);
+      return new DatagramConn(rid, { transport: "unixpacket", path });
+    }
+    default:
+      throw new TypeError(`Unsupported transport: '${args.transport}'`);
+  }
+}
+
 async function connect(args) {
   switch (args.transport ?? "tcp") {
     case "tcp": {
@@ -615,6 +743,10 @@ async function connect(args) {
           hostname: args.hostname ?? "127.0.0.1",
           port,
         },
+        {
+          connectionAttemptDelay: args.connectionAttemptDelay,
+          proxy: args.proxy,
+        },
       );
       localAddr.transport = "tcp";
       remoteAddr.transport = "tcp";
@@ -638,9 +770,14 @@ async function connect(args) {
 export {
   Conn,
   connect,
+  connectDatagram,
+  createDnsResolver,
   createListenDatagram,
+  DatagramConn,
+  DnsResolver,
   listen,
   Listener,
+  listenFd,
   listenOptionApiName,
   resolveDns,
   TcpConn,
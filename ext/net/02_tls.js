@@ -10,19 +10,23 @@ import {
   op_tls_cert_resolver_poll,
   op_tls_cert_resolver_resolve,
   op_tls_cert_resolver_resolve_error,
+  op_tls_export_keying_material,
   op_tls_handshake,
   op_tls_key_null,
   op_tls_key_static,
   op_tls_key_static_from_file,
+  op_tls_session_cache_create,
   op_tls_start,
 } from "ext:core/ops";
 const {
   ObjectDefineProperty,
   TypeError,
   SymbolFor,
+  Uint8Array,
 } = primordials;
 
 import { Conn, Listener, validatePort } from "ext:deno_net/01_net.js";
+import { SymbolDispose } from "ext:deno_web/00_infra.js";
 
 class TlsConn extends Conn {
   #rid = 0;
@@ -48,6 +52,35 @@ class TlsConn extends Conn {
   handshake() {
     return op_tls_handshake(this.#rid);
   }
+
+  exportKeyingMaterial(length, label, context = new Uint8Array()) {
+    return op_tls_export_keying_material(this.#rid, label, context, length);
+  }
+}
+
+class TlsSessionCache {
+  #rid = 0;
+
+  constructor(rid) {
+    ObjectDefineProperty(this, internalRidSymbol, {
+      enumerable: false,
+      value: rid,
+    });
+    this.#rid = rid;
+  }
+
+  close() {
+    core.close(this.#rid);
+  }
+
+  [SymbolDispose]() {
+    core.tryClose(this.#rid);
+  }
+}
+
+function createTlsSessionCache(options = { __proto__: null }) {
+  const rid = op_tls_session_cache_create(options.capacity ?? 256);
+  return new TlsSessionCache(rid);
 }
 
 async function connectTls({
@@ -56,6 +89,11 @@ async function connectTls({
   transport = "tcp",
   caCerts = [],
   alpnProtocols = undefined,
+  connectionAttemptDelay = undefined,
+  proxy = undefined,
+  caCertFiles = [],
+  pinnedPublicKeys = [],
+  sessionCache = undefined,
   keyFormat = undefined,
   cert = undefined,
   certFile = undefined,
@@ -96,7 +134,17 @@ async function connectTls({
   const serverName = arguments[0][serverNameSymbol] ?? null;
   const { 0: rid, 1: localAddr, 2: remoteAddr } = await op_net_connect_tls(
     { hostname, port },
-    { certFile: deprecatedCertFile, caCerts, alpnProtocols, serverName },
+    {
+      certFile: deprecatedCertFile,
+      caCerts,
+      alpnProtocols,
+      serverName,
+      connectionAttemptDelay,
+      proxy,
+      caCertFiles,
+      pinnedPublicKeys,
+      sessionCache: sessionCache?.[internalRidSymbol],
+    },
     keyPair,
   );
   localAddr.transport = "tcp";
@@ -254,6 +302,8 @@ function listenTls({
   transport = "tcp",
   alpnProtocols = undefined,
   reusePort = false,
+  clientCaCerts = undefined,
+  requireClientCert = false,
 }) {
   if (transport !== "tcp") {
     throw new TypeError(`Unsupported transport: '${transport}'`);
@@ -268,7 +318,7 @@ function listenTls({
   const keyPair = loadTlsKeyPair("Deno.listenTls", arguments[0]);
   const { 0: rid, 1: localAddr } = op_net_listen_tls(
     { hostname, port },
-    { alpnProtocols, reusePort },
+    { alpnProtocols, reusePort, clientCaCerts, requireClientCert },
     keyPair,
   );
   return new TlsListener(rid, localAddr);
@@ -281,6 +331,9 @@ async function startTls(
     hostname = "127.0.0.1",
     caCerts = [],
     alpnProtocols = undefined,
+    caCertFiles = [],
+    pinnedPublicKeys = [],
+    sessionCache = undefined,
   } = { __proto__: null },
 ) {
   const { 0: rid, 1: localAddr, 2: remoteAddr } = op_tls_start({
@@ -288,6 +341,9 @@ async function startTls(
     hostname,
     caCerts,
     alpnProtocols,
+    caCertFiles,
+    pinnedPublicKeys,
+    sessionCache: sessionCache?.[internalRidSymbol],
   });
   return new TlsConn(rid, remoteAddr, localAddr);
 }
@@ -325,10 +381,12 @@ internals.createTlsKeyResolver = createTlsKeyResolver;
 
 export {
   connectTls,
+  createTlsSessionCache,
   hasTlsKeyPairOptions,
   listenTls,
   loadTlsKeyPair,
   startTls,
   TlsConn,
   TlsListener,
+  TlsSessionCache,
 };
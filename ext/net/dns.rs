@@ -0,0 +1,862 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! DNS resolvers which are created with `Deno.createDnsResolver()`. Unlike
+//! the one-off resolvers of `Deno.resolveDns()`, they keep the answers in a
+//! cache for as long as their TTL allows, and can send the queries over TCP,
+//! TLS (RFC 7858) and HTTPS (RFC 8484) as well as UDP.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use deno_core::anyhow::bail;
+use deno_core::error::custom_error;
+use deno_core::error::generic_error;
+use deno_core::error::invalid_hostname;
+use deno_core::error::AnyError;
+use deno_core::op2;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_tls::create_client_config;
+use deno_tls::rustls::pki_types::ServerName;
+use deno_tls::rustls::ClientConfig;
+use deno_tls::rustls::ClientConnection;
+use deno_tls::SocketUse;
+use deno_tls::TlsKeys;
+use http_body_util::BodyExt;
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::header;
+use hyper::Request;
+use hyper_util::rt::TokioIo;
+use ring::rand::SecureRandom;
+use ring::rand::SystemRandom;
+use serde::Deserialize;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::net::UdpSocket;
+use trust_dns_proto::op::Edns;
+use trust_dns_proto::op::Message;
+use trust_dns_proto::op::MessageType;
+use trust_dns_proto::op::OpCode;
+use trust_dns_proto::op::Query;
+use trust_dns_proto::op::ResponseCode;
+use trust_dns_proto::rr::record_type::RecordType;
+use trust_dns_proto::rr::Name;
+use trust_dns_resolver::config::Protocol;
+use trust_dns_resolver::system_conf;
+
+use crate::ops::rdata_to_return_record;
+use crate::ops::DnsReturnRecord;
+use crate::ops_tls::TlsStream;
+use crate::ops_tls::TLS_BUFFER_SIZE;
+use crate::DefaultTlsOptions;
+use crate::NetPermissions;
+use crate::UnsafelyIgnoreCertificateErrors;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The largest UDP payload which is advertised with EDNS, as recommended by
+/// the DNS flag day 2020.
+const MAX_UDP_PAYLOAD: u16 = 1232;
+
+#[derive(Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DnsProtocol {
+  #[default]
+  Udp,
+  Tcp,
+  Tls,
+  Https,
+}
+
+impl DnsProtocol {
+  fn default_port(self) -> u16 {
+    match self {
+      DnsProtocol::Udp | DnsProtocol::Tcp => 53,
+      DnsProtocol::Tls => 853,
+      DnsProtocol::Https => 443,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NameServerArgs {
+  ip_addr: String,
+  port: Option<u16>,
+  #[serde(default)]
+  protocol: DnsProtocol,
+  server_name: Option<String>,
+  path: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateDnsResolverArgs {
+  name_servers: Option<Vec<NameServerArgs>>,
+  ca_certs: Vec<String>,
+  cache_size: usize,
+  timeout: Option<u64>,
+}
+
+#[derive(Debug)]
+struct NameServer {
+  addr: SocketAddr,
+  protocol: DnsProtocol,
+  server_name: ServerName<'static>,
+  path: String,
+}
+
+impl NameServer {
+  fn new(addr: SocketAddr, protocol: DnsProtocol) -> Self {
+    Self {
+      addr,
+      protocol,
+      server_name: ServerName::IpAddress(addr.ip().into()),
+      path: "/dns-query".to_string(),
+    }
+  }
+}
+
+struct CacheEntry {
+  records: Vec<DnsReturnRecord>,
+  expires: Instant,
+}
+
+/// The answers of earlier queries, which are kept until their TTL runs out.
+/// An entry without records stands for a name which has no records of the
+/// type, as described by RFC 2308.
+pub struct DnsCache {
+  capacity: usize,
+  entries: HashMap<(String, RecordType), CacheEntry>,
+}
+
+impl DnsCache {
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      capacity,
+      entries: HashMap::new(),
+    }
+  }
+
+  fn get(
+    &mut self,
+    key: &(String, RecordType),
+    now: Instant,
+  ) -> Option<Vec<DnsReturnRecord>> {
+    let entry = self.entries.get(key)?;
+    if entry.expires <= now {
+      self.entries.remove(key);
+      return None;
+    }
+    Some(entry.records.clone())
+  }
+
+  fn insert(
+    &mut self,
+    key: (String, RecordType),
+    records: Vec<DnsReturnRecord>,
+    ttl: Duration,
+    now: Instant,
+  ) {
+    if self.capacity == 0 || ttl.is_zero() {
+      return;
+    }
+    if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+      self.entries.retain(|_, entry| entry.expires > now);
+      if self.entries.len() >= self.capacity {
+        // makes room by dropping the entry which would expire first
+        let first = self
+          .entries
+          .iter()
+          .min_by_key(|(_, entry)| entry.expires)
+          .map(|(key, _)| key.clone());
+        if let Some(first) = first {
+          self.entries.remove(&first);
+        }
+      }
+    }
+    let expires = now + ttl;
+    self.entries.insert(key, CacheEntry { records, expires });
+  }
+
+  fn clear(&mut self) {
+    self.entries.clear();
+  }
+}
+
+pub struct DnsResolver {
+  name_servers: Vec<NameServer>,
+  tls_config: Option<Arc<ClientConfig>>,
+  timeout: Duration,
+  cache: RefCell<DnsCache>,
+}
+
+impl Resource for DnsResolver {
+  fn name(&self) -> Cow<str> {
+    "dnsResolver".into()
+  }
+}
+
+impl DnsResolver {
+  /// Resolves the records of `record_type` for `query`, which is always
+  /// treated as a fully qualified name. The name servers are tried in order
+  /// until one of them answers.
+  pub async fn resolve(
+    &self,
+    query: &str,
+    record_type: RecordType,
+  ) -> Result<Vec<DnsReturnRecord>, AnyError> {
+    let mut name = Name::from_utf8(query)?;
+    name.set_fqdn(true);
+    let key = (name.to_lowercase().to_ascii(), record_type);
+    if let Some(records) = self.cache.borrow_mut().get(&key, Instant::now()) {
+      return found(records, query, record_type);
+    }
+
+    let mut last_error = None;
+    for name_server in &self.name_servers {
+      let request = query_message(name.clone(), record_type, name_server)?;
+      let response = match tokio::time::timeout(
+        self.timeout,
+        self.exchange(name_server, &request),
+      )
+      .await
+      {
+        Ok(Ok(response)) => response,
+        Ok(Err(error)) => {
+          last_error = Some(error);
+          continue;
+        }
+        Err(_) => {
+          last_error =
+            Some(custom_error("TimedOut", "The DNS query has timed out"));
+          continue;
+        }
+      };
+      match response.response_code() {
+        ResponseCode::NoError | ResponseCode::NXDomain => {}
+        code => {
+          last_error = Some(generic_error(format!(
+            "The name server {} responded with {code}",
+            name_server.addr
+          )));
+          continue;
+        }
+      }
+
+      let (records, ttl) = answer_records(&response, record_type)?;
+      self.cache.borrow_mut().insert(
+        key,
+        records.clone(),
+        Duration::from_secs(ttl.into()),
+        Instant::now(),
+      );
+      return found(records, query, record_type);
+    }
+    Err(last_error.unwrap_or_else(|| {
+      custom_error("NotConnected", "No name servers are configured")
+    }))
+  }
+
+  pub fn clear_cache(&self) {
+    self.cache.borrow_mut().clear();
+  }
+
+  async fn exchange(
+    &self,
+    name_server: &NameServer,
+    request: &Message,
+  ) -> Result<Message, AnyError> {
+    let message = request.to_vec()?;
+    let response = match name_server.protocol {
+      DnsProtocol::Udp => {
+        let response = udp_exchange(name_server.addr, &message).await?;
+        // answers which don't fit in a datagram have to be asked for again
+        // over TCP
+        if Message::from_vec(&response)?.truncated() {
+          let stream = TcpStream::connect(name_server.addr).await?;
+          stream_exchange(stream, &message).await?
+        } else {
+          response
+        }
+      }
+      DnsProtocol::Tcp => {
+        let stream = TcpStream::connect(name_server.addr).await?;
+        stream_exchange(stream, &message).await?
+      }
+      DnsProtocol::Tls => {
+        let stream = self.connect_tls(name_server, vec![]).await?;
+        stream_exchange(stream, &message).await?
+      }
+      DnsProtocol::Https => {
+        let stream = self
+          .connect_tls(name_server, vec![b"http/1.1".to_vec()])
+          .await?;
+        https_exchange(stream, name_server, message).await?
+      }
+    };
+    let response = Message::from_vec(&response)?;
+    if response.id() != request.id() {
+      bail!("The name server answered a different query");
+    }
+    Ok(response)
+  }
+
+  async fn connect_tls(
+    &self,
+    name_server: &NameServer,
+    alpn_protocols: Vec<Vec<u8>>,
+  ) -> Result<TlsStream, AnyError> {
+    let mut tls_config = ClientConfig::clone(
+      self
+        .tls_config
+        .as_ref()
+        .expect("the TLS config is created for TLS name servers"),
+    );
+    tls_config.alpn_protocols = alpn_protocols;
+    let tcp_stream = TcpStream::connect(name_server.addr).await?;
+    Ok(TlsStream::new_client_side(
+      tcp_stream,
+      ClientConnection::new(
+        Arc::new(tls_config),
+        name_server.server_name.clone(),
+      )?,
+      TLS_BUFFER_SIZE,
+    ))
+  }
+}
+
+fn query_message(
+  name: Name,
+  record_type: RecordType,
+  name_server: &NameServer,
+) -> Result<Message, AnyError> {
+  let mut message = Message::new();
+  // RFC 8484 asks for an id of 0 so that the answers can be cached by HTTP
+  // caches, as the transport tells the answers apart already
+  if name_server.protocol != DnsProtocol::Https {
+    let mut id = [0; 2];
+    SystemRandom::new()
+      .fill(&mut id)
+      .map_err(|_| generic_error("Failed to generate a query id"))?;
+    message.set_id(u16::from_be_bytes(id));
+  }
+  message
+    .set_message_type(MessageType::Query)
+    .set_op_code(OpCode::Query)
+    .set_recursion_desired(true)
+    .add_query(Query::query(name, record_type));
+  let mut edns = Edns::new();
+  edns.set_max_payload(MAX_UDP_PAYLOAD);
+  message.set_edns(edns);
+  Ok(message)
+}
+
+/// Returns the records of the answer, and for how many seconds they can be
+/// cached.
+fn answer_records(
+  response: &Message,
+  record_type: RecordType,
+) -> Result<(Vec<DnsReturnRecord>, u32), AnyError> {
+  let to_return_record = rdata_to_return_record(record_type);
+  let mut records = vec![];
+  for answer in response.answers() {
+    if let Some(record) = answer.data().map(&to_return_record).transpose()? {
+      records.extend(record);
+    }
+  }
+  let ttl = if records.is_empty() {
+    // the TTL of the absence of records comes from the SOA record of the
+    // zone, if the name server sent it
+    response
+      .name_servers()
+      .iter()
+      .find_map(|record| {
+        let soa = record.data()?.as_soa()?;
+        Some(record.ttl().min(soa.minimum()))
+      })
+      .unwrap_or(0)
+  } else {
+    response
+      .answers()
+      .iter()
+      .map(|record| record.ttl())
+      .min()
+      .unwrap_or(0)
+  };
+  Ok((records, ttl))
+}
+
+fn found(
+  records: Vec<DnsReturnRecord>,
+  query: &str,
+  record_type: RecordType,
+) -> Result<Vec<DnsReturnRecord>, AnyError> {
+  if records.is_empty() {
+    return Err(custom_error(
+      "NotFound",
+      format!("No {record_type} records found for {query}"),
+    ));
+  }
+  Ok(records)
+}
+
+async fn udp_exchange(
+  addr: SocketAddr,
+  message: &[u8],
+) -> Result<Vec<u8>, AnyError> {
+  let local_addr: SocketAddr = if addr.is_ipv4() {
+    ([0, 0, 0, 0], 0).into()
+  } else {
+    ([0; 16], 0).into()
+  };
+  let socket = UdpSocket::bind(local_addr).await?;
+  socket.connect(addr).await?;
+  socket.send(message).await?;
+  let mut buf = vec![0; MAX_UDP_PAYLOAD as usize];
+  loop {
+    let len = socket.recv(&mut buf).await?;
+    // datagrams with another id can't be the answer, and are ignored like
+    // `dig` does
+    if len >= 2 && buf[..2] == message[..2] {
+      buf.truncate(len);
+      return Ok(buf);
+    }
+  }
+}
+
+/// Sends the message over a stream, with the length prefix of RFC 1035
+/// section 4.2.2, and reads the answer.
+async fn stream_exchange(
+  mut stream: impl AsyncRead + AsyncWrite + Unpin,
+  message: &[u8],
+) -> Result<Vec<u8>, AnyError> {
+  let mut buf = Vec::with_capacity(message.len() + 2);
+  buf.extend_from_slice(&(message.len() as u16).to_be_bytes());
+  buf.extend_from_slice(message);
+  stream.write_all(&buf).await?;
+  stream.flush().await?;
+  let len = stream.read_u16().await?;
+  let mut response = vec![0; len as usize];
+  stream.read_exact(&mut response).await?;
+  Ok(response)
+}
+
+/// POSTs the message to the name server, as described by RFC 8484.
+async fn https_exchange(
+  stream: TlsStream,
+  name_server: &NameServer,
+  message: Vec<u8>,
+) -> Result<Vec<u8>, AnyError> {
+  let host = match &name_server.server_name {
+    ServerName::DnsName(name) => name.as_ref().to_string(),
+    _ => name_server.addr.ip().to_string(),
+  };
+  let request = Request::post(name_server.path.as_str())
+    .header(header::HOST, host)
+    .header(header::CONTENT_TYPE, "application/dns-message")
+    .header(header::ACCEPT, "application/dns-message")
+    .body(Full::new(Bytes::from(message)))?;
+  let (mut sender, conn) =
+    hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+  let exchange = async move {
+    let response = sender.send_request(request).await?;
+    if !response.status().is_success() {
+      bail!(
+        "The name server {} responded with status {}",
+        name_server.addr,
+        response.status()
+      );
+    }
+    let body = response.into_body().collect().await?.to_bytes();
+    Ok(body.to_vec())
+  };
+  tokio::select! {
+    response = exchange => response,
+    result = conn => {
+      result?;
+      bail!("The name server {} closed the connection", name_server.addr)
+    }
+  }
+}
+
+#[op2]
+#[smi]
+pub fn op_dns_resolver_create<NP>(
+  state: &mut OpState,
+  #[serde] args: CreateDnsResolverArgs,
+) -> Result<ResourceId, AnyError>
+where
+  NP: NetPermissions + 'static,
+{
+  super::check_unstable(state, "Deno.createDnsResolver");
+  let (name_servers, timeout) = match args.name_servers {
+    Some(name_servers) => {
+      let name_servers = name_servers
+        .into_iter()
+        .map(|ns| {
+          let ip = ns.ip_addr.parse::<std::net::IpAddr>()?;
+          let port = ns.port.unwrap_or(ns.protocol.default_port());
+          let mut name_server = NameServer::new((ip, port).into(), ns.protocol);
+          if let Some(server_name) = ns.server_name {
+            name_server.server_name = ServerName::try_from(server_name.clone())
+              .map_err(|_| invalid_hostname(&server_name))?;
+          }
+          if let Some(path) = ns.path {
+            name_server.path = path;
+          }
+          Ok(name_server)
+        })
+        .collect::<Result<Vec<_>, AnyError>>()?;
+      (name_servers, None)
+    }
+    None => {
+      let (config, opts) = system_conf::read_system_conf()?;
+      // the system configuration lists every name server for UDP and TCP,
+      // and UDP falls back to TCP by itself
+      let name_servers = config
+        .name_servers()
+        .iter()
+        .filter(|ns| ns.protocol == Protocol::Udp)
+        .map(|ns| NameServer::new(ns.socket_addr, DnsProtocol::Udp))
+        .collect();
+      (name_servers, Some(opts.timeout))
+    }
+  };
+
+  let permissions = state.borrow_mut::<NP>();
+  for name_server in &name_servers {
+    permissions.check_net(
+      &(
+        name_server.addr.ip().to_string(),
+        Some(name_server.addr.port()),
+      ),
+      "Deno.createDnsResolver()",
+    )?;
+  }
+
+  let needs_tls = name_servers
+    .iter()
+    .any(|ns| matches!(ns.protocol, DnsProtocol::Tls | DnsProtocol::Https));
+  let tls_config = if needs_tls {
+    let root_cert_store =
+      state.borrow::<DefaultTlsOptions>().root_cert_store()?;
+    let unsafely_ignore_certificate_errors = state
+      .try_borrow::<UnsafelyIgnoreCertificateErrors>()
+      .and_then(|it| it.0.clone());
+    let tls_config = create_client_config(
+      root_cert_store,
+      args.ca_certs.into_iter().map(String::into_bytes).collect(),
+      unsafely_ignore_certificate_errors,
+      TlsKeys::Null,
+      SocketUse::GeneralSsl,
+      vec![],
+    )?;
+    Some(Arc::new(tls_config))
+  } else {
+    None
+  };
+
+  let resolver = DnsResolver {
+    name_servers,
+    tls_config,
+    timeout: args
+      .timeout
+      .map(Duration::from_millis)
+      .or(timeout)
+      .unwrap_or(DEFAULT_TIMEOUT),
+    cache: RefCell::new(DnsCache::new(args.cache_size)),
+  };
+  Ok(state.resource_table.add(resolver))
+}
+
+#[op2(fast)]
+pub fn op_dns_resolver_clear_cache(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<(), AnyError> {
+  state.resource_table.get::<DnsResolver>(rid)?.clear_cache();
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use deno_tls::rustls::pki_types::CertificateDer;
+  use deno_tls::rustls::pki_types::PrivateKeyDer;
+  use deno_tls::rustls::ServerConfig;
+  use deno_tls::NoCertificateVerification;
+  use hyper::body::Incoming;
+  use hyper::server::conn::http1;
+  use hyper::service::service_fn;
+  use hyper::Response;
+  use std::sync::atomic::AtomicUsize;
+  use std::sync::atomic::Ordering;
+  use tokio::net::TcpListener;
+  use trust_dns_proto::rr::rdata::A;
+  use trust_dns_proto::rr::rdata::SOA;
+  use trust_dns_proto::rr::RData;
+  use trust_dns_proto::rr::Record;
+
+  fn answer(request: &Message) -> Message {
+    let query = request.queries()[0].clone();
+    let mut response = Message::new();
+    response
+      .set_id(request.id())
+      .set_message_type(MessageType::Response)
+      .set_op_code(OpCode::Query)
+      .add_query(query.clone());
+    let name = query.name().to_ascii();
+    if name == "example.com." {
+      response.add_answer(Record::from_rdata(
+        query.name().clone(),
+        60,
+        RData::A(A::new(127, 0, 0, 1)),
+      ));
+    } else if name == "uncached.example.com." {
+      response.add_answer(Record::from_rdata(
+        query.name().clone(),
+        0,
+        RData::A(A::new(127, 0, 0, 2)),
+      ));
+    } else {
+      response.set_response_code(ResponseCode::NXDomain);
+      let zone = Name::from_ascii("example.com.").unwrap();
+      response.add_name_server(Record::from_rdata(
+        zone.clone(),
+        3600,
+        RData::SOA(SOA::new(zone.clone(), zone, 1, 1, 1, 1, 30)),
+      ));
+    }
+    response
+  }
+
+  /// A name server which answers over UDP and TCP, and counts the queries.
+  /// With `truncate`, it answers over UDP with the TC flag only.
+  async fn name_server(truncate: bool) -> (SocketAddr, Arc<AtomicUsize>) {
+    let udp = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let addr = udp.local_addr().unwrap();
+    let tcp = TcpListener::bind(addr).await.unwrap();
+    let queries = Arc::new(AtomicUsize::new(0));
+
+    let udp_queries = queries.clone();
+    tokio::spawn(async move {
+      let mut buf = vec![0; 512];
+      loop {
+        let (len, from) = udp.recv_from(&mut buf).await.unwrap();
+        udp_queries.fetch_add(1, Ordering::SeqCst);
+        let request = Message::from_vec(&buf[..len]).unwrap();
+        let mut response = answer(&request);
+        if truncate {
+          response.take_answers();
+          response.set_truncated(true);
+        }
+        udp
+          .send_to(&response.to_vec().unwrap(), from)
+          .await
+          .unwrap();
+      }
+    });
+    let tcp_queries = queries.clone();
+    tokio::spawn(async move {
+      loop {
+        let (mut stream, _) = tcp.accept().await.unwrap();
+        tcp_queries.fetch_add(1, Ordering::SeqCst);
+        let len = stream.read_u16().await.unwrap();
+        let mut buf = vec![0; len as usize];
+        stream.read_exact(&mut buf).await.unwrap();
+        let response = answer(&Message::from_vec(&buf).unwrap());
+        let response = response.to_vec().unwrap();
+        stream.write_u16(response.len() as u16).await.unwrap();
+        stream.write_all(&response).await.unwrap();
+      }
+    });
+    (addr, queries)
+  }
+
+  fn resolver(addr: SocketAddr, protocol: DnsProtocol) -> DnsResolver {
+    DnsResolver {
+      name_servers: vec![NameServer::new(addr, protocol)],
+      tls_config: None,
+      timeout: DEFAULT_TIMEOUT,
+      cache: RefCell::new(DnsCache::new(16)),
+    }
+  }
+
+  #[tokio::test]
+  async fn resolve_and_cache() {
+    let (addr, queries) = name_server(false).await;
+    for protocol in [DnsProtocol::Udp, DnsProtocol::Tcp] {
+      queries.store(0, Ordering::SeqCst);
+      let resolver = resolver(addr, protocol);
+      let expected = vec![DnsReturnRecord::A("127.0.0.1".to_string())];
+      let records = resolver.resolve("example.com", RecordType::A).await;
+      assert_eq!(records.unwrap(), expected);
+      let records = resolver.resolve("EXAMPLE.com.", RecordType::A).await;
+      assert_eq!(records.unwrap(), expected);
+      assert_eq!(queries.load(Ordering::SeqCst), 1);
+
+      resolver.clear_cache();
+      let records = resolver.resolve("example.com", RecordType::A).await;
+      assert_eq!(records.unwrap(), expected);
+      assert_eq!(queries.load(Ordering::SeqCst), 2);
+
+      // a TTL of 0 isn't cached
+      for _ in 0..2 {
+        let records = resolver
+          .resolve("uncached.example.com", RecordType::A)
+          .await
+          .unwrap();
+        assert_eq!(records, vec![DnsReturnRecord::A("127.0.0.2".to_string())]);
+      }
+      assert_eq!(queries.load(Ordering::SeqCst), 4);
+
+      // the absence of records is cached with the TTL of the SOA record
+      for _ in 0..2 {
+        let error = resolver
+          .resolve("missing.example.com", RecordType::A)
+          .await
+          .unwrap_err();
+        assert_eq!(
+          error.to_string(),
+          "No A records found for missing.example.com"
+        );
+      }
+      assert_eq!(queries.load(Ordering::SeqCst), 5);
+    }
+  }
+
+  #[tokio::test]
+  async fn truncated_answers_are_asked_for_over_tcp() {
+    let (addr, queries) = name_server(true).await;
+    let resolver = resolver(addr, DnsProtocol::Udp);
+    let records = resolver.resolve("example.com", RecordType::A).await;
+    assert_eq!(
+      records.unwrap(),
+      vec![DnsReturnRecord::A("127.0.0.1".to_string())]
+    );
+    assert_eq!(queries.load(Ordering::SeqCst), 2);
+  }
+
+  /// A name server which answers over TLS, or over HTTPS with `https`.
+  async fn tls_name_server(https: bool) -> SocketAddr {
+    let testdata =
+      std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../tls/testdata");
+    let cert = std::fs::read(testdata.join("example1_cert.der")).unwrap();
+    let key = std::fs::read(testdata.join("example1_prikey.der")).unwrap();
+    let mut config = ServerConfig::builder()
+      .with_no_client_auth()
+      .with_single_cert(
+        vec![CertificateDer::from(cert)],
+        PrivateKeyDer::try_from(key).unwrap(),
+      )
+      .unwrap();
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    let config = Arc::new(config);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+      loop {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut stream =
+          TlsStream::new_server_side(tcp, config.clone(), TLS_BUFFER_SIZE);
+        if !https {
+          let len = stream.read_u16().await.unwrap();
+          let mut buf = vec![0; len as usize];
+          stream.read_exact(&mut buf).await.unwrap();
+          let response = answer(&Message::from_vec(&buf).unwrap());
+          let response = response.to_vec().unwrap();
+          stream.write_u16(response.len() as u16).await.unwrap();
+          stream.write_all(&response).await.unwrap();
+          continue;
+        }
+        let service = service_fn(|request: Request<Incoming>| async move {
+          assert_eq!(request.uri().path(), "/dns-query");
+          assert_eq!(
+            request.headers()[header::CONTENT_TYPE],
+            "application/dns-message"
+          );
+          let body = request.into_body().collect().await?.to_bytes();
+          let response = answer(&Message::from_vec(&body).unwrap());
+          let body = Bytes::from(response.to_vec().unwrap());
+          Ok::<_, hyper::Error>(Response::new(Full::new(body)))
+        });
+        http1::Builder::new()
+          .serve_connection(TokioIo::new(stream), service)
+          .await
+          .unwrap();
+      }
+    });
+    addr
+  }
+
+  #[tokio::test]
+  async fn resolve_over_tls_and_https() {
+    for protocol in [DnsProtocol::Tls, DnsProtocol::Https] {
+      let addr = tls_name_server(protocol == DnsProtocol::Https).await;
+      let mut resolver = resolver(addr, protocol);
+      resolver.name_servers[0].server_name =
+        ServerName::try_from("example1.com").unwrap();
+      let tls_config = ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(
+          NoCertificateVerification::new(vec![]),
+        ))
+        .with_no_client_auth();
+      resolver.tls_config = Some(Arc::new(tls_config));
+      let records = resolver.resolve("example.com", RecordType::A).await;
+      assert_eq!(
+        records.unwrap(),
+        vec![DnsReturnRecord::A("127.0.0.1".to_string())]
+      );
+    }
+  }
+
+  #[tokio::test]
+  async fn unreachable_name_server() {
+    // nothing answers on this socket
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let mut resolver = resolver(socket.local_addr().unwrap(), DnsProtocol::Udp);
+    resolver.timeout = Duration::from_millis(100);
+    let error = resolver
+      .resolve("example.com", RecordType::A)
+      .await
+      .unwrap_err();
+    assert_eq!(error.to_string(), "The DNS query has timed out");
+  }
+
+  #[test]
+  fn cache_expiry_and_capacity() {
+    let mut cache = DnsCache::new(2);
+    let now = Instant::now();
+    let key = |name: &str| (name.to_string(), RecordType::A);
+    let records = vec![DnsReturnRecord::A("127.0.0.1".to_string())];
+
+    cache.insert(key("a."), records.clone(), Duration::from_secs(10), now);
+    cache.insert(key("b."), records.clone(), Duration::from_secs(20), now);
+    assert_eq!(cache.get(&key("a."), now), Some(records.clone()));
+    assert_eq!(cache.get(&key("a."), now + Duration::from_secs(10)), None);
+
+    cache.insert(key("a."), records.clone(), Duration::from_secs(10), now);
+    // "a." expires first, so it makes room for "c."
+    cache.insert(key("c."), records.clone(), Duration::from_secs(30), now);
+    assert_eq!(cache.get(&key("a."), now), None);
+    assert_eq!(cache.get(&key("b."), now), Some(records.clone()));
+    assert_eq!(cache.get(&key("c."), now), Some(records.clone()));
+
+    let mut disabled = DnsCache::new(0);
+    disabled.insert(key("a."), records, Duration::from_secs(10), now);
+    assert_eq!(disabled.get(&key("a."), now), None);
+  }
+}
@@ -0,0 +1,149 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Connecting to a host with both IPv4 and IPv6 addresses by racing the
+//! connection attempts, like RFC 8305 ("Happy Eyeballs") describes, so that
+//! a broken address family doesn't hold up the connection until it times out.
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use deno_core::error::generic_error;
+use deno_core::error::AnyError;
+use deno_core::futures::stream::FuturesUnordered;
+use deno_core::futures::StreamExt;
+use tokio::net::TcpStream;
+
+use crate::resolve_addr::resolve_addr;
+
+/// How long to wait for an attempt before starting the next one, which is
+/// what RFC 8305 recommends.
+pub const DEFAULT_CONNECTION_ATTEMPT_DELAY: Duration =
+  Duration::from_millis(250);
+
+/// Connects to the first of `addrs` which accepts the connection. Attempts
+/// are started one after the other, a `delay` apart or as soon as the one
+/// before failed, and the ones which are still pending once one succeeds are
+/// dropped. Fails with the error of the first attempt if they all fail.
+pub async fn connect(
+  addrs: impl IntoIterator<Item = SocketAddr>,
+  delay: Duration,
+) -> io::Result<TcpStream> {
+  let mut addrs = interleave(addrs).into_iter().peekable();
+  let mut attempts = FuturesUnordered::new();
+  let mut error = None;
+  loop {
+    if let Some(addr) = addrs.next() {
+      attempts.push(TcpStream::connect(addr));
+    }
+    if attempts.is_empty() {
+      return Err(error.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "No resolved address found")
+      }));
+    }
+    let more_addrs = addrs.peek().is_some();
+    tokio::select! {
+      Some(result) = attempts.next() => match result {
+        Ok(stream) => return Ok(stream),
+        Err(err) => {
+          error.get_or_insert(err);
+        }
+      },
+      _ = tokio::time::sleep(delay), if more_addrs => {}
+    }
+  }
+}
+
+/// Resolves `hostname`, and connects to one of its addresses.
+pub async fn connect_host(
+  hostname: &str,
+  port: u16,
+  delay: Duration,
+) -> Result<TcpStream, AnyError> {
+  let addrs = resolve_addr(hostname, port).await?.collect::<Vec<_>>();
+  if addrs.is_empty() {
+    return Err(generic_error("No resolved address found"));
+  }
+  Ok(connect(addrs, delay).await?)
+}
+
+/// Alternates between the address families, starting with the family of the
+/// first address, and otherwise keeps the order of the resolver, which sorts
+/// them by preference.
+fn interleave(addrs: impl IntoIterator<Item = SocketAddr>) -> Vec<SocketAddr> {
+  let mut addrs = addrs.into_iter().peekable();
+  let Some(first_is_ipv6) = addrs.peek().map(|addr| addr.is_ipv6()) else {
+    return vec![];
+  };
+  let (first, second): (Vec<_>, Vec<_>) =
+    addrs.partition(|addr| addr.is_ipv6() == first_is_ipv6);
+  let mut first = first.into_iter();
+  let mut second = second.into_iter();
+  let mut interleaved = Vec::with_capacity(first.len() + second.len());
+  loop {
+    match (first.next(), second.next()) {
+      (None, None) => return interleaved,
+      (a, b) => interleaved.extend(a.into_iter().chain(b)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tokio::net::TcpListener;
+
+  fn addr(s: &str) -> SocketAddr {
+    s.parse().unwrap()
+  }
+
+  #[test]
+  fn interleaves_families() {
+    let addrs = [
+      "[::1]:80",
+      "[::2]:80",
+      "[::3]:80",
+      "1.1.1.1:80",
+      "2.2.2.2:80",
+    ];
+    assert_eq!(
+      interleave(addrs.map(addr)),
+      [
+        "[::1]:80",
+        "1.1.1.1:80",
+        "[::2]:80",
+        "2.2.2.2:80",
+        "[::3]:80"
+      ]
+      .map(addr)
+    );
+    let addrs = ["1.1.1.1:80", "[::1]:80", "2.2.2.2:80"];
+    assert_eq!(
+      interleave(addrs.map(addr)),
+      ["1.1.1.1:80", "[::1]:80", "2.2.2.2:80"].map(addr)
+    );
+    assert!(interleave([]).is_empty());
+  }
+
+  #[tokio::test]
+  async fn connects_to_first_working_address() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let listening = listener.local_addr().unwrap();
+    let closed = {
+      let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+      listener.local_addr().unwrap()
+    };
+
+    let stream = connect([closed, listening], Duration::from_secs(60))
+      .await
+      .unwrap();
+    assert_eq!(stream.peer_addr().unwrap(), listening);
+
+    let err = connect([closed, closed], Duration::from_millis(10))
+      .await
+      .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+    let err = connect([], Duration::from_millis(10)).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+  }
+}
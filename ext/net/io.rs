@@ -9,6 +9,7 @@ use deno_core::CancelHandle;
 use deno_core::CancelTryFuture;
 use deno_core::RcRef;
 use deno_core::Resource;
+use deno_core::ResourceHandle;
 use socket2::SockRef;
 use std::borrow::Cow;
 use std::rc::Rc;
@@ -99,6 +100,12 @@ impl Resource for TcpStreamResource {
     "tcpStream".into()
   }
 
+  fn backing_handle(self: Rc<Self>) -> Option<ResourceHandle> {
+    // not available while a write is pending
+    let wr = RcRef::map(&self, |r| &r.wr).try_borrow()?;
+    Some(ResourceHandle::from_socket_like(wr.as_ref().as_ref()))
+  }
+
   fn shutdown(self: Rc<Self>) -> AsyncResult<()> {
     Box::pin(self.shutdown())
   }
@@ -110,22 +117,20 @@ impl Resource for TcpStreamResource {
 
 impl TcpStreamResource {
   pub fn set_nodelay(self: Rc<Self>, nodelay: bool) -> Result<(), AnyError> {
-    self.map_socket(Box::new(move |socket| Ok(socket.set_nodelay(nodelay)?)))
+    self.map_socket(move |socket| Ok(socket.set_nodelay(nodelay)?))
   }
 
   pub fn set_keepalive(
     self: Rc<Self>,
     keepalive: bool,
   ) -> Result<(), AnyError> {
-    self
-      .map_socket(Box::new(move |socket| Ok(socket.set_keepalive(keepalive)?)))
+    self.map_socket(move |socket| Ok(socket.set_keepalive(keepalive)?))
   }
 
-  #[allow(clippy::type_complexity)]
-  fn map_socket(
+  pub(crate) fn map_socket<R>(
     self: Rc<Self>,
-    map: Box<dyn FnOnce(SockRef) -> Result<(), AnyError>>,
-  ) -> Result<(), AnyError> {
+    map: impl FnOnce(SockRef) -> Result<R, AnyError>,
+  ) -> Result<R, AnyError> {
     if let Some(wr) = RcRef::map(self, |r| &r.wr).try_borrow() {
       let stream = wr.as_ref().as_ref();
       let socket = socket2::SockRef::from(stream);
@@ -169,6 +174,12 @@ impl Resource for UnixStreamResource {
     "unixStream".into()
   }
 
+  #[cfg(unix)]
+  fn backing_handle(self: Rc<Self>) -> Option<ResourceHandle> {
+    let wr = RcRef::map(&self, |r| &r.wr).try_borrow()?;
+    Some(ResourceHandle::from_socket_like(wr.as_ref().as_ref()))
+  }
+
   fn shutdown(self: Rc<Self>) -> AsyncResult<()> {
     Box::pin(self.shutdown())
   }
@@ -0,0 +1,229 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Exporting keying material from TLS 1.3 connections, as described by
+//! RFC 8446 section 7.5. The TLS streams don't give access to the connection
+//! once they were split, so the exporter secret is captured with a `KeyLog`
+//! while the handshake is done, and the keying material is derived from it.
+
+use std::sync::Mutex;
+
+use deno_core::error::custom_error;
+use deno_core::error::range_error;
+use deno_core::error::AnyError;
+use deno_tls::rustls::KeyLog;
+use ring::digest;
+use ring::hkdf;
+
+/// Captures the exporter secret of a client connection.
+#[derive(Debug, Default)]
+pub struct ExporterSecret(Mutex<Option<Vec<u8>>>);
+
+impl KeyLog for ExporterSecret {
+  fn log(&self, label: &str, _client_random: &[u8], secret: &[u8]) {
+    if label == "EXPORTER_SECRET" {
+      *self.0.lock().unwrap() = Some(secret.to_vec());
+    }
+  }
+
+  fn will_log(&self, label: &str) -> bool {
+    label == "EXPORTER_SECRET"
+  }
+}
+
+impl ExporterSecret {
+  /// Derives `len` bytes of keying material for `label` and `context`, which
+  /// is done with the hash of the cipher suite, after the handshake.
+  pub fn export(
+    &self,
+    label: &[u8],
+    context: &[u8],
+    len: usize,
+  ) -> Result<Vec<u8>, AnyError> {
+    let secret = self.0.lock().unwrap();
+    let Some(secret) = secret.as_deref() else {
+      return Err(custom_error(
+        "NotSupported",
+        "Keying material can only be exported from TLS 1.3 connections",
+      ));
+    };
+    // the secret is as long as the output of the hash
+    let (hkdf_alg, digest_alg) = match secret.len() {
+      32 => (hkdf::HKDF_SHA256, &digest::SHA256),
+      48 => (hkdf::HKDF_SHA384, &digest::SHA384),
+      _ => unreachable!("TLS 1.3 cipher suites use SHA-256 or SHA-384"),
+    };
+    if label.len() > 255 - b"tls13 ".len() {
+      return Err(range_error("The label can be at most 249 bytes long"));
+    }
+    if len > 255 * digest_alg.output_len() {
+      return Err(range_error(format!(
+        "Keying material can be at most {} bytes long",
+        255 * digest_alg.output_len()
+      )));
+    }
+
+    // TLS-Exporter(label, context_value, key_length) =
+    //   HKDF-Expand-Label(Derive-Secret(Secret, label, ""),
+    //                     "exporter", Hash(context_value), key_length)
+    let empty_hash = digest::digest(digest_alg, b"");
+    let derived = expand_label(
+      hkdf_alg,
+      secret,
+      label,
+      empty_hash.as_ref(),
+      digest_alg.output_len(),
+    );
+    let context_hash = digest::digest(digest_alg, context);
+    Ok(expand_label(
+      hkdf_alg,
+      &derived,
+      b"exporter",
+      context_hash.as_ref(),
+      len,
+    ))
+  }
+}
+
+struct Len(usize);
+
+impl hkdf::KeyType for Len {
+  fn len(&self) -> usize {
+    self.0
+  }
+}
+
+/// `HKDF-Expand-Label` of RFC 8446 section 7.1.
+fn expand_label(
+  alg: hkdf::Algorithm,
+  secret: &[u8],
+  label: &[u8],
+  context: &[u8],
+  len: usize,
+) -> Vec<u8> {
+  let output_len = (len as u16).to_be_bytes();
+  let label_len = [(b"tls13 ".len() + label.len()) as u8];
+  let context_len = [context.len() as u8];
+  let info: [&[u8]; 6] = [
+    &output_len,
+    &label_len,
+    b"tls13 ",
+    label,
+    &context_len,
+    context,
+  ];
+  let mut out = vec![0; len];
+  hkdf::Prk::new_less_safe(alg, secret)
+    .expand(&info, Len(len))
+    .and_then(|okm| okm.fill(&mut out))
+    .expect("the length was checked");
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use deno_tls::rustls;
+  use deno_tls::rustls::pki_types::CertificateDer;
+  use deno_tls::rustls::pki_types::PrivateKeyDer;
+  use deno_tls::rustls::ClientConfig;
+  use deno_tls::rustls::ClientConnection;
+  use deno_tls::rustls::ServerConfig;
+  use deno_tls::rustls::ServerConnection;
+  use deno_tls::NoCertificateVerification;
+  use std::sync::Arc;
+
+  fn server_config(
+    cipher_suite: rustls::SupportedCipherSuite,
+  ) -> Arc<ServerConfig> {
+    let testdata =
+      std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../tls/testdata");
+    let cert = std::fs::read(testdata.join("example1_cert.der")).unwrap();
+    let key = std::fs::read(testdata.join("example1_prikey.der")).unwrap();
+    let provider = rustls::crypto::CryptoProvider {
+      cipher_suites: vec![cipher_suite],
+      ..rustls::crypto::ring::default_provider()
+    };
+    let config = ServerConfig::builder_with_provider(Arc::new(provider))
+      .with_protocol_versions(&[&rustls::version::TLS13])
+      .unwrap()
+      .with_no_client_auth()
+      .with_single_cert(
+        vec![CertificateDer::from(cert)],
+        PrivateKeyDer::try_from(key).unwrap(),
+      )
+      .unwrap();
+    Arc::new(config)
+  }
+
+  fn handshake(client: &mut ClientConnection, server: &mut ServerConnection) {
+    while client.is_handshaking() || server.is_handshaking() {
+      let mut buf = vec![];
+      while client.wants_write() {
+        client.write_tls(&mut buf).unwrap();
+      }
+      let mut rd = buf.as_slice();
+      while !rd.is_empty() {
+        server.read_tls(&mut rd).unwrap();
+      }
+      server.process_new_packets().unwrap();
+
+      let mut buf = vec![];
+      while server.wants_write() {
+        server.write_tls(&mut buf).unwrap();
+      }
+      let mut rd = buf.as_slice();
+      while !rd.is_empty() {
+        client.read_tls(&mut rd).unwrap();
+      }
+      client.process_new_packets().unwrap();
+    }
+  }
+
+  #[test]
+  fn export_keying_material() {
+    for cipher_suite in [
+      rustls::crypto::ring::cipher_suite::TLS13_AES_128_GCM_SHA256,
+      rustls::crypto::ring::cipher_suite::TLS13_AES_256_GCM_SHA384,
+    ] {
+      let secret = Arc::new(ExporterSecret::default());
+      let mut client_config = ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(
+          NoCertificateVerification::new(vec![]),
+        ))
+        .with_no_client_auth();
+      client_config.key_log = secret.clone();
+      let mut client = ClientConnection::new(
+        Arc::new(client_config),
+        "example1.com".try_into().unwrap(),
+      )
+      .unwrap();
+      let mut server =
+        ServerConnection::new(server_config(cipher_suite)).unwrap();
+      handshake(&mut client, &mut server);
+
+      for (label, context, len) in [
+        (&b"EXPORTER-Channel-Binding"[..], &b""[..], 32),
+        (b"label", b"context", 100),
+      ] {
+        let mut expected = vec![0; len];
+        server
+          .export_keying_material(&mut expected, label, Some(context))
+          .unwrap();
+        assert_eq!(secret.export(label, context, len).unwrap(), expected);
+      }
+      assert!(secret.export(b"label", b"", 255 * 48 + 1).is_err());
+    }
+  }
+
+  #[test]
+  fn no_exporter_secret() {
+    let err = ExporterSecret::default()
+      .export(b"label", b"", 32)
+      .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Keying material can only be exported from TLS 1.3 connections"
+    );
+  }
+}
@@ -68,7 +68,62 @@ declare namespace Deno {
    *
    * @category Network
    */
-  export type TcpListener = Listener<TcpConn, NetAddr>;
+  export interface TcpListener extends Listener<TcpConn, NetAddr> {
+    /** Sets a socket option, see {@linkcode Deno.SocketOptions}. */
+    setSocketOption<K extends keyof SocketOptions>(
+      name: K,
+      value: SocketOptions[K],
+    ): void;
+    /** Returns the value of a socket option, see
+     * {@linkcode Deno.SocketOptions}. */
+    getSocketOption<K extends keyof SocketOptions>(name: K): SocketOptions[K];
+  }
+
+  /** The options of a socket which can be set and read with
+   * `setSocketOption()` and `getSocketOption()`, and the types of their
+   * values.
+   *
+   * ```ts
+   * const listener = Deno.listen({ port: 8080 });
+   * listener.setSocketOption("receiveBufferSize", 1024 * 1024);
+   * ```
+   *
+   * @category Network
+   */
+  export interface SocketOptions {
+    /** `SO_REUSEPORT`, which lets other sockets bind to the same address and
+     * port. Not supported on Windows. */
+    reusePort: boolean;
+    /** `SO_RCVBUF`, the size of the receive buffer in bytes. Linux doubles
+     * the size which is set, to leave room for its bookkeeping. */
+    receiveBufferSize: number;
+    /** `SO_SNDBUF`, the size of the send buffer in bytes. Linux doubles the
+     * size which is set, to leave room for its bookkeeping. */
+    sendBufferSize: number;
+    /** `TCP_KEEPINTVL`, the seconds between keep-alive probes. Setting it
+     * doesn't turn keep-alive on, see {@linkcode Deno.TcpConn.setKeepAlive}.
+     * Only supported on Linux, macOS and FreeBSD. */
+    keepAliveInterval: number;
+    /** `TCP_KEEPCNT`, how many keep-alive probes can go unanswered before the
+     * connection is dropped. Only supported on Linux, macOS and FreeBSD. */
+    keepAliveCount: number;
+    /** `IP_TOS`, or `IPV6_TCLASS` for IPv6 sockets, the type of service of
+     * the packets which are sent, e.g. for DSCP. */
+    tos: number;
+    /** `SO_BROADCAST`, which lets a UDP socket send messages to broadcast
+     * addresses. */
+    broadcast: boolean;
+    /** `IP_MULTICAST_TTL`, or `IPV6_MULTICAST_HOPS` for IPv6 sockets, how
+     * many hops the multicast messages of a UDP socket can take. */
+    multicastTtl: number;
+    /** `IP_MULTICAST_LOOP`, or `IPV6_MULTICAST_LOOP` for IPv6 sockets,
+     * whether the multicast messages of a UDP socket are looped back to the
+     * sockets of the local host. */
+    multicastLoopback: boolean;
+    /** `IP_TTL`, or `IPV6_UNICAST_HOPS` for IPv6 sockets, how many hops the
+     * unicast packets of a socket can take. */
+    ttl: number;
+  }
 
   /** Specialized listener that accepts Unix connections.
    *
@@ -179,7 +234,8 @@ declare namespace Deno {
    * @category Network
    */
   export interface UnixListenOptions {
-    /** A path to the Unix Socket. */
+    /** A path to the Unix Socket. On Linux, a path which starts with a NUL
+     * byte, like `"\0name"`, is a name in the abstract namespace. */
     path: string;
   }
 
@@ -292,11 +348,19 @@ declare namespace Deno {
   export interface ListenTlsOptions extends TcpListenOptions {
     transport?: "tcp";
 
-    /** Application-Layer Protocol Negotiation (ALPN) protocols to announce to
+    /** Application-Layer Protocol Negotiation (ALPN) protocols supported by
      * the client. If not specified, no ALPN extension will be included in the
-     * TLS handshake.
+     * TLS handshake. The protocol which the server selected is the
+     * `alpnProtocol` of {@linkcode Deno.TlsConn.handshake}.
      */
     alpnProtocols?: string[];
+    /** See {@linkcode Deno.ConnectTlsOptions.caCertFiles}.
+     *
+     * @tags allow-read
+     */
+    caCertFiles?: string[];
+    /** See {@linkcode Deno.ConnectTlsOptions.pinnedPublicKeys}. */
+    pinnedPublicKeys?: string[];
   }
 
   /** Listen announces on the local transport address over TLS (transport layer
@@ -329,6 +393,13 @@ declare namespace Deno {
      * @default {"127.0.0.1"} */
     hostname?: string;
     transport?: "tcp";
+    /** When the host name resolves to several addresses, like both an IPv4
+     * and an IPv6 one, the connection attempts to them are raced, and the
+     * next attempt is started after this many milliseconds if the previous
+     * one is still pending, as described by RFC 8305 ("Happy Eyeballs").
+     *
+     * @default {250} */
+    connectionAttemptDelay?: number;
   }
 
   /**
@@ -359,6 +430,27 @@ declare namespace Deno {
     setNoDelay(noDelay?: boolean): void;
     /** Enable/disable keep-alive functionality. */
     setKeepAlive(keepAlive?: boolean): void;
+    /** Sends the contents of a file, or a range of it, and resolves with the
+     * number of bytes sent. Where the OS supports it, like with `sendfile(2)`
+     * on Linux and macOS, the file is sent without being copied, which is
+     * faster than writing it to the connection.
+     *
+     * ```ts
+     * using file = await Deno.open("./index.html");
+     * await conn.sendFile(file);
+     * ```
+     *
+     * The position of the file isn't changed, except on Windows.
+     */
+    sendFile(file: FsFile, options?: SendFileOptions): Promise<number>;
+    /** Sets a socket option, see {@linkcode Deno.SocketOptions}. */
+    setSocketOption<K extends keyof SocketOptions>(
+      name: K,
+      value: SocketOptions[K],
+    ): void;
+    /** Returns the value of a socket option, see
+     * {@linkcode Deno.SocketOptions}. */
+    getSocketOption<K extends keyof SocketOptions>(name: K): SocketOptions[K];
     /**
      * The resource ID of the connection.
      *
@@ -369,9 +461,25 @@ declare namespace Deno {
     readonly rid: number;
   }
 
+  /** Options which can be set when calling {@linkcode Deno.TcpConn.sendFile}.
+   *
+   * @category Network
+   */
+  export interface SendFileOptions {
+    /** Where in the file to start sending from.
+     *
+     * @default {0} */
+    offset?: number;
+    /** How many bytes to send at most. By default, the file is sent up to its
+     * end. */
+    length?: number;
+  }
+
   /** @category Network */
   export interface UnixConnectOptions {
     transport: "unix";
+    /** A path to the Unix Socket. On Linux, a path which starts with a NUL
+     * byte, like `"\0name"`, is a name in the abstract namespace. */
     path: string;
   }
 
@@ -434,6 +542,32 @@ declare namespace Deno {
      * TLS handshake.
      */
     alpnProtocols?: string[];
+    /** See {@linkcode Deno.ConnectOptions.connectionAttemptDelay}.
+     *
+     * @default {250} */
+    connectionAttemptDelay?: number;
+    /** Paths to files containing PEM formatted root certificates, which are
+     * used like {@linkcode Deno.ConnectTlsOptions.caCerts}. Requires
+     * `--allow-read`.
+     *
+     * @tags allow-read
+     */
+    caCertFiles?: string[];
+    /** Base64 encoded SHA-256 hashes of the DER encoded SubjectPublicKeyInfo
+     * of public keys, like the `pin-sha256` of HTTP Public Key Pinning. If
+     * given, the connection is rejected unless the certificate of the peer or
+     * one of the certificates of its chain has one of the keys, in addition
+     * to the chain being verified.
+     *
+     * The hash of the key of a certificate can be computed with:
+     *
+     * ```sh
+     * openssl x509 -in cert.pem -pubkey -noout \
+     *   | openssl pkey -pubin -outform der \
+     *   | openssl dgst -sha256 -binary | base64
+     * ```
+     */
+    pinnedPublicKeys?: string[];
   }
 
   /** Establishes a secure connection over TLS (transport layer security) using
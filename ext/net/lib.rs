@@ -1,12 +1,17 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+mod dns;
+mod happy_eyeballs;
 pub mod io;
+mod keying_material;
 pub mod ops;
 pub mod ops_tls;
 #[cfg(unix)]
 pub mod ops_unix;
+mod proxy;
 pub mod raw;
 pub mod resolve_addr;
+mod sendfile;
 mod tcp;
 
 use deno_core::error::AnyError;
@@ -110,8 +115,16 @@ deno_core::extension!(deno_net,
     ops::op_net_set_multi_loopback_udp,
     ops::op_net_set_multi_ttl_udp,
     ops::op_dns_resolve<P>,
+    dns::op_dns_resolver_create<P>,
+    dns::op_dns_resolver_clear_cache,
     ops::op_set_nodelay,
     ops::op_set_keepalive,
+    ops::op_net_set_socket_option,
+    ops::op_net_get_socket_option,
+    ops::op_net_set_membership_udp,
+    ops::op_net_set_source_membership_udp,
+    ops::op_net_set_multicast_interface_udp,
+    sendfile::op_net_sendfile,
 
     ops_tls::op_tls_key_null,
     ops_tls::op_tls_key_static,
@@ -125,9 +138,13 @@ deno_core::extension!(deno_net,
     ops_tls::op_net_listen_tls<P>,
     ops_tls::op_net_accept_tls,
     ops_tls::op_tls_handshake,
+    ops_tls::op_tls_export_keying_material,
+    ops_tls::op_tls_session_cache_create,
 
     ops_unix::op_net_accept_unix,
     ops_unix::op_net_connect_unix<P>,
+    ops_unix::op_net_connect_unixpacket<P>,
+    ops_unix::op_net_listen_fd<P>,
     ops_unix::op_net_listen_unix<P>,
     ops_unix::op_net_listen_unixpacket<P>,
     ops_unix::op_node_unstable_net_listen_unixpacket<P>,
@@ -186,6 +203,8 @@ mod ops_unix {
 
   stub_op!(op_net_accept_unix);
   stub_op!(op_net_connect_unix<P>);
+  stub_op!(op_net_connect_unixpacket<P>);
+  stub_op!(op_net_listen_fd<P>);
   stub_op!(op_net_listen_unix<P>);
   stub_op!(op_net_listen_unixpacket<P>);
   stub_op!(op_node_unstable_net_listen_unixpacket<P>);
@@ -1,15 +1,24 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+use crate::dns::DnsResolver;
+use crate::happy_eyeballs;
 use crate::io::TcpStreamResource;
+use crate::proxy;
+use crate::proxy::Proxy;
 use crate::raw::NetworkListenerResource;
 use crate::resolve_addr::resolve_addr;
 use crate::resolve_addr::resolve_addr_sync;
 use crate::tcp::TcpListener;
 use crate::NetPermissions;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
 use deno_core::error::bad_resource;
 use deno_core::error::custom_error;
 use deno_core::error::generic_error;
+use deno_core::error::range_error;
+use deno_core::error::type_error;
 use deno_core::error::AnyError;
+use deno_core::futures::FutureExt;
 use deno_core::op2;
 use deno_core::CancelFuture;
 
@@ -26,6 +35,7 @@ use serde::Deserialize;
 use serde::Serialize;
 use socket2::Domain;
 use socket2::Protocol;
+use socket2::SockRef;
 use socket2::Socket;
 use socket2::Type;
 use std::borrow::Cow;
@@ -35,9 +45,15 @@ use std::net::Ipv6Addr;
 use std::net::SocketAddr;
 use std::rc::Rc;
 use std::str::FromStr;
-use tokio::net::TcpStream;
+use std::time::Duration;
 use tokio::net::UdpSocket;
 use trust_dns_proto::rr::rdata::caa::Value;
+use trust_dns_proto::rr::rdata::svcb::Alpn;
+use trust_dns_proto::rr::rdata::svcb::EchConfig;
+use trust_dns_proto::rr::rdata::svcb::IpHint;
+use trust_dns_proto::rr::rdata::svcb::Mandatory;
+use trust_dns_proto::rr::rdata::svcb::SvcParamValue;
+use trust_dns_proto::rr::rdata::svcb::SVCB;
 use trust_dns_proto::rr::record_data::RData;
 use trust_dns_proto::rr::record_type::RecordType;
 use trust_dns_resolver::config::NameServerConfigGroup;
@@ -275,6 +291,7 @@ pub async fn op_net_set_multi_loopback_udp(
 pub async fn op_net_set_multi_ttl_udp(
   state: Rc<RefCell<OpState>>,
   #[smi] rid: ResourceId,
+  is_v4_membership: bool,
   #[smi] ttl: u32,
 ) -> Result<(), AnyError> {
   let resource = state
@@ -284,43 +301,65 @@ pub async fn op_net_set_multi_ttl_udp(
     .map_err(|_| bad_resource("Socket has been closed"))?;
   let socket = RcRef::map(&resource, |r| &r.socket).borrow().await;
 
-  socket.set_multicast_ttl_v4(ttl)?;
+  if is_v4_membership {
+    socket.set_multicast_ttl_v4(ttl)?;
+  } else {
+    SockRef::from(&*socket).set_multicast_hops_v6(ttl)?;
+  }
 
   Ok(())
 }
 
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ConnectArgs {
+  connection_attempt_delay: Option<u64>,
+  proxy: Option<deno_tls::Proxy>,
+}
+
 #[op2(async)]
 #[serde]
 pub async fn op_net_connect_tcp<NP>(
   state: Rc<RefCell<OpState>>,
   #[serde] addr: IpAddr,
+  #[serde] args: ConnectArgs,
 ) -> Result<(ResourceId, IpAddr, IpAddr), AnyError>
 where
   NP: NetPermissions + 'static,
 {
-  op_net_connect_tcp_inner::<NP>(state, addr).await
+  op_net_connect_tcp_inner::<NP>(state, addr, args).await
 }
 
 #[inline]
 pub async fn op_net_connect_tcp_inner<NP>(
   state: Rc<RefCell<OpState>>,
   addr: IpAddr,
+  args: ConnectArgs,
 ) -> Result<(ResourceId, IpAddr, IpAddr), AnyError>
 where
   NP: NetPermissions + 'static,
 {
+  let proxy = args.proxy.map(Proxy::parse).transpose()?;
   {
     let mut state_ = state.borrow_mut();
-    state_
-      .borrow_mut::<NP>()
+    if proxy.is_some() {
+      super::check_unstable(&state_, "Deno.connect({ proxy })");
+    }
+    let permissions = state_.borrow_mut::<NP>();
+    permissions
       .check_net(&(&addr.hostname, Some(addr.port)), "Deno.connect()")?;
+    if let Some(proxy) = &proxy {
+      permissions
+        .check_net(&(proxy.hostname(), Some(proxy.port())), "Deno.connect()")?;
+    }
   }
 
-  let addr = resolve_addr(&addr.hostname, addr.port)
-    .await?
-    .next()
-    .ok_or_else(|| generic_error("No resolved address found"))?;
-  let tcp_stream = TcpStream::connect(&addr).await?;
+  let delay = args
+    .connection_attempt_delay
+    .map(Duration::from_millis)
+    .unwrap_or(happy_eyeballs::DEFAULT_CONNECTION_ATTEMPT_DELAY);
+  let tcp_stream =
+    proxy::connect(&addr.hostname, addr.port, delay, proxy.as_ref()).await?;
   let local_addr = tcp_stream.local_addr()?;
   let remote_addr = tcp_stream.peer_addr()?;
 
@@ -332,11 +371,20 @@ where
   Ok((rid, IpAddr::from(local_addr), IpAddr::from(remote_addr)))
 }
 
-struct UdpSocketResource {
-  socket: AsyncRefCell<UdpSocket>,
+pub(crate) struct UdpSocketResource {
+  pub(crate) socket: AsyncRefCell<UdpSocket>,
   cancel: CancelHandle,
 }
 
+impl UdpSocketResource {
+  pub(crate) fn new(socket: UdpSocket) -> Self {
+    Self {
+      socket: AsyncRefCell::new(socket),
+      cancel: Default::default(),
+    }
+  }
+}
+
 impl Resource for UdpSocketResource {
   fn name(&self) -> Cow<str> {
     "udpSocket".into()
@@ -385,6 +433,7 @@ fn net_listen_udp<NP>(
   addr: IpAddr,
   reuse_address: bool,
   loopback: bool,
+  broadcast: bool,
 ) -> Result<(ResourceId, IpAddr), AnyError>
 where
   NP: NetPermissions + 'static,
@@ -425,8 +474,9 @@ where
   socket_tmp.bind(&socket_addr)?;
   socket_tmp.set_nonblocking(true)?;
 
-  // Enable messages to be sent to the broadcast address (255.255.255.255) by default
-  socket_tmp.set_broadcast(true)?;
+  // Messages can be sent to the broadcast address (255.255.255.255) unless
+  // this was turned off
+  socket_tmp.set_broadcast(broadcast)?;
 
   if domain == Domain::IPV4 {
     socket_tmp.set_multicast_loop_v4(loopback)?;
@@ -438,11 +488,7 @@ where
 
   let socket = UdpSocket::from_std(std_socket)?;
   let local_addr = socket.local_addr()?;
-  let socket_resource = UdpSocketResource {
-    socket: AsyncRefCell::new(socket),
-    cancel: Default::default(),
-  };
-  let rid = state.resource_table.add(socket_resource);
+  let rid = state.resource_table.add(UdpSocketResource::new(socket));
 
   Ok((rid, IpAddr::from(local_addr)))
 }
@@ -454,12 +500,13 @@ pub fn op_net_listen_udp<NP>(
   #[serde] addr: IpAddr,
   reuse_address: bool,
   loopback: bool,
+  broadcast: bool,
 ) -> Result<(ResourceId, IpAddr), AnyError>
 where
   NP: NetPermissions + 'static,
 {
   super::check_unstable(state, "Deno.listenDatagram");
-  net_listen_udp::<NP>(state, addr, reuse_address, loopback)
+  net_listen_udp::<NP>(state, addr, reuse_address, loopback, broadcast)
 }
 
 #[op2]
@@ -469,14 +516,15 @@ pub fn op_node_unstable_net_listen_udp<NP>(
   #[serde] addr: IpAddr,
   reuse_address: bool,
   loopback: bool,
+  broadcast: bool,
 ) -> Result<(ResourceId, IpAddr), AnyError>
 where
   NP: NetPermissions + 'static,
 {
-  net_listen_udp::<NP>(state, addr, reuse_address, loopback)
+  net_listen_udp::<NP>(state, addr, reuse_address, loopback, broadcast)
 }
 
-#[derive(Serialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Eq, PartialEq, Debug, Clone)]
 #[serde(untagged)]
 pub enum DnsReturnRecord {
   A(String),
@@ -517,9 +565,35 @@ pub enum DnsReturnRecord {
     port: u16,
     target: String,
   },
+  Svcb {
+    priority: u16,
+    target: String,
+    params: SvcParams,
+  },
   Txt(Vec<String>),
 }
 
+/// The parameters of `SVCB` and `HTTPS` records which are known, as
+/// described by RFC 9460 section 7.
+#[derive(Serialize, Eq, PartialEq, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SvcParams {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  mandatory: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  alpn: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "std::ops::Not::not")]
+  no_default_alpn: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  port: Option<u16>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  ipv4_hint: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  ech: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  ipv6_hint: Option<Vec<String>>,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ResolveAddrArgs {
@@ -527,6 +601,7 @@ pub struct ResolveAddrArgs {
   query: String,
   record_type: RecordType,
   options: Option<ResolveDnsOption>,
+  resolver_rid: Option<ResourceId>,
 }
 
 #[derive(Deserialize)]
@@ -561,40 +636,70 @@ where
     record_type,
     options,
     cancel_rid,
+    resolver_rid,
   } = args;
 
-  let (config, opts) = if let Some(name_server) =
-    options.as_ref().and_then(|o| o.name_server.as_ref())
-  {
-    let group = NameServerConfigGroup::from_ips_clear(
-      &[name_server.ip_addr.parse()?],
-      name_server.port,
-      true,
-    );
-    (
-      ResolverConfig::from_parts(None, vec![], group),
-      ResolverOpts::default(),
-    )
+  let lookup_fut = if let Some(rid) = resolver_rid {
+    let resolver = state.borrow().resource_table.get::<DnsResolver>(rid)?;
+    async move { resolver.resolve(&query, record_type).await }.boxed_local()
   } else {
-    system_conf::read_system_conf()?
-  };
+    let (config, opts) = if let Some(name_server) =
+      options.as_ref().and_then(|o| o.name_server.as_ref())
+    {
+      let group = NameServerConfigGroup::from_ips_clear(
+        &[name_server.ip_addr.parse()?],
+        name_server.port,
+        true,
+      );
+      (
+        ResolverConfig::from_parts(None, vec![], group),
+        ResolverOpts::default(),
+      )
+    } else {
+      system_conf::read_system_conf()?
+    };
 
-  {
-    let mut s = state.borrow_mut();
-    let perm = s.borrow_mut::<NP>();
-
-    // Checks permission against the name servers which will be actually queried.
-    for ns in config.name_servers() {
-      let socker_addr = &ns.socket_addr;
-      let ip = socker_addr.ip().to_string();
-      let port = socker_addr.port();
-      perm.check_net(&(ip, Some(port)), "Deno.resolveDns()")?;
+    {
+      let mut s = state.borrow_mut();
+      let perm = s.borrow_mut::<NP>();
+
+      // Checks permission against the name servers which will be actually
+      // queried.
+      for ns in config.name_servers() {
+        let socker_addr = &ns.socket_addr;
+        let ip = socker_addr.ip().to_string();
+        let port = socker_addr.port();
+        perm.check_net(&(ip, Some(port)), "Deno.resolveDns()")?;
+      }
     }
-  }
 
-  let resolver = AsyncResolver::tokio(config, opts);
-
-  let lookup_fut = resolver.lookup(query, record_type);
+    let resolver = AsyncResolver::tokio(config, opts);
+
+    async move {
+      resolver
+        .lookup(query, record_type)
+        .await
+        .map_err(|e| {
+          let message = format!("{e}");
+          match e.kind() {
+            ResolveErrorKind::NoRecordsFound { .. } => {
+              custom_error("NotFound", message)
+            }
+            ResolveErrorKind::Message("No connections available") => {
+              custom_error("NotConnected", message)
+            }
+            ResolveErrorKind::Timeout => custom_error("TimedOut", message),
+            _ => generic_error(message),
+          }
+        })?
+        .iter()
+        .filter_map(|rdata| {
+          rdata_to_return_record(record_type)(rdata).transpose()
+        })
+        .collect::<Result<Vec<DnsReturnRecord>, AnyError>>()
+    }
+    .boxed_local()
+  };
 
   let cancel_handle = cancel_rid.and_then(|rid| {
     state
@@ -604,7 +709,7 @@ where
       .ok()
   });
 
-  let lookup = if let Some(cancel_handle) = cancel_handle {
+  if let Some(cancel_handle) = cancel_handle {
     let lookup_rv = lookup_fut.or_cancel(cancel_handle).await;
 
     if let Some(cancel_rid) = cancel_rid {
@@ -616,25 +721,7 @@ where
     lookup_rv?
   } else {
     lookup_fut.await
-  };
-
-  lookup
-    .map_err(|e| {
-      let message = format!("{e}");
-      match e.kind() {
-        ResolveErrorKind::NoRecordsFound { .. } => {
-          custom_error("NotFound", message)
-        }
-        ResolveErrorKind::Message("No connections available") => {
-          custom_error("NotConnected", message)
-        }
-        ResolveErrorKind::Timeout => custom_error("TimedOut", message),
-        _ => generic_error(message),
-      }
-    })?
-    .iter()
-    .filter_map(|rdata| rdata_to_return_record(record_type)(rdata).transpose())
-    .collect::<Result<Vec<DnsReturnRecord>, AnyError>>()
+  }
 }
 
 #[op2(fast)]
@@ -677,7 +764,463 @@ pub fn op_set_keepalive_inner(
   resource.set_keepalive(keepalive)
 }
 
-fn rdata_to_return_record(
+/// The options of [`op_net_set_socket_option`] and
+/// [`op_net_get_socket_option`], by their names in JavaScript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketOption {
+  /// `SO_REUSEPORT`, which is either 0 or 1.
+  ReusePort,
+  /// `SO_RCVBUF`, in bytes.
+  ReceiveBufferSize,
+  /// `SO_SNDBUF`, in bytes.
+  SendBufferSize,
+  /// `TCP_KEEPINTVL`, in seconds.
+  KeepAliveInterval,
+  /// `TCP_KEEPCNT`.
+  KeepAliveCount,
+  /// `IP_TOS`, or `IPV6_TCLASS` for IPv6 sockets.
+  Tos,
+  /// `SO_BROADCAST`, which is either 0 or 1.
+  Broadcast,
+  /// `IP_MULTICAST_TTL`, or `IPV6_MULTICAST_HOPS` for IPv6 sockets.
+  MulticastTtl,
+  /// `IP_MULTICAST_LOOP`, or `IPV6_MULTICAST_LOOP` for IPv6 sockets, which
+  /// is either 0 or 1.
+  MulticastLoopback,
+  /// `IP_TTL`, or `IPV6_UNICAST_HOPS` for IPv6 sockets.
+  Ttl,
+}
+
+impl FromStr for SocketOption {
+  type Err = AnyError;
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    Ok(match name {
+      "reusePort" => Self::ReusePort,
+      "receiveBufferSize" => Self::ReceiveBufferSize,
+      "sendBufferSize" => Self::SendBufferSize,
+      "keepAliveInterval" => Self::KeepAliveInterval,
+      "keepAliveCount" => Self::KeepAliveCount,
+      "tos" => Self::Tos,
+      "broadcast" => Self::Broadcast,
+      "multicastTtl" => Self::MulticastTtl,
+      "multicastLoopback" => Self::MulticastLoopback,
+      "ttl" => Self::Ttl,
+      _ => return Err(type_error(format!("Invalid socket option: {name}"))),
+    })
+  }
+}
+
+#[op2(fast)]
+pub fn op_net_set_socket_option(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[string] name: &str,
+  #[number] value: u64,
+) -> Result<(), AnyError> {
+  let option = name.parse()?;
+  with_socket(state, rid, |socket| {
+    set_socket_option(&socket, option, value)
+  })
+}
+
+#[op2(fast)]
+#[number]
+pub fn op_net_get_socket_option(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[string] name: &str,
+) -> Result<u64, AnyError> {
+  let option = name.parse()?;
+  with_socket(state, rid, |socket| get_socket_option(&socket, option))
+}
+
+/// Joins or leaves the multicast group `group` on the interface `interface`,
+/// or on the one that the system chooses if there is none. The interface of
+/// an IPv6 group is given as an address with a scope id, e.g. `::%eth0`, like
+/// in Node.
+#[op2]
+pub fn op_net_set_membership_udp(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[string] group: &str,
+  #[string] interface: Option<String>,
+  join: bool,
+) -> Result<(), AnyError> {
+  let interface = interface.as_deref();
+  let group = parse_address(group)?;
+  with_socket(state, rid, |socket| match group {
+    std::net::IpAddr::V4(group) => {
+      let interface = parse_ipv4_interface(interface)?;
+      if join {
+        Ok(socket.join_multicast_v4(&group, &interface)?)
+      } else {
+        Ok(socket.leave_multicast_v4(&group, &interface)?)
+      }
+    }
+    std::net::IpAddr::V6(group) => {
+      let interface = parse_ipv6_interface(interface)?;
+      if join {
+        Ok(socket.join_multicast_v6(&group, interface)?)
+      } else {
+        Ok(socket.leave_multicast_v6(&group, interface)?)
+      }
+    }
+  })
+}
+
+/// Joins or leaves the source-specific multicast channel of `source` and
+/// `group`, which is only supported for IPv4.
+#[op2]
+pub fn op_net_set_source_membership_udp(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[string] source: &str,
+  #[string] group: &str,
+  #[string] interface: Option<String>,
+  join: bool,
+) -> Result<(), AnyError> {
+  let source = parse_address(source)?;
+  let group = parse_address(group)?;
+  let (std::net::IpAddr::V4(source), std::net::IpAddr::V4(group)) =
+    (source, group)
+  else {
+    return Err(std::io::Error::from(std::io::ErrorKind::Unsupported).into());
+  };
+  let interface = parse_ipv4_interface(interface.as_deref())?;
+  with_socket(state, rid, |socket| {
+    set_source_membership_v4(&socket, &source, &group, &interface, join)
+  })
+}
+
+/// Sets the interface which the multicast messages of a UDP socket are sent
+/// from, or lets the system choose it again if there is none.
+#[op2]
+pub fn op_net_set_multicast_interface_udp(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[string] interface: Option<String>,
+) -> Result<(), AnyError> {
+  let interface = interface.as_deref();
+  with_socket(state, rid, |socket| {
+    if socket.local_addr()?.is_ipv6() {
+      Ok(socket.set_multicast_if_v6(parse_ipv6_interface(interface)?)?)
+    } else {
+      Ok(socket.set_multicast_if_v4(&parse_ipv4_interface(interface)?)?)
+    }
+  })
+}
+
+/// Parse errors are `EINVAL`s in Node.
+fn invalid_address(addr: &str) -> AnyError {
+  std::io::Error::new(
+    std::io::ErrorKind::InvalidInput,
+    format!("Invalid address: {addr}"),
+  )
+  .into()
+}
+
+fn parse_address(addr: &str) -> Result<std::net::IpAddr, AnyError> {
+  addr.parse().map_err(|_| invalid_address(addr))
+}
+
+fn parse_ipv4_interface(interface: Option<&str>) -> Result<Ipv4Addr, AnyError> {
+  match interface {
+    Some(interface) => {
+      interface.parse().map_err(|_| invalid_address(interface))
+    }
+    None => Ok(Ipv4Addr::UNSPECIFIED),
+  }
+}
+
+/// Returns the index of the interface that is the scope of an IPv6 address,
+/// which is either the index itself or the name of the interface, the same
+/// as libuv's `uv_ip6_addr`.
+fn parse_ipv6_interface(interface: Option<&str>) -> Result<u32, AnyError> {
+  let Some(interface) = interface else {
+    return Ok(0);
+  };
+  let (addr, scope) = interface.split_once('%').unwrap_or((interface, ""));
+  if addr.parse::<Ipv6Addr>().is_err() {
+    return Err(invalid_address(interface));
+  }
+  Ok(match scope.parse() {
+    Ok(index) => index,
+    Err(_) if scope.is_empty() => 0,
+    Err(_) => interface_index(scope),
+  })
+}
+
+#[cfg(unix)]
+fn interface_index(name: &str) -> u32 {
+  let Ok(name) = std::ffi::CString::new(name) else {
+    return 0;
+  };
+  // SAFETY: `name` is a valid C string
+  unsafe { libc::if_nametoindex(name.as_ptr()) }
+}
+
+/// Interfaces are only identified by their index on Windows.
+#[cfg(windows)]
+fn interface_index(_name: &str) -> u32 {
+  0
+}
+
+#[cfg(any(
+  target_os = "android",
+  target_os = "freebsd",
+  target_os = "linux",
+  target_os = "macos",
+  windows
+))]
+fn set_source_membership_v4(
+  socket: &SockRef,
+  source: &Ipv4Addr,
+  group: &Ipv4Addr,
+  interface: &Ipv4Addr,
+  join: bool,
+) -> Result<(), AnyError> {
+  if join {
+    Ok(socket.join_ssm_v4(source, group, interface)?)
+  } else {
+    Ok(socket.leave_ssm_v4(source, group, interface)?)
+  }
+}
+
+#[cfg(not(any(
+  target_os = "android",
+  target_os = "freebsd",
+  target_os = "linux",
+  target_os = "macos",
+  windows
+)))]
+fn set_source_membership_v4(
+  _socket: &SockRef,
+  _source: &Ipv4Addr,
+  _group: &Ipv4Addr,
+  _interface: &Ipv4Addr,
+  _join: bool,
+) -> Result<(), AnyError> {
+  Err(std::io::Error::from(std::io::ErrorKind::Unsupported).into())
+}
+
+/// Calls `f` with the socket of a TCP connection, a TCP listener or a UDP
+/// socket.
+fn with_socket<R>(
+  state: &OpState,
+  rid: ResourceId,
+  f: impl FnOnce(SockRef) -> Result<R, AnyError>,
+) -> Result<R, AnyError> {
+  let table = &state.resource_table;
+  if let Ok(resource) = table.get::<TcpStreamResource>(rid) {
+    return resource.map_socket(f);
+  }
+  if let Ok(resource) = table.get::<NetworkListenerResource<TcpListener>>(rid) {
+    let listener = RcRef::map(&resource, |r| &r.listener)
+      .try_borrow()
+      .ok_or_else(|| custom_error("Busy", "Another accept task is ongoing"))?;
+    return f(listener.socket());
+  }
+  let resource = table.get::<UdpSocketResource>(rid)?;
+  let socket = RcRef::map(&resource, |r| &r.socket)
+    .try_borrow()
+    .ok_or_else(|| custom_error("Busy", "Socket already in use"))?;
+  f(SockRef::from(&*socket))
+}
+
+fn set_socket_option(
+  socket: &SockRef,
+  option: SocketOption,
+  value: u64,
+) -> Result<(), AnyError> {
+  let value_u32 = || {
+    u32::try_from(value)
+      .map_err(|_| range_error(format!("Invalid socket option value: {value}")))
+  };
+  match option {
+    SocketOption::ReusePort => set_reuse_port(socket, value != 0),
+    SocketOption::ReceiveBufferSize => {
+      Ok(socket.set_recv_buffer_size(value as usize)?)
+    }
+    SocketOption::SendBufferSize => {
+      Ok(socket.set_send_buffer_size(value as usize)?)
+    }
+    SocketOption::KeepAliveInterval | SocketOption::KeepAliveCount => {
+      set_keepalive_option(socket, option, value_u32()?)
+    }
+    SocketOption::Tos if socket.local_addr()?.is_ipv6() => {
+      set_tclass_v6(socket, value_u32()?)
+    }
+    SocketOption::Tos => Ok(socket.set_tos(value_u32()?)?),
+    SocketOption::Broadcast => Ok(socket.set_broadcast(value != 0)?),
+    SocketOption::MulticastTtl if socket.local_addr()?.is_ipv6() => {
+      Ok(socket.set_multicast_hops_v6(value_u32()?)?)
+    }
+    SocketOption::MulticastTtl => {
+      Ok(socket.set_multicast_ttl_v4(value_u32()?)?)
+    }
+    SocketOption::MulticastLoopback if socket.local_addr()?.is_ipv6() => {
+      Ok(socket.set_multicast_loop_v6(value != 0)?)
+    }
+    SocketOption::MulticastLoopback => {
+      Ok(socket.set_multicast_loop_v4(value != 0)?)
+    }
+    SocketOption::Ttl if socket.local_addr()?.is_ipv6() => {
+      Ok(socket.set_unicast_hops_v6(value_u32()?)?)
+    }
+    SocketOption::Ttl => Ok(socket.set_ttl(value_u32()?)?),
+  }
+}
+
+fn get_socket_option(
+  socket: &SockRef,
+  option: SocketOption,
+) -> Result<u64, AnyError> {
+  match option {
+    SocketOption::ReusePort => reuse_port(socket).map(u64::from),
+    SocketOption::ReceiveBufferSize => Ok(socket.recv_buffer_size()? as u64),
+    SocketOption::SendBufferSize => Ok(socket.send_buffer_size()? as u64),
+    SocketOption::KeepAliveInterval | SocketOption::KeepAliveCount => {
+      keepalive_option(socket, option).map(u64::from)
+    }
+    SocketOption::Tos if socket.local_addr()?.is_ipv6() => {
+      tclass_v6(socket).map(u64::from)
+    }
+    SocketOption::Tos => Ok(socket.tos()?.into()),
+    SocketOption::Broadcast => Ok(socket.broadcast()?.into()),
+    SocketOption::MulticastTtl if socket.local_addr()?.is_ipv6() => {
+      Ok(socket.multicast_hops_v6()?.into())
+    }
+    SocketOption::MulticastTtl => Ok(socket.multicast_ttl_v4()?.into()),
+    SocketOption::MulticastLoopback if socket.local_addr()?.is_ipv6() => {
+      Ok(socket.multicast_loop_v6()?.into())
+    }
+    SocketOption::MulticastLoopback => Ok(socket.multicast_loop_v4()?.into()),
+    SocketOption::Ttl if socket.local_addr()?.is_ipv6() => {
+      Ok(socket.unicast_hops_v6()?.into())
+    }
+    SocketOption::Ttl => Ok(socket.ttl()?.into()),
+  }
+}
+
+#[cfg(not(windows))]
+fn set_reuse_port(socket: &SockRef, reuse_port: bool) -> Result<(), AnyError> {
+  Ok(socket.set_reuse_port(reuse_port)?)
+}
+
+#[cfg(not(windows))]
+fn reuse_port(socket: &SockRef) -> Result<bool, AnyError> {
+  Ok(socket.reuse_port()?)
+}
+
+#[cfg(windows)]
+fn set_reuse_port(
+  _socket: &SockRef,
+  _reuse_port: bool,
+) -> Result<(), AnyError> {
+  Err(deno_core::error::not_supported())
+}
+
+#[cfg(windows)]
+fn reuse_port(_socket: &SockRef) -> Result<bool, AnyError> {
+  Err(deno_core::error::not_supported())
+}
+
+/// Sets the interval or the count of the keep-alive probes, without turning
+/// keep-alive on or off.
+#[cfg(any(
+  target_os = "android",
+  target_os = "freebsd",
+  target_os = "linux",
+  target_os = "macos"
+))]
+fn set_keepalive_option(
+  socket: &SockRef,
+  option: SocketOption,
+  value: u32,
+) -> Result<(), AnyError> {
+  let params = socket2::TcpKeepalive::new();
+  let params = match option {
+    SocketOption::KeepAliveInterval => {
+      params.with_interval(std::time::Duration::from_secs(value.into()))
+    }
+    _ => params.with_retries(value),
+  };
+  let keepalive = socket.keepalive()?;
+  // this turns keep-alive on as well
+  socket.set_tcp_keepalive(&params)?;
+  if !keepalive {
+    socket.set_keepalive(false)?;
+  }
+  Ok(())
+}
+
+#[cfg(any(
+  target_os = "android",
+  target_os = "freebsd",
+  target_os = "linux",
+  target_os = "macos"
+))]
+fn keepalive_option(
+  socket: &SockRef,
+  option: SocketOption,
+) -> Result<u32, AnyError> {
+  match option {
+    SocketOption::KeepAliveInterval => {
+      Ok(socket.keepalive_interval()?.as_secs() as u32)
+    }
+    _ => Ok(socket.keepalive_retries()?),
+  }
+}
+
+#[cfg(not(any(
+  target_os = "android",
+  target_os = "freebsd",
+  target_os = "linux",
+  target_os = "macos"
+)))]
+fn set_keepalive_option(
+  _socket: &SockRef,
+  _option: SocketOption,
+  _value: u32,
+) -> Result<(), AnyError> {
+  Err(deno_core::error::not_supported())
+}
+
+#[cfg(not(any(
+  target_os = "android",
+  target_os = "freebsd",
+  target_os = "linux",
+  target_os = "macos"
+)))]
+fn keepalive_option(
+  _socket: &SockRef,
+  _option: SocketOption,
+) -> Result<u32, AnyError> {
+  Err(deno_core::error::not_supported())
+}
+
+#[cfg(not(windows))]
+fn set_tclass_v6(socket: &SockRef, tclass: u32) -> Result<(), AnyError> {
+  Ok(socket.set_tclass_v6(tclass)?)
+}
+
+#[cfg(not(windows))]
+fn tclass_v6(socket: &SockRef) -> Result<u32, AnyError> {
+  Ok(socket.tclass_v6()?)
+}
+
+#[cfg(windows)]
+fn set_tclass_v6(_socket: &SockRef, _tclass: u32) -> Result<(), AnyError> {
+  Err(deno_core::error::not_supported())
+}
+
+#[cfg(windows)]
+fn tclass_v6(_socket: &SockRef) -> Result<u32, AnyError> {
+  Err(deno_core::error::not_supported())
+}
+
+pub(crate) fn rdata_to_return_record(
   ty: RecordType,
 ) -> impl Fn(&RData) -> Result<Option<DnsReturnRecord>, AnyError> {
   use RecordType::*;
@@ -752,6 +1295,8 @@ fn rdata_to_return_record(
         port: srv.port(),
         target: srv.target().to_string(),
       }),
+      SVCB => r.as_svcb().map(svcb_to_return_record),
+      HTTPS => r.as_https().map(|https| svcb_to_return_record(&https.0)),
       TXT => r.as_txt().map(|txt| {
         let texts: Vec<String> = txt
           .iter()
@@ -773,6 +1318,38 @@ fn rdata_to_return_record(
   }
 }
 
+fn svcb_to_return_record(svcb: &SVCB) -> DnsReturnRecord {
+  let mut params = SvcParams::default();
+  for (_, value) in svcb.svc_params() {
+    match value {
+      SvcParamValue::Mandatory(Mandatory(keys)) => {
+        params.mandatory = Some(keys.iter().map(ToString::to_string).collect());
+      }
+      SvcParamValue::Alpn(Alpn(protocols)) => {
+        params.alpn = Some(protocols.clone());
+      }
+      SvcParamValue::NoDefaultAlpn => params.no_default_alpn = true,
+      SvcParamValue::Port(port) => params.port = Some(*port),
+      SvcParamValue::Ipv4Hint(IpHint(ips)) => {
+        params.ipv4_hint = Some(ips.iter().map(ToString::to_string).collect());
+      }
+      SvcParamValue::EchConfig(EchConfig(config)) => {
+        params.ech = Some(BASE64_STANDARD.encode(config));
+      }
+      SvcParamValue::Ipv6Hint(IpHint(ips)) => {
+        params.ipv6_hint = Some(ips.iter().map(ToString::to_string).collect());
+      }
+      // the keys which aren't known yet have no presentation to return
+      SvcParamValue::Unknown(_) => {}
+    }
+  }
+  DnsReturnRecord::Svcb {
+    priority: svcb.svc_priority(),
+    target: svcb.target_name().to_string(),
+    params,
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -797,7 +1374,9 @@ mod tests {
   use trust_dns_proto::rr::rdata::name::PTR;
   use trust_dns_proto::rr::rdata::naptr::NAPTR;
   use trust_dns_proto::rr::rdata::srv::SRV;
+  use trust_dns_proto::rr::rdata::svcb::SvcParamKey;
   use trust_dns_proto::rr::rdata::txt::TXT;
+  use trust_dns_proto::rr::rdata::HTTPS;
   use trust_dns_proto::rr::rdata::SOA;
   use trust_dns_proto::rr::record_data::RData;
   use trust_dns_proto::rr::Name;
@@ -958,6 +1537,48 @@ mod tests {
     );
   }
 
+  #[test]
+  fn rdata_to_return_record_svcb() {
+    let svcb = SVCB::new(
+      1,
+      Name::from_ascii("svc.example.com.").unwrap(),
+      vec![
+        (
+          SvcParamKey::Alpn,
+          SvcParamValue::Alpn(Alpn(vec!["h2".to_string(), "h3".to_string()])),
+        ),
+        (SvcParamKey::NoDefaultAlpn, SvcParamValue::NoDefaultAlpn),
+        (SvcParamKey::Port, SvcParamValue::Port(8443)),
+        (
+          SvcParamKey::Ipv4Hint,
+          SvcParamValue::Ipv4Hint(IpHint(vec![A::new(127, 0, 0, 1)])),
+        ),
+        (
+          SvcParamKey::EchConfig,
+          SvcParamValue::EchConfig(EchConfig(vec![1, 2, 3])),
+        ),
+      ],
+    );
+    let expected = DnsReturnRecord::Svcb {
+      priority: 1,
+      target: "svc.example.com.".to_string(),
+      params: SvcParams {
+        alpn: Some(vec!["h2".to_string(), "h3".to_string()]),
+        no_default_alpn: true,
+        port: Some(8443),
+        ipv4_hint: Some(vec!["127.0.0.1".to_string()]),
+        ech: Some("AQID".to_string()),
+        ..Default::default()
+      },
+    };
+    let func = rdata_to_return_record(RecordType::SVCB);
+    let rdata = RData::SVCB(svcb.clone());
+    assert_eq!(func(&rdata).unwrap(), Some(expected.clone()));
+    let func = rdata_to_return_record(RecordType::HTTPS);
+    let rdata = RData::HTTPS(HTTPS(svcb));
+    assert_eq!(func(&rdata).unwrap(), Some(expected));
+  }
+
   #[test]
   fn rdata_to_return_record_txt() {
     let func = rdata_to_return_record(RecordType::TXT);
@@ -1030,6 +1651,79 @@ mod tests {
     check_sockopt(String::from("127.0.0.1:4146"), set_keepalive, test_fn).await;
   }
 
+  #[cfg(any(target_os = "linux", target_os = "macos"))]
+  #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+  async fn tcp_set_socket_options() {
+    let set_options = Box::new(|state: &mut OpState, rid| {
+      for (name, value) in [("keepAliveInterval", 15), ("keepAliveCount", 4)] {
+        let option = name.parse().unwrap();
+        with_socket(state, rid, |socket| {
+          set_socket_option(&socket, option, value)
+        })
+        .unwrap();
+      }
+      let count = with_socket(state, rid, |socket| {
+        get_socket_option(&socket, SocketOption::KeepAliveCount)
+      });
+      assert_eq!(count.unwrap(), 4);
+      assert!("nope".parse::<SocketOption>().is_err());
+    });
+    let test_fn = Box::new(|socket: SockRef| {
+      assert!(!socket.keepalive().unwrap());
+      assert_eq!(socket.keepalive_interval().unwrap().as_secs(), 15);
+      assert_eq!(socket.keepalive_retries().unwrap(), 4);
+    });
+    check_sockopt(String::from("127.0.0.1:4147"), set_options, test_fn).await;
+  }
+
+  #[test]
+  fn multicast_interfaces() {
+    assert_eq!(parse_ipv4_interface(None).unwrap(), Ipv4Addr::UNSPECIFIED);
+    assert_eq!(
+      parse_ipv4_interface(Some("10.0.0.2")).unwrap(),
+      Ipv4Addr::new(10, 0, 0, 2)
+    );
+    assert!(parse_ipv4_interface(Some("::")).is_err());
+    assert_eq!(parse_ipv6_interface(None).unwrap(), 0);
+    assert_eq!(parse_ipv6_interface(Some("::")).unwrap(), 0);
+    assert_eq!(parse_ipv6_interface(Some("::%2")).unwrap(), 2);
+    assert_eq!(parse_ipv6_interface(Some("::%nope")).unwrap(), 0);
+    assert!(parse_ipv6_interface(Some("10.0.0.2")).is_err());
+  }
+
+  #[test]
+  fn udp_set_socket_options() {
+    let options = [
+      (SocketOption::Broadcast, 1),
+      (SocketOption::MulticastTtl, 8),
+      (SocketOption::MulticastLoopback, 0),
+      (SocketOption::Ttl, 32),
+    ];
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let socket = SockRef::from(&socket);
+    for (option, value) in options {
+      set_socket_option(&socket, option, value).unwrap();
+      assert_eq!(get_socket_option(&socket, option).unwrap(), value);
+    }
+    assert!(socket.broadcast().unwrap());
+    assert!(!socket.multicast_loop_v4().unwrap());
+    assert_eq!(socket.multicast_ttl_v4().unwrap(), 8);
+    assert_eq!(socket.ttl().unwrap(), 32);
+
+    // IPv6 may not be available
+    let Ok(socket) = std::net::UdpSocket::bind("[::1]:0") else {
+      return;
+    };
+    let socket = SockRef::from(&socket);
+    for (option, value) in options {
+      set_socket_option(&socket, option, value).unwrap();
+      assert_eq!(get_socket_option(&socket, option).unwrap(), value);
+    }
+    assert_eq!(socket.multicast_hops_v6().unwrap(), 8);
+    assert_eq!(socket.unicast_hops_v6().unwrap(), 32);
+    assert!(!socket.multicast_loop_v6().unwrap());
+  }
+
   #[allow(clippy::type_complexity)]
   async fn check_sockopt(
     addr: String,
@@ -1071,9 +1765,12 @@ mod tests {
       port: server_addr[1].parse().unwrap(),
     };
 
-    let mut connect_fut =
-      op_net_connect_tcp_inner::<TestPermission>(conn_state, ip_addr)
-        .boxed_local();
+    let mut connect_fut = op_net_connect_tcp_inner::<TestPermission>(
+      conn_state,
+      ip_addr,
+      Default::default(),
+    )
+    .boxed_local();
     let mut rid = None;
 
     tokio::select! {
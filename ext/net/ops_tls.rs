@@ -1,21 +1,27 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+use crate::happy_eyeballs;
 use crate::io::TcpStreamResource;
+use crate::keying_material::ExporterSecret;
 use crate::ops::IpAddr;
 use crate::ops::TlsHandshakeInfo;
+use crate::proxy;
+use crate::proxy::Proxy;
 use crate::raw::NetworkListenerResource;
-use crate::resolve_addr::resolve_addr;
 use crate::resolve_addr::resolve_addr_sync;
 use crate::tcp::TcpListener;
 use crate::DefaultTlsOptions;
 use crate::NetPermissions;
 use crate::UnsafelyIgnoreCertificateErrors;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
 use deno_core::anyhow::anyhow;
 use deno_core::anyhow::bail;
 use deno_core::error::bad_resource;
 use deno_core::error::custom_error;
 use deno_core::error::generic_error;
 use deno_core::error::invalid_hostname;
+use deno_core::error::type_error;
 use deno_core::error::AnyError;
 use deno_core::op2;
 use deno_core::v8;
@@ -23,19 +29,27 @@ use deno_core::AsyncRefCell;
 use deno_core::AsyncResult;
 use deno_core::CancelHandle;
 use deno_core::CancelTryFuture;
+use deno_core::JsBuffer;
 use deno_core::OpState;
 use deno_core::RcRef;
 use deno_core::Resource;
 use deno_core::ResourceId;
+use deno_tls::create_client_cert_verifier;
 use deno_tls::create_client_config;
 use deno_tls::load_certs;
 use deno_tls::load_private_keys;
 use deno_tls::new_resolver;
+use deno_tls::rustls::client::ClientSessionMemoryCache;
+use deno_tls::rustls::client::Resumption;
 use deno_tls::rustls::pki_types::ServerName;
+use deno_tls::rustls::server::danger::ClientCertVerifier;
+use deno_tls::rustls::ClientConfig;
 use deno_tls::rustls::ClientConnection;
 use deno_tls::rustls::ServerConfig;
 use deno_tls::webpki::types::CertificateDer;
 use deno_tls::webpki::types::PrivateKeyDer;
+use deno_tls::ClientCertificate;
+use deno_tls::ClientCertificateVerification;
 use deno_tls::ServerConfigProvider;
 use deno_tls::SocketUse;
 use deno_tls::TlsKey;
@@ -57,9 +71,9 @@ use std::num::NonZeroUsize;
 use std::path::Path;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::AsyncReadExt;
 use tokio::io::AsyncWriteExt;
-use tokio::net::TcpStream;
 
 pub use rustls_tokio_stream::TlsStream;
 
@@ -70,26 +84,58 @@ pub struct TlsListener {
   pub(crate) tcp_listener: TcpListener,
   pub(crate) tls_config: Option<Arc<ServerConfig>>,
   pub(crate) server_config_provider: Option<ServerConfigProvider>,
+  pub(crate) client_auth: Option<ClientAuth>,
+}
+
+/// How a [`TlsListener`] authenticates its clients with certificates.
+#[derive(Clone)]
+pub(crate) struct ClientAuth {
+  verifier: Arc<dyn ClientCertVerifier>,
+  required: bool,
 }
 
 impl TlsListener {
   pub async fn accept(&self) -> std::io::Result<(TlsStream, SocketAddr)> {
+    let (tls, addr, _) = self.accept_with_client_certificate().await?;
+    Ok((tls, addr))
+  }
+
+  /// Accepts a connection, along with the certificate that its client will
+  /// present during the handshake if the listener requests one.
+  pub async fn accept_with_client_certificate(
+    &self,
+  ) -> std::io::Result<(TlsStream, SocketAddr, Option<Arc<ClientCertificate>>)>
+  {
     let (tcp, addr) = self.tcp_listener.accept().await?;
-    let tls = if let Some(provider) = &self.server_config_provider {
-      TlsStream::new_server_side_acceptor(
+    if let Some(provider) = &self.server_config_provider {
+      let tls = TlsStream::new_server_side_acceptor(
         tcp,
         provider.clone(),
         TLS_BUFFER_SIZE,
-      )
-    } else {
-      TlsStream::new_server_side(
-        tcp,
-        self.tls_config.clone().unwrap(),
-        TLS_BUFFER_SIZE,
-      )
+      );
+      return Ok((tls, addr, None));
+    }
+    let tls_config = self.tls_config.clone().unwrap();
+    let Some(client_auth) = &self.client_auth else {
+      let tls = TlsStream::new_server_side(tcp, tls_config, TLS_BUFFER_SIZE);
+      return Ok((tls, addr, None));
     };
-    Ok((tls, addr))
+    // The verifier captures the certificate, so every connection gets its own
+    // config. Sessions are not resumed with it, which would skip the verifier.
+    let certificate = Arc::new(ClientCertificate::default());
+    let verifier = ClientCertificateVerification::new(
+      client_auth.verifier.clone(),
+      client_auth.required,
+      certificate.clone(),
+    );
+    let mut config = ServerConfig::builder()
+      .with_client_cert_verifier(Arc::new(verifier))
+      .with_cert_resolver(tls_config.cert_resolver.clone());
+    config.alpn_protocols.clone_from(&tls_config.alpn_protocols);
+    let tls = TlsStream::new_server_side(tcp, config.into(), TLS_BUFFER_SIZE);
+    Ok((tls, addr, Some(certificate)))
   }
+
   pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
     self.tcp_listener.local_addr()
   }
@@ -102,6 +148,8 @@ pub struct TlsStreamResource {
   // `None` when a TLS handshake hasn't been done.
   handshake_info: RefCell<Option<TlsHandshakeInfo>>,
   cancel_handle: CancelHandle, // Only read and handshake ops get canceled.
+  // `None` for the server side of connections.
+  exporter_secret: Option<Arc<ExporterSecret>>,
 }
 
 impl TlsStreamResource {
@@ -111,6 +159,14 @@ impl TlsStreamResource {
       wr: wr.into(),
       handshake_info: RefCell::new(None),
       cancel_handle: Default::default(),
+      exporter_secret: None,
+    }
+  }
+
+  fn with_exporter_secret(self, exporter_secret: Arc<ExporterSecret>) -> Self {
+    Self {
+      exporter_secret: Some(exporter_secret),
+      ..self
     }
   }
 
@@ -158,6 +214,16 @@ impl TlsStreamResource {
   }
 }
 
+/// A cache of the sessions of TLS connections, which lets later connections
+/// to the same server resume them instead of doing a full handshake.
+struct TlsSessionCacheResource(Arc<ClientSessionMemoryCache>);
+
+impl Resource for TlsSessionCacheResource {
+  fn name(&self) -> Cow<str> {
+    "tlsSessionCache".into()
+  }
+}
+
 impl Resource for TlsStreamResource {
   deno_core::impl_readable_byob!();
   deno_core::impl_writable!();
@@ -182,6 +248,13 @@ pub struct ConnectTlsArgs {
   ca_certs: Vec<String>,
   alpn_protocols: Option<Vec<String>>,
   server_name: Option<String>,
+  connection_attempt_delay: Option<u64>,
+  proxy: Option<deno_tls::Proxy>,
+  #[serde(default)]
+  ca_cert_files: Vec<String>,
+  #[serde(default)]
+  pinned_public_keys: Vec<String>,
+  session_cache: Option<ResourceId>,
 }
 
 #[derive(Deserialize)]
@@ -191,6 +264,83 @@ pub struct StartTlsArgs {
   ca_certs: Vec<String>,
   hostname: String,
   alpn_protocols: Option<Vec<String>>,
+  #[serde(default)]
+  ca_cert_files: Vec<String>,
+  #[serde(default)]
+  pinned_public_keys: Vec<String>,
+  session_cache: Option<ResourceId>,
+}
+
+/// Decodes the pinned public keys, which are base64 encoded SHA-256 hashes of
+/// the SubjectPublicKeyInfo of the keys.
+fn decode_pinned_public_keys(
+  pins: Vec<String>,
+) -> Result<Vec<[u8; 32]>, AnyError> {
+  pins
+    .into_iter()
+    .map(|pin| {
+      BASE64_STANDARD
+        .decode(&pin)
+        .ok()
+        .and_then(|hash| <[u8; 32]>::try_from(hash).ok())
+        .ok_or_else(|| type_error(format!("Invalid pinned public key: {pin}")))
+    })
+    .collect()
+}
+
+/// Lets the connection resume the sessions in the cache, and captures its
+/// exporter secret.
+fn configure_client_session(
+  state: &OpState,
+  tls_config: &mut ClientConfig,
+  session_cache: Option<ResourceId>,
+) -> Result<Arc<ExporterSecret>, AnyError> {
+  if let Some(rid) = session_cache {
+    let cache = state.resource_table.get::<TlsSessionCacheResource>(rid)?;
+    tls_config.resumption = Resumption::store(cache.0.clone());
+  }
+  let exporter_secret = Arc::new(ExporterSecret::default());
+  tls_config.key_log = exporter_secret.clone();
+  Ok(exporter_secret)
+}
+
+#[op2(fast)]
+#[smi]
+pub fn op_tls_session_cache_create(
+  state: &mut OpState,
+  #[smi] capacity: u32,
+) -> ResourceId {
+  super::check_unstable(state, "Deno.createTlsSessionCache");
+  let cache = ClientSessionMemoryCache::new(capacity as usize);
+  state
+    .resource_table
+    .add(TlsSessionCacheResource(Arc::new(cache)))
+}
+
+/// Exports keying material from a client connection with TLS 1.3, as
+/// described by RFC 5705, once the handshake is done.
+#[op2(async)]
+#[buffer]
+pub async fn op_tls_export_keying_material(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  #[string] label: String,
+  #[buffer] context: JsBuffer,
+  #[number] length: usize,
+) -> Result<Vec<u8>, AnyError> {
+  let resource = {
+    let state = state.borrow();
+    super::check_unstable(&state, "Deno.TlsConn.exportKeyingMaterial");
+    state.resource_table.get::<TlsStreamResource>(rid)?
+  };
+  let Some(exporter_secret) = resource.exporter_secret.clone() else {
+    return Err(custom_error(
+      "NotSupported",
+      "Keying material can only be exported by the client",
+    ));
+  };
+  resource.handshake().await?;
+  exporter_secret.export(label.as_bytes(), &context, length)
 }
 
 #[op2]
@@ -301,13 +451,22 @@ where
     let mut s = state.borrow_mut();
     let permissions = s.borrow_mut::<NP>();
     permissions.check_net(&(&hostname, Some(0)), "Deno.startTls()")?;
+    for path in &args.ca_cert_files {
+      permissions.check_read(Path::new(path), "Deno.startTls()")?;
+    }
   }
 
-  let ca_certs = args
+  let pinned_public_keys = decode_pinned_public_keys(args.pinned_public_keys)?;
+  let mut ca_certs = args
     .ca_certs
     .into_iter()
     .map(|s| s.into_bytes())
     .collect::<Vec<_>>();
+  for path in args.ca_cert_files {
+    let mut buf = Vec::new();
+    File::open(path)?.read_to_end(&mut buf)?;
+    ca_certs.push(buf);
+  }
 
   let hostname_dns = ServerName::try_from(hostname.to_string())
     .map_err(|_| invalid_hostname(&hostname))?;
@@ -343,6 +502,7 @@ where
     unsafely_ignore_certificate_errors,
     TlsKeys::Null,
     SocketUse::GeneralSsl,
+    pinned_public_keys,
   )?;
 
   if let Some(alpn_protocols) = args.alpn_protocols {
@@ -350,6 +510,11 @@ where
       alpn_protocols.into_iter().map(|s| s.into_bytes()).collect();
   }
 
+  let exporter_secret = configure_client_session(
+    &state.borrow(),
+    &mut tls_config,
+    args.session_cache,
+  )?;
   let tls_config = Arc::new(tls_config);
   let tls_stream = TlsStream::new_client_side(
     tcp_stream,
@@ -359,9 +524,10 @@ where
 
   let rid = {
     let mut state_ = state.borrow_mut();
-    state_
-      .resource_table
-      .add(TlsStreamResource::new(tls_stream.into_split()))
+    state_.resource_table.add(
+      TlsStreamResource::new(tls_stream.into_split())
+        .with_exporter_secret(exporter_secret),
+    )
   };
 
   Ok((rid, IpAddr::from(local_addr), IpAddr::from(remote_addr)))
@@ -384,14 +550,27 @@ where
     .try_borrow::<UnsafelyIgnoreCertificateErrors>()
     .and_then(|it| it.0.clone());
 
+  let proxy = args.proxy.map(Proxy::parse).transpose()?;
   {
     let mut s = state.borrow_mut();
+    if proxy.is_some() {
+      super::check_unstable(&s, "Deno.connectTls({ proxy })");
+    }
     let permissions = s.borrow_mut::<NP>();
     permissions
       .check_net(&(&addr.hostname, Some(addr.port)), "Deno.connectTls()")?;
+    if let Some(proxy) = &proxy {
+      permissions.check_net(
+        &(proxy.hostname(), Some(proxy.port())),
+        "Deno.connectTls()",
+      )?;
+    }
     if let Some(path) = cert_file {
       permissions.check_read(Path::new(path), "Deno.connectTls()")?;
     }
+    for path in &args.ca_cert_files {
+      permissions.check_read(Path::new(path), "Deno.connectTls()")?;
+    }
   }
 
   let mut ca_certs = args
@@ -405,6 +584,12 @@ where
     File::open(path)?.read_to_end(&mut buf)?;
     ca_certs.push(buf);
   };
+  for path in &args.ca_cert_files {
+    let mut buf = Vec::new();
+    File::open(path)?.read_to_end(&mut buf)?;
+    ca_certs.push(buf);
+  }
+  let pinned_public_keys = decode_pinned_public_keys(args.pinned_public_keys)?;
 
   let root_cert_store = state
     .borrow()
@@ -416,11 +601,12 @@ where
     ServerName::try_from(addr.hostname.clone())
   }
   .map_err(|_| invalid_hostname(&addr.hostname))?;
-  let connect_addr = resolve_addr(&addr.hostname, addr.port)
-    .await?
-    .next()
-    .ok_or_else(|| generic_error("No resolved address found"))?;
-  let tcp_stream = TcpStream::connect(connect_addr).await?;
+  let delay = args
+    .connection_attempt_delay
+    .map(Duration::from_millis)
+    .unwrap_or(happy_eyeballs::DEFAULT_CONNECTION_ATTEMPT_DELAY);
+  let tcp_stream =
+    proxy::connect(&addr.hostname, addr.port, delay, proxy.as_ref()).await?;
   let local_addr = tcp_stream.local_addr()?;
   let remote_addr = tcp_stream.peer_addr()?;
 
@@ -430,6 +616,7 @@ where
     unsafely_ignore_certificate_errors,
     key_pair.take(),
     SocketUse::GeneralSsl,
+    pinned_public_keys,
   )?;
 
   if let Some(alpn_protocols) = args.alpn_protocols {
@@ -437,6 +624,11 @@ where
       alpn_protocols.into_iter().map(|s| s.into_bytes()).collect();
   }
 
+  let exporter_secret = configure_client_session(
+    &state.borrow(),
+    &mut tls_config,
+    args.session_cache,
+  )?;
   let tls_config = Arc::new(tls_config);
 
   let tls_stream = TlsStream::new_client_side(
@@ -447,9 +639,10 @@ where
 
   let rid = {
     let mut state_ = state.borrow_mut();
-    state_
-      .resource_table
-      .add(TlsStreamResource::new(tls_stream.into_split()))
+    state_.resource_table.add(
+      TlsStreamResource::new(tls_stream.into_split())
+        .with_exporter_secret(exporter_secret),
+    )
   };
 
   Ok((rid, IpAddr::from(local_addr), IpAddr::from(remote_addr)))
@@ -477,6 +670,9 @@ pub struct ListenTlsArgs {
   reuse_port: bool,
   #[serde(default)]
   load_balanced: bool,
+  client_ca_certs: Option<Vec<String>>,
+  #[serde(default)]
+  require_client_cert: bool,
 }
 
 #[op2]
@@ -510,6 +706,20 @@ where
     TcpListener::bind_direct(bind_addr, args.reuse_port)
   }?;
   let local_addr = tcp_listener.local_addr()?;
+  let client_auth = match args.client_ca_certs {
+    Some(ca_certs) => {
+      super::check_unstable(state, "Deno.listenTls({ clientCaCerts })");
+      let ca_certs = ca_certs.into_iter().map(|s| s.into_bytes()).collect();
+      Some(ClientAuth {
+        verifier: create_client_cert_verifier(ca_certs)?,
+        required: args.require_client_cert,
+      })
+    }
+    None if args.require_client_cert => {
+      return Err(type_error("requireClientCert requires clientCaCerts"));
+    }
+    None => None,
+  };
   let alpn = args
     .alpn_protocols
     .unwrap_or_default()
@@ -528,12 +738,17 @@ where
         tcp_listener,
         tls_config: Some(tls_config.into()),
         server_config_provider: None,
+        client_auth,
       })
     }
+    TlsKeys::Resolver(_) if client_auth.is_some() => Err(anyhow!(
+      "Client certificates can't be requested with a certificate resolver"
+    )),
     TlsKeys::Resolver(resolver) => Ok(TlsListener {
       tcp_listener,
       tls_config: None,
       server_config_provider: Some(resolver.into_server_config_provider(alpn)),
+      client_auth: None,
     }),
   }
   .map_err(|e| {
@@ -2,9 +2,11 @@
 
 use crate::io::UnixStreamResource;
 use crate::raw::NetworkListenerResource;
+use crate::tcp::TcpListener;
 use crate::NetPermissions;
 use deno_core::error::bad_resource;
 use deno_core::error::custom_error;
+use deno_core::error::type_error;
 use deno_core::error::AnyError;
 use deno_core::op2;
 use deno_core::AsyncRefCell;
@@ -17,10 +19,19 @@ use deno_core::Resource;
 use deno_core::ResourceId;
 use serde::Deserialize;
 use serde::Serialize;
+use socket2::Domain;
+use socket2::SockAddr;
+use socket2::SockRef;
+use socket2::Socket;
+use socket2::Type;
 use std::borrow::Cow;
 use std::cell::RefCell;
+use std::mem::MaybeUninit;
+use std::os::fd::AsFd;
+use std::os::fd::BorrowedFd;
 use std::path::Path;
 use std::rc::Rc;
+use tokio::io::Interest;
 use tokio::net::UnixDatagram;
 use tokio::net::UnixListener;
 pub use tokio::net::UnixStream;
@@ -58,6 +69,49 @@ pub struct UnixListenArgs {
   pub path: String,
 }
 
+/// Parses the path of a unix socket. A path which starts with a NUL byte,
+/// like `"\0name"`, is a name in the abstract namespace, which only Linux
+/// has.
+fn unix_addr(path: &str) -> Result<SockAddr, AnyError> {
+  if path.starts_with('\0')
+    && !cfg!(any(target_os = "android", target_os = "linux"))
+  {
+    return Err(custom_error(
+      "NotSupported",
+      "Abstract unix socket addresses are only supported on Linux",
+    ));
+  }
+  Ok(SockAddr::unix(path)?)
+}
+
+/// The reverse of [`unix_addr`], or `None` if the socket is unnamed.
+fn addr_path(addr: &SockAddr) -> Result<Option<String>, AnyError> {
+  if let Some(path) = addr.as_pathname() {
+    return pathstring(path).map(Some);
+  }
+  let Some(name) = addr.as_abstract_namespace() else {
+    return Ok(None);
+  };
+  let name = std::str::from_utf8(name).map_err(|_| {
+    custom_error("InvalidData", "Abstract socket name is not valid UTF-8")
+  })?;
+  Ok(Some(format!("\0{name}")))
+}
+
+fn local_addr_path(socket: &impl AsFd) -> Result<Option<String>, AnyError> {
+  addr_path(&SockRef::from(socket).local_addr()?)
+}
+
+fn peer_addr_path(socket: &impl AsFd) -> Result<Option<String>, AnyError> {
+  addr_path(&SockRef::from(socket).peer_addr()?)
+}
+
+fn new_socket(ty: Type) -> std::io::Result<Socket> {
+  let socket = Socket::new(Domain::UNIX, ty, None)?;
+  socket.set_nonblocking(true)?;
+  Ok(socket)
+}
+
 #[op2(async)]
 #[serde]
 pub async fn op_net_accept_unix(
@@ -79,11 +133,8 @@ pub async fn op_net_accept_unix(
     .await
     .map_err(crate::ops::accept_err)?;
 
-  let local_addr = unix_stream.local_addr()?;
-  let remote_addr = unix_stream.peer_addr()?;
-  let local_addr_path = local_addr.as_pathname().map(pathstring).transpose()?;
-  let remote_addr_path =
-    remote_addr.as_pathname().map(pathstring).transpose()?;
+  let local_addr_path = local_addr_path(&unix_stream)?;
+  let remote_addr_path = peer_addr_path(&unix_stream)?;
   let resource = UnixStreamResource::new(unix_stream.into_split());
   let mut state = state.borrow_mut();
   let rid = state.resource_table.add(resource);
@@ -109,12 +160,21 @@ where
       .borrow_mut::<NP>()
       .check_write(address_path, "Deno.connect()")?;
   }
-  let unix_stream = UnixStream::connect(Path::new(&path)).await?;
-  let local_addr = unix_stream.local_addr()?;
-  let remote_addr = unix_stream.peer_addr()?;
-  let local_addr_path = local_addr.as_pathname().map(pathstring).transpose()?;
-  let remote_addr_path =
-    remote_addr.as_pathname().map(pathstring).transpose()?;
+  let addr = unix_addr(&path)?;
+  let socket = new_socket(Type::STREAM)?;
+  match socket.connect(&addr) {
+    Ok(()) => {}
+    Err(err) if err.raw_os_error() == Some(libc::EINPROGRESS) => {}
+    Err(err) => return Err(err.into()),
+  }
+  let unix_stream = UnixStream::from_std(socket.into())?;
+  // like `UnixStream::connect`, wait until the connection is established
+  unix_stream.writable().await?;
+  if let Some(err) = unix_stream.take_error()? {
+    return Err(err.into());
+  }
+  let local_addr_path = local_addr_path(&unix_stream)?;
+  let remote_addr_path = peer_addr_path(&unix_stream)?;
   let mut state_ = state.borrow_mut();
   let resource = UnixStreamResource::new(unix_stream.into_split());
   let rid = state_.resource_table.add(resource);
@@ -137,9 +197,17 @@ pub async fn op_net_recv_unixpacket(
     .try_borrow_mut()
     .ok_or_else(|| custom_error("Busy", "Socket already in use"))?;
   let cancel = RcRef::map(resource, |r| &r.cancel);
-  let (nread, remote_addr) =
-    socket.recv_from(&mut buf).try_or_cancel(cancel).await?;
-  let path = remote_addr.as_pathname().map(pathstring).transpose()?;
+  // tokio's addresses can't be in the abstract namespace
+  let (nread, remote_addr) = socket
+    .async_io(Interest::READABLE, || {
+      // SAFETY: the buffer is initialized already, and it's only written to
+      let buf =
+        unsafe { &mut *(&mut *buf as *mut [u8] as *mut [MaybeUninit<u8>]) };
+      SockRef::from(&*socket).recv_from(buf)
+    })
+    .try_or_cancel(cancel)
+    .await?;
+  let path = addr_path(&remote_addr)?;
   Ok((nread, path))
 }
 
@@ -148,18 +216,23 @@ pub async fn op_net_recv_unixpacket(
 pub async fn op_net_send_unixpacket<NP>(
   state: Rc<RefCell<OpState>>,
   #[smi] rid: ResourceId,
-  #[string] path: String,
+  #[string] path: Option<String>,
   #[buffer] zero_copy: JsBuffer,
 ) -> Result<usize, AnyError>
 where
   NP: NetPermissions + 'static,
 {
-  let address_path = Path::new(&path);
-  {
-    let mut s = state.borrow_mut();
-    s.borrow_mut::<NP>()
-      .check_write(address_path, "Deno.DatagramConn.send()")?;
-  }
+  // without a path, the message is sent to the peer that the socket is
+  // connected to, which was checked already
+  let addr = match &path {
+    Some(path) => {
+      let mut s = state.borrow_mut();
+      s.borrow_mut::<NP>()
+        .check_write(Path::new(path), "Deno.DatagramConn.send()")?;
+      Some(unix_addr(path)?)
+    }
+    None => None,
+  };
 
   let resource = state
     .borrow()
@@ -169,7 +242,16 @@ where
   let socket = RcRef::map(&resource, |r| &r.socket)
     .try_borrow_mut()
     .ok_or_else(|| custom_error("Busy", "Socket already in use"))?;
-  let nwritten = socket.send_to(&zero_copy, address_path).await?;
+  let nwritten = match addr {
+    Some(addr) => {
+      socket
+        .async_io(Interest::WRITABLE, || {
+          SockRef::from(&*socket).send_to(&zero_copy, &addr)
+        })
+        .await?
+    }
+    None => socket.send(&zero_copy).await?,
+  };
 
   Ok(nwritten)
 }
@@ -189,14 +271,110 @@ where
   let api_call_expr = format!("{}()", api_name);
   permissions.check_read(address_path, &api_call_expr)?;
   permissions.check_write(address_path, &api_call_expr)?;
-  let listener = UnixListener::bind(address_path)?;
-  let local_addr = listener.local_addr()?;
-  let pathname = local_addr.as_pathname().map(pathstring).transpose()?;
+  let socket = new_socket(Type::STREAM)?;
+  socket.bind(&unix_addr(&path)?)?;
+  // the same backlog as `UnixListener::bind`
+  socket.listen(1024)?;
+  let listener = UnixListener::from_std(socket.into())?;
+  let pathname = local_addr_path(&listener)?;
   let listener_resource = NetworkListenerResource::new(listener);
   let rid = state.resource_table.add(listener_resource);
   Ok((rid, pathname))
 }
 
+/// The address of a listener adopted with [`op_net_listen_fd`].
+#[derive(Serialize)]
+#[serde(tag = "transport", rename_all = "lowercase")]
+pub enum ListenFdAddr {
+  Tcp { hostname: String, port: u16 },
+  Unix { path: Option<String> },
+}
+
+/// Duplicates an inherited file descriptor, checking that it is a listening
+/// stream socket. The original stays open, so that the same descriptor may be
+/// adopted again.
+fn adopt_listener_fd(fd: i32) -> Result<Socket, AnyError> {
+  if fd < 0 {
+    return Err(type_error(format!("Invalid file descriptor: {fd}")));
+  }
+  // SAFETY: the file descriptor is only borrowed to duplicate it, which fails
+  // if it is not open.
+  let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+  let socket = Socket::from(borrowed.try_clone_to_owned()?);
+  let not_a_listener = || {
+    type_error(format!(
+      "File descriptor {fd} is not a listening stream socket"
+    ))
+  };
+  if !matches!(socket.r#type(), Ok(Type::STREAM)) {
+    return Err(not_a_listener());
+  }
+  #[cfg(any(target_os = "android", target_os = "linux"))]
+  if !socket.is_listener()? {
+    return Err(not_a_listener());
+  }
+  socket.set_nonblocking(true)?;
+  Ok(socket)
+}
+
+/// Adopts a listening socket inherited from the parent process, as with
+/// systemd socket activation (`LISTEN_FDS`), and checks the permissions for
+/// the address it is bound to.
+#[op2]
+#[serde]
+pub fn op_net_listen_fd<NP>(
+  state: &mut OpState,
+  #[smi] fd: i32,
+  #[string] api_name: String,
+) -> Result<(ResourceId, ListenFdAddr), AnyError>
+where
+  NP: NetPermissions + 'static,
+{
+  super::check_unstable(state, &format!("{api_name}({{ fd }})"));
+  let socket = adopt_listener_fd(fd)?;
+  let local_addr = socket.local_addr()?;
+  let api_call_expr = format!("{}()", api_name);
+
+  if let Some(addr) = local_addr.as_socket() {
+    let hostname = addr.ip().to_string();
+    state
+      .borrow_mut::<NP>()
+      .check_net(&(&hostname, Some(addr.port())), &api_call_expr)?;
+    let listener = TcpListener::from_std(socket.into())?;
+    let rid = state
+      .resource_table
+      .add(NetworkListenerResource::new(listener));
+    return Ok((
+      rid,
+      ListenFdAddr::Tcp {
+        hostname,
+        port: addr.port(),
+      },
+    ));
+  }
+
+  if !local_addr.is_unix() {
+    return Err(type_error(format!(
+      "File descriptor {fd} is neither a TCP nor a unix socket"
+    )));
+  }
+  let Some(path) = addr_path(&local_addr)? else {
+    return Err(custom_error(
+      "NotSupported",
+      "Unnamed unix sockets can not be adopted",
+    ));
+  };
+  let address_path = Path::new(&path);
+  let permissions = state.borrow_mut::<NP>();
+  permissions.check_read(address_path, &api_call_expr)?;
+  permissions.check_write(address_path, &api_call_expr)?;
+  let listener = UnixListener::from_std(socket.into())?;
+  let rid = state
+    .resource_table
+    .add(NetworkListenerResource::new(listener));
+  Ok((rid, ListenFdAddr::Unix { path: Some(path) }))
+}
+
 pub fn net_listen_unixpacket<NP>(
   state: &mut OpState,
   path: String,
@@ -208,9 +386,10 @@ where
   let permissions = state.borrow_mut::<NP>();
   permissions.check_read(address_path, "Deno.listenDatagram()")?;
   permissions.check_write(address_path, "Deno.listenDatagram()")?;
-  let socket = UnixDatagram::bind(address_path)?;
-  let local_addr = socket.local_addr()?;
-  let pathname = local_addr.as_pathname().map(pathstring).transpose()?;
+  let socket = new_socket(Type::DGRAM)?;
+  socket.bind(&unix_addr(&path)?)?;
+  let socket = UnixDatagram::from_std(socket.into())?;
+  let pathname = local_addr_path(&socket)?;
   let datagram_resource = UnixDatagramResource {
     socket: AsyncRefCell::new(socket),
     cancel: Default::default(),
@@ -244,6 +423,85 @@ where
   net_listen_unixpacket::<NP>(state, path)
 }
 
+/// Creates an unnamed datagram socket which is connected to `path`, so that
+/// messages can be sent without an address, e.g. to syslog.
+#[op2]
+#[serde]
+pub fn op_net_connect_unixpacket<NP>(
+  state: &mut OpState,
+  #[string] path: String,
+) -> Result<(ResourceId, Option<String>, Option<String>), AnyError>
+where
+  NP: NetPermissions + 'static,
+{
+  super::check_unstable(state, "Deno.connectDatagram");
+  let address_path = Path::new(&path);
+  let permissions = state.borrow_mut::<NP>();
+  permissions.check_read(address_path, "Deno.connectDatagram()")?;
+  permissions.check_write(address_path, "Deno.connectDatagram()")?;
+  let socket = new_socket(Type::DGRAM)?;
+  // connecting a datagram socket doesn't block
+  socket.connect(&unix_addr(&path)?)?;
+  let socket = UnixDatagram::from_std(socket.into())?;
+  let local_addr_path = local_addr_path(&socket)?;
+  let remote_addr_path = peer_addr_path(&socket)?;
+  let rid = state.resource_table.add(UnixDatagramResource {
+    socket: AsyncRefCell::new(socket),
+    cancel: Default::default(),
+  });
+  Ok((rid, local_addr_path, remote_addr_path))
+}
+
 pub fn pathstring(pathname: &Path) -> Result<String, AnyError> {
   into_string(pathname.into())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::os::fd::AsRawFd;
+
+  #[test]
+  fn adopt_listener_fd_duplicates_listeners() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let socket = adopt_listener_fd(listener.as_raw_fd()).unwrap();
+    assert_ne!(socket.as_raw_fd(), listener.as_raw_fd());
+    assert_eq!(
+      socket.local_addr().unwrap().as_socket(),
+      Some(listener.local_addr().unwrap())
+    );
+    assert!(socket.nonblocking().unwrap());
+
+    // The original is still open
+    drop(socket);
+    assert!(listener.local_addr().is_ok());
+  }
+
+  #[test]
+  fn adopt_listener_fd_rejects_other_fds() {
+    let udp = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let err = adopt_listener_fd(udp.as_raw_fd()).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      format!(
+        "File descriptor {} is not a listening stream socket",
+        udp.as_raw_fd()
+      )
+    );
+
+    let file = std::fs::File::open(std::env::current_exe().unwrap()).unwrap();
+    assert!(adopt_listener_fd(file.as_raw_fd()).is_err());
+
+    let err = adopt_listener_fd(-1).unwrap_err();
+    assert_eq!(err.to_string(), "Invalid file descriptor: -1");
+  }
+
+  #[cfg(any(target_os = "android", target_os = "linux"))]
+  #[test]
+  fn adopt_listener_fd_rejects_unlistened_sockets() {
+    let socket = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    let addr: std::net::SocketAddr = "127.0.0.1:0".parse().unwrap();
+    socket.bind(&addr.into()).unwrap();
+    assert!(adopt_listener_fd(socket.as_raw_fd()).is_err());
+  }
+}
@@ -6,11 +6,14 @@ use deno_core::error::bad_resource_id;
 use deno_core::error::AnyError;
 use deno_core::AsyncRefCell;
 use deno_core::CancelHandle;
+use deno_core::RcRef;
 use deno_core::Resource;
 use deno_core::ResourceId;
 use deno_core::ResourceTable;
+use deno_tls::ClientCertificate;
 use std::borrow::Cow;
 use std::rc::Rc;
+use std::sync::Arc;
 
 pub trait NetworkStreamTrait: Into<NetworkStream> {
   type Resource;
@@ -301,6 +304,29 @@ network_stream!(
   ]
 );
 
+impl NetworkStreamListener {
+  /// Accepts a connection on this listener, along with the certificate that
+  /// its client presents, for TLS listeners that request one.
+  pub async fn accept_with_client_certificate(
+    &self,
+  ) -> Result<
+    (
+      NetworkStream,
+      NetworkStreamAddress,
+      Option<Arc<ClientCertificate>>,
+    ),
+    std::io::Error,
+  > {
+    if let Self::Tls(listener) = self {
+      let (stm, addr, certificate) =
+        listener.accept_with_client_certificate().await?;
+      return Ok((NetworkStream::Tls(stm), addr.into(), certificate));
+    }
+    let (stm, addr) = self.accept().await?;
+    Ok((stm, addr, None))
+  }
+}
+
 pub enum NetworkStreamAddress {
   Ip(std::net::SocketAddr),
   #[cfg(unix)]
@@ -373,3 +399,56 @@ pub fn take_network_stream_listener_resource(
 ) -> Result<NetworkStreamListener, AnyError> {
   NetworkStreamListener::take_resource(resource_table, listener_rid)
 }
+
+/// Duplicates the socket of a TCP listener resource, for example to share it
+/// with another process. The listener must not be accepting connections.
+pub fn clone_tcp_listener(
+  resource_table: &ResourceTable,
+  listener_rid: ResourceId,
+) -> Result<std::net::TcpListener, AnyError> {
+  let resource = resource_table
+    .get::<NetworkListenerResource<crate::tcp::TcpListener>>(listener_rid)?;
+  let listener = RcRef::map(&resource, |r| &r.listener)
+    .try_borrow()
+    .ok_or_else(|| bad_resource("Listener is currently in use"))?;
+  Ok(listener.socket().try_clone()?.into())
+}
+
+/// Adds a resource for a TCP listener whose socket was created elsewhere, for
+/// example one that was received from another process.
+pub fn add_tcp_listener_resource(
+  resource_table: &mut ResourceTable,
+  listener: std::net::TcpListener,
+) -> Result<(ResourceId, std::net::SocketAddr), AnyError> {
+  listener.set_nonblocking(true)?;
+  let listener = crate::tcp::TcpListener::from_std(listener)?;
+  let addr = listener.local_addr()?;
+  let rid = resource_table.add(NetworkListenerResource::new(listener));
+  Ok((rid, addr))
+}
+
+/// Duplicates the socket of a UDP socket resource, for example to share it
+/// with another process.
+pub fn clone_udp_socket(
+  resource_table: &ResourceTable,
+  rid: ResourceId,
+) -> Result<std::net::UdpSocket, AnyError> {
+  let resource = resource_table.get::<crate::ops::UdpSocketResource>(rid)?;
+  let socket = RcRef::map(&resource, |r| &r.socket)
+    .try_borrow()
+    .ok_or_else(|| bad_resource("Socket is currently in use"))?;
+  Ok(socket2::SockRef::from(&*socket).try_clone()?.into())
+}
+
+/// Adds a resource for a UDP socket whose socket was created elsewhere, for
+/// example one that was received from another process.
+pub fn add_udp_socket_resource(
+  resource_table: &mut ResourceTable,
+  socket: std::net::UdpSocket,
+) -> Result<(ResourceId, std::net::SocketAddr), AnyError> {
+  socket.set_nonblocking(true)?;
+  let socket = tokio::net::UdpSocket::from_std(socket)?;
+  let addr = socket.local_addr()?;
+  let rid = resource_table.add(crate::ops::UdpSocketResource::new(socket));
+  Ok((rid, addr))
+}
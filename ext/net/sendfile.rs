@@ -0,0 +1,249 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Sending a range of a file to a TCP connection without copying it through
+//! JavaScript. Linux, macOS and FreeBSD use `sendfile(2)`, so the file isn't
+//! copied at all. Windows has `TransmitFile`, but it needs the overlapped I/O
+//! of the socket, which tokio owns, so the range is copied in chunks there.
+
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use deno_core::op2;
+use deno_core::OpState;
+use deno_core::ResourceHandle;
+use deno_core::ResourceHandleFd;
+use deno_core::ResourceId;
+use tokio::io::Interest;
+use tokio::net::TcpStream;
+
+use crate::io::TcpStreamResource;
+
+/// At most this many bytes are sent at once, which is what Linux sends at
+/// most anyway.
+const MAX_CHUNK_SIZE: u64 = 0x7fff_f000;
+
+/// Sends `len` bytes of the file at `offset`, or all of them up to the end of
+/// the file, and resolves with how many were sent. The position of the file
+/// isn't changed, except on Windows.
+#[op2(async)]
+#[number]
+pub async fn op_net_sendfile(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  #[smi] file_rid: ResourceId,
+  #[number] offset: u64,
+  #[serde] len: Option<u64>,
+) -> Result<u64, AnyError> {
+  let (resource, file) = {
+    let state = state.borrow();
+    let resource = state.resource_table.get::<TcpStreamResource>(rid)?;
+    let file = state.resource_table.get_any(file_rid)?;
+    (resource, file)
+  };
+  // the resource is held onto until the end, so that the file stays open
+  let Some(ResourceHandle::Fd(fd)) = file.clone().backing_handle() else {
+    return Err(type_error("Only files can be sent"));
+  };
+
+  let wr = resource.wr_borrow_mut().await;
+  Ok(send_file(wr.as_ref().as_ref(), fd, offset, len).await?)
+}
+
+async fn send_file(
+  stream: &TcpStream,
+  file: ResourceHandleFd,
+  offset: u64,
+  len: Option<u64>,
+) -> io::Result<u64> {
+  let mut sent = 0;
+  while len.map_or(true, |len| sent < len) {
+    let count = len.map_or(MAX_CHUNK_SIZE, |len| len - sent);
+    let count = count.min(MAX_CHUNK_SIZE);
+    let nsent = stream
+      .async_io(Interest::WRITABLE, || {
+        send_chunk(stream, file, offset + sent, count)
+      })
+      .await?;
+    if nsent == 0 {
+      // the end of the file
+      break;
+    }
+    sent += nsent as u64;
+  }
+  Ok(sent)
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn send_chunk(
+  stream: &TcpStream,
+  file: ResourceHandleFd,
+  offset: u64,
+  count: u64,
+) -> io::Result<usize> {
+  use std::os::fd::AsRawFd;
+
+  let mut offset = offset as libc::off_t;
+  // SAFETY: libc call, both fds are valid
+  let res = unsafe {
+    libc::sendfile(stream.as_raw_fd(), file, &mut offset, count as usize)
+  };
+  if res == -1 {
+    return Err(io::Error::last_os_error());
+  }
+  Ok(res as usize)
+}
+
+#[cfg(any(target_os = "freebsd", target_os = "macos"))]
+fn send_chunk(
+  stream: &TcpStream,
+  file: ResourceHandleFd,
+  offset: u64,
+  count: u64,
+) -> io::Result<usize> {
+  use std::os::fd::AsRawFd;
+
+  #[cfg(target_os = "macos")]
+  let (res, nsent) = {
+    let mut len = count as libc::off_t;
+    // SAFETY: libc call, both fds are valid and `len` lives long enough
+    let res = unsafe {
+      libc::sendfile(
+        file,
+        stream.as_raw_fd(),
+        offset as libc::off_t,
+        &mut len,
+        std::ptr::null_mut(),
+        0,
+      )
+    };
+    (res, len)
+  };
+  #[cfg(target_os = "freebsd")]
+  let (res, nsent) = {
+    let mut nsent: libc::off_t = 0;
+    // SAFETY: libc call, both fds are valid and `nsent` lives long enough
+    let res = unsafe {
+      libc::sendfile(
+        file,
+        stream.as_raw_fd(),
+        offset as libc::off_t,
+        count as usize,
+        std::ptr::null_mut(),
+        &mut nsent,
+        0,
+      )
+    };
+    (res, nsent)
+  };
+  if res == -1 {
+    let err = io::Error::last_os_error();
+    // some of the range may have been sent before the socket's buffer was
+    // full, which isn't an error yet
+    if nsent == 0 || err.kind() != io::ErrorKind::WouldBlock {
+      return Err(err);
+    }
+  }
+  Ok(nsent as usize)
+}
+
+#[cfg(not(any(
+  target_os = "android",
+  target_os = "freebsd",
+  target_os = "linux",
+  target_os = "macos"
+)))]
+fn send_chunk(
+  stream: &TcpStream,
+  file: ResourceHandleFd,
+  offset: u64,
+  count: u64,
+) -> io::Result<usize> {
+  let mut buf = vec![0; count.min(64 * 1024) as usize];
+  let nread = read_at(file, &mut buf, offset)?;
+  if nread == 0 {
+    return Ok(0);
+  }
+  // what isn't written is read again for the next chunk
+  stream.try_write(&buf[..nread])
+}
+
+#[cfg(windows)]
+fn read_at(
+  file: ResourceHandleFd,
+  buf: &mut [u8],
+  offset: u64,
+) -> io::Result<usize> {
+  use std::os::windows::fs::FileExt;
+  use std::os::windows::io::FromRawHandle;
+
+  // SAFETY: the handle is valid, and it's not closed when this is dropped
+  let file = std::mem::ManuallyDrop::new(unsafe {
+    std::fs::File::from_raw_handle(file)
+  });
+  file.seek_read(buf, offset)
+}
+
+#[cfg(all(
+  unix,
+  not(any(
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "linux",
+    target_os = "macos"
+  ))
+))]
+fn read_at(
+  file: ResourceHandleFd,
+  buf: &mut [u8],
+  offset: u64,
+) -> io::Result<usize> {
+  use std::os::fd::FromRawFd;
+  use std::os::unix::fs::FileExt;
+
+  // SAFETY: the fd is valid, and it's not closed when this is dropped
+  let file =
+    std::mem::ManuallyDrop::new(unsafe { std::fs::File::from_raw_fd(file) });
+  file.read_at(buf, offset)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+  use std::os::fd::AsRawFd;
+  use tokio::io::AsyncReadExt;
+  use tokio::net::TcpListener;
+
+  #[tokio::test]
+  async fn send_file_ranges() {
+    let path = std::env::temp_dir()
+      .join(format!("deno_net_sendfile_{}.bin", std::process::id()));
+    let data = (0..200_000).map(|i| i as u8).collect::<Vec<_>>();
+    std::fs::write(&path, &data).unwrap();
+    let file = std::fs::File::open(&path).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let stream = TcpStream::connect(listener.local_addr().unwrap())
+      .await
+      .unwrap();
+    let (mut peer, _) = listener.accept().await.unwrap();
+    let reader = tokio::spawn(async move {
+      let mut received = vec![];
+      peer.read_to_end(&mut received).await.unwrap();
+      received
+    });
+
+    let fd = file.as_raw_fd();
+    assert_eq!(send_file(&stream, fd, 10, Some(5)).await.unwrap(), 5);
+    assert_eq!(send_file(&stream, fd, 1000, None).await.unwrap(), 199_000);
+    assert_eq!(send_file(&stream, fd, 300_000, None).await.unwrap(), 0);
+    drop(stream);
+
+    let received = reader.await.unwrap();
+    assert_eq!(received[..5], data[10..15]);
+    assert_eq!(received[5..], data[1000..]);
+    std::fs::remove_file(path).unwrap();
+  }
+}
@@ -119,6 +119,15 @@ impl TcpListener {
     })
   }
 
+  /// Wraps a listening socket that was created elsewhere, for example one
+  /// inherited from the parent process. It must be in non-blocking mode.
+  pub fn from_std(listener: std::net::TcpListener) -> std::io::Result<Self> {
+    Ok(Self {
+      listener: Some(tokio::net::TcpListener::from_std(listener)?),
+      conn: None,
+    })
+  }
+
   pub async fn accept(
     &self,
   ) -> std::io::Result<(tokio::net::TcpStream, SocketAddr)> {
@@ -129,6 +138,10 @@ impl TcpListener {
   pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
     self.listener.as_ref().unwrap().local_addr()
   }
+
+  pub fn socket(&self) -> socket2::SockRef<'_> {
+    socket2::SockRef::from(self.listener.as_ref().unwrap())
+  }
 }
 
 impl Drop for TcpListener {
@@ -31,6 +31,8 @@ pub use node_resolver::PathClean;
 pub use ops::ipc::ChildPipeFd;
 pub use ops::ipc::IpcJsonStreamResource;
 pub use ops::ipc::IpcRefTracker;
+pub use ops::os::cpus::cpu_info;
+pub use ops::os::cpus::CpuInfo;
 use ops::vm;
 pub use ops::vm::create_v8_context;
 pub use ops::vm::init_global_template;
@@ -364,8 +366,15 @@ deno_core::extension!(deno_node,
     ops::util::op_node_guess_handle_type,
     ops::worker_threads::op_worker_threads_filename<P>,
     ops::ipc::op_node_child_ipc_pipe,
+    ops::ipc::op_node_cluster_env,
     ops::ipc::op_node_ipc_write,
+    ops::ipc::op_node_ipc_write_advanced,
     ops::ipc::op_node_ipc_read,
+    ops::ipc::op_node_ipc_read_advanced,
+    ops::ipc::op_node_ipc_dup_handle,
+    ops::ipc::op_node_ipc_take_socket,
+    ops::ipc::op_node_ipc_take_listener,
+    ops::ipc::op_node_ipc_take_udp,
     ops::ipc::op_node_ipc_ref,
     ops::ipc::op_node_ipc_unref,
     ops::process::op_node_process_kill,
@@ -461,6 +470,7 @@ deno_core::extension!(deno_node,
     "internal_binding/node_file.ts",
     "internal_binding/node_options.ts",
     "internal_binding/pipe_wrap.ts",
+    "internal_binding/spawn_sync.ts",
     "internal_binding/stream_wrap.ts",
     "internal_binding/string_decoder.ts",
     "internal_binding/symbols.ts",
@@ -475,6 +485,12 @@ deno_core::extension!(deno_node,
     "internal/buffer.mjs",
     "internal/child_process.ts",
     "internal/cli_table.ts",
+    "internal/cluster/child.ts",
+    "internal/cluster/primary.ts",
+    "internal/cluster/round_robin_handle.ts",
+    "internal/cluster/shared_handle.ts",
+    "internal/cluster/utils.ts",
+    "internal/cluster/worker.ts",
     "internal/console/constructor.mjs",
     "internal/constants.ts",
     "internal/crypto/_keys.ts",
@@ -152,6 +152,7 @@ where
   Ok(FetchReturn {
     request_rid,
     cancel_handle_rid: Some(cancel_handle_rid),
+    progress_rid: None,
   })
 }
 
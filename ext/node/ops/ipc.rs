@@ -4,8 +4,18 @@ pub use impl_::*;
 
 pub struct ChildPipeFd(pub i64);
 
+/// The environment variable that tells a child how the messages on the IPC
+/// pipe are serialized, `json` or `advanced`.
+pub const SERIALIZATION_MODE_ENV_VAR: &str = "NODE_CHANNEL_SERIALIZATION_MODE";
+
+/// The environment variable with the id of a `node:cluster` worker, which is
+/// set by the primary.
+pub const CLUSTER_WORKER_ID_ENV_VAR: &str = "NODE_UNIQUE_ID";
+
 mod impl_ {
   use std::cell::RefCell;
+  use std::collections::VecDeque;
+  use std::future::poll_fn;
   use std::future::Future;
   use std::io;
   use std::mem;
@@ -18,6 +28,7 @@ mod impl_ {
   use std::task::Poll;
 
   use deno_core::error::bad_resource_id;
+  use deno_core::error::type_error;
   use deno_core::error::AnyError;
   use deno_core::op2;
   use deno_core::serde;
@@ -28,10 +39,13 @@ mod impl_ {
   use deno_core::CancelFuture;
   use deno_core::CancelHandle;
   use deno_core::ExternalOpsTracker;
+  use deno_core::JsBuffer;
   use deno_core::OpState;
   use deno_core::RcRef;
   use deno_core::ResourceId;
+  use deno_core::ToJsBuffer;
   use deno_core::ToV8;
+  use deno_net::ops::IpAddr;
   use memchr::memchr;
   use pin_project_lite::pin_project;
   use serde::Serialize;
@@ -162,22 +176,41 @@ mod impl_ {
     }
   }
 
-  // Open IPC pipe from bootstrap options.
+  // Open IPC pipe from bootstrap options, returning it along with how its
+  // messages are serialized.
   #[op2]
-  #[smi]
+  #[serde]
   pub fn op_node_child_ipc_pipe(
     state: &mut OpState,
-  ) -> Result<Option<ResourceId>, AnyError> {
+  ) -> Result<Option<(ResourceId, String)>, AnyError> {
     let fd = match state.try_borrow_mut::<crate::ChildPipeFd>() {
       Some(child_pipe_fd) => child_pipe_fd.0,
       None => return Ok(None),
     };
+    // Removed so that child processes don't inherit it.
+    let serialization = std::env::var(super::SERIALIZATION_MODE_ENV_VAR)
+      .unwrap_or_else(|_| "json".to_string());
+    std::env::remove_var(super::SERIALIZATION_MODE_ENV_VAR);
     let ref_tracker = IpcRefTracker::new(state.external_ops_tracker.clone());
-    Ok(Some(
+    Ok(Some((
       state
         .resource_table
         .add(IpcJsonStreamResource::new(fd, ref_tracker)?),
-    ))
+      serialization,
+    )))
+  }
+
+  // Read the configuration of `node:cluster` from the environment, without
+  // permission checks like for the IPC pipe: the id of this worker, if it is
+  // one, and the scheduling policy.
+  #[op2]
+  #[serde]
+  pub fn op_node_cluster_env() -> (Option<String>, Option<String>) {
+    let worker_id = std::env::var(super::CLUSTER_WORKER_ID_ENV_VAR).ok();
+    // Removed so that child processes don't inherit it.
+    std::env::remove_var(super::CLUSTER_WORKER_ID_ENV_VAR);
+    let policy = std::env::var("NODE_CLUSTER_SCHED_POLICY").ok();
+    (worker_id, policy)
   }
 
   #[op2(async)]
@@ -192,6 +225,8 @@ mod impl_ {
     // ideally we would just return `Result<(impl Future, bool), ..>`, but that's not
     // supported by `op2` currently.
     queue_ok: v8::Local<'a, v8::Array>,
+    // a socket to send along with the message.
+    handle: Option<u32>,
   ) -> Result<impl Future<Output = Result<(), AnyError>>, AnyError> {
     let mut serialized = Vec::with_capacity(64);
     let mut ser = serde_json::Serializer::new(&mut serialized);
@@ -202,11 +237,47 @@ mod impl_ {
     })?;
     serialized.push(b'\n');
 
+    queue_write(scope, &state, rid, serialized, queue_ok, handle)
+  }
+
+  /// Writes a message that was serialized with the structured clone algorithm
+  /// in JS, prefixed with its length as a big endian `u32`.
+  #[op2(async)]
+  pub fn op_node_ipc_write_advanced<'a>(
+    scope: &mut v8::HandleScope<'a>,
+    state: Rc<RefCell<OpState>>,
+    #[smi] rid: ResourceId,
+    #[buffer] value: JsBuffer,
+    queue_ok: v8::Local<'a, v8::Array>,
+    handle: Option<u32>,
+  ) -> Result<impl Future<Output = Result<(), AnyError>>, AnyError> {
+    let len = u32::try_from(value.len())
+      .map_err(|_| type_error("IPC message is too large"))?;
+    let mut serialized = Vec::with_capacity(4 + value.len());
+    serialized.extend_from_slice(&len.to_be_bytes());
+    serialized.extend_from_slice(&value);
+
+    queue_write(scope, &state, rid, serialized, queue_ok, handle)
+  }
+
+  fn queue_write<'a>(
+    scope: &mut v8::HandleScope<'a>,
+    state: &Rc<RefCell<OpState>>,
+    rid: ResourceId,
+    serialized: Vec<u8>,
+    queue_ok: v8::Local<'a, v8::Array>,
+    handle: Option<u32>,
+  ) -> Result<impl Future<Output = Result<(), AnyError>>, AnyError> {
     let stream = state
       .borrow()
       .resource_table
       .get::<IpcJsonStreamResource>(rid)
       .map_err(|_| bad_resource_id())?;
+    // The socket is duplicated right away, so that it may be closed once this
+    // returned.
+    let handle = handle
+      .map(|handle| dup_handle(&state.borrow(), handle))
+      .transpose()?;
     let old = stream
       .queued_bytes
       .fetch_add(serialized.len(), std::sync::atomic::Ordering::Relaxed);
@@ -216,14 +287,271 @@ mod impl_ {
       queue_ok.set_index(scope, 0, v);
     }
     Ok(async move {
-      stream.clone().write_msg_bytes(&serialized).await?;
+      let res = stream.clone().write_msg_bytes(&serialized, handle).await;
       stream
         .queued_bytes
         .fetch_sub(serialized.len(), std::sync::atomic::Ordering::Relaxed);
-      Ok(())
+      res
     })
   }
 
+  #[cfg(unix)]
+  type Handle = std::os::fd::OwnedFd;
+
+  #[cfg(windows)]
+  type Handle = std::convert::Infallible;
+
+  /// Duplicates the socket of a resource to send it to the other end. TCP
+  /// streams and listeners, and UDP sockets can be sent.
+  #[cfg(unix)]
+  fn dup_handle(state: &OpState, rid: ResourceId) -> Result<Handle, AnyError> {
+    if let Ok(socket) = state.resource_table.get_socket(rid) {
+      // SAFETY: the socket of the resource is open while it is borrowed here
+      let socket = unsafe { std::os::fd::BorrowedFd::borrow_raw(socket) };
+      return Ok(socket.try_clone_to_owned()?);
+    }
+    clone_socket(state, rid)
+  }
+
+  /// Duplicates the socket of a TCP listener or a UDP socket, whose resources
+  /// don't expose their sockets.
+  fn clone_socket(
+    state: &OpState,
+    rid: ResourceId,
+  ) -> Result<OwnedSocket, AnyError> {
+    let table = &state.resource_table;
+    if let Ok(listener) = deno_net::raw::clone_tcp_listener(table, rid) {
+      return Ok(listener.into());
+    }
+    let socket = deno_net::raw::clone_udp_socket(table, rid)
+      .map_err(|_| type_error("This handle type cannot be sent"))?;
+    Ok(socket.into())
+  }
+
+  /// On Windows sockets are not sent over the pipe, see
+  /// [`op_node_ipc_dup_handle`].
+  #[cfg(windows)]
+  fn dup_handle(
+    _state: &OpState,
+    _rid: ResourceId,
+  ) -> Result<Handle, AnyError> {
+    Err(deno_core::error::not_supported())
+  }
+
+  /// Duplicates the socket of a resource for the process `pid` on Windows,
+  /// returning the protocol info that the process creates its socket from
+  /// with [`op_node_ipc_take_socket`], [`op_node_ipc_take_listener`] or
+  /// [`op_node_ipc_take_udp`]. The info is sent along with the message,
+  /// encoded as base64.
+  #[op2]
+  #[string]
+  pub fn op_node_ipc_dup_handle(
+    state: &mut OpState,
+    #[smi] rid: ResourceId,
+    pid: u32,
+  ) -> Result<String, AnyError> {
+    #[cfg(windows)]
+    {
+      use base64::Engine;
+      use std::os::windows::io::AsRawSocket;
+
+      let info = match state.resource_table.get_socket(rid) {
+        Ok(socket) => winsock::duplicate(socket, pid)?,
+        Err(_) => {
+          let socket = clone_socket(state, rid)?;
+          winsock::duplicate(socket.as_raw_socket(), pid)?
+        }
+      };
+      Ok(base64::prelude::BASE64_STANDARD.encode(info))
+    }
+    #[cfg(unix)]
+    {
+      let _ = (state, rid, pid);
+      Err(deno_core::error::not_supported())
+    }
+  }
+
+  #[cfg(unix)]
+  type OwnedSocket = std::os::fd::OwnedFd;
+
+  #[cfg(windows)]
+  type OwnedSocket = std::os::windows::io::OwnedSocket;
+
+  /// Takes the socket that was sent along with a `NODE_HANDLE` message. On
+  /// unix it is the next one that was received over the pipe, on Windows it
+  /// is created from the protocol info in the message.
+  fn take_handle(
+    state: &OpState,
+    rid: ResourceId,
+    info: Option<String>,
+  ) -> Result<OwnedSocket, AnyError> {
+    #[cfg(unix)]
+    {
+      let _ = info;
+      let stream = state
+        .resource_table
+        .get::<IpcJsonStreamResource>(rid)
+        .map_err(|_| bad_resource_id())?;
+      let handle = stream.received_handles.borrow_mut().pop_front();
+      handle.ok_or_else(|| type_error("No handle was received"))
+    }
+    #[cfg(windows)]
+    {
+      use base64::Engine;
+
+      let _ = (state, rid);
+      let info = info.ok_or_else(|| type_error("No handle was received"))?;
+      let info = base64::prelude::BASE64_STANDARD
+        .decode(info)
+        .map_err(|_| type_error("Invalid handle"))?;
+      Ok(winsock::from_protocol_info(&info)?)
+    }
+  }
+
+  /// Takes the socket that was sent along with a `NODE_HANDLE` message as a
+  /// TCP stream.
+  #[op2]
+  #[serde]
+  pub fn op_node_ipc_take_socket(
+    state: &mut OpState,
+    #[smi] rid: ResourceId,
+    #[string] info: Option<String>,
+  ) -> Result<(ResourceId, IpAddr, IpAddr), AnyError> {
+    let socket = take_handle(state, rid, info)?;
+    let stream = std::net::TcpStream::from(socket);
+    stream.set_nonblocking(true)?;
+    let stream = tokio::net::TcpStream::from_std(stream)?;
+    let local_addr = stream.local_addr()?;
+    let remote_addr = stream.peer_addr()?;
+    let rid = state
+      .resource_table
+      .add(deno_net::io::TcpStreamResource::new(stream.into_split()));
+    Ok((rid, IpAddr::from(local_addr), IpAddr::from(remote_addr)))
+  }
+
+  /// Takes the socket that was sent along with a `NODE_HANDLE` message as a
+  /// TCP listener, e.g. one that is shared by the workers of a cluster.
+  #[op2]
+  #[serde]
+  pub fn op_node_ipc_take_listener(
+    state: &mut OpState,
+    #[smi] rid: ResourceId,
+    #[string] info: Option<String>,
+  ) -> Result<(ResourceId, IpAddr), AnyError> {
+    let socket = take_handle(state, rid, info)?;
+    let (rid, addr) = deno_net::raw::add_tcp_listener_resource(
+      &mut state.resource_table,
+      std::net::TcpListener::from(socket),
+    )?;
+    Ok((rid, IpAddr::from(addr)))
+  }
+
+  /// Takes the socket that was sent along with a `NODE_HANDLE` message as a
+  /// UDP socket, e.g. one that is shared by the workers of a cluster.
+  #[op2]
+  #[serde]
+  pub fn op_node_ipc_take_udp(
+    state: &mut OpState,
+    #[smi] rid: ResourceId,
+    #[string] info: Option<String>,
+  ) -> Result<(ResourceId, IpAddr), AnyError> {
+    let socket = take_handle(state, rid, info)?;
+    let (rid, addr) = deno_net::raw::add_udp_socket_resource(
+      &mut state.resource_table,
+      std::net::UdpSocket::from(socket),
+    )?;
+    Ok((rid, IpAddr::from(addr)))
+  }
+
+  /// Sockets are duplicated for another process with `WSADuplicateSocketW`,
+  /// which fills in a `WSAPROTOCOL_INFOW` that the other process passes to
+  /// `WSASocketW` to create its socket.
+  #[cfg(windows)]
+  mod winsock {
+    use std::io;
+    use std::mem::size_of;
+    use std::os::windows::io::FromRawSocket;
+    use std::os::windows::io::OwnedSocket;
+    use std::os::windows::io::RawSocket;
+    use std::sync::Once;
+
+    use windows_sys::Win32::Networking::WinSock::WSADuplicateSocketW;
+    use windows_sys::Win32::Networking::WinSock::WSAGetLastError;
+    use windows_sys::Win32::Networking::WinSock::WSASocketW;
+    use windows_sys::Win32::Networking::WinSock::WSAStartup;
+    use windows_sys::Win32::Networking::WinSock::FROM_PROTOCOL_INFO;
+    use windows_sys::Win32::Networking::WinSock::INVALID_SOCKET;
+    use windows_sys::Win32::Networking::WinSock::SOCKET;
+    use windows_sys::Win32::Networking::WinSock::WSADATA;
+    use windows_sys::Win32::Networking::WinSock::WSAPROTOCOL_INFOW;
+    use windows_sys::Win32::Networking::WinSock::WSA_FLAG_NO_HANDLE_INHERIT;
+    use windows_sys::Win32::Networking::WinSock::WSA_FLAG_OVERLAPPED;
+
+    pub fn duplicate(socket: RawSocket, pid: u32) -> io::Result<Vec<u8>> {
+      // SAFETY: `WSAPROTOCOL_INFOW` is plain data
+      let mut info: WSAPROTOCOL_INFOW = unsafe { std::mem::zeroed() };
+      // SAFETY: winsock call, the socket is open while it is borrowed
+      if unsafe { WSADuplicateSocketW(socket as SOCKET, pid, &mut info) } != 0 {
+        return Err(last_error());
+      }
+      // SAFETY: `info` is plain data of this size
+      let bytes = unsafe {
+        std::slice::from_raw_parts(
+          &info as *const WSAPROTOCOL_INFOW as *const u8,
+          size_of::<WSAPROTOCOL_INFOW>(),
+        )
+      };
+      Ok(bytes.to_vec())
+    }
+
+    pub fn from_protocol_info(bytes: &[u8]) -> io::Result<OwnedSocket> {
+      if bytes.len() != size_of::<WSAPROTOCOL_INFOW>() {
+        return Err(io::Error::new(
+          io::ErrorKind::InvalidData,
+          "Invalid socket protocol info",
+        ));
+      }
+      // SAFETY: `bytes` has the size of the plain data struct
+      let info = unsafe {
+        std::ptr::read_unaligned(bytes.as_ptr() as *const WSAPROTOCOL_INFOW)
+      };
+      startup();
+      // SAFETY: winsock call
+      let socket = unsafe {
+        WSASocketW(
+          FROM_PROTOCOL_INFO,
+          FROM_PROTOCOL_INFO,
+          FROM_PROTOCOL_INFO,
+          &info,
+          0,
+          WSA_FLAG_OVERLAPPED | WSA_FLAG_NO_HANDLE_INHERIT,
+        )
+      };
+      if socket == INVALID_SOCKET {
+        return Err(last_error());
+      }
+      // SAFETY: the socket was just created, and is owned from now on
+      Ok(unsafe { OwnedSocket::from_raw_socket(socket as RawSocket) })
+    }
+
+    // Winsock is initialized by the standard library when it creates its
+    // first socket, which may not have happened yet in this process.
+    fn startup() {
+      static STARTUP: Once = Once::new();
+      STARTUP.call_once(|| {
+        // SAFETY: `WSADATA` is plain data
+        let mut data: WSADATA = unsafe { std::mem::zeroed() };
+        // SAFETY: winsock call, it is never cleaned up like in std
+        unsafe { WSAStartup(0x202, &mut data) };
+      });
+    }
+
+    fn last_error() -> io::Error {
+      // SAFETY: winsock call
+      io::Error::from_raw_os_error(unsafe { WSAGetLastError() })
+    }
+  }
+
   /// Value signaling that the other end ipc channel has closed.
   ///
   /// Node reserves objects of this form (`{ "cmd": "NODE_<something>"`)
@@ -256,6 +584,26 @@ mod impl_ {
     }
   }
 
+  /// Reads a message written by `op_node_ipc_write_advanced`, or returns
+  /// `None` once the other end closed the channel.
+  #[op2(async)]
+  #[serde]
+  pub async fn op_node_ipc_read_advanced(
+    state: Rc<RefCell<OpState>>,
+    #[smi] rid: ResourceId,
+  ) -> Result<Option<ToJsBuffer>, AnyError> {
+    let stream = state
+      .borrow()
+      .resource_table
+      .get::<IpcJsonStreamResource>(rid)
+      .map_err(|_| bad_resource_id())?;
+
+    let cancel = stream.cancel.clone();
+    let mut stream = RcRef::map(stream, |r| &r.read_half).borrow_mut().await;
+    let msg = stream.read_msg_bytes().or_cancel(cancel).await??;
+    Ok(msg.map(ToJsBuffer::from))
+  }
+
   #[op2(fast)]
   pub fn op_node_ipc_ref(state: &mut OpState, #[smi] rid: ResourceId) {
     let stream = state
@@ -343,8 +691,15 @@ mod impl_ {
     cancel: Rc<CancelHandle>,
     queued_bytes: AtomicUsize,
     ref_tracker: IpcRefTracker,
+    /// The sockets received from the other end, in the order of the
+    /// `NODE_HANDLE` messages that they were sent with. Always empty on
+    /// Windows, where they are sent with the messages.
+    #[cfg_attr(windows, allow(dead_code))]
+    received_handles: ReceivedHandles,
   }
 
+  type ReceivedHandles = Rc<RefCell<VecDeque<Handle>>>;
+
   impl deno_core::Resource for IpcJsonStreamResource {
     fn close(self: Rc<Self>) {
       self.cancel.cancel();
@@ -357,12 +712,17 @@ mod impl_ {
       ref_tracker: IpcRefTracker,
     ) -> Result<Self, std::io::Error> {
       let (read_half, write_half) = BiPipe::from_raw(stream as _)?.split();
+      let received_handles = ReceivedHandles::default();
       Ok(Self {
-        read_half: AsyncRefCell::new(IpcJsonStream::new(read_half)),
+        read_half: AsyncRefCell::new(IpcJsonStream::new(
+          read_half,
+          received_handles.clone(),
+        )),
         write_half: AsyncRefCell::new(write_half),
         cancel: Default::default(),
         queued_bytes: Default::default(),
         ref_tracker,
+        received_handles,
       })
     }
 
@@ -372,12 +732,17 @@ mod impl_ {
       ref_tracker: IpcRefTracker,
     ) -> Self {
       let (read_half, write_half) = stream.into_split();
+      let received_handles = ReceivedHandles::default();
       Self {
-        read_half: AsyncRefCell::new(IpcJsonStream::new(read_half.into())),
+        read_half: AsyncRefCell::new(IpcJsonStream::new(
+          read_half.into(),
+          received_handles.clone(),
+        )),
         write_half: AsyncRefCell::new(write_half.into()),
         cancel: Default::default(),
         queued_bytes: Default::default(),
         ref_tracker,
+        received_handles,
       }
     }
 
@@ -387,23 +752,39 @@ mod impl_ {
       ref_tracker: IpcRefTracker,
     ) -> Self {
       let (read_half, write_half) = tokio::io::split(pipe);
+      let received_handles = ReceivedHandles::default();
       Self {
-        read_half: AsyncRefCell::new(IpcJsonStream::new(read_half.into())),
+        read_half: AsyncRefCell::new(IpcJsonStream::new(
+          read_half.into(),
+          received_handles.clone(),
+        )),
         write_half: AsyncRefCell::new(write_half.into()),
         cancel: Default::default(),
         queued_bytes: Default::default(),
         ref_tracker,
+        received_handles,
       }
     }
 
-    /// writes _newline terminated_ JSON message to the IPC pipe.
+    /// writes a serialized message to the IPC pipe, along with a socket if
+    /// there is one.
     async fn write_msg_bytes(
       self: Rc<Self>,
       msg: &[u8],
+      handle: Option<Handle>,
     ) -> Result<(), AnyError> {
       let mut write_half =
         RcRef::map(self, |r| &r.write_half).borrow_mut().await;
-      write_half.write_all(msg).await?;
+      match handle {
+        #[cfg(unix)]
+        Some(handle) => {
+          use std::os::fd::AsFd;
+          write_half.write_all_with_fd(msg, handle.as_fd()).await?
+        }
+        #[cfg(windows)]
+        Some(handle) => match handle {},
+        None => write_half.write_all(msg).await?,
+      }
       Ok(())
     }
   }
@@ -455,24 +836,101 @@ mod impl_ {
     }
   }
 
+  /// The read half of the IPC pipe, which keeps the sockets that are received
+  /// along with the data.
+  struct IpcPipeRead {
+    pipe: BiPipeRead,
+    #[cfg_attr(windows, allow(dead_code))]
+    received_handles: ReceivedHandles,
+  }
+
+  impl AsyncRead for IpcPipeRead {
+    fn poll_read(
+      self: Pin<&mut Self>,
+      cx: &mut Context<'_>,
+      buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+      let this = self.get_mut();
+      #[cfg(unix)]
+      {
+        let mut received_handles = this.received_handles.borrow_mut();
+        this
+          .pipe
+          .poll_read_with_fds(cx, buf, &mut *received_handles)
+      }
+      #[cfg(windows)]
+      Pin::new(&mut this.pipe).poll_read(cx, buf)
+    }
+  }
+
   // JSON serialization stream over IPC pipe.
   //
-  // `\n` is used as a delimiter between messages.
+  // `\n` is used as a delimiter between messages, unless they are read with
+  // `read_msg_bytes`.
   struct IpcJsonStream {
-    pipe: BiPipeRead,
+    pipe: IpcPipeRead,
     buffer: Vec<u8>,
     read_buffer: ReadBuffer,
   }
 
   impl IpcJsonStream {
-    fn new(pipe: BiPipeRead) -> Self {
+    fn new(pipe: BiPipeRead, received_handles: ReceivedHandles) -> Self {
       Self {
-        pipe,
+        pipe: IpcPipeRead {
+          pipe,
+          received_handles,
+        },
         buffer: Vec::with_capacity(INITIAL_CAPACITY),
         read_buffer: ReadBuffer::new(),
       }
     }
 
+    /// Reads a message that is prefixed with its length as a big endian
+    /// `u32`. Returns `None` on EOF.
+    async fn read_msg_bytes(&mut self) -> Result<Option<Vec<u8>>, AnyError> {
+      let mut len = [0; 4];
+      if !self.read_exact(&mut len).await? {
+        return Ok(None);
+      }
+      let mut msg = vec![0; u32::from_be_bytes(len) as usize];
+      if !self.read_exact(&mut msg).await? {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+      }
+      Ok(Some(msg))
+    }
+
+    /// Fills `out`, first from the read buffer. Returns `false` if the pipe
+    /// was at EOF before anything was read.
+    async fn read_exact(&mut self, out: &mut [u8]) -> io::Result<bool> {
+      let mut filled = 0;
+      while filled < out.len() {
+        if self.read_buffer.needs_fill() {
+          let pipe = &mut self.pipe;
+          let read_buffer = &mut self.read_buffer;
+          let nread = poll_fn(|cx| {
+            let mut read_buf = ReadBuf::new(read_buffer.get_mut());
+            ready!(Pin::new(&mut *pipe).poll_read(cx, &mut read_buf))?;
+            Poll::Ready(Ok::<_, io::Error>(read_buf.filled().len()))
+          })
+          .await?;
+          self.read_buffer.cap = nread;
+          self.read_buffer.pos = 0;
+          if nread == 0 {
+            if filled == 0 {
+              return Ok(false);
+            }
+            return Err(io::ErrorKind::UnexpectedEof.into());
+          }
+        }
+        let available = self.read_buffer.available_mut();
+        let n = std::cmp::min(available.len(), out.len() - filled);
+        out[filled..filled + n].copy_from_slice(&available[..n]);
+        self.read_buffer.consume(n);
+        filled += n;
+      }
+      Ok(true)
+    }
+
     async fn read_msg(
       &mut self,
     ) -> Result<Option<serde_json::Value>, AnyError> {
@@ -709,7 +1167,7 @@ mod impl_ {
 
       ipc
         .clone()
-        .write_msg_bytes(&json_to_bytes(json!("hello")))
+        .write_msg_bytes(&json_to_bytes(json!("hello")), None)
         .await?;
 
       let mut ipc = RcRef::map(ipc, |r| &r.read_half).borrow_mut().await;
@@ -744,11 +1202,11 @@ mod impl_ {
 
       ipc
         .clone()
-        .write_msg_bytes(&json_to_bytes(json!("hello")))
+        .write_msg_bytes(&json_to_bytes(json!("hello")), None)
         .await?;
       ipc
         .clone()
-        .write_msg_bytes(&json_to_bytes(json!("world")))
+        .write_msg_bytes(&json_to_bytes(json!("world")), None)
         .await?;
 
       let mut ipc = RcRef::map(ipc, |r| &r.read_half).borrow_mut().await;
@@ -776,6 +1234,57 @@ mod impl_ {
       Ok(())
     }
 
+    #[tokio::test]
+    async fn unix_ipc_advanced() -> Result<(), Box<dyn std::error::Error>> {
+      let (ipc, mut fd2) = pair().await;
+      let child = tokio::spawn(async move {
+        use tokio::io::AsyncWriteExt;
+
+        fd2
+          .write_all(b"\0\0\0\x05hello\0\0\0\0\0\0\0\x05wor")
+          .await?;
+        fd2.write_all(b"ld").await?;
+        Ok::<_, std::io::Error>(())
+      });
+
+      let mut ipc = RcRef::map(ipc, |r| &r.read_half).borrow_mut().await;
+      assert_eq!(ipc.read_msg_bytes().await?.unwrap(), b"hello");
+      assert_eq!(ipc.read_msg_bytes().await?.unwrap(), b"");
+      assert_eq!(ipc.read_msg_bytes().await?.unwrap(), b"world");
+      child.await??;
+      assert_eq!(ipc.read_msg_bytes().await?, None);
+
+      Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_ipc_handle() -> Result<(), Box<dyn std::error::Error>> {
+      use std::os::fd::AsFd;
+
+      let (ipc, fd2) = pair().await;
+      let other = Rc::new(IpcJsonStreamResource::from_stream(
+        fd2,
+        super::IpcRefTracker::new_test(),
+      ));
+      let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+      let handle = listener.as_fd().try_clone_to_owned()?;
+
+      ipc
+        .clone()
+        .write_msg_bytes(&json_to_bytes(json!("hello")), Some(handle))
+        .await?;
+
+      let mut read_half =
+        RcRef::map(&other, |r| &r.read_half).borrow_mut().await;
+      assert_eq!(read_half.read_msg().await?.unwrap(), json!("hello"));
+      let handle = other.received_handles.borrow_mut().pop_front().unwrap();
+      let received = std::net::TcpListener::from(handle);
+      assert_eq!(received.local_addr()?, listener.local_addr()?);
+
+      Ok(())
+    }
+
     #[test]
     fn memchr() {
       let str = b"hello world";
@@ -6,7 +6,7 @@ use deno_core::error::AnyError;
 use deno_core::op2;
 use deno_core::OpState;
 
-mod cpus;
+pub mod cpus;
 mod priority;
 
 #[op2(fast)]
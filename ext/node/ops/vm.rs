@@ -6,10 +6,14 @@ use deno_core::serde_v8;
 use deno_core::v8;
 use deno_core::v8::MapFnTo;
 use deno_core::JsBuffer;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU8;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
 
+const WATCHDOG_RUNNING: u8 = 0;
+const WATCHDOG_DONE: u8 = 1;
+const WATCHDOG_TIMED_OUT: u8 = 2;
+
 pub const PRIVATE_SYMBOL_NAME: v8::OneByteConst =
   v8::String::create_external_onebyte_const(b"node:contextify:context");
 
@@ -191,31 +195,54 @@ impl ContextifyScript {
       r
     };
 
-    #[allow(clippy::disallowed_types)]
-    let timed_out = std::sync::Arc::new(AtomicBool::new(false));
-    let result = if timeout != -1 {
-      let timed_out = timed_out.clone();
+    let (result, timed_out) = if timeout != -1 {
+      #[allow(clippy::disallowed_types)]
+      let state = std::sync::Arc::new(AtomicU8::new(WATCHDOG_RUNNING));
       let (tx, rx) = std::sync::mpsc::channel();
-      deno_core::unsync::spawn_blocking(move || {
-        if rx
-          .recv_timeout(Duration::from_millis(timeout as _))
-          .is_err()
-        {
-          timed_out.store(true, Ordering::Relaxed);
-          handle.terminate_execution();
-        }
-      });
-      let r = run();
+      let (terminated_tx, terminated_rx) = std::sync::mpsc::channel();
+      {
+        let state = state.clone();
+        deno_core::unsync::spawn_blocking(move || {
+          if rx
+            .recv_timeout(Duration::from_millis(timeout as _))
+            .is_err()
+            && state
+              .compare_exchange(
+                WATCHDOG_RUNNING,
+                WATCHDOG_TIMED_OUT,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+              )
+              .is_ok()
+          {
+            handle.terminate_execution();
+            let _ = terminated_tx.send(());
+          }
+        });
+      }
+      let result = run();
       let _ = tx.send(());
-      r
+      // The watchdog may have fired right as the script finished, in which
+      // case wait for it to request the termination so that it can be
+      // cancelled below, instead of hitting whatever code runs next.
+      let timed_out = state
+        .compare_exchange(
+          WATCHDOG_RUNNING,
+          WATCHDOG_DONE,
+          Ordering::AcqRel,
+          Ordering::Acquire,
+        )
+        .is_err();
+      if timed_out {
+        let _ = terminated_rx.recv();
+      }
+      (result, timed_out)
     } else {
-      run()
+      (run(), false)
     };
 
-    if timed_out.load(Ordering::Relaxed) {
-      if scope.has_terminated() {
-        scope.cancel_terminate_execution();
-      }
+    if timed_out {
+      scope.cancel_terminate_execution();
       let message = v8::String::new(
         scope,
         &format!("Script execution timed out after {timeout}ms"),
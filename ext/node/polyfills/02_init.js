@@ -43,6 +43,7 @@ function initialize(args) {
       maybeWorkerMetadata,
     );
     internals.__setupChildProcessIpcChannel();
+    internals.__initCluster();
     // `Deno[Deno.internal].requireImpl` will be unreachable after this line.
     delete internals.requireImpl;
   } else {
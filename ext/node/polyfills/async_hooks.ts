@@ -108,12 +108,9 @@ export class AsyncLocalStorage {
     if (!this.enabled) {
       return ReflectApply(callback, null, args);
     }
-    this.enabled = false;
-    try {
-      return ReflectApply(callback, null, args);
-    } finally {
-      this.enabled = true;
-    }
+    // Only this store is cleared for the callback and anything it schedules,
+    // other stores and the other callbacks in flight keep their values.
+    return this.run(undefined, callback, ...args);
   }
 
   // deno-lint-ignore no-explicit-any
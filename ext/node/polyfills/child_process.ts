@@ -833,9 +833,14 @@ export function execFileSync(
 }
 
 function setupChildProcessIpcChannel() {
-  const fd = op_node_child_ipc_pipe();
-  if (typeof fd != "number" || fd < 0) return;
-  const control = setupChannel(process, fd);
+  const pipe = op_node_child_ipc_pipe();
+  if (pipe === null) return;
+  const { 0: fd, 1: serialization } = pipe;
+  const control = setupChannel(
+    process,
+    fd,
+    serialization === "advanced" ? "advanced" : "json",
+  );
   process.on("newListener", (name: string) => {
     if (name === "message" || name === "disconnect") {
       control.refCounted();
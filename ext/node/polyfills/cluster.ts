@@ -1,92 +1,86 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 // Copyright Joyent and Node contributors. All rights reserved. MIT license.
 
-import { notImplemented } from "ext:deno_node/_utils.ts";
+import { internals } from "ext:core/mod.js";
+import { op_node_cluster_env } from "ext:core/ops";
 import { EventEmitter } from "node:events";
+import process from "node:process";
+import {
+  initializePrimary,
+  SCHED_NONE,
+  SCHED_RR,
+} from "ext:deno_node/internal/cluster/primary.ts";
+import { initializeWorker } from "ext:deno_node/internal/cluster/child.ts";
+import { Worker } from "ext:deno_node/internal/cluster/worker.ts";
+
+export { SCHED_NONE, SCHED_RR, Worker };
 
-/** A Worker object contains all public information and method about a worker.
- * In the primary it can be obtained using cluster.workers. In a worker it can
- * be obtained using cluster.worker.
- */
-export class Worker {
-  constructor() {
-    notImplemented("cluster.Worker.prototype.constructor");
-  }
-}
 /** Calls .disconnect() on each worker in cluster.workers. */
-export function disconnect() {
-  notImplemented("cluster.disconnect");
-}
+export let disconnect: (cb?: () => void) => void;
 /** Spawn a new worker process. */
 // deno-lint-ignore no-explicit-any
-export function fork(_env?: any): Worker {
-  notImplemented("cluster.fork");
-}
+export let fork: (env?: any) => typeof Worker;
 /** True if the process is a primary. This is determined by
  * the process.env.NODE_UNIQUE_ID. If process.env.NODE_UNIQUE_ID is undefined,
  * then isPrimary is true. */
-// TODO(@marvinhagemeister): Replace this with an env check once
-// we properly set NODE_UNIQUE_ID
-export const isPrimary = true;
+export let isPrimary = true;
 /** True if the process is not a primary (it is the negation of
  * cluster.isPrimary). */
-export const isWorker = false;
+export let isWorker = false;
 /** Deprecated alias for cluster.isPrimary. details. */
-export const isMaster = isPrimary;
+export let isMaster = isPrimary;
 /** The scheduling policy, either cluster.SCHED_RR for round-robin or
  * cluster.SCHED_NONE to leave it to the operating system. This is a global
  * setting and effectively frozen once either the first worker is spawned, or
  * .setupPrimary() is called, whichever comes first. */
-export const schedulingPolicy = undefined;
+export let schedulingPolicy: number | undefined = undefined;
 /** The settings object */
-export const settings = {};
+export let settings: Record<string, unknown> | undefined = {};
 /** setupPrimary is used to change the default 'fork' behavior. Once called,
  * the settings will be present in cluster.settings. */
-export function setupPrimary() {
-  notImplemented("cluster.setupPrimary");
-}
+// deno-lint-ignore no-explicit-any
+export let setupPrimary: (options?: any) => void;
 /** Deprecated alias for .setupPrimary(). */
-export const setupMaster = setupPrimary;
+// deno-lint-ignore no-explicit-any
+export let setupMaster: (options?: any) => void;
 /** A reference to the current worker object. Not available in the primary
  * process. */
-export const worker = undefined;
+export let worker: typeof Worker | undefined = undefined;
 /** A hash that stores the active worker objects, keyed by id field. Makes it
  * easy to loop through all the workers. It is only available in the primary
  * process. */
-export const workers = {};
+export let workers: Record<string, typeof Worker> | undefined = {};
 
-export const SCHED_NONE = 1;
-export const SCHED_RR = 2;
-
-const cluster = new EventEmitter() as EventEmitter & {
-  isWorker: boolean;
-  isMaster: boolean;
-  isPrimary: boolean;
-  Worker: Worker;
-  workers: Record<string, Worker>;
-  settings: Record<string, unknown>;
-  // deno-lint-ignore no-explicit-any
-  setupPrimary(options?: any): void;
-  // deno-lint-ignore no-explicit-any
-  setupMaster(options?: any): void;
-  // deno-lint-ignore no-explicit-any
-  fork(env: any): Worker;
-  // deno-lint-ignore no-explicit-any
-  disconnect(cb: any): void;
-  SCHED_NONE: 1;
-  SCHED_RR: 2;
-};
-cluster.isWorker = isWorker;
-cluster.isMaster = isMaster;
-cluster.isPrimary = isPrimary;
+// deno-lint-ignore no-explicit-any
+const cluster = new EventEmitter() as any;
 cluster.Worker = Worker;
-cluster.workers = workers;
-cluster.settings = {};
-cluster.setupPrimary = setupPrimary;
-cluster.setupMaster = setupMaster;
-cluster.fork = fork;
-cluster.disconnect = disconnect;
 cluster.SCHED_NONE = SCHED_NONE;
 cluster.SCHED_RR = SCHED_RR;
 
+function syncExports() {
+  disconnect = cluster.disconnect;
+  fork = cluster.fork;
+  isPrimary = cluster.isPrimary;
+  isWorker = cluster.isWorker;
+  isMaster = cluster.isMaster;
+  schedulingPolicy = cluster.schedulingPolicy;
+  settings = cluster.settings;
+  setupPrimary = cluster.setupPrimary;
+  setupMaster = cluster.setupMaster;
+  worker = cluster.worker;
+  workers = cluster.workers;
+}
+
+// The process is a worker if it was forked by `cluster.fork()`, which is only
+// known at runtime, after the IPC channel to the primary has been set up.
+internals.__initCluster = () => {
+  const { 0: id, 1: policy } = op_node_cluster_env();
+  if (id !== null && process.channel) {
+    initializeWorker(cluster, id);
+  } else {
+    initializePrimary(cluster, policy);
+  }
+  syncExports();
+};
+
 export default cluster;
@@ -24,6 +24,7 @@
 // deno-lint-ignore-file prefer-primordials
 
 import { Buffer } from "node:buffer";
+import cluster from "node:cluster";
 import { EventEmitter } from "node:events";
 import { lookup as defaultLookup } from "node:dns";
 import type {
@@ -444,12 +445,15 @@ export class Socket extends EventEmitter {
     }
 
     let address: string;
+    let exclusive: boolean;
 
     if (isBindOptions(port)) {
       address = port.address || "";
+      exclusive = !!port.exclusive;
       port = port.port;
     } else {
       address = typeof address_ === "function" ? "" : (address_ as string);
+      exclusive = false;
     }
 
     // Defaulting address for bind to all interfaces
@@ -479,13 +483,23 @@ export class Socket extends EventEmitter {
         flags |= UV_UDP_IPV6ONLY!;
       }
 
-      // TODO(cmorten): here we deviate somewhat from the Node implementation which
-      // makes use of the https://nodejs.org/api/cluster.html module to run servers
-      // across a "cluster" of Node processes to take advantage of multi-core
-      // systems.
-      //
-      // Though Deno has has a Worker capability from which we could simulate this,
-      // for now we assert that we are _always_ on the primary process.
+      // deno-lint-ignore no-explicit-any
+      if ((cluster as any).isWorker && !exclusive) {
+        bindServerHandle(this, {
+          address: ip,
+          port: port,
+          addressType: this.type,
+          fd: -1,
+          flags: flags,
+        }, (err) => {
+          // Callback to handle error.
+          const ex = exceptionWithHostPort(err, "bind", ip, port as number);
+          state.bindState = BIND_STATE_UNBOUND;
+          this.emit("error", ex);
+        });
+
+        return;
+      }
 
       if (!state.handle) {
         return; // Handle has been closed in the mean time
@@ -1254,6 +1268,34 @@ function startListening(socket: Socket) {
   socket.emit("listening");
 }
 
+// The workers of a cluster share the socket of the primary.
+function bindServerHandle(
+  self: Socket,
+  options: Record<string, unknown>,
+  errCb: (err: number) => void,
+) {
+  const state = self[kStateSymbol];
+
+  // deno-lint-ignore no-explicit-any
+  (cluster as any)._getServer(self, options, (err: number, handle: UDP) => {
+    if (err) {
+      errCb(err);
+
+      return;
+    }
+
+    if (!state.handle) {
+      // Handle has been closed in the mean time.
+      handle.close();
+
+      return;
+    }
+
+    replaceHandle(self, handle);
+    startListening(self);
+  });
+}
+
 function replaceHandle(self: Socket, newHandle: UDP) {
   const state = self[kStateSymbol];
   const oldHandle = state.handle!;
@@ -15,6 +15,7 @@ import { setTimeout } from "ext:deno_web/02_timers.js";
 import {
   _normalizeArgs,
   // createConnection,
+  isIPv6,
   ListenOptions,
   Socket,
 } from "node:net";
@@ -58,6 +59,7 @@ import {
   ERR_INVALID_HTTP_TOKEN,
   ERR_INVALID_PROTOCOL,
   ERR_UNESCAPED_CHARACTERS,
+  exceptionWithHostPort,
 } from "ext:deno_node/internal/errors.ts";
 import { getTimerDuration } from "ext:deno_node/internal/timers.mjs";
 import { serve, upgradeHttpRaw } from "ext:deno_http/00_serve.ts";
@@ -67,8 +69,10 @@ import { timerId } from "ext:deno_web/03_abort_signal.js";
 import { clearTimeout as webClearTimeout } from "ext:deno_web/02_timers.js";
 import { resourceForReadableStream } from "ext:deno_web/06_streams.js";
 import { TcpConn } from "ext:deno_net/01_net.js";
+import cluster from "node:cluster";
+import { ownerSymbol } from "ext:deno_node/internal_binding/symbols.ts";
 
-const { internalRidSymbol } = core;
+const { internalRidSymbol, getAsyncContext, setAsyncContext } = core;
 const { ArrayIsArray } = primordials;
 
 enum STATUS_CODES {
@@ -326,6 +330,9 @@ class ClientRequest extends OutgoingMessage {
   useChunkedEncodingByDefault: boolean;
   path: string;
   _req: { requestRid: number; cancelHandleRid: number | null } | undefined;
+  // The response is handled in the async context of `http.request()`, not in
+  // the one of whatever ended up sending the headers.
+  #asyncContext = getAsyncContext();
 
   constructor(
     input: string | URL,
@@ -629,6 +636,8 @@ class ClientRequest extends OutgoingMessage {
       this._bodyWriteRid,
     );
 
+    const oldContext = getAsyncContext();
+    setAsyncContext(this.#asyncContext);
     (async () => {
       try {
         const res = await op_node_http_fetch_send(this._req.requestRid);
@@ -742,6 +751,7 @@ class ClientRequest extends OutgoingMessage {
         }
       }
     })();
+    setAsyncContext(oldContext);
   }
 
   _implicitHeader() {
@@ -1714,6 +1724,8 @@ export class ServerImpl extends EventEmitter {
   #unref = false;
   #ac?: AbortController;
   #serveDeferred: ReturnType<typeof Promise.withResolvers<void>>;
+  #exclusive = false;
+  #clusterHandle: TCP | null = null;
   listening = false;
 
   constructor(opts, requestListener?: ServerHandler) {
@@ -1764,6 +1776,7 @@ export class ServerImpl extends EventEmitter {
       hostname,
       port,
     } as Deno.NetAddr;
+    this.#exclusive = !!options.exclusive;
     this.listening = true;
     nextTick(() => this._serve());
 
@@ -1810,12 +1823,58 @@ export class ServerImpl extends EventEmitter {
       return;
     }
     this.#ac = ac;
+    // TODO(bartlomieju): share the listener with the workers for HTTPS too.
+    // deno-lint-ignore no-explicit-any
+    if ((cluster as any).isWorker && !this.#exclusive && !this._encrypted) {
+      this.#serveInCluster(handler as Deno.ServeHandler, ac.signal);
+      return;
+    }
+    this.#startServing({
+      handler: handler as Deno.ServeHandler,
+      ...this.#addr,
+      signal: ac.signal,
+    });
+  }
+
+  // Workers of a cluster serve on the listener of the primary, which is
+  // shared with them regardless of the scheduling policy, as `Deno.serve`
+  // accepts the connections itself.
+  #serveInCluster(handler: Deno.ServeHandler, signal: AbortSignal) {
+    const { hostname, port } = this.#addr!;
+    // deno-lint-ignore no-explicit-any
+    (cluster as any)._getServer(this, {
+      address: hostname,
+      port,
+      addressType: isIPv6(hostname) ? 6 : 4,
+      flags: 0,
+      shared: true,
+    }, (err: number, handle: TCP) => {
+      if (err) {
+        this.emit("error", exceptionWithHostPort(err, "bind", hostname, port));
+        return;
+      }
+      if (this.#hasClosed) {
+        handle.close();
+        return;
+      }
+      // Lets the worker close the server when it's disconnected.
+      // deno-lint-ignore no-explicit-any
+      (handle as any)[ownerSymbol] = this;
+      this.#clusterHandle = handle;
+      this.#startServing({
+        handler,
+        listener: handle.getListener() as Deno.Listener,
+        signal,
+      });
+    });
+  }
+
+  // deno-lint-ignore no-explicit-any
+  #startServing(options: any) {
     try {
       this.#server = serve(
         {
-          handler: handler as Deno.ServeHandler,
-          ...this.#addr,
-          signal: ac.signal,
+          ...options,
           // @ts-ignore Might be any without `--unstable` flag
           onListen: ({ port }) => {
             this.#addr!.port = port;
@@ -1880,6 +1939,13 @@ export class ServerImpl extends EventEmitter {
       this.#serveDeferred!.resolve();
     }
 
+    // Lets the primary of a cluster know that the listener isn't used here
+    // anymore.
+    if (this.#clusterHandle) {
+      this.#clusterHandle.close();
+      this.#clusterHandle = null;
+    }
+
     this.#server = undefined;
     return this;
   }
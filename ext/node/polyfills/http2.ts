@@ -28,6 +28,8 @@ import { emitWarning } from "node:process";
 import Stream from "node:stream";
 import { connect as netConnect, Server, Socket, TCP } from "node:net";
 import { connect as tlsConnect } from "node:tls";
+import { ServerImpl as TLSServer } from "ext:deno_node/_tls_wrap.ts";
+import { isIPv6 } from "ext:deno_node/internal/net.ts";
 import { TypedArray } from "ext:deno_node/internal/util/types.ts";
 import {
   kHandle,
@@ -35,7 +37,8 @@ import {
   kUpdateTimer,
   setStreamTimeout,
 } from "ext:deno_node/internal/stream_base_commons.ts";
-import { FileHandle } from "node:fs/promises";
+import { FileHandle } from "ext:deno_node/internal/fs/handle.ts";
+import { createReadStream, fstat, stat, Stats } from "node:fs";
 import { kStreamBaseField } from "ext:deno_node/internal_binding/stream_wrap.ts";
 import { serveHttpOnConnection } from "ext:deno_http/00_serve.ts";
 import { nextTick } from "ext:deno_node/_next_tick.ts";
@@ -47,14 +50,21 @@ import {
   ERR_HTTP2_CONNECT_PATH,
   ERR_HTTP2_CONNECT_SCHEME,
   ERR_HTTP2_GOAWAY_SESSION,
+  ERR_HTTP2_HEADERS_AFTER_RESPOND,
   ERR_HTTP2_HEADERS_SENT,
   ERR_HTTP2_INFO_STATUS_NOT_ALLOWED,
+  ERR_HTTP2_INVALID_CONNECTION_HEADERS,
+  ERR_HTTP2_INVALID_INFO_STATUS,
   ERR_HTTP2_INVALID_PSEUDOHEADER,
   ERR_HTTP2_INVALID_SESSION,
   ERR_HTTP2_INVALID_STREAM,
   ERR_HTTP2_NO_SOCKET_MANIPULATION,
+  ERR_HTTP2_PUSH_DISABLED,
+  ERR_HTTP2_SEND_FILE,
+  ERR_HTTP2_SEND_FILE_NOSEEK,
   ERR_HTTP2_SESSION_ERROR,
   ERR_HTTP2_SOCKET_UNBOUND,
+  ERR_HTTP2_STATUS_101,
   ERR_HTTP2_STATUS_INVALID,
   ERR_HTTP2_STREAM_CANCEL,
   ERR_HTTP2_STREAM_ERROR,
@@ -77,6 +87,8 @@ const {
   StringPrototypeToLowerCase,
   ReflectApply,
   ArrayIsArray,
+  MathMax,
+  MathMin,
   ObjectPrototypeHasOwnProperty,
 } = primordials;
 
@@ -104,6 +116,9 @@ const kDenoRid = Symbol("kDenoRid");
 const kDenoClientRid = Symbol("kDenoClientRid");
 const kDenoConnRid = Symbol("kDenoConnRid");
 const kPollConnPromise = Symbol("kPollConnPromise");
+const kDenoServer = Symbol("kDenoServer");
+const kDenoAbortController = Symbol("kDenoAbortController");
+const kResetStream = Symbol("kResetStream");
 
 const STREAM_FLAGS_PENDING = 0x0;
 const STREAM_FLAGS_READY = 0x1;
@@ -426,14 +441,18 @@ function closeSession(session: Http2Session, code?: number, error?: Error) {
   if (session[kDenoClientRid]) {
     core.tryClose(session[kDenoClientRid]);
   }
+  session[kDenoAbortController]?.abort();
 
   finishSessionClose(session, error);
 }
 
 export class ServerHttp2Session extends Http2Session {
-  constructor() {
-    // TODO(satyarohith): pass socket instead of undefined
-    super(constants.NGHTTP2_SESSION_SERVER, {}, undefined);
+  constructor(conn: Deno.Conn, encrypted: boolean) {
+    super(constants.NGHTTP2_SESSION_SERVER, {}, connectionInfo(conn));
+    this[kEncrypted] = encrypted;
+    this[kAlpnProtocol] = encrypted ? undefined : "h2c";
+    // Streams opened by the client have odd identifiers.
+    this[kState].nextStreamId = 1;
   }
 
   altsvc(
@@ -446,6 +465,30 @@ export class ServerHttp2Session extends Http2Session {
   origin(..._origins: (string | URL | { origin: string })[]) {
     notImplemented("ServerHttp2Session.origins");
   }
+
+  goaway(
+    code?: number,
+    lastStreamID?: number,
+    opaqueData?: Buffer | TypedArray | DataView,
+  ) {
+    debugHttp2(">>> goaway - ignored args", code, lastStreamID, opaqueData);
+    // Lets the open streams finish, the connection is closed afterwards.
+    this[kDenoServer]?.shutdown();
+  }
+}
+
+// The connection of a server session is owned by deno_http, so its socket only
+// describes the addresses of the connection.
+function connectionInfo(conn: Deno.Conn) {
+  const remoteAddr = conn.remoteAddr as Deno.NetAddr;
+  const localAddr = conn.localAddr as Deno.NetAddr;
+  return {
+    remoteAddress: remoteAddr.hostname,
+    remotePort: remoteAddr.port,
+    remoteFamily: isIPv6(remoteAddr.hostname) ? "IPv6" : "IPv4",
+    localAddress: localAddr.hostname,
+    localPort: localAddr.port,
+  };
 }
 
 function assertValidPseudoHeader(header: string) {
@@ -661,154 +704,6 @@ function getAuthority(headers) {
   return undefined;
 }
 
-export class Http2Stream extends EventEmitter {
-  #session: Http2Session;
-  #headers: Promise<Http2Headers>;
-  #controllerPromise: Promise<ReadableStreamDefaultController<Uint8Array>>;
-  #readerPromise: Promise<ReadableStream<Uint8Array>>;
-  #closed: boolean;
-  _response: Response;
-  // This is required to set the trailers on the response.
-  _request: Request;
-
-  constructor(
-    session: Http2Session,
-    headers: Promise<Http2Headers>,
-    controllerPromise: Promise<ReadableStreamDefaultController<Uint8Array>>,
-    readerPromise: Promise<ReadableStream<Uint8Array>>,
-  ) {
-    super();
-    this.#session = session;
-    this.#headers = headers;
-    this.#controllerPromise = controllerPromise;
-    this.#readerPromise = readerPromise;
-    this.#closed = false;
-    nextTick(() => {
-      (async () => {
-        const headers = await this.#headers;
-        this.emit("headers", headers);
-      })();
-      (async () => {
-        const reader = await this.#readerPromise;
-        if (reader) {
-          for await (const data of reader) {
-            this.emit("data", new Buffer(data));
-          }
-        }
-        this.emit("end");
-      })();
-    });
-  }
-
-  // TODO(mmastrac): Implement duplex
-  end() {
-    (async () => {
-      const controller = await this.#controllerPromise;
-      controller.close();
-    })();
-  }
-
-  write(buffer, callback?: () => void) {
-    (async () => {
-      const controller = await this.#controllerPromise;
-      if (typeof buffer === "string") {
-        controller.enqueue(ENCODER.encode(buffer));
-      } else {
-        controller.enqueue(buffer);
-      }
-      callback?.();
-    })();
-  }
-
-  setEncoding(_encoding) {}
-
-  resume() {
-  }
-
-  pause() {
-  }
-
-  get aborted(): boolean {
-    notImplemented("Http2Stream.aborted");
-    return false;
-  }
-
-  get bufferSize(): number {
-    notImplemented("Http2Stream.bufferSize");
-    return 0;
-  }
-
-  close(_code: number, _callback: () => void) {
-    this.#closed = true;
-    this.emit("close");
-  }
-
-  get closed(): boolean {
-    return this.#closed;
-  }
-
-  get destroyed(): boolean {
-    return false;
-  }
-
-  get endAfterHeaders(): boolean {
-    notImplemented("Http2Stream.endAfterHeaders");
-    return false;
-  }
-
-  get id(): number | undefined {
-    notImplemented("Http2Stream.id");
-    return undefined;
-  }
-
-  get pending(): boolean {
-    notImplemented("Http2Stream.pending");
-    return false;
-  }
-
-  priority(_options: Record<string, unknown>) {
-    notImplemented("Http2Stream.priority");
-  }
-
-  get rstCode(): number {
-    // notImplemented("Http2Stream.rstCode");
-    return 0;
-  }
-
-  get sentHeaders(): boolean {
-    notImplemented("Http2Stream.sentHeaders");
-    return false;
-  }
-
-  get sentInfoHeaders(): Record<string, unknown> {
-    notImplemented("Http2Stream.sentInfoHeaders");
-    return {};
-  }
-
-  get sentTrailers(): Record<string, unknown> {
-    notImplemented("Http2Stream.sentTrailers");
-    return {};
-  }
-
-  get session(): Http2Session {
-    return this.#session;
-  }
-
-  setTimeout(msecs: number, callback?: () => void) {
-    setStreamTimeout(this, msecs, callback);
-  }
-
-  get state(): Record<string, unknown> {
-    notImplemented("Http2Stream.state");
-    return {};
-  }
-
-  sendTrailers(headers: Record<string, unknown>) {
-    const request = toInnerRequest(this._request);
-    op_http_set_response_trailers(request.external, Object.entries(headers));
-  }
-}
-
 async function clientHttp2Request(
   session,
   sessionConnectPromise,
@@ -1408,99 +1303,603 @@ function finishCloseStream(stream, code) {
   }
 }
 
-function callTimeout() {
-  notImplemented("callTimeout");
+function callTimeout(self, _session) {
+  if (self.destroyed) {
+    return;
+  }
+  self.emit("timeout");
+}
+
+// Headers that are specific to HTTP/1 connections and can't be sent with an
+// HTTP/2 response.
+function isConnectionHeader(name: string, value: unknown) {
+  switch (name) {
+    case constants.HTTP2_HEADER_CONNECTION:
+    case constants.HTTP2_HEADER_UPGRADE:
+    case constants.HTTP2_HEADER_HTTP2_SETTINGS:
+    case constants.HTTP2_HEADER_KEEP_ALIVE:
+    case constants.HTTP2_HEADER_PROXY_CONNECTION:
+    case constants.HTTP2_HEADER_TRANSFER_ENCODING:
+      return true;
+    case constants.HTTP2_HEADER_TE:
+      return value !== "trailers";
+    default:
+      return false;
+  }
 }
 
-export class ServerHttp2Stream extends Http2Stream {
+// Converts the regular headers of `headers` to the list of (name, value)
+// pairs deno_http expects, repeating the name of multi-value headers.
+function toHeaderList(headers: Http2Headers): [string, string][] {
+  const headerList: [string, string][] = [];
+  for (const key of ObjectKeys(headers)) {
+    const value = headers[key];
+    const name = StringPrototypeToLowerCase(key);
+    if (name[0] === ":" || value === undefined) {
+      continue;
+    }
+    if (isConnectionHeader(name, value)) {
+      throw new ERR_HTTP2_INVALID_CONNECTION_HEADERS(name);
+    }
+    if (ArrayIsArray(value)) {
+      for (let i = 0; i < value.length; i++) {
+        headerList.push([name, String(value[i])]);
+      }
+    } else {
+      headerList.push([name, String(value)]);
+    }
+  }
+  return headerList;
+}
+
+function isNullBodyStatus(status: number) {
+  return status === constants.HTTP_STATUS_NO_CONTENT ||
+    status === constants.HTTP_STATUS_RESET_CONTENT ||
+    status === constants.HTTP_STATUS_NOT_MODIFIED;
+}
+
+// A stream of a `ServerHttp2Session`, which is backed by a request of
+// deno_http: the readable side is the request body and the writable side the
+// body of the response that is returned to `Deno.serve`.
+export class ServerHttp2Stream extends Duplex {
   _deferred: ReturnType<typeof Promise.withResolvers<Response>>;
+  #request: Request;
+  #reader: ReadableStreamDefaultReader<Uint8Array> | null;
   #body: ReadableStream<Uint8Array>;
-  #waitForTrailers: boolean;
-  #headersSent: boolean;
+  #controller!: ReadableStreamDefaultController<Uint8Array>;
+  #pendingWrite: (() => void) | null = null;
+  #pendingFinal: (() => void) | null = null;
 
   constructor(
-    session: Http2Session,
-    headers: Promise<Http2Headers>,
-    controllerPromise: Promise<ReadableStreamDefaultController<Uint8Array>>,
-    reader: ReadableStream<Uint8Array>,
-    body: ReadableStream<Uint8Array>,
-    // This is required to set the trailers on the response.
-    req: Request,
+    session: ServerHttp2Session,
+    id: number,
+    headers: Http2Headers,
+    request: Request,
   ) {
-    super(session, headers, controllerPromise, Promise.resolve(reader));
+    super({ allowHalfOpen: true, decodeStrings: false, autoDestroy: false });
+    this[kSession] = session;
+    this[kID] = id;
+    this[kState] = {
+      flags: STREAM_FLAGS_READY,
+      rstCode: constants.NGHTTP2_NO_ERROR,
+      trailersReady: false,
+      endAfterHeaders: request.body === null,
+    };
+    if (headers[constants.HTTP2_HEADER_METHOD] === "HEAD") {
+      this[kState].flags |= STREAM_FLAGS_HEAD_REQUEST;
+    }
+    this[kSentHeaders] = undefined;
+    this[kSentTrailers] = undefined;
+    this[kInfoHeaders] = undefined;
+    session[kState].streams.set(id, this);
+
     this._deferred = Promise.withResolvers<Response>();
-    this.#body = body;
-    this._request = req;
+    this.#request = request;
+    this.#reader = request.body?.getReader() ?? null;
+    this.#body = new ReadableStream({
+      start: (controller) => {
+        this.#controller = controller;
+      },
+      pull: () => this.#continueWrite(),
+    });
   }
 
-  additionalHeaders(_headers: Record<string, unknown>) {
-    notImplemented("ServerHttp2Stream.additionalHeaders");
+  #continueWrite() {
+    const callback = this.#pendingWrite;
+    this.#pendingWrite = null;
+    callback?.();
   }
 
-  end(): void {
-    super.end();
-    if (this.#waitForTrailers) {
-      this.emit("wantTrailers");
+  #closeBody() {
+    try {
+      this.#controller.close();
+    } catch {
+      // The body was already closed or reset.
     }
   }
 
+  // Makes deno_http reset the stream, unless the response was sent already.
+  #resetBody(code: number) {
+    try {
+      this.#controller.error(new ERR_HTTP2_STREAM_ERROR(code));
+    } catch {
+      // The body was already closed or reset.
+    }
+    this.#continueWrite();
+    if (!this.headersSent) {
+      this[kState].flags |= STREAM_FLAGS_HEADERS_SENT;
+      this._deferred.resolve(
+        new Response(this.#body, { status: constants.HTTP_STATUS_OK }),
+      );
+    }
+  }
+
+  get id(): number {
+    return this[kID];
+  }
+
+  get pending(): boolean {
+    return false;
+  }
+
+  get session(): ServerHttp2Session | undefined {
+    return this[kSession];
+  }
+
+  get aborted(): boolean {
+    return !!(this[kState].flags & STREAM_FLAGS_ABORTED);
+  }
+
+  get closed(): boolean {
+    return !!(this[kState].flags & STREAM_FLAGS_CLOSED);
+  }
+
   get headersSent(): boolean {
-    return this.#headersSent;
+    return !!(this[kState].flags & STREAM_FLAGS_HEADERS_SENT);
+  }
+
+  get headRequest(): boolean {
+    return !!(this[kState].flags & STREAM_FLAGS_HEAD_REQUEST);
+  }
+
+  get endAfterHeaders(): boolean {
+    return this[kState].endAfterHeaders;
   }
 
+  get rstCode(): number {
+    return this[kState].rstCode;
+  }
+
+  get bufferSize(): number {
+    return this.writableLength;
+  }
+
+  get sentHeaders(): Http2Headers | undefined {
+    return this[kSentHeaders];
+  }
+
+  get sentInfoHeaders(): Http2Headers[] | undefined {
+    return this[kInfoHeaders];
+  }
+
+  get sentTrailers(): Http2Headers | undefined {
+    return this[kSentTrailers];
+  }
+
+  get state(): Record<string, unknown> {
+    notImplemented("Http2Stream.state");
+    return {};
+  }
+
+  priority(_options: Record<string, unknown>) {
+    notImplemented("Http2Stream.priority");
+  }
+
+  // deno_http's server can't initiate streams, so pushing behaves as if the
+  // client had disabled push streams.
   get pushAllowed(): boolean {
-    notImplemented("ServerHttp2Stream.pushAllowed");
     return false;
   }
 
   pushStream(
-    _headers: Record<string, unknown>,
+    _headers: Http2Headers,
     _options: Record<string, unknown>,
     _callback: () => unknown,
   ) {
-    notImplemented("ServerHttp2Stream.pushStream");
+    throw new ERR_HTTP2_PUSH_DISABLED();
+  }
+
+  additionalHeaders(headers: Http2Headers) {
+    if (this.destroyed || this.closed) {
+      throw new ERR_HTTP2_INVALID_STREAM();
+    }
+    if (this.headersSent) {
+      throw new ERR_HTTP2_HEADERS_AFTER_RESPOND();
+    }
+    const status = Number(headers[constants.HTTP2_HEADER_STATUS]);
+    if (status === constants.HTTP_STATUS_SWITCHING_PROTOCOLS) {
+      throw new ERR_HTTP2_STATUS_101();
+    }
+    if (status < 100 || status >= 200) {
+      throw new ERR_HTTP2_INVALID_INFO_STATUS(status);
+    }
+    (this[kInfoHeaders] ??= []).push(headers);
+    // deno_http sends "100 Continue" by itself, once the request body is read.
+    if (status !== constants.HTTP_STATUS_CONTINUE) {
+      warnNotImplemented("ServerHttp2Stream.additionalHeaders");
+    }
   }
 
   respond(
-    headers: Http2Headers,
-    options: Record<string, unknown>,
+    headers?: Http2Headers,
+    options?: Record<string, unknown>,
   ) {
-    this.#headersSent = true;
-    const response: ResponseInit = {};
-    if (headers) {
-      for (const [name, value] of Object.entries(headers)) {
-        if (name == constants.HTTP2_HEADER_STATUS) {
-          response.status = Number(value);
-        }
-      }
+    if (this.destroyed || this.closed) {
+      throw new ERR_HTTP2_INVALID_STREAM();
     }
-    if (options?.endStream) {
-      this._deferred.resolve(this._response = new Response("", response));
-    } else {
-      this.#waitForTrailers = options?.waitForTrailers;
-      this._deferred.resolve(
-        this._response = new Response(this.#body, response),
+    if (this.headersSent) {
+      throw new ERR_HTTP2_HEADERS_SENT();
+    }
+    headers = ObjectAssign({ __proto__: null }, headers);
+    options = { ...options };
+
+    const status = headers[constants.HTTP2_HEADER_STATUS] === undefined
+      ? constants.HTTP_STATUS_OK
+      : Number(headers[constants.HTTP2_HEADER_STATUS]);
+    if (status === constants.HTTP_STATUS_SWITCHING_PROTOCOLS) {
+      throw new ERR_HTTP2_STATUS_101();
+    }
+    if (!(status >= 200 && status <= 599)) {
+      throw new ERR_HTTP2_STATUS_INVALID(
+        headers[constants.HTTP2_HEADER_STATUS],
       );
     }
+    headers[constants.HTTP2_HEADER_STATUS] = status;
+
+    const state = this[kState];
+    if (isNullBodyStatus(status) || this.headRequest) {
+      options.endStream = true;
+      options.waitForTrailers = false;
+    }
+    const response = new Response(
+      options.endStream && !options.waitForTrailers ? null : this.#body,
+      { status, headers: toHeaderList(headers) },
+    );
+
+    state.flags |= STREAM_FLAGS_HEADERS_SENT;
+    if (options.waitForTrailers) {
+      state.flags |= STREAM_FLAGS_HAS_TRAILERS;
+    }
+    this[kSentHeaders] = headers;
+    this._deferred.resolve(response);
+    if (options.endStream) {
+      this.end();
+    }
   }
 
   respondWithFD(
-    _fd: number | FileHandle,
-    _headers: Record<string, unknown>,
-    _options: Record<string, unknown>,
+    fd: number | FileHandle,
+    headers?: Http2Headers,
+    options?: Record<string, unknown>,
   ) {
-    notImplemented("ServerHttp2Stream.respondWithFD");
+    const fileDescriptor = fd instanceof FileHandle ? fd.fd : fd;
+    this.#respondWithFile(
+      (callback) => fstat(fileDescriptor, callback),
+      (start, end) =>
+        createReadStream(null, {
+          fd: fileDescriptor,
+          start,
+          end,
+          autoClose: false,
+        }),
+      headers,
+      options,
+    );
   }
 
   respondWithFile(
-    _path: string | Buffer | URL,
-    _headers: Record<string, unknown>,
-    _options: Record<string, unknown>,
+    path: string | Buffer | URL,
+    headers?: Http2Headers,
+    options?: Record<string, unknown>,
   ) {
-    notImplemented("ServerHttp2Stream.respondWithFile");
+    this.#respondWithFile(
+      (callback) => stat(path, callback),
+      (start, end) => createReadStream(path, { start, end }),
+      headers,
+      options,
+    );
+  }
+
+  #respondWithFile(
+    statFile: (callback: (err: Error | null, stat: Stats) => void) => void,
+    openFile: (start: number, end: number | undefined) => Readable,
+    headers?: Http2Headers,
+    options?: Record<string, unknown>,
+  ) {
+    if (this.destroyed || this.closed) {
+      throw new ERR_HTTP2_INVALID_STREAM();
+    }
+    if (this.headersSent) {
+      throw new ERR_HTTP2_HEADERS_SENT();
+    }
+    headers = ObjectAssign({ __proto__: null }, headers);
+    options = { ...options };
+    const onError = (err: Error) => {
+      if (typeof options.onError === "function") {
+        options.onError(err);
+      } else {
+        this.destroy(err);
+      }
+    };
+
+    statFile((err, stat) => {
+      if (this.destroyed || this.closed) {
+        return;
+      }
+      if (err) {
+        onError(err);
+        return;
+      }
+      if (stat.isDirectory()) {
+        onError(new ERR_HTTP2_SEND_FILE());
+        return;
+      }
+      const offset = Number(options.offset ?? 0);
+      const length = options.length === undefined
+        ? undefined
+        : Number(options.length);
+      if (!stat.isFile() && (offset !== 0 || length !== undefined)) {
+        onError(new ERR_HTTP2_SEND_FILE_NOSEEK());
+        return;
+      }
+      if (
+        typeof options.statCheck === "function" &&
+        options.statCheck(stat, headers, options) === false
+      ) {
+        return;
+      }
+      if (stat.isFile()) {
+        const size = MathMax(0, stat.size - offset);
+        headers[constants.HTTP2_HEADER_CONTENT_LENGTH] = length === undefined
+          ? size
+          : MathMin(size, length);
+      }
+      try {
+        this.respond(headers, options);
+      } catch (e) {
+        onError(e);
+        return;
+      }
+      if (this.headRequest || this.writableEnded) {
+        return;
+      }
+      const end = length === undefined ? undefined : offset + length - 1;
+      const file = openFile(offset, end);
+      file.on("error", onError);
+      file.pipe(this);
+    });
+  }
+
+  setTimeout(msecs: number, callback?: () => void) {
+    setStreamTimeout.call(this, msecs, callback);
+  }
+
+  _onTimeout() {
+    callTimeout(this, this[kSession]);
+  }
+
+  close(code: number = constants.NGHTTP2_NO_ERROR, callback?: () => void) {
+    if (this.closed) {
+      return;
+    }
+    if (typeof callback === "function") {
+      this.once("close", callback);
+    }
+    closeServerStream(this, code);
+  }
+
+  _read() {
+    const reader = this.#reader;
+    if (reader === null) {
+      this.push(null);
+      return;
+    }
+    reader.read().then(({ done, value }) => {
+      if (done) {
+        this.#reader = null;
+        this.push(null);
+      } else {
+        this.push(Buffer.from(value.buffer, value.byteOffset, value.length));
+      }
+    }, () => {
+      // The request body fails once the client reset the stream, or once
+      // deno_http is done with the request.
+      this.#reader = null;
+      if (this.writableEnded) {
+        this.push(null);
+      } else {
+        this.destroy();
+      }
+    });
+  }
+
+  _write(chunk, encoding, callback) {
+    if (this.closed) {
+      // The stream was reset, so its data is discarded.
+      callback();
+      return;
+    }
+    if (!this.headersSent) {
+      this.respond();
+    }
+    if (typeof chunk === "string") {
+      chunk = Buffer.from(chunk, encoding);
+    }
+    // Copy the chunk, as it can be reused once the callback is called.
+    this.#controller.enqueue(new Uint8Array(chunk));
+    if (this.#controller.desiredSize > 0) {
+      callback();
+    } else {
+      this.#pendingWrite = callback;
+    }
+  }
+
+  _final(callback) {
+    if (this.closed) {
+      callback();
+      return;
+    }
+    if (!this.headersSent) {
+      this.respond();
+    }
+    if (this[kState].flags & STREAM_FLAGS_HAS_TRAILERS) {
+      this.#pendingFinal = callback;
+      onStreamTrailers(this);
+    } else {
+      this.#closeBody();
+      callback();
+    }
+  }
+
+  sendTrailers(headers: Http2Headers) {
+    if (this.destroyed || this.closed) {
+      throw new ERR_HTTP2_INVALID_STREAM();
+    }
+    if (this[kSentTrailers]) {
+      throw new ERR_HTTP2_TRAILERS_ALREADY_SENT();
+    }
+    if (!this[kState].trailersReady) {
+      throw new ERR_HTTP2_TRAILERS_NOT_READY();
+    }
+    headers = ObjectAssign({ __proto__: null }, headers);
+    const trailerList = toHeaderList(headers);
+    this[kSentTrailers] = headers;
+    this[kState].flags &= ~STREAM_FLAGS_HAS_TRAILERS;
+    // The trailers must be set before the body is complete, deno_http sends
+    // them right after its last chunk.
+    const { external } = toInnerRequest(this.#request);
+    if (trailerList.length > 0 && external !== null) {
+      op_http_set_response_trailers(external, trailerList);
+    }
+    this.#closeBody();
+    const callback = this.#pendingFinal;
+    this.#pendingFinal = null;
+    callback?.();
+  }
+
+  [kResetStream](code: number) {
+    this.#resetBody(code);
+  }
+
+  _destroy(err, callback) {
+    const session = this[kSession];
+    if (!this.closed) {
+      let code = constants.NGHTTP2_CANCEL;
+      if (err != null && !(err instanceof AbortError)) {
+        code = constants.NGHTTP2_INTERNAL_ERROR;
+      }
+      closeServerStream(this, code);
+    }
+    this.#reader?.cancel().catch(() => {});
+    this.#reader = null;
+    if (session !== undefined) {
+      session[kState].streams.delete(this[kID]);
+      this[kSession] = undefined;
+      session[kMaybeDestroy]();
+    }
+    callback(err);
   }
 }
 
+function closeServerStream(stream: ServerHttp2Stream, code: number) {
+  const state = stream[kState];
+  state.flags |= STREAM_FLAGS_CLOSED;
+  state.rstCode = code;
+
+  stream.setTimeout(0);
+  stream.removeAllListeners("timeout");
+
+  const { ending } = stream._writableState;
+  if (!ending) {
+    if (!stream.aborted) {
+      state.flags |= STREAM_FLAGS_ABORTED;
+      stream.emit("aborted");
+    }
+    stream.end();
+  }
+  // Without a response there is nothing to finish gracefully.
+  if (code !== constants.NGHTTP2_NO_ERROR || !stream.headersSent) {
+    stream[kResetStream](code);
+  }
+}
+
+// Creates the stream of a request that was received by `session`, and returns
+// the response to send as soon as the stream responds.
+function onServerRequest(
+  session: ServerHttp2Session,
+  request: Request,
+  info: Deno.ServeHandlerInfo,
+): Promise<Response> {
+  const url = new URL(request.url);
+  const headers: Http2Headers = {
+    [constants.HTTP2_HEADER_METHOD]: request.method,
+    [constants.HTTP2_HEADER_SCHEME]: url.protocol.slice(0, -1),
+    [constants.HTTP2_HEADER_AUTHORITY]: url.host,
+    [constants.HTTP2_HEADER_PATH]: url.pathname + url.search,
+  };
+  const rawHeaders = [];
+  for (const key of ObjectKeys(headers)) {
+    rawHeaders.push(key, headers[key]);
+  }
+  for (const [name, value] of request.headers) {
+    rawHeaders.push(name, value);
+    // `Headers` only yields a separate entry for every `set-cookie` header.
+    const existing = headers[name];
+    if (existing === undefined) {
+      headers[name] = value;
+    } else if (ArrayIsArray(existing)) {
+      existing.push(value);
+    } else {
+      headers[name] = [existing, value];
+    }
+  }
+
+  const id = session[kState].nextStreamId;
+  session[kState].nextStreamId += 2;
+  const stream = new ServerHttp2Stream(session, id, headers, request);
+  const flags = request.body === null
+    ? constants.NGHTTP2_FLAG_END_STREAM
+    : constants.NGHTTP2_FLAG_NONE;
+
+  // The stream closes once deno_http is done with the response, or when the
+  // client reset it.
+  info.completed.then(
+    () => constants.NGHTTP2_NO_ERROR,
+    () => constants.NGHTTP2_CANCEL,
+  ).then((code) => {
+    if (!stream.closed) {
+      closeServerStream(stream, code);
+    }
+    // deno_http is done as soon as a response without body is set, which can
+    // be before the writable side of the stream finished.
+    if (stream.writableEnded && !stream.writableFinished) {
+      stream.once("finish", () => stream.destroy());
+    } else {
+      stream.destroy();
+    }
+  });
+
+  try {
+    session.emit("stream", stream, headers, flags, rawHeaders);
+  } catch (e) {
+    stream.destroy(e);
+    nextTick(() => {
+      throw e;
+    });
+  }
+  return stream._deferred.promise;
+}
+
 function setupCompat(ev) {
   if (ev === "request") {
     this.removeListener("newListener", setupCompat);
@@ -1604,8 +2003,6 @@ function initializeOptions(options) {
 
 export class Http2Server extends Server {
   #options: Record<string, unknown> = {};
-  #abortController;
-  #server;
   timeout = 0;
 
   constructor(
@@ -1615,62 +2012,11 @@ export class Http2Server extends Server {
     options = initializeOptions(options);
     super(options);
     this[kOptions] = options;
-    this.#abortController = new AbortController();
     this.on("newListener", setupCompat);
 
-    this.on(
-      "connection",
-      (conn: Deno.Conn) => {
-        try {
-          const session = new ServerHttp2Session();
-          this.emit("session", session);
-          this.#server = serveHttpOnConnection(
-            conn,
-            this.#abortController.signal,
-            async (req: Request) => {
-              try {
-                const controllerDeferred = Promise.withResolvers<
-                  ReadableStreamDefaultController<Uint8Array>
-                >();
-                const body = new ReadableStream({
-                  start(controller) {
-                    controllerDeferred.resolve(controller);
-                  },
-                });
-                const headers: Http2Headers = {};
-                for (const [name, value] of req.headers) {
-                  headers[name] = value;
-                }
-                headers[constants.HTTP2_HEADER_PATH] =
-                  new URL(req.url).pathname;
-                const stream = new ServerHttp2Stream(
-                  session,
-                  Promise.resolve(headers),
-                  controllerDeferred.promise,
-                  req.body,
-                  body,
-                  req,
-                );
-                this.emit("stream", stream, headers);
-                return await stream._deferred.promise;
-              } catch (e) {
-                // deno-lint-ignore no-console
-                console.log(">>> Error in serveHttpOnConnection", e);
-              }
-              return new Response("");
-            },
-            () => {
-              // deno-lint-ignore no-console
-              console.log(">>> error");
-            },
-            () => {},
-          );
-        } catch (e) {
-          // deno-lint-ignore no-console
-          console.log(">>> Error in Http2Server", e);
-        }
-      },
-    );
+    this.on("connection", (conn: Deno.Conn) => {
+      serveSession(this, conn, false);
+    });
     this.#options = options;
     if (typeof requestListener === "function") {
       this.on("request", requestListener);
@@ -1694,7 +2040,7 @@ export class Http2Server extends Server {
   }
 }
 
-export class Http2SecureServer extends Server {
+export class Http2SecureServer extends TLSServer {
   #options: Record<string, unknown> = {};
   timeout = 0;
 
@@ -1702,8 +2048,18 @@ export class Http2SecureServer extends Server {
     options: Record<string, unknown>,
     requestListener: () => unknown,
   ) {
-    super(options, function () {
-      notImplemented("connectionListener");
+    options = initializeOptions(options);
+    // deno_http falls back to HTTP/1.1 if the client doesn't negotiate a
+    // protocol, so clients can only be limited to HTTP/2 through ALPN.
+    options.ALPNProtocols ??= options.allowHTTP1
+      ? ["h2", "http/1.1"]
+      : ["h2"];
+    super(options);
+    this[kOptions] = options;
+    this.on("newListener", setupCompat);
+
+    this.on("secureConnection", (conn: Deno.TlsConn) => {
+      serveSession(this, conn, true);
     });
     this.#options = options;
     if (typeof requestListener === "function") {
@@ -1711,8 +2067,9 @@ export class Http2SecureServer extends Server {
     }
   }
 
-  close(_callback?: () => unknown) {
-    notImplemented("Http2SecureServer.close");
+  // Prevent the TLS server from wrapping this in a socket, as for Http2Server
+  _createSocket(conn: Deno.TlsConn) {
+    return conn;
   }
 
   setTimeout(msecs: number, callback?: () => unknown) {
@@ -1727,6 +2084,38 @@ export class Http2SecureServer extends Server {
   }
 }
 
+// Serves the requests of a connection that was accepted by `server` with
+// deno_http, as the streams of a new `ServerHttp2Session`.
+function serveSession(
+  server: Http2Server | Http2SecureServer,
+  conn: Deno.Conn,
+  encrypted: boolean,
+) {
+  const session = new ServerHttp2Session(conn, encrypted);
+  session.on("stream", (...args) => server.emit("stream", ...args));
+  server.emit("session", session);
+
+  // Aborting closes the connection right away, see `closeSession()`.
+  session[kDenoAbortController] = new AbortController();
+  session[kDenoServer] = serveHttpOnConnection(
+    conn,
+    session[kDenoAbortController].signal,
+    (request: Request, info: Deno.ServeHandlerInfo) =>
+      onServerRequest(session, request, info),
+    () =>
+      new Response(null, {
+        status: constants.HTTP_STATUS_INTERNAL_SERVER_ERROR,
+      }),
+    () => {},
+  );
+  session[kState].flags |= SESSION_FLAGS_READY;
+  session[kDenoServer].finished.catch(() => {}).then(() => {
+    if (!session.destroyed) {
+      closeSession(session, constants.NGHTTP2_NO_ERROR);
+    }
+  });
+}
+
 export function createServer(
   options: Record<string, unknown>,
   onRequestHandler: () => unknown,
@@ -1739,11 +2128,14 @@ export function createServer(
 }
 
 export function createSecureServer(
-  _options: Record<string, unknown>,
-  _onRequestHandler: () => unknown,
+  options: Record<string, unknown>,
+  onRequestHandler: () => unknown,
 ): Http2SecureServer {
-  notImplemented("http2.createSecureServer");
-  return new Http2SecureServer();
+  if (typeof options === "function") {
+    onRequestHandler = options;
+    options = {};
+  }
+  return new Http2SecureServer(options, onRequestHandler);
 }
 
 export function connect(
@@ -2383,16 +2775,15 @@ class Http2ServerRequest extends Readable {
     return this.socket;
   }
 
-  // _read(nread) {
-  //   const state = this[kState];
-  //   assert(!state.closed);
-  //   if (!state.didRead) {
-  //     state.didRead = true;
-  //     this[kStream].on("data", onStreamData);
-  //   } else {
-  //     nextTick(resumeStream, this[kStream]);
-  //   }
-  // }
+  _read(_nread) {
+    const state = this[kState];
+    if (!state.didRead) {
+      state.didRead = true;
+      this[kStream].on("data", onStreamData);
+    } else {
+      nextTick(resumeStream, this[kStream]);
+    }
+  }
 
   get method() {
     return this[kHeaders][constants.HTTP2_HEADER_METHOD];
@@ -2431,6 +2822,19 @@ class Http2ServerRequest extends Readable {
   }
 }
 
+function onStreamData(chunk) {
+  const request = this[kRequest];
+  if (request !== undefined && !request.push(chunk)) {
+    this.pause();
+  }
+}
+
+function resumeStream(stream) {
+  if (!stream.destroyed) {
+    stream.resume();
+  }
+}
+
 function onStreamEnd() {
   // Cause the request stream to end as well.
   const request = this[kRequest];
@@ -8,10 +8,16 @@
 
 import { core, internals } from "ext:core/mod.js";
 import {
+  op_node_ipc_dup_handle,
   op_node_ipc_read,
+  op_node_ipc_read_advanced,
   op_node_ipc_ref,
+  op_node_ipc_take_listener,
+  op_node_ipc_take_socket,
+  op_node_ipc_take_udp,
   op_node_ipc_unref,
   op_node_ipc_write,
+  op_node_ipc_write_advanced,
 } from "ext:core/ops";
 import {
   ArrayIsArray,
@@ -36,6 +42,7 @@ import {
   AbortError,
   ERR_INVALID_ARG_TYPE,
   ERR_INVALID_ARG_VALUE,
+  ERR_INVALID_HANDLE_TYPE,
   ERR_IPC_CHANNEL_CLOSED,
   ERR_UNKNOWN_SIGNAL,
 } from "ext:deno_node/internal/errors.ts";
@@ -43,6 +50,11 @@ import { Buffer } from "node:buffer";
 import { errnoException } from "ext:deno_node/internal/errors.ts";
 import { ErrnoException } from "ext:deno_node/_global.d.ts";
 import { codeMap } from "ext:deno_node/internal_binding/uv.ts";
+import { isArrayBufferView } from "ext:deno_node/internal/util/types.ts";
+import {
+  spawn as spawnSyncProcess,
+  SpawnSyncStdio,
+} from "ext:deno_node/internal_binding/spawn_sync.ts";
 import {
   isInt32,
   validateBoolean,
@@ -56,6 +68,15 @@ import { StringPrototypeSlice } from "ext:deno_node/internal/primordials.mjs";
 import { StreamBase } from "ext:deno_node/internal_binding/stream_wrap.ts";
 import { Pipe, socketType } from "ext:deno_node/internal_binding/pipe_wrap.ts";
 import { Socket } from "node:net";
+import { Socket as DgramSocket } from "node:dgram";
+import { kStateSymbol } from "ext:deno_node/internal/dgram.ts";
+import { UDP } from "ext:deno_node/internal_binding/udp_wrap.ts";
+import {
+  constants as TCPConstants,
+  TCP,
+} from "ext:deno_node/internal_binding/tcp_wrap.ts";
+import { kStreamBaseField } from "ext:deno_node/internal_binding/stream_wrap.ts";
+import { DatagramConn, Listener, TcpConn } from "ext:deno_net/01_net.js";
 
 export function mapValues<T, O>(
   record: Readonly<Record<string, T>>,
@@ -141,6 +162,9 @@ class StreamResource implements StreamBase {
   constructor(rid: number) {
     this.#rid = rid;
   }
+  get [core.internalRidSymbol](): number {
+    return this.#rid;
+  }
   close(): void {
     core.close(this.#rid);
   }
@@ -238,7 +262,16 @@ export class ChildProcess extends EventEmitter {
       shell = false,
       signal,
       windowsVerbatimArguments = false,
+      argv0,
+      serialization = "json",
     } = options || {};
+    if (serialization !== "json" && serialization !== "advanced") {
+      throw new ERR_INVALID_ARG_VALUE(
+        "options.serialization",
+        serialization,
+        'must be "json" or "advanced"',
+      );
+    }
     const normalizedStdio = normalizeStdioOption(stdio);
     const [
       stdin = "pipe",
@@ -266,9 +299,14 @@ export class ChildProcess extends EventEmitter {
     }
 
     const stringEnv = mapValues(env, (value) => value.toString());
+    if (ipc >= 0) {
+      // Read by the child to set up its end of the channel.
+      stringEnv.NODE_CHANNEL_SERIALIZATION_MODE = serialization;
+    }
     try {
       this.#process = new Deno.Command(cmd, {
         args: cmdArgs,
+        argv0,
         cwd,
         env: stringEnv,
         stdin: toDenoStdio(stdin),
@@ -370,7 +408,7 @@ export class ChildProcess extends EventEmitter {
 
       const pipeRid = internals.getIpcPipeRid(this.#process);
       if (typeof pipeRid == "number") {
-        setupChannel(this, pipeRid);
+        setupChannel(this, pipeRid, serialization);
         this[kClosesNeeded]++;
         this.on("disconnect", () => {
           maybeClose(this);
@@ -567,7 +605,8 @@ export interface ChildProcessOptions {
   gid?: number;
 
   /**
-   * NOTE: This option is not yet implemented.
+   * Explicitly set the value of `argv[0]` sent to the child process. Ignored
+   * on Windows.
    */
   argv0?: string;
 
@@ -583,7 +622,8 @@ export interface ChildProcessOptions {
   signal?: AbortSignal;
 
   /**
-   * NOTE: This option is not yet implemented.
+   * How the messages of the IPC channel are serialized, with `JSON.stringify`
+   * or the structured clone algorithm. Default: `"json"`.
    */
   serialization?: "json" | "advanced";
 
@@ -902,12 +942,12 @@ function buildCommand(
 }
 
 function _createSpawnSyncError(
-  status: string,
+  status: string | number,
   command: string,
   args: string[] = [],
 ): ErrnoException {
   const error = errnoException(
-    codeMap.get(status),
+    typeof status === "number" ? status : codeMap.get(status),
     "spawnSync " + command,
   );
   error.path = command;
@@ -939,7 +979,7 @@ export interface SpawnSyncOptions extends
     | "windowsVerbatimArguments"
     | "windowsHide"
   > {
-  input?: string | Buffer | DataView;
+  input?: string | ArrayBufferView;
   timeout?: number;
   maxBuffer?: number;
   encoding?: string;
@@ -951,7 +991,7 @@ export interface SpawnSyncOptions extends
 
 export interface SpawnSyncResult {
   pid?: number;
-  output?: [string | null, string | Buffer | null, string | Buffer | null];
+  output?: [null, string | Buffer | null, string | Buffer | null] | null;
   stdout?: Buffer | string | null;
   stderr?: Buffer | string | null;
   status?: number | null;
@@ -959,17 +999,35 @@ export interface SpawnSyncResult {
   error?: Error;
 }
 
-function parseSpawnSyncOutputStreams(
-  output: Deno.CommandOutput,
-  name: "stdout" | "stderr",
-): string | Buffer | null {
-  // new Deno.Command().outputSync() returns getters for stdout and stderr that throw when set
-  // to 'inherit'.
-  try {
-    return Buffer.from(output[name]) as string | Buffer;
-  } catch {
-    return null;
+function toSpawnSyncStdio(
+  pipe: NodeStdio | number | Stream | null | undefined,
+): SpawnSyncStdio {
+  if (pipe instanceof Stream) {
+    return { type: "inherit" };
+  }
+  if (typeof pipe === "number") {
+    return { type: "fd", fd: pipe };
+  }
+  switch (pipe) {
+    case "pipe":
+    case "overlapped":
+    case undefined:
+    case null:
+      return { type: "pipe" };
+    case "ignore":
+      return { type: "ignore" };
+    case "inherit":
+      return { type: "inherit" };
+    default:
+      notImplemented(`spawnSync stdio=${typeof pipe} (${pipe})`);
+  }
+}
+
+function inputToBytes(input: string | ArrayBufferView): Uint8Array {
+  if (typeof input === "string") {
+    return Buffer.from(input);
   }
+  return new Uint8Array(input.buffer, input.byteOffset, input.byteLength);
 }
 
 export function spawnSync(
@@ -987,6 +1045,8 @@ export function spawnSync(
     gid,
     maxBuffer,
     windowsVerbatimArguments = false,
+    argv0,
+    input,
   } = options;
   const [
     stdin_ = "pipe",
@@ -996,47 +1056,52 @@ export function spawnSync(
   ] = normalizeStdioOption(stdio);
   [command, args] = buildCommand(command, args ?? [], shell);
 
-  const result: SpawnSyncResult = {};
-  try {
-    const output = new Deno.Command(command, {
-      args,
-      cwd,
-      env: mapValues(env, (value) => value.toString()),
-      stdout: toDenoStdio(stdout_),
-      stderr: toDenoStdio(stderr_),
-      stdin: stdin_ == "inherit" ? "inherit" : "null",
-      uid,
-      gid,
-      windowsRawArguments: windowsVerbatimArguments,
-    }).outputSync();
-
-    const status = output.signal ? null : output.code;
-    let stdout = parseSpawnSyncOutputStreams(output, "stdout");
-    let stderr = parseSpawnSyncOutputStreams(output, "stderr");
-
-    if (
-      (stdout && stdout.length > maxBuffer!) ||
-      (stderr && stderr.length > maxBuffer!)
-    ) {
-      result.error = _createSpawnSyncError("ENOBUFS", command, args);
+  const stdioOptions = [stdin_, stdout_, stderr_].map(toSpawnSyncStdio);
+  if (input != null) {
+    if (typeof input !== "string" && !isArrayBufferView(input)) {
+      throw new ERR_INVALID_ARG_TYPE(
+        "options.stdio[0]",
+        ["Buffer", "TypedArray", "DataView", "string"],
+        input,
+      );
     }
+    stdioOptions[0].input = inputToBytes(input);
+  }
 
-    if (encoding && encoding !== "buffer") {
-      stdout = stdout && stdout.toString(encoding);
-      stderr = stderr && stderr.toString(encoding);
-    }
+  const envPairs = Object.entries(mapValues(env, (value) => value.toString()))
+    .map(([key, value]) => `${key}=${value}`);
+  const result = spawnSyncProcess({
+    file: command,
+    args: [argv0 ?? command, ...args],
+    cwd,
+    envPairs,
+    stdio: stdioOptions,
+    uid,
+    gid,
+    maxBuffer,
+    windowsVerbatimArguments,
+  });
 
-    result.status = status;
-    result.signal = output.signal;
-    result.stdout = stdout;
-    result.stderr = stderr;
-    result.output = [output.signal, stdout, stderr];
-  } catch (err) {
-    if (err instanceof Deno.errors.NotFound) {
-      result.error = _createSpawnSyncError("ENOENT", command, args);
-    }
+  let output: SpawnSyncResult["output"] = result.output;
+  if (output && encoding && encoding !== "buffer") {
+    output = [
+      null,
+      output[1] && output[1].toString(encoding),
+      output[2] && output[2].toString(encoding),
+    ];
   }
-  return result;
+
+  const ret: SpawnSyncResult = {};
+  if (result.error !== undefined) {
+    ret.error = _createSpawnSyncError(result.error, command, args);
+  }
+  ret.status = result.status;
+  ret.signal = result.signal;
+  ret.output = output;
+  ret.pid = result.pid;
+  ret.stdout = output && output[1];
+  ret.stderr = output && output[2];
+  return ret;
 }
 
 // These are Node.js CLI flags that expect a value. It's necessary to
@@ -1294,34 +1359,170 @@ function internalCmdName(msg: InternalMessage): string {
   return StringPrototypeSlice(msg.cmd, 5);
 }
 
+const kCloseMessage = { cmd: "NODE_CLOSE" };
+
+// There will always be a pending read promise, but it shouldn't keep the
+// event loop from exiting.
+//
+// Messages with the "advanced" serialization are cloned by V8, and prefixed
+// with their length by the ops.
+async function readAdvanced(ipc: number) {
+  const prom = op_node_ipc_read_advanced(ipc);
+  core.unrefOpPromise(prom);
+  const buf = await prom;
+  return buf === null
+    ? kCloseMessage
+    : core.deserialize(buf, { forStorage: true });
+}
+
+function readJson(ipc: number) {
+  const prom = op_node_ipc_read(ipc);
+  core.unrefOpPromise(prom);
+  return prom;
+}
+
+// Handles are sent along with a `NODE_HANDLE` message wrapping the message
+// they were sent with. On unix their sockets are received over the pipe in
+// the same order, on Windows the message carries the info to create a
+// duplicate for this process from.
+//
+// `net.Socket` and `dgram.Socket` handles are received as sockets, and
+// `net.Native` and `dgram.Native` ones as the TCP and UDP handles that are
+// used internally, e.g. by `node:cluster`.
+function receiveHandle(ipc: number, msg) {
+  if (msg.type === "dgram.Native" || msg.type === "dgram.Socket") {
+    const { 0: rid, 1: addr } = op_node_ipc_take_udp(ipc, msg.info);
+    addr.transport = "udp";
+    const handle = new UDP();
+    handle.setListener(new DatagramConn(rid, addr));
+    if (msg.type === "dgram.Native") {
+      return handle;
+    }
+    const socket = new DgramSocket(msg.dgramType);
+    socket.bind(handle);
+    return socket;
+  }
+
+  if (msg.server) {
+    const { 0: rid, 1: addr } = op_node_ipc_take_listener(ipc, msg.info);
+    addr.transport = "tcp";
+    const handle = new TCP(TCPConstants.SERVER);
+    handle.setListener(new Listener(rid, addr));
+    return handle;
+  }
+
+  const { 0: rid, 1: localAddr, 2: remoteAddr } = op_node_ipc_take_socket(
+    ipc,
+    msg.info,
+  );
+  localAddr.transport = "tcp";
+  remoteAddr.transport = "tcp";
+  const conn = new TcpConn(rid, remoteAddr, localAddr);
+  const handle = new TCP(TCPConstants.SOCKET, conn);
+  if (msg.type === "net.Native") {
+    return handle;
+  }
+  return new Socket({ handle, readable: true, writable: true });
+}
+
+// Returns the rid of the resource whose socket is sent for `handle`, and the
+// type of the handle, or throws if it can't be sent.
 // deno-lint-ignore no-explicit-any
-export function setupChannel(target: any, ipc: number) {
+function handleToSend(handle: any) {
+  if (handle instanceof Socket) {
+    const conn = handle._handle instanceof TCP &&
+      handle._handle[kStreamBaseField];
+    if (conn) {
+      return { rid: conn[core.internalRidSymbol], type: "net.Socket" };
+    }
+  } else if (handle instanceof TCP) {
+    if (handle[kStreamBaseField]) {
+      return {
+        rid: handle[kStreamBaseField][core.internalRidSymbol],
+        type: "net.Native",
+      };
+    }
+    const listener = handle.getListener();
+    if (typeof listener === "number") {
+      throw errnoException(listener, "listen");
+    }
+    return {
+      rid: listener[core.internalRidSymbol],
+      type: "net.Native",
+      server: true,
+    };
+  } else if (handle instanceof DgramSocket) {
+    const conn = handle[kStateSymbol].handle?.getListener();
+    if (conn) {
+      return {
+        rid: conn[core.internalRidSymbol],
+        type: "dgram.Socket",
+        dgramType: handle.type,
+      };
+    }
+  } else if (handle instanceof UDP) {
+    const conn = handle.getListener();
+    if (conn) {
+      return { rid: conn[core.internalRidSymbol], type: "dgram.Native" };
+    }
+  }
+  throw new ERR_INVALID_HANDLE_TYPE();
+}
+
+export function setupChannel(
+  // deno-lint-ignore no-explicit-any
+  target: any,
+  ipc: number,
+  serialization: "json" | "advanced" = "json",
+) {
   const control = new Control(ipc);
   target.channel = control;
 
+  const read = serialization === "advanced" ? readAdvanced : readJson;
+  const write = serialization === "advanced"
+    ? (message, queueOk, handleRid) =>
+      op_node_ipc_write_advanced(
+        ipc,
+        core.serialize(message, { forStorage: true }),
+        queueOk,
+        handleRid,
+      )
+    : (message, queueOk, handleRid) =>
+      op_node_ipc_write(ipc, message, queueOk, handleRid);
+
+  // Sockets that were sent, which are destroyed once the other end
+  // acknowledges that it received them, unless they should be kept open.
+  const sentHandles: (Socket | null)[] = [];
+  // Windows duplicates sockets for the process at the other end.
+  const peerPid = () => target === process ? process.ppid : target.pid;
+
   async function readLoop() {
     try {
       while (true) {
         if (!target.connected || target.killed) {
           return;
         }
-        const prom = op_node_ipc_read(ipc);
-        // there will always be a pending read promise,
-        // but it shouldn't keep the event loop from exiting
-        core.unrefOpPromise(prom);
-        const msg = await prom;
+        const msg = await read(ipc);
         if (isInternal(msg)) {
           const cmd = internalCmdName(msg);
           if (cmd === "CLOSE") {
             // Channel closed.
             target.disconnect();
             return;
+          } else if (cmd === "HANDLE") {
+            const handle = receiveHandle(ipc, msg);
+            // The other end closes its handle once this one exists.
+            target.send({ cmd: "NODE_HANDLE_ACK" });
+            process.nextTick(handleMessage, msg.msg, handle);
+          } else if (cmd === "HANDLE_ACK") {
+            const handle = sentHandles.shift();
+            if (handle) {
+              handle.destroy();
+            }
           } else {
-            // TODO(nathanwhit): once we add support for sending
-            // handles, if we want to support deno-node IPC interop,
-            // we'll need to handle the NODE_HANDLE_* messages here.
-            continue;
+            process.nextTick(handleMessage, msg);
           }
+          continue;
         }
 
         process.nextTick(handleMessage, msg);
@@ -1336,16 +1537,21 @@ export function setupChannel(target: any, ipc: number) {
     }
   }
 
-  function handleMessage(msg) {
+  // deno-lint-ignore no-explicit-any
+  function handleMessage(msg, handle?: any) {
     if (!target.channel) {
       return;
     }
+    if (isInternal(msg)) {
+      target.emit("internalMessage", msg, handle);
+      return;
+    }
     if (target.listenerCount("message") !== 0) {
-      target.emit("message", msg);
+      target.emit("message", msg, handle);
       return;
     }
 
-    ArrayPrototypePush(target.channel[kPendingMessages], msg);
+    ArrayPrototypePush(target.channel[kPendingMessages], [msg, handle]);
   }
 
   target.on("newListener", () => {
@@ -1353,8 +1559,8 @@ export function setupChannel(target: any, ipc: number) {
       if (!target.channel || !target.listenerCount("message")) {
         return;
       }
-      for (const msg of target.channel[kPendingMessages]) {
-        target.emit("message", msg);
+      for (const { 0: msg, 1: handle } of target.channel[kPendingMessages]) {
+        target.emit("message", msg, handle);
       }
       target.channel[kPendingMessages] = [];
     });
@@ -1378,8 +1584,15 @@ export function setupChannel(target: any, ipc: number) {
       throw new TypeError("ERR_MISSING_ARGS", "message");
     }
 
-    if (handle !== undefined) {
-      notImplemented("ChildProcess.send with handle");
+    let handleRid;
+    if (handle != null) {
+      const { rid, ...info } = handleToSend(handle);
+      message = { cmd: "NODE_HANDLE", ...info, msg: message };
+      if (isWindows) {
+        message.info = op_node_ipc_dup_handle(rid, peerPid());
+      } else {
+        handleRid = rid;
+      }
     }
 
     if (!target.connected) {
@@ -1397,7 +1610,13 @@ export function setupChannel(target: any, ipc: number) {
     // this acts as a backpressure mechanism.
     const queueOk = [true];
     control.refCounted();
-    op_node_ipc_write(ipc, message, queueOk)
+    if (handle != null) {
+      ArrayPrototypePush(
+        sentHandles,
+        handle instanceof Socket && !options.keepOpen ? handle : null,
+      );
+    }
+    write(message, queueOk, handleRid)
       .then(() => {
         control.unrefCounted();
         if (callback) {
@@ -0,0 +1,319 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+// Copyright Joyent and Node contributors. All rights reserved. MIT license.
+
+// TODO(petamoriken): enable prefer-primordials for node polyfills
+// deno-lint-ignore-file prefer-primordials no-explicit-any
+
+import { assert } from "ext:deno_node/_util/asserts.ts";
+import path from "node:path";
+import process from "node:process";
+import { ownerSymbol } from "ext:deno_node/internal_binding/symbols.ts";
+import { TIMEOUT_MAX } from "ext:deno_node/internal/timers.mjs";
+import { Worker } from "ext:deno_node/internal/cluster/worker.ts";
+import { internal, sendHelper } from "ext:deno_node/internal/cluster/utils.ts";
+
+const handles = new Map<string, any>();
+const indexes = new Map<string, { nextIndex: number; set: Set<number> }>();
+const noop = () => {};
+
+/**
+ * Sets up `cluster` for a worker process, whose servers get their
+ * connections or listeners from the primary.
+ * @param id The id of the worker, from `NODE_UNIQUE_ID`.
+ */
+export function initializeWorker(cluster: any, id: string) {
+  cluster.isWorker = true;
+  cluster.isMaster = false; // Deprecated alias. Must be same as isPrimary.
+  cluster.isPrimary = false;
+
+  const worker = new (Worker as any)({
+    id: +id | 0,
+    process: process,
+    state: "online",
+  });
+
+  cluster.worker = worker;
+
+  process.once("disconnect", () => {
+    worker.emit("disconnect");
+
+    if (!worker.exitedAfterDisconnect) {
+      // Unexpected disconnect, primary exited, or some such nastiness, so
+      // worker exits immediately.
+      process.exit(0);
+    }
+  });
+
+  process.on("internalMessage", internal(worker, onmessage));
+  send({ act: "online" });
+
+  function onmessage(message: any, handle: any) {
+    if (message.act === "newconn") {
+      onconnection(message, handle);
+    } else if (message.act === "disconnect") {
+      disconnect.call(worker, true);
+    }
+  }
+
+  // `obj` is a net#Server.
+  cluster._getServer = function (obj: any, options: any, cb: any) {
+    let address = options.address;
+
+    // Resolve unix socket paths to absolute paths
+    if (
+      options.port < 0 && typeof address === "string" &&
+      process.platform !== "win32"
+    ) {
+      address = path.resolve(address);
+    }
+
+    const indexesKey = [
+      address,
+      options.port,
+      options.addressType,
+      options.fd,
+    ].join(":");
+
+    let indexSet = indexes.get(indexesKey);
+
+    if (indexSet === undefined) {
+      indexSet = { nextIndex: 0, set: new Set() };
+      indexes.set(indexesKey, indexSet);
+    }
+    const index = indexSet.nextIndex++;
+    indexSet.set.add(index);
+
+    const message = {
+      act: "queryServer",
+      index,
+      data: null,
+      ...options,
+    };
+
+    message.address = address;
+
+    // Set custom data on handle (i.e. tls tickets key)
+    if (obj._getServerData) {
+      message.data = obj._getServerData();
+    }
+
+    send(message, (reply: any, handle: any) => {
+      if (typeof obj._setServerData === "function") {
+        obj._setServerData(reply.data);
+      }
+
+      if (handle) {
+        // Shared listen socket
+        shared(reply, { handle, indexesKey, index }, cb);
+      } else {
+        // Round-robin.
+        rr(reply, { indexesKey, index }, cb);
+      }
+    });
+
+    obj.once("listening", () => {
+      // short-lived sockets might have been closed
+      if (!indexes.has(indexesKey)) {
+        return;
+      }
+      cluster.worker.state = "listening";
+      const address = obj.address();
+      message.act = "listening";
+      message.port = (address && address.port) || options.port;
+      send(message);
+    });
+  };
+
+  // Extend generic Worker with methods specific to worker processes.
+  Worker.prototype.disconnect = function (this: any) {
+    if (this.state !== "disconnecting" && this.state !== "destroying") {
+      this.state = "disconnecting";
+      disconnect.call(this, false);
+    }
+
+    return this;
+  };
+
+  Worker.prototype.destroy = function (this: any) {
+    if (this.state === "destroying") {
+      return;
+    }
+
+    this.exitedAfterDisconnect = true;
+    if (!this.isConnected()) {
+      process.exit(0);
+    } else {
+      this.state = "destroying";
+      send({ act: "exitedAfterDisconnect" }, () => process.disconnect());
+      process.once("disconnect", () => process.exit(0));
+    }
+  };
+}
+
+function removeIndexesKey(indexesKey: string, index: number) {
+  const indexSet = indexes.get(indexesKey);
+  if (!indexSet) {
+    return;
+  }
+
+  indexSet.set.delete(index);
+  if (indexSet.set.size === 0) {
+    indexes.delete(indexesKey);
+  }
+}
+
+// Shared listen socket.
+function shared(
+  message: any,
+  { handle, indexesKey, index }: any,
+  cb: (errno: number, handle: any) => void,
+) {
+  const key = message.key;
+  // Monkey-patch the close() method so we can keep track of when it's
+  // closed. Avoids resource leaks when the handle is short-lived.
+  const close = handle.close;
+
+  handle.close = function (...args: unknown[]) {
+    send({ act: "close", key });
+    handles.delete(key);
+    removeIndexesKey(indexesKey, index);
+    return close.apply(handle, args);
+  };
+  assert(handles.has(key) === false);
+  handles.set(key, handle);
+  cb(message.errno, handle);
+}
+
+// Round-robin. Primary distributes handles across workers.
+function rr(
+  message: any,
+  { indexesKey, index }: any,
+  cb: (errno: number, handle: any) => void,
+) {
+  if (message.errno) {
+    return cb(message.errno, null);
+  }
+
+  let key = message.key;
+
+  let fakeHandle: number | null = null;
+
+  function ref() {
+    if (!fakeHandle) {
+      fakeHandle = setInterval(noop, TIMEOUT_MAX);
+    }
+  }
+
+  function unref() {
+    if (fakeHandle) {
+      clearInterval(fakeHandle);
+      fakeHandle = null;
+    }
+  }
+
+  function listen(_backlog: number) {
+    // TODO(bnoordhuis) Send a message to the primary that tells it to
+    // update the backlog size. The actual backlog should probably be
+    // the largest requested size by any worker.
+    return 0;
+  }
+
+  function close() {
+    // lib/net.js treats server._handle.close() as effectively synchronous.
+    // That means there is a time window between the call to close() and
+    // the ack by the primary process in which we can still receive handles.
+    // onconnection() below handles that by sending those handles back to
+    // the primary.
+    if (key === undefined) {
+      return;
+    }
+    unref();
+    // If the handle is the last handle in process, the parent process will
+    // delete the handle when worker process exits. So it is ok if the close
+    // message get lost.
+    send({ act: "close", key });
+    handles.delete(key);
+    removeIndexesKey(indexesKey, index);
+    key = undefined;
+  }
+
+  function getsockname(out: Record<string, unknown>) {
+    if (key) {
+      Object.assign(out, message.sockname);
+    }
+
+    return 0;
+  }
+
+  // Faux handle. net.Server is not associated with handle, so we control its
+  // state (ref or unref) by setInterval.
+  const handle = { close, listen, ref, unref, getsockname };
+  handle.ref();
+
+  assert(handles.has(key) === false);
+  handles.set(key, handle);
+  cb(0, handle);
+}
+
+// Round-robin connection.
+function onconnection(message: any, handle: any) {
+  const key = message.key;
+  const server = handles.get(key);
+  let accepted = server !== undefined;
+
+  if (accepted && server[ownerSymbol]) {
+    const self = server[ownerSymbol];
+    if (
+      self.maxConnections != null &&
+      self._connections >= self.maxConnections
+    ) {
+      accepted = false;
+    }
+  }
+
+  send({ ack: message.seq, accepted });
+
+  if (accepted) {
+    server.onconnection(0, handle);
+  } else {
+    handle.close();
+  }
+}
+
+function send(message: any, cb?: (...args: any[]) => void) {
+  return sendHelper(process, message, null, cb);
+}
+
+function disconnect(this: any, primaryInitiated: boolean) {
+  this.exitedAfterDisconnect = true;
+  let waitingCount = 1;
+
+  function checkWaitingCount() {
+    waitingCount--;
+
+    if (waitingCount === 0) {
+      // If disconnect is worker initiated, wait for ack to be sure
+      // exitedAfterDisconnect is properly set in the primary, otherwise, if
+      // it's primary initiated there's no need to send the
+      // exitedAfterDisconnect message
+      if (primaryInitiated) {
+        process.disconnect();
+      } else {
+        send({ act: "exitedAfterDisconnect" }, () => process.disconnect());
+      }
+    }
+  }
+
+  handles.forEach((handle) => {
+    waitingCount++;
+
+    if (handle[ownerSymbol]) {
+      handle[ownerSymbol].close(checkWaitingCount);
+    } else {
+      handle.close(checkWaitingCount);
+    }
+  });
+
+  handles.clear();
+  checkWaitingCount();
+}
@@ -0,0 +1,329 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+// Copyright Joyent and Node contributors. All rights reserved. MIT license.
+
+// TODO(petamoriken): enable prefer-primordials for node polyfills
+// deno-lint-ignore-file prefer-primordials no-explicit-any
+
+import { assert } from "ext:deno_node/_util/asserts.ts";
+import { EventEmitter } from "node:events";
+import { fork } from "node:child_process";
+import process from "node:process";
+import { codeMap } from "ext:deno_node/internal_binding/uv.ts";
+import { RoundRobinHandle } from "ext:deno_node/internal/cluster/round_robin_handle.ts";
+import { SharedHandle } from "ext:deno_node/internal/cluster/shared_handle.ts";
+import { Worker } from "ext:deno_node/internal/cluster/worker.ts";
+import { internal, sendHelper } from "ext:deno_node/internal/cluster/utils.ts";
+
+export const SCHED_NONE = 1;
+export const SCHED_RR = 2;
+
+const handles = new Map<string, RoundRobinHandle | SharedHandle>();
+const intercom = new EventEmitter();
+
+let ids = 0;
+let initialized = false;
+let schedulingPolicy: number;
+
+/**
+ * Sets up `cluster` for the primary process, which forks the workers and
+ * listens on their behalf.
+ * @param policy The value of `NODE_CLUSTER_SCHED_POLICY`.
+ */
+export function initializePrimary(cluster: any, policy: string | null) {
+  if (policy === "rr") {
+    schedulingPolicy = SCHED_RR;
+  } else if (policy === "none") {
+    schedulingPolicy = SCHED_NONE;
+  } else if (process.platform === "win32") {
+    // Round-robin doesn't perform well on Windows, where each connection is
+    // duplicated for the worker that handles it.
+    schedulingPolicy = SCHED_NONE;
+  } else {
+    schedulingPolicy = SCHED_RR;
+  }
+
+  cluster.isWorker = false;
+  cluster.isMaster = true; // Deprecated alias. Must be same as isPrimary.
+  cluster.isPrimary = true;
+  cluster.workers = {};
+  cluster.settings = {};
+  cluster.schedulingPolicy = schedulingPolicy;
+
+  cluster.setupPrimary = function (options?: any) {
+    const settings = {
+      args: process.argv.slice(2),
+      exec: process.argv[1],
+      execArgv: process.execArgv,
+      silent: false,
+      ...cluster.settings,
+      ...options,
+    };
+
+    cluster.settings = settings;
+
+    if (initialized === true) {
+      return process.nextTick(setupSettingsNT, settings);
+    }
+
+    initialized = true;
+    schedulingPolicy = cluster.schedulingPolicy; // Freeze policy.
+    assert(
+      schedulingPolicy === SCHED_NONE || schedulingPolicy === SCHED_RR,
+      `Bad cluster.schedulingPolicy: ${schedulingPolicy}`,
+    );
+
+    process.nextTick(setupSettingsNT, settings);
+  };
+
+  // Deprecated alias must be same as setupPrimary
+  cluster.setupMaster = cluster.setupPrimary;
+
+  function setupSettingsNT(settings: any) {
+    cluster.emit("setup", settings);
+  }
+
+  function createWorkerProcess(id: number, env: any) {
+    const workerEnv = { ...process.env, ...env, NODE_UNIQUE_ID: `${id}` };
+
+    return fork(cluster.settings.exec, cluster.settings.args, {
+      cwd: cluster.settings.cwd,
+      env: workerEnv,
+      serialization: cluster.settings.serialization,
+      silent: cluster.settings.silent,
+      windowsHide: cluster.settings.windowsHide,
+      execArgv: cluster.settings.execArgv,
+      stdio: cluster.settings.stdio,
+      gid: cluster.settings.gid,
+      uid: cluster.settings.uid,
+    } as any);
+  }
+
+  function removeWorker(worker: any) {
+    assert(worker);
+    delete cluster.workers[worker.id];
+
+    if (Object.keys(cluster.workers).length === 0) {
+      assert(handles.size === 0, "Resource leak detected.");
+      intercom.emit("disconnect");
+    }
+  }
+
+  function removeHandlesForWorker(worker: any) {
+    assert(worker);
+
+    handles.forEach((handle, key) => {
+      if (handle.remove(worker)) {
+        handles.delete(key);
+      }
+    });
+  }
+
+  cluster.fork = function (env?: any) {
+    cluster.setupPrimary();
+    const id = ++ids;
+    const workerProcess = createWorkerProcess(id, env);
+    const worker = new (Worker as any)({
+      id: id,
+      process: workerProcess,
+    });
+
+    worker.on("message", function (this: any, message: any, handle: any) {
+      cluster.emit("message", this, message, handle);
+    });
+
+    worker.process.once("exit", (exitCode: number, signalCode: string) => {
+      // Remove the worker from the workers list only if it has disconnected,
+      // otherwise we might still want to access it.
+      if (!worker.isConnected()) {
+        removeHandlesForWorker(worker);
+        removeWorker(worker);
+      }
+
+      worker.exitedAfterDisconnect = !!worker.exitedAfterDisconnect;
+      worker.state = "dead";
+      worker.emit("exit", exitCode, signalCode);
+      cluster.emit("exit", worker, exitCode, signalCode);
+    });
+
+    worker.process.once("disconnect", () => {
+      // Now is a good time to remove the handles associated with this worker
+      // because it is not connected to the primary anymore.
+      removeHandlesForWorker(worker);
+
+      // Remove the worker from the workers list only if its process has
+      // exited. Otherwise, we might still want to access it.
+      if (worker.isDead()) {
+        removeWorker(worker);
+      }
+
+      worker.exitedAfterDisconnect = !!worker.exitedAfterDisconnect;
+      worker.state = "disconnected";
+      worker.emit("disconnect");
+      cluster.emit("disconnect", worker);
+    });
+
+    worker.process.on("internalMessage", internal(worker, onmessage));
+    process.nextTick(emitForkNT, worker);
+    cluster.workers[worker.id] = worker;
+    return worker;
+  };
+
+  function emitForkNT(worker: any) {
+    cluster.emit("fork", worker);
+  }
+
+  cluster.disconnect = function (cb?: () => void) {
+    const workers = Object.keys(cluster.workers);
+
+    if (workers.length === 0) {
+      process.nextTick(() => intercom.emit("disconnect"));
+    } else {
+      for (const worker of Object.values(cluster.workers) as any[]) {
+        if (worker.isConnected()) {
+          worker.disconnect();
+        }
+      }
+    }
+
+    if (typeof cb === "function") {
+      intercom.once("disconnect", cb);
+    }
+  };
+
+  const methodMessageMapping: Record<string, (...args: any[]) => void> = {
+    close,
+    exitedAfterDisconnect,
+    listening,
+    online,
+    queryServer,
+  };
+
+  function onmessage(this: any, message: any, _handle: any) {
+    const fn = methodMessageMapping[message.act];
+
+    if (typeof fn === "function") {
+      fn(this, message);
+    }
+  }
+
+  function online(worker: any) {
+    worker.state = "online";
+    worker.emit("online");
+    cluster.emit("online", worker);
+  }
+
+  function exitedAfterDisconnect(worker: any, message: any) {
+    worker.exitedAfterDisconnect = true;
+    send(worker, { ack: message.seq });
+  }
+
+  function queryServer(worker: any, message: any) {
+    // Stop processing if worker already disconnecting
+    if (worker.exitedAfterDisconnect) {
+      return;
+    }
+
+    const key = `${message.address}:${message.port}:${message.addressType}:` +
+      `${message.fd}:${message.index}`;
+
+    // Only TCP listeners and UDP sockets can be shared with the workers.
+    if (message.addressType === -1 || message.fd >= 0) {
+      send(worker, {
+        errno: codeMap.get("ENOTSUP"),
+        key,
+        ack: message.seq,
+        data: null,
+      });
+      return;
+    }
+
+    let handle = handles.get(key);
+
+    if (handle === undefined) {
+      // HTTP servers always share the listener, as they accept the
+      // connections themselves, and UDP sockets have no connections to
+      // distribute.
+      if (
+        schedulingPolicy !== SCHED_RR || message.shared ||
+        message.addressType === "udp4" || message.addressType === "udp6"
+      ) {
+        handle = new SharedHandle(key, message.address, message);
+      } else {
+        handle = new RoundRobinHandle(key, message.address, message);
+      }
+
+      handles.set(key, handle);
+    }
+
+    if (!(handle as any).data) {
+      (handle as any).data = message.data;
+    }
+
+    // Set custom server data
+    handle.add(worker, (errno: number, reply: any, handle: any) => {
+      const { data } = handles.get(key) as any;
+
+      if (errno) {
+        handles.delete(key); // Gives other workers a chance to retry.
+      }
+
+      send(worker, {
+        errno,
+        key,
+        ack: message.seq,
+        data,
+        ...reply,
+      }, handle);
+    });
+  }
+
+  function listening(worker: any, message: any) {
+    const info = {
+      addressType: message.addressType,
+      address: message.address,
+      port: message.port,
+      fd: message.fd,
+    };
+
+    worker.state = "listening";
+    worker.emit("listening", info);
+    cluster.emit("listening", worker, info);
+  }
+
+  // Server in worker is closing, remove from list. The handle may have been
+  // removed by a prior call to removeHandlesForWorker() so guard against that.
+  function close(worker: any, message: any) {
+    const key = message.key;
+    const handle = handles.get(key);
+
+    if (handle && handle.remove(worker)) {
+      handles.delete(key);
+    }
+  }
+
+  function send(worker: any, message: any, handle?: any, cb?: any) {
+    return sendHelper(worker.process, message, handle, cb);
+  }
+
+  // Extend generic Worker with methods specific to the primary process.
+  Worker.prototype.disconnect = function (this: any) {
+    this.exitedAfterDisconnect = true;
+    send(this, { act: "disconnect" });
+    removeHandlesForWorker(this);
+    removeWorker(this);
+    return this;
+  };
+
+  Worker.prototype.destroy = function (this: any, signo?: string) {
+    const proc = this.process;
+    const signal = signo || "SIGTERM";
+
+    if (this.isConnected()) {
+      this.once("disconnect", () => proc.kill(signal));
+      this.disconnect();
+      return;
+    }
+
+    proc.kill(signal);
+  };
+}
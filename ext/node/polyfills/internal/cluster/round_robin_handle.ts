@@ -0,0 +1,144 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+// Copyright Joyent and Node contributors. All rights reserved. MIT license.
+
+// TODO(petamoriken): enable prefer-primordials for node polyfills
+// deno-lint-ignore-file prefer-primordials no-explicit-any
+
+import { assert } from "ext:deno_node/_util/asserts.ts";
+import { constants as TCPConstants } from "ext:deno_node/internal_binding/tcp_wrap.ts";
+import { sendHelper } from "ext:deno_node/internal/cluster/utils.ts";
+import net from "node:net";
+
+/**
+ * Listens in the primary, and hands off the accepted connections to the
+ * workers one at a time, in the order that they become free.
+ */
+export class RoundRobinHandle {
+  key: string;
+  all = new Map<number, any>();
+  free = new Map<number, any>();
+  handles: any[] = [];
+  handle: any = null;
+  server: any;
+
+  constructor(
+    key: string,
+    address: string,
+    { port, flags, backlog }: any,
+  ) {
+    this.key = key;
+    this.server = net.createServer(() => {
+      assert(false, "connections are handled by the round-robin handle");
+    });
+
+    this.server.listen({
+      port,
+      host: address,
+      // Currently, net module only supports `ipv6Only` option in `flags`.
+      ipv6Only: Boolean(flags & TCPConstants.UV_TCP_IPV6ONLY),
+      backlog,
+    });
+
+    this.server.once("listening", () => {
+      this.handle = this.server._handle;
+      this.handle.onconnection = (err: number, handle: any) =>
+        this.distribute(err, handle);
+      this.server._handle = null;
+      this.server = null;
+    });
+  }
+
+  add(worker: any, send: (...args: any[]) => void) {
+    assert(this.all.has(worker.id) === false);
+    this.all.set(worker.id, worker);
+
+    const done = () => {
+      const out = {};
+      this.handle.getsockname(out);
+      send(null, { sockname: out }, null);
+
+      this.handoff(worker); // In case there are connections pending.
+    };
+
+    if (this.server === null) {
+      return done();
+    }
+
+    // Still busy binding.
+    this.server.once("listening", done);
+    this.server.once("error", (err: any) => {
+      send(err.errno, null);
+    });
+  }
+
+  remove(worker: any) {
+    const existed = this.all.delete(worker.id);
+
+    if (!existed) {
+      return false;
+    }
+
+    this.free.delete(worker.id);
+
+    if (this.all.size !== 0) {
+      return false;
+    }
+
+    for (const handle of this.handles) {
+      handle.close();
+    }
+    this.handles = [];
+
+    // The server may still be busy binding.
+    if (this.handle) {
+      this.handle.close();
+      this.handle = null;
+    } else {
+      this.server.close();
+    }
+    return true;
+  }
+
+  distribute(err: number, handle: any) {
+    // If `accept` fails just skip it (handle is undefined)
+    if (err) {
+      return;
+    }
+    this.handles.push(handle);
+    const { value: workerEntry } = this.free.entries().next();
+
+    if (workerEntry !== undefined) {
+      const { 0: workerId, 1: worker } = workerEntry;
+      this.free.delete(workerId);
+      this.handoff(worker);
+    }
+  }
+
+  handoff(worker: any) {
+    if (!this.all.has(worker.id)) {
+      return; // Worker is closing (or has closed) the server.
+    }
+
+    const handle = this.handles.shift();
+
+    if (handle === undefined) {
+      this.free.set(worker.id, worker); // Add to ready queue again.
+      return;
+    }
+
+    const message = { act: "newconn", key: this.key };
+
+    sendHelper(worker.process, message, handle, (reply: any) => {
+      if (reply.accepted) {
+        handle.close();
+      } else {
+        // Worker is shutting down. Send to another.
+        this.distribute(0, handle);
+      }
+
+      this.handoff(worker);
+    });
+  }
+}
+
+export default RoundRobinHandle;
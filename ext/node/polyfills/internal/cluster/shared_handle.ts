@@ -0,0 +1,79 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+// Copyright Joyent and Node contributors. All rights reserved. MIT license.
+
+// TODO(petamoriken): enable prefer-primordials for node polyfills
+// deno-lint-ignore-file prefer-primordials no-explicit-any
+
+import { assert } from "ext:deno_node/_util/asserts.ts";
+import net from "node:net";
+import { _createSocketHandle } from "ext:deno_node/internal/dgram.ts";
+
+/**
+ * Creates the listener in the primary and sends it to the workers, which all
+ * accept connections on it, leaving the distribution to the operating
+ * system. UDP sockets are shared the same way.
+ */
+export class SharedHandle {
+  key: string;
+  workers = new Map<number, any>();
+  handle: any = null;
+  errno = 0;
+
+  constructor(
+    key: string,
+    address: string,
+    { port, addressType, fd, flags }: any,
+  ) {
+    this.key = key;
+
+    const isUdp = addressType === "udp4" || addressType === "udp6";
+    const rval = isUdp
+      ? _createSocketHandle(address, port, addressType, fd, flags)
+      : net._createServerHandle(address, port, addressType, fd, flags);
+
+    if (typeof rval === "number") {
+      this.errno = rval;
+      return;
+    }
+
+    // UDP sockets are bound right away.
+    if (isUdp) {
+      this.handle = rval;
+      return;
+    }
+
+    // The listener is created up front, as binding doesn't create a socket
+    // that could be shared.
+    const listener = rval.getListener();
+    if (typeof listener === "number") {
+      this.errno = listener;
+      rval.close();
+    } else {
+      this.handle = rval;
+    }
+  }
+
+  add(worker: any, send: (...args: any[]) => void) {
+    assert(!this.workers.has(worker.id));
+    this.workers.set(worker.id, worker);
+    send(this.errno, null, this.handle);
+  }
+
+  remove(worker: any) {
+    if (!this.workers.has(worker.id)) {
+      return false;
+    }
+
+    this.workers.delete(worker.id);
+
+    if (this.workers.size !== 0) {
+      return false;
+    }
+
+    this.handle?.close();
+    this.handle = null;
+    return true;
+  }
+}
+
+export default SharedHandle;
@@ -0,0 +1,57 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+// Copyright Joyent and Node contributors. All rights reserved. MIT license.
+
+// TODO(petamoriken): enable prefer-primordials for node polyfills
+// deno-lint-ignore-file prefer-primordials no-explicit-any
+
+const callbacks = new Map<number, (...args: any[]) => void>();
+let seq = 0;
+
+/** Sends an internal `NODE_CLUSTER` message, whose reply is passed to `cb`. */
+export function sendHelper(
+  proc: any,
+  message: Record<string, unknown>,
+  handle?: any,
+  cb?: (...args: any[]) => void,
+) {
+  if (!proc.connected) {
+    return false;
+  }
+
+  // Mark message as internal, see `isInternal()` in
+  // ext:deno_node/internal/child_process.ts
+  message = { cmd: "NODE_CLUSTER", ...message, seq };
+
+  if (typeof cb === "function") {
+    callbacks.set(seq, cb);
+  }
+
+  seq += 1;
+  return proc.send(message, handle);
+}
+
+/**
+ * Returns an `internalMessage` listener that hands off messages to `cb`, but
+ * intercepts the replies to `sendHelper()` and redirects them to their
+ * callbacks.
+ */
+export function internal(worker: any, cb: (...args: any[]) => void) {
+  return function onInternalMessage(message: any, handle?: any) {
+    if (message.cmd !== "NODE_CLUSTER") {
+      return;
+    }
+
+    let fn = cb;
+
+    if (message.ack !== undefined) {
+      const callback = callbacks.get(message.ack);
+
+      if (callback !== undefined) {
+        fn = callback;
+        callbacks.delete(message.ack);
+      }
+    }
+
+    fn.call(worker, message, handle);
+  };
+}
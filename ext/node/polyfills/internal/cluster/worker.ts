@@ -0,0 +1,67 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+// Copyright Joyent and Node contributors. All rights reserved. MIT license.
+
+// TODO(petamoriken): enable prefer-primordials for node polyfills
+// deno-lint-ignore-file prefer-primordials no-explicit-any
+
+import { EventEmitter } from "node:events";
+import { kEmptyObject } from "ext:deno_node/internal/util.mjs";
+
+/**
+ * A Worker object contains all public information and method about a worker.
+ * In the primary it can be obtained using `cluster.workers`. In a worker it
+ * can be obtained using `cluster.worker`.
+ *
+ * `disconnect()` and `destroy()` differ between the primary and the workers,
+ * and are added when the cluster is initialized.
+ */
+export function Worker(this: any, options?: any) {
+  if (!(this instanceof Worker)) {
+    return new (Worker as any)(options);
+  }
+
+  EventEmitter.call(this);
+
+  if (options === null || typeof options !== "object") {
+    options = kEmptyObject;
+  }
+
+  this.exitedAfterDisconnect = undefined;
+
+  this.state = options.state || "none";
+  this.id = options.id | 0;
+
+  if (options.process) {
+    this.process = options.process;
+    this.process.on(
+      "error",
+      (code: unknown, signal: unknown) => this.emit("error", code, signal),
+    );
+    this.process.on(
+      "message",
+      (message: unknown, handle: unknown) =>
+        this.emit("message", message, handle),
+    );
+  }
+}
+
+Object.setPrototypeOf(Worker.prototype, EventEmitter.prototype);
+Object.setPrototypeOf(Worker, EventEmitter);
+
+Worker.prototype.kill = function (this: any, ...args: unknown[]) {
+  this.destroy(...args);
+};
+
+Worker.prototype.send = function (this: any, ...args: unknown[]) {
+  return this.process.send(...args);
+};
+
+Worker.prototype.isDead = function (this: any) {
+  return this.process.exitCode != null || this.process.signalCode != null;
+};
+
+Worker.prototype.isConnected = function (this: any) {
+  return this.process.connected;
+};
+
+export default Worker;
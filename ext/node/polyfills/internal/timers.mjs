@@ -4,7 +4,8 @@
 // TODO(petamoriken): enable prefer-primordials for node polyfills
 // deno-lint-ignore-file prefer-primordials
 
-import { primordials } from "ext:core/mod.js";
+import { core, primordials } from "ext:core/mod.js";
+const { getAsyncContext, setAsyncContext } = core;
 const {
   MapPrototypeDelete,
   MapPrototypeSet,
@@ -31,6 +32,7 @@ export const TIMEOUT_MAX = 2 ** 31 - 1;
 export const kTimerId = Symbol("timerId");
 export const kTimeout = Symbol("timeout");
 const kRefed = Symbol("refed");
+const kAsyncContext = Symbol("asyncContext");
 const createTimer = Symbol("createTimer");
 
 /**
@@ -52,6 +54,9 @@ export function Timeout(callback, after, args, isRepeat, isRefed) {
   this._isRepeat = isRepeat;
   this._destroyed = false;
   this[kRefed] = isRefed;
+  // The callback runs in the async context the timer was created in, even
+  // after the timer has been refreshed from a different one.
+  this[kAsyncContext] = getAsyncContext();
   this[kTimerId] = this[createTimer]();
 }
 
@@ -63,9 +68,16 @@ Timeout.prototype[createTimer] = function () {
     }
     return callback.bind(this)(...args);
   };
-  const id = this._isRepeat
-    ? setInterval_(cb, this._idleTimeout, ...this._timerArgs)
-    : setTimeout_(cb, this._idleTimeout, ...this._timerArgs);
+  const oldContext = getAsyncContext();
+  let id;
+  try {
+    setAsyncContext(this[kAsyncContext]);
+    id = this._isRepeat
+      ? setInterval_(cb, this._idleTimeout, ...this._timerArgs)
+      : setTimeout_(cb, this._idleTimeout, ...this._timerArgs);
+  } finally {
+    setAsyncContext(oldContext);
+  }
   if (!this[kRefed]) {
     Deno.unrefTimer(id);
   }
@@ -9,6 +9,7 @@ import * as caresWrap from "ext:deno_node/internal_binding/cares_wrap.ts";
 import * as constants from "ext:deno_node/internal_binding/constants.ts";
 import * as crypto from "ext:deno_node/internal_binding/crypto.ts";
 import * as pipeWrap from "ext:deno_node/internal_binding/pipe_wrap.ts";
+import * as spawnSync from "ext:deno_node/internal_binding/spawn_sync.ts";
 import * as streamWrap from "ext:deno_node/internal_binding/stream_wrap.ts";
 import * as stringDecoder from "ext:deno_node/internal_binding/string_decoder.ts";
 import * as symbols from "ext:deno_node/internal_binding/symbols.ts";
@@ -48,7 +49,7 @@ const modules = {
   report: {},
   serdes: {},
   "signal_wrap": {},
-  "spawn_sync": {},
+  "spawn_sync": spawnSync,
   "stream_wrap": streamWrap,
   "string_decoder": stringDecoder,
   symbols,
@@ -0,0 +1,151 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+// Copyright Joyent, Inc. and other Node contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit
+// persons to whom the Software is furnished to do so, subject to the
+// following conditions:
+//
+// The above copyright notice and this permission notice shall be included
+// in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN
+// NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+// OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE
+// USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// This module ports:
+// - https://github.com/nodejs/node/blob/master/src/spawn_sync.cc
+// - https://github.com/nodejs/node/blob/master/src/spawn_sync.h
+
+// TODO(petamoriken): enable prefer-primordials for node polyfills
+// deno-lint-ignore-file prefer-primordials
+
+import { internals } from "ext:core/mod.js";
+import { Buffer } from "node:buffer";
+import { codeMap } from "ext:deno_node/internal_binding/uv.ts";
+
+export interface SpawnSyncStdio {
+  type: "pipe" | "ignore" | "inherit" | "fd";
+  // The fd to inherit, or a rid returned by the fs APIs.
+  fd?: number;
+  // Written to the child's stdin, if this is a pipe.
+  input?: Uint8Array;
+}
+
+export interface SpawnSyncOptions {
+  file: string;
+  // The arguments, starting with `argv0`.
+  args: string[];
+  cwd?: string;
+  envPairs: string[];
+  stdio: SpawnSyncStdio[];
+  uid?: number;
+  gid?: number;
+  maxBuffer?: number;
+  windowsVerbatimArguments?: boolean;
+}
+
+export interface SpawnSyncResult {
+  error?: number;
+  status: number | null;
+  signal: string | null;
+  output: [null, Buffer | null, Buffer | null] | null;
+  pid: number;
+}
+
+function toDenoStdio(
+  stdio: SpawnSyncStdio | undefined,
+  fd: number,
+): "piped" | "inherit" | "null" | number {
+  switch (stdio?.type) {
+    case "pipe":
+      // A piped stdin without an input is closed right away.
+      return fd === 0 ? "null" : "piped";
+    case "inherit":
+      return "inherit";
+    case "fd":
+      return stdio.fd === fd ? "inherit" : stdio.fd!;
+    default:
+      return "null";
+  }
+}
+
+function toErrno(err: Error & { code?: string }): number {
+  if (err instanceof Deno.errors.NotFound) {
+    return codeMap.get("ENOENT")!;
+  }
+  const errno = err.code === undefined ? undefined : codeMap.get(err.code);
+  if (errno === undefined) {
+    throw err;
+  }
+  return errno;
+}
+
+function toEnv(envPairs: string[]): Record<string, string> {
+  const env: Record<string, string> = {};
+  for (const pair of envPairs) {
+    // Keys can start with "=" on Windows, e.g. `=C:=C:\`.
+    const index = pair.indexOf("=", 1);
+    if (index !== -1) {
+      env[pair.slice(0, index)] = pair.slice(index + 1);
+    }
+  }
+  return env;
+}
+
+/**
+ * Runs `options.file` to completion, returning its exit status and buffered
+ * output in the same shape as Node. Spawn failures and exceeding
+ * `maxBuffer` are reported through `error`, as a negative errno.
+ */
+export function spawn(options: SpawnSyncOptions): SpawnSyncResult {
+  const { 0: stdin, 1: stdout, 2: stderr } = options.stdio;
+  const input = stdin?.type === "pipe" ? stdin.input : undefined;
+
+  let output: Deno.CommandOutput;
+  try {
+    output = new Deno.Command(options.file, {
+      args: options.args.slice(1),
+      argv0: options.args[0],
+      cwd: options.cwd,
+      env: toEnv(options.envPairs),
+      stdin: input != null ? { bytes: input } : toDenoStdio(stdin, 0),
+      stdout: toDenoStdio(stdout, 1),
+      stderr: toDenoStdio(stderr, 2),
+      uid: options.uid,
+      gid: options.gid,
+      windowsRawArguments: options.windowsVerbatimArguments,
+    }).outputSync();
+  } catch (err) {
+    return {
+      error: toErrno(err as Error),
+      status: null,
+      signal: null,
+      output: null,
+      pid: 0,
+    };
+  }
+
+  const result: SpawnSyncResult = {
+    status: output.signal ? null : output.code,
+    signal: output.signal,
+    output: [
+      null,
+      stdout?.type === "pipe" ? Buffer.from(output.stdout) : null,
+      stderr?.type === "pipe" ? Buffer.from(output.stderr) : null,
+    ],
+    pid: internals.getOutputPid(output),
+  };
+  const maxBuffer = options.maxBuffer ?? Infinity;
+  if (result.output!.some((buffer) => buffer && buffer.length > maxBuffer)) {
+    result.error = codeMap.get("ENOBUFS");
+  }
+  return result;
+}
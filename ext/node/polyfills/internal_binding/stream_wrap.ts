@@ -32,7 +32,12 @@
 
 import { core } from "ext:core/mod.js";
 const { internalRidSymbol } = core;
-import { op_can_write_vectored, op_raw_write_vectored } from "ext:core/ops";
+import {
+  op_bi_pipe_can_write_vectored,
+  op_bi_pipe_write_vectored,
+  op_can_write_vectored,
+  op_raw_write_vectored,
+} from "ext:core/ops";
 
 import { TextEncoder } from "ext:deno_web/08_text_encoding.js";
 import { Buffer } from "node:buffer";
@@ -255,6 +260,37 @@ export class LibuvStreamWrap extends HandleWrap {
       }
     }
 
+    if (
+      this.provider === providerType.PIPEWRAP && rid !== undefined &&
+      op_bi_pipe_can_write_vectored(rid)
+    ) {
+      // Bidirectional pipes, e.g. the extra stdio of a child process, write
+      // all chunks at once, and wait for the pipe to drain first.
+      op_bi_pipe_write_vectored(rid, buffers).then((nwritten) => {
+        streamBaseState[kBytesWritten] = nwritten;
+        this.bytesWritten += nwritten;
+
+        try {
+          req.oncomplete(0);
+        } catch {
+          // swallow callback errors.
+        }
+      }, (e) => {
+        const status = e instanceof Deno.errors.BadResource ||
+            e instanceof Deno.errors.BrokenPipe
+          ? codeMap.get("EBADF")!
+          : codeMap.get("UNKNOWN")!;
+
+        try {
+          req.oncomplete(status);
+        } catch {
+          // swallow callback errors.
+        }
+      });
+
+      return 0;
+    }
+
     return this.writeBuffer(req, Buffer.concat(buffers));
   }
 
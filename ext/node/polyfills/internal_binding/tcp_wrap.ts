@@ -200,37 +200,48 @@ export class TCP extends ConnectionWrap {
   listen(backlog: number): number {
     this.#backlog = ceilPowOf2(backlog + 1);
 
-    const listenOptions = {
-      hostname: this.#address!,
-      port: this.#port!,
-      transport: "tcp" as const,
-    };
+    if (!this.#listener) {
+      const err = this.#createListener();
 
-    let listener;
-
-    try {
-      listener = Deno.listen(listenOptions);
-    } catch (e) {
-      if (e instanceof Deno.errors.AddrInUse) {
-        return codeMap.get("EADDRINUSE")!;
-      } else if (e instanceof Deno.errors.AddrNotAvailable) {
-        return codeMap.get("EADDRNOTAVAIL")!;
-      } else if (e instanceof Deno.errors.PermissionDenied) {
-        throw e;
+      if (err) {
+        return err;
       }
+    }
 
-      // TODO(cmorten): map errors to appropriate error codes.
-      return codeMap.get("UNKNOWN")!;
+    this.#accept();
+
+    return 0;
+  }
+
+  /**
+   * Returns the listener of a server handle without accepting connections on
+   * it, creating it if the handle is only bound. Used to share the listener
+   * with other processes, e.g. the workers of a cluster.
+   * @return The listener, or an error status code.
+   */
+  getListener(): Deno.Listener | number {
+    if (!this.#listener) {
+      const err = this.#createListener();
+
+      if (err) {
+        return err;
+      }
     }
 
+    return this.#listener;
+  }
+
+  /**
+   * Uses a listener that was created elsewhere, e.g. one received from the
+   * primary of a cluster, rather than creating one in `listen()`.
+   * @param listener The listener to accept connections on.
+   */
+  setListener(listener: Deno.Listener) {
     const address = listener.addr as Deno.NetAddr;
     this.#address = address.hostname;
     this.#port = address.port;
 
     this.#listener = listener;
-    this.#accept();
-
-    return 0;
   }
 
   override ref() {
@@ -398,6 +409,43 @@ export class TCP extends ConnectionWrap {
     return 0;
   }
 
+  /**
+   * Creates the listener for the bound address.
+   * @return An error status code.
+   */
+  #createListener(): number {
+    const listenOptions = {
+      hostname: this.#address!,
+      port: this.#port!,
+      transport: "tcp" as const,
+    };
+
+    let listener;
+
+    try {
+      listener = Deno.listen(listenOptions);
+    } catch (e) {
+      if (e instanceof Deno.errors.AddrInUse) {
+        return codeMap.get("EADDRINUSE")!;
+      } else if (e instanceof Deno.errors.AddrNotAvailable) {
+        return codeMap.get("EADDRNOTAVAIL")!;
+      } else if (e instanceof Deno.errors.PermissionDenied) {
+        throw e;
+      }
+
+      // TODO(cmorten): map errors to appropriate error codes.
+      return codeMap.get("UNKNOWN")!;
+    }
+
+    const address = listener.addr as Deno.NetAddr;
+    this.#address = address.hostname;
+    this.#port = address.port;
+
+    this.#listener = listener;
+
+    return 0;
+  }
+
   /** Handle backoff delays following an unsuccessful accept. */
   async #acceptBackoff() {
     // Backoff after transient errors to allow time for the system to
@@ -23,7 +23,11 @@
 // TODO(petamoriken): enable prefer-primordials for node polyfills
 // deno-lint-ignore-file prefer-primordials
 
+import { core } from "ext:core/mod.js";
 import {
+  op_net_set_membership_udp,
+  op_net_set_multicast_interface_udp,
+  op_net_set_source_membership_udp,
   op_node_unstable_net_listen_udp,
   op_node_unstable_net_listen_unixpacket,
 } from "ext:core/ops";
@@ -36,6 +40,7 @@ import { GetAddrInfoReqWrap } from "ext:deno_node/internal_binding/cares_wrap.ts
 import { HandleWrap } from "ext:deno_node/internal_binding/handle_wrap.ts";
 import { ownerSymbol } from "ext:deno_node/internal_binding/symbols.ts";
 import { codeMap, errorMap } from "ext:deno_node/internal_binding/uv.ts";
+import { os } from "ext:deno_node/internal_binding/constants.ts";
 import { notImplemented } from "ext:deno_node/_utils.ts";
 import { Buffer } from "node:buffer";
 import type { ErrnoException } from "ext:deno_node/internal/errors.ts";
@@ -53,6 +58,8 @@ type MessageType = string | Uint8Array | Buffer | DataView;
 const AF_INET = 2;
 const AF_INET6 = 10;
 
+const { UV_UDP_REUSEADDR } = os;
+
 const UDP_DGRAM_MAXSIZE = 64 * 1024;
 
 export class SendWrap extends AsyncWrap {
@@ -111,16 +118,36 @@ export class UDP extends HandleWrap {
     super(providerType.UDPWRAP);
   }
 
-  addMembership(_multicastAddress: string, _interfaceAddress?: string): number {
-    notImplemented("udp.UDP.prototype.addMembership");
+  /**
+   * Joins a multicast group, binding the handle to a random port first if it
+   * isn't bound yet.
+   * @param multicastAddress The address of the group.
+   * @param interfaceAddress The address of the interface to join it on.
+   * @return An error status code.
+   */
+  addMembership(multicastAddress: string, interfaceAddress?: string): number {
+    return this.#setMembership(multicastAddress, interfaceAddress, true);
   }
 
+  /**
+   * Joins a source-specific multicast channel, binding the handle to a random
+   * port first if it isn't bound yet.
+   * @param sourceAddress The address of the source.
+   * @param groupAddress The address of the group.
+   * @param interfaceAddress The address of the interface to join it on.
+   * @return An error status code.
+   */
   addSourceSpecificMembership(
-    _sourceAddress: string,
-    _groupAddress: string,
-    _interfaceAddress?: string,
+    sourceAddress: string,
+    groupAddress: string,
+    interfaceAddress?: string,
   ): number {
-    notImplemented("udp.UDP.prototype.addSourceSpecificMembership");
+    return this.#setSourceMembership(
+      sourceAddress,
+      groupAddress,
+      interfaceAddress,
+      true,
+    );
   }
 
   /**
@@ -195,18 +222,47 @@ export class UDP extends HandleWrap {
   }
 
   dropMembership(
-    _multicastAddress: string,
-    _interfaceAddress?: string,
+    multicastAddress: string,
+    interfaceAddress?: string,
   ): number {
-    notImplemented("udp.UDP.prototype.dropMembership");
+    return this.#setMembership(multicastAddress, interfaceAddress, false);
   }
 
   dropSourceSpecificMembership(
-    _sourceAddress: string,
-    _groupAddress: string,
-    _interfaceAddress?: string,
+    sourceAddress: string,
+    groupAddress: string,
+    interfaceAddress?: string,
   ): number {
-    notImplemented("udp.UDP.prototype.dropSourceSpecificMembership");
+    return this.#setSourceMembership(
+      sourceAddress,
+      groupAddress,
+      interfaceAddress,
+      false,
+    );
+  }
+
+  /**
+   * Returns the socket of a bound handle, e.g. to share it with the workers
+   * of a cluster.
+   */
+  getListener(): Deno.DatagramConn | undefined {
+    return this.#listener;
+  }
+
+  /**
+   * Uses a socket that was bound elsewhere, e.g. one received from the
+   * primary of a cluster, rather than binding one in `bind()`.
+   * @param listener The socket to send and receive on.
+   */
+  setListener(listener: Deno.DatagramConn) {
+    const address = listener.addr as Deno.NetAddr;
+    this.#address = address.hostname;
+    this.#port = address.port;
+    this.#family = isIP(address.hostname) === 6
+      ? ("IPv6" as const)
+      : ("IPv4" as const);
+
+    this.#listener = listener;
   }
 
   /**
@@ -299,24 +355,51 @@ export class UDP extends HandleWrap {
     return this.#doSend(req, bufs, count, args, AF_INET6);
   }
 
-  setBroadcast(_bool: 0 | 1): number {
-    notImplemented("udp.UDP.prototype.setBroadcast");
+  setBroadcast(bool: 0 | 1): number {
+    return this.#setSocketOption("broadcast", bool);
   }
 
-  setMulticastInterface(_interfaceAddress: string): number {
-    notImplemented("udp.UDP.prototype.setMulticastInterface");
+  /**
+   * Sets the interface that multicast messages are sent from.
+   * @param interfaceAddress The address of the interface, which for IPv6 is
+   * the scope of an address, e.g. `::%eth1`.
+   * @return An error status code.
+   */
+  setMulticastInterface(interfaceAddress: string): number {
+    if (!this.#listener) {
+      return codeMap.get("EBADF")!;
+    }
+
+    try {
+      op_net_set_multicast_interface_udp(
+        this.#listener[core.internalRidSymbol],
+        interfaceAddress,
+      );
+    } catch (e) {
+      return errorCode(e);
+    }
+
+    return 0;
   }
 
-  setMulticastLoopback(_bool: 0 | 1): number {
-    notImplemented("udp.UDP.prototype.setMulticastLoopback");
+  setMulticastLoopback(bool: 0 | 1): number {
+    return this.#setSocketOption("multicastLoopback", bool);
   }
 
-  setMulticastTTL(_ttl: number): number {
-    notImplemented("udp.UDP.prototype.setMulticastTTL");
+  setMulticastTTL(ttl: number): number {
+    if (ttl < 0 || ttl > 255) {
+      return codeMap.get("EINVAL")!;
+    }
+
+    return this.#setSocketOption("multicastTtl", ttl);
   }
 
-  setTTL(_ttl: number): number {
-    notImplemented("udp.UDP.prototype.setTTL");
+  setTTL(ttl: number): number {
+    if (ttl < 1 || ttl > 255) {
+      return codeMap.get("EINVAL")!;
+    }
+
+    return this.#setSocketOption("ttl", ttl);
   }
 
   override unref() {
@@ -324,12 +407,20 @@ export class UDP extends HandleWrap {
     this.#unrefed = true;
   }
 
-  #doBind(ip: string, port: number, _flags: number, family: number): number {
-    // TODO(cmorten): use flags to inform socket reuse etc.
+  #doBind(ip: string, port: number, flags: number, family: number): number {
+    if (this.#listener) {
+      return codeMap.get("EINVAL")!;
+    }
+
+    // Multicast messages are looped back, and broadcasts can't be sent
+    // without `setBroadcast()`, like with the defaults of the system.
     const listenOptions = {
       port,
       hostname: ip,
       transport: "udp" as const,
+      reuseAddress: (flags & UV_UDP_REUSEADDR) !== 0,
+      loopback: true,
+      broadcast: false,
     };
 
     let listener;
@@ -358,6 +449,89 @@ export class UDP extends HandleWrap {
     return 0;
   }
 
+  #setMembership(
+    multicastAddress: string,
+    interfaceAddress: string | undefined,
+    join: boolean,
+  ): number {
+    const err = this.#bindForMembership(multicastAddress);
+
+    if (err) {
+      return err;
+    }
+
+    try {
+      op_net_set_membership_udp(
+        this.#listener![core.internalRidSymbol],
+        multicastAddress,
+        interfaceAddress ?? null,
+        join,
+      );
+    } catch (e) {
+      return errorCode(e);
+    }
+
+    return 0;
+  }
+
+  #setSourceMembership(
+    sourceAddress: string,
+    groupAddress: string,
+    interfaceAddress: string | undefined,
+    join: boolean,
+  ): number {
+    const err = this.#bindForMembership(groupAddress);
+
+    if (err) {
+      return err;
+    }
+
+    try {
+      op_net_set_source_membership_udp(
+        this.#listener![core.internalRidSymbol],
+        sourceAddress,
+        groupAddress,
+        interfaceAddress ?? null,
+        join,
+      );
+    } catch (e) {
+      return errorCode(e);
+    }
+
+    return 0;
+  }
+
+  /** Binds an unbound handle to a random port, like libuv does before
+   * setting the memberships of a socket. */
+  #bindForMembership(address: string): number {
+    if (this.#listener) {
+      return 0;
+    }
+
+    switch (isIP(address)) {
+      case 4:
+        return this.#doBind("0.0.0.0", 0, UV_UDP_REUSEADDR, AF_INET);
+      case 6:
+        return this.#doBind("::", 0, UV_UDP_REUSEADDR, AF_INET6);
+      default:
+        return codeMap.get("EINVAL")!;
+    }
+  }
+
+  #setSocketOption(name: keyof Deno.SocketOptions, value: number): number {
+    if (!this.#listener) {
+      return codeMap.get("EBADF")!;
+    }
+
+    try {
+      this.#listener.setSocketOption(name, value);
+    } catch (e) {
+      return errorCode(e);
+    }
+
+    return 0;
+  }
+
   #doConnect(ip: string, port: number, family: number): number {
     this.#remoteAddress = ip;
     this.#remotePort = port;
@@ -515,3 +689,10 @@ export class UDP extends HandleWrap {
     return 0;
   }
 }
+
+/** Returns the status code of an error that an op threw. */
+function errorCode(e: unknown): number {
+  const code = (e as { code?: string })?.code;
+
+  return (code && codeMap.get(code)) ?? codeMap.get("UNKNOWN")!;
+}
@@ -107,6 +107,7 @@ import type { DuplexOptions } from "ext:deno_node/_stream.d.ts";
 import type { BufferEncoding } from "ext:deno_node/_global.d.ts";
 import type { Abortable } from "ext:deno_node/_events.d.ts";
 import { channel } from "node:diagnostics_channel";
+import cluster from "node:cluster";
 
 let debug = debuglog("net", (fn) => {
   debug = fn;
@@ -1656,16 +1657,8 @@ function _listenInCluster(
 ) {
   exclusive = !!exclusive;
 
-  // TODO(cmorten): here we deviate somewhat from the Node implementation which
-  // makes use of the https://nodejs.org/api/cluster.html module to run servers
-  // across a "cluster" of Node processes to take advantage of multi-core
-  // systems.
-  //
-  // Though Deno has has a Worker capability from which we could simulate this,
-  // for now we assert that we are _always_ on the primary process.
-  const isPrimary = true;
-
-  if (isPrimary || exclusive) {
+  // deno-lint-ignore no-explicit-any
+  if ((cluster as any).isPrimary || exclusive) {
     // Will create a new handle
     // _listen2 sets up the listened handle, it is still named like this
     // to avoid breaking code that wraps this method
@@ -1673,6 +1666,34 @@ function _listenInCluster(
 
     return;
   }
+
+  const serverQuery = {
+    address,
+    port,
+    addressType,
+    fd,
+    flags,
+    backlog,
+  };
+
+  // Get the primary's server handle, and listen on it
+  // deno-lint-ignore no-explicit-any
+  (cluster as any)._getServer(server, serverQuery, listenOnPrimaryHandle);
+
+  function listenOnPrimaryHandle(err: number, handle: TCP) {
+    err = _checkBindError(err, port!, handle);
+
+    if (err) {
+      const ex = exceptionWithHostPort(err, "bind", address!, port!);
+
+      return server.emit("error", ex);
+    }
+
+    server._handle = handle;
+    // _listen2 sets up the listened handle, it is still named like this
+    // to avoid breaking code that wraps this method
+    server._listen2(address, port, addressType, backlog, fd, flags);
+  }
 }
 
 function _lookupAndListen(
@@ -7,7 +7,9 @@ import {
   op_host_post_message,
   op_host_recv_ctrl,
   op_host_recv_message,
+  op_host_take_worker_stdio,
   op_host_terminate_worker,
+  op_host_worker_heap_snapshot,
   op_message_port_recv_message_sync,
   op_worker_threads_filename,
 } from "ext:core/ops";
@@ -28,20 +30,30 @@ import { notImplemented } from "ext:deno_node/_utils.ts";
 import { EventEmitter } from "node:events";
 import { BroadcastChannel } from "ext:deno_broadcast_channel/01_broadcast_channel.js";
 import process from "node:process";
+import { Buffer } from "node:buffer";
+import { Readable } from "node:stream";
+import { ERR_WORKER_NOT_RUNNING } from "ext:deno_node/internal/errors.ts";
 
 const { JSONParse, JSONStringify, ObjectPrototypeIsPrototypeOf } = primordials;
 const {
   Error,
+  MathCeil,
+  MathMax,
+  ObjectEntries,
   ObjectHasOwn,
   PromiseResolve,
+  SafeArrayIterator,
   SafeSet,
+  SafeSetIterator,
   Symbol,
   SymbolFor,
   SymbolIterator,
   StringPrototypeTrim,
   SafeWeakMap,
   SafeMap,
+  TypedArrayPrototypeGetBuffer,
   TypeError,
+  Uint8Array,
 } = primordials;
 
 const debugWorkerThreads = false;
@@ -91,6 +103,7 @@ class NodeWorker extends EventEmitter {
   #refCount = 1;
   #messagePromise = undefined;
   #controlPromise = undefined;
+  #stdioPromises = new SafeSet();
   #workerOnline = false;
   // "RUNNING" | "CLOSED" | "TERMINATED"
   // "TERMINATED" means that any controls or messages received will be
@@ -110,6 +123,12 @@ class NodeWorker extends EventEmitter {
     codeRangeSizeMb: -1,
     stackSizeMb: 4,
   };
+  // https://nodejs.org/api/worker_threads.html#workerstdout
+  // Only receives data with the `stdout` option, the output of the worker is
+  // written to the stdout of the process otherwise.
+  stdout = new Readable({ read() {} });
+  // https://nodejs.org/api/worker_threads.html#workerstderr
+  stderr = new Readable({ read() {} });
 
   constructor(specifier: URL | string, options?: WorkerOptions) {
     super();
@@ -148,11 +167,40 @@ class NodeWorker extends EventEmitter {
     if (options?.env) {
       env_ = JSONParse(JSONStringify(options?.env));
     }
+
+    let limits = null;
+    if (options?.resourceLimits) {
+      const entries = ObjectEntries(options.resourceLimits);
+      for (const { 0: key, 1: value } of new SafeArrayIterator(entries)) {
+        if (
+          typeof value === "number" && ObjectHasOwn(this.resourceLimits, key)
+        ) {
+          (this.resourceLimits as Record<string, number>)[key] = value;
+        }
+      }
+      const {
+        maxYoungGenerationSizeMb,
+        maxOldGenerationSizeMb,
+        stackSizeMb,
+      } = this.resourceLimits;
+      // V8 only lets us limit the size of the whole heap, which includes both
+      // generations.
+      limits = {
+        heapMb: maxOldGenerationSizeMb > 0
+          ? maxOldGenerationSizeMb + MathMax(maxYoungGenerationSizeMb, 0)
+          : null,
+        stackMb: options.resourceLimits.stackSizeMb !== undefined
+          ? MathCeil(stackSizeMb)
+          : null,
+      };
+    }
+
     const serializedWorkerMetadata = serializeJsMessageData({
       workerData: options?.workerData,
       environmentData: environmentData,
       env: env_,
       isWorkerThread: true,
+      resourceLimits: this.resourceLimits,
     }, options?.transferList ?? []);
     const id = op_create_worker(
       {
@@ -164,6 +212,9 @@ class NodeWorker extends EventEmitter {
         name: this.#name,
         workerType: "module",
         closeOnIdle: true,
+        resourceLimits: limits,
+        captureStdout: !!options?.stdout,
+        captureStderr: !!options?.stderr,
       },
       serializedWorkerMetadata,
     );
@@ -171,6 +222,14 @@ class NodeWorker extends EventEmitter {
     this.threadId = id;
     this.#pollControl();
     this.#pollMessages();
+
+    const { 0: stdoutRid, 1: stderrRid } = op_host_take_worker_stdio(id);
+    if (stdoutRid !== null) {
+      this.#readStdio(stdoutRid, this.stdout);
+    }
+    if (stderrRid !== null) {
+      this.#readStdio(stderrRid, this.stderr);
+    }
   }
 
   [privateWorkerRef](ref) {
@@ -187,6 +246,9 @@ class NodeWorker extends EventEmitter {
       if (this.#messagePromise) {
         core.unrefOpPromise(this.#messagePromise);
       }
+      for (const promise of new SafeSetIterator(this.#stdioPromises)) {
+        core.unrefOpPromise(promise);
+      }
     } else if (ref && this.#refCount == 1) {
       if (this.#controlPromise) {
         core.refOpPromise(this.#controlPromise);
@@ -194,6 +256,9 @@ class NodeWorker extends EventEmitter {
       if (this.#messagePromise) {
         core.refOpPromise(this.#messagePromise);
       }
+      for (const promise of new SafeSetIterator(this.#stdioPromises)) {
+        core.refOpPromise(promise);
+      }
     }
   }
 
@@ -267,6 +332,36 @@ class NodeWorker extends EventEmitter {
     }
   };
 
+  // Reads the captured stdout or stderr of the worker, until the worker
+  // exits and closes its end of the pipe.
+  #readStdio = async (rid: number, stream: Readable) => {
+    try {
+      while (true) {
+        const buf = new Uint8Array(64 * 1024);
+        const promise = core.read(rid, buf);
+        if (this.#refCount < 1) {
+          core.unrefOpPromise(promise);
+        }
+        this.#stdioPromises.add(promise);
+        let nread;
+        try {
+          nread = await promise;
+        } finally {
+          this.#stdioPromises.delete(promise);
+        }
+        if (nread === 0) {
+          break;
+        }
+        stream.push(Buffer.from(TypedArrayPrototypeGetBuffer(buf), 0, nread));
+      }
+      stream.push(null);
+    } catch (err) {
+      stream.destroy(err);
+    } finally {
+      core.tryClose(rid);
+    }
+  };
+
   postMessage(message, transferOrOptions = {}) {
     const prefix = "Failed to execute 'postMessage' on 'MessagePort'";
     webidl.requiredArguments(arguments.length, 1, prefix);
@@ -315,8 +410,16 @@ class NodeWorker extends EventEmitter {
     this[privateWorkerRef](false);
   }
 
-  readonly getHeapSnapshot = () =>
-    notImplemented("Worker.prototype.getHeapSnapshot");
+  // https://nodejs.org/api/worker_threads.html#workergetheapsnapshotoptions
+  async getHeapSnapshot(): Promise<Readable> {
+    const snapshot = this.#status === "RUNNING"
+      ? await op_host_worker_heap_snapshot(this.#id)
+      : null;
+    if (snapshot === null) {
+      throw new ERR_WORKER_NOT_RUNNING();
+    }
+    return Readable.from([Buffer.from(snapshot)], { objectMode: false });
+  }
   // fake performance
   readonly performance = globalThis.performance;
 }
@@ -359,7 +462,7 @@ internals.__initWorkerThreads = (
   isMainThread = runningOnMainThread;
 
   defaultExport.isMainThread = isMainThread;
-  // fake resourceLimits
+  // fake resourceLimits, replaced by the ones given to the `Worker` below
   resourceLimits = isMainThread ? {} : {
     maxYoungGenerationSizeMb: 48,
     maxOldGenerationSizeMb: 2048,
@@ -384,6 +487,10 @@ internals.__initWorkerThreads = (
       workerData = metadata.workerData;
       environmentData = metadata.environmentData;
       isWorkerThread = metadata.isWorkerThread;
+      if (metadata.resourceLimits) {
+        resourceLimits = metadata.resourceLimits;
+        defaultExport.resourceLimits = resourceLimits;
+      }
       const env = metadata.env;
       if (env) {
         process.env = env;
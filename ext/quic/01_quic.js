@@ -0,0 +1,278 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+import { core, primordials } from "ext:core/mod.js";
+const { BadResourcePrototype, internalRidSymbol } = core;
+import {
+  op_quic_accept,
+  op_quic_connect,
+  op_quic_connection_accept_bi,
+  op_quic_connection_accept_uni,
+  op_quic_connection_close,
+  op_quic_connection_closed,
+  op_quic_connection_max_datagram_size,
+  op_quic_connection_open_bi,
+  op_quic_connection_open_uni,
+  op_quic_connection_read_datagram,
+  op_quic_connection_send_datagram,
+  op_quic_listen,
+} from "ext:core/ops";
+const {
+  ObjectDefineProperty,
+  ObjectPrototypeIsPrototypeOf,
+  PromisePrototypeThen,
+  PromiseResolve,
+  SymbolAsyncIterator,
+} = primordials;
+
+import { SymbolDispose } from "ext:deno_web/00_infra.js";
+import {
+  readableStreamForRid,
+  ReadableStream,
+  writableStreamForRid,
+} from "ext:deno_web/06_streams.js";
+import { loadTlsKeyPair } from "ext:deno_net/02_tls.js";
+import { validatePort } from "ext:deno_net/01_net.js";
+
+class QuicBidirectionalStream {
+  #readable;
+  #writable;
+
+  constructor(sendRid, recvRid) {
+    this.#readable = readableStreamForRid(recvRid);
+    this.#writable = writableStreamForRid(sendRid);
+  }
+
+  get readable() {
+    return this.#readable;
+  }
+
+  get writable() {
+    return this.#writable;
+  }
+}
+
+/** Streams what `accept` resolves to, until it resolves to `null`. */
+function incomingStream(accept) {
+  return new ReadableStream({
+    async pull(controller) {
+      const value = await accept();
+      if (value === null) {
+        controller.close();
+      } else {
+        controller.enqueue(value);
+      }
+    },
+  }, { highWaterMark: 0 });
+}
+
+class QuicConn {
+  #rid = 0;
+  #localAddr = null;
+  #remoteAddr = null;
+  #protocol = null;
+  #closeInfo = null;
+  #closed = null;
+  #incomingBidirectionalStreams = null;
+  #incomingUnidirectionalStreams = null;
+
+  constructor(rid, localAddr, remoteAddr, protocol) {
+    ObjectDefineProperty(this, internalRidSymbol, {
+      enumerable: false,
+      value: rid,
+    });
+    this.#rid = rid;
+    this.#localAddr = localAddr;
+    this.#remoteAddr = remoteAddr;
+    this.#protocol = protocol;
+  }
+
+  get localAddr() {
+    return this.#localAddr;
+  }
+
+  get remoteAddr() {
+    return this.#remoteAddr;
+  }
+
+  get protocol() {
+    return this.#protocol;
+  }
+
+  get maxDatagramSize() {
+    return op_quic_connection_max_datagram_size(this.#rid);
+  }
+
+  async createBidirectionalStream() {
+    const { 0: sendRid, 1: recvRid } = await op_quic_connection_open_bi(
+      this.#rid,
+    );
+    return new QuicBidirectionalStream(sendRid, recvRid);
+  }
+
+  async createUnidirectionalStream() {
+    const rid = await op_quic_connection_open_uni(this.#rid);
+    return writableStreamForRid(rid);
+  }
+
+  get incomingBidirectionalStreams() {
+    if (this.#incomingBidirectionalStreams === null) {
+      this.#incomingBidirectionalStreams = incomingStream(async () => {
+        const streams = await op_quic_connection_accept_bi(this.#rid);
+        return streams === null
+          ? null
+          : new QuicBidirectionalStream(streams[0], streams[1]);
+      });
+    }
+    return this.#incomingBidirectionalStreams;
+  }
+
+  get incomingUnidirectionalStreams() {
+    if (this.#incomingUnidirectionalStreams === null) {
+      this.#incomingUnidirectionalStreams = incomingStream(async () => {
+        const rid = await op_quic_connection_accept_uni(this.#rid);
+        return rid === null ? null : readableStreamForRid(rid);
+      });
+    }
+    return this.#incomingUnidirectionalStreams;
+  }
+
+  sendDatagram(data) {
+    op_quic_connection_send_datagram(this.#rid, data);
+  }
+
+  readDatagram() {
+    return op_quic_connection_read_datagram(this.#rid);
+  }
+
+  get closed() {
+    if (this.#closed === null) {
+      this.#closed = this.#closeInfo !== null
+        ? PromiseResolve(this.#closeInfo)
+        : PromisePrototypeThen(
+          op_quic_connection_closed(this.#rid),
+          (info) => info ?? this.#closeInfo,
+        );
+    }
+    return this.#closed;
+  }
+
+  close({ closeCode = 0, reason = "" } = { __proto__: null }) {
+    op_quic_connection_close(this.#rid, closeCode, reason);
+    this.#closeInfo = { closeCode, reason };
+  }
+
+  [SymbolDispose]() {
+    if (this.#closeInfo === null) {
+      this.close();
+    }
+  }
+}
+
+class QuicListener {
+  #rid = 0;
+  #addr = null;
+
+  constructor(rid, addr) {
+    ObjectDefineProperty(this, internalRidSymbol, {
+      enumerable: false,
+      value: rid,
+    });
+    this.#rid = rid;
+    this.#addr = addr;
+  }
+
+  get addr() {
+    return this.#addr;
+  }
+
+  async accept() {
+    const { 0: rid, 1: localAddr, 2: remoteAddr, 3: protocol } =
+      await op_quic_accept(this.#rid);
+    localAddr.transport = "udp";
+    remoteAddr.transport = "udp";
+    return new QuicConn(rid, localAddr, remoteAddr, protocol);
+  }
+
+  async next() {
+    let conn;
+    try {
+      conn = await this.accept();
+    } catch (error) {
+      if (ObjectPrototypeIsPrototypeOf(BadResourcePrototype, error)) {
+        return { value: undefined, done: true };
+      }
+      throw error;
+    }
+    return { value: conn, done: false };
+  }
+
+  return(value) {
+    this.close();
+    return PromiseResolve({ value, done: true });
+  }
+
+  close() {
+    core.close(this.#rid);
+  }
+
+  [SymbolDispose]() {
+    core.tryClose(this.#rid);
+  }
+
+  [SymbolAsyncIterator]() {
+    return this;
+  }
+}
+
+/** Adds the options which both sides of a connection can set to `args`. */
+function withTransportOptions(args, options) {
+  args.keepAliveInterval = options.keepAliveInterval;
+  args.maxIdleTimeout = options.maxIdleTimeout;
+  args.maxConcurrentBidirectionalStreams =
+    options.maxConcurrentBidirectionalStreams;
+  args.maxConcurrentUnidirectionalStreams =
+    options.maxConcurrentUnidirectionalStreams;
+  return args;
+}
+
+function listenQuic(options) {
+  const keyPair = loadTlsKeyPair("Deno.listenQuic", options);
+  const { 0: rid, 1: localAddr } = op_quic_listen(
+    {
+      hostname: options.hostname ?? "0.0.0.0",
+      port: validatePort(options.port),
+    },
+    withTransportOptions({ alpnProtocols: options.alpnProtocols }, options),
+    keyPair,
+  );
+  localAddr.transport = "udp";
+  return new QuicListener(rid, localAddr);
+}
+
+async function connectQuic(options) {
+  const keyPair = loadTlsKeyPair("Deno.connectQuic", options);
+  const { 0: rid, 1: localAddr, 2: remoteAddr, 3: protocol } =
+    await op_quic_connect(
+      {
+        hostname: options.hostname ?? "127.0.0.1",
+        port: validatePort(options.port),
+      },
+      withTransportOptions({
+        caCerts: options.caCerts ?? [],
+        alpnProtocols: options.alpnProtocols,
+        serverName: options.serverName,
+      }, options),
+      keyPair,
+    );
+  localAddr.transport = "udp";
+  remoteAddr.transport = "udp";
+  return new QuicConn(rid, localAddr, remoteAddr, protocol);
+}
+
+export {
+  connectQuic,
+  listenQuic,
+  QuicBidirectionalStream,
+  QuicConn,
+  QuicListener,
+};
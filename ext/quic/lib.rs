@@ -0,0 +1,681 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::net::SocketAddr;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use deno_core::anyhow::anyhow;
+use deno_core::error::bad_resource;
+use deno_core::error::custom_error;
+use deno_core::error::generic_error;
+use deno_core::error::range_error;
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use deno_core::op2;
+use deno_core::AsyncRefCell;
+use deno_core::AsyncResult;
+use deno_core::BufView;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_core::ToJsBuffer;
+use deno_core::WriteOutcome;
+use deno_net::ops::IpAddr;
+use deno_net::resolve_addr::resolve_addr;
+use deno_net::resolve_addr::resolve_addr_sync;
+use deno_net::DefaultTlsOptions;
+use deno_net::NetPermissions;
+use deno_net::UnsafelyIgnoreCertificateErrors;
+use deno_tls::create_client_config;
+use deno_tls::rustls;
+use deno_tls::SocketUse;
+use deno_tls::TlsKey;
+use deno_tls::TlsKeys;
+use deno_tls::TlsKeysHolder;
+use quinn::crypto::rustls::HandshakeData;
+use quinn::crypto::rustls::QuicClientConfig;
+use quinn::crypto::rustls::QuicServerConfig;
+use quinn::ConnectionError;
+use quinn::VarInt;
+use serde::Deserialize;
+use serde::Serialize;
+
+pub const UNSTABLE_FEATURE_NAME: &str = "quic";
+
+fn check_unstable(state: &OpState, api_name: &str) {
+  // TODO(bartlomieju): replace with `state.feature_checker.check_or_exit`
+  // once we phase out `check_or_exit_with_legacy_fallback`
+  state
+    .feature_checker
+    .check_or_exit_with_legacy_fallback(UNSTABLE_FEATURE_NAME, api_name)
+}
+
+deno_core::extension!(deno_quic,
+  deps = [ deno_web, deno_net ],
+  parameters = [ P: NetPermissions ],
+  ops = [
+    op_quic_listen<P>,
+    op_quic_accept,
+    op_quic_connect<P>,
+    op_quic_connection_open_bi,
+    op_quic_connection_open_uni,
+    op_quic_connection_accept_bi,
+    op_quic_connection_accept_uni,
+    op_quic_connection_send_datagram,
+    op_quic_connection_read_datagram,
+    op_quic_connection_max_datagram_size,
+    op_quic_connection_close,
+    op_quic_connection_closed,
+  ],
+  esm = [ "01_quic.js" ],
+);
+
+/// The options of a connection which both sides can set. Anything which
+/// isn't given keeps the default of quinn.
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TransportArgs {
+  keep_alive_interval: Option<u64>,
+  max_idle_timeout: Option<u64>,
+  max_concurrent_bidirectional_streams: Option<u32>,
+  max_concurrent_unidirectional_streams: Option<u32>,
+}
+
+impl TryFrom<TransportArgs> for quinn::TransportConfig {
+  type Error = AnyError;
+
+  fn try_from(args: TransportArgs) -> Result<Self, Self::Error> {
+    let mut config = quinn::TransportConfig::default();
+    if let Some(interval) = args.keep_alive_interval {
+      config.keep_alive_interval(Some(Duration::from_millis(interval)));
+    }
+    if let Some(timeout) = args.max_idle_timeout {
+      let timeout = Duration::from_millis(timeout)
+        .try_into()
+        .map_err(|_| range_error("The max idle timeout is too large"))?;
+      config.max_idle_timeout(Some(timeout));
+    }
+    if let Some(max) = args.max_concurrent_bidirectional_streams {
+      config.max_concurrent_bidi_streams(VarInt::from_u32(max));
+    }
+    if let Some(max) = args.max_concurrent_unidirectional_streams {
+      config.max_concurrent_uni_streams(VarInt::from_u32(max));
+    }
+    Ok(config)
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloseInfo {
+  close_code: u64,
+  reason: String,
+}
+
+/// Maps the error of a connection which was closed by either side to `None`,
+/// so that the streams of incoming streams and datagrams can end.
+fn unless_closed<T>(
+  result: Result<T, ConnectionError>,
+) -> Result<Option<T>, AnyError> {
+  match result {
+    Ok(value) => Ok(Some(value)),
+    Err(
+      ConnectionError::ApplicationClosed(_) | ConnectionError::LocallyClosed,
+    ) => Ok(None),
+    Err(err) => Err(err.into()),
+  }
+}
+
+fn negotiated_protocol(conn: &quinn::Connection) -> Option<String> {
+  let data = conn.handshake_data()?.downcast::<HandshakeData>().ok()?;
+  data
+    .protocol
+    .map(|protocol| String::from_utf8_lossy(&protocol).into_owned())
+}
+
+struct EndpointResource {
+  endpoint: quinn::Endpoint,
+  cancel: CancelHandle,
+}
+
+impl Resource for EndpointResource {
+  fn name(&self) -> Cow<str> {
+    "quicListener".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    // The connections which were accepted keep the endpoint alive, and go on
+    // until they are closed.
+    self.endpoint.set_server_config(None);
+    self.cancel.cancel();
+  }
+}
+
+struct ConnectionResource {
+  conn: quinn::Connection,
+  /// Connections don't keep their endpoint open by themselves.
+  _endpoint: quinn::Endpoint,
+}
+
+impl Resource for ConnectionResource {
+  fn name(&self) -> Cow<str> {
+    "quicConnection".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.conn.close(VarInt::from_u32(0), b"");
+  }
+}
+
+struct SendStreamResource(AsyncRefCell<quinn::SendStream>);
+
+impl Resource for SendStreamResource {
+  fn name(&self) -> Cow<str> {
+    "quicSendStream".into()
+  }
+
+  fn write(self: Rc<Self>, buf: BufView) -> AsyncResult<WriteOutcome> {
+    Box::pin(async move {
+      let mut stream = RcRef::map(&self, |r| &r.0).borrow_mut().await;
+      let nwritten = stream.write(&buf).await?;
+      Ok(WriteOutcome::Partial {
+        nwritten,
+        view: buf,
+      })
+    })
+  }
+
+  fn shutdown(self: Rc<Self>) -> AsyncResult<()> {
+    Box::pin(async move {
+      let mut stream = RcRef::map(&self, |r| &r.0).borrow_mut().await;
+      stream.finish()?;
+      Ok(())
+    })
+  }
+
+  // Dropping the stream finishes it, once everything which was written has
+  // been sent.
+}
+
+struct RecvStreamResource {
+  stream: AsyncRefCell<quinn::RecvStream>,
+  cancel: CancelHandle,
+}
+
+impl Resource for RecvStreamResource {
+  fn name(&self) -> Cow<str> {
+    "quicRecvStream".into()
+  }
+
+  fn read(self: Rc<Self>, limit: usize) -> AsyncResult<BufView> {
+    Box::pin(async move {
+      let mut stream = RcRef::map(&self, |r| &r.stream).borrow_mut().await;
+      let cancel = RcRef::map(&self, |r| &r.cancel);
+      let mut buf = vec![0; limit];
+      let nread = stream.read(&mut buf).or_cancel(cancel).await??.unwrap_or(0);
+      buf.truncate(nread);
+      Ok(buf.into())
+    })
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+  }
+}
+
+fn add_send_stream(
+  state: &RefCell<OpState>,
+  stream: quinn::SendStream,
+) -> ResourceId {
+  let resource = SendStreamResource(AsyncRefCell::new(stream));
+  state.borrow_mut().resource_table.add(resource)
+}
+
+fn add_recv_stream(
+  state: &RefCell<OpState>,
+  stream: quinn::RecvStream,
+) -> ResourceId {
+  let resource = RecvStreamResource {
+    stream: AsyncRefCell::new(stream),
+    cancel: Default::default(),
+  };
+  state.borrow_mut().resource_table.add(resource)
+}
+
+fn get_connection(
+  state: &RefCell<OpState>,
+  rid: ResourceId,
+) -> Result<Rc<ConnectionResource>, AnyError> {
+  state
+    .borrow()
+    .resource_table
+    .get::<ConnectionResource>(rid)
+    .map_err(|_| bad_resource("Connection has been closed"))
+}
+
+fn alpn(protocols: Vec<String>) -> Vec<Vec<u8>> {
+  protocols.into_iter().map(|s| s.into_bytes()).collect()
+}
+
+/// QUIC always uses TLS 1.3, so the server doesn't offer anything else.
+fn server_config(
+  TlsKey(cert, key): TlsKey,
+  alpn_protocols: Vec<String>,
+  transport: TransportArgs,
+) -> Result<quinn::ServerConfig, AnyError> {
+  let mut tls_config = rustls::ServerConfig::builder_with_protocol_versions(&[
+    &rustls::version::TLS13,
+  ])
+  .with_no_client_auth()
+  .with_single_cert(cert, key)
+  .map_err(|e| {
+    custom_error("InvalidData", "Error creating TLS certificate")
+      .context(anyhow!(e))
+  })?;
+  tls_config.alpn_protocols = alpn(alpn_protocols);
+  let mut config = quinn::ServerConfig::with_crypto(Arc::new(
+    QuicServerConfig::try_from(tls_config)?,
+  ));
+  config.transport_config(Arc::new(transport.try_into()?));
+  Ok(config)
+}
+
+fn client_config(
+  mut tls_config: rustls::ClientConfig,
+  alpn_protocols: Vec<String>,
+  transport: TransportArgs,
+) -> Result<quinn::ClientConfig, AnyError> {
+  tls_config.alpn_protocols = alpn(alpn_protocols);
+  let mut config =
+    quinn::ClientConfig::new(Arc::new(QuicClientConfig::try_from(tls_config)?));
+  config.transport_config(Arc::new(transport.try_into()?));
+  Ok(config)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenArgs {
+  alpn_protocols: Vec<String>,
+  #[serde(flatten)]
+  transport: TransportArgs,
+}
+
+#[op2]
+#[serde]
+pub fn op_quic_listen<NP>(
+  state: &mut OpState,
+  #[serde] addr: IpAddr,
+  #[serde] args: ListenArgs,
+  #[cppgc] keys: &TlsKeysHolder,
+) -> Result<(ResourceId, IpAddr), AnyError>
+where
+  NP: NetPermissions + 'static,
+{
+  check_unstable(state, "Deno.listenQuic()");
+  state
+    .borrow_mut::<NP>()
+    .check_net(&(&addr.hostname, Some(addr.port)), "Deno.listenQuic()")?;
+
+  let bind_addr = resolve_addr_sync(&addr.hostname, addr.port)?
+    .next()
+    .ok_or_else(|| generic_error("No resolved address found"))?;
+
+  let TlsKeys::Static(key) = keys.take() else {
+    return Err(type_error("Deno.listenQuic requires a key"));
+  };
+  let config = server_config(key, args.alpn_protocols, args.transport)?;
+  let endpoint = quinn::Endpoint::server(config, bind_addr)?;
+  let local_addr = endpoint.local_addr()?;
+
+  let rid = state.resource_table.add(EndpointResource {
+    endpoint,
+    cancel: Default::default(),
+  });
+  Ok((rid, IpAddr::from(local_addr)))
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_quic_accept(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<(ResourceId, IpAddr, IpAddr, Option<String>), AnyError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<EndpointResource>(rid)
+    .map_err(|_| bad_resource("Listener has been closed"))?;
+  let cancel = RcRef::map(&resource, |r| &r.cancel);
+
+  let conn = async {
+    while let Some(incoming) = resource.endpoint.accept().await {
+      // A client which fails the handshake doesn't concern the listener, so
+      // it's skipped.
+      if let Ok(conn) = incoming.await {
+        return Some(conn);
+      }
+    }
+    None
+  }
+  .or_cancel(cancel)
+  .await;
+  let Ok(Some(conn)) = conn else {
+    return Err(bad_resource("Listener has been closed"));
+  };
+
+  let local_addr = resource.endpoint.local_addr()?;
+  let remote_addr = conn.remote_address();
+  let protocol = negotiated_protocol(&conn);
+  let rid = state.borrow_mut().resource_table.add(ConnectionResource {
+    conn,
+    _endpoint: resource.endpoint.clone(),
+  });
+  Ok((
+    rid,
+    IpAddr::from(local_addr),
+    IpAddr::from(remote_addr),
+    protocol,
+  ))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectArgs {
+  #[serde(default)]
+  ca_certs: Vec<String>,
+  alpn_protocols: Vec<String>,
+  server_name: Option<String>,
+  #[serde(flatten)]
+  transport: TransportArgs,
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_quic_connect<NP>(
+  state: Rc<RefCell<OpState>>,
+  #[serde] addr: IpAddr,
+  #[serde] args: ConnectArgs,
+  #[cppgc] key_pair: &TlsKeysHolder,
+) -> Result<(ResourceId, IpAddr, IpAddr, Option<String>), AnyError>
+where
+  NP: NetPermissions + 'static,
+{
+  let (root_cert_store, unsafely_ignore_certificate_errors) = {
+    let mut s = state.borrow_mut();
+    check_unstable(&s, "Deno.connectQuic()");
+    s.borrow_mut::<NP>()
+      .check_net(&(&addr.hostname, Some(addr.port)), "Deno.connectQuic()")?;
+    (
+      s.borrow::<DefaultTlsOptions>().root_cert_store()?,
+      s.try_borrow::<UnsafelyIgnoreCertificateErrors>()
+        .and_then(|it| it.0.clone()),
+    )
+  };
+
+  let tls_config = create_client_config(
+    root_cert_store,
+    args.ca_certs.into_iter().map(|s| s.into_bytes()).collect(),
+    unsafely_ignore_certificate_errors,
+    key_pair.take(),
+    SocketUse::GeneralSsl,
+    vec![],
+  )?;
+  let config = client_config(tls_config, args.alpn_protocols, args.transport)?;
+
+  let remote_addr = resolve_addr(&addr.hostname, addr.port)
+    .await?
+    .next()
+    .ok_or_else(|| generic_error("No resolved address found"))?;
+  let bind_addr = match remote_addr {
+    SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+    SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+  };
+  let server_name = args.server_name.unwrap_or(addr.hostname);
+  let server_name = server_name.trim_start_matches('[').trim_end_matches(']');
+
+  let endpoint = quinn::Endpoint::client(bind_addr)?;
+  let conn = endpoint
+    .connect_with(config, remote_addr, server_name)?
+    .await?;
+  let local_addr = endpoint.local_addr()?;
+  let protocol = negotiated_protocol(&conn);
+  let rid = state.borrow_mut().resource_table.add(ConnectionResource {
+    conn,
+    _endpoint: endpoint,
+  });
+  Ok((
+    rid,
+    IpAddr::from(local_addr),
+    IpAddr::from(remote_addr),
+    protocol,
+  ))
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_quic_connection_open_bi(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<(ResourceId, ResourceId), AnyError> {
+  let resource = get_connection(&state, rid)?;
+  let (send, recv) = resource.conn.open_bi().await?;
+  Ok((add_send_stream(&state, send), add_recv_stream(&state, recv)))
+}
+
+#[op2(async)]
+#[smi]
+pub async fn op_quic_connection_open_uni(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<ResourceId, AnyError> {
+  let resource = get_connection(&state, rid)?;
+  let send = resource.conn.open_uni().await?;
+  Ok(add_send_stream(&state, send))
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_quic_connection_accept_bi(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<(ResourceId, ResourceId)>, AnyError> {
+  let resource = get_connection(&state, rid)?;
+  let streams = unless_closed(resource.conn.accept_bi().await)?;
+  Ok(streams.map(|(send, recv)| {
+    (add_send_stream(&state, send), add_recv_stream(&state, recv))
+  }))
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_quic_connection_accept_uni(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<ResourceId>, AnyError> {
+  let resource = get_connection(&state, rid)?;
+  let stream = unless_closed(resource.conn.accept_uni().await)?;
+  Ok(stream.map(|recv| add_recv_stream(&state, recv)))
+}
+
+#[op2(fast)]
+pub fn op_quic_connection_send_datagram(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[buffer] data: &[u8],
+) -> Result<(), AnyError> {
+  let resource = state
+    .resource_table
+    .get::<ConnectionResource>(rid)
+    .map_err(|_| bad_resource("Connection has been closed"))?;
+  resource.conn.send_datagram(Bytes::copy_from_slice(data))?;
+  Ok(())
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_quic_connection_read_datagram(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<ToJsBuffer>, AnyError> {
+  let resource = get_connection(&state, rid)?;
+  let data = unless_closed(resource.conn.read_datagram().await)?;
+  Ok(data.map(|data| data.to_vec().into()))
+}
+
+/// Returns 0 if the peer doesn't accept datagrams.
+#[op2(fast)]
+pub fn op_quic_connection_max_datagram_size(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<u32, AnyError> {
+  let resource = state
+    .resource_table
+    .get::<ConnectionResource>(rid)
+    .map_err(|_| bad_resource("Connection has been closed"))?;
+  Ok(resource.conn.max_datagram_size().unwrap_or(0) as u32)
+}
+
+#[op2(fast)]
+pub fn op_quic_connection_close(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[number] close_code: u64,
+  #[string] reason: &str,
+) -> Result<(), AnyError> {
+  let close_code = VarInt::from_u64(close_code)
+    .map_err(|_| range_error("The close code must be less than 2^62"))?;
+  let resource = state
+    .resource_table
+    .take::<ConnectionResource>(rid)
+    .map_err(|_| bad_resource("Connection has been closed"))?;
+  resource.conn.close(close_code, reason.as_bytes());
+  Ok(())
+}
+
+/// Resolves once the connection was closed, with the code and reason which
+/// the peer gave, or `None` if it was closed locally. Fails if the connection
+/// was lost.
+#[op2(async)]
+#[serde]
+pub async fn op_quic_connection_closed(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<CloseInfo>, AnyError> {
+  let resource = get_connection(&state, rid)?;
+  match resource.conn.closed().await {
+    ConnectionError::ApplicationClosed(close) => Ok(Some(CloseInfo {
+      close_code: close.error_code.into_inner(),
+      reason: String::from_utf8_lossy(&close.reason).into_owned(),
+    })),
+    ConnectionError::LocallyClosed => Ok(None),
+    err => Err(err.into()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::BufReader;
+  use std::path::Path;
+
+  fn testdata(name: &str) -> Vec<u8> {
+    let dir =
+      Path::new(env!("CARGO_MANIFEST_DIR")).join("../../tests/testdata");
+    std::fs::read(dir.join("tls").join(name)).unwrap()
+  }
+
+  #[tokio::test]
+  async fn streams_and_datagrams() {
+    let cert =
+      deno_tls::load_certs(&mut BufReader::new(&testdata("localhost.crt")[..]))
+        .unwrap();
+    let key = deno_tls::load_private_keys(&testdata("localhost.key"))
+      .unwrap()
+      .remove(0);
+    let config = server_config(
+      TlsKey(cert, key),
+      vec!["deno-test".to_string()],
+      Default::default(),
+    )
+    .unwrap();
+    let server =
+      quinn::Endpoint::server(config, "127.0.0.1:0".parse().unwrap()).unwrap();
+
+    let tls_config = create_client_config(
+      None,
+      vec![testdata("RootCA.pem")],
+      None,
+      TlsKeys::Null,
+      SocketUse::GeneralSsl,
+      vec![],
+    )
+    .unwrap();
+    let config = client_config(
+      tls_config,
+      vec!["other".to_string(), "deno-test".to_string()],
+      TransportArgs {
+        max_idle_timeout: Some(5000),
+        ..Default::default()
+      },
+    )
+    .unwrap();
+    let client =
+      quinn::Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+    let connecting = client
+      .connect_with(config, server.local_addr().unwrap(), "localhost")
+      .unwrap();
+    let (client_conn, server_conn) =
+      tokio::join!(connecting, async { server.accept().await.unwrap().await });
+    let client_conn = client_conn.unwrap();
+    let server_conn = server_conn.unwrap();
+    assert_eq!(negotiated_protocol(&client_conn).unwrap(), "deno-test");
+    assert_eq!(negotiated_protocol(&server_conn).unwrap(), "deno-test");
+
+    let (mut send, mut recv) = client_conn.open_bi().await.unwrap();
+    send.write_all(b"ping").await.unwrap();
+    send.finish().unwrap();
+    let (mut server_send, mut server_recv) =
+      server_conn.accept_bi().await.unwrap();
+    assert_eq!(server_recv.read_to_end(16).await.unwrap(), b"ping");
+    server_send.write_all(b"pong").await.unwrap();
+    server_send.finish().unwrap();
+    assert_eq!(recv.read_to_end(16).await.unwrap(), b"pong");
+
+    client_conn
+      .send_datagram(Bytes::from_static(b"datagram"))
+      .unwrap();
+    assert_eq!(server_conn.read_datagram().await.unwrap(), &b"datagram"[..]);
+
+    client_conn.close(VarInt::from_u32(42), b"bye");
+    assert!(matches!(
+      unless_closed(server_conn.accept_uni().await),
+      Ok(None)
+    ));
+    match server_conn.closed().await {
+      ConnectionError::ApplicationClosed(close) => {
+        assert_eq!(close.error_code, VarInt::from_u32(42));
+        assert_eq!(&close.reason[..], b"bye");
+      }
+      err => panic!("unexpected error: {err}"),
+    }
+  }
+
+  #[test]
+  fn transport_args() {
+    let args = TransportArgs {
+      max_idle_timeout: Some(u64::MAX),
+      ..Default::default()
+    };
+    assert!(quinn::TransportConfig::try_from(args).is_err());
+    assert!(quinn::TransportConfig::try_from(TransportArgs::default()).is_ok());
+  }
+}
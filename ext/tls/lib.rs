@@ -17,6 +17,10 @@ use rustls::client::danger::HandshakeSignatureValid;
 use rustls::client::danger::ServerCertVerified;
 use rustls::client::danger::ServerCertVerifier;
 use rustls::client::WebPkiServerVerifier;
+use rustls::server::danger::ClientCertVerified;
+use rustls::server::danger::ClientCertVerifier;
+use rustls::server::WebPkiClientVerifier;
+use rustls::CertificateError;
 use rustls::ClientConfig;
 use rustls::DigitallySignedStruct;
 use rustls::Error;
@@ -31,6 +35,7 @@ use std::io::BufReader;
 use std::io::Cursor;
 use std::net::IpAddr;
 use std::sync::Arc;
+use std::sync::Mutex;
 
 mod tls_key;
 pub use tls_key::*;
@@ -138,6 +143,219 @@ impl ServerCertVerifier for NoCertificateVerification {
   }
 }
 
+/// Verifies the certificate chain with another verifier, and then rejects it
+/// unless one of its certificates has a pinned public key. The pins are
+/// SHA-256 hashes of the DER encoded SubjectPublicKeyInfo of the keys.
+#[derive(Debug)]
+pub struct PinnedPublicKeyVerification {
+  verifier: Arc<dyn ServerCertVerifier>,
+  pins: Vec<[u8; 32]>,
+}
+
+impl PinnedPublicKeyVerification {
+  pub fn new(
+    verifier: Arc<dyn ServerCertVerifier>,
+    pins: Vec<[u8; 32]>,
+  ) -> Self {
+    Self { verifier, pins }
+  }
+
+  fn is_pinned(&self, cert: &CertificateDer<'_>) -> bool {
+    let Ok(cert) = webpki::EndEntityCert::try_from(cert) else {
+      return false;
+    };
+    let hash = ring::digest::digest(
+      &ring::digest::SHA256,
+      &cert.subject_public_key_info(),
+    );
+    self.pins.iter().any(|pin| pin == hash.as_ref())
+  }
+}
+
+impl ServerCertVerifier for PinnedPublicKeyVerification {
+  fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+    self.verifier.supported_verify_schemes()
+  }
+
+  fn verify_server_cert(
+    &self,
+    end_entity: &rustls::pki_types::CertificateDer<'_>,
+    intermediates: &[rustls::pki_types::CertificateDer<'_>],
+    server_name: &rustls::pki_types::ServerName<'_>,
+    ocsp_response: &[u8],
+    now: rustls::pki_types::UnixTime,
+  ) -> Result<ServerCertVerified, Error> {
+    let verified = self.verifier.verify_server_cert(
+      end_entity,
+      intermediates,
+      server_name,
+      ocsp_response,
+      now,
+    )?;
+    let mut chain = std::iter::once(end_entity).chain(intermediates);
+    if chain.any(|cert| self.is_pinned(cert)) {
+      Ok(verified)
+    } else {
+      Err(Error::InvalidCertificate(
+        CertificateError::ApplicationVerificationFailure,
+      ))
+    }
+  }
+
+  fn verify_tls12_signature(
+    &self,
+    message: &[u8],
+    cert: &rustls::pki_types::CertificateDer,
+    dss: &DigitallySignedStruct,
+  ) -> Result<HandshakeSignatureValid, Error> {
+    self.verifier.verify_tls12_signature(message, cert, dss)
+  }
+
+  fn verify_tls13_signature(
+    &self,
+    message: &[u8],
+    cert: &rustls::pki_types::CertificateDer,
+    dss: &DigitallySignedStruct,
+  ) -> Result<HandshakeSignatureValid, Error> {
+    self.verifier.verify_tls13_signature(message, cert, dss)
+  }
+}
+
+/// The certificate chain that the client of a TLS server connection presented,
+/// captured by [`ClientCertificateVerification`] during the handshake.
+#[derive(Debug, Default)]
+pub struct ClientCertificate(Mutex<Option<PeerCertificate>>);
+
+#[derive(Clone, Debug)]
+pub struct PeerCertificate {
+  /// The end-entity certificate, followed by the intermediates.
+  pub chain: Vec<CertificateDer<'static>>,
+  /// Why the chain does not verify, `None` if it does.
+  pub error: Option<String>,
+}
+
+impl PeerCertificate {
+  /// The SHA-256 hash of the SubjectPublicKeyInfo of the end-entity
+  /// certificate, as used for pinning by [`PinnedPublicKeyVerification`].
+  pub fn public_key_hash(&self) -> Option<[u8; 32]> {
+    let cert = webpki::EndEntityCert::try_from(self.chain.first()?).ok()?;
+    let hash = ring::digest::digest(
+      &ring::digest::SHA256,
+      &cert.subject_public_key_info(),
+    );
+    hash.as_ref().try_into().ok()
+  }
+}
+
+impl ClientCertificate {
+  /// `None` if the client did not present a certificate, or the handshake is
+  /// not done yet.
+  pub fn get(&self) -> Option<PeerCertificate> {
+    self.0.lock().unwrap().clone()
+  }
+}
+
+/// Verifies the certificate chain of clients with another verifier, and
+/// records it in a [`ClientCertificate`]. Unless client authentication is
+/// required, chains that don't verify are accepted as well, with the error
+/// recorded next to them. Clients still have to prove that they own the key
+/// of the certificate either way.
+#[derive(Debug)]
+pub struct ClientCertificateVerification {
+  verifier: Arc<dyn ClientCertVerifier>,
+  required: bool,
+  certificate: Arc<ClientCertificate>,
+}
+
+impl ClientCertificateVerification {
+  pub fn new(
+    verifier: Arc<dyn ClientCertVerifier>,
+    required: bool,
+    certificate: Arc<ClientCertificate>,
+  ) -> Self {
+    Self {
+      verifier,
+      required,
+      certificate,
+    }
+  }
+}
+
+impl ClientCertVerifier for ClientCertificateVerification {
+  fn client_auth_mandatory(&self) -> bool {
+    self.required
+  }
+
+  fn root_hint_subjects(&self) -> &[rustls::DistinguishedName] {
+    self.verifier.root_hint_subjects()
+  }
+
+  fn verify_client_cert(
+    &self,
+    end_entity: &CertificateDer<'_>,
+    intermediates: &[CertificateDer<'_>],
+    now: rustls::pki_types::UnixTime,
+  ) -> Result<ClientCertVerified, Error> {
+    let result =
+      self
+        .verifier
+        .verify_client_cert(end_entity, intermediates, now);
+    let chain = std::iter::once(end_entity)
+      .chain(intermediates)
+      .map(|cert| cert.clone().into_owned())
+      .collect();
+    *self.certificate.0.lock().unwrap() = Some(PeerCertificate {
+      chain,
+      error: result.as_ref().err().map(|e| e.to_string()),
+    });
+    match result {
+      Err(_) if !self.required => Ok(ClientCertVerified::assertion()),
+      result => result,
+    }
+  }
+
+  fn verify_tls12_signature(
+    &self,
+    message: &[u8],
+    cert: &CertificateDer<'_>,
+    dss: &DigitallySignedStruct,
+  ) -> Result<HandshakeSignatureValid, Error> {
+    self.verifier.verify_tls12_signature(message, cert, dss)
+  }
+
+  fn verify_tls13_signature(
+    &self,
+    message: &[u8],
+    cert: &CertificateDer<'_>,
+    dss: &DigitallySignedStruct,
+  ) -> Result<HandshakeSignatureValid, Error> {
+    self.verifier.verify_tls13_signature(message, cert, dss)
+  }
+
+  fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+    self.verifier.supported_verify_schemes()
+  }
+}
+
+/// Creates a verifier of client certificates that must chain to one of the
+/// PEM encoded `ca_certs`, to be wrapped by [`ClientCertificateVerification`].
+pub fn create_client_cert_verifier(
+  ca_certs: Vec<Vec<u8>>,
+) -> Result<Arc<dyn ClientCertVerifier>, AnyError> {
+  let mut root_cert_store = RootCertStore::empty();
+  for cert in ca_certs {
+    let reader = &mut BufReader::new(Cursor::new(cert));
+    for cert in load_certs(reader)? {
+      root_cert_store.add(cert)?;
+    }
+  }
+  WebPkiClientVerifier::builder(root_cert_store.into())
+    // whether a certificate is required is up to ClientCertificateVerification
+    .allow_unauthenticated()
+    .build()
+    .map_err(|e| anyhow!("Unable to create certificate verifier: {}", e))
+}
+
 #[derive(Deserialize, Default, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 #[serde(default)]
@@ -172,19 +390,29 @@ pub enum SocketUse {
   Http2Only,
 }
 
+/// Creates the config of a TLS client. Unless `pinned_public_keys` is empty,
+/// servers are rejected unless one of the certificates of their chain has
+/// one of the keys, see [`PinnedPublicKeyVerification`].
 pub fn create_client_config(
   root_cert_store: Option<RootCertStore>,
   ca_certs: Vec<Vec<u8>>,
   unsafely_ignore_certificate_errors: Option<Vec<String>>,
   maybe_cert_chain_and_key: TlsKeys,
   socket_use: SocketUse,
+  pinned_public_keys: Vec<[u8; 32]>,
 ) -> Result<ClientConfig, AnyError> {
   if let Some(ic_allowlist) = unsafely_ignore_certificate_errors {
+    let mut verifier: Arc<dyn ServerCertVerifier> =
+      Arc::new(NoCertificateVerification::new(ic_allowlist));
+    if !pinned_public_keys.is_empty() {
+      verifier = Arc::new(PinnedPublicKeyVerification::new(
+        verifier,
+        pinned_public_keys,
+      ));
+    }
     let client_config = ClientConfig::builder()
       .dangerous()
-      .with_custom_certificate_verifier(Arc::new(
-        NoCertificateVerification::new(ic_allowlist),
-      ));
+      .with_custom_certificate_verifier(verifier);
 
     // NOTE(bartlomieju): this if/else is duplicated at the end of the body of this function.
     // However it's not really feasible to deduplicate it as the `client_config` instances
@@ -223,8 +451,18 @@ pub fn create_client_config(
     }
   }
 
-  let client_config =
-    ClientConfig::builder().with_root_certificates(root_cert_store);
+  let client_config = if pinned_public_keys.is_empty() {
+    ClientConfig::builder().with_root_certificates(root_cert_store)
+  } else {
+    let verifier = WebPkiServerVerifier::builder(root_cert_store.into())
+      .build()
+      .map_err(|e| anyhow!("Unable to create certificate verifier: {}", e))?;
+    ClientConfig::builder()
+      .dangerous()
+      .with_custom_certificate_verifier(Arc::new(
+        PinnedPublicKeyVerification::new(verifier, pinned_public_keys),
+      ))
+  };
 
   let mut client = match maybe_cert_chain_and_key {
     TlsKeys::Static(TlsKey(cert_chain, private_key)) => client_config
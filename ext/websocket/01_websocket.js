@@ -33,7 +33,9 @@ const {
   ObjectPrototypeIsPrototypeOf,
   PromisePrototypeCatch,
   PromisePrototypeThen,
+  RangeError,
   RegExpPrototypeExec,
+  SafeArrayIterator,
   SafeSet,
   SetPrototypeGetSize,
   String,
@@ -42,6 +44,7 @@ const {
   Symbol,
   SymbolFor,
   SymbolIterator,
+  TypeError,
   TypedArrayPrototypeGetByteLength,
 } = primordials;
 
@@ -79,6 +82,83 @@ webidl.converters["sequence<DOMString> or DOMString"] = (
   return webidl.converters.DOMString(V, prefix, context, opts);
 };
 
+function windowBitsConverter(V, prefix, context, opts) {
+  const bits = webidl.converters.octet(V, prefix, context, {
+    ...opts,
+    enforceRange: true,
+  });
+  if (bits < 8 || bits > 15) {
+    throw new RangeError(`${prefix}: ${context} must be between 8 and 15`);
+  }
+  return bits;
+}
+
+webidl.converters.WebSocketDeflateOptions = webidl.createDictionaryConverter(
+  "WebSocketDeflateOptions",
+  [
+    {
+      key: "serverNoContextTakeover",
+      converter: webidl.converters.boolean,
+      defaultValue: false,
+    },
+    {
+      key: "clientNoContextTakeover",
+      converter: webidl.converters.boolean,
+      defaultValue: false,
+    },
+    { key: "serverMaxWindowBits", converter: windowBitsConverter },
+    { key: "clientMaxWindowBits", converter: windowBitsConverter },
+  ],
+);
+
+/**
+ * Converts the non standard `deflate`, `maxFrameSize` and `maxMessageSize`
+ * options of `WebSocketStream` and `Deno.upgradeWebSocket`.
+ * @param {object} options
+ * @param {string} prefix
+ * @param {boolean} server
+ */
+function webSocketExtensionOptions(options, prefix, server) {
+  let deflate = null;
+  if (options.deflate !== undefined && options.deflate !== false) {
+    deflate = webidl.converters.WebSocketDeflateOptions(
+      options.deflate === true ? undefined : options.deflate,
+      prefix,
+      "'deflate'",
+    );
+    // Only the window of the peer can be limited.
+    const ownWindowBits = server
+      ? "serverMaxWindowBits"
+      : "clientMaxWindowBits";
+    if (deflate[ownWindowBits] !== undefined) {
+      throw new TypeError(
+        `${prefix}: '${ownWindowBits}' can only be requested by ${
+          server ? "clients" : "servers"
+        }`,
+      );
+    }
+  }
+  const limits = { __proto__: null, maxFrameSize: null, maxMessageSize: null };
+  for (const key of new SafeArrayIterator(["maxFrameSize", "maxMessageSize"])) {
+    if (options[key] !== undefined) {
+      limits[key] = webidl.converters["unsigned long long"](
+        options[key],
+        prefix,
+        `'${key}'`,
+        { enforceRange: true },
+      );
+      if (limits[key] === 0) {
+        throw new RangeError(`${prefix}: '${key}' must be greater than 0`);
+      }
+    }
+  }
+  return {
+    deflate,
+    maxFrameSize: limits.maxFrameSize,
+    maxMessageSize: limits.maxMessageSize,
+  };
+}
+
 webidl.converters["WebSocketSend"] = (V, prefix, context, opts) => {
   // Union for (Blob or ArrayBufferView or ArrayBuffer or USVString)
   if (ObjectPrototypeIsPrototypeOf(BlobPrototype, V)) {
@@ -120,6 +200,7 @@ const _server = Symbol("[[server]]");
 const _idleTimeoutDuration = Symbol("[[idleTimeout]]");
 const _idleTimeoutTimeout = Symbol("[[idleTimeoutTimeout]]");
 const _serverHandleIdleTimeout = Symbol("[[serverHandleIdleTimeout]]");
+const _upgradeOptions = Symbol("[[upgradeOptions]]");
 
 class WebSocket extends EventTarget {
   constructor(url, protocols = []) {
@@ -664,11 +745,13 @@ function createWebSocketBranded() {
   socket[_idleTimeoutDuration] = 0;
   socket[_idleTimeoutTimeout] = undefined;
   socket[_sendQueue] = [];
+  socket[_upgradeOptions] = null;
   return socket;
 }
 
 export {
   _eventLoop,
+  _extensions,
   _idleTimeoutDuration,
   _idleTimeoutTimeout,
   _protocol,
@@ -677,7 +760,9 @@ export {
   _role,
   _server,
   _serverHandleIdleTimeout,
+  _upgradeOptions,
   createWebSocketBranded,
   SERVER,
   WebSocket,
+  webSocketExtensionOptions,
 };
@@ -11,8 +11,11 @@ import {
   op_ws_get_buffer_as_string,
   op_ws_get_error,
   op_ws_next_event,
-  op_ws_send_binary_async,
-  op_ws_send_text_async,
+  op_ws_stream_drain,
+  op_ws_stream_next,
+  op_ws_stream_read_ahead,
+  op_ws_stream_send_binary,
+  op_ws_stream_send_text,
 } from "ext:core/ops";
 const {
   ArrayPrototypeJoin,
@@ -42,6 +45,7 @@ import {
   headerListFromHeaders,
   headersFromHeaderList,
 } from "ext:deno_fetch/20_headers.js";
+import { webSocketExtensionOptions } from "ext:deno_websocket/01_websocket.js";
 
 webidl.converters.WebSocketStreamOptions = webidl.createDictionaryConverter(
   "WebSocketStreamOptions",
@@ -61,6 +65,9 @@ webidl.converters.WebSocketStreamOptions = webidl.createDictionaryConverter(
       key: "headers",
       converter: webidl.converters.HeadersInit,
     },
+    { key: "deflate", converter: webidl.converters.any },
+    { key: "maxFrameSize", converter: webidl.converters.any },
+    { key: "maxMessageSize", converter: webidl.converters.any },
   ],
 );
 webidl.converters.WebSocketCloseInfo = webidl.createDictionaryConverter(
@@ -83,6 +90,8 @@ webidl.converters.WebSocketCloseInfo = webidl.createDictionaryConverter(
 );
 
 const CLOSE_RESPONSE_TIMEOUT = 5000;
+// Bytes of incoming messages that are read ahead of the readable stream.
+const READ_CREDIT = 256 * 1024;
 
 const _rid = Symbol("[[rid]]");
 const _url = Symbol("[[url]]");
@@ -150,6 +159,8 @@ class WebSocketStream {
       fillHeaders(headers, options.headers);
     }
 
+    const extensionOptions = webSocketExtensionOptions(options, prefix, false);
+
     const cancelRid = op_ws_check_permission_and_cancel_handle(
       "WebSocketStream.abort()",
       this[_url],
@@ -173,6 +184,7 @@ class WebSocketStream {
           options.protocols ? ArrayPrototypeJoin(options.protocols, ", ") : "",
           cancelRid,
           headerListFromHeaders(headers),
+          extensionOptions,
         ),
         (create) => {
           options.signal?.[remove](abort);
@@ -207,20 +219,27 @@ class WebSocketStream {
           } else {
             this[_rid] = create.rid;
 
+            op_ws_stream_read_ahead(this[_rid], READ_CREDIT);
+
             const writable = new WritableStream({
               write: async (chunk) => {
+                let hasCredit;
                 if (typeof chunk === "string") {
-                  await op_ws_send_text_async(this[_rid], chunk);
+                  hasCredit = op_ws_stream_send_text(this[_rid], chunk);
                 } else if (
                   TypedArrayPrototypeGetSymbolToStringTag(chunk) ===
                     "Uint8Array"
                 ) {
-                  await op_ws_send_binary_async(this[_rid], chunk);
+                  hasCredit = op_ws_stream_send_binary(this[_rid], chunk);
                 } else {
                   throw new TypeError(
                     "A chunk may only be either a string or an Uint8Array",
                   );
                 }
+                // Messages are queued in Rust until it runs out of credit.
+                if (!hasCredit) {
+                  await op_ws_stream_drain(this[_rid]);
+                }
               },
               close: async () => {
                 this.close();
@@ -250,7 +269,7 @@ class WebSocketStream {
             });
             const pull = async (controller) => {
               // Remember that this pull method may be re-entered before it has completed
-              const kind = await op_ws_next_event(this[_rid]);
+              const kind = await op_ws_stream_next(this[_rid]);
               switch (kind) {
                 case 0:
                   /* string */
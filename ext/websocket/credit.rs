@@ -0,0 +1,246 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Credit-based flow control for `WebSocketStream`.
+//!
+//! Incoming messages are read ahead by a task for as long as the credit that
+//! JS granted lasts. Every message that JS takes gives its credit back, so a
+//! stream that is not read stops reading from the socket instead of queueing
+//! messages without bound.
+//!
+//! Outgoing messages are accepted right away while the bytes that were not
+//! written yet stay below [`WRITE_CREDIT`]. A writer that runs out of credit
+//! waits for the socket to drain.
+
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use deno_core::op2;
+use deno_core::unsync::spawn;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::ResourceId;
+use fastwebsockets::Frame;
+use fastwebsockets::OpCode;
+use tokio::sync::Notify;
+
+use crate::next_event;
+use crate::MessageKind;
+use crate::ServerWebSocket;
+
+/// Bytes that may be queued for writing before writers have to wait.
+const WRITE_CREDIT: usize = 256 * 1024;
+
+struct Event {
+  kind: u16,
+  string: Option<String>,
+  buffer: Option<Vec<u8>>,
+  error: Option<String>,
+}
+
+impl Event {
+  /// The credit used by the event. Empty messages cost a byte, so that they
+  /// can't be read ahead without limit either.
+  fn cost(&self) -> usize {
+    let len = match (&self.string, &self.buffer) {
+      (Some(string), _) => string.len(),
+      (_, Some(buffer)) => buffer.len(),
+      _ => 0,
+    };
+    len.max(1)
+  }
+
+  fn is_last(&self) -> bool {
+    self.kind > MessageKind::Pong as u16
+  }
+}
+
+#[derive(Default)]
+pub(crate) struct StreamCredit {
+  read_ahead: Cell<bool>,
+  read_credit: Cell<usize>,
+  read_finished: Cell<bool>,
+  events: RefCell<VecDeque<Event>>,
+  /// Wakes the read-ahead task when credit was given back.
+  credit_granted: Notify,
+  /// Wakes [`op_ws_stream_next`] when an event was queued.
+  event_queued: Notify,
+  unwritten: Cell<usize>,
+  write_error: RefCell<Option<String>>,
+  drained: Notify,
+  pub(crate) cancel: CancelHandle,
+}
+
+impl StreamCredit {
+  fn grant(&self, credit: usize) {
+    self
+      .read_credit
+      .set(self.read_credit.get().saturating_add(credit));
+    self.credit_granted.notify_one();
+  }
+
+  fn check_write_error(&self) -> Result<(), AnyError> {
+    match &*self.write_error.borrow() {
+      Some(error) => Err(type_error(error.clone())),
+      None => Ok(()),
+    }
+  }
+}
+
+async fn read_ahead(resource: Rc<ServerWebSocket>) {
+  let credit = &resource.credit;
+  loop {
+    while credit.read_credit.get() == 0 {
+      credit.credit_granted.notified().await;
+    }
+    let kind = next_event(&resource).await;
+    let event = Event {
+      kind,
+      string: resource.string.take(),
+      buffer: resource.buffer.take(),
+      error: (kind > MessageKind::Pong as u16)
+        .then(|| resource.error.take())
+        .flatten(),
+    };
+    credit
+      .read_credit
+      .set(credit.read_credit.get().saturating_sub(event.cost()));
+    let is_last = event.is_last();
+    credit.events.borrow_mut().push_back(event);
+    credit.event_queued.notify_one();
+    if is_last {
+      return;
+    }
+  }
+}
+
+/// Starts reading messages ahead of [`op_ws_stream_next`], up to `credit`
+/// bytes.
+#[op2(fast)]
+pub fn op_ws_stream_read_ahead(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[smi] credit: u32,
+) -> Result<(), AnyError> {
+  let resource = state.resource_table.get::<ServerWebSocket>(rid)?;
+  resource.credit.grant(credit as usize);
+  if resource.credit.read_ahead.replace(true) {
+    return Ok(());
+  }
+  spawn(async move {
+    let cancel = RcRef::map(&resource, |r| &r.credit.cancel);
+    _ = read_ahead(resource.clone()).or_cancel(cancel).await;
+    resource.credit.read_finished.set(true);
+    resource.credit.event_queued.notify_waiters();
+  });
+  Ok(())
+}
+
+/// Like `op_ws_next_event`, for a socket that is read ahead. Taking an event
+/// gives its credit back.
+#[op2(async)]
+pub async fn op_ws_stream_next(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> u16 {
+  let Ok(resource) = state
+    .borrow_mut()
+    .resource_table
+    .get::<ServerWebSocket>(rid)
+  else {
+    return MessageKind::Error as u16;
+  };
+  let credit = &resource.credit;
+  loop {
+    let event = credit.events.borrow_mut().pop_front();
+    if let Some(event) = event {
+      credit.grant(event.cost());
+      if event.is_last() {
+        resource.set_error(event.error);
+      }
+      resource.string.set(event.string);
+      resource.buffer.set(event.buffer);
+      return event.kind;
+    }
+    if credit.read_finished.get() {
+      return MessageKind::Error as u16;
+    }
+    credit.event_queued.notified().await;
+  }
+}
+
+fn stream_send(
+  state: &mut OpState,
+  rid: ResourceId,
+  opcode: OpCode,
+  data: Vec<u8>,
+) -> Result<bool, AnyError> {
+  let resource = state.resource_table.get::<ServerWebSocket>(rid)?;
+  resource.credit.check_write_error()?;
+  let len = data.len();
+  let unwritten = resource.credit.unwritten.get() + len;
+  resource.credit.unwritten.set(unwritten);
+  let lock = resource.reserve_lock();
+  spawn(async move {
+    let res = resource
+      .write_frame(lock, Frame::new(true, opcode, None, data.into()))
+      .await;
+    let credit = &resource.credit;
+    if let Err(err) = res {
+      credit
+        .write_error
+        .borrow_mut()
+        .get_or_insert(err.to_string());
+    }
+    credit.unwritten.set(credit.unwritten.get() - len);
+    credit.drained.notify_one();
+  });
+  Ok(unwritten < WRITE_CREDIT)
+}
+
+/// Queues a text message. Returns `false` once the writer ran out of credit
+/// and should wait for [`op_ws_stream_drain`].
+#[op2(fast)]
+pub fn op_ws_stream_send_text(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[string] data: String,
+) -> Result<bool, AnyError> {
+  stream_send(state, rid, OpCode::Text, data.into_bytes())
+}
+
+/// Queues a binary message, see [`op_ws_stream_send_text`].
+#[op2]
+pub fn op_ws_stream_send_binary(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[anybuffer] data: &[u8],
+) -> Result<bool, AnyError> {
+  stream_send(state, rid, OpCode::Binary, data.to_vec())
+}
+
+/// Resolves once at most half of the write credit is in use, or rejects if a
+/// queued message could not be written.
+#[op2(async)]
+pub async fn op_ws_stream_drain(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<(), AnyError> {
+  let resource = state
+    .borrow_mut()
+    .resource_table
+    .get::<ServerWebSocket>(rid)?;
+  let credit = &resource.credit;
+  loop {
+    credit.check_write_error()?;
+    if credit.unwritten.get() <= WRITE_CREDIT / 2 {
+      return Ok(());
+    }
+    credit.drained.notified().await;
+  }
+}
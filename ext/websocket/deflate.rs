@@ -0,0 +1,350 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! The `permessage-deflate` extension (RFC 7692): negotiation of its
+//! parameters, and compression of message payloads.
+//!
+//! The deflate backend always compresses with a 32 KiB window, so only the
+//! window of the peer can be limited: clients may request
+//! `server_max_window_bits`, servers may request `client_max_window_bits`.
+//! Offers asking us to use a smaller window are declined.
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use flate2::Compress;
+use flate2::Compression;
+use flate2::Decompress;
+use flate2::FlushCompress;
+use flate2::FlushDecompress;
+use flate2::Status;
+use serde::Deserialize;
+
+const EXTENSION_NAME: &str = "permessage-deflate";
+const MAX_WINDOW_BITS: u8 = 15;
+const MIN_WINDOW_BITS: u8 = 8;
+/// The end of a sync flush, which senders remove from every message.
+const SYNC_FLUSH_TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Options for negotiating `permessage-deflate`, as passed from JS.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeflateOptions {
+  server_no_context_takeover: bool,
+  client_no_context_takeover: bool,
+  server_max_window_bits: Option<u8>,
+  client_max_window_bits: Option<u8>,
+}
+
+/// Negotiated parameters of `permessage-deflate`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct DeflateParams {
+  pub server_no_context_takeover: bool,
+  pub client_no_context_takeover: bool,
+  pub server_max_window_bits: Option<u8>,
+  /// `Some(None)` is a client offering to accept a window size of the
+  /// server's choice.
+  pub client_max_window_bits: Option<Option<u8>>,
+}
+
+impl DeflateParams {
+  fn parse(params: &[(&str, Option<&str>)]) -> Result<Self, String> {
+    let mut parsed = DeflateParams::default();
+    let mut seen: Vec<&str> = Vec::with_capacity(params.len());
+    for (name, value) in params {
+      if seen.contains(name) {
+        return Err(format!("duplicate parameter '{name}'"));
+      }
+      seen.push(name);
+      match (*name, value) {
+        ("server_no_context_takeover", None) => {
+          parsed.server_no_context_takeover = true;
+        }
+        ("client_no_context_takeover", None) => {
+          parsed.client_no_context_takeover = true;
+        }
+        ("server_max_window_bits", Some(value)) => {
+          parsed.server_max_window_bits = Some(parse_window_bits(value)?);
+        }
+        ("client_max_window_bits", None) => {
+          parsed.client_max_window_bits = Some(None);
+        }
+        ("client_max_window_bits", Some(value)) => {
+          parsed.client_max_window_bits = Some(Some(parse_window_bits(value)?));
+        }
+        _ => return Err(format!("invalid parameter '{name}'")),
+      }
+    }
+    Ok(parsed)
+  }
+
+  /// Parses the `Sec-WebSocket-Extensions` header that was negotiated, which
+  /// is either empty or a single `permessage-deflate` element.
+  pub fn from_header(header: &str) -> Result<Option<Self>, AnyError> {
+    let extensions = parse_extensions(header);
+    let [(name, params)] = extensions.as_slice() else {
+      if extensions.is_empty() {
+        return Ok(None);
+      }
+      return Err(type_error(format!("Unexpected extensions '{header}'")));
+    };
+    if !name.eq_ignore_ascii_case(EXTENSION_NAME) {
+      return Err(type_error(format!("Unexpected extension '{name}'")));
+    }
+    let params = DeflateParams::parse(params).map_err(|err| {
+      type_error(format!("Invalid '{EXTENSION_NAME}' response: {err}"))
+    })?;
+    if params.client_max_window_bits == Some(None) {
+      return Err(type_error(format!(
+        "Invalid '{EXTENSION_NAME}' response: 'client_max_window_bits' needs a value"
+      )));
+    }
+    Ok(Some(params))
+  }
+
+  pub fn to_header(&self) -> String {
+    let mut header = EXTENSION_NAME.to_string();
+    if self.server_no_context_takeover {
+      header.push_str("; server_no_context_takeover");
+    }
+    if self.client_no_context_takeover {
+      header.push_str("; client_no_context_takeover");
+    }
+    if let Some(bits) = self.server_max_window_bits {
+      header.push_str(&format!("; server_max_window_bits={bits}"));
+    }
+    match self.client_max_window_bits {
+      Some(Some(bits)) => {
+        header.push_str(&format!("; client_max_window_bits={bits}"))
+      }
+      Some(None) => header.push_str("; client_max_window_bits"),
+      None => {}
+    }
+    header
+  }
+}
+
+fn parse_window_bits(value: &str) -> Result<u8, String> {
+  let value = value.trim_matches('"');
+  match value.parse::<u8>() {
+    Ok(bits)
+      if (MIN_WINDOW_BITS..=MAX_WINDOW_BITS).contains(&bits)
+        && !value.starts_with('0') =>
+    {
+      Ok(bits)
+    }
+    _ => Err(format!("invalid window size '{value}'")),
+  }
+}
+
+type Params<'a> = Vec<(&'a str, Option<&'a str>)>;
+
+/// Splits a `Sec-WebSocket-Extensions` header into its elements and their
+/// parameters.
+fn parse_extensions(header: &str) -> Vec<(&str, Params)> {
+  header
+    .split(',')
+    .filter(|element| !element.trim().is_empty())
+    .map(|element| {
+      let mut parts = element.split(';').map(str::trim);
+      let name = parts.next().unwrap_or_default();
+      let params = parts
+        .filter(|param| !param.is_empty())
+        .map(|param| match param.split_once('=') {
+          Some((name, value)) => (name.trim(), Some(value.trim())),
+          None => (param, None),
+        })
+        .collect();
+      (name, params)
+    })
+    .collect()
+}
+
+/// The `Sec-WebSocket-Extensions` header offered by a client.
+pub(crate) fn client_offer(options: &DeflateOptions) -> String {
+  DeflateParams {
+    server_no_context_takeover: options.server_no_context_takeover,
+    client_no_context_takeover: options.client_no_context_takeover,
+    server_max_window_bits: options.server_max_window_bits,
+    client_max_window_bits: None,
+  }
+  .to_header()
+}
+
+/// Checks the response of the server to [`client_offer`].
+pub(crate) fn client_accept(
+  options: &DeflateOptions,
+  response: &str,
+) -> Result<Option<DeflateParams>, AnyError> {
+  let Some(mut params) = DeflateParams::from_header(response)? else {
+    return Ok(None);
+  };
+  if params.client_max_window_bits.is_some() {
+    return Err(type_error(format!(
+      "Invalid '{EXTENSION_NAME}' response: 'client_max_window_bits' was not offered"
+    )));
+  }
+  if let (Some(offered), Some(bits)) = (
+    options.server_max_window_bits,
+    params.server_max_window_bits,
+  ) {
+    if bits > offered {
+      return Err(type_error(format!(
+        "Invalid '{EXTENSION_NAME}' response: 'server_max_window_bits' is larger than offered"
+      )));
+    }
+  }
+  params.client_no_context_takeover |= options.client_no_context_takeover;
+  Ok(Some(params))
+}
+
+/// Accepts the first offer of a client that can be served, returning the
+/// parameters to respond with.
+pub(crate) fn server_accept(
+  options: &DeflateOptions,
+  offers: &str,
+) -> Option<DeflateParams> {
+  parse_extensions(offers)
+    .iter()
+    .filter(|(name, _)| name.eq_ignore_ascii_case(EXTENSION_NAME))
+    .filter_map(|(_, params)| DeflateParams::parse(params).ok())
+    .find(|offer| {
+      offer
+        .server_max_window_bits
+        .map_or(true, |bits| bits == MAX_WINDOW_BITS)
+    })
+    .map(|offer| DeflateParams {
+      server_no_context_takeover: offer.server_no_context_takeover
+        || options.server_no_context_takeover,
+      client_no_context_takeover: offer.client_no_context_takeover
+        || options.client_no_context_takeover,
+      server_max_window_bits: offer.server_max_window_bits,
+      client_max_window_bits: match (
+        offer.client_max_window_bits,
+        options.client_max_window_bits,
+      ) {
+        (Some(offered), Some(bits)) => {
+          Some(Some(bits.min(offered.unwrap_or(MAX_WINDOW_BITS))))
+        }
+        _ => None,
+      },
+    })
+}
+
+pub(crate) struct Compressor {
+  inner: Compress,
+  no_context_takeover: bool,
+}
+
+impl Compressor {
+  pub fn new(no_context_takeover: bool) -> Self {
+    Self {
+      inner: Compress::new(Compression::default(), false),
+      no_context_takeover,
+    }
+  }
+
+  /// Compresses the payload of a message.
+  pub fn compress(&mut self, mut data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 2 + 64);
+    loop {
+      if out.capacity() - out.len() < 64 {
+        out.reserve(out.capacity());
+      }
+      let before = self.inner.total_in();
+      // Raw deflate streams can't fail while compressing.
+      self
+        .inner
+        .compress_vec(data, &mut out, FlushCompress::Sync)
+        .unwrap();
+      data = &data[(self.inner.total_in() - before) as usize..];
+      if data.is_empty() && out.len() < out.capacity() {
+        break;
+      }
+    }
+    if out.ends_with(&SYNC_FLUSH_TAIL) {
+      out.truncate(out.len() - SYNC_FLUSH_TAIL.len());
+    }
+    if self.no_context_takeover {
+      self.inner.reset();
+    }
+    out
+  }
+}
+
+#[derive(Debug)]
+pub(crate) enum DecompressError {
+  Invalid,
+  TooBig,
+}
+
+pub(crate) struct Decompressor {
+  inner: Decompress,
+  no_context_takeover: bool,
+}
+
+impl Decompressor {
+  pub fn new(no_context_takeover: bool) -> Self {
+    Self {
+      inner: Decompress::new(false),
+      no_context_takeover,
+    }
+  }
+
+  /// Decompresses the payload of a frame of a compressed message into `out`,
+  /// failing once `out` would grow beyond `limit` bytes.
+  pub fn decompress(
+    &mut self,
+    data: &[u8],
+    fin: bool,
+    limit: usize,
+    out: &mut Vec<u8>,
+  ) -> Result<(), DecompressError> {
+    self.feed(data, limit, out)?;
+    if fin {
+      self.feed(&SYNC_FLUSH_TAIL, limit, out)?;
+      if self.no_context_takeover {
+        self.inner.reset(false);
+      }
+    }
+    Ok(())
+  }
+
+  fn feed(
+    &mut self,
+    mut data: &[u8],
+    limit: usize,
+    out: &mut Vec<u8>,
+  ) -> Result<(), DecompressError> {
+    loop {
+      if out.capacity() - out.len() < 1024 {
+        out.reserve(out.capacity().max(4096));
+      }
+      let (before_in, before_out) =
+        (self.inner.total_in(), self.inner.total_out());
+      let status = self
+        .inner
+        .decompress_vec(data, out, FlushDecompress::Sync)
+        .map_err(|_| DecompressError::Invalid)?;
+      if out.len() > limit {
+        return Err(DecompressError::TooBig);
+      }
+      data = &data[(self.inner.total_in() - before_in) as usize..];
+      let progress = self.inner.total_in() != before_in
+        || self.inner.total_out() != before_out;
+      if status == Status::StreamEnd {
+        // A final block ends the stream, the next message starts a new one.
+        self.inner.reset(false);
+        if data.is_empty() {
+          return Ok(());
+        }
+      } else if data.is_empty() && out.len() < out.capacity() {
+        return Ok(());
+      } else if !progress {
+        return if data.is_empty() {
+          Ok(())
+        } else {
+          Err(DecompressError::Invalid)
+        };
+      }
+    }
+  }
+}
@@ -0,0 +1,358 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! A filter over the raw frames of a WebSocket connection, below
+//! `fastwebsockets`, which neither supports the reserved bits nor limits the
+//! size of fragmented messages.
+//!
+//! Incoming frames of compressed messages are inflated and handed on without
+//! RSV1, and the size limits are checked before a frame is buffered. Outgoing
+//! data frames, whose payloads were compressed before they were written, get
+//! RSV1 set.
+
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::task::ready;
+use std::task::Context;
+use std::task::Poll;
+
+use bytes::Buf;
+use bytes::BytesMut;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::io::ReadBuf;
+
+use crate::deflate::DecompressError;
+use crate::deflate::Decompressor;
+
+/// The limit of `fastwebsockets` for a single frame, also used for messages
+/// that are inflated.
+pub(crate) const DEFAULT_MAX_SIZE: usize = 64 << 20;
+
+const RSV1: u8 = 0b0100_0000;
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+
+#[derive(Debug)]
+pub(crate) struct MessageTooBig;
+
+impl fmt::Display for MessageTooBig {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("WebSocket message is too big")
+  }
+}
+
+impl std::error::Error for MessageTooBig {}
+
+struct FrameHeader {
+  fin: bool,
+  rsv1: bool,
+  opcode: u8,
+  mask: Option<[u8; 4]>,
+  header_len: usize,
+  payload_len: usize,
+}
+
+impl FrameHeader {
+  /// Parses the header at the start of `buf`, if it is complete.
+  fn parse(buf: &[u8]) -> io::Result<Option<Self>> {
+    if buf.len() < 2 {
+      return Ok(None);
+    }
+    let masked = buf[1] & 0x80 != 0;
+    let (extra, payload_len) = match buf[1] & 0x7f {
+      126 => (
+        2,
+        buf
+          .get(2..4)
+          .map(|b| u16::from_be_bytes([b[0], b[1]]) as u64),
+      ),
+      127 => (
+        8,
+        buf
+          .get(2..10)
+          .map(|b| u64::from_be_bytes(b.try_into().unwrap())),
+      ),
+      len => (0, Some(len as u64)),
+    };
+    let header_len = 2 + extra + if masked { 4 } else { 0 };
+    let Some(payload_len) = payload_len.filter(|_| buf.len() >= header_len)
+    else {
+      return Ok(None);
+    };
+    let payload_len = usize::try_from(payload_len)
+      .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, MessageTooBig))?;
+    let mask = masked.then(|| {
+      let offset = header_len - 4;
+      buf[offset..header_len].try_into().unwrap()
+    });
+    Ok(Some(FrameHeader {
+      fin: buf[0] & 0x80 != 0,
+      rsv1: buf[0] & RSV1 != 0,
+      opcode: buf[0] & 0x0f,
+      mask,
+      header_len,
+      payload_len,
+    }))
+  }
+
+  fn is_control(&self) -> bool {
+    self.opcode & 0x08 != 0
+  }
+
+  /// An unmasked header without reserved bits for a payload of `len` bytes.
+  fn write_unmasked(&self, len: usize, out: &mut BytesMut) {
+    out.extend_from_slice(&[((self.fin as u8) << 7) | self.opcode]);
+    if len < 126 {
+      out.extend_from_slice(&[len as u8]);
+    } else if len <= u16::MAX as usize {
+      out.extend_from_slice(&[126]);
+      out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+      out.extend_from_slice(&[127]);
+      out.extend_from_slice(&(len as u64).to_be_bytes());
+    }
+  }
+}
+
+#[derive(Default)]
+pub(crate) struct FrameFilterConfig {
+  /// Inflates messages with RSV1 set.
+  pub decompressor: Option<Decompressor>,
+  /// Sets RSV1 on outgoing data frames.
+  pub compressed_writes: bool,
+  pub max_frame_size: Option<usize>,
+  pub max_message_size: Option<usize>,
+}
+
+impl FrameFilterConfig {
+  fn is_active(&self) -> bool {
+    self.decompressor.is_some()
+      || self.compressed_writes
+      || self.max_frame_size.is_some()
+      || self.max_message_size.is_some()
+  }
+}
+
+pub(crate) struct FrameFilter<S> {
+  inner: S,
+  active: bool,
+  config: FrameFilterConfig,
+  /// Bytes read from `inner` that were not processed yet.
+  read_buf: BytesMut,
+  /// Processed bytes for the reader.
+  out_buf: BytesMut,
+  /// Payload bytes of the current incoming frame to copy as they are.
+  passthrough: usize,
+  message_size: usize,
+  message_compressed: bool,
+  /// Bytes of the current outgoing frame that were not written yet.
+  write_remaining: usize,
+  write_scratch: Vec<u8>,
+}
+
+impl<S> FrameFilter<S> {
+  pub fn new(inner: S, config: FrameFilterConfig) -> Self {
+    Self {
+      inner,
+      active: config.is_active(),
+      config,
+      read_buf: BytesMut::new(),
+      out_buf: BytesMut::new(),
+      passthrough: 0,
+      message_size: 0,
+      message_compressed: false,
+      write_remaining: 0,
+      write_scratch: Vec::new(),
+    }
+  }
+
+  /// Processes the frame at the start of `read_buf`. Returns `false` if more
+  /// bytes are needed.
+  fn process_frame(&mut self, header: FrameHeader) -> io::Result<bool> {
+    let too_big = || io::Error::new(io::ErrorKind::InvalidData, MessageTooBig);
+    let len = header.payload_len;
+    if header.is_control() {
+      // Control frames may be interleaved with the frames of a message.
+      self.forward_header(&header);
+      return Ok(true);
+    }
+    if len > self.config.max_frame_size.unwrap_or(usize::MAX) {
+      return Err(too_big());
+    }
+    if header.opcode != OPCODE_CONTINUATION {
+      self.message_size = 0;
+      self.message_compressed =
+        header.rsv1 && self.config.decompressor.is_some();
+    }
+    let max_message_size = self.config.max_message_size;
+
+    if !self.message_compressed {
+      self.message_size += len;
+      if self.message_size > max_message_size.unwrap_or(usize::MAX) {
+        return Err(too_big());
+      }
+      self.forward_header(&header);
+      return Ok(true);
+    }
+
+    if len > self.config.max_frame_size.unwrap_or(DEFAULT_MAX_SIZE) {
+      return Err(too_big());
+    }
+    if self.read_buf.len() < header.header_len + len {
+      self
+        .read_buf
+        .reserve(header.header_len + len - self.read_buf.len());
+      return Ok(false);
+    }
+    self.read_buf.advance(header.header_len);
+    let mut payload = self.read_buf.split_to(len);
+    if let Some(mask) = header.mask {
+      for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i & 3];
+      }
+    }
+    let limit = max_message_size
+      .unwrap_or(DEFAULT_MAX_SIZE)
+      .saturating_sub(self.message_size);
+    let mut data = Vec::new();
+    self
+      .config
+      .decompressor
+      .as_mut()
+      .unwrap()
+      .decompress(&payload, header.fin, limit, &mut data)
+      .map_err(|err| match err {
+        DecompressError::TooBig => too_big(),
+        DecompressError::Invalid => io::Error::new(
+          io::ErrorKind::InvalidData,
+          "Invalid compressed WebSocket message",
+        ),
+      })?;
+    self.message_size += data.len();
+    header.write_unmasked(data.len(), &mut self.out_buf);
+    self.out_buf.extend_from_slice(&data);
+    Ok(true)
+  }
+
+  fn forward_header(&mut self, header: &FrameHeader) {
+    let header_bytes = self.read_buf.split_to(header.header_len);
+    self.out_buf.extend_from_slice(&header_bytes);
+    self.passthrough = header.payload_len;
+  }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for FrameFilter<S> {
+  fn poll_read(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: &mut ReadBuf<'_>,
+  ) -> Poll<io::Result<()>> {
+    if !self.active {
+      return Pin::new(&mut self.inner).poll_read(cx, buf);
+    }
+    let this = &mut *self;
+    loop {
+      if !this.out_buf.is_empty() {
+        let len = this.out_buf.len().min(buf.remaining());
+        buf.put_slice(&this.out_buf.split_to(len));
+        return Poll::Ready(Ok(()));
+      }
+      if !this.read_buf.is_empty() {
+        if this.passthrough > 0 {
+          let len = this.passthrough.min(this.read_buf.len());
+          let payload = this.read_buf.split_to(len);
+          this.out_buf.extend_from_slice(&payload);
+          this.passthrough -= len;
+          continue;
+        }
+        if let Some(header) = FrameHeader::parse(&this.read_buf)? {
+          if this.process_frame(header)? {
+            continue;
+          }
+        }
+      }
+
+      let mut chunk = [0; 16 * 1024];
+      let mut chunk = ReadBuf::new(&mut chunk);
+      ready!(Pin::new(&mut this.inner).poll_read(cx, &mut chunk))?;
+      if chunk.filled().is_empty() {
+        // Leave incomplete frames to the reader, which reports the EOF.
+        if this.read_buf.is_empty() {
+          return Poll::Ready(Ok(()));
+        }
+        let rest = this.read_buf.split();
+        this.out_buf.extend_from_slice(&rest);
+        continue;
+      }
+      this.read_buf.extend_from_slice(chunk.filled());
+    }
+  }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for FrameFilter<S> {
+  fn poll_write(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: &[u8],
+  ) -> Poll<io::Result<usize>> {
+    if !self.config.compressed_writes {
+      return Pin::new(&mut self.inner).poll_write(cx, buf);
+    }
+    let this = &mut *self;
+    if this.write_remaining > 0 {
+      let len = this.write_remaining.min(buf.len());
+      let written =
+        ready!(Pin::new(&mut this.inner).poll_write(cx, &buf[..len]))?;
+      this.write_remaining -= written;
+      return Poll::Ready(Ok(written));
+    }
+
+    // Frames are written with a single buffer that starts with the header.
+    let Some(header) = FrameHeader::parse(buf)? else {
+      return Pin::new(&mut this.inner).poll_write(cx, buf);
+    };
+    let frame_len = (header.header_len + header.payload_len).min(buf.len());
+    let written = if matches!(header.opcode, OPCODE_TEXT | OPCODE_BINARY) {
+      this.write_scratch.clear();
+      this.write_scratch.extend_from_slice(&buf[..frame_len]);
+      this.write_scratch[0] |= RSV1;
+      ready!(Pin::new(&mut this.inner).poll_write(cx, &this.write_scratch))?
+    } else {
+      ready!(Pin::new(&mut this.inner).poll_write(cx, &buf[..frame_len]))?
+    };
+    this.write_remaining = header.header_len + header.payload_len - written;
+    Poll::Ready(Ok(written))
+  }
+
+  fn poll_flush(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<io::Result<()>> {
+    Pin::new(&mut self.inner).poll_flush(cx)
+  }
+
+  fn poll_shutdown(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<io::Result<()>> {
+    Pin::new(&mut self.inner).poll_shutdown(cx)
+  }
+
+  fn is_write_vectored(&self) -> bool {
+    !self.config.compressed_writes && self.inner.is_write_vectored()
+  }
+
+  fn poll_write_vectored(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    bufs: &[io::IoSlice<'_>],
+  ) -> Poll<io::Result<usize>> {
+    if self.config.compressed_writes {
+      let buf = bufs.iter().find(|b| !b.is_empty()).map_or(&[][..], |b| b);
+      return self.poll_write(cx, buf);
+    }
+    Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+  }
+}
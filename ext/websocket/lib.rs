@@ -1,4 +1,11 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+use crate::credit::StreamCredit;
+use crate::deflate::Compressor;
+use crate::deflate::Decompressor;
+use crate::deflate::DeflateParams;
+use crate::frames::FrameFilter;
+use crate::frames::FrameFilterConfig;
+use crate::frames::MessageTooBig;
 use crate::stream::WebSocketStream;
 use bytes::Bytes;
 use deno_core::anyhow::bail;
@@ -39,6 +46,7 @@ use once_cell::sync::Lazy;
 use rustls_tokio_stream::rustls::pki_types::ServerName;
 use rustls_tokio_stream::rustls::RootCertStore;
 use rustls_tokio_stream::TlsStream;
+use serde::Deserialize;
 use serde::Serialize;
 use std::borrow::Cow;
 use std::cell::Cell;
@@ -61,10 +69,18 @@ use fastwebsockets::Frame;
 use fastwebsockets::OpCode;
 use fastwebsockets::Role;
 use fastwebsockets::WebSocket;
+use fastwebsockets::WebSocketError;
 use fastwebsockets::WebSocketWrite;
 
+mod credit;
+mod deflate;
+mod frames;
 mod stream;
 
+pub use deflate::DeflateOptions;
+
+type FilteredStream = FrameFilter<WebSocketStream>;
+
 static USE_WRITEV: Lazy<bool> = Lazy::new(|| {
   let enable = std::env::var("DENO_USE_WRITEV").ok();
 
@@ -155,6 +171,33 @@ where
   }
 }
 
+/// Options of a client WebSocket, as passed to `WebSocketStream`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebSocketClientOptions {
+  deflate: Option<DeflateOptions>,
+  max_frame_size: Option<usize>,
+  max_message_size: Option<usize>,
+}
+
+/// Options of a server WebSocket, as passed to `Deno.upgradeWebSocket`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebSocketServerOptions {
+  /// The `Sec-WebSocket-Extensions` header the upgrade was answered with,
+  /// see `op_ws_server_negotiate_deflate`.
+  extensions: Option<String>,
+  max_frame_size: Option<usize>,
+  max_message_size: Option<usize>,
+}
+
+struct StreamOptions {
+  role: Role,
+  deflate: Option<DeflateParams>,
+  max_frame_size: Option<usize>,
+  max_message_size: Option<usize>,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateResponse {
@@ -168,6 +211,7 @@ async fn handshake_websocket(
   uri: &Uri,
   protocols: &str,
   headers: Option<Vec<(ByteString, ByteString)>>,
+  extensions: Option<&str>,
 ) -> Result<(WebSocket<WebSocketStream>, http::HeaderMap), AnyError> {
   let mut request = Request::builder().method(Method::GET).uri(
     uri
@@ -191,8 +235,13 @@ async fn handshake_websocket(
     );
 
   let user_agent = state.borrow().borrow::<WsUserAgent>().0.clone();
-  request =
-    populate_common_request_headers(request, &user_agent, protocols, &headers)?;
+  request = populate_common_request_headers(
+    request,
+    &user_agent,
+    protocols,
+    &headers,
+    extensions,
+  )?;
 
   let request = request.body(http_body_util::Empty::new())?;
   let domain = &uri.host().unwrap().to_string();
@@ -217,6 +266,7 @@ async fn handshake_websocket(
             protocols,
             domain,
             &headers,
+            extensions,
             &addr,
           )
           .await?
@@ -265,6 +315,7 @@ async fn handshake_http2_wss(
   protocols: &str,
   domain: &str,
   headers: &Option<Vec<(ByteString, ByteString)>>,
+  extensions: Option<&str>,
   addr: &str,
 ) -> Result<(WebSocket<WebSocketStream>, http::HeaderMap), AnyError> {
   let tcp_socket = TcpStream::connect(addr).await?;
@@ -292,8 +343,9 @@ async fn handshake_http2_wss(
     .scheme("https")
     .build()?;
   request = request.uri(uri);
-  request =
-    populate_common_request_headers(request, user_agent, protocols, headers)?;
+  request = populate_common_request_headers(
+    request, user_agent, protocols, headers, extensions,
+  )?;
   request = request.extension(h2::ext::Protocol::from("websocket"));
   let (resp, send) = send.send_request(request.body(())?, false)?;
   let resp = resp.await?;
@@ -348,6 +400,7 @@ pub fn create_ws_client_config(
     unsafely_ignore_certificate_errors,
     TlsKeys::Null,
     socket_use,
+    vec![],
   )
 }
 
@@ -357,6 +410,7 @@ fn populate_common_request_headers(
   user_agent: &str,
   protocols: &str,
   headers: &Option<Vec<(ByteString, ByteString)>>,
+  extensions: Option<&str>,
 ) -> Result<http::request::Builder, AnyError> {
   request = request
     .header("User-Agent", user_agent)
@@ -366,6 +420,10 @@ fn populate_common_request_headers(
     request = request.header("Sec-WebSocket-Protocol", protocols);
   }
 
+  if let Some(extensions) = extensions {
+    request = request.header("Sec-WebSocket-Extensions", extensions);
+  }
+
   if let Some(headers) = headers {
     for (key, value) in headers {
       let name = HeaderName::from_bytes(key)
@@ -401,6 +459,7 @@ pub async fn op_ws_create<WP>(
   #[string] protocols: String,
   #[smi] cancel_handle: Option<ResourceId>,
   #[serde] headers: Option<Vec<(ByteString, ByteString)>>,
+  #[serde] options: Option<WebSocketClientOptions>,
 ) -> Result<CreateResponse, AnyError>
 where
   WP: WebSocketPermissions + 'static,
@@ -425,13 +484,16 @@ where
   };
 
   let uri: Uri = url.parse()?;
-
-  let handshake = handshake_websocket(&state, &uri, &protocols, headers)
-    .map_err(|err| {
-      AnyError::from(DomExceptionNetworkError::new(&format!(
-        "failed to connect to WebSocket: {err}"
-      )))
-    });
+  let options = options.unwrap_or_default();
+  let offer = options.deflate.as_ref().map(deflate::client_offer);
+
+  let handshake =
+    handshake_websocket(&state, &uri, &protocols, headers, offer.as_deref())
+      .map_err(|err| {
+        AnyError::from(DomExceptionNetworkError::new(&format!(
+          "failed to connect to WebSocket: {err}"
+        )))
+      });
   let (stream, response) = match cancel_resource {
     Some(rc) => handshake.try_or_cancel(rc).await,
     None => handshake.await,
@@ -443,9 +505,6 @@ where
     }
   }
 
-  let mut state = state.borrow_mut();
-  let rid = state.resource_table.add(ServerWebSocket::new(stream));
-
   let protocol = match response.get("Sec-WebSocket-Protocol") {
     Some(header) => header.to_str().unwrap(),
     None => "",
@@ -454,7 +513,30 @@ where
     .get_all("Sec-WebSocket-Extensions")
     .iter()
     .map(|header| header.to_str().unwrap())
-    .collect::<String>();
+    .collect::<Vec<_>>()
+    .join(", ");
+  let deflate = match &options.deflate {
+    Some(deflate) => {
+      deflate::client_accept(deflate, &extensions).map_err(|err| {
+        DomExceptionNetworkError::new(&format!(
+          "failed to connect to WebSocket: {err}"
+        ))
+      })?
+    }
+    None => None,
+  };
+
+  let mut state = state.borrow_mut();
+  let rid = state.resource_table.add(ServerWebSocket::new(
+    stream,
+    StreamOptions {
+      role: Role::Client,
+      deflate,
+      max_frame_size: options.max_frame_size,
+      max_message_size: options.max_message_size,
+    },
+  ));
+
   Ok(CreateResponse {
     rid,
     protocol: protocol.to_string(),
@@ -479,13 +561,57 @@ pub struct ServerWebSocket {
   closed: Cell<bool>,
   buffer: Cell<Option<Vec<u8>>>,
   string: Cell<Option<String>>,
-  ws_read: AsyncRefCell<FragmentCollectorRead<ReadHalf<WebSocketStream>>>,
-  ws_write: AsyncRefCell<WebSocketWrite<WriteHalf<WebSocketStream>>>,
+  /// Compresses outgoing messages if `permessage-deflate` was negotiated.
+  compressor: Option<RefCell<Compressor>>,
+  ws_read: AsyncRefCell<FragmentCollectorRead<ReadHalf<FilteredStream>>>,
+  ws_write: AsyncRefCell<WebSocketWrite<WriteHalf<FilteredStream>>>,
+  /// Flow control of the `op_ws_stream_*` ops.
+  credit: StreamCredit,
 }
 
 impl ServerWebSocket {
-  fn new(ws: WebSocket<WebSocketStream>) -> Self {
-    let (ws_read, ws_write) = ws.split(tokio::io::split);
+  fn new(mut ws: WebSocket<WebSocketStream>, options: StreamOptions) -> Self {
+    let (compressor, decompressor) = match &options.deflate {
+      Some(params) => {
+        let (ours, theirs) = if options.role == Role::Server {
+          (
+            params.server_no_context_takeover,
+            params.client_no_context_takeover,
+          )
+        } else {
+          (
+            params.client_no_context_takeover,
+            params.server_no_context_takeover,
+          )
+        };
+        (Some(Compressor::new(ours)), Some(Decompressor::new(theirs)))
+      }
+      None => (None, None),
+    };
+    if compressor.is_some() {
+      // The filter sets RSV1 on the header of a frame, which must be written
+      // together with the payload.
+      ws.set_writev(false);
+    }
+    // Inflated frames and large limits are checked by the filter instead.
+    if let Some(max) = options.max_frame_size.max(options.max_message_size) {
+      if max >= frames::DEFAULT_MAX_SIZE {
+        ws.set_max_message_size(max.saturating_add(1));
+      }
+    }
+
+    let config = Cell::new(Some(FrameFilterConfig {
+      decompressor,
+      compressed_writes: compressor.is_some(),
+      max_frame_size: options.max_frame_size,
+      max_message_size: options.max_message_size,
+    }));
+    let (ws_read, ws_write) = ws.split(|stream| {
+      tokio::io::split(FrameFilter::new(
+        stream,
+        config.take().unwrap_or_default(),
+      ))
+    });
     Self {
       buffered: Cell::new(0),
       error: Cell::new(None),
@@ -493,8 +619,10 @@ impl ServerWebSocket {
       closed: Cell::new(false),
       buffer: Cell::new(None),
       string: Cell::new(None),
+      compressor: compressor.map(RefCell::new),
       ws_read: AsyncRefCell::new(FragmentCollectorRead::new(ws_read)),
       ws_write: AsyncRefCell::new(ws_write),
+      credit: StreamCredit::default(),
     }
   }
 
@@ -511,20 +639,29 @@ impl ServerWebSocket {
   /// Reserve a lock, but don't wait on it. This gets us our place in line.
   fn reserve_lock(
     self: &Rc<Self>,
-  ) -> AsyncMutFuture<WebSocketWrite<WriteHalf<WebSocketStream>>> {
+  ) -> AsyncMutFuture<WebSocketWrite<WriteHalf<FilteredStream>>> {
     RcRef::map(self, |r| &r.ws_write).borrow_mut()
   }
 
   #[inline]
   async fn write_frame(
     self: &Rc<Self>,
-    lock: AsyncMutFuture<WebSocketWrite<WriteHalf<WebSocketStream>>>,
+    lock: AsyncMutFuture<WebSocketWrite<WriteHalf<FilteredStream>>>,
     frame: Frame<'_>,
   ) -> Result<(), AnyError> {
     let mut ws = lock.await;
     if ws.is_closed() {
       return Ok(());
     }
+    let frame = match &self.compressor {
+      Some(compressor)
+        if matches!(frame.opcode, OpCode::Text | OpCode::Binary) =>
+      {
+        let payload = compressor.borrow_mut().compress(&frame.payload);
+        Frame::new(frame.fin, frame.opcode, None, payload.into())
+      }
+      _ => frame,
+    };
     ws.write_frame(frame)
       .await
       .map_err(|err| type_error(err.to_string()))?;
@@ -536,13 +673,22 @@ impl Resource for ServerWebSocket {
   fn name(&self) -> Cow<str> {
     "serverWebSocket".into()
   }
+
+  fn close(self: Rc<Self>) {
+    self.credit.cancel.cancel();
+  }
 }
 
 pub fn ws_create_server_stream(
   state: &mut OpState,
   transport: NetworkStream,
   read_buf: Bytes,
+  options: WebSocketServerOptions,
 ) -> Result<ResourceId, AnyError> {
+  let deflate = match &options.extensions {
+    Some(extensions) => DeflateParams::from_header(extensions)?,
+    None => None,
+  };
   let mut ws = WebSocket::after_handshake(
     WebSocketStream::new(
       stream::WsStreamKind::Network(transport),
@@ -554,10 +700,29 @@ pub fn ws_create_server_stream(
   ws.set_auto_close(true);
   ws.set_auto_pong(true);
 
-  let rid = state.resource_table.add(ServerWebSocket::new(ws));
+  let rid = state.resource_table.add(ServerWebSocket::new(
+    ws,
+    StreamOptions {
+      role: Role::Server,
+      deflate,
+      max_frame_size: options.max_frame_size,
+      max_message_size: options.max_message_size,
+    },
+  ));
   Ok(rid)
 }
 
+/// Picks the `permessage-deflate` offer of a client to accept, returning the
+/// `Sec-WebSocket-Extensions` header to respond with.
+#[op2]
+#[string]
+pub fn op_ws_server_negotiate_deflate(
+  #[string] offers: String,
+  #[serde] options: DeflateOptions,
+) -> Option<String> {
+  deflate::server_accept(&options, &offers).map(|params| params.to_header())
+}
+
 fn send_binary(state: &mut OpState, rid: ResourceId, data: &[u8]) {
   let resource = state.resource_table.get::<ServerWebSocket>(rid).unwrap();
   let data = data.to_vec();
@@ -764,13 +929,17 @@ pub async fn op_ws_next_event(
     return MessageKind::Error as u16;
   };
 
+  next_event(&resource).await
+}
+
+async fn next_event(resource: &Rc<ServerWebSocket>) -> u16 {
   // If there's a pending error, this always returns error
   if resource.errored.get() {
     return MessageKind::Error as u16;
   }
 
-  let mut ws = RcRef::map(&resource, |r| &r.ws_read).borrow_mut().await;
-  let writer = RcRef::map(&resource, |r| &r.ws_write);
+  let mut ws = RcRef::map(resource, |r| &r.ws_read).borrow_mut().await;
+  let writer = RcRef::map(resource, |r| &r.ws_write);
   let mut sender = move |frame| {
     let writer = writer.clone();
     async move { writer.borrow_mut().await.write_frame(frame).await }
@@ -786,6 +955,12 @@ pub async fn op_ws_next_event(
           return MessageKind::ClosedDefault as u16;
         }
 
+        if is_message_too_big(&err) {
+          // Let the peer know why the connection is closed.
+          let lock = resource.reserve_lock();
+          let frame = Frame::close(1009, b"Message too big");
+          _ = resource.write_frame(lock, frame).await;
+        }
         resource.set_error(Some(err.to_string()));
         return MessageKind::Error as u16;
       }
@@ -829,6 +1004,16 @@ pub async fn op_ws_next_event(
   }
 }
 
+fn is_message_too_big(err: &WebSocketError) -> bool {
+  match err {
+    WebSocketError::FrameTooLarge => true,
+    WebSocketError::IoError(err) => {
+      err.get_ref().is_some_and(|err| err.is::<MessageTooBig>())
+    }
+    _ => false,
+  }
+}
+
 deno_core::extension!(deno_websocket,
   deps = [ deno_url, deno_webidl ],
   parameters = [P: WebSocketPermissions],
@@ -847,6 +1032,12 @@ deno_core::extension!(deno_websocket,
     op_ws_send_text_async,
     op_ws_send_ping,
     op_ws_get_buffered_amount,
+    op_ws_server_negotiate_deflate,
+    credit::op_ws_stream_read_ahead,
+    credit::op_ws_stream_next,
+    credit::op_ws_stream_send_text,
+    credit::op_ws_stream_send_binary,
+    credit::op_ws_stream_drain,
   ],
   esm = [ "01_websocket.js", "02_websocketstream.js" ],
   options = {
@@ -120,6 +120,19 @@ fn get_url_parse_error_class(_error: &url::ParseError) -> &'static str {
   "URIError"
 }
 
+fn get_process_error_class(
+  error: &crate::ops::process::ProcessError,
+) -> &'static str {
+  use crate::ops::process::ProcessError::*;
+  match error {
+    NotFound { .. } => "NotFound",
+    PermissionDenied { .. } => "PermissionDenied",
+    CwdNotFound { .. } => "CwdNotFound",
+    CwdNotDirectory { .. } => "CwdNotDirectory",
+    Io { source, .. } => get_io_error_class(source),
+  }
+}
+
 fn get_hyper_error_class(_error: &hyper::Error) -> &'static str {
   "Http"
 }
@@ -186,6 +199,10 @@ pub fn get_error_class_name(e: &AnyError) -> Option<&'static str> {
       e.downcast_ref::<env::VarError>()
         .map(get_env_var_error_class)
     })
+    .or_else(|| {
+      e.downcast_ref::<crate::ops::process::ProcessError>()
+        .map(get_process_error_class)
+    })
     .or_else(|| e.downcast_ref::<io::Error>().map(get_io_error_class))
     .or_else(|| {
       e.downcast_ref::<ModuleResolutionError>()
@@ -151,6 +151,27 @@ class NotADirectory extends Error {
   }
 }
 
+class MaxBufferExceeded extends Error {
+  constructor(msg) {
+    super(msg);
+    this.name = "MaxBufferExceeded";
+  }
+}
+
+class CwdNotFound extends NotFound {
+  constructor(msg) {
+    super(msg);
+    this.name = "CwdNotFound";
+  }
+}
+
+class CwdNotDirectory extends NotFound {
+  constructor(msg) {
+    super(msg);
+    this.name = "CwdNotDirectory";
+  }
+}
+
 const errors = {
   NotFound,
   PermissionDenied,
@@ -176,6 +197,9 @@ const errors = {
   IsADirectory,
   NetworkUnreachable,
   NotADirectory,
+  MaxBufferExceeded,
+  CwdNotFound,
+  CwdNotDirectory,
 };
 
 export { errors };
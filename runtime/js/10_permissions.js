@@ -2,9 +2,12 @@
 
 import { primordials } from "ext:core/mod.js";
 import {
+  op_mint_permission_token,
+  op_permissions_snapshot,
   op_query_permission,
   op_request_permission,
   op_revoke_permission,
+  op_revoke_permission_token,
 } from "ext:core/ops";
 const {
   ArrayIsArray,
@@ -15,6 +18,7 @@ const {
   MapPrototypeHas,
   MapPrototypeSet,
   FunctionPrototypeCall,
+  JSONStringify,
   PromiseResolve,
   PromiseReject,
   ReflectHas,
@@ -58,10 +62,11 @@ function opQuery(desc) {
 
 /**
  * @param {Deno.PermissionDescriptor} desc
+ * @param {boolean} workers
  * @returns {Deno.PermissionState}
  */
-function opRevoke(desc) {
-  return op_revoke_permission(desc);
+function opRevoke(desc, workers) {
+  return op_revoke_permission(desc, workers);
 }
 
 /**
@@ -140,6 +145,9 @@ function cache(desc, rawStatus) {
     key += `-${desc.host}&`;
   } else if (desc.name === "run" && desc.command) {
     key += `-${desc.command}&`;
+    if (desc.args !== undefined || desc.env !== undefined) {
+      key += JSONStringify([desc.args, desc.env]);
+    }
   } else if (desc.name === "env" && desc.variable) {
     key += `-${desc.variable}&`;
   } else if (desc.name === "sys" && desc.kind) {
@@ -219,15 +227,15 @@ class Permissions {
     return cache(desc, status);
   }
 
-  revoke(desc) {
+  revoke(desc, options) {
     try {
-      return PromiseResolve(this.revokeSync(desc));
+      return PromiseResolve(this.revokeSync(desc, options));
     } catch (error) {
       return PromiseReject(error);
     }
   }
 
-  revokeSync(desc) {
+  revokeSync(desc, options = { __proto__: null }) {
     if (!isValidDescriptor(desc)) {
       throw new TypeError(
         `The provided value "${desc?.name}" is not a valid permission name.`,
@@ -236,7 +244,7 @@ class Permissions {
 
     formDescriptor(desc);
 
-    const status = opRevoke(desc);
+    const status = opRevoke(desc, !!options?.workers);
     return cache(desc, status);
   }
 
@@ -260,6 +268,23 @@ class Permissions {
     const status = opRequest(desc);
     return cache(desc, status);
   }
+
+  snapshot() {
+    return op_permissions_snapshot();
+  }
+
+  mintToken(desc) {
+    if (desc?.name !== "run" || desc.command == null) {
+      throw new TypeError(
+        "Permission tokens can only be minted for running a command.",
+      );
+    }
+    return op_mint_permission_token(pathFromURL(desc.command));
+  }
+
+  revokeToken(token) {
+    return op_revoke_permission_token(String(token));
+  }
 }
 
 const permissions = new Permissions(illegalConstructorKey);
@@ -2,16 +2,20 @@
 
 import { core, primordials } from "ext:core/mod.js";
 import {
+  op_bootstrap_numcpus,
   op_create_worker,
   op_host_post_message,
   op_host_recv_ctrl,
   op_host_recv_message,
   op_host_terminate_worker,
+  op_worker_pool_create,
+  op_worker_pool_run,
 } from "ext:core/ops";
 const {
   ArrayPrototypeFilter,
   Error,
   ObjectPrototypeIsPrototypeOf,
+  RangeError,
   String,
   StringPrototypeStartsWith,
   Symbol,
@@ -23,6 +27,7 @@ const {
 import * as webidl from "ext:deno_webidl/00_webidl.js";
 import { createFilteredInspectProxy } from "ext:deno_console/01_console.js";
 import { URL } from "ext:deno_url/00_url.js";
+import { SymbolDispose } from "ext:deno_web/00_infra.js";
 import { getLocationHref } from "ext:deno_web/12_location.js";
 import { serializePermissions } from "ext:runtime/10_permissions.js";
 import { log } from "ext:runtime/06_util.js";
@@ -44,18 +49,22 @@ function createWorker(
   hasSourceCode,
   sourceCode,
   permissions,
+  permissionsSnapshot,
   name,
   workerType,
   closeOnIdle,
+  limits,
 ) {
   return op_create_worker({
     hasSourceCode,
     name,
     permissions: serializePermissions(permissions),
+    permissionsSnapshot,
     sourceCode,
     specifier,
     workerType,
     closeOnIdle,
+    limits,
   });
 }
 
@@ -101,6 +110,14 @@ class Worker extends EventTarget {
     } = options;
 
     const workerType = webidl.converters["WorkerType"](type);
+    let limits = null;
+    if (deno?.limits !== undefined) {
+      limits = webidl.converters["WorkerLimits"](
+        deno.limits,
+        "Failed to construct 'Worker'",
+        "'deno.limits'",
+      );
+    }
 
     if (
       StringPrototypeStartsWith(specifier, "./") ||
@@ -128,9 +145,11 @@ class Worker extends EventTarget {
       hasSourceCode,
       sourceCode,
       deno?.permissions,
+      deno?.permissionsSnapshot,
       this.#name,
       workerType,
       false,
+      limits,
     );
     this.#id = id;
     this.#pollControl();
@@ -320,4 +339,126 @@ webidl.converters["WorkerType"] = webidl.createEnumConverter("WorkerType", [
   "module",
 ]);
 
-export { Worker };
+function positiveLimitConverter(V, prefix, context, opts) {
+  const limit = webidl.converters["unsigned long long"](V, prefix, context, {
+    ...opts,
+    enforceRange: true,
+  });
+  if (limit === 0) {
+    throw new RangeError(`${prefix}: ${context} must be greater than 0`);
+  }
+  return limit;
+}
+
+webidl.converters["WorkerLimits"] = webidl.createDictionaryConverter(
+  "WorkerLimits",
+  [
+    { key: "heapMb", converter: positiveLimitConverter },
+    { key: "cpuMs", converter: positiveLimitConverter },
+  ],
+);
+
+class WorkerPool {
+  #rid = 0;
+  #size = 0;
+  #terminated = false;
+
+  constructor(specifier, options = { __proto__: null }) {
+    const prefix = "Failed to construct 'WorkerPool'";
+    webidl.requiredArguments(arguments.length, 1, prefix);
+    specifier = String(specifier);
+    const { deno, name = "" } = options;
+    let size = op_bootstrap_numcpus();
+    if (options.size !== undefined) {
+      size = webidl.converters["unsigned short"](
+        options.size,
+        prefix,
+        "'size'",
+        { enforceRange: true },
+      );
+      if (size === 0) {
+        throw new RangeError(`${prefix}: 'size' must be greater than 0`);
+      }
+    }
+    let limits = null;
+    if (deno?.limits !== undefined) {
+      limits = webidl.converters["WorkerLimits"](
+        deno.limits,
+        prefix,
+        "'deno.limits'",
+      );
+    }
+
+    if (
+      StringPrototypeStartsWith(specifier, "./") ||
+      StringPrototypeStartsWith(specifier, "../") ||
+      StringPrototypeStartsWith(specifier, "/")
+    ) {
+      const baseUrl = getLocationHref();
+      if (baseUrl != null) {
+        specifier = new URL(specifier, baseUrl).href;
+      }
+    }
+
+    this.#rid = op_worker_pool_create({
+      hasSourceCode: false,
+      name: String(name),
+      permissions: serializePermissions(deno?.permissions),
+      permissionsSnapshot: deno?.permissionsSnapshot,
+      sourceCode: "",
+      specifier,
+      workerType: "module",
+      closeOnIdle: false,
+      limits,
+    }, size);
+    this.#size = size;
+  }
+
+  get size() {
+    return this.#size;
+  }
+
+  async run(message, transfer = []) {
+    const prefix = "Failed to execute 'run' on 'WorkerPool'";
+    webidl.requiredArguments(arguments.length, 1, prefix);
+    transfer = webidl.converters["sequence<object>"](
+      transfer,
+      prefix,
+      "Argument 2",
+    );
+    if (this.#terminated) {
+      throw new Error("Worker pool was terminated");
+    }
+    const data = serializeJsMessageData(message, transfer);
+    const result = await op_worker_pool_run(this.#rid, data);
+    return deserializeJsMessageData(result)[0];
+  }
+
+  terminate() {
+    if (!this.#terminated) {
+      this.#terminated = true;
+      core.close(this.#rid);
+    }
+  }
+
+  [SymbolDispose]() {
+    this.terminate();
+  }
+
+  [SymbolFor("Deno.privateCustomInspect")](inspect, inspectOptions) {
+    return inspect(
+      createFilteredInspectProxy({
+        object: this,
+        evaluate: ObjectPrototypeIsPrototypeOf(WorkerPoolPrototype, this),
+        keys: ["size"],
+      }),
+      inspectOptions,
+    );
+  }
+
+  [SymbolToStringTag] = "WorkerPool";
+}
+
+const WorkerPoolPrototype = WorkerPool.prototype;
+
+export { Worker, WorkerPool };
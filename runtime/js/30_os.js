@@ -3,7 +3,10 @@
 import { primordials } from "ext:core/mod.js";
 import {
   op_delete_env,
+  op_disk_usage,
   op_env,
+  op_env_diff,
+  op_env_listen,
   op_exec_path,
   op_exit,
   op_get_env,
@@ -16,6 +19,7 @@ import {
   op_os_uptime,
   op_set_env,
   op_set_exit_code,
+  op_system_cpu_usage,
   op_system_memory_info,
   op_uid,
 } from "ext:core/ops";
@@ -29,6 +33,7 @@ const {
 } = primordials;
 
 import { Event, EventTarget } from "ext:deno_web/02_event.js";
+import { pathFromURL } from "ext:deno_web/00_infra.js";
 
 const windowDispatchEvent = FunctionPrototypeBind(
   EventTarget.prototype.dispatchEvent,
@@ -55,6 +60,14 @@ function systemMemoryInfo() {
   return op_system_memory_info();
 }
 
+function systemCpuUsage() {
+  return op_system_cpu_usage();
+}
+
+function diskUsage(path) {
+  return op_disk_usage(pathFromURL(path));
+}
+
 function networkInterfaces() {
   return op_network_interfaces();
 }
@@ -116,8 +129,46 @@ function setExitCode(value) {
   op_set_exit_code(value);
 }
 
+class EnvChangeEvent extends Event {
+  #key;
+  #oldValue;
+  #newValue;
+
+  constructor(key, oldValue, newValue) {
+    super("change");
+    this.#key = key;
+    this.#oldValue = oldValue;
+    this.#newValue = newValue;
+  }
+
+  get key() {
+    return this.#key;
+  }
+
+  get oldValue() {
+    return this.#oldValue;
+  }
+
+  get newValue() {
+    return this.#newValue;
+  }
+}
+
+const envEventTarget = new EventTarget();
+const addEnvListener = FunctionPrototypeBind(
+  EventTarget.prototype.addEventListener,
+  envEventTarget,
+);
+
+function dispatchEnvChange(key, oldValue, newValue) {
+  if (oldValue !== newValue) {
+    envEventTarget.dispatchEvent(new EnvChangeEvent(key, oldValue, newValue));
+  }
+}
+
 function setEnv(key, value) {
-  op_set_env(key, value);
+  const oldValue = op_set_env(key, value) ?? undefined;
+  dispatchEnvChange(key, oldValue, value);
 }
 
 function getEnv(key) {
@@ -125,7 +176,8 @@ function getEnv(key) {
 }
 
 function deleteEnv(key) {
-  op_delete_env(key);
+  const oldValue = op_delete_env(key) ?? undefined;
+  dispatchEnvChange(key, oldValue, undefined);
 }
 
 const env = {
@@ -138,6 +190,17 @@ const env = {
     return getEnv(key) !== undefined;
   },
   delete: deleteEnv,
+  diff(snapshot) {
+    return op_env_diff(snapshot);
+  },
+  addEventListener(type, listener, options) {
+    op_env_listen();
+    addEnvListener(type, listener, options);
+  },
+  removeEventListener: FunctionPrototypeBind(
+    EventTarget.prototype.removeEventListener,
+    envEventTarget,
+  ),
 };
 
 function execPath() {
@@ -145,6 +208,7 @@ function execPath() {
 }
 
 export {
+  diskUsage,
   env,
   execPath,
   exit,
@@ -157,6 +221,7 @@ export {
   osUptime,
   setExitCode,
   setExitHandler,
+  systemCpuUsage,
   systemMemoryInfo,
   uid,
 };
@@ -8,6 +8,8 @@ const {
 } = core;
 const {
   ArrayIsArray,
+  MathFloor,
+  NumberIsFinite,
   ObjectPrototypeIsPrototypeOf,
   PromiseResolve,
   SymbolAsyncIterator,
@@ -27,8 +29,20 @@ class FsWatcher {
         value: undefined,
       });
     }
-    const { recursive } = options;
-    this.#rid = op_fs_events_open({ recursive, paths });
+    const { recursive = true, correlateRenames = false, debounce = 0 } =
+      options;
+    if (typeof debounce !== "number" || !NumberIsFinite(debounce)) {
+      throw new TypeError("The debounce window must be a finite number");
+    }
+    if (debounce < 0) {
+      throw new RangeError("The debounce window must not be negative");
+    }
+    this.#rid = op_fs_events_open({
+      recursive,
+      paths,
+      correlateRenames,
+      debounce: MathFloor(debounce),
+    });
   }
 
   get rid() {
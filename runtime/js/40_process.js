@@ -2,26 +2,53 @@
 
 import { core, internals, primordials } from "ext:core/mod.js";
 import {
+  op_child_stdio_lines,
+  op_ipc_channel_parent,
+  op_ipc_channel_recv,
+  op_ipc_channel_send,
   op_kill,
+  op_pty_resize,
+  op_read_line,
   op_run,
   op_run_status,
+  op_shared_memory_buffer,
+  op_shared_memory_create,
+  op_shared_memory_open,
+  op_spawn_abort,
   op_spawn_child,
+  op_spawn_children_list,
+  op_spawn_forward_signals,
   op_spawn_kill,
   op_spawn_sync,
+  op_spawn_try_wait,
   op_spawn_wait,
+  op_splice,
+  op_tee_child_stdout,
 } from "ext:core/ops";
+const {
+  BadResourcePrototype,
+  InterruptedPrototype,
+  internalRidSymbol,
+} = core;
 const {
   ArrayPrototypeMap,
   ArrayPrototypeSlice,
+  JSONParse,
+  JSONStringify,
+  NumberIsSafeInteger,
+  RegExpPrototypeGetIgnoreCase,
+  RegExpPrototypeGetSource,
   TypeError,
   ObjectEntries,
   SafeArrayIterator,
   String,
   ObjectPrototypeIsPrototypeOf,
   PromisePrototypeThen,
+  RangeError,
   SafePromiseAll,
   Symbol,
   SymbolFor,
+  TypedArrayPrototypeGetSymbolToStringTag,
 } = primordials;
 
 import { FsFile } from "ext:deno_fs/30_fs.js";
@@ -30,10 +57,13 @@ import {
   assert,
   pathFromURL,
   SymbolAsyncDispose,
+  SymbolDispose,
 } from "ext:deno_web/00_infra.js";
 import * as abortSignal from "ext:deno_web/03_abort_signal.js";
+import { MessageEvent } from "ext:deno_web/02_event.js";
 import {
   readableStreamCollectIntoUint8Array,
+  readableStreamForRid,
   readableStreamForRidUnrefable,
   readableStreamForRidUnrefableRef,
   readableStreamForRidUnrefableUnref,
@@ -162,39 +192,171 @@ function run({
 
 const illegalConstructorKey = Symbol("illegalConstructorKey");
 
+function serializeEnvInherit(envInherit) {
+  if (envInherit === undefined) {
+    return undefined;
+  }
+  return ArrayPrototypeMap(envInherit, (pattern) => {
+    if (typeof pattern === "string") {
+      return pattern;
+    }
+    return {
+      source: RegExpPrototypeGetSource(pattern),
+      ignoreCase: RegExpPrototypeGetIgnoreCase(pattern),
+    };
+  });
+}
+
+/**
+ * Splits the `stdin` option into the stdio passed to the op, and the bytes to
+ * write to the child if it is given as `{ bytes }`.
+ */
+function serializeStdin(stdin) {
+  if (typeof stdin !== "object" || stdin === null) {
+    return [stdin, null];
+  }
+  const { bytes } = stdin;
+  if (TypedArrayPrototypeGetSymbolToStringTag(bytes) !== "Uint8Array") {
+    throw new TypeError("stdin.bytes must be a Uint8Array");
+  }
+  return ["piped", bytes];
+}
+
+class SharedMemory {
+  #rid;
+  #name;
+  #buffer;
+
+  constructor(key = null, rid, name) {
+    if (key !== illegalConstructorKey) {
+      throw new TypeError("Illegal constructor.");
+    }
+    this.#rid = rid;
+    this.#name = name;
+    this.#buffer = op_shared_memory_buffer(rid);
+  }
+
+  get [internalRidSymbol]() {
+    return this.#rid;
+  }
+
+  get name() {
+    return this.#name;
+  }
+
+  get buffer() {
+    return this.#buffer;
+  }
+
+  close() {
+    core.close(this.#rid);
+  }
+
+  [SymbolDispose]() {
+    core.tryClose(this.#rid);
+  }
+}
+const SharedMemoryPrototype = SharedMemory.prototype;
+
+function createSharedMemory(size) {
+  if (!NumberIsSafeInteger(size) || size <= 0) {
+    throw new RangeError(`Invalid shared memory size: ${size}`);
+  }
+  const { 0: rid, 1: name } = op_shared_memory_create(size);
+  return new SharedMemory(illegalConstructorKey, rid, name);
+}
+
+function openSharedMemory(name) {
+  name = String(name);
+  const rid = op_shared_memory_open(name);
+  return new SharedMemory(illegalConstructorKey, rid, name);
+}
+
+function serializeSharedMemory(sharedMemory) {
+  return ArrayPrototypeMap(sharedMemory, (segment) => {
+    if (!ObjectPrototypeIsPrototypeOf(SharedMemoryPrototype, segment)) {
+      throw new TypeError("sharedMemory must only contain Deno.SharedMemory");
+    }
+    return segment[internalRidSymbol];
+  });
+}
+
 function spawnChildInner(command, apiName, {
   args = [],
+  argv0 = undefined,
   cwd = undefined,
   clearEnv = false,
   env = { __proto__: null },
+  envInherit = undefined,
   uid = undefined,
   gid = undefined,
+  umask = undefined,
   stdin = "null",
   stdout = "piped",
   stderr = "piped",
   signal = undefined,
+  abortGracePeriod = undefined,
   windowsRawArguments = false,
+  creationFlags = undefined,
+  processGroup = false,
+  priority = undefined,
+  cpuAffinity = undefined,
+  chroot = undefined,
+  unshare = undefined,
+  shell = undefined,
+  permissionToken = undefined,
+  forwardSignals = false,
+  pty = undefined,
+  timeout = undefined,
   ipc = -1,
+  serialization = "json",
   extraStdio = [],
+  sharedMemory = [],
 } = { __proto__: null }) {
+  if (serialization !== "json" && serialization !== "advanced") {
+    throw new TypeError(`Invalid serialization: ${serialization}`);
+  }
+  const { 0: stdinStdio, 1: stdinBytes } = serializeStdin(stdin);
   const child = op_spawn_child({
     cmd: pathFromURL(command),
     args: ArrayPrototypeMap(args, String),
+    argv0,
     cwd: pathFromURL(cwd),
     clearEnv,
     env: ObjectEntries(env),
+    envInherit: serializeEnvInherit(envInherit),
     uid,
     gid,
-    stdin,
+    umask,
+    stdin: stdinStdio,
     stdout,
     stderr,
     windowsRawArguments,
-    ipc,
+    creationFlags,
+    processGroup,
+    priority,
+    cpuAffinity,
+    chroot: pathFromURL(chroot),
+    unshare,
+    shell,
+    permissionToken,
+    pty,
+    timeoutMs: timeout,
+    // a number is the fd used by Node.js' `child_process`
+    ipc: typeof ipc === "number" ? ipc : -1,
+    ipcChannel: ipc === true ? serialization : null,
     extraStdio,
-  }, apiName);
+    sharedMemory: serializeSharedMemory(sharedMemory),
+  }, apiName, stdinBytes);
+  if (forwardSignals) {
+    const promise = op_spawn_forward_signals(child.rid);
+    core.unrefOpPromise(promise);
+  }
   return new ChildProcess(illegalConstructorKey, {
     ...child,
     signal,
+    abortGracePeriod,
+    serialization,
   });
 }
 
@@ -216,25 +378,183 @@ function collectOutput(readableStream) {
   return readableStreamCollectIntoUint8Array(readableStream);
 }
 
+class ChildPty {
+  #rid;
+  #readable;
+  #writable;
+
+  constructor(key = null, rid) {
+    if (key !== illegalConstructorKey) {
+      throw new TypeError("Illegal constructor.");
+    }
+    this.#rid = rid;
+    this.#readable = readableStreamForRid(rid);
+    // the resource is closed by the readable side once the child has exited
+    this.#writable = writableStreamForRid(rid, false);
+  }
+
+  get readable() {
+    return this.#readable;
+  }
+
+  get writable() {
+    return this.#writable;
+  }
+
+  resize({ rows, cols }) {
+    op_pty_resize(this.#rid, rows, cols);
+  }
+}
+
+const _childExited = Symbol("[[childExited]]");
+
+class IpcChannel {
+  #rid;
+  #serialization;
+  #onmessage = null;
+  #recvPromise = null;
+  #readLoop = null;
+  #closed = false;
+
+  constructor(key = null, rid, serialization) {
+    if (key !== illegalConstructorKey) {
+      throw new TypeError("Illegal constructor.");
+    }
+    this.#rid = rid;
+    this.#serialization = serialization;
+  }
+
+  get serialization() {
+    return this.#serialization;
+  }
+
+  get onmessage() {
+    return this.#onmessage;
+  }
+
+  set onmessage(handler) {
+    this.#onmessage = handler;
+    if (this.#closed) {
+      return;
+    }
+    if (this.#readLoop === null) {
+      if (handler !== null) {
+        this.#readLoop = this.#read();
+      }
+    } else if (handler === null) {
+      // messages are still read, but don't keep the event loop alive
+      core.unrefOpPromise(this.#recvPromise);
+    } else {
+      core.refOpPromise(this.#recvPromise);
+    }
+  }
+
+  async send(message) {
+    if (this.#closed) {
+      throw new TypeError("IPC channel is closed");
+    }
+    const buf = this.#serialization === "advanced"
+      ? core.serialize(message, { forStorage: true })
+      : core.encode(JSONStringify(message));
+    await op_ipc_channel_send(this.#rid, buf);
+  }
+
+  async #read() {
+    try {
+      while (true) {
+        this.#recvPromise = op_ipc_channel_recv(this.#rid);
+        if (this.#onmessage === null) {
+          core.unrefOpPromise(this.#recvPromise);
+        }
+        const buf = await this.#recvPromise;
+        if (buf === null) {
+          break;
+        }
+        const data = this.#serialization === "advanced"
+          ? core.deserialize(buf, { forStorage: true })
+          : JSONParse(core.decode(buf));
+        this.#onmessage?.(new MessageEvent("message", { data }));
+      }
+    } catch (error) {
+      if (
+        !ObjectPrototypeIsPrototypeOf(BadResourcePrototype, error) &&
+        !ObjectPrototypeIsPrototypeOf(InterruptedPrototype, error)
+      ) {
+        throw error;
+      }
+    } finally {
+      this.close();
+    }
+  }
+
+  close() {
+    if (this.#closed) {
+      return;
+    }
+    this.#closed = true;
+    core.tryClose(this.#rid);
+  }
+
+  // Called once the child exited. Messages it sent before exiting are still
+  // delivered, as the pipe is only done once its end was closed.
+  [_childExited]() {
+    if (this.#readLoop === null) {
+      this.close();
+      return;
+    }
+    return this.#readLoop;
+  }
+}
+
+let parentIpc;
+function getParentIpc() {
+  if (parentIpc === undefined) {
+    const channel = op_ipc_channel_parent();
+    parentIpc = channel === null
+      ? null
+      : new IpcChannel(illegalConstructorKey, channel[0], channel[1]);
+  }
+  return parentIpc;
+}
+
 const _ipcPipeRid = Symbol("[[ipcPipeRid]]");
 const _extraPipeRids = Symbol("[[_extraPipeRids]]");
+const _pid = Symbol("[[pid]]");
 
 internals.getIpcPipeRid = (process) => process[_ipcPipeRid];
 internals.getExtraPipeRids = (process) => process[_extraPipeRids];
+internals.listChildren = () => op_spawn_children_list();
+internals.getOutputPid = (output) => output[_pid];
 
 class ChildProcess {
   #rid;
   #waitPromise;
   #waitComplete = false;
+  #exitStatus = null;
 
   [_ipcPipeRid];
   [_extraPipeRids];
 
   #pid;
   get pid() {
+    if (this.#pid === null) {
+      throw new TypeError(
+        "pid is not available for commands run by the built-in shell",
+      );
+    }
     return this.#pid;
   }
 
+  #startTime;
+  get startTime() {
+    return this.#startTime;
+  }
+
+  #pidfd;
+  get pidfd() {
+    return this.#pidfd;
+  }
+
   #stdin = null;
   get stdin() {
     if (this.#stdin == null) {
@@ -244,30 +564,119 @@ class ChildProcess {
   }
 
   #stdout = null;
+  #stdoutRid = null;
+  #stdoutTee = null;
+  // the method stdout was handed over to, if any
+  #stdoutTakenBy = null;
   get stdout() {
+    if (this.#stdoutTakenBy != null) {
+      throw new TypeError(`stdout is read by ${this.#stdoutTakenBy}`);
+    }
     if (this.#stdout == null) {
       throw new TypeError("stdout is not piped");
     }
     return this.#stdout;
   }
 
+  #takeStdout(method) {
+    if (this.stdout.locked) {
+      throw new TypeError(`Can't use ${method} because stdout is locked`);
+    }
+    this.#stdout = null;
+    this.#stdoutTakenBy = method;
+    return this.#stdoutRid;
+  }
+
+  teeStdout(count = 2) {
+    const rids = op_tee_child_stdout(this.#takeStdout("teeStdout()"), count);
+    this.#stdoutTee = ArrayPrototypeMap(
+      rids,
+      (rid) => readableStreamForRidUnrefable(rid),
+    );
+    return ArrayPrototypeSlice(this.#stdoutTee);
+  }
+
+  spliceStdout(dst) {
+    const dstRid = dst?.[internalRidSymbol];
+    if (typeof dstRid !== "number") {
+      throw new TypeError("Destination must be a Deno.Conn or Deno.FsFile");
+    }
+    return op_splice(this.#takeStdout("spliceStdout()"), dstRid);
+  }
+
   #stderr = null;
+  #stderrRid = null;
+  #stderrTakenBy = null;
   get stderr() {
+    if (this.#stderrTakenBy != null) {
+      throw new TypeError(`stderr is read by ${this.#stderrTakenBy}`);
+    }
     if (this.#stderr == null) {
       throw new TypeError("stderr is not piped");
     }
     return this.#stderr;
   }
 
+  lines(stream = "stdout") {
+    let rid;
+    if (stream === "stdout") {
+      rid = op_child_stdio_lines(this.#takeStdout("lines()"));
+    } else if (stream === "stderr") {
+      if (this.stderr.locked) {
+        throw new TypeError("Can't use lines() because stderr is locked");
+      }
+      rid = op_child_stdio_lines(this.#stderrRid);
+      this.#stderr = null;
+      this.#stderrTakenBy = "lines()";
+    } else {
+      throw new TypeError(`Invalid stream: ${stream}`);
+    }
+    return readLines(rid);
+  }
+
+  #pty = null;
+  get pty() {
+    if (this.#pty == null) {
+      throw new TypeError("pty is not enabled");
+    }
+    return this.#pty;
+  }
+
+  #ipc = null;
+  #getIpc() {
+    if (this.#ipc == null) {
+      throw new TypeError("ipc is not enabled");
+    }
+    return this.#ipc;
+  }
+
+  get onmessage() {
+    return this.#getIpc().onmessage;
+  }
+
+  set onmessage(handler) {
+    this.#getIpc().onmessage = handler;
+  }
+
+  send(message) {
+    return this.#getIpc().send(message);
+  }
+
   constructor(key = null, {
     signal,
+    abortGracePeriod,
     rid,
     pid,
     stdinRid,
     stdoutRid,
     stderrRid,
+    startTime,
+    pidfdRid,
     ipcPipeRid, // internal
     extraPipeRids,
+    ptyRid,
+    ipcChannelRid,
+    serialization,
   } = null) {
     if (key !== illegalConstructorKey) {
       throw new TypeError("Illegal constructor.");
@@ -275,6 +684,8 @@ class ChildProcess {
 
     this.#rid = rid;
     this.#pid = pid;
+    this.#startTime = startTime;
+    this.#pidfd = pidfdRid;
     this[_ipcPipeRid] = ipcPipeRid;
     this[_extraPipeRids] = extraPipeRids;
 
@@ -283,21 +694,48 @@ class ChildProcess {
     }
 
     if (stdoutRid !== null) {
+      this.#stdoutRid = stdoutRid;
       this.#stdout = readableStreamForRidUnrefable(stdoutRid);
     }
 
     if (stderrRid !== null) {
+      this.#stderrRid = stderrRid;
       this.#stderr = readableStreamForRidUnrefable(stderrRid);
     }
 
-    const onAbort = () => this.kill("SIGTERM");
+    if (ptyRid !== null) {
+      this.#pty = new ChildPty(illegalConstructorKey, ptyRid);
+    }
+
+    if (ipcChannelRid !== null) {
+      this.#ipc = new IpcChannel(
+        illegalConstructorKey,
+        ipcChannelRid,
+        serialization,
+      );
+    }
+
+    const onAbort = () => {
+      if (abortGracePeriod === undefined) {
+        this.kill("SIGTERM");
+      } else if (!this.#waitComplete) {
+        op_spawn_abort(this.#rid, abortGracePeriod);
+      }
+    };
     signal?.[abortSignal.add](onAbort);
 
     const waitPromise = op_spawn_wait(this.#rid);
     this.#waitPromise = waitPromise;
-    this.#status = PromisePrototypeThen(waitPromise, (res) => {
+    this.#status = PromisePrototypeThen(waitPromise, async (res) => {
       signal?.[abortSignal.remove](onAbort);
       this.#waitComplete = true;
+      this.#exitStatus = res;
+      // kept open by `op_spawn_wait` for `tryStatus()` until now
+      core.tryClose(this.#rid);
+      if (this.#pidfd !== null) {
+        core.tryClose(this.#pidfd);
+      }
+      await this.#ipc?.[_childExited]();
       return res;
     });
   }
@@ -307,6 +745,13 @@ class ChildProcess {
     return this.#status;
   }
 
+  tryStatus() {
+    if (this.#waitComplete) {
+      return this.#exitStatus;
+    }
+    return op_spawn_try_wait(this.#rid);
+  }
+
   async output() {
     if (this.#stdout?.locked) {
       throw new TypeError(
@@ -329,6 +774,8 @@ class ChildProcess {
       success: status.success,
       code: status.code,
       signal: status.signal,
+      timedOut: status.timedOut,
+      usage: status.usage,
       get stdout() {
         if (stdout == null) {
           throw new TypeError("stdout is not piped");
@@ -344,16 +791,16 @@ class ChildProcess {
     };
   }
 
-  kill(signo = "SIGTERM") {
+  kill(signo = "SIGTERM", { tree = false } = { __proto__: null }) {
     if (this.#waitComplete) {
       throw new TypeError("Child process has already terminated.");
     }
-    op_spawn_kill(this.#rid, signo);
+    op_spawn_kill(this.#rid, signo, tree);
   }
 
   async [SymbolAsyncDispose]() {
     try {
-      op_spawn_kill(this.#rid, "SIGTERM");
+      op_spawn_kill(this.#rid, "SIGTERM", false);
     } catch {
       // ignore errors from killing the process (such as ESRCH or BadResource)
     }
@@ -364,12 +811,32 @@ class ChildProcess {
     core.refOpPromise(this.#waitPromise);
     if (this.#stdout) readableStreamForRidUnrefableRef(this.#stdout);
     if (this.#stderr) readableStreamForRidUnrefableRef(this.#stderr);
+    for (const stream of new SafeArrayIterator(this.#stdoutTee ?? [])) {
+      readableStreamForRidUnrefableRef(stream);
+    }
   }
 
   unref() {
     core.unrefOpPromise(this.#waitPromise);
     if (this.#stdout) readableStreamForRidUnrefableUnref(this.#stdout);
     if (this.#stderr) readableStreamForRidUnrefableUnref(this.#stderr);
+    for (const stream of new SafeArrayIterator(this.#stdoutTee ?? [])) {
+      readableStreamForRidUnrefableUnref(stream);
+    }
+  }
+}
+
+async function* readLines(rid) {
+  try {
+    while (true) {
+      const line = await op_read_line(rid);
+      if (line === null) {
+        return;
+      }
+      yield line;
+    }
+  } finally {
+    core.tryClose(rid);
   }
 }
 
@@ -389,39 +856,69 @@ function spawn(command, options) {
 
 function spawnSync(command, {
   args = [],
+  argv0 = undefined,
   cwd = undefined,
   clearEnv = false,
   env = { __proto__: null },
+  envInherit = undefined,
   uid = undefined,
   gid = undefined,
+  umask = undefined,
   stdin = "null",
   stdout = "piped",
   stderr = "piped",
   windowsRawArguments = false,
+  creationFlags = undefined,
+  processGroup = false,
+  priority = undefined,
+  cpuAffinity = undefined,
+  chroot = undefined,
+  unshare = undefined,
+  shell = undefined,
+  permissionToken = undefined,
+  maxBuffer = undefined,
+  sharedMemory = [],
 } = { __proto__: null }) {
   if (stdin === "piped") {
     throw new TypeError(
       "Piped stdin is not supported for this function, use 'Deno.Command().spawn()' instead",
     );
   }
+  const { 0: stdinStdio, 1: stdinBytes } = serializeStdin(stdin);
   const result = op_spawn_sync({
     cmd: pathFromURL(command),
     args: ArrayPrototypeMap(args, String),
+    argv0,
     cwd: pathFromURL(cwd),
     clearEnv,
     env: ObjectEntries(env),
+    envInherit: serializeEnvInherit(envInherit),
     uid,
     gid,
-    stdin,
+    umask,
+    stdin: stdinStdio,
     stdout,
     stderr,
     windowsRawArguments,
+    creationFlags,
+    processGroup,
+    priority,
+    cpuAffinity,
+    chroot: pathFromURL(chroot),
+    unshare,
+    shell,
+    permissionToken,
+    maxBuffer,
     extraStdio: [],
-  });
+    sharedMemory: serializeSharedMemory(sharedMemory),
+  }, stdinBytes);
   return {
     success: result.status.success,
     code: result.status.code,
     signal: result.status.signal,
+    timedOut: result.status.timedOut,
+    usage: result.status.usage,
+    [_pid]: result.pid,
     get stdout() {
       if (result.stdout == null) {
         throw new TypeError("stdout is not piped");
@@ -475,4 +972,15 @@ class Command {
   }
 }
 
-export { ChildProcess, Command, kill, Process, run };
+export {
+  ChildProcess,
+  Command,
+  createSharedMemory,
+  getParentIpc,
+  IpcChannel,
+  kill,
+  openSharedMemory,
+  Process,
+  run,
+  SharedMemory,
+};
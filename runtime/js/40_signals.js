@@ -1,7 +1,15 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
 import { core, primordials } from "ext:core/mod.js";
-import { op_signal_bind, op_signal_poll, op_signal_unbind } from "ext:core/ops";
+import {
+  op_signal_bind,
+  op_signal_block,
+  op_signal_info,
+  op_signal_poll,
+  op_signal_queue,
+  op_signal_unbind,
+  op_signal_unblock,
+} from "ext:core/ops";
 const {
   SafeSet,
   SafeSetIterator,
@@ -25,13 +33,13 @@ function unbindSignal(rid) {
 }
 
 // Stores signal listeners and resource data. This has type of
-// `Record<string, { rid: number | undefined, listeners: Set<() => void> }`
+// `Record<string, { signo: string, rid: number | undefined, listeners: Set<(info?: object) => void> }`
 const signalData = { __proto__: null };
 
 /** Gets the signal handlers and resource data of the given signal */
 function getSignalData(signo) {
   return signalData[signo] ??
-    (signalData[signo] = { rid: undefined, listeners: new SafeSet() });
+    (signalData[signo] = { signo, rid: undefined, listeners: new SafeSet() });
 }
 
 function checkSignalListenerType(listener) {
@@ -73,10 +81,28 @@ async function loop(sigData) {
     if (await pollSignal(sigData.rid)) {
       return;
     }
+    // only these signals come with details for the listeners
+    const info = sigData.signo === "SIGCHLD" || sigData.signo === "SIGWINCH"
+      ? op_signal_info(sigData.signo)
+      : undefined;
     for (const listener of new SafeSetIterator(sigData.listeners)) {
-      listener();
+      listener(info);
     }
   }
 }
 
-export { addSignalListener, removeSignalListener };
+function queue(pid, signo, value) {
+  op_signal_queue(pid, signo, value);
+}
+
+function block(signos) {
+  op_signal_block(signos);
+}
+
+function unblock(signos) {
+  op_signal_unblock(signos);
+}
+
+const signal = { block, queue, unblock };
+
+export { addSignalListener, removeSignalListener, signal };
@@ -4,6 +4,7 @@ import { core, internals } from "ext:core/mod.js";
 import {
   op_net_listen_udp,
   op_net_listen_unixpacket,
+  op_process_metrics,
   op_runtime_memory_usage,
 } from "ext:core/ops";
 
@@ -28,7 +29,10 @@ import * as process from "ext:runtime/40_process.js";
 import * as signals from "ext:runtime/40_signals.js";
 import * as tty from "ext:runtime/40_tty.js";
 import * as kv from "ext:deno_kv/01_db.ts";
+import * as archive from "ext:deno_archive/01_archive.js";
+import * as quic from "ext:deno_quic/01_quic.js";
 import * as cron from "ext:deno_cron/01_cron.ts";
+import * as workers from "ext:runtime/11_workers.js";
 import * as webgpuSurface from "ext:deno_webgpu/02_surface.js";
 
 const denoNs = {
@@ -232,10 +236,20 @@ const unstableIds = {
   unsafeProto: 10,
   webgpu: 11,
   workerOptions: 12,
+  archive: 13,
+  quic: 14,
+  tty: 15,
+  os: 16,
+  permissions: 17,
 };
 
 const denoNsUnstableById = { __proto__: null };
 
+denoNsUnstableById[unstableIds.archive] = {
+  createArchive: archive.createArchive,
+  extractArchive: archive.extractArchive,
+};
+
 // denoNsUnstableById[unstableIds.broadcastChannel] = { __proto__: null }
 
 denoNsUnstableById[unstableIds.cron] = {
@@ -253,7 +267,17 @@ denoNsUnstableById[unstableIds.ffi] = {
 denoNsUnstableById[unstableIds.fs] = {
   funlock: fs.funlock,
   funlockSync: fs.funlockSync,
+  getXattr: fs.getXattr,
+  getXattrSync: fs.getXattrSync,
+  setXattr: fs.setXattr,
+  setXattrSync: fs.setXattrSync,
+  listXattrs: fs.listXattrs,
+  listXattrsSync: fs.listXattrsSync,
+  glob: fs.glob,
+  globSync: fs.globSync,
   umask: fs.umask,
+  writeFileAtomic: fs.writeFileAtomic,
+  writeFileAtomicSync: fs.writeFileAtomicSync,
 };
 
 denoNsUnstableById[unstableIds.http] = {
@@ -270,26 +294,63 @@ denoNsUnstableById[unstableIds.kv] = {
 };
 
 denoNsUnstableById[unstableIds.net] = {
+  connectDatagram: net.connectDatagram,
   listenDatagram: net.createListenDatagram(
     op_net_listen_udp,
     op_net_listen_unixpacket,
   ),
+  createTlsSessionCache: tls.createTlsSessionCache,
+  TlsSessionCache: tls.TlsSessionCache,
+  createDnsResolver: net.createDnsResolver,
+  DnsResolver: net.DnsResolver,
+};
+
+denoNsUnstableById[unstableIds.os] = {
+  processMetrics: () => op_process_metrics(),
+  systemCpuUsage: os.systemCpuUsage,
+  diskUsage: os.diskUsage,
 };
 
+// denoNsUnstableById[unstableIds.permissions] = { __proto__: null }
+
+denoNsUnstableById[unstableIds.process] = {
+  createSharedMemory: process.createSharedMemory,
+  openSharedMemory: process.openSharedMemory,
+  SharedMemory: process.SharedMemory,
+  signal: signals.signal,
+};
+
+denoNsUnstableById[unstableIds.quic] = {
+  connectQuic: quic.connectQuic,
+  listenQuic: quic.listenQuic,
+  QuicBidirectionalStream: quic.QuicBidirectionalStream,
+  QuicConn: quic.QuicConn,
+  QuicListener: quic.QuicListener,
+};
+
+// denoNsUnstableById[unstableIds.tty] = { __proto__: null }
+
 // denoNsUnstableById[unstableIds.unsafeProto] = { __proto__: null }
 
 denoNsUnstableById[unstableIds.webgpu] = {
   UnsafeWindowSurface: webgpuSurface.UnsafeWindowSurface,
 };
 
-// denoNsUnstableById[unstableIds.workerOptions] = { __proto__: null }
+denoNsUnstableById[unstableIds.workerOptions] = {
+  WorkerPool: workers.WorkerPool,
+};
 
 // when editing this list, also update unstableDenoProps in cli/tsc/99_main_compiler.js
 const denoNsUnstable = {
+  connectDatagram: net.connectDatagram,
   listenDatagram: net.createListenDatagram(
     op_net_listen_udp,
     op_net_listen_unixpacket,
   ),
+  createTlsSessionCache: tls.createTlsSessionCache,
+  TlsSessionCache: tls.TlsSessionCache,
+  createDnsResolver: net.createDnsResolver,
+  DnsResolver: net.DnsResolver,
   umask: fs.umask,
   HttpClient: httpClient.HttpClient,
   createHttpClient: httpClient.createHttpClient,
@@ -301,12 +362,37 @@ const denoNsUnstable = {
   UnsafeWindowSurface: webgpuSurface.UnsafeWindowSurface,
   funlock: fs.funlock,
   funlockSync: fs.funlockSync,
+  getXattr: fs.getXattr,
+  getXattrSync: fs.getXattrSync,
+  setXattr: fs.setXattr,
+  setXattrSync: fs.setXattrSync,
+  listXattrs: fs.listXattrs,
+  listXattrsSync: fs.listXattrsSync,
+  glob: fs.glob,
+  globSync: fs.globSync,
+  writeFileAtomic: fs.writeFileAtomic,
+  writeFileAtomicSync: fs.writeFileAtomicSync,
   openKv: kv.openKv,
   AtomicOperation: kv.AtomicOperation,
   Kv: kv.Kv,
   KvU64: kv.KvU64,
   KvListIterator: kv.KvListIterator,
   cron: cron.cron,
+  createArchive: archive.createArchive,
+  extractArchive: archive.extractArchive,
+  connectQuic: quic.connectQuic,
+  listenQuic: quic.listenQuic,
+  QuicBidirectionalStream: quic.QuicBidirectionalStream,
+  QuicConn: quic.QuicConn,
+  QuicListener: quic.QuicListener,
+  WorkerPool: workers.WorkerPool,
+  createSharedMemory: process.createSharedMemory,
+  openSharedMemory: process.openSharedMemory,
+  SharedMemory: process.SharedMemory,
+  signal: signals.signal,
+  processMetrics: () => op_process_metrics(),
+  systemCpuUsage: os.systemCpuUsage,
+  diskUsage: os.diskUsage,
 };
 
 export { denoNs, denoNsUnstable, denoNsUnstableById, unstableIds };
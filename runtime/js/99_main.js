@@ -54,6 +54,7 @@ import * as event from "ext:deno_web/02_event.js";
 import * as location from "ext:deno_web/12_location.js";
 import * as version from "ext:runtime/01_version.ts";
 import * as os from "ext:runtime/30_os.js";
+import { getParentIpc } from "ext:runtime/40_process.js";
 import * as timers from "ext:deno_web/02_timers.js";
 import {
   getDefaultInspectOptions,
@@ -308,6 +309,9 @@ core.registerErrorClass("FilesystemLoop", errors.FilesystemLoop);
 core.registerErrorClass("IsADirectory", errors.IsADirectory);
 core.registerErrorClass("NetworkUnreachable", errors.NetworkUnreachable);
 core.registerErrorClass("NotADirectory", errors.NotADirectory);
+core.registerErrorClass("MaxBufferExceeded", errors.MaxBufferExceeded);
+core.registerErrorClass("CwdNotFound", errors.CwdNotFound);
+core.registerErrorClass("CwdNotDirectory", errors.CwdNotDirectory);
 core.registerErrorBuilder(
   "DOMExceptionOperationError",
   function DOMExceptionOperationError(msg) {
@@ -750,6 +754,18 @@ function bootstrapMainRuntime(runtimeOptions, warmup = false) {
       }
     }
 
+    // A getter, which `ObjectAssign` can't copy.
+    if (
+      unstableFlag ||
+      ArrayPrototypeIncludes(unstableFeatures, unstableIds.process)
+    ) {
+      ObjectDefineProperty(
+        finalDenoNs,
+        "ipc",
+        core.propGetterOnly(getParentIpc),
+      );
+    }
+
     if (!ArrayPrototypeIncludes(unstableFeatures, unstableIds.unsafeProto)) {
       // Removes the `__proto__` for security reasons.
       // https://tc39.es/ecma262/#sec-get-object.prototype.__proto__
@@ -918,6 +934,18 @@ function bootstrapWorkerRuntime(
       }
     }
 
+    // A getter, which `ObjectAssign` can't copy.
+    if (
+      unstableFlag ||
+      ArrayPrototypeIncludes(unstableFeatures, unstableIds.process)
+    ) {
+      ObjectDefineProperty(
+        finalDenoNs,
+        "ipc",
+        core.propGetterOnly(getParentIpc),
+      );
+    }
+
     // Not available in workers
     delete finalDenoNs.mainModule;
 
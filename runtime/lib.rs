@@ -1,5 +1,6 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+pub use deno_archive;
 pub use deno_broadcast_channel;
 pub use deno_cache;
 pub use deno_canvas;
@@ -17,6 +18,7 @@ pub use deno_napi;
 pub use deno_net;
 pub use deno_node;
 pub use deno_permissions;
+pub use deno_quic;
 pub use deno_terminal::colors;
 pub use deno_tls;
 pub use deno_url;
@@ -44,6 +46,7 @@ pub use worker_bootstrap::WorkerExecutionMode;
 pub use worker_bootstrap::WorkerLogLevel;
 
 mod shared;
+mod thread_cpu_clock;
 pub use shared::import_assertion_callback;
 pub use shared::runtime;
 
@@ -57,6 +60,12 @@ pub struct UnstableGranularFlag {
 
 // NOTE(bartlomieju): keep IDs in sync with `runtime/90_deno_ns.js` (search for `unstableFeatures`)
 pub static UNSTABLE_GRANULAR_FLAGS: &[UnstableGranularFlag] = &[
+  UnstableGranularFlag {
+    name: deno_archive::UNSTABLE_FEATURE_NAME,
+    help_text: "Enable unstable archive APIs",
+    show_in_help: false,
+    id: 13,
+  },
   UnstableGranularFlag {
     name: deno_broadcast_channel::UNSTABLE_FEATURE_NAME,
     help_text: "Enable unstable `BroadcastChannel` API",
@@ -99,6 +108,18 @@ pub static UNSTABLE_GRANULAR_FLAGS: &[UnstableGranularFlag] = &[
     show_in_help: true,
     id: 7,
   },
+  UnstableGranularFlag {
+    name: ops::os::UNSTABLE_FEATURE_NAME,
+    help_text: "Enable unstable OS APIs",
+    show_in_help: false,
+    id: 16,
+  },
+  UnstableGranularFlag {
+    name: ops::permissions::UNSTABLE_FEATURE_NAME,
+    help_text: "Enable unstable permissions APIs",
+    show_in_help: false,
+    id: 17,
+  },
   // TODO(bartlomieju): consider removing it
   UnstableGranularFlag {
     name: ops::process::UNSTABLE_FEATURE_NAME,
@@ -106,12 +127,24 @@ pub static UNSTABLE_GRANULAR_FLAGS: &[UnstableGranularFlag] = &[
     show_in_help: false,
     id: 8,
   },
+  UnstableGranularFlag {
+    name: deno_quic::UNSTABLE_FEATURE_NAME,
+    help_text: "Enable unstable QUIC APIs",
+    show_in_help: false,
+    id: 14,
+  },
   UnstableGranularFlag {
     name: "temporal",
     help_text: "Enable unstable Temporal API",
     show_in_help: true,
     id: 9,
   },
+  UnstableGranularFlag {
+    name: ops::tty::UNSTABLE_FEATURE_NAME,
+    help_text: "Enable unstable TTY APIs",
+    show_in_help: false,
+    id: 15,
+  },
   UnstableGranularFlag {
     name: "unsafe-proto",
     help_text: "Enable unsafe __proto__ support. This is a security risk.",
@@ -15,6 +15,7 @@ use deno_core::op2;
 use deno_permissions::PermissionsContainer;
 use notify::event::Event as NotifyEvent;
 use notify::event::ModifyKind;
+use notify::event::RenameMode;
 use notify::Error as NotifyError;
 use notify::EventKind;
 use notify::RecommendedWatcher;
@@ -24,20 +25,35 @@ use serde::Deserialize;
 use serde::Serialize;
 use std::borrow::Cow;
 use std::cell::RefCell;
+use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::convert::From;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::time::Instant;
 
 deno_core::extension!(
   deno_fs_events,
   ops = [op_fs_events_open, op_fs_events_poll],
 );
 
+/// The number of events which are buffered until they are polled. Once it is
+/// full, further events are dropped and an "overflow" event is reported.
+const FS_EVENTS_BUFFER_SIZE: usize = 1024;
+
+/// How long the "from" half of a rename waits for its "to" half.
+const RENAME_PAIR_TIMEOUT: Duration = Duration::from_millis(50);
+
 struct FsEventsResource {
   #[allow(unused)]
   watcher: RecommendedWatcher,
-  receiver: AsyncRefCell<mpsc::Receiver<Result<FsEvent, AnyError>>>,
+  stream: AsyncRefCell<FsEventStream>,
   cancel: CancelHandle,
 }
 
@@ -59,7 +75,7 @@ impl Resource for FsEventsResource {
 ///
 /// Feel free to expand this struct as long as you can add tests to demonstrate
 /// the complexity.
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash)]
 struct FsEvent {
   kind: &'static str,
   paths: Vec<PathBuf>,
@@ -93,10 +109,212 @@ impl From<NotifyEvent> for FsEvent {
   }
 }
 
+impl FsEvent {
+  fn overflow() -> Self {
+    FsEvent {
+      kind: "other",
+      paths: vec![],
+      flag: Some("overflow"),
+    }
+  }
+}
+
+fn rename_mode(event: &NotifyEvent) -> Option<RenameMode> {
+  match event.kind {
+    EventKind::Modify(ModifyKind::Name(mode)) => Some(mode),
+    _ => None,
+  }
+}
+
+/// The "from" half of a rename, waiting for its "to" half.
+struct PendingRename {
+  tracker: Option<usize>,
+  path: PathBuf,
+  deadline: Instant,
+}
+
+/// Turns the events of the watcher into the events reported to JS: pairs the
+/// halves of renames if `correlate_renames` is set, and coalesces identical
+/// events within `debounce` of the first one if it isn't zero.
+struct FsEventStream {
+  receiver: mpsc::Receiver<Result<NotifyEvent, AnyError>>,
+  /// Set by the watcher when an event didn't fit into the buffer.
+  overflowed: Arc<AtomicBool>,
+  correlate_renames: bool,
+  debounce: Duration,
+  ready: VecDeque<FsEvent>,
+  batch: Vec<FsEvent>,
+  batch_seen: HashSet<FsEvent>,
+  batch_deadline: Option<Instant>,
+  pending_rename: Option<PendingRename>,
+  /// inotify reports a rename a third time, with both paths, after the halves
+  /// which were already paired.
+  paired_tracker: Option<usize>,
+}
+
+impl FsEventStream {
+  fn new(
+    receiver: mpsc::Receiver<Result<NotifyEvent, AnyError>>,
+    overflowed: Arc<AtomicBool>,
+    correlate_renames: bool,
+    debounce: Duration,
+  ) -> Self {
+    Self {
+      receiver,
+      overflowed,
+      correlate_renames,
+      debounce,
+      ready: VecDeque::new(),
+      batch: vec![],
+      batch_seen: HashSet::new(),
+      batch_deadline: None,
+      pending_rename: None,
+      paired_tracker: None,
+    }
+  }
+
+  /// Returns the next event, or `None` once the watcher is gone.
+  async fn next(&mut self) -> Result<Option<FsEvent>, AnyError> {
+    loop {
+      if self.overflowed.swap(false, Ordering::Relaxed) {
+        // events that were already received happened before the lost ones
+        self.flush_rename();
+        self.flush_batch();
+        self.ready.push_back(FsEvent::overflow());
+      }
+      if let Some(event) = self.ready.pop_front() {
+        return Ok(Some(event));
+      }
+
+      let deadline = [
+        self.batch_deadline,
+        self.pending_rename.as_ref().map(|p| p.deadline),
+      ]
+      .into_iter()
+      .flatten()
+      .min();
+      let received = match deadline {
+        Some(deadline) => {
+          match tokio::time::timeout_at(deadline, self.receiver.recv()).await {
+            Ok(received) => received,
+            Err(_) => {
+              self.flush_expired();
+              continue;
+            }
+          }
+        }
+        None => self.receiver.recv().await,
+      };
+      match received {
+        Some(Ok(event)) => self.push(event),
+        Some(Err(err)) => return Err(err),
+        None => {
+          self.flush_rename();
+          self.flush_batch();
+          if self.ready.is_empty() {
+            return Ok(None);
+          }
+        }
+      }
+    }
+  }
+
+  fn push(&mut self, event: NotifyEvent) {
+    if !self.correlate_renames {
+      return self.add(FsEvent::from(event));
+    }
+    match rename_mode(&event) {
+      Some(RenameMode::From) if event.paths.len() == 1 => {
+        self.flush_rename();
+        self.pending_rename = Some(PendingRename {
+          tracker: event.tracker(),
+          path: event.paths.into_iter().next().unwrap(),
+          deadline: Instant::now() + RENAME_PAIR_TIMEOUT,
+        });
+      }
+      Some(RenameMode::To) => {
+        let tracker = event.tracker();
+        let from = self.pending_rename.take();
+        match from {
+          // without trackers, the halves are paired if they're adjacent
+          Some(from) if from.tracker == tracker => {
+            self.paired_tracker = tracker;
+            let mut paths = vec![from.path];
+            paths.extend(event.paths);
+            self.add(FsEvent {
+              kind: "rename",
+              paths,
+              flag: None,
+            });
+          }
+          from => {
+            self.pending_rename = from;
+            self.flush_rename();
+            self.add(FsEvent::from(event));
+          }
+        }
+      }
+      Some(RenameMode::Both)
+        if event.tracker().is_some()
+          && event.tracker() == self.paired_tracker => {}
+      _ => {
+        self.flush_rename();
+        self.add(FsEvent::from(event));
+      }
+    }
+  }
+
+  fn add(&mut self, event: FsEvent) {
+    if self.debounce.is_zero() {
+      return self.ready.push_back(event);
+    }
+    if self.batch_deadline.is_none() {
+      self.batch_deadline = Some(Instant::now() + self.debounce);
+    }
+    if self.batch_seen.insert(event.clone()) {
+      self.batch.push(event);
+    }
+  }
+
+  /// Reports the "from" half of a rename which has no "to" half, e.g.
+  /// because the file was moved out of the watched paths.
+  fn flush_rename(&mut self) {
+    if let Some(from) = self.pending_rename.take() {
+      self.add(FsEvent {
+        kind: "rename",
+        paths: vec![from.path],
+        flag: None,
+      });
+    }
+  }
+
+  fn flush_batch(&mut self) {
+    self.ready.extend(self.batch.drain(..));
+    self.batch_seen.clear();
+    self.batch_deadline = None;
+  }
+
+  fn flush_expired(&mut self) {
+    let now = Instant::now();
+    if matches!(&self.pending_rename, Some(p) if p.deadline <= now) {
+      self.flush_rename();
+    }
+    if matches!(self.batch_deadline, Some(deadline) if deadline <= now) {
+      self.flush_batch();
+    }
+  }
+}
+
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct OpenArgs {
   recursive: bool,
   paths: Vec<String>,
+  #[serde(default)]
+  correlate_renames: bool,
+  /// In milliseconds.
+  #[serde(default)]
+  debounce: u64,
 }
 
 #[op2]
@@ -105,15 +323,20 @@ fn op_fs_events_open(
   state: &mut OpState,
   #[serde] args: OpenArgs,
 ) -> Result<ResourceId, AnyError> {
-  let (sender, receiver) = mpsc::channel::<Result<FsEvent, AnyError>>(16);
+  let (sender, receiver) =
+    mpsc::channel::<Result<NotifyEvent, AnyError>>(FS_EVENTS_BUFFER_SIZE);
   let sender = Mutex::new(sender);
+  let overflowed = Arc::new(AtomicBool::new(false));
+  let watcher_overflowed = overflowed.clone();
   let mut watcher: RecommendedWatcher = Watcher::new(
     move |res: Result<NotifyEvent, NotifyError>| {
-      let res2 = res.map(FsEvent::from).map_err(AnyError::from);
+      let res2 = res.map_err(AnyError::from);
       let sender = sender.lock();
-      // Ignore result, if send failed it means that watcher was already closed,
-      // but not all messages have been flushed.
-      let _ = sender.try_send(res2);
+      // If the receiver is gone, the watcher was already closed, but not all
+      // messages have been flushed.
+      if let Err(TrySendError::Full(_)) = sender.try_send(res2) {
+        watcher_overflowed.store(true, Ordering::Relaxed);
+      }
     },
     Default::default(),
   )?;
@@ -131,7 +354,12 @@ fn op_fs_events_open(
   }
   let resource = FsEventsResource {
     watcher,
-    receiver: AsyncRefCell::new(receiver),
+    stream: AsyncRefCell::new(FsEventStream::new(
+      receiver,
+      overflowed,
+      args.correlate_renames,
+      Duration::from_millis(args.debounce),
+    )),
     cancel: Default::default(),
   };
   let rid = state.resource_table.add(resource);
@@ -145,12 +373,88 @@ async fn op_fs_events_poll(
   #[smi] rid: ResourceId,
 ) -> Result<Option<FsEvent>, AnyError> {
   let resource = state.borrow().resource_table.get::<FsEventsResource>(rid)?;
-  let mut receiver = RcRef::map(&resource, |r| &r.receiver).borrow_mut().await;
+  let mut stream = RcRef::map(&resource, |r| &r.stream).borrow_mut().await;
   let cancel = RcRef::map(resource, |r| &r.cancel);
-  let maybe_result = receiver.recv().or_cancel(cancel).await?;
-  match maybe_result {
-    Some(Ok(value)) => Ok(Some(value)),
-    Some(Err(err)) => Err(err),
-    None => Ok(None),
+  stream.next().or_cancel(cancel).await?
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use notify::event::CreateKind;
+
+  fn rename(mode: RenameMode, tracker: usize, path: &str) -> NotifyEvent {
+    NotifyEvent::new(EventKind::Modify(ModifyKind::Name(mode)))
+      .add_path(PathBuf::from(path))
+      .set_tracker(tracker)
+  }
+
+  fn stream(
+    events: Vec<NotifyEvent>,
+    correlate_renames: bool,
+    debounce: Duration,
+  ) -> FsEventStream {
+    let (sender, receiver) = mpsc::channel(16);
+    for event in events {
+      sender.try_send(Ok(event)).unwrap();
+    }
+    let overflowed = Arc::new(AtomicBool::new(false));
+    FsEventStream::new(receiver, overflowed, correlate_renames, debounce)
+  }
+
+  async fn collect(mut stream: FsEventStream) -> Vec<FsEvent> {
+    let mut events = vec![];
+    while let Some(event) = stream.next().await.unwrap() {
+      events.push(event);
+    }
+    events
+  }
+
+  #[tokio::test]
+  async fn correlate_renames() {
+    let both = rename(RenameMode::Both, 1, "/a").add_path(PathBuf::from("/b"));
+    let events = vec![
+      rename(RenameMode::From, 1, "/a"),
+      rename(RenameMode::To, 1, "/b"),
+      both,
+      rename(RenameMode::From, 2, "/c"),
+    ];
+    let events = collect(stream(events, true, Duration::ZERO)).await;
+    let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect();
+    assert_eq!(
+      events,
+      [
+        FsEvent {
+          kind: "rename",
+          paths: paths(&["/a", "/b"]),
+          flag: None,
+        },
+        FsEvent {
+          kind: "rename",
+          paths: paths(&["/c"]),
+          flag: None,
+        },
+      ]
+    );
+  }
+
+  #[tokio::test]
+  async fn debounce() {
+    let create = NotifyEvent::new(EventKind::Create(CreateKind::File))
+      .add_path(PathBuf::from("/a"));
+    let modify = NotifyEvent::new(EventKind::Modify(ModifyKind::Any))
+      .add_path(PathBuf::from("/a"));
+    let events = vec![create, modify.clone(), modify.clone(), modify];
+    let events =
+      collect(stream(events, false, Duration::from_millis(10))).await;
+    let kinds = events.iter().map(|e| e.kind).collect::<Vec<_>>();
+    assert_eq!(kinds, ["create", "modify"]);
+  }
+
+  #[tokio::test]
+  async fn overflow() {
+    let stream = stream(vec![], false, Duration::ZERO);
+    stream.overflowed.store(true, Ordering::Relaxed);
+    assert_eq!(collect(stream).await, [FsEvent::overflow()]);
   }
 }
@@ -12,6 +12,7 @@ pub mod tty;
 mod utils;
 pub mod web_worker;
 pub mod worker_host;
+pub mod worker_pool;
 
 use deno_core::OpState;
 
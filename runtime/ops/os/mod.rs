@@ -9,17 +9,23 @@ use deno_core::op2;
 use deno_core::v8;
 use deno_core::OpState;
 use deno_node::NODE_ENV_VAR_ALLOWLIST;
+use deno_permissions::PermissionState;
 use deno_permissions::PermissionsContainer;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::env;
+use std::path::PathBuf;
 
 mod sys_info;
 
+pub const UNSTABLE_FEATURE_NAME: &str = "os";
+
 deno_core::extension!(
   deno_os,
   ops = [
     op_env,
+    op_env_diff,
+    op_env_listen,
     op_exec_path,
     op_exit,
     op_delete_env,
@@ -34,8 +40,11 @@ deno_core::extension!(
     op_set_exit_code,
     op_get_exit_code,
     op_system_memory_info,
+    op_system_cpu_usage,
+    op_disk_usage,
     op_uid,
     op_runtime_memory_usage,
+    op_process_metrics,
   ],
   options = {
     exit_code: ExitCode,
@@ -49,6 +58,8 @@ deno_core::extension!(
   deno_os_worker,
   ops = [
     op_env,
+    op_env_diff,
+    op_env_listen,
     op_exec_path,
     op_exit,
     op_delete_env,
@@ -63,8 +74,11 @@ deno_core::extension!(
     op_set_exit_code,
     op_get_exit_code,
     op_system_memory_info,
+    op_system_cpu_usage,
+    op_disk_usage,
     op_uid,
     op_runtime_memory_usage,
+    op_process_metrics,
   ],
   middleware = |op| match op.name {
     "op_exit" | "op_set_exit_code" | "op_get_exit_code" =>
@@ -86,12 +100,14 @@ fn op_exec_path(state: &mut OpState) -> Result<String, AnyError> {
   into_string(path.into_os_string())
 }
 
-#[op2(fast)]
+/// Returns the previous value, for `change` events of `Deno.env`.
+#[op2]
+#[string]
 fn op_set_env(
   state: &mut OpState,
   #[string] key: &str,
   #[string] value: &str,
-) -> Result<(), AnyError> {
+) -> Result<Option<String>, AnyError> {
   state.borrow_mut::<PermissionsContainer>().check_env(key)?;
   if key.is_empty() {
     return Err(type_error("Key is an empty string."));
@@ -106,17 +122,88 @@ fn op_set_env(
       "Value contains invalid characters: {value:?}"
     )));
   }
+  let previous = previous_var(key);
   env::set_var(key, value);
-  Ok(())
+  Ok(previous)
+}
+
+fn previous_var(key: &str) -> Option<String> {
+  env::var_os(key).map(|value| value.to_string_lossy().into_owned())
 }
 
 #[op2]
 #[serde]
 fn op_env(state: &mut OpState) -> Result<HashMap<String, String>, AnyError> {
-  state.borrow_mut::<PermissionsContainer>().check_env_all()?;
+  env_vars(state)
+}
+
+fn env_vars(state: &mut OpState) -> Result<HashMap<String, String>, AnyError> {
+  let permissions = state.borrow_mut::<PermissionsContainer>();
+  {
+    // with access to a namespace of variables granted, e.g. `AWS_*`, only
+    // those are returned
+    let permissions = permissions.0.lock();
+    if permissions.env.has_granted_patterns() {
+      return Ok(
+        env::vars()
+          .filter(|(key, _)| {
+            permissions.env.query(Some(key)) == PermissionState::Granted
+          })
+          .collect(),
+      );
+    }
+  }
+  permissions.check_env_all()?;
   Ok(env::vars().collect())
 }
 
+#[derive(Default, Serialize)]
+struct EnvDiff {
+  added: HashMap<String, String>,
+  changed: HashMap<String, String>,
+  removed: Vec<String>,
+}
+
+/// Called before adding a listener for `change` events of `Deno.env`, which
+/// are dispatched in JS.
+#[op2(fast)]
+fn op_env_listen(state: &mut OpState) {
+  super::check_unstable(
+    state,
+    UNSTABLE_FEATURE_NAME,
+    "Deno.env.addEventListener",
+  );
+}
+
+/// Compares `snapshot`, from `op_env`, to the current environment. Only the
+/// variables `op_env` would return are compared.
+#[op2]
+#[serde]
+fn op_env_diff(
+  state: &mut OpState,
+  #[serde] snapshot: HashMap<String, String>,
+) -> Result<EnvDiff, AnyError> {
+  super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.env.diff");
+  let mut current = env_vars(state)?;
+  let permissions = state.borrow_mut::<PermissionsContainer>().0.lock();
+  let mut diff = EnvDiff::default();
+  for (key, value) in snapshot {
+    match current.remove(&key) {
+      Some(current) if current != value => {
+        diff.changed.insert(key, current);
+      }
+      Some(_) => {}
+      // variables without access would be missing from `current` either way
+      None if permissions.env.query(Some(&key)) == PermissionState::Granted => {
+        diff.removed.push(key);
+      }
+      None => {}
+    }
+  }
+  diff.added = current;
+  Ok(diff)
+}
+
 #[op2]
 #[string]
 fn op_get_env(
@@ -146,17 +233,20 @@ fn op_get_env(
   Ok(r)
 }
 
-#[op2(fast)]
+/// Returns the previous value, like `op_set_env`.
+#[op2]
+#[string]
 fn op_delete_env(
   state: &mut OpState,
   #[string] key: String,
-) -> Result<(), AnyError> {
+) -> Result<Option<String>, AnyError> {
   state.borrow_mut::<PermissionsContainer>().check_env(&key)?;
   if key.is_empty() || key.contains(&['=', '\0'] as &[char]) {
     return Err(type_error("Key contains invalid characters."));
   }
+  let previous = previous_var(&key);
   env::remove_var(key);
-  Ok(())
+  Ok(previous)
 }
 
 #[op2(fast)]
@@ -173,6 +263,7 @@ fn op_get_exit_code(state: &mut OpState) -> i32 {
 #[op2(fast)]
 fn op_exit(state: &mut OpState) {
   let code = state.borrow::<ExitCode>().get();
+  crate::ops::process::shutdown_children();
   std::process::exit(code)
 }
 
@@ -266,6 +357,62 @@ fn op_system_memory_info(
   Ok(sys_info::mem_info())
 }
 
+/// The busy and total time of every core when `Deno.systemCpuUsage()` was last
+/// called.
+#[derive(Default)]
+struct CpuTimesSample(Vec<(u64, u64)>);
+
+#[op2]
+#[serde]
+fn op_system_cpu_usage(state: &mut OpState) -> Result<Vec<f64>, AnyError> {
+  super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.systemCpuUsage");
+  state
+    .borrow_mut::<PermissionsContainer>()
+    .check_sys("cpus", "Deno.systemCpuUsage()")?;
+  let cpus = deno_node::cpu_info()
+    .ok_or_else(|| type_error("Failed to get cpu info"))?;
+  let sample = cpus
+    .iter()
+    .map(|cpu| {
+      let times = &cpu.times;
+      let busy = times.user + times.nice + times.sys + times.irq;
+      (busy, busy + times.idle)
+    })
+    .collect::<Vec<_>>();
+  // the first call measures the usage since boot
+  let previous = state.try_take::<CpuTimesSample>().unwrap_or_default();
+  let usage = sample
+    .iter()
+    .enumerate()
+    .map(|(i, (busy, total))| {
+      let (previous_busy, previous_total) =
+        previous.0.get(i).copied().unwrap_or_default();
+      let elapsed = total.saturating_sub(previous_total);
+      if elapsed == 0 {
+        0.0
+      } else {
+        busy.saturating_sub(previous_busy) as f64 / elapsed as f64
+      }
+    })
+    .collect();
+  state.put(CpuTimesSample(sample));
+  Ok(usage)
+}
+
+#[op2]
+#[serde]
+fn op_disk_usage(
+  state: &mut OpState,
+  #[string] path: String,
+) -> Result<sys_info::DiskUsage, AnyError> {
+  super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.diskUsage");
+  let path = PathBuf::from(path);
+  let permissions = state.borrow_mut::<PermissionsContainer>();
+  permissions.check_read(&path, "Deno.diskUsage()")?;
+  permissions.check_sys("statfs", "Deno.diskUsage()")?;
+  Ok(sys_info::disk_usage(&path)?)
+}
+
 #[cfg(not(windows))]
 #[op2]
 #[smi]
@@ -337,6 +484,32 @@ fn op_runtime_memory_usage(scope: &mut v8::HandleScope) -> MemoryUsage {
   }
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProcessMetrics {
+  rss: usize,
+  max_rss: u64,
+  user_cpu_time: u64,
+  system_cpu_time: u64,
+  open_fds: Option<u64>,
+  resources: usize,
+}
+
+#[op2]
+#[serde]
+fn op_process_metrics(state: &mut OpState) -> ProcessMetrics {
+  super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.processMetrics");
+  let usage = sys_info::process_usage();
+  ProcessMetrics {
+    rss: rss(),
+    max_rss: usage.max_rss,
+    user_cpu_time: usage.user_cpu_time,
+    system_cpu_time: usage.system_cpu_time,
+    open_fds: usage.open_fds,
+    resources: state.resource_table.len(),
+  }
+}
+
 #[cfg(any(target_os = "android", target_os = "linux"))]
 fn rss() -> usize {
   // Inspired by https://github.com/Arc-blroth/memory-stats/blob/5364d0d09143de2a470d33161b2330914228fde9/src/linux.rs
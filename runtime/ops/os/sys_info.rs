@@ -2,6 +2,8 @@
 #[cfg(target_family = "windows")]
 use std::sync::Once;
 
+use std::path::Path;
+
 type LoadAvg = (f64, f64, f64);
 const DEFAULT_LOADAVG: LoadAvg = (0.0, 0.0, 0.0);
 
@@ -192,6 +194,8 @@ pub fn hostname() -> String {
   }
 }
 
+/// The memory of the system in bytes. Fields which a platform doesn't report
+/// are 0.
 #[derive(serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MemInfo {
@@ -231,14 +235,22 @@ pub fn mem_info() -> Option<MemInfo> {
       mem_info.buffers = info.bufferram * mem_unit;
     }
 
-    // Gets the available memory from /proc/meminfo in linux for compatibility
+    // Gets the available memory and the page cache from /proc/meminfo in
+    // linux, which `sysinfo` doesn't report
     #[allow(clippy::disallowed_methods)]
     if let Ok(meminfo) = std::fs::read_to_string("/proc/meminfo") {
-      let line = meminfo.lines().find(|l| l.starts_with("MemAvailable:"));
-      if let Some(line) = line {
-        let mem = line.split_whitespace().nth(1);
-        let mem = mem.and_then(|v| v.parse::<u64>().ok());
-        mem_info.available = mem.unwrap_or(0);
+      let kib = |name: &str| {
+        meminfo.lines().find_map(|line| {
+          let value = line.strip_prefix(name)?.strip_prefix(':')?;
+          let value = value.trim().strip_suffix("kB").unwrap_or(value);
+          value.trim().parse::<u64>().ok()
+        })
+      };
+      if let Some(available) = kib("MemAvailable") {
+        mem_info.available = available * 1024;
+      }
+      if let Some(cached) = kib("Cached") {
+        mem_info.cached = cached * 1024;
       }
     }
   }
@@ -291,11 +303,9 @@ pub fn mem_info() -> Option<MemInfo> {
         // TODO(@littledivy): Put this in a once_cell
         let page_size = libc::sysconf(libc::_SC_PAGESIZE) as u64;
         mem_info.available =
-          (stat.free_count as u64 + stat.inactive_count as u64) * page_size
-            / 1024;
+          (stat.free_count as u64 + stat.inactive_count as u64) * page_size;
         mem_info.free =
-          (stat.free_count as u64 - stat.speculative_count as u64) * page_size
-            / 1024;
+          (stat.free_count as u64 - stat.speculative_count as u64) * page_size;
       }
     }
   }
@@ -320,7 +330,7 @@ pub fn mem_info() -> Option<MemInfo> {
     if result != 0 {
       let stat = mem_status.assume_init();
       mem_info.total = stat.ullTotalPhys;
-      mem_info.available = 0;
+      mem_info.available = stat.ullAvailPhys;
       mem_info.free = stat.ullAvailPhys;
       mem_info.cached = 0;
       mem_info.buffers = 0;
@@ -346,8 +356,8 @@ pub fn mem_info() -> Option<MemInfo> {
             .CommitLimit
             .saturating_sub(perf_info.PhysicalTotal)
             .saturating_sub(perf_info.PhysicalAvailable);
-        mem_info.swap_total = (swap_total / 1000) as u64;
-        mem_info.swap_free = (swap_free / 1000) as u64;
+        mem_info.swap_total = swap_total as u64;
+        mem_info.swap_free = swap_free as u64;
       }
     }
   }
@@ -423,3 +433,188 @@ pub fn os_uptime() -> u64 {
 
   uptime
 }
+
+/// The usage of a file system in bytes. `available` is what unprivileged users
+/// can still use, which may be less than `free`.
+#[derive(serde::Serialize)]
+pub struct DiskUsage {
+  pub total: u64,
+  pub free: u64,
+  pub available: u64,
+}
+
+pub fn disk_usage(path: &Path) -> std::io::Result<DiskUsage> {
+  #[cfg(target_family = "unix")]
+  {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: `path` is NUL-terminated and `stat` is written by `statvfs`.
+    let stat = unsafe {
+      let mut stat = std::mem::zeroed::<libc::statvfs>();
+      if libc::statvfs(path.as_ptr(), &mut stat) != 0 {
+        return Err(std::io::Error::last_os_error());
+      }
+      stat
+    };
+    let block_size = stat.f_frsize as u64;
+    Ok(DiskUsage {
+      total: stat.f_blocks as u64 * block_size,
+      free: stat.f_bfree as u64 * block_size,
+      available: stat.f_bavail as u64 * block_size,
+    })
+  }
+
+  #[cfg(target_family = "windows")]
+  {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let path = path
+      .as_os_str()
+      .encode_wide()
+      .chain(Some(0))
+      .collect::<Vec<_>>();
+    let mut usage = DiskUsage {
+      total: 0,
+      free: 0,
+      available: 0,
+    };
+    // SAFETY: `path` is NUL-terminated.
+    let res = unsafe {
+      GetDiskFreeSpaceExW(
+        path.as_ptr(),
+        &mut usage.available,
+        &mut usage.total,
+        &mut usage.free,
+      )
+    };
+    if res == 0 {
+      return Err(std::io::Error::last_os_error());
+    }
+    Ok(usage)
+  }
+}
+
+/// Resource usage of the current process. CPU times are in microseconds.
+pub struct ProcessUsage {
+  pub user_cpu_time: u64,
+  pub system_cpu_time: u64,
+  pub max_rss: u64,
+  /// The number of open file descriptors, or handles on Windows.
+  pub open_fds: Option<u64>,
+}
+
+pub fn process_usage() -> ProcessUsage {
+  #[cfg(target_family = "unix")]
+  {
+    // SAFETY: `usage` is written by `getrusage`, which can't fail for
+    // `RUSAGE_SELF`.
+    let usage = unsafe {
+      let mut usage = std::mem::zeroed::<libc::rusage>();
+      libc::getrusage(libc::RUSAGE_SELF, &mut usage);
+      usage
+    };
+    let micros = |time: libc::timeval| {
+      time.tv_sec as u64 * 1_000_000 + time.tv_usec as u64
+    };
+    // `ru_maxrss` is in bytes on apple platforms, and in KiB elsewhere
+    #[cfg(target_vendor = "apple")]
+    let max_rss = usage.ru_maxrss as u64;
+    #[cfg(not(target_vendor = "apple"))]
+    let max_rss = usage.ru_maxrss as u64 * 1024;
+    ProcessUsage {
+      user_cpu_time: micros(usage.ru_utime),
+      system_cpu_time: micros(usage.ru_stime),
+      max_rss,
+      open_fds: open_fds(),
+    }
+  }
+
+  #[cfg(target_family = "windows")]
+  // SAFETY: winapi calls
+  unsafe {
+    use winapi::shared::minwindef::DWORD;
+    use winapi::shared::minwindef::FALSE;
+    use winapi::shared::minwindef::FILETIME;
+    use winapi::um::processthreadsapi::GetCurrentProcess;
+    use winapi::um::processthreadsapi::GetProcessHandleCount;
+    use winapi::um::processthreadsapi::GetProcessTimes;
+    use winapi::um::psapi::GetProcessMemoryInfo;
+    use winapi::um::psapi::PROCESS_MEMORY_COUNTERS;
+
+    let mut usage = ProcessUsage {
+      user_cpu_time: 0,
+      system_cpu_time: 0,
+      max_rss: 0,
+      open_fds: None,
+    };
+    // this handle is a constant—no need to close it
+    let current_process = GetCurrentProcess();
+
+    let mut creation_time = std::mem::zeroed::<FILETIME>();
+    let mut exit_time = std::mem::zeroed::<FILETIME>();
+    let mut kernel_time = std::mem::zeroed::<FILETIME>();
+    let mut user_time = std::mem::zeroed::<FILETIME>();
+    if GetProcessTimes(
+      current_process,
+      &mut creation_time,
+      &mut exit_time,
+      &mut kernel_time,
+      &mut user_time,
+    ) != FALSE
+    {
+      // `FILETIME`s count 100 nanosecond intervals
+      let micros = |time: FILETIME| {
+        ((time.dwHighDateTime as u64) << 32 | time.dwLowDateTime as u64) / 10
+      };
+      usage.user_cpu_time = micros(user_time);
+      usage.system_cpu_time = micros(kernel_time);
+    }
+
+    let mut pmc: PROCESS_MEMORY_COUNTERS = std::mem::zeroed();
+    if GetProcessMemoryInfo(
+      current_process,
+      &mut pmc,
+      std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as DWORD,
+    ) != FALSE
+    {
+      usage.max_rss = pmc.PeakWorkingSetSize as u64;
+    }
+
+    let mut handles: DWORD = 0;
+    if GetProcessHandleCount(current_process, &mut handles) != FALSE {
+      usage.open_fds = Some(handles as u64);
+    }
+    usage
+  }
+}
+
+#[cfg(any(
+  target_os = "android",
+  target_os = "linux",
+  target_vendor = "apple"
+))]
+fn open_fds() -> Option<u64> {
+  #[cfg(any(target_os = "android", target_os = "linux"))]
+  let dir = "/proc/self/fd";
+  #[cfg(target_vendor = "apple")]
+  let dir = "/dev/fd";
+
+  #[allow(clippy::disallowed_methods)]
+  let entries = std::fs::read_dir(dir).ok()?;
+  // the directory itself is open while it's read
+  Some(entries.count().saturating_sub(1) as u64)
+}
+
+#[cfg(all(
+  target_family = "unix",
+  not(any(
+    target_os = "android",
+    target_os = "linux",
+    target_vendor = "apple"
+  ))
+))]
+fn open_fds() -> Option<u64> {
+  None
+}
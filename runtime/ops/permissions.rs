@@ -2,21 +2,31 @@
 
 use ::deno_permissions::parse_sys_kind;
 use ::deno_permissions::PermissionState;
+use ::deno_permissions::Permissions;
 use ::deno_permissions::PermissionsContainer;
+use ::deno_permissions::PermissionsSnapshot;
 use deno_core::error::custom_error;
 use deno_core::error::AnyError;
 use deno_core::op2;
 use deno_core::OpState;
+
+use super::process::get_requires_allow_all_env_var;
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::path::Path;
 
+pub const UNSTABLE_FEATURE_NAME: &str = "permissions";
+
 deno_core::extension!(
   deno_permissions,
   ops = [
     op_query_permission,
     op_revoke_permission,
     op_request_permission,
+    op_permissions_snapshot,
+    op_mint_permission_token,
+    op_revoke_permission_token,
   ],
 );
 
@@ -28,6 +38,10 @@ pub struct PermissionArgs {
   variable: Option<String>,
   kind: Option<String>,
   command: Option<String>,
+  /// The arguments of a run invocation, for queries.
+  args: Option<Vec<String>>,
+  /// The env vars of a run invocation, for queries.
+  env: Option<HashMap<String, String>>,
 }
 
 #[derive(Serialize)]
@@ -71,7 +85,12 @@ pub fn op_query_permission(
     "sys" => permissions
       .sys
       .query(args.kind.as_deref().map(parse_sys_kind).transpose()?),
-    "run" => permissions.run.query(args.command.as_deref()),
+    "run" => match &args.command {
+      Some(command) if args.args.is_some() || args.env.is_some() => {
+        query_run_invocation(&permissions, command, &args)
+      }
+      command => permissions.run.query(command.as_deref()),
+    },
     "ffi" => permissions.ffi.query(args.path.as_deref().map(Path::new)),
     n => {
       return Err(custom_error(
@@ -83,13 +102,61 @@ pub fn op_query_permission(
   Ok(PermissionStatus::from(perm))
 }
 
+/// Whether spawning `command` with the given arguments and env vars would be
+/// allowed by the same checks as `Deno.Command`, without spawning it.
+fn query_run_invocation(
+  permissions: &Permissions,
+  command: &str,
+  args: &PermissionArgs,
+) -> PermissionState {
+  let state = permissions.run.query(Some(command));
+  if state == PermissionState::Denied {
+    return state;
+  }
+  let command_args = args.args.as_deref().unwrap_or_default();
+  if permissions.run.check_args(command, command_args).is_err() {
+    return PermissionState::Denied;
+  }
+  let env = args
+    .env
+    .iter()
+    .flatten()
+    .map(|(key, value)| (key.clone(), value.clone()))
+    .collect::<Vec<_>>();
+  if get_requires_allow_all_env_var(&env, false, None).is_some() {
+    // spawning then requires all run permissions, which can still be
+    // granted in a prompt
+    match permissions.run.query(None) {
+      PermissionState::Granted => {}
+      PermissionState::Prompt => return PermissionState::Prompt,
+      _ => return PermissionState::Denied,
+    }
+  }
+  state
+}
+
 #[op2]
 #[serde]
 pub fn op_revoke_permission(
   state: &mut OpState,
   #[serde] args: PermissionArgs,
+  workers: bool,
 ) -> Result<PermissionStatus, AnyError> {
-  let mut permissions = state.borrow_mut::<PermissionsContainer>().0.lock();
+  let permissions = state.borrow::<PermissionsContainer>();
+  let perm = revoke(&mut permissions.0.lock(), &args)?;
+  if workers {
+    // the descriptor was already validated above
+    permissions.for_each_descendant(|permissions| {
+      let _ = revoke(permissions, &args);
+    });
+  }
+  Ok(PermissionStatus::from(perm))
+}
+
+fn revoke(
+  permissions: &mut Permissions,
+  args: &PermissionArgs,
+) -> Result<PermissionState, AnyError> {
   let path = args.path.as_deref();
   let perm = match args.name.as_ref() {
     "read" => permissions.read.revoke(path.map(Path::new)),
@@ -114,7 +181,7 @@ pub fn op_revoke_permission(
       ))
     }
   };
-  Ok(PermissionStatus::from(perm))
+  Ok(perm)
 }
 
 #[op2]
@@ -150,3 +217,45 @@ pub fn op_request_permission(
   };
   Ok(PermissionStatus::from(perm))
 }
+
+#[op2]
+#[serde]
+pub fn op_permissions_snapshot(state: &mut OpState) -> PermissionsSnapshot {
+  super::check_unstable(
+    state,
+    UNSTABLE_FEATURE_NAME,
+    "Deno.permissions.snapshot",
+  );
+  state.borrow::<PermissionsContainer>().0.lock().snapshot()
+}
+
+#[op2]
+#[string]
+pub fn op_mint_permission_token(
+  state: &mut OpState,
+  #[string] command: String,
+) -> Result<String, AnyError> {
+  super::check_unstable(
+    state,
+    UNSTABLE_FEATURE_NAME,
+    "Deno.permissions.mintToken",
+  );
+  state
+    .borrow::<PermissionsContainer>()
+    .mint_run_token(&command, "Deno.permissions.mintToken()")
+}
+
+#[op2(fast)]
+pub fn op_revoke_permission_token(
+  state: &mut OpState,
+  #[string] token: &str,
+) -> bool {
+  super::check_unstable(
+    state,
+    UNSTABLE_FEATURE_NAME,
+    "Deno.permissions.revokeToken",
+  );
+  state
+    .borrow::<PermissionsContainer>()
+    .revoke_run_token(token)
+}
@@ -1,12 +1,15 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
-use deno_core::anyhow::Context;
+use deno_core::error::custom_error;
 use deno_core::error::type_error;
 use deno_core::error::AnyError;
 use deno_core::op2;
 use deno_core::serde_json;
 use deno_core::AsyncMutFuture;
 use deno_core::AsyncRefCell;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::JsBuffer;
 use deno_core::OpState;
 use deno_core::RcRef;
 use deno_core::Resource;
@@ -20,9 +23,13 @@ use deno_permissions::PermissionsContainer;
 use serde::Deserialize;
 use serde::Serialize;
 use std::borrow::Cow;
+use std::cell::Cell;
 use std::cell::RefCell;
+use std::mem::ManuallyDrop;
 use std::process::ExitStatus;
 use std::rc::Rc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
 
 #[cfg(windows)]
@@ -33,8 +40,37 @@ use std::os::unix::prelude::ExitStatusExt;
 #[cfg(unix)]
 use std::os::unix::process::CommandExt;
 
+mod affinity;
+#[cfg(windows)]
+mod create_process;
+mod env;
+mod error;
+mod inherited_fds;
+mod ipc;
+#[cfg(target_os = "linux")]
+mod pidfd;
+mod pty;
+mod reaper;
+mod shared_memory;
+mod shell;
+mod shutdown;
+mod usage;
+
+pub use error::ProcessError;
+pub use inherited_fds::InheritedFdsTable;
+pub use inherited_fds::RawInheritedHandle;
+pub use ipc::IpcSerialization;
+pub use ipc::ParentIpcChannel;
+pub use shutdown::set_child_shutdown_policy;
+pub use shutdown::shutdown_children;
+pub use shutdown::ChildShutdownPolicy;
+
 pub const UNSTABLE_FEATURE_NAME: &str = "process";
 
+/// How long a child that exceeded its `timeout` has to exit after `SIGTERM`,
+/// before it is sent `SIGKILL`.
+const TIMEOUT_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
 #[derive(Copy, Clone, Eq, PartialEq, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Stdio {
@@ -117,22 +153,421 @@ deno_core::extension!(
   ops = [
     op_spawn_child,
     op_spawn_wait,
+    op_spawn_try_wait,
     op_spawn_sync,
+    op_spawn_children_list,
     op_spawn_kill,
+    op_spawn_abort,
+    op_spawn_forward_signals,
+    pty::op_pty_resize,
+    ipc::op_ipc_channel_parent,
+    ipc::op_ipc_channel_send,
+    ipc::op_ipc_channel_recv,
+    shared_memory::op_shared_memory_create,
+    shared_memory::op_shared_memory_open,
+    shared_memory::op_shared_memory_buffer,
     deprecated::op_run,
     deprecated::op_run_status,
     deprecated::op_kill,
   ],
 );
 
-/// Second member stores the pid separately from the RefCell. It's needed for
-/// `op_spawn_kill`, where the RefCell is borrowed mutably by `op_spawn_wait`.
-struct ChildResource(RefCell<tokio::process::Child>, u32);
+/// A child spawned by `op_spawn_child`. On Windows, children which
+/// `std::process::Command` can't create are created by
+/// `create_process::spawn` instead.
+enum SpawnedChild {
+  Tokio(tokio::process::Child),
+  #[cfg(windows)]
+  Win32(create_process::Child),
+}
+
+impl SpawnedChild {
+  /// Returns `None` once the child was reaped.
+  fn id(&self) -> Option<u32> {
+    match self {
+      Self::Tokio(child) => child.id(),
+      #[cfg(windows)]
+      Self::Win32(child) => Some(child.id()),
+    }
+  }
+
+  #[cfg(windows)]
+  fn raw_handle(&self) -> Option<std::os::windows::io::RawHandle> {
+    match self {
+      Self::Tokio(child) => child.raw_handle(),
+      Self::Win32(child) => Some(child.as_raw_handle()),
+    }
+  }
+
+  async fn wait(&mut self) -> std::io::Result<ExitStatus> {
+    match self {
+      Self::Tokio(child) => child.wait().await,
+      #[cfg(windows)]
+      Self::Win32(child) => child.wait().await,
+    }
+  }
+
+  /// Kills the child if it is still running, and makes sure that it is
+  /// reaped.
+  fn abandon(self) {
+    match self {
+      Self::Tokio(child) => reaper::adopt(child),
+      // killed on drop, and there are no zombies to reap on Windows
+      #[cfg(windows)]
+      Self::Win32(child) => drop(child),
+    }
+  }
+}
+
+/// A child spawned by `op_spawn_sync`, see [`SpawnedChild`].
+enum SyncChild {
+  Std(std::process::Child),
+  #[cfg(windows)]
+  Win32(create_process::Child),
+}
+
+/// The ends of the stdio pipes of a [`SyncChild`] for this process.
+type SyncPipes = (
+  Option<Box<dyn std::io::Write + Send>>,
+  Option<Box<dyn std::io::Read + Send>>,
+  Option<Box<dyn std::io::Read + Send>>,
+);
+
+impl SyncChild {
+  fn id(&self) -> u32 {
+    match self {
+      Self::Std(child) => child.id(),
+      #[cfg(windows)]
+      Self::Win32(child) => child.id(),
+    }
+  }
+
+  #[cfg(windows)]
+  fn raw_handle(&self) -> std::os::windows::io::RawHandle {
+    use std::os::windows::io::AsRawHandle;
+    match self {
+      Self::Std(child) => child.as_raw_handle(),
+      Self::Win32(child) => child.as_raw_handle(),
+    }
+  }
+
+  fn take_pipes(&mut self) -> SyncPipes {
+    match self {
+      Self::Std(child) => (
+        child.stdin.take().map(|pipe| Box::new(pipe) as _),
+        child.stdout.take().map(|pipe| Box::new(pipe) as _),
+        child.stderr.take().map(|pipe| Box::new(pipe) as _),
+      ),
+      #[cfg(windows)]
+      Self::Win32(child) => (
+        child
+          .stdin
+          .take()
+          .map(|pipe| Box::new(std::fs::File::from(pipe)) as _),
+        child
+          .stdout
+          .take()
+          .map(|pipe| Box::new(std::fs::File::from(pipe)) as _),
+        child
+          .stderr
+          .take()
+          .map(|pipe| Box::new(std::fs::File::from(pipe)) as _),
+      ),
+    }
+  }
+
+  /// Kills the child and waits for it, after it couldn't be set up.
+  fn kill(&mut self) {
+    match self {
+      Self::Std(child) => {
+        let _ = child.kill();
+        let _ = child.wait();
+      }
+      #[cfg(windows)]
+      Self::Win32(child) => {
+        let _ = child.start_kill();
+        let _ = child.wait_sync();
+      }
+    }
+  }
+
+  fn wait(&mut self) -> std::io::Result<ExitStatus> {
+    match self {
+      Self::Std(child) => child.wait(),
+      #[cfg(windows)]
+      Self::Win32(child) => child.wait_sync(),
+    }
+  }
+}
+
+struct ChildResource {
+  /// Only taken on drop, to be abandoned.
+  child: RefCell<ManuallyDrop<SpawnedChild>>,
+  /// Stored separately from the RefCell. It's needed for `op_spawn_kill`,
+  /// where the RefCell is borrowed mutably by `op_spawn_wait`.
+  pid: u32,
+  /// The program the child was spawned with.
+  command: String,
+  /// Set if the child was spawned with `processGroup: true`.
+  group: Option<ProcessGroup>,
+  /// Canceled once the child has exited, which stops any pending kill
+  /// escalation started by `op_spawn_abort` and the forwarding of signals.
+  exited: CancelHandle,
+  /// The child is killed by `op_spawn_wait` if it is still running at this
+  /// point in time.
+  deadline: Option<tokio::time::Instant>,
+  /// Set by `op_spawn_wait` once the child was killed for exceeding its
+  /// deadline.
+  timed_out: Cell<bool>,
+  /// The pseudo console the child is attached to, which is closed by
+  /// `op_spawn_wait` once the child has exited.
+  #[cfg(windows)]
+  pty: Option<Rc<pty::PtyResource>>,
+  /// Set by `op_spawn_wait` once the child was reaped. The resource is closed
+  /// by JS once it got the status, and until then `op_spawn_try_wait` returns
+  /// this status, as the child can't be looked up by its pid anymore.
+  status: RefCell<Option<ChildStatus>>,
+}
+
+impl ChildResource {
+  /// Whether the child was reaped. Its pid may have been reused since, so it
+  /// must not be signaled anymore.
+  fn reaped(&self) -> bool {
+    self.status.borrow().is_some()
+  }
+
+  /// Sends `signal` to the child, or to its whole group if it has one.
+  fn kill(&self, signal: &str) -> Result<(), AnyError> {
+    match &self.group {
+      Some(group) => group.kill(signal),
+      None => deprecated::kill(self.pid as i32, signal),
+    }
+  }
+
+  /// Waits for `child`, which is the child of this resource. Once the
+  /// deadline has passed, the child is sent `SIGTERM`, and `SIGKILL` if it is
+  /// still running after `TIMEOUT_GRACE_PERIOD`. Returns whether it timed out
+  /// along with its status.
+  async fn wait(
+    &self,
+    child: &mut SpawnedChild,
+  ) -> std::io::Result<((ExitStatus, Option<usage::ResourceUsage>), bool)> {
+    let Some(deadline) = self.deadline else {
+      return Ok((usage::wait(child).await?, false));
+    };
+    if let Ok(result) =
+      tokio::time::timeout_at(deadline, usage::wait(child)).await
+    {
+      return Ok((result?, false));
+    }
+    self.timed_out.set(true);
+    // Killing fails with ESRCH if the child exited in the meantime. It is
+    // waited for either way.
+    let _ = self.kill("SIGTERM");
+    if let Ok(result) =
+      tokio::time::timeout(TIMEOUT_GRACE_PERIOD, usage::wait(child)).await
+    {
+      return Ok((result?, true));
+    }
+    let _ = self.kill("SIGKILL");
+    Ok((usage::wait(child).await?, true))
+  }
+}
 
 impl Resource for ChildResource {
   fn name(&self) -> Cow<str> {
     "child".into()
   }
+
+  fn close(self: Rc<Self>) {
+    self.exited.cancel();
+  }
+}
+
+impl Drop for ChildResource {
+  fn drop(&mut self) {
+    shutdown::unregister(self.pid);
+    #[cfg(windows)]
+    deprecated::unregister_group_leader(self.pid);
+    // SAFETY: the child is never accessed again
+    let child = unsafe { ManuallyDrop::take(self.child.get_mut()) };
+    child.abandon();
+  }
+}
+
+/// Checks whether the child with the given pid has exited, without reaping
+/// it. `ChildResource::child` can't be used for this, as it is borrowed by a
+/// pending `op_spawn_wait`, which is still the one to collect the exit status.
+#[cfg(unix)]
+fn peek_exit_status(pid: u32) -> Result<Option<ExitStatus>, AnyError> {
+  // SAFETY: zeroed is a valid representation for `siginfo_t`
+  let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+  // SAFETY: libc call, `info` outlives the call
+  let ret = unsafe {
+    libc::waitid(
+      libc::P_PID,
+      pid as libc::id_t,
+      &mut info,
+      libc::WEXITED | libc::WNOHANG | libc::WNOWAIT,
+    )
+  };
+  if ret == -1 {
+    return Err(std::io::Error::last_os_error().into());
+  }
+  // SAFETY: `si_pid` is set by a successful `waitid` call, and left zeroed
+  // by `WNOHANG` if the child is still running
+  if unsafe { info.si_pid() } == 0 {
+    return Ok(None);
+  }
+  // SAFETY: the child has exited, so `si_status` is set
+  let status = unsafe { info.si_status() };
+  // Re-encode as a wait status, which is what `ExitStatus` wraps.
+  let raw = match info.si_code {
+    libc::CLD_EXITED => (status & 0xff) << 8,
+    libc::CLD_DUMPED => status | 0x80,
+    _ => status,
+  };
+  Ok(Some(ExitStatus::from_raw(raw)))
+}
+
+#[cfg(windows)]
+fn peek_exit_status(pid: u32) -> Result<Option<ExitStatus>, AnyError> {
+  use std::os::windows::process::ExitStatusExt;
+  use winapi::shared::minwindef::DWORD;
+  use winapi::shared::minwindef::FALSE;
+  use winapi::shared::winerror::WAIT_TIMEOUT;
+  use winapi::um::handleapi::CloseHandle;
+  use winapi::um::processthreadsapi::GetExitCodeProcess;
+  use winapi::um::processthreadsapi::OpenProcess;
+  use winapi::um::synchapi::WaitForSingleObject;
+  use winapi::um::winbase::WAIT_OBJECT_0;
+  use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+  use winapi::um::winnt::SYNCHRONIZE;
+
+  // The pid can't be reused while the child isn't reaped, as tokio holds on
+  // to the child's own handle.
+  // SAFETY: winapi call
+  let handle = unsafe {
+    OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION | SYNCHRONIZE, FALSE, pid)
+  };
+  if handle.is_null() {
+    return Err(std::io::Error::last_os_error().into());
+  }
+  let mut code: DWORD = 0;
+  // SAFETY: winapi calls, `handle` is valid and closed exactly once
+  unsafe {
+    let result = match WaitForSingleObject(handle, 0) {
+      WAIT_TIMEOUT => Ok(None),
+      WAIT_OBJECT_0 if GetExitCodeProcess(handle, &mut code) != FALSE => {
+        Ok(Some(ExitStatus::from_raw(code)))
+      }
+      _ => Err(std::io::Error::last_os_error().into()),
+    };
+    CloseHandle(handle);
+    result
+  }
+}
+
+/// The process group (unix) or job object (Windows) a child was placed in,
+/// used to signal the child together with all of its descendants.
+#[cfg(unix)]
+struct ProcessGroup {
+  pgid: i32,
+}
+
+#[cfg(unix)]
+impl ProcessGroup {
+  fn new(child: &SpawnedChild) -> Result<Self, AnyError> {
+    // The child is made a group leader in `create_command`, so its pgid is
+    // the same as its pid.
+    let pid = child
+      .id()
+      .ok_or_else(|| type_error("Child process has already terminated."))?;
+    Ok(Self { pgid: pid as i32 })
+  }
+
+  fn kill(&self, signal: &str) -> Result<(), AnyError> {
+    let signo = super::signal::signal_str_to_int(signal)?;
+    // SAFETY: libc call, nix's `Signal` doesn't cover realtime signals
+    let res = unsafe { libc::killpg(self.pgid, signo) };
+    nix::errno::Errno::result(res)?;
+    Ok(())
+  }
+}
+
+#[cfg(windows)]
+struct ProcessGroup {
+  job: winapi::um::winnt::HANDLE,
+}
+
+#[cfg(windows)]
+impl ProcessGroup {
+  fn new(child: &SpawnedChild) -> Result<Self, AnyError> {
+    use winapi::shared::minwindef::FALSE;
+    use winapi::um::jobapi2::AssignProcessToJobObject;
+    use winapi::um::jobapi2::CreateJobObjectW;
+
+    // SAFETY: winapi call
+    let job =
+      unsafe { CreateJobObjectW(std::ptr::null_mut(), std::ptr::null()) };
+    if job.is_null() {
+      return Err(std::io::Error::last_os_error().into());
+    }
+    // Constructed before assigning so the job handle is closed on error.
+    let group = Self { job };
+    let handle = child
+      .raw_handle()
+      .ok_or_else(|| type_error("Child process has already terminated."))?;
+    // SAFETY: winapi call, both handles are valid
+    if unsafe { AssignProcessToJobObject(job, handle as _) } == FALSE {
+      return Err(std::io::Error::last_os_error().into());
+    }
+    // The child was spawned suspended, so that it couldn't start any process
+    // outside of the job before it was assigned to it.
+    resume(handle)?;
+    Ok(group)
+  }
+
+  fn kill(&self, signal: &str) -> Result<(), AnyError> {
+    use winapi::shared::minwindef::FALSE;
+    use winapi::um::jobapi2::TerminateJobObject;
+
+    if !matches!(signal, "SIGKILL" | "SIGTERM") {
+      return Err(type_error(format!("Invalid signal: {signal}")));
+    }
+    // SAFETY: winapi call
+    if unsafe { TerminateJobObject(self.job, 1) } == FALSE {
+      return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+  }
+}
+
+/// Resumes a child which was spawned with `CREATE_SUSPENDED`.
+#[cfg(windows)]
+fn resume(handle: std::os::windows::io::RawHandle) -> Result<(), AnyError> {
+  use ntapi::ntpsapi::NtResumeProcess;
+  use ntapi::ntrtl::RtlNtStatusToDosError;
+  use winapi::shared::ntdef::NT_SUCCESS;
+
+  // SAFETY: ntapi call, the handle is valid
+  let status = unsafe { NtResumeProcess(handle as _) };
+  if !NT_SUCCESS(status) {
+    // SAFETY: ntapi call
+    let code = unsafe { RtlNtStatusToDosError(status) };
+    return Err(std::io::Error::from_raw_os_error(code as i32).into());
+  }
+  Ok(())
+}
+
+#[cfg(windows)]
+impl Drop for ProcessGroup {
+  fn drop(&mut self) {
+    // SAFETY: winapi call
+    unsafe {
+      winapi::um::handleapi::CloseHandle(self.job);
+    }
+  }
 }
 
 #[derive(Deserialize)]
@@ -140,21 +575,66 @@ impl Resource for ChildResource {
 pub struct SpawnArgs {
   cmd: String,
   args: Vec<String>,
+  argv0: Option<String>,
   cwd: Option<String>,
   clear_env: bool,
+  env_inherit: Option<Vec<env::EnvInheritPattern>>,
   env: Vec<(String, String)>,
   #[cfg(unix)]
   gid: Option<u32>,
   #[cfg(unix)]
   uid: Option<u32>,
+  #[cfg(unix)]
+  umask: Option<u32>,
   #[cfg(windows)]
   windows_raw_arguments: bool,
+  #[cfg(windows)]
+  creation_flags: Option<u32>,
+  process_group: bool,
+  priority: Option<i32>,
+  cpu_affinity: Option<Vec<u32>>,
+  chroot: Option<String>,
+  unshare: Option<UnshareOptions>,
+  pty: Option<pty::PtySize>,
+  max_buffer: Option<usize>,
+  timeout_ms: Option<u64>,
   ipc: Option<i32>,
+  ipc_channel: Option<IpcSerialization>,
+  shell: Option<shell::ShellOption>,
+  permission_token: Option<String>,
+  shared_memory: Vec<ResourceId>,
 
   #[serde(flatten)]
   stdio: ChildStdio,
 
-  extra_stdio: Vec<Stdio>,
+  extra_stdio: Vec<StdioOrRid>,
+}
+
+/// The Linux namespaces to move the child into before it is executed.
+#[derive(Copy, Clone, Default, Deserialize)]
+#[serde(default)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub struct UnshareOptions {
+  net: bool,
+  mount: bool,
+  user: bool,
+}
+
+#[cfg(target_os = "linux")]
+impl UnshareOptions {
+  fn clone_flags(&self) -> libc::c_int {
+    let mut flags = 0;
+    if self.net {
+      flags |= libc::CLONE_NEWNET;
+    }
+    if self.mount {
+      flags |= libc::CLONE_NEWNS;
+    }
+    if self.user {
+      flags |= libc::CLONE_NEWUSER;
+    }
+    flags
+  }
 }
 
 #[derive(Deserialize)]
@@ -165,12 +645,14 @@ pub struct ChildStdio {
   stderr: StdioOrRid,
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ChildStatus {
   success: bool,
   code: i32,
   signal: Option<String>,
+  timed_out: bool,
+  usage: Option<usage::ResourceUsage>,
 }
 
 impl TryFrom<ExitStatus> for ChildStatus {
@@ -193,6 +675,8 @@ impl TryFrom<ExitStatus> for ChildStatus {
         ),
         #[cfg(not(unix))]
         signal: None,
+        timed_out: false,
+        usage: None,
       }
     } else {
       let code = code.expect("Should have either an exit code or a signal.");
@@ -201,6 +685,8 @@ impl TryFrom<ExitStatus> for ChildStatus {
         success: code == 0,
         code,
         signal: None,
+        timed_out: false,
+        usage: None,
       }
     };
 
@@ -211,101 +697,288 @@ impl TryFrom<ExitStatus> for ChildStatus {
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SpawnOutput {
+  /// `None` for commands run by the built-in shell.
+  pid: Option<u32>,
   status: ChildStatus,
   stdout: Option<ToJsBuffer>,
   stderr: Option<ToJsBuffer>,
 }
 
-type CreateCommand = (
-  std::process::Command,
-  Option<ResourceId>,
-  Vec<Option<ResourceId>>,
-  Vec<deno_io::RawBiPipeHandle>,
-);
+/// The parent's ends of the pipes set up by `create_command`, other than the
+/// ones used for stdio.
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChildPipes {
+  ipc_pipe_rid: Option<ResourceId>,
+  extra_pipe_rids: Vec<Option<ResourceId>>,
+  pty_rid: Option<ResourceId>,
+  ipc_channel_rid: Option<ResourceId>,
+}
 
-fn create_command(
-  state: &mut OpState,
-  mut args: SpawnArgs,
-  api_name: &str,
-) -> Result<CreateCommand, AnyError> {
-  fn get_requires_allow_all_env_var(args: &SpawnArgs) -> Option<Cow<str>> {
-    fn requires_allow_all(key: &str) -> bool {
-      let key = key.trim();
-      // we could be more targted here, but there are quite a lot of
-      // LD_* and DYLD_* env variables
-      key.starts_with("LD_") || key.starts_with("DYLD_")
-    }
-
-    /// Checks if the user set this env var to an empty
-    /// string in order to clear it.
-    fn args_has_empty_env_value(args: &SpawnArgs, key_name: &str) -> bool {
-      args
-        .env
-        .iter()
-        .find(|(k, _)| k == key_name)
-        .map(|(_, v)| v.trim().is_empty())
-        .unwrap_or(false)
-    }
-
-    if let Some((key, _)) = args
-      .env
+impl ChildPipes {
+  /// Closes the parent ends of the pipes, for a child that failed to start.
+  fn close(self, state: &mut OpState) {
+    let rids = self
+      .extra_pipe_rids
+      .into_iter()
+      .flatten()
+      .chain(self.ipc_pipe_rid)
+      .chain(self.pty_rid)
+      .chain(self.ipc_channel_rid);
+    for rid in rids {
+      if let Ok(resource) = state.resource_table.take_any(rid) {
+        resource.close();
+      }
+    }
+  }
+}
+
+struct CreateCommand {
+  command: std::process::Command,
+  pipes: ChildPipes,
+  /// The child's ends of the pipes, to be closed once it was spawned.
+  handles_to_close: Vec<deno_io::RawBiPipeHandle>,
+  /// Set for children which have to be spawned by `create_process::spawn`.
+  #[cfg(windows)]
+  win32: Option<create_process::Options>,
+}
+
+/// The handle an `"inherit"` entry in the extra stdio passes through as `fd`,
+/// if the embedder allowed it to be inherited.
+fn inherited_fd(state: &OpState, fd: u32) -> Option<RawInheritedHandle> {
+  state
+    .try_borrow::<InheritedFdsTable>()
+    .and_then(|table| table.get(fd))
+}
+
+/// Returns an env var that would be set for the child and requires all run
+/// permissions. We don't allow users to launch subprocesses with any LD_ or
+/// DYLD_* env vars set because this allows executing code (ex. LD_PRELOAD).
+pub(crate) fn get_requires_allow_all_env_var<'a>(
+  env: &'a [(String, String)],
+  clear_env: bool,
+  inherited_env: Option<&'a [(String, String)]>,
+) -> Option<Cow<'a, str>> {
+  fn requires_allow_all(key: &str) -> bool {
+    let key = key.trim();
+    // we could be more targted here, but there are quite a lot of
+    // LD_* and DYLD_* env variables
+    key.starts_with("LD_") || key.starts_with("DYLD_")
+  }
+
+  /// Checks if the user set this env var to an empty
+  /// string in order to clear it.
+  fn env_has_empty_value(env: &[(String, String)], key_name: &str) -> bool {
+    env
       .iter()
-      .find(|(k, v)| requires_allow_all(k) && !v.trim().is_empty())
+      .find(|(k, _)| k == key_name)
+      .map(|(_, v)| v.trim().is_empty())
+      .unwrap_or(false)
+  }
+
+  if let Some((key, _)) = env
+    .iter()
+    .find(|(k, v)| requires_allow_all(k) && !v.trim().is_empty())
+  {
+    return Some(key.into());
+  }
+
+  let is_inherited_allow_all_var = |k: &str, v: &str| {
+    requires_allow_all(k)
+      && !v.trim().is_empty()
+      && !env_has_empty_value(env, k)
+  };
+  if let Some(inherited_env) = inherited_env {
+    if let Some((key, _)) = inherited_env
+      .iter()
+      .find(|(k, v)| is_inherited_allow_all_var(k, v))
+    {
+      return Some(key.as_str().into());
+    }
+  } else if !clear_env {
+    if let Some((key, _)) =
+      std::env::vars().find(|(k, v)| is_inherited_allow_all_var(k, v))
     {
       return Some(key.into());
     }
+  }
 
-    if !args.clear_env {
-      if let Some((key, _)) = std::env::vars().find(|(k, v)| {
-        requires_allow_all(k)
-          && !v.trim().is_empty()
-          && !args_has_empty_env_value(args, k)
-      }) {
-        return Some(key.into());
-      }
-    }
+  None
+}
 
-    None
+/// Validates `args` and checks the permissions required to spawn the child.
+/// Returns the variables to inherit, if `envInherit` was given.
+fn check_command(
+  state: &mut OpState,
+  args: &SpawnArgs,
+  api_name: &str,
+) -> Result<Option<Vec<(String, String)>>, AnyError> {
+  if args.clear_env && args.env_inherit.is_some() {
+    return Err(type_error(
+      "The clearEnv and envInherit options can't be combined",
+    ));
+  }
+  let inherited_env = args
+    .env_inherit
+    .as_deref()
+    .map(env::inherited_vars)
+    .transpose()?;
+
+  if args.chroot.is_some() {
+    super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.Command.chroot");
+  }
+  if args.ipc_channel.is_some() && args.ipc.is_some_and(|ipc| ipc >= 0) {
+    return Err(type_error(
+      "The ipc option can't be combined with Node.js IPC",
+    ));
+  }
+
+  if args.pty.is_some() {
+    super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.Command.pty");
+  }
+  if args.ipc_channel.is_some() {
+    super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.Command.ipc");
+  }
+  if !matches!(args.shell, None | Some(shell::ShellOption::Builtin(false))) {
+    super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.Command.shell");
+  }
+  if args.permission_token.is_some() {
+    super::check_unstable(
+      state,
+      super::permissions::UNSTABLE_FEATURE_NAME,
+      "Deno.Command.permissionToken",
+    );
+  }
+
+  if args.unshare.is_some() {
+    super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.Command.unshare");
+    #[cfg(not(target_os = "linux"))]
+    return Err(type_error("The unshare option is only supported on Linux"));
   }
 
   {
     let permissions = state.borrow_mut::<PermissionsContainer>();
-    permissions.check_run(&args.cmd, api_name)?;
-    if permissions.check_run_all(api_name).is_err() {
-      // error the same on all platforms
-      if let Some(name) = get_requires_allow_all_env_var(&args) {
-        // we don't allow users to launch subprocesses with any LD_ or DYLD_*
-        // env vars set because this allows executing code (ex. LD_PRELOAD)
+    // a shell can run any program
+    if matches!(args.shell, None | Some(shell::ShellOption::Builtin(false))) {
+      permissions.check_run_with_token(
+        args.permission_token.as_deref(),
+        &args.cmd,
+        &args.args,
+        api_name,
+      )?;
+    } else if args.permission_token.is_some() {
+      return Err(type_error(
+        "The shell and permissionToken options can't be combined",
+      ));
+    } else {
+      permissions.check_run_all(api_name)?;
+    }
+    // inheriting a variable selectively exposes it to the child like
+    // `Deno.env.get()` would, so it's checked the same way, including
+    // patterns like `--allow-env=AWS_*`
+    for (key, _) in inherited_env.iter().flatten() {
+      permissions.check_env(key)?;
+    }
+    // error the same on all platforms
+    if let Some(name) = get_requires_allow_all_env_var(
+      &args.env,
+      args.clear_env,
+      inherited_env.as_deref(),
+    ) {
+      if permissions.check_run_all(api_name).is_err() {
         return Err(deno_core::error::custom_error(
           "PermissionDenied",
           format!("Requires --allow-all permissions to spawn subprocess with {} environment variable.", name)
         ));
       }
     }
+    if args.chroot.is_some()
+      && permissions.check_was_allow_all_flag_passed().is_err()
+    {
+      return Err(custom_error(
+        "PermissionDenied",
+        "Requires --allow-all permissions to spawn subprocess with chroot.",
+      ));
+    }
   }
+  Ok(inherited_env)
+}
 
-  let mut command = std::process::Command::new(args.cmd);
+fn create_command(
+  state: &mut OpState,
+  mut args: SpawnArgs,
+  api_name: &str,
+) -> Result<CreateCommand, AnyError> {
+  let inherited_env = check_command(state, &args, api_name)?;
 
+  #[cfg(unix)]
+  let chroot = args
+    .chroot
+    .take()
+    .map(std::ffi::CString::new)
+    .transpose()
+    .map_err(|_| type_error("chroot path must not contain null bytes"))?;
   #[cfg(windows)]
-  if args.windows_raw_arguments {
-    for arg in args.args.iter() {
-      command.raw_arg(arg);
-    }
+  if args.chroot.is_some() {
+    return Err(type_error("The chroot option is not supported on Windows"));
+  }
+
+  let shell_command = args
+    .shell
+    .as_ref()
+    .and_then(|shell| shell::command(shell, &args.cmd, &args.args));
+  // Arguments are only added with `raw_arg` for `cmd.exe` as a shell, which
+  // gets a single one.
+  #[cfg(windows)]
+  let raw_args = if shell_command.is_some() {
+    args.shell.as_ref().is_some_and(shell::ShellOption::is_cmd)
+  } else {
+    args.windows_raw_arguments
+  };
+  let mut command = if let Some(command) = shell_command {
+    command
   } else {
+    let mut command = std::process::Command::new(args.cmd);
+
+    #[cfg(windows)]
+    if args.windows_raw_arguments {
+      for arg in args.args.iter() {
+        command.raw_arg(arg);
+      }
+    } else {
+      command.args(args.args);
+    }
+
+    #[cfg(not(windows))]
     command.args(args.args);
-  }
 
-  #[cfg(not(windows))]
-  command.args(args.args);
+    command
+  };
+
+  #[cfg(unix)]
+  if let Some(argv0) = args.argv0 {
+    command.arg0(argv0);
+  }
+  // `std::process::Command` builds the command line from the program on
+  // Windows, so `argv0` can't be overridden there.
+  #[cfg(windows)]
+  let _ = args.argv0;
 
   if let Some(cwd) = args.cwd {
     command.current_dir(cwd);
   }
 
-  if args.clear_env {
+  #[cfg(windows)]
+  let env_cleared = args.clear_env || inherited_env.is_some();
+  if let Some(inherited_env) = inherited_env {
+    command.env_clear();
+    command.envs(inherited_env);
+  } else if args.clear_env {
     command.env_clear();
   }
   command.envs(args.env);
+  // Segments are only inherited by the children they were passed to.
+  command.env_remove(shared_memory::ENV_VAR);
 
   #[cfg(unix)]
   if let Some(gid) = args.gid {
@@ -316,20 +989,79 @@ fn create_command(
     command.uid(uid);
   }
 
-  if args.stdio.stdin.is_ipc() {
-    args.ipc = Some(0);
-  } else {
-    command.stdin(args.stdio.stdin.as_stdio(state)?);
+  if let Some(priority) = args.priority {
+    if !(-20..=19).contains(&priority) {
+      return Err(type_error(format!("Invalid priority: {priority}")));
+    }
   }
 
-  command.stdout(match args.stdio.stdout {
-    StdioOrRid::Stdio(Stdio::Inherit) => StdioOrRid::Rid(1).as_stdio(state)?,
-    value => value.as_stdio(state)?,
-  });
-  command.stderr(match args.stdio.stderr {
-    StdioOrRid::Stdio(Stdio::Inherit) => StdioOrRid::Rid(2).as_stdio(state)?,
-    value => value.as_stdio(state)?,
-  });
+  #[cfg(windows)]
+  let mut creation_flags = args.creation_flags.unwrap_or(0);
+  #[cfg(windows)]
+  if let Some(priority) = args.priority {
+    creation_flags |= priority_class(priority);
+  }
+
+  // A child attached to a pty becomes a session leader, which already puts it
+  // into a new process group of its own.
+  if args.process_group && args.pty.is_none() {
+    // On Windows the child is additionally assigned to a job object once it
+    // has been spawned, see `ProcessGroup::new`, and it is suspended until
+    // then.
+    #[cfg(unix)]
+    command.process_group(0);
+    #[cfg(windows)]
+    {
+      use winapi::um::winbase::CREATE_NEW_PROCESS_GROUP;
+      use winapi::um::winbase::CREATE_SUSPENDED;
+      creation_flags |= CREATE_NEW_PROCESS_GROUP | CREATE_SUSPENDED;
+    }
+  }
+
+  // The affinity is set while the child is suspended on Windows, so that it
+  // already applies to the first instruction the child runs.
+  #[cfg(windows)]
+  if args.cpu_affinity.is_some() {
+    creation_flags |= winapi::um::winbase::CREATE_SUSPENDED;
+  }
+
+  // `creation_flags` replaces any previously set flags, so it's only called
+  // once with all of them combined.
+  #[cfg(windows)]
+  if creation_flags != 0 {
+    command.creation_flags(creation_flags);
+  }
+
+  #[cfg(windows)]
+  let mut pseudo_console = None;
+  let pty_rid = if let Some(size) = args.pty {
+    let pty = pty::setup(&mut command, size)?;
+    #[cfg(windows)]
+    {
+      pseudo_console = pty.console();
+    }
+    Some(state.resource_table.add(pty))
+  } else {
+    if args.stdio.stdin.is_ipc() {
+      args.ipc = Some(0);
+    } else {
+      command.stdin(args.stdio.stdin.as_stdio(state)?);
+    }
+
+    command.stdout(match args.stdio.stdout {
+      StdioOrRid::Stdio(Stdio::Inherit) => {
+        StdioOrRid::Rid(1).as_stdio(state)?
+      }
+      value => value.as_stdio(state)?,
+    });
+    command.stderr(match args.stdio.stderr {
+      StdioOrRid::Stdio(Stdio::Inherit) => {
+        StdioOrRid::Rid(2).as_stdio(state)?
+      }
+      value => value.as_stdio(state)?,
+    });
+    None
+  };
 
   #[cfg(unix)]
   // TODO(bartlomieju):
@@ -358,33 +1090,114 @@ fn create_command(
       }
     }
 
+    // The fd right after the extra stdio ones is used for the IPC channel,
+    // and the fds of shared memory segments follow it.
+    let ipc_channel_fd = (args.extra_stdio.len() + 3) as i32;
+    let shared_memory_fd = ipc_channel_fd + 1;
+    // Resource fds are duplicated above all of the target fds, so that the
+    // `dup2` calls in the child can't clobber a source fd that is yet to be
+    // mapped.
+    let min_resource_fd = shared_memory_fd + args.shared_memory.len() as i32;
     for (i, stdio) in args.extra_stdio.into_iter().enumerate() {
       // index 0 in `extra_stdio` actually refers to fd 3
       // because we handle stdin,stdout,stderr specially
       let fd = (i + 3) as i32;
-      // TODO(nathanwhit): handle inherited, but this relies on the parent process having
-      // fds open already. since we don't generally support dealing with raw fds,
-      // we can't properly support this
-      if matches!(stdio, Stdio::Piped) {
-        let (fd1, fd2) = deno_io::bi_pipe_pair_raw()?;
-        fds_to_dup.push((fd2, fd));
-        fds_to_close.push(fd2);
-        let rid = state.resource_table.add(
-          match deno_io::BiPipeResource::from_raw_handle(fd1) {
-            Ok(v) => v,
-            Err(e) => {
-              log::warn!("Failed to open bidirectional pipe for fd {fd}: {e}");
-              extra_pipe_rids.push(None);
-              continue;
+      match stdio {
+        StdioOrRid::Stdio(Stdio::Piped) => {
+          let (fd1, fd2) = deno_io::bi_pipe_pair_raw()?;
+          fds_to_dup.push((fd2, fd));
+          fds_to_close.push(fd2);
+          let rid = state.resource_table.add(
+            match deno_io::BiPipeResource::from_raw_handle(fd1) {
+              Ok(v) => v,
+              Err(e) => {
+                log::warn!(
+                  "Failed to open bidirectional pipe for fd {fd}: {e}"
+                );
+                extra_pipe_rids.push(None);
+                continue;
+              }
+            },
+          );
+          extra_pipe_rids.push(Some(rid));
+        }
+        StdioOrRid::Rid(_) | StdioOrRid::Stdio(Stdio::Inherit) => {
+          let raw_fd = match stdio {
+            StdioOrRid::Rid(rid) => {
+              match state.resource_table.get_handle(rid)? {
+                deno_core::ResourceHandle::Fd(fd) => fd,
+                deno_core::ResourceHandle::Socket(fd) => fd,
+              }
             }
-          },
-        );
-        extra_pipe_rids.push(Some(rid));
-      } else {
-        extra_pipe_rids.push(None);
+            _ => match inherited_fd(state, fd as u32) {
+              Some(fd) => fd,
+              None => {
+                extra_pipe_rids.push(None);
+                continue;
+              }
+            },
+          };
+          let resource_fd =
+            libc::fcntl(raw_fd, libc::F_DUPFD_CLOEXEC, min_resource_fd);
+          if resource_fd == -1 {
+            return Err(std::io::Error::last_os_error().into());
+          }
+          fds_to_dup.push((resource_fd, fd));
+          fds_to_close.push(resource_fd);
+          extra_pipe_rids.push(None);
+        }
+        _ => extra_pipe_rids.push(None),
       }
     }
 
+    let mut ipc_channel_rid = None;
+    if let Some(serialization) = args.ipc_channel {
+      let (fd1, fd2) = deno_io::bi_pipe_pair_raw()?;
+      fds_to_dup.push((fd2, ipc_channel_fd));
+      fds_to_close.push(fd2);
+      command.env(ipc::FD_ENV_VAR, ipc_channel_fd.to_string());
+      command.env(ipc::SERIALIZATION_ENV_VAR, serialization.as_str());
+      ipc_channel_rid =
+        Some(state.resource_table.add(ipc::IpcChannelResource::new(fd1)?));
+    }
+
+    if !args.shared_memory.is_empty() {
+      let (fds, segments) = shared_memory::inherit(
+        state,
+        &args.shared_memory,
+        shared_memory_fd,
+        min_resource_fd,
+      )?;
+      for (fd, target) in fds {
+        fds_to_dup.push((fd, target));
+        fds_to_close.push(fd);
+      }
+      command.env(shared_memory::ENV_VAR, segments);
+    }
+
+    let priority = args.priority;
+    let umask = args
+      .umask
+      .or_else(deno_fs::umask::scoped)
+      .map(|umask| {
+        if umask > 0o777 {
+          return Err(type_error(format!("Invalid umask: {umask:#o}")));
+        }
+        Ok(umask as libc::mode_t)
+      })
+      .transpose()?;
+    #[cfg(target_os = "linux")]
+    let cpu_set = args
+      .cpu_affinity
+      .as_deref()
+      .map(affinity::cpu_set)
+      .transpose()?;
+    #[cfg(not(target_os = "linux"))]
+    if args.cpu_affinity.is_some() {
+      return Err(affinity::unsupported());
+    }
+    #[cfg(target_os = "linux")]
+    let unshare_flags = args.unshare.map(|unshare| unshare.clone_flags());
     command.pre_exec(move || {
       for &(src, dst) in &fds_to_dup {
         if src >= 0 && dst >= 0 {
@@ -393,10 +1206,50 @@ fn create_command(
         }
       }
       libc::setgroups(0, std::ptr::null());
+      if let Some(umask) = umask {
+        libc::umask(umask);
+      }
+      if let Some(priority) = priority {
+        if libc::setpriority(libc::PRIO_PROCESS, 0, priority) == -1 {
+          return Err(std::io::Error::last_os_error());
+        }
+      }
+      #[cfg(target_os = "linux")]
+      if let Some(set) = &cpu_set {
+        let size = std::mem::size_of::<libc::cpu_set_t>();
+        if libc::sched_setaffinity(0, size, set) == -1 {
+          return Err(std::io::Error::last_os_error());
+        }
+      }
+      // Done before `chroot`, as a chrooted process can't create a new user
+      // namespace.
+      #[cfg(target_os = "linux")]
+      if let Some(flags) = unshare_flags {
+        if flags != 0 && libc::unshare(flags) == -1 {
+          return Err(std::io::Error::last_os_error());
+        }
+      }
+      if let Some(root) = &chroot {
+        if libc::chroot(root.as_ptr()) == -1
+          || libc::chdir(b"/\0".as_ptr() as _) == -1
+        {
+          return Err(std::io::Error::last_os_error());
+        }
+      }
       Ok(())
     });
 
-    Ok((command, ipc_rid, extra_pipe_rids, fds_to_close))
+    let pipes = ChildPipes {
+      ipc_pipe_rid: ipc_rid,
+      extra_pipe_rids,
+      pty_rid,
+      ipc_channel_rid,
+    };
+    Ok(CreateCommand {
+      command,
+      pipes,
+      handles_to_close: fds_to_close,
+    })
   }
 
   #[cfg(windows)]
@@ -424,13 +1277,204 @@ fn create_command(
       }
     }
 
-    if args.extra_stdio.iter().any(|s| matches!(s, Stdio::Piped)) {
-      log::warn!(
-        "Additional stdio pipes beyond stdin/stdout/stderr are not currently supported on windows"
+    // Windows has no notion of fds beyond stdio, so the child inherits the
+    // handles of the extra stdio entries under their values in this process,
+    // and is told which handle belongs to which fd through
+    // `deno_io::EXTRA_STDIO_HANDLES_ENV_VAR`. A Deno child gives the handles
+    // their fds as rids, see `deno_io::EXTRA_STDIO_HANDLES`.
+    let mut extra_pipe_rids = Vec::new();
+    let mut extra_pipe_handles = Vec::new();
+    for (i, stdio) in args.extra_stdio.into_iter().enumerate() {
+      // index 0 in `extra_stdio` actually refers to fd 3
+      // because we handle stdin,stdout,stderr specially
+      let fd = i + 3;
+      match stdio {
+        StdioOrRid::Stdio(Stdio::Piped) => {
+          // The child's end is synchronous, so that it can be used like a
+          // file.
+          let (hd1, hd2) = deno_io::create_named_pipe_with_sync_client()?;
+          handles_to_close.push(hd2);
+          let rid = state.resource_table.add(
+            match deno_io::BiPipeResource::from_raw_handle(hd1) {
+              Ok(v) => v,
+              Err(e) => {
+                log::warn!(
+                  "Failed to open bidirectional pipe for fd {fd}: {e}"
+                );
+                extra_pipe_rids.push(None);
+                continue;
+              }
+            },
+          );
+          extra_pipe_handles.push(format!("{fd}:{}", hd2 as usize));
+          extra_pipe_rids.push(Some(rid));
+        }
+        StdioOrRid::Rid(_) | StdioOrRid::Stdio(Stdio::Inherit) => {
+          use winapi::shared::minwindef::FALSE;
+          use winapi::um::handleapi::DuplicateHandle;
+          use winapi::um::processthreadsapi::GetCurrentProcess;
+          use winapi::um::winnt::DUPLICATE_SAME_ACCESS;
+
+          let handle = match stdio {
+            StdioOrRid::Rid(rid) => {
+              match state.resource_table.get_handle(rid)? {
+                deno_core::ResourceHandle::Fd(handle) => handle,
+                deno_core::ResourceHandle::Socket(socket) => socket as _,
+              }
+            }
+            _ => match inherited_fd(state, fd as u32) {
+              Some(handle) => handle,
+              None => {
+                extra_pipe_rids.push(None);
+                continue;
+              }
+            },
+          };
+          // The child gets a duplicate, which is closed again once it was
+          // spawned.
+          let mut duplicate = std::ptr::null_mut();
+          // SAFETY: winapi calls, `handle` is kept open by the resource or the
+          // embedder
+          let ok = unsafe {
+            let current = GetCurrentProcess();
+            DuplicateHandle(
+              current,
+              handle as _,
+              current,
+              &mut duplicate,
+              0,
+              FALSE,
+              DUPLICATE_SAME_ACCESS,
+            )
+          };
+          if ok == FALSE {
+            return Err(std::io::Error::last_os_error().into());
+          }
+          handles_to_close.push(duplicate as _);
+          extra_pipe_handles.push(format!("{fd}:{}", duplicate as usize));
+          extra_pipe_rids.push(None);
+        }
+        _ => extra_pipe_rids.push(None),
+      }
+    }
+    if !extra_pipe_handles.is_empty() {
+      command.env(
+        deno_io::EXTRA_STDIO_HANDLES_ENV_VAR,
+        extra_pipe_handles.join(","),
       );
     }
 
-    Ok((command, ipc_rid, vec![], handles_to_close))
+    let mut ipc_channel_rid = None;
+    if let Some(serialization) = args.ipc_channel {
+      let (hd1, hd2) = deno_io::bi_pipe_pair_raw()?;
+      handles_to_close.push(hd2);
+      command.env(ipc::FD_ENV_VAR, (hd2 as i64).to_string());
+      command.env(ipc::SERIALIZATION_ENV_VAR, serialization.as_str());
+      ipc_channel_rid =
+        Some(state.resource_table.add(ipc::IpcChannelResource::new(hd1)?));
+    }
+
+    if !args.shared_memory.is_empty() {
+      let (handles, segments) =
+        shared_memory::inherit(state, &args.shared_memory)?;
+      handles_to_close.extend(handles);
+      command.env(shared_memory::ENV_VAR, segments);
+    }
+
+    let pipes = ChildPipes {
+      ipc_pipe_rid: ipc_rid,
+      extra_pipe_rids,
+      pty_rid,
+      ipc_channel_rid,
+    };
+    // `std::process::Command` can't attach a child to a pseudo console, and
+    // lets it inherit all inheritable handles instead of the listed ones.
+    let win32 = if pseudo_console.is_some() || !handles_to_close.is_empty() {
+      let stdio = match pseudo_console {
+        Some(_) => None,
+        None => Some([
+          win32_stdio(state, args.stdio.stdin)?,
+          win32_stdio(
+            state,
+            match args.stdio.stdout {
+              StdioOrRid::Stdio(Stdio::Inherit) => StdioOrRid::Rid(1),
+              value => value,
+            },
+          )?,
+          win32_stdio(
+            state,
+            match args.stdio.stderr {
+              StdioOrRid::Stdio(Stdio::Inherit) => StdioOrRid::Rid(2),
+              value => value,
+            },
+          )?,
+        ]),
+      };
+      Some(create_process::Options {
+        env_cleared,
+        raw_args,
+        creation_flags,
+        stdio,
+        pseudo_console,
+        inherited_handles: handles_to_close.clone(),
+      })
+    } else {
+      None
+    };
+
+    Ok(CreateCommand {
+      command,
+      pipes,
+      handles_to_close,
+      win32,
+    })
+  }
+}
+
+/// Like [`StdioOrRid::as_stdio`], for children spawned by
+/// `create_process::spawn`.
+#[cfg(windows)]
+fn win32_stdio(
+  state: &OpState,
+  stdio: StdioOrRid,
+) -> Result<create_process::Stdio, AnyError> {
+  Ok(match stdio {
+    StdioOrRid::Stdio(Stdio::Piped) => create_process::Stdio::Piped,
+    StdioOrRid::Stdio(Stdio::Null) => create_process::Stdio::Null,
+    // stdin is left alone for the IPC pipe, like with
+    // `std::process::Command`
+    StdioOrRid::Stdio(Stdio::Inherit | Stdio::IpcForInternalUse) => {
+      create_process::Stdio::Inherit
+    }
+    StdioOrRid::Rid(rid) => {
+      let handle =
+        FileResource::with_file(state, rid, |file| Ok(file.backing_fd()))?;
+      create_process::Stdio::Handle(
+        handle.ok_or_else(deno_core::error::bad_resource_id)?,
+      )
+    }
+  })
+}
+
+/// Maps a unix niceness in the range of -20 to 19 to the closest Windows
+/// priority class.
+// Ported from: https://github.com/libuv/libuv/blob/a877ca2435134ef86315326ef4ef0c16bdbabf17/src/win/util.c#L1688-L1719
+#[cfg(windows)]
+fn priority_class(priority: i32) -> u32 {
+  use winapi::um::winbase::ABOVE_NORMAL_PRIORITY_CLASS;
+  use winapi::um::winbase::BELOW_NORMAL_PRIORITY_CLASS;
+  use winapi::um::winbase::HIGH_PRIORITY_CLASS;
+  use winapi::um::winbase::IDLE_PRIORITY_CLASS;
+  use winapi::um::winbase::NORMAL_PRIORITY_CLASS;
+  use winapi::um::winbase::REALTIME_PRIORITY_CLASS;
+
+  match priority {
+    ..=-15 => REALTIME_PRIORITY_CLASS,
+    -14..=-8 => HIGH_PRIORITY_CLASS,
+    -7..=-1 => ABOVE_NORMAL_PRIORITY_CLASS,
+    0..=9 => NORMAL_PRIORITY_CLASS,
+    10..=18 => BELOW_NORMAL_PRIORITY_CLASS,
+    19.. => IDLE_PRIORITY_CLASS,
   }
 }
 
@@ -438,103 +1482,193 @@ fn create_command(
 #[serde(rename_all = "camelCase")]
 struct Child {
   rid: ResourceId,
-  pid: u32,
+  /// `None` for commands run by the built-in shell.
+  pid: Option<u32>,
   stdin_rid: Option<ResourceId>,
   stdout_rid: Option<ResourceId>,
   stderr_rid: Option<ResourceId>,
-  ipc_pipe_rid: Option<ResourceId>,
-  extra_pipe_rids: Vec<Option<ResourceId>>,
+  /// Milliseconds since the Unix epoch.
+  start_time: f64,
+  pidfd_rid: Option<ResourceId>,
+  #[serde(flatten)]
+  pipes: ChildPipes,
 }
 
 fn spawn_child(
   state: &mut OpState,
-  command: std::process::Command,
-  ipc_pipe_rid: Option<ResourceId>,
-  extra_pipe_rids: Vec<Option<ResourceId>>,
+  spawn: CreateCommand,
+  process_group: bool,
+  timeout: Option<Duration>,
+  cpu_affinity: Option<Vec<u32>>,
+  stdin_bytes: Option<JsBuffer>,
 ) -> Result<Child, AnyError> {
-  let mut command = tokio::process::Command::from(command);
+  let mut command = tokio::process::Command::from(spawn.command);
   // TODO(@crowlkats): allow detaching processes.
   //  currently deno will orphan a process when exiting with an error or Deno.exit()
   // We want to kill child when it's closed
   command.kill_on_drop(true);
 
-  let mut child = match command.spawn() {
-    Ok(child) => child,
+  #[cfg(windows)]
+  let started = start_child(
+    &mut command,
+    spawn.win32.as_ref(),
+    process_group,
+    cpu_affinity,
+  );
+  #[cfg(not(windows))]
+  let started = start_child(&mut command, process_group, cpu_affinity);
+  for handle in spawn.handles_to_close {
+    close_raw_handle(handle);
+  }
+  let pipes = spawn.pipes;
+  let (mut child, group) = match started {
+    Ok(started) => started,
     Err(err) => {
-      let command = command.as_std();
-      let command_name = command.get_program().to_string_lossy();
-
-      if let Some(cwd) = command.get_current_dir() {
-        // launching a sub process always depends on the real
-        // file system so using these methods directly is ok
-        #[allow(clippy::disallowed_methods)]
-        if !cwd.exists() {
-          return Err(
-            std::io::Error::new(
-              std::io::ErrorKind::NotFound,
-              format!(
-                "Failed to spawn '{}': No such cwd '{}'",
-                command_name,
-                cwd.to_string_lossy()
-              ),
-            )
-            .into(),
-          );
-        }
-
-        #[allow(clippy::disallowed_methods)]
-        if !cwd.is_dir() {
-          return Err(
-            std::io::Error::new(
-              std::io::ErrorKind::NotFound,
-              format!(
-                "Failed to spawn '{}': cwd is not a directory '{}'",
-                command_name,
-                cwd.to_string_lossy()
-              ),
-            )
-            .into(),
-          );
-        }
-      }
-
-      return Err(AnyError::from(err).context(format!(
-        "Failed to spawn '{}'",
-        command.get_program().to_string_lossy()
-      )));
+      pipes.close(state);
+      return Err(err);
     }
   };
+  let start_time = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|elapsed| elapsed.as_secs_f64() * 1000.0)
+    .unwrap_or_default();
 
   let pid = child.id().expect("Process ID should be set.");
+  let program = command
+    .as_std()
+    .get_program()
+    .to_string_lossy()
+    .into_owned();
+
+  let (stdin, stdout, stderr) = match &mut child {
+    SpawnedChild::Tokio(child) => {
+      (child.stdin.take(), child.stdout.take(), child.stderr.take())
+    }
+    #[cfg(windows)]
+    SpawnedChild::Win32(child) => {
+      use tokio::process::ChildStderr;
+      use tokio::process::ChildStdin;
+      use tokio::process::ChildStdout;
+      let stdin = child
+        .stdin
+        .take()
+        .map(|pipe| ChildStdin::from_std(pipe.into()));
+      let stdout = child
+        .stdout
+        .take()
+        .map(|pipe| ChildStdout::from_std(pipe.into()));
+      let stderr = child
+        .stderr
+        .take()
+        .map(|pipe| ChildStderr::from_std(pipe.into()));
+      // the child is killed on drop
+      (stdin.transpose()?, stdout.transpose()?, stderr.transpose()?)
+    }
+  };
 
-  let stdin_rid = child
-    .stdin
-    .take()
-    .map(|stdin| state.resource_table.add(ChildStdinResource::from(stdin)));
+  let stdin_rid = match (stdin, stdin_bytes) {
+    (Some(stdin), Some(bytes)) => {
+      write_stdin_bytes(stdin, bytes);
+      None
+    }
+    (stdin, _) => stdin
+      .map(|stdin| state.resource_table.add(ChildStdinResource::from(stdin))),
+  };
 
-  let stdout_rid = child
-    .stdout
-    .take()
+  let stdout_rid = stdout
     .map(|stdout| state.resource_table.add(ChildStdoutResource::from(stdout)));
 
-  let stderr_rid = child
-    .stderr
-    .take()
+  let stderr_rid = stderr
     .map(|stderr| state.resource_table.add(ChildStderrResource::from(stderr)));
 
-  let child_rid = state
-    .resource_table
-    .add(ChildResource(RefCell::new(child), pid));
+  shutdown::register(pid);
+  // A child attached to a pty wasn't created with `CREATE_NEW_PROCESS_GROUP`.
+  #[cfg(windows)]
+  if group.is_some() && pipes.pty_rid.is_none() {
+    deprecated::register_group_leader(pid);
+  }
+  // The child can't have been reaped yet, so its pid wasn't reused.
+  #[cfg(target_os = "linux")]
+  let pidfd_rid = pidfd::open(pid).map(|pidfd| state.resource_table.add(pidfd));
+  #[cfg(not(target_os = "linux"))]
+  let pidfd_rid = None;
+
+  #[cfg(windows)]
+  let pty = pipes
+    .pty_rid
+    .and_then(|rid| state.resource_table.get::<pty::PtyResource>(rid).ok());
+
+  let child_rid = state.resource_table.add(ChildResource {
+    child: RefCell::new(ManuallyDrop::new(child)),
+    pid,
+    command: program,
+    group,
+    exited: CancelHandle::new(),
+    deadline: timeout.map(|timeout| tokio::time::Instant::now() + timeout),
+    timed_out: Cell::new(false),
+    #[cfg(windows)]
+    pty,
+    status: RefCell::new(None),
+  });
 
   Ok(Child {
     rid: child_rid,
-    pid,
+    pid: Some(pid),
     stdin_rid,
     stdout_rid,
     stderr_rid,
-    ipc_pipe_rid,
-    extra_pipe_rids,
+    start_time,
+    pidfd_rid,
+    pipes,
+  })
+}
+
+/// Spawns the child and puts it in its own process group if requested. If
+/// this fails, the child is gone by the time it returns.
+///
+/// `cpu_affinity` is only applied here on Windows, where the child was spawned
+/// suspended for it by `create_command`. On Linux `create_command` takes care
+/// of it.
+fn start_child(
+  command: &mut tokio::process::Command,
+  #[cfg(windows)] win32: Option<&create_process::Options>,
+  process_group: bool,
+  cpu_affinity: Option<Vec<u32>>,
+) -> Result<(SpawnedChild, Option<ProcessGroup>), AnyError> {
+  // the umask must not be changed while it's inherited
+  let child = deno_fs::umask::creating(None, || {
+    #[cfg(windows)]
+    if let Some(options) = win32 {
+      return create_process::spawn(command.as_std(), options)
+        .map(SpawnedChild::Win32);
+    }
+    command.spawn().map(SpawnedChild::Tokio)
   })
+  .map_err(|err| ProcessError::spawn_failed(command.as_std(), err))?;
+
+  // The child is killed on drop if this fails.
+  #[cfg(windows)]
+  if let (Some(cpus), Some(handle)) = (&cpu_affinity, child.raw_handle()) {
+    affinity::set_process_affinity(handle, cpus)?;
+    // otherwise it is resumed once it was assigned to its job object
+    if !process_group {
+      resume(handle)?;
+    }
+  }
+  #[cfg(not(windows))]
+  let _ = cpu_affinity;
+
+  if !process_group {
+    return Ok((child, None));
+  }
+  match ProcessGroup::new(&child) {
+    Ok(group) => Ok((child, Some(group))),
+    Err(err) => {
+      // killed and reaped like a child whose resource was dropped
+      child.abandon();
+      Err(err)
+    }
+  }
 }
 
 fn close_raw_handle(handle: deno_io::RawBiPipeHandle) {
@@ -554,20 +1688,57 @@ fn close_raw_handle(handle: deno_io::RawBiPipeHandle) {
   }
 }
 
+/// Writes `bytes` to stdin of a child in the background, and closes it
+/// afterwards. Errors are ignored, as the child is free to exit without
+/// reading all of its input.
+fn write_stdin_bytes(mut stdin: tokio::process::ChildStdin, bytes: JsBuffer) {
+  deno_core::unsync::spawn(async move {
+    let _ = stdin.write_all(&bytes).await;
+  });
+}
+
+/// Makes `stdin` of the child a pipe if its input is given as bytes.
+fn pipe_stdin_bytes(
+  args: &mut SpawnArgs,
+  stdin_bytes: &Option<JsBuffer>,
+) -> Result<(), AnyError> {
+  if stdin_bytes.is_some() {
+    if args.pty.is_some() {
+      return Err(type_error("The stdin bytes can't be combined with a pty"));
+    }
+    args.stdio.stdin = StdioOrRid::Stdio(Stdio::Piped);
+  }
+  Ok(())
+}
+
 #[op2]
 #[serde]
 fn op_spawn_child(
   state: &mut OpState,
-  #[serde] args: SpawnArgs,
+  #[serde] mut args: SpawnArgs,
   #[string] api_name: String,
+  #[buffer] stdin_bytes: Option<JsBuffer>,
 ) -> Result<Child, AnyError> {
-  let (command, pipe_rid, extra_pipe_rids, handles_to_close) =
-    create_command(state, args, &api_name)?;
-  let child = spawn_child(state, command, pipe_rid, extra_pipe_rids);
-  for handle in handles_to_close {
-    close_raw_handle(handle);
+  pipe_stdin_bytes(&mut args, &stdin_bytes)?;
+  if args
+    .shell
+    .as_ref()
+    .is_some_and(shell::ShellOption::is_builtin)
+  {
+    return shell::spawn(state, args, &api_name, stdin_bytes);
   }
-  child
+  let process_group = args.process_group;
+  let timeout = args.timeout_ms.map(Duration::from_millis);
+  let cpu_affinity = args.cpu_affinity.clone();
+  let command = create_command(state, args, &api_name)?;
+  spawn_child(
+    state,
+    command,
+    process_group,
+    timeout,
+    cpu_affinity,
+    stdin_bytes,
+  )
 }
 
 #[op2(async)]
@@ -577,62 +1748,392 @@ async fn op_spawn_wait(
   state: Rc<RefCell<OpState>>,
   #[smi] rid: ResourceId,
 ) -> Result<ChildStatus, AnyError> {
+  let shell_child = state
+    .borrow()
+    .resource_table
+    .get::<shell::ShellChildResource>(rid);
+  if let Ok(child) = shell_child {
+    let status = child.wait().await;
+    if let Ok(resource) = state.borrow_mut().resource_table.take_any(rid) {
+      resource.close();
+    }
+    return status;
+  }
   let resource = state
     .borrow_mut()
     .resource_table
     .get::<ChildResource>(rid)?;
-  let result = resource.0.try_borrow_mut()?.wait().await?.try_into();
-  if let Ok(resource) = state.borrow_mut().resource_table.take_any(rid) {
-    resource.close();
+  let mut child = resource.child.try_borrow_mut()?;
+  let waited = resource.wait(&mut child).await;
+  drop(child);
+  shutdown::unregister(resource.pid);
+  #[cfg(windows)]
+  deprecated::unregister_group_leader(resource.pid);
+  resource.exited.cancel();
+  #[cfg(windows)]
+  if let Some(pty) = &resource.pty {
+    pty.close_console();
+  }
+  let ((status, usage), timed_out) = match waited {
+    Ok(waited) => waited,
+    Err(err) => {
+      if let Ok(resource) = state.borrow_mut().resource_table.take_any(rid) {
+        resource.close();
+      }
+      return Err(err.into());
+    }
+  };
+  #[cfg(unix)]
+  if let Ok(child_status) = ChildStatus::try_from(status) {
+    super::signal::record_reaped_child(
+      &mut state.borrow_mut(),
+      ExitedChild {
+        rid,
+        pid: resource.pid,
+        status: ChildStatus {
+          timed_out,
+          ..child_status
+        },
+      },
+    );
+  }
+  let result = ChildStatus::try_from(status).map(|status| ChildStatus {
+    timed_out,
+    usage,
+    ..status
+  });
+  match &result {
+    Ok(status) => *resource.status.borrow_mut() = Some(status.clone()),
+    Err(_) => {
+      if let Ok(resource) = state.borrow_mut().resource_table.take_any(rid) {
+        resource.close();
+      }
+    }
   }
   result
 }
 
+/// Returns the status of the child if it has exited, or `None` if it is still
+/// running. Unlike `op_spawn_wait`, this doesn't block and leaves the
+/// resource open.
+#[op2]
+#[serde]
+fn op_spawn_try_wait(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<Option<ChildStatus>, AnyError> {
+  if let Ok(child) = state.resource_table.get::<shell::ShellChildResource>(rid)
+  {
+    return Ok(child.try_wait());
+  }
+  let resource = state.resource_table.get::<ChildResource>(rid)?;
+  if let Some(status) = &*resource.status.borrow() {
+    return Ok(Some(status.clone()));
+  }
+  let Some(status) = peek_exit_status(resource.pid)? else {
+    return Ok(None);
+  };
+  Ok(Some(ChildStatus {
+    timed_out: resource.timed_out.get(),
+    usage: usage::peek(resource.pid),
+    ..status.try_into()?
+  }))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChildInfo {
+  rid: ResourceId,
+  pid: u32,
+  command: String,
+}
+
+#[cfg(unix)]
+#[derive(Serialize)]
+pub(crate) struct ExitedChild {
+  rid: ResourceId,
+  pub(crate) pid: u32,
+  status: ChildStatus,
+}
+
+/// The children in the resource table which exited, but weren't waited for
+/// yet. As signals are coalesced, a single `SIGCHLD` may stand for several of
+/// them. Children which were waited for are kept by the signal state.
+#[cfg(unix)]
+pub(crate) fn exited_children(state: &OpState) -> Vec<ExitedChild> {
+  state
+    .resource_table
+    .names()
+    .filter(|(_, name)| name == "child")
+    .filter_map(|(rid, _)| {
+      let child = state.resource_table.get::<ChildResource>(rid).ok()?;
+      let status = peek_exit_status(child.pid).ok()??;
+      Some(ExitedChild {
+        rid,
+        pid: child.pid,
+        status: ChildStatus {
+          timed_out: child.timed_out.get(),
+          ..status.try_into().ok()?
+        },
+      })
+    })
+    .collect()
+}
+
+/// Lists the children that are still tracked in the resource table, i.e. the
+/// ones whose exit status hasn't been collected yet.
+#[op2]
+#[serde]
+fn op_spawn_children_list(state: &mut OpState) -> Vec<ChildInfo> {
+  state
+    .resource_table
+    .names()
+    .filter(|(_, name)| name == "child")
+    .filter_map(|(rid, _)| {
+      // `Deno.run` children and the ones of the built-in shell share the
+      // resource name, but not the type
+      let child = state.resource_table.get::<ChildResource>(rid).ok()?;
+      if child.reaped() {
+        return None;
+      }
+      Some(ChildInfo {
+        rid,
+        pid: child.pid,
+        command: child.command.clone(),
+      })
+    })
+    .collect()
+}
+
 #[op2]
 #[serde]
 fn op_spawn_sync(
   state: &mut OpState,
-  #[serde] args: SpawnArgs,
+  #[serde] mut args: SpawnArgs,
+  #[buffer] stdin_bytes: Option<JsBuffer>,
 ) -> Result<SpawnOutput, AnyError> {
-  let stdout = matches!(args.stdio.stdout, StdioOrRid::Stdio(Stdio::Piped));
-  let stderr = matches!(args.stdio.stderr, StdioOrRid::Stdio(Stdio::Piped));
-  let (mut command, _, _, _) =
-    create_command(state, args, "Deno.Command().outputSync()")?;
-  let output = command.output().with_context(|| {
-    format!(
-      "Failed to spawn '{}'",
-      command.get_program().to_string_lossy()
-    )
-  })?;
+  if args.pty.is_some() {
+    return Err(type_error(
+      "The pty option is not supported for Deno.Command().outputSync()",
+    ));
+  }
+  pipe_stdin_bytes(&mut args, &stdin_bytes)?;
+  if args
+    .shell
+    .as_ref()
+    .is_some_and(shell::ShellOption::is_builtin)
+  {
+    return shell::output(state, args, stdin_bytes);
+  }
+  let max_buffer = args.max_buffer;
+  #[cfg(windows)]
+  let cpu_affinity = args.cpu_affinity.clone();
+  #[cfg(windows)]
+  let process_group = args.process_group;
+  let spawn = create_command(state, args, "Deno.Command().outputSync()")?;
+  let mut command = spawn.command;
+  let spawned = deno_fs::umask::creating(None, || {
+    #[cfg(windows)]
+    if let Some(options) = &spawn.win32 {
+      return create_process::spawn(&command, options).map(SyncChild::Win32);
+    }
+    command.spawn().map(SyncChild::Std)
+  });
+  for handle in spawn.handles_to_close {
+    close_raw_handle(handle);
+  }
+  let mut child =
+    spawned.map_err(|err| ProcessError::spawn_failed(&command, err))?;
+  #[cfg(windows)]
+  if let Some(cpus) = &cpu_affinity {
+    if let Err(err) = affinity::set_process_affinity(child.raw_handle(), cpus) {
+      child.kill();
+      return Err(err);
+    }
+  }
+  // Only asynchronously spawned children are assigned to a job object, but
+  // the child was spawned suspended all the same.
+  #[cfg(windows)]
+  if process_group || cpu_affinity.is_some() {
+    if let Err(err) = resume(child.raw_handle()) {
+      child.kill();
+      return Err(err);
+    }
+  }
+  let pid = child.id();
+  let (stdin_pipe, stdout_pipe, stderr_pipe) = child.take_pipes();
+
+  // Both pipes are drained concurrently, as the child could otherwise block
+  // on writing to one of them while we wait on the other. For the same
+  // reason the input is written from another thread.
+  let (stdout, stderr) = std::thread::scope(|scope| {
+    if let (Some(mut pipe), Some(bytes)) = (stdin_pipe, &stdin_bytes) {
+      scope.spawn(move || {
+        use std::io::Write;
+        // the child is free to exit without reading all of its input
+        let _ = pipe.write_all(bytes);
+      });
+    }
+    // The child has not been waited on yet, so its pid can't have been
+    // reused.
+    let kill = || {
+      let _ = deprecated::kill(pid as i32, "SIGKILL");
+    };
+    let stderr_thread = stderr_pipe.map(|pipe| {
+      scope.spawn(move || collect_output(pipe, max_buffer, kill, "stderr"))
+    });
+    let stdout = stdout_pipe
+      .map(|pipe| collect_output(pipe, max_buffer, kill, "stdout"))
+      .transpose();
+    let stderr = stderr_thread
+      .map(|thread| thread.join().expect("stderr reader thread panicked"))
+      .transpose();
+    (stdout, stderr)
+  });
+  let (status, usage) = usage::wait_sync(&mut child)?;
+  let stdout = stdout?;
+  let stderr = stderr?;
 
   Ok(SpawnOutput {
-    status: output.status.try_into()?,
-    stdout: if stdout {
-      Some(output.stdout.into())
-    } else {
-      None
-    },
-    stderr: if stderr {
-      Some(output.stderr.into())
-    } else {
-      None
+    pid: Some(pid),
+    status: ChildStatus {
+      usage,
+      ..status.try_into()?
     },
+    stdout: stdout.map(|buf| buf.into()),
+    stderr: stderr.map(|buf| buf.into()),
   })
 }
 
+/// Reads `pipe` to the end. If more than `max_buffer` bytes are read, the
+/// child is killed and a `MaxBufferExceeded` error is returned.
+fn collect_output(
+  mut pipe: impl std::io::Read,
+  max_buffer: Option<usize>,
+  kill: impl Fn(),
+  name: &str,
+) -> Result<Vec<u8>, AnyError> {
+  let mut output = Vec::new();
+  let mut chunk = vec![0; 64 * 1024];
+  loop {
+    let nread = match pipe.read(&mut chunk) {
+      Ok(0) => return Ok(output),
+      Ok(nread) => nread,
+      Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+      Err(err) => return Err(err.into()),
+    };
+    if let Some(max_buffer) = max_buffer {
+      if output.len() + nread > max_buffer {
+        kill();
+        return Err(custom_error(
+          "MaxBufferExceeded",
+          format!("{name} maxBuffer length of {max_buffer} bytes exceeded"),
+        ));
+      }
+    }
+    output.extend_from_slice(&chunk[..nread]);
+  }
+}
+
 #[op2(fast)]
 fn op_spawn_kill(
   state: &mut OpState,
   #[smi] rid: ResourceId,
   #[string] signal: String,
+  kill_tree: bool,
 ) -> Result<(), AnyError> {
   if let Ok(child_resource) = state.resource_table.get::<ChildResource>(rid) {
-    deprecated::kill(child_resource.1 as i32, &signal)?;
+    if child_resource.reaped() {
+      return Err(type_error("Child process has already terminated."));
+    }
+    if kill_tree {
+      let Some(group) = &child_resource.group else {
+        return Err(type_error(
+          "Child process was not spawned in its own process group.",
+        ));
+      };
+      group.kill(&signal)?;
+    } else {
+      deprecated::kill(child_resource.pid as i32, &signal)?;
+    }
     return Ok(());
   }
+  if let Ok(child) = state.resource_table.get::<shell::ShellChildResource>(rid)
+  {
+    return child.kill(&signal);
+  }
   Err(type_error("Child process has already terminated."))
 }
 
+/// Terminates a child in response to its `AbortSignal` being aborted. The
+/// child is sent `SIGTERM` and, if it is still running after
+/// `grace_period_ms`, `SIGKILL`.
+#[op2(fast)]
+fn op_spawn_abort(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[smi] grace_period_ms: u32,
+) -> Result<(), AnyError> {
+  if let Ok(child) = state.resource_table.get::<shell::ShellChildResource>(rid)
+  {
+    child.cancel();
+    return Ok(());
+  }
+  let child_resource = state
+    .resource_table
+    .get::<ChildResource>(rid)
+    .ok()
+    .filter(|child| !child.reaped());
+  let Some(child_resource) = child_resource else {
+    return Err(type_error("Child process has already terminated."));
+  };
+  let pid = child_resource.pid as i32;
+  deprecated::kill(pid, "SIGTERM")?;
+
+  deno_core::unsync::spawn(async move {
+    let exited = RcRef::map(&child_resource, |r| &r.exited);
+    let grace_period =
+      tokio::time::sleep(Duration::from_millis(grace_period_ms as u64));
+    if grace_period.or_cancel(exited).await.is_ok() {
+      // The child may have exited on its own in the meantime.
+      let _ = deprecated::kill(pid, "SIGKILL");
+    }
+  });
+  Ok(())
+}
+
+/// Relays SIGINT and SIGTERM received by this process to the child until it
+/// exits, for `forwardSignals: true`.
+#[op2(async)]
+async fn op_spawn_forward_signals(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<(), AnyError> {
+  // The processes run by the built-in shell share the console, and get ctrl-c
+  // themselves.
+  if state
+    .borrow()
+    .resource_table
+    .get::<shell::ShellChildResource>(rid)
+    .is_ok()
+  {
+    return Ok(());
+  }
+  let child_resource =
+    state.borrow().resource_table.get::<ChildResource>(rid)?;
+  #[cfg(unix)]
+  {
+    let exited = RcRef::map(&child_resource, |r| &r.exited);
+    super::signal::forward_signals(state, child_resource.pid, exited).await
+  }
+  // Children which share the console get ctrl-c and ctrl-break themselves.
+  #[cfg(windows)]
+  {
+    drop(child_resource);
+    Ok(())
+  }
+}
+
 mod deprecated {
   use super::*;
 
@@ -681,9 +2182,11 @@ mod deprecated {
     #[serde] run_args: RunArgs,
   ) -> Result<RunInfo, AnyError> {
     let args = run_args.cmd;
-    state
-      .borrow_mut::<PermissionsContainer>()
-      .check_run(&args[0], "Deno.run()")?;
+    state.borrow_mut::<PermissionsContainer>().check_run(
+      &args[0],
+      &args[1..],
+      "Deno.run()",
+    )?;
     let env = run_args.env;
     let cwd = run_args.cwd;
 
@@ -729,7 +2232,7 @@ mod deprecated {
     c.kill_on_drop(true);
 
     // Spawn the command.
-    let mut child = c.spawn()?;
+    let mut child = deno_fs::umask::creating(None, || c.spawn())?;
     let pid = child.id();
 
     let stdin_rid = match child.stdin.take() {
@@ -815,21 +2318,55 @@ mod deprecated {
     })
   }
 
+  /// A negative `pid` signals the process group `-pid`. `-1` would signal
+  /// every process the user is allowed to, which is rejected.
   #[cfg(unix)]
   pub fn kill(pid: i32, signal: &str) -> Result<(), AnyError> {
+    if pid == -1 {
+      return Err(type_error("Invalid pid"));
+    }
     let signo = super::super::signal::signal_str_to_int(signal)?;
-    use nix::sys::signal::kill as unix_kill;
-    use nix::sys::signal::Signal;
-    use nix::unistd::Pid;
-    let sig = Signal::try_from(signo)?;
-    unix_kill(Pid::from_raw(pid), Option::Some(sig)).map_err(AnyError::from)
+    // SAFETY: libc call, nix's `Signal` doesn't cover realtime signals
+    let res = unsafe { libc::kill(pid, signo) };
+    nix::errno::Errno::result(res)?;
+    Ok(())
   }
 
+  /// There are no process groups on Windows, so a negative `pid` terminates
+  /// the process `-pid` together with all of its descendants instead, like
+  /// `taskkill /T` does. SIGBREAK is delivered as a ctrl-break event, see
+  /// `send_ctrl_break()`.
   #[cfg(not(unix))]
   pub fn kill(pid: i32, signal: &str) -> Result<(), AnyError> {
+    if !matches!(signal, "SIGKILL" | "SIGTERM" | "SIGBREAK") {
+      Err(type_error(format!("Invalid signal: {signal}")))
+    } else if pid == 0 || pid == -1 {
+      Err(type_error("Invalid pid"))
+    } else if signal == "SIGBREAK" {
+      if pid < 0 {
+        return Err(type_error(
+          "Sending SIGBREAK to a process tree is not supported on Windows",
+        ));
+      }
+      send_ctrl_break(pid as u32)
+    } else if pid < 0 {
+      let root = pid.unsigned_abs();
+      // collected first, so that the root can't spawn any more of them
+      let descendants = process_descendants(root);
+      terminate(root)?;
+      for descendant in descendants {
+        let _ = terminate(descendant);
+      }
+      Ok(())
+    } else {
+      terminate(pid as u32)
+    }
+  }
+
+  #[cfg(not(unix))]
+  fn terminate(pid: u32) -> Result<(), AnyError> {
     use std::io::Error;
     use std::io::ErrorKind::NotFound;
-    use winapi::shared::minwindef::DWORD;
     use winapi::shared::minwindef::FALSE;
     use winapi::shared::minwindef::TRUE;
     use winapi::shared::winerror::ERROR_INVALID_PARAMETER;
@@ -839,35 +2376,205 @@ mod deprecated {
     use winapi::um::processthreadsapi::TerminateProcess;
     use winapi::um::winnt::PROCESS_TERMINATE;
 
-    if !matches!(signal, "SIGKILL" | "SIGTERM") {
-      Err(type_error(format!("Invalid signal: {signal}")))
-    } else if pid <= 0 {
-      Err(type_error("Invalid pid"))
+    // SAFETY: winapi call
+    let handle = unsafe { OpenProcess(PROCESS_TERMINATE, FALSE, pid) };
+
+    if handle.is_null() {
+      // SAFETY: winapi call
+      let err = match unsafe { GetLastError() } {
+        ERROR_INVALID_PARAMETER => Error::from(NotFound), // Invalid `pid`.
+        errno => Error::from_raw_os_error(errno as i32),
+      };
+      Err(err.into())
     } else {
-      let handle =
-        // SAFETY: winapi call
-        unsafe { OpenProcess(PROCESS_TERMINATE, FALSE, pid as DWORD) };
-
-      if handle.is_null() {
-        // SAFETY: winapi call
-        let err = match unsafe { GetLastError() } {
-          ERROR_INVALID_PARAMETER => Error::from(NotFound), // Invalid `pid`.
-          errno => Error::from_raw_os_error(errno as i32),
-        };
-        Err(err.into())
-      } else {
-        // SAFETY: winapi calls
-        unsafe {
-          let is_terminated = TerminateProcess(handle, 1);
-          CloseHandle(handle);
-          match is_terminated {
-            FALSE => Err(Error::last_os_error().into()),
-            TRUE => Ok(()),
-            _ => unreachable!(),
+      // SAFETY: winapi calls
+      unsafe {
+        let is_terminated = TerminateProcess(handle, 1);
+        CloseHandle(handle);
+        match is_terminated {
+          FALSE => Err(Error::last_os_error().into()),
+          TRUE => Ok(()),
+          _ => unreachable!(),
+        }
+      }
+    }
+  }
+
+  /// Whether `pid` is attached to the console of this process.
+  #[cfg(not(unix))]
+  fn shares_console(pid: u32) -> bool {
+    use winapi::um::wincon::GetConsoleProcessList;
+
+    let mut pids = vec![0u32; 64];
+    loop {
+      // SAFETY: winapi call, `pids` has room for `pids.len()` entries
+      let count =
+        unsafe { GetConsoleProcessList(pids.as_mut_ptr(), pids.len() as u32) }
+          as usize;
+      if count == 0 {
+        // this process has no console
+        return false;
+      }
+      if count <= pids.len() {
+        return pids[..count].contains(&pid);
+      }
+      pids.resize(count, 0);
+    }
+  }
+
+  /// The pids of the running children spawned with `processGroup: true`,
+  /// which were created with `CREATE_NEW_PROCESS_GROUP` and so lead a console
+  /// process group of their own.
+  #[cfg(not(unix))]
+  static GROUP_LEADERS: std::sync::Mutex<Vec<u32>> =
+    std::sync::Mutex::new(Vec::new());
+
+  #[cfg(not(unix))]
+  pub(super) fn register_group_leader(pid: u32) {
+    GROUP_LEADERS.lock().unwrap().push(pid);
+  }
+
+  /// Must be called once the child was reaped at the latest, as its pid may be
+  /// reused afterwards.
+  #[cfg(not(unix))]
+  pub(super) fn unregister_group_leader(pid: u32) {
+    GROUP_LEADERS
+      .lock()
+      .unwrap()
+      .retain(|&leader| leader != pid);
+  }
+
+  /// Delivers SIGBREAK as a ctrl-break event, which gives console programs a
+  /// chance to exit gracefully. The event is sent to the process group of
+  /// `pid` alone, so it is limited to children spawned with
+  /// `processGroup: true` that share the console of this process. Windows
+  /// can't limit a ctrl-c event to a process group, so SIGINT is never sent.
+  #[cfg(not(unix))]
+  fn send_ctrl_break(pid: u32) -> Result<(), AnyError> {
+    use winapi::shared::minwindef::FALSE;
+    use winapi::um::wincon::GenerateConsoleCtrlEvent;
+    use winapi::um::wincon::CTRL_BREAK_EVENT;
+
+    if !GROUP_LEADERS.lock().unwrap().contains(&pid) {
+      return Err(type_error(
+        "SIGBREAK can only be sent to child processes spawned with processGroup: true on Windows",
+      ));
+    }
+    if !shares_console(pid) {
+      return Err(type_error(
+        "SIGBREAK can only be sent to child processes sharing the console on Windows",
+      ));
+    }
+    // SAFETY: winapi call
+    if unsafe { GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid) } == FALSE {
+      return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+  }
+
+  /// The creation time of the process `pid` in 100 nanosecond intervals, or
+  /// `None` if it can't be queried, e.g. because it exited.
+  #[cfg(not(unix))]
+  fn creation_time(pid: u32) -> Option<u64> {
+    use winapi::shared::minwindef::FALSE;
+    use winapi::shared::minwindef::FILETIME;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::processthreadsapi::GetProcessTimes;
+    use winapi::um::processthreadsapi::OpenProcess;
+    use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+    // SAFETY: winapi call
+    let handle =
+      unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid) };
+    if handle.is_null() {
+      return None;
+    }
+    // SAFETY: zeroed is a valid representation for FILETIME
+    let (mut creation, mut exit, mut kernel, mut user) = unsafe {
+      (
+        std::mem::zeroed::<FILETIME>(),
+        std::mem::zeroed::<FILETIME>(),
+        std::mem::zeroed::<FILETIME>(),
+        std::mem::zeroed::<FILETIME>(),
+      )
+    };
+    // SAFETY: winapi calls, all pointers are valid for the duration of the
+    // calls
+    let success = unsafe {
+      let success = GetProcessTimes(
+        handle,
+        &mut creation,
+        &mut exit,
+        &mut kernel,
+        &mut user,
+      );
+      CloseHandle(handle);
+      success
+    };
+    if success == FALSE {
+      return None;
+    }
+    Some(
+      ((creation.dwHighDateTime as u64) << 32) | creation.dwLowDateTime as u64,
+    )
+  }
+
+  /// Returns the pids of all running descendants of the process `root`.
+  ///
+  /// Windows doesn't clear the parent pid of a process whose parent exited, so
+  /// after the pid of the parent was reused, the process would appear to be a
+  /// child of an unrelated one. Such processes are told apart by being
+  /// created before their supposed parent.
+  #[cfg(not(unix))]
+  fn process_descendants(root: u32) -> Vec<u32> {
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+    use winapi::um::tlhelp32::CreateToolhelp32Snapshot;
+    use winapi::um::tlhelp32::Process32First;
+    use winapi::um::tlhelp32::Process32Next;
+    use winapi::um::tlhelp32::PROCESSENTRY32;
+    use winapi::um::tlhelp32::TH32CS_SNAPPROCESS;
+
+    // (pid, parent pid) of every process
+    let mut processes = Vec::new();
+    // SAFETY: winapi calls, `entry` outlives them
+    unsafe {
+      let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+      if snapshot == INVALID_HANDLE_VALUE {
+        return Vec::new();
+      }
+      let mut entry: PROCESSENTRY32 = std::mem::zeroed();
+      entry.dwSize = std::mem::size_of::<PROCESSENTRY32>() as u32;
+      let mut success = Process32First(snapshot, &mut entry);
+      while success != 0 {
+        processes.push((entry.th32ProcessID, entry.th32ParentProcessID));
+        success = Process32Next(snapshot, &mut entry);
+      }
+      CloseHandle(snapshot);
+    }
+
+    let Some(root_created) = creation_time(root) else {
+      return Vec::new();
+    };
+    let mut descendants = Vec::new();
+    let mut parents = vec![(root, root_created)];
+    while let Some((parent, parent_created)) = parents.pop() {
+      for &(pid, ppid) in &processes {
+        // a pid can be its own parent pid, e.g. the System Idle process
+        if ppid != parent || pid == root || descendants.contains(&pid) {
+          continue;
+        }
+        match creation_time(pid) {
+          Some(created) if created >= parent_created => {
+            descendants.push(pid);
+            parents.push((pid, created));
           }
+          // the process exited, or its parent pid is stale
+          _ => {}
         }
       }
     }
+    descendants
   }
 
   #[op2(fast)]
@@ -0,0 +1,58 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Pinning a child to a set of CPUs for `Deno.Command`'s `cpuAffinity`
+//! option.
+//!
+//! On Linux the affinity is set in the child before it executes the program.
+//! On Windows it can only be set once the child exists, so the child is
+//! spawned suspended and only resumed after it was set.
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+
+#[cfg(any(target_os = "linux", windows))]
+fn validate(cpus: &[u32], max: usize) -> Result<(), AnyError> {
+  if cpus.is_empty() {
+    return Err(type_error("cpuAffinity must list at least one CPU"));
+  }
+  if let Some(cpu) = cpus.iter().find(|&&cpu| cpu as usize >= max) {
+    return Err(type_error(format!("Invalid CPU: {cpu}")));
+  }
+  Ok(())
+}
+
+/// Builds the CPU set passed to `sched_setaffinity`, which has to happen
+/// before forking.
+#[cfg(target_os = "linux")]
+pub fn cpu_set(cpus: &[u32]) -> Result<libc::cpu_set_t, AnyError> {
+  validate(cpus, libc::CPU_SETSIZE as usize)?;
+  // SAFETY: zeroed is a valid representation for `cpu_set_t`
+  let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+  for &cpu in cpus {
+    // SAFETY: `cpu` was validated to be within the set
+    unsafe { libc::CPU_SET(cpu as usize, &mut set) };
+  }
+  Ok(set)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn unsupported() -> AnyError {
+  type_error("The cpuAffinity option is only supported on Linux and Windows")
+}
+
+#[cfg(windows)]
+pub fn set_process_affinity(
+  handle: std::os::windows::io::RawHandle,
+  cpus: &[u32],
+) -> Result<(), AnyError> {
+  use winapi::shared::minwindef::FALSE;
+  use winapi::um::winbase::SetProcessAffinityMask;
+
+  validate(cpus, usize::BITS as usize)?;
+  let mask = cpus.iter().fold(0usize, |mask, &cpu| mask | (1 << cpu));
+  // SAFETY: winapi call, `handle` is a valid process handle
+  if unsafe { SetProcessAffinityMask(handle as _, mask) } == FALSE {
+    return Err(std::io::Error::last_os_error().into());
+  }
+  Ok(())
+}
@@ -0,0 +1,714 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Creating children with `CreateProcessW` directly on Windows, for what
+//! `std::process::Command` doesn't support on stable Rust: attaching a child
+//! to a pseudo console, and restricting the handles a child inherits to a
+//! `PROC_THREAD_ATTRIBUTE_HANDLE_LIST`.
+//!
+//! `std::process::Command` lets a child inherit every inheritable handle of
+//! this process. Handles meant for one child would therefore leak into any
+//! other child spawned at the same time, so they are created non-inheritable
+//! and only made inheritable here, right before they are listed.
+//!
+//! The child is still described by a `std::process::Command`, so that it is
+//! configured the same way as any other child. What can't be read back from
+//! it is recorded in [`Options`].
+
+use std::collections::BTreeMap;
+use std::ffi::c_void;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::io;
+use std::os::windows::ffi::OsStrExt;
+use std::os::windows::io::AsRawHandle;
+use std::os::windows::io::BorrowedHandle;
+use std::os::windows::io::FromRawHandle;
+use std::os::windows::io::OwnedHandle;
+use std::os::windows::io::RawHandle;
+use std::os::windows::process::ExitStatusExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use tokio::sync::oneshot;
+use windows_sys::Win32::Foundation::SetHandleInformation;
+use windows_sys::Win32::Foundation::BOOLEAN;
+use windows_sys::Win32::Foundation::FALSE;
+use windows_sys::Win32::Foundation::HANDLE;
+use windows_sys::Win32::Foundation::HANDLE_FLAG_INHERIT;
+use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+use windows_sys::Win32::Foundation::TRUE;
+use windows_sys::Win32::Foundation::WAIT_OBJECT_0;
+use windows_sys::Win32::Foundation::WAIT_TIMEOUT;
+use windows_sys::Win32::System::Console::GetStdHandle;
+use windows_sys::Win32::System::Console::HPCON;
+use windows_sys::Win32::System::Console::STD_ERROR_HANDLE;
+use windows_sys::Win32::System::Console::STD_INPUT_HANDLE;
+use windows_sys::Win32::System::Console::STD_OUTPUT_HANDLE;
+use windows_sys::Win32::System::Pipes::CreatePipe;
+use windows_sys::Win32::System::Threading::CreateProcessW;
+use windows_sys::Win32::System::Threading::DeleteProcThreadAttributeList;
+use windows_sys::Win32::System::Threading::GetExitCodeProcess;
+use windows_sys::Win32::System::Threading::InitializeProcThreadAttributeList;
+use windows_sys::Win32::System::Threading::RegisterWaitForSingleObject;
+use windows_sys::Win32::System::Threading::TerminateProcess;
+use windows_sys::Win32::System::Threading::UnregisterWaitEx;
+use windows_sys::Win32::System::Threading::UpdateProcThreadAttribute;
+use windows_sys::Win32::System::Threading::WaitForSingleObject;
+use windows_sys::Win32::System::Threading::CREATE_UNICODE_ENVIRONMENT;
+use windows_sys::Win32::System::Threading::EXTENDED_STARTUPINFO_PRESENT;
+use windows_sys::Win32::System::Threading::INFINITE;
+use windows_sys::Win32::System::Threading::PROCESS_INFORMATION;
+use windows_sys::Win32::System::Threading::PROC_THREAD_ATTRIBUTE_HANDLE_LIST;
+use windows_sys::Win32::System::Threading::PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE;
+use windows_sys::Win32::System::Threading::STARTF_USESTDHANDLES;
+use windows_sys::Win32::System::Threading::STARTUPINFOEXW;
+use windows_sys::Win32::System::Threading::WT_EXECUTEINWAITTHREAD;
+use windows_sys::Win32::System::Threading::WT_EXECUTEONLYONCE;
+
+/// The parts of the configuration of a `std::process::Command` that can't be
+/// read back from it, along with what it doesn't support.
+pub struct Options {
+  /// Whether the environment starts out empty instead of being inherited.
+  pub env_cleared: bool,
+  /// Whether the arguments were added with `raw_arg`, and are passed on as
+  /// they are instead of being quoted.
+  pub raw_args: bool,
+  pub creation_flags: u32,
+  /// How stdin, stdout and stderr of the child are set up. `None` if it is
+  /// attached to `pseudo_console` instead.
+  pub stdio: Option<[Stdio; 3]>,
+  /// The pseudo console the child is attached to instead of stdio. It must
+  /// stay open until the child was spawned.
+  pub pseudo_console: Option<HPCON>,
+  /// Handles the child inherits in addition to its stdio, under the same
+  /// values. They are made inheritable when the child is spawned, so they are
+  /// meant to be closed right after.
+  pub inherited_handles: Vec<RawHandle>,
+}
+
+/// How a stdio stream of the child is set up, like `std::process::Stdio`.
+#[derive(Clone, Copy)]
+pub enum Stdio {
+  /// The corresponding stdio handle of this process.
+  Inherit,
+  /// A duplicate of the given handle, which stays owned by the caller.
+  Handle(RawHandle),
+  /// A new pipe, whose other end is returned with the child.
+  Piped,
+  /// The null device.
+  Null,
+}
+
+impl Stdio {
+  /// Returns the handle of the child for stdio stream `fd`, and the other end
+  /// of its pipe if one was created.
+  fn open(
+    self,
+    fd: usize,
+  ) -> io::Result<(Option<OwnedHandle>, Option<OwnedHandle>)> {
+    match self {
+      Stdio::Inherit => {
+        let id = [STD_INPUT_HANDLE, STD_OUTPUT_HANDLE, STD_ERROR_HANDLE][fd];
+        // SAFETY: winapi call
+        let handle = unsafe { GetStdHandle(id) };
+        // this process may not have the stream at all
+        if handle == 0 || handle == INVALID_HANDLE_VALUE {
+          return Ok((None, None));
+        }
+        Ok((Some(duplicate(handle as _)?), None))
+      }
+      Stdio::Handle(handle) => Ok((Some(duplicate(handle)?), None)),
+      Stdio::Piped => {
+        let (read, write) = pipe()?;
+        Ok(match fd {
+          0 => (Some(read), Some(write)),
+          _ => (Some(write), Some(read)),
+        })
+      }
+      Stdio::Null => {
+        let null = std::fs::OpenOptions::new()
+          .read(true)
+          .write(true)
+          .open("NUL")?;
+        Ok((Some(null.into()), None))
+      }
+    }
+  }
+}
+
+/// Returns a non-inheritable duplicate of `handle`.
+fn duplicate(handle: RawHandle) -> io::Result<OwnedHandle> {
+  // SAFETY: the handle is kept open by the caller
+  unsafe { BorrowedHandle::borrow_raw(handle) }.try_clone_to_owned()
+}
+
+/// A child created by [`spawn`]. Like a child spawned with `kill_on_drop`, it
+/// is killed if it is still running when it is dropped.
+pub struct Child {
+  process: OwnedHandle,
+  pid: u32,
+  /// The ends of the pipes of [`Stdio::Piped`] streams, for this process.
+  pub stdin: Option<OwnedHandle>,
+  pub stdout: Option<OwnedHandle>,
+  pub stderr: Option<OwnedHandle>,
+}
+
+impl Child {
+  pub fn id(&self) -> u32 {
+    self.pid
+  }
+
+  pub fn as_raw_handle(&self) -> RawHandle {
+    self.process.as_raw_handle()
+  }
+
+  pub fn start_kill(&self) -> io::Result<()> {
+    // SAFETY: winapi call, the handle is valid
+    if unsafe { TerminateProcess(self.as_raw_handle() as _, 1) } == FALSE {
+      return Err(io::Error::last_os_error());
+    }
+    Ok(())
+  }
+
+  pub fn try_wait(&self) -> io::Result<Option<ExitStatus>> {
+    // SAFETY: winapi call, the handle is valid
+    match unsafe { WaitForSingleObject(self.as_raw_handle() as _, 0) } {
+      WAIT_OBJECT_0 => {}
+      WAIT_TIMEOUT => return Ok(None),
+      _ => return Err(io::Error::last_os_error()),
+    }
+    let mut code = 0;
+    // SAFETY: winapi call, `code` outlives the call
+    if unsafe { GetExitCodeProcess(self.as_raw_handle() as _, &mut code) }
+      == FALSE
+    {
+      return Err(io::Error::last_os_error());
+    }
+    Ok(Some(ExitStatus::from_raw(code)))
+  }
+
+  pub async fn wait(&mut self) -> io::Result<ExitStatus> {
+    if let Some(status) = self.try_wait()? {
+      return Ok(status);
+    }
+    let (sender, receiver) = oneshot::channel();
+    let _registration = ExitWait::register(self.as_raw_handle(), sender)?;
+    // The sender is only dropped without sending along with the
+    // registration.
+    let _ = receiver.await;
+    self.try_wait()?.ok_or_else(|| {
+      io::Error::other("The child was signaled, but is still running")
+    })
+  }
+
+  /// Blocks the current thread until the child has exited.
+  pub fn wait_sync(&self) -> io::Result<ExitStatus> {
+    // SAFETY: winapi call, the handle is valid
+    if unsafe { WaitForSingleObject(self.as_raw_handle() as _, INFINITE) }
+      != WAIT_OBJECT_0
+    {
+      return Err(io::Error::last_os_error());
+    }
+    self.try_wait()?.ok_or_else(|| {
+      io::Error::other("The child was signaled, but is still running")
+    })
+  }
+}
+
+impl Drop for Child {
+  fn drop(&mut self) {
+    if let Ok(None) = self.try_wait() {
+      let _ = self.start_kill();
+    }
+  }
+}
+
+/// A wait for a process to exit on the thread pool, which signals `sender`
+/// once it did.
+struct ExitWait {
+  handle: HANDLE,
+  sender: *mut Option<oneshot::Sender<()>>,
+}
+
+impl ExitWait {
+  fn register(
+    process: RawHandle,
+    sender: oneshot::Sender<()>,
+  ) -> io::Result<Self> {
+    unsafe extern "system" fn on_exit(context: *mut c_void, _: BOOLEAN) {
+      // SAFETY: `context` is the sender, which is only freed once the wait
+      // was unregistered and this callback returned
+      let sender =
+        unsafe { &mut *(context as *mut Option<oneshot::Sender<()>>) };
+      if let Some(sender) = sender.take() {
+        let _ = sender.send(());
+      }
+    }
+
+    let sender = Box::into_raw(Box::new(Some(sender)));
+    let mut handle = 0 as HANDLE;
+    // SAFETY: winapi call, the sender stays valid until the wait is
+    // unregistered
+    let ok = unsafe {
+      RegisterWaitForSingleObject(
+        &mut handle,
+        process as _,
+        Some(on_exit),
+        sender as *const c_void,
+        INFINITE,
+        WT_EXECUTEINWAITTHREAD | WT_EXECUTEONLYONCE,
+      )
+    };
+    if ok == FALSE {
+      // SAFETY: the sender was never handed to the callback
+      drop(unsafe { Box::from_raw(sender) });
+      return Err(io::Error::last_os_error());
+    }
+    Ok(Self { handle, sender })
+  }
+}
+
+impl Drop for ExitWait {
+  fn drop(&mut self) {
+    // SAFETY: winapi call. With `INVALID_HANDLE_VALUE` it only returns once
+    // a running callback has returned, so the sender can be freed after it.
+    unsafe {
+      UnregisterWaitEx(self.handle, INVALID_HANDLE_VALUE);
+      drop(Box::from_raw(self.sender));
+    }
+  }
+}
+
+/// An attribute list for `STARTUPINFOEXW`.
+struct AttributeList {
+  // `usize` for the alignment of the list.
+  buffer: Vec<usize>,
+}
+
+impl AttributeList {
+  fn new(count: u32) -> io::Result<Self> {
+    let mut size = 0;
+    // SAFETY: winapi call, it only writes the required size when called
+    // without a list
+    unsafe {
+      InitializeProcThreadAttributeList(
+        std::ptr::null_mut(),
+        count,
+        0,
+        &mut size,
+      )
+    };
+    let mut list = Self {
+      buffer: vec![0; size.div_ceil(std::mem::size_of::<usize>())],
+    };
+    // SAFETY: winapi call, the buffer has the required size
+    if unsafe {
+      InitializeProcThreadAttributeList(list.as_mut_ptr(), count, 0, &mut size)
+    } == FALSE
+    {
+      let err = io::Error::last_os_error();
+      // not initialized, so it must not be deleted on drop
+      list.buffer = Vec::new();
+      return Err(err);
+    }
+    Ok(list)
+  }
+
+  fn as_mut_ptr(&mut self) -> *mut c_void {
+    self.buffer.as_mut_ptr() as _
+  }
+
+  /// # Safety
+  ///
+  /// `value` must stay valid until the list was used to create the process.
+  unsafe fn set(
+    &mut self,
+    attribute: usize,
+    value: *const c_void,
+    size: usize,
+  ) -> io::Result<()> {
+    // SAFETY: winapi call, `value` is valid as guaranteed by the caller
+    let ok = unsafe {
+      UpdateProcThreadAttribute(
+        self.as_mut_ptr(),
+        0,
+        attribute,
+        value,
+        size,
+        std::ptr::null_mut(),
+        std::ptr::null(),
+      )
+    };
+    if ok == FALSE {
+      return Err(io::Error::last_os_error());
+    }
+    Ok(())
+  }
+}
+
+impl Drop for AttributeList {
+  fn drop(&mut self) {
+    if !self.buffer.is_empty() {
+      // SAFETY: winapi call, the list was initialized
+      unsafe { DeleteProcThreadAttributeList(self.as_mut_ptr()) };
+    }
+  }
+}
+
+/// Spawns `command` with `options`. Of the handles of this process, the child
+/// only inherits its stdio and `options.inherited_handles`.
+pub fn spawn(
+  command: &std::process::Command,
+  options: &Options,
+) -> io::Result<Child> {
+  let env = environment(command, options.env_cleared);
+  let cwd = match command.get_current_dir() {
+    Some(cwd) => cwd.to_path_buf(),
+    None => std::env::current_dir()?,
+  };
+  let program = resolve_program(
+    command.get_program(),
+    env
+      .get(OsStr::new("PATH"))
+      .map(|(_, path)| path.as_os_str()),
+    &cwd,
+  )?;
+  let is_batch_file = program.extension().is_some_and(|ext| {
+    ext.eq_ignore_ascii_case("bat") || ext.eq_ignore_ascii_case("cmd")
+  });
+  if is_batch_file {
+    // `std::process::Command` escapes their arguments for `cmd.exe`, which
+    // isn't replicated here.
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      "batch files have to be run through cmd.exe explicitly",
+    ));
+  }
+  let program = to_wide(program.as_os_str())?;
+  let mut command_line = command_line(command, options.raw_args)?;
+  let env_block = env_block(&env)?;
+  let cwd = to_wide(cwd.as_os_str())?;
+
+  // The handles of the child are kept open until it was created.
+  let mut child_stdio = [None, None, None];
+  let mut pipes = [None, None, None];
+  if let Some(stdio) = options.stdio {
+    for (fd, stdio) in stdio.into_iter().enumerate() {
+      (child_stdio[fd], pipes[fd]) = stdio.open(fd)?;
+    }
+  }
+  let handle_list = child_stdio
+    .iter()
+    .flatten()
+    .map(|handle| handle.as_raw_handle())
+    .chain(options.inherited_handles.iter().copied())
+    .map(|handle| handle as HANDLE)
+    .collect::<Vec<_>>();
+  for &handle in &handle_list {
+    // SAFETY: winapi call, the handle is valid
+    let ok = unsafe {
+      SetHandleInformation(handle, HANDLE_FLAG_INHERIT, HANDLE_FLAG_INHERIT)
+    };
+    if ok == FALSE {
+      return Err(io::Error::last_os_error());
+    }
+  }
+
+  let mut attributes = AttributeList::new(2)?;
+  // An empty list is rejected, and no handles are inherited without one.
+  if !handle_list.is_empty() {
+    // SAFETY: the list outlives the attribute list
+    unsafe {
+      attributes.set(
+        PROC_THREAD_ATTRIBUTE_HANDLE_LIST as usize,
+        handle_list.as_ptr() as *const c_void,
+        handle_list.len() * std::mem::size_of::<HANDLE>(),
+      )?;
+    }
+  }
+  if let Some(console) = options.pseudo_console {
+    // SAFETY: the value of this attribute is the handle itself
+    unsafe {
+      attributes.set(
+        PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE as usize,
+        console as *const c_void,
+        std::mem::size_of::<HPCON>(),
+      )?;
+    }
+  }
+
+  // SAFETY: zeroed is a valid representation for both structs
+  let (mut startup_info, mut info) = unsafe {
+    (
+      std::mem::zeroed::<STARTUPINFOEXW>(),
+      std::mem::zeroed::<PROCESS_INFORMATION>(),
+    )
+  };
+  startup_info.StartupInfo.cb = std::mem::size_of::<STARTUPINFOEXW>() as u32;
+  startup_info.lpAttributeList = attributes.as_mut_ptr();
+  if options.stdio.is_some() {
+    let [stdin, stdout, stderr] = child_stdio.each_ref().map(|stdio| {
+      stdio
+        .as_ref()
+        .map_or(0, |handle| handle.as_raw_handle() as HANDLE)
+    });
+    startup_info.StartupInfo.dwFlags |= STARTF_USESTDHANDLES;
+    startup_info.StartupInfo.hStdInput = stdin;
+    startup_info.StartupInfo.hStdOutput = stdout;
+    startup_info.StartupInfo.hStdError = stderr;
+  }
+  // SAFETY: winapi call, all pointers are valid for the duration of the call
+  let ok = unsafe {
+    CreateProcessW(
+      program.as_ptr(),
+      command_line.as_mut_ptr(),
+      std::ptr::null(),
+      std::ptr::null(),
+      if handle_list.is_empty() { FALSE } else { TRUE },
+      options.creation_flags
+        | CREATE_UNICODE_ENVIRONMENT
+        | EXTENDED_STARTUPINFO_PRESENT,
+      env_block.as_ptr() as *const c_void,
+      cwd.as_ptr(),
+      &startup_info.StartupInfo,
+      &mut info,
+    )
+  };
+  if ok == FALSE {
+    return Err(io::Error::last_os_error());
+  }
+  // SAFETY: both handles were just returned by `CreateProcessW`, and are
+  // owned by us
+  let (process, _thread) = unsafe {
+    (
+      OwnedHandle::from_raw_handle(info.hProcess as _),
+      OwnedHandle::from_raw_handle(info.hThread as _),
+    )
+  };
+  let [stdin, stdout, stderr] = pipes;
+  Ok(Child {
+    process,
+    pid: info.dwProcessId,
+    stdin,
+    stdout,
+    stderr,
+  })
+}
+
+/// Creates an anonymous pipe, returning its read and write ends. Both ends are
+/// synchronous and not inheritable. Unlike the overlapped ends of a named
+/// pipe, they can be used from tokio's blocking stdio on this side.
+pub fn pipe() -> io::Result<(OwnedHandle, OwnedHandle)> {
+  let (mut read, mut write) = (0 as HANDLE, 0 as HANDLE);
+  // SAFETY: winapi call, both handles outlive the call
+  if unsafe { CreatePipe(&mut read, &mut write, std::ptr::null(), 0) } == FALSE
+  {
+    return Err(io::Error::last_os_error());
+  }
+  // SAFETY: both handles were just created and are owned by us
+  Ok(unsafe {
+    (
+      OwnedHandle::from_raw_handle(read as _),
+      OwnedHandle::from_raw_handle(write as _),
+    )
+  })
+}
+
+/// The environment of the child, keyed by the uppercased names, as they are
+/// case-insensitive and sorted that way in the environment block.
+fn environment(
+  command: &std::process::Command,
+  cleared: bool,
+) -> BTreeMap<OsString, (OsString, OsString)> {
+  let mut env = BTreeMap::new();
+  if !cleared {
+    for (key, value) in std::env::vars_os() {
+      env.insert(key.to_ascii_uppercase(), (key, value));
+    }
+  }
+  for (key, value) in command.get_envs() {
+    match value {
+      Some(value) => {
+        env.insert(key.to_ascii_uppercase(), (key.into(), value.into()));
+      }
+      None => {
+        env.remove(&key.to_ascii_uppercase());
+      }
+    }
+  }
+  env
+}
+
+fn env_block(
+  env: &BTreeMap<OsString, (OsString, OsString)>,
+) -> io::Result<Vec<u16>> {
+  let mut block = Vec::new();
+  for (key, value) in env.values() {
+    block.extend(to_wide(key)?.into_iter().take_while(|&c| c != 0));
+    block.push(b'=' as u16);
+    block.extend(to_wide(value)?);
+  }
+  // the block is terminated by an empty entry, which also makes an empty
+  // block terminated twice
+  if block.is_empty() {
+    block.push(0);
+  }
+  block.push(0);
+  Ok(block)
+}
+
+/// Looks up `program` in `path` the way `std::process::Command` does, if it
+/// isn't a path already.
+fn resolve_program(
+  program: &OsStr,
+  path: Option<&OsStr>,
+  cwd: &Path,
+) -> io::Result<PathBuf> {
+  let is_path = program
+    .encode_wide()
+    .any(|c| c == b'/' as u16 || c == b'\\' as u16);
+  if is_path {
+    return Ok(PathBuf::from(program));
+  }
+  which::which_in(program, path, cwd)
+    .map_err(|_| io::Error::from(io::ErrorKind::NotFound))
+}
+
+/// Builds the command line the way `std::process::Command` does, so that the
+/// child gets back the same arguments from it.
+fn command_line(
+  command: &std::process::Command,
+  raw_args: bool,
+) -> io::Result<Vec<u16>> {
+  let mut line = Vec::new();
+  // The program is always quoted. It can't contain quotes itself.
+  line.push(b'"' as u16);
+  line.extend(command.get_program().encode_wide());
+  line.push(b'"' as u16);
+  for arg in command.get_args() {
+    line.push(b' ' as u16);
+    if raw_args {
+      line.extend(arg.encode_wide());
+    } else {
+      append_quoted(&mut line, arg);
+    }
+  }
+  if line.contains(&0) {
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      "nul byte found in provided data",
+    ));
+  }
+  line.push(0);
+  Ok(line)
+}
+
+/// Quotes `arg` so that `CommandLineToArgvW` parses it back as it is.
+fn append_quoted(line: &mut Vec<u16>, arg: &OsStr) {
+  let quote = arg.is_empty()
+    || arg
+      .encode_wide()
+      .any(|c| c == b' ' as u16 || c == b'\t' as u16);
+  if quote {
+    line.push(b'"' as u16);
+  }
+  let mut backslashes = 0;
+  for c in arg.encode_wide() {
+    if c == b'\\' as u16 {
+      backslashes += 1;
+    } else {
+      if c == b'"' as u16 {
+        // n backslashes followed by a quote become 2n + 1 backslashes
+        line.extend(std::iter::repeat(b'\\' as u16).take(backslashes + 1));
+      }
+      backslashes = 0;
+    }
+    line.push(c);
+  }
+  if quote {
+    // n backslashes followed by the closing quote become 2n backslashes
+    line.extend(std::iter::repeat(b'\\' as u16).take(backslashes));
+    line.push(b'"' as u16);
+  }
+}
+
+/// Encodes `s` as a nul terminated wide string.
+fn to_wide(s: &OsStr) -> io::Result<Vec<u16>> {
+  let mut wide: Vec<u16> = s.encode_wide().collect();
+  if wide.contains(&0) {
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      "nul byte found in provided data",
+    ));
+  }
+  wide.push(0);
+  Ok(wide)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn quoted(arg: &str) -> String {
+    let mut line = Vec::new();
+    append_quoted(&mut line, OsStr::new(arg));
+    String::from_utf16(&line).unwrap()
+  }
+
+  #[test]
+  fn quotes_arguments() {
+    assert_eq!(quoted("abc"), "abc");
+    assert_eq!(quoted(""), r#""""#);
+    assert_eq!(quoted("a b"), r#""a b""#);
+    assert_eq!(quoted(r#"a"b"#), r#"a\"b"#);
+    assert_eq!(quoted(r"a\b"), r"a\b");
+    assert_eq!(quoted(r"a b\"), r#""a b\\""#);
+    assert_eq!(quoted(r#"a\"b"#), r#"a\\\"b"#);
+  }
+
+  #[tokio::test]
+  async fn spawns_and_waits() {
+    let mut command = std::process::Command::new("cmd");
+    command.arg("/c").arg("exit 3");
+    let mut child = spawn(
+      &command,
+      &Options {
+        env_cleared: false,
+        raw_args: false,
+        creation_flags: 0,
+        stdio: Some([Stdio::Null; 3]),
+        pseudo_console: None,
+        inherited_handles: Vec::new(),
+      },
+    )
+    .unwrap();
+    assert_eq!(child.wait().await.unwrap().code(), Some(3));
+    assert_eq!(child.try_wait().unwrap().unwrap().code(), Some(3));
+  }
+
+  #[test]
+  fn pipes_stdio() {
+    use std::io::Read;
+
+    let mut command = std::process::Command::new("cmd");
+    command.arg("/c").arg("echo hello");
+    let mut child = spawn(
+      &command,
+      &Options {
+        env_cleared: false,
+        raw_args: false,
+        creation_flags: 0,
+        stdio: Some([Stdio::Null, Stdio::Piped, Stdio::Null]),
+        pseudo_console: None,
+        inherited_handles: Vec::new(),
+      },
+    )
+    .unwrap();
+    let mut output = String::new();
+    std::fs::File::from(child.stdout.take().unwrap())
+      .read_to_string(&mut output)
+      .unwrap();
+    assert_eq!(output, "hello\r\n");
+    assert!(child.wait_sync().unwrap().success());
+  }
+}
@@ -0,0 +1,67 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Filtering the environment variables a child inherits, for
+//! `Deno.Command`'s `envInherit` option.
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use regex::Regex;
+use regex::RegexBuilder;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum EnvInheritPattern {
+  /// The exact name of a variable, compared case insensitively on Windows.
+  Name(String),
+  /// A regular expression, which matches if it is found anywhere in the name
+  /// like `RegExp.prototype.test()`.
+  #[serde(rename_all = "camelCase")]
+  Regex { source: String, ignore_case: bool },
+}
+
+enum Matcher {
+  Name(String),
+  Regex(Regex),
+}
+
+impl Matcher {
+  fn matches(&self, key: &str) -> bool {
+    match self {
+      #[cfg(windows)]
+      Self::Name(name) => name.eq_ignore_ascii_case(key),
+      #[cfg(not(windows))]
+      Self::Name(name) => name == key,
+      Self::Regex(regex) => regex.is_match(key),
+    }
+  }
+}
+
+/// Returns the variables of the current process whose name matches any of the
+/// given patterns.
+pub fn inherited_vars(
+  patterns: &[EnvInheritPattern],
+) -> Result<Vec<(String, String)>, AnyError> {
+  let matchers = patterns
+    .iter()
+    .map(|pattern| match pattern {
+      EnvInheritPattern::Name(name) => Ok(Matcher::Name(name.clone())),
+      EnvInheritPattern::Regex {
+        source,
+        ignore_case,
+      } => RegexBuilder::new(source)
+        .case_insensitive(*ignore_case)
+        .build()
+        .map(Matcher::Regex)
+        .map_err(|err| {
+          type_error(format!("Invalid envInherit pattern /{source}/: {err}"))
+        }),
+    })
+    .collect::<Result<Vec<_>, AnyError>>()?;
+
+  Ok(
+    std::env::vars()
+      .filter(|(key, _)| matchers.iter().any(|matcher| matcher.matches(key)))
+      .collect(),
+  )
+}
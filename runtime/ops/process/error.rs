@@ -0,0 +1,97 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! The error returned when a child could not be spawned, which keeps the
+//! reason so that it is surfaced to JS as a distinct error class.
+
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum ProcessError {
+  /// The program does not exist.
+  NotFound { command: String, source: io::Error },
+  /// The program is not allowed to be executed.
+  PermissionDenied { command: String, source: io::Error },
+  /// The `cwd` does not exist.
+  CwdNotFound { command: String, cwd: String },
+  /// The `cwd` exists, but is not a directory.
+  CwdNotDirectory { command: String, cwd: String },
+  /// Any other failure.
+  Io { command: String, source: io::Error },
+}
+
+impl ProcessError {
+  pub(super) fn spawn_failed(
+    command: &std::process::Command,
+    err: io::Error,
+  ) -> Self {
+    let command_name = command.get_program().to_string_lossy().into_owned();
+
+    if let Some(cwd) = command.get_current_dir() {
+      // launching a sub process always depends on the real
+      // file system so using these methods directly is ok
+      #[allow(clippy::disallowed_methods)]
+      if !cwd.exists() {
+        return Self::CwdNotFound {
+          command: command_name,
+          cwd: cwd.to_string_lossy().into_owned(),
+        };
+      }
+
+      #[allow(clippy::disallowed_methods)]
+      if !cwd.is_dir() {
+        return Self::CwdNotDirectory {
+          command: command_name,
+          cwd: cwd.to_string_lossy().into_owned(),
+        };
+      }
+    }
+
+    match err.kind() {
+      io::ErrorKind::NotFound => Self::NotFound {
+        command: command_name,
+        source: err,
+      },
+      io::ErrorKind::PermissionDenied => Self::PermissionDenied {
+        command: command_name,
+        source: err,
+      },
+      _ => Self::Io {
+        command: command_name,
+        source: err,
+      },
+    }
+  }
+}
+
+impl fmt::Display for ProcessError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::NotFound { command, source }
+      | Self::PermissionDenied { command, source }
+      | Self::Io { command, source } => {
+        write!(f, "Failed to spawn '{command}': {source}")
+      }
+      Self::CwdNotFound { command, cwd } => {
+        write!(f, "Failed to spawn '{command}': No such cwd '{cwd}'")
+      }
+      Self::CwdNotDirectory { command, cwd } => {
+        write!(
+          f,
+          "Failed to spawn '{command}': cwd is not a directory '{cwd}'"
+        )
+      }
+    }
+  }
+}
+
+impl std::error::Error for ProcessError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::NotFound { source, .. }
+      | Self::PermissionDenied { source, .. }
+      | Self::Io { source, .. } => Some(source),
+      Self::CwdNotFound { .. } | Self::CwdNotDirectory { .. } => None,
+    }
+  }
+}
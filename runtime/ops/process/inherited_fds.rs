@@ -0,0 +1,56 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Passing fds of the current process beyond stdio through to children, for
+//! `"inherit"` entries in the extra stdio of a command.
+//!
+//! Deno doesn't generally deal with raw fds, so only the fds an embedder put
+//! into an [`InheritedFdsTable`] are passed through, e.g. sockets that were
+//! received through systemd or launchd socket activation. Other `"inherit"`
+//! entries are ignored like before.
+
+use std::collections::HashMap;
+
+#[cfg(unix)]
+pub type RawInheritedHandle = std::os::fd::RawFd;
+#[cfg(windows)]
+pub type RawInheritedHandle = std::os::windows::io::RawHandle;
+
+/// The fds which children may inherit, keyed by the fd number they get in the
+/// child. Embedders put this table into the `OpState`, and have to keep the
+/// handles open for as long as it is there.
+#[derive(Clone, Default)]
+pub struct InheritedFdsTable {
+  handles: HashMap<u32, RawInheritedHandle>,
+}
+
+impl InheritedFdsTable {
+  /// Allows children to inherit `handle` as `fd`. On unix `handle` is usually
+  /// the fd itself. `fd` must not be one of stdin, stdout and stderr.
+  pub fn insert(&mut self, fd: u32, handle: RawInheritedHandle) {
+    assert!(fd > 2, "stdio can't be added to the inherited fds");
+    self.handles.insert(fd, handle);
+  }
+
+  pub fn remove(&mut self, fd: u32) -> Option<RawInheritedHandle> {
+    self.handles.remove(&fd)
+  }
+
+  pub fn get(&self, fd: u32) -> Option<RawInheritedHandle> {
+    self.handles.get(&fd).copied()
+  }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn insert_and_remove() {
+    let mut table = InheritedFdsTable::default();
+    assert_eq!(table.get(3), None);
+    table.insert(3, 7);
+    assert_eq!(table.get(3), Some(7));
+    assert_eq!(table.remove(3), Some(7));
+    assert_eq!(table.get(3), None);
+  }
+}
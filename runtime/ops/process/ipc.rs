@@ -0,0 +1,230 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! The message channel between a parent and a child spawned with
+//! `Deno.Command`'s `ipc` option.
+//!
+//! Messages are serialized in JS and sent over a bidirectional pipe, each one
+//! prefixed with its length as a little endian `u32`, which is at most
+//! `MAX_MESSAGE_LEN`. The child finds its end of the pipe through the
+//! `DENO_IPC_FD` and `DENO_IPC_SERIALIZATION` environment variables.
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use deno_core::op2;
+use deno_core::AsyncRefCell;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::JsBuffer;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_core::ToJsBuffer;
+use deno_io::BiPipe;
+use deno_io::BiPipeRead;
+use deno_io::BiPipeWrite;
+use serde::Deserialize;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+
+use super::UNSTABLE_FEATURE_NAME;
+
+pub const FD_ENV_VAR: &str = "DENO_IPC_FD";
+pub const SERIALIZATION_ENV_VAR: &str = "DENO_IPC_SERIALIZATION";
+
+/// Longer messages are rejected on both ends, so that the other end can't
+/// make this one allocate arbitrary amounts of memory by sending a length.
+const MAX_MESSAGE_LEN: u32 = 256 * 1024 * 1024;
+
+/// How messages are serialized, which both ends have to agree on.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpcSerialization {
+  /// `JSON.stringify()`, compatible with any other program.
+  Json,
+  /// The structured clone algorithm, for Deno to Deno channels.
+  Advanced,
+}
+
+impl IpcSerialization {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Json => "json",
+      Self::Advanced => "advanced",
+    }
+  }
+}
+
+/// The end of the channel a child was spawned with, taken from the
+/// environment on startup.
+#[derive(Clone, Debug)]
+pub struct ParentIpcChannel {
+  pub fd: i64,
+  pub serialization: IpcSerialization,
+}
+
+impl ParentIpcChannel {
+  /// Reads the channel from the environment variables set by the parent, and
+  /// removes them so that they aren't inherited by the children of this
+  /// process.
+  pub fn from_env() -> Option<Self> {
+    let fd = std::env::var(FD_ENV_VAR).ok()?;
+    let serialization = std::env::var(SERIALIZATION_ENV_VAR).ok();
+    std::env::remove_var(FD_ENV_VAR);
+    std::env::remove_var(SERIALIZATION_ENV_VAR);
+    let serialization = match serialization.as_deref() {
+      Some("advanced") => IpcSerialization::Advanced,
+      _ => IpcSerialization::Json,
+    };
+    let fd = fd.parse().ok()?;
+    disable_inheritance(fd);
+    Some(Self { fd, serialization })
+  }
+}
+
+/// Keeps the children of this process from inheriting the channel, which would
+/// keep it open after this process exited.
+pub(super) fn disable_inheritance(fd: i64) {
+  #[cfg(unix)]
+  // SAFETY: libc call, an invalid fd is reported as an error
+  unsafe {
+    libc::fcntl(fd as i32, libc::F_SETFD, libc::FD_CLOEXEC);
+  }
+  #[cfg(windows)]
+  // SAFETY: winapi call, an invalid handle is reported as an error
+  unsafe {
+    winapi::um::handleapi::SetHandleInformation(
+      fd as _,
+      winapi::um::winbase::HANDLE_FLAG_INHERIT,
+      0,
+    );
+  }
+}
+
+pub struct IpcChannelResource {
+  read_half: AsyncRefCell<BiPipeRead>,
+  write_half: AsyncRefCell<BiPipeWrite>,
+  cancel: CancelHandle,
+}
+
+impl Resource for IpcChannelResource {
+  fn name(&self) -> Cow<str> {
+    "ipcChannel".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.cancel.cancel();
+  }
+}
+
+impl IpcChannelResource {
+  pub fn new(raw: deno_io::RawBiPipeHandle) -> Result<Self, std::io::Error> {
+    let (read_half, write_half) = BiPipe::from_raw(raw)?.split();
+    Ok(Self {
+      read_half: AsyncRefCell::new(read_half),
+      write_half: AsyncRefCell::new(write_half),
+      cancel: Default::default(),
+    })
+  }
+
+  async fn send(self: Rc<Self>, msg: &[u8]) -> Result<(), AnyError> {
+    let len = u32::try_from(msg.len())
+      .ok()
+      .filter(|len| *len <= MAX_MESSAGE_LEN)
+      .ok_or_else(|| type_error("IPC message is too large"))?;
+    let mut write_half = RcRef::map(self, |r| &r.write_half).borrow_mut().await;
+    write_half.write_all(&len.to_le_bytes()).await?;
+    write_half.write_all(msg).await?;
+    Ok(())
+  }
+
+  /// Returns `None` once the other end was closed.
+  async fn recv(self: Rc<Self>) -> Result<Option<Vec<u8>>, AnyError> {
+    let cancel = RcRef::map(&self, |r| &r.cancel);
+    let mut read_half = RcRef::map(&self, |r| &r.read_half).borrow_mut().await;
+    let fut = async {
+      let mut len = [0; 4];
+      match read_half.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+          return Ok(None)
+        }
+        Err(err) => return Err(err),
+      }
+      let len = u32::from_le_bytes(len);
+      if len > MAX_MESSAGE_LEN {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidData,
+          format!("IPC message of {len} bytes exceeds the maximum size"),
+        ));
+      }
+      let mut msg = vec![0; len as usize];
+      read_half.read_exact(&mut msg).await?;
+      Ok(Some(msg))
+    };
+    Ok(fut.or_cancel(cancel).await??)
+  }
+}
+
+/// Opens the channel to the parent, if this process was spawned with one.
+/// Returns `None` on subsequent calls.
+#[op2]
+#[serde]
+pub fn op_ipc_channel_parent(
+  state: &mut OpState,
+) -> Result<Option<(ResourceId, IpcSerialization)>, AnyError> {
+  super::super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.ipc");
+  let Some(channel) = state.try_take::<ParentIpcChannel>() else {
+    return Ok(None);
+  };
+  let resource = IpcChannelResource::new(channel.fd as _)?;
+  let rid = state.resource_table.add(resource);
+  Ok(Some((rid, channel.serialization)))
+}
+
+#[op2(async)]
+pub async fn op_ipc_channel_send(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  #[buffer] msg: JsBuffer,
+) -> Result<(), AnyError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<IpcChannelResource>(rid)?;
+  resource.send(&msg).await
+}
+
+#[op2(async)]
+#[serde]
+pub async fn op_ipc_channel_recv(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+) -> Result<Option<ToJsBuffer>, AnyError> {
+  let resource = state
+    .borrow()
+    .resource_table
+    .get::<IpcChannelResource>(rid)?;
+  Ok(resource.recv().await?.map(ToJsBuffer::from))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn recv_rejects_oversized_messages() {
+    let (fd1, fd2) = deno_io::bi_pipe_pair_raw().unwrap();
+    let channel = Rc::new(IpcChannelResource::new(fd1).unwrap());
+    let mut other = BiPipe::from_raw(fd2).unwrap();
+    let len = MAX_MESSAGE_LEN + 1;
+    other.write_all(&len.to_le_bytes()).await.unwrap();
+    let err = channel.recv().await.unwrap_err();
+    let err = err.downcast_ref::<std::io::Error>().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+  }
+}
@@ -0,0 +1,41 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! A pidfd refers to a process without being subject to pid reuse, and
+//! becomes readable once the process has exited. One is opened for every
+//! spawned child on Linux.
+
+use std::borrow::Cow;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+use std::rc::Rc;
+
+use deno_core::Resource;
+use deno_core::ResourceHandle;
+
+pub struct PidFdResource(OwnedFd);
+
+impl Resource for PidFdResource {
+  fn name(&self) -> Cow<str> {
+    "pidfd".into()
+  }
+
+  fn backing_handle(self: Rc<Self>) -> Option<ResourceHandle> {
+    Some(ResourceHandle::from_fd_like(&self.0))
+  }
+}
+
+/// Opens a pidfd for `pid`, which has to be a child that wasn't reaped yet so
+/// that the pid can't have been reused. Returns `None` on kernels without
+/// pidfd support.
+pub fn open(pid: u32) -> Option<PidFdResource> {
+  // SAFETY: libc call, `pidfd_open` takes a pid and flags
+  let fd =
+    unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+  if fd < 0 {
+    return None;
+  }
+  // SAFETY: `pidfd_open` returned a new fd, which is owned from now on. It is
+  // created with `O_CLOEXEC`, so children don't inherit it.
+  let fd = unsafe { OwnedFd::from_raw_fd(fd as _) };
+  Some(PidFdResource(fd))
+}
@@ -0,0 +1,353 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Pseudo-terminal support for `Deno.Command`'s `pty` option.
+
+use deno_core::error::AnyError;
+use deno_core::op2;
+use deno_core::OpState;
+use deno_core::ResourceId;
+use serde::Deserialize;
+
+#[derive(Copy, Clone, Deserialize)]
+pub struct PtySize {
+  rows: u16,
+  cols: u16,
+}
+
+#[cfg(unix)]
+pub use unix::*;
+
+#[cfg(unix)]
+mod unix {
+  use super::PtySize;
+  use deno_core::error::AnyError;
+  use deno_core::AsyncResult;
+  use deno_core::CancelHandle;
+  use deno_core::CancelTryFuture;
+  use deno_core::RcRef;
+  use deno_core::Resource;
+  use std::borrow::Cow;
+  use std::os::fd::AsRawFd;
+  use std::os::fd::OwnedFd;
+  use std::os::unix::process::CommandExt;
+  use std::rc::Rc;
+  use tokio::io::unix::AsyncFd;
+
+  /// The controlling (master) side of a pseudo-terminal allocated for a
+  /// child process. Reading yields everything the child writes to its
+  /// terminal; writing feeds the child's terminal input.
+  pub struct PtyResource {
+    master: AsyncFd<OwnedFd>,
+    cancel: CancelHandle,
+  }
+
+  impl Resource for PtyResource {
+    fn name(&self) -> Cow<str> {
+      "pty".into()
+    }
+
+    fn close(self: Rc<Self>) {
+      self.cancel.cancel();
+    }
+
+    fn backing_handle(self: Rc<Self>) -> Option<deno_core::ResourceHandle> {
+      Some(deno_core::ResourceHandle::from_fd_like(
+        self.master.get_ref(),
+      ))
+    }
+
+    deno_core::impl_readable_byob!();
+    deno_core::impl_writable!();
+  }
+
+  impl PtyResource {
+    pub async fn read(
+      self: Rc<Self>,
+      data: &mut [u8],
+    ) -> Result<usize, AnyError> {
+      let cancel_handle = RcRef::map(&self, |r| &r.cancel);
+      let fut = async {
+        loop {
+          let mut guard = self.master.readable().await?;
+          let result = guard.try_io(|fd| {
+            // SAFETY: libc call, `data` is a valid buffer of `data.len()`
+            let nread = unsafe {
+              libc::read(fd.as_raw_fd(), data.as_mut_ptr() as _, data.len())
+            };
+            if nread < 0 {
+              Err(std::io::Error::last_os_error())
+            } else {
+              Ok(nread as usize)
+            }
+          });
+          match result {
+            Ok(Ok(nread)) => return Ok(nread),
+            // Once every slave fd is closed (i.e. the child and all of its
+            // descendants exited) Linux reports EIO instead of EOF.
+            Ok(Err(err)) if err.raw_os_error() == Some(libc::EIO) => {
+              return Ok(0)
+            }
+            Ok(Err(err)) => return Err(err),
+            Err(_would_block) => continue,
+          }
+        }
+      };
+      Ok(fut.try_or_cancel(cancel_handle).await?)
+    }
+
+    pub async fn write(self: Rc<Self>, data: &[u8]) -> Result<usize, AnyError> {
+      loop {
+        let mut guard = self.master.writable().await?;
+        let result = guard.try_io(|fd| {
+          // SAFETY: libc call, `data` is a valid buffer of `data.len()`
+          let nwritten = unsafe {
+            libc::write(fd.as_raw_fd(), data.as_ptr() as _, data.len())
+          };
+          if nwritten < 0 {
+            Err(std::io::Error::last_os_error())
+          } else {
+            Ok(nwritten as usize)
+          }
+        });
+        match result {
+          Ok(result) => return Ok(result?),
+          Err(_would_block) => continue,
+        }
+      }
+    }
+
+    pub fn resize(&self, size: PtySize) -> Result<(), AnyError> {
+      set_window_size(self.master.as_raw_fd(), size)?;
+      Ok(())
+    }
+  }
+
+  fn set_window_size(fd: i32, size: PtySize) -> std::io::Result<()> {
+    let winsize = libc::winsize {
+      ws_row: size.rows,
+      ws_col: size.cols,
+      ws_xpixel: 0,
+      ws_ypixel: 0,
+    };
+    // SAFETY: libc call, `winsize` outlives the call
+    if unsafe { libc::ioctl(fd, libc::TIOCSWINSZ, &winsize) } == -1 {
+      return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+  }
+
+  /// Allocates a new pseudo-terminal of the given size and connects the
+  /// stdin, stdout and stderr of `command` to it. The child is made a session
+  /// leader with the pseudo-terminal as its controlling terminal.
+  pub fn setup(
+    command: &mut std::process::Command,
+    size: PtySize,
+  ) -> Result<PtyResource, AnyError> {
+    let winsize = nix::pty::Winsize {
+      ws_row: size.rows,
+      ws_col: size.cols,
+      ws_xpixel: 0,
+      ws_ypixel: 0,
+    };
+    let pty = nix::pty::openpty(Some(&winsize), None)?;
+    // SAFETY: both fds were just returned by `openpty` and are owned by us
+    let (master, slave) = unsafe {
+      use std::os::fd::FromRawFd;
+      (
+        OwnedFd::from_raw_fd(pty.master),
+        OwnedFd::from_raw_fd(pty.slave),
+      )
+    };
+
+    // SAFETY: libc calls on a valid fd
+    unsafe {
+      let fd = master.as_raw_fd();
+      libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+      let flags = libc::fcntl(fd, libc::F_GETFL);
+      libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+    }
+
+    command.stdin(slave.try_clone()?);
+    command.stdout(slave.try_clone()?);
+    command.stderr(slave);
+
+    // SAFETY: only async-signal-safe libc calls are made in the child
+    unsafe {
+      command.pre_exec(|| {
+        if libc::setsid() == -1 {
+          return Err(std::io::Error::last_os_error());
+        }
+        // stdin was already replaced with the slave side of the pty
+        if libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+          return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+      });
+    }
+
+    Ok(PtyResource {
+      master: AsyncFd::new(master)?,
+      cancel: Default::default(),
+    })
+  }
+}
+
+#[cfg(windows)]
+pub use windows::*;
+
+#[cfg(windows)]
+mod windows {
+  use super::super::create_process;
+  use super::PtySize;
+  use deno_core::error::AnyError;
+  use deno_core::AsyncRefCell;
+  use deno_core::CancelHandle;
+  use deno_core::CancelTryFuture;
+  use deno_core::RcRef;
+  use deno_core::Resource;
+  use std::borrow::Cow;
+  use std::cell::RefCell;
+  use std::os::windows::io::AsRawHandle;
+  use std::rc::Rc;
+  use tokio::io::AsyncReadExt;
+  use tokio::io::AsyncWriteExt;
+  use windows_sys::core::HRESULT;
+  use windows_sys::Win32::System::Console::ClosePseudoConsole;
+  use windows_sys::Win32::System::Console::CreatePseudoConsole;
+  use windows_sys::Win32::System::Console::ResizePseudoConsole;
+  use windows_sys::Win32::System::Console::COORD;
+  use windows_sys::Win32::System::Console::HPCON;
+
+  /// A pseudo console (ConPTY), closed on drop.
+  struct PseudoConsole(HPCON);
+
+  impl Drop for PseudoConsole {
+    fn drop(&mut self) {
+      // SAFETY: winapi call, the console is valid
+      unsafe { ClosePseudoConsole(self.0) };
+    }
+  }
+
+  /// The pseudo console allocated for a child process. Reading yields
+  /// everything the child writes to its console, encoded as VT sequences;
+  /// writing feeds the child's console input.
+  pub struct PtyResource {
+    input: AsyncRefCell<tokio::process::ChildStdin>,
+    output: AsyncRefCell<tokio::process::ChildStdout>,
+    cancel: CancelHandle,
+    /// Declared after `output`, so that the output pipe is closed before the
+    /// console on drop. Closing the console blocks while it has output left
+    /// that wasn't read otherwise.
+    console: RefCell<Option<PseudoConsole>>,
+  }
+
+  impl Resource for PtyResource {
+    fn name(&self) -> Cow<str> {
+      "pty".into()
+    }
+
+    fn close(self: Rc<Self>) {
+      self.cancel.cancel();
+    }
+
+    deno_core::impl_readable_byob!();
+    deno_core::impl_writable!();
+  }
+
+  impl PtyResource {
+    pub async fn read(
+      self: Rc<Self>,
+      data: &mut [u8],
+    ) -> Result<usize, AnyError> {
+      let mut output = RcRef::map(&self, |r| &r.output).borrow_mut().await;
+      let cancel_handle = RcRef::map(&self, |r| &r.cancel);
+      Ok(output.read(data).try_or_cancel(cancel_handle).await?)
+    }
+
+    pub async fn write(self: Rc<Self>, data: &[u8]) -> Result<usize, AnyError> {
+      let mut input = RcRef::map(&self, |r| &r.input).borrow_mut().await;
+      Ok(input.write(data).await?)
+    }
+
+    pub fn resize(&self, size: PtySize) -> Result<(), AnyError> {
+      // nothing to resize once the child has exited
+      if let Some(console) = &*self.console.borrow() {
+        // SAFETY: winapi call, the console is valid
+        check(unsafe { ResizePseudoConsole(console.0, coord(size)) })?;
+      }
+      Ok(())
+    }
+
+    /// The console to attach the child to, see `create_process::spawn`.
+    pub fn console(&self) -> Option<HPCON> {
+      self.console.borrow().as_ref().map(|console| console.0)
+    }
+
+    /// Closes the console once the child has exited. Unlike a pty on unix,
+    /// its output only ends once the console is closed. This is done on
+    /// another thread, as closing blocks until the remaining output was read.
+    pub fn close_console(&self) {
+      if let Some(console) = self.console.borrow_mut().take() {
+        std::thread::spawn(move || drop(console));
+      }
+    }
+  }
+
+  fn coord(size: PtySize) -> COORD {
+    COORD {
+      X: size.cols as i16,
+      Y: size.rows as i16,
+    }
+  }
+
+  fn check(result: HRESULT) -> std::io::Result<()> {
+    if result < 0 {
+      return Err(std::io::Error::from_raw_os_error(result));
+    }
+    Ok(())
+  }
+
+  /// Allocates a new pseudo console of the given size. The child is attached
+  /// to it in place of its stdio once it is spawned by
+  /// `create_process::spawn`, which `command` is left to.
+  pub fn setup(
+    _command: &mut std::process::Command,
+    size: PtySize,
+  ) -> Result<PtyResource, AnyError> {
+    let (console_input, input) = create_process::pipe()?;
+    let (output, console_output) = create_process::pipe()?;
+    let mut console = 0;
+    // SAFETY: winapi call, the console duplicates the pipe handles it is
+    // given, so they are closed again by us
+    check(unsafe {
+      CreatePseudoConsole(
+        coord(size),
+        console_input.as_raw_handle() as _,
+        console_output.as_raw_handle() as _,
+        0,
+        &mut console,
+      )
+    })?;
+    let console = PseudoConsole(console);
+
+    let input = std::process::ChildStdin::from(input);
+    let output = std::process::ChildStdout::from(output);
+    Ok(PtyResource {
+      input: AsyncRefCell::new(tokio::process::ChildStdin::from_std(input)?),
+      output: AsyncRefCell::new(tokio::process::ChildStdout::from_std(output)?),
+      cancel: Default::default(),
+      console: RefCell::new(Some(console)),
+    })
+  }
+}
+
+#[op2(fast)]
+pub fn op_pty_resize(
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+  #[smi] rows: u16,
+  #[smi] cols: u16,
+) -> Result<(), AnyError> {
+  let resource = state.resource_table.get::<PtyResource>(rid)?;
+  resource.resize(PtySize { rows, cols })
+}
@@ -0,0 +1,88 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Reaping children whose resource was dropped before they were waited for,
+//! e.g. because the worker that spawned them was terminated.
+//!
+//! Such children are killed, but tokio only reaps them on a best effort basis
+//! while the runtime that spawned them is still alive, so they could otherwise
+//! pile up as zombies in long running processes. On unix the children are
+//! instead handed to a background thread which waits for them. Windows has no
+//! zombies, closing the process handle is all that's needed there.
+
+#[cfg(unix)]
+use std::sync::Condvar;
+#[cfg(unix)]
+use std::sync::Mutex;
+#[cfg(unix)]
+use std::sync::Once;
+#[cfg(unix)]
+use std::time::Duration;
+
+#[cfg(unix)]
+static ORPHANS: Mutex<Vec<tokio::process::Child>> = Mutex::new(Vec::new());
+#[cfg(unix)]
+static ORPHAN_ADDED: Condvar = Condvar::new();
+
+/// Kills the child if it is still running, and makes sure that it is reaped.
+pub(super) fn adopt(mut child: tokio::process::Child) {
+  // fails if it was already reaped
+  let _ = child.start_kill();
+
+  // The child stays owned by the thread until it is reaped through it, so that
+  // tokio doesn't queue it as an orphan, and it is only dropped, along with
+  // its registration with the runtime, once it has exited.
+  #[cfg(unix)]
+  if matches!(child.try_wait(), Ok(None)) {
+    static START: Once = Once::new();
+    START.call_once(|| {
+      std::thread::Builder::new()
+        .name("deno-child-reaper".to_string())
+        .spawn(reap_orphans)
+        .expect("failed to spawn the child reaper thread");
+    });
+    ORPHANS.lock().unwrap().push(child);
+    ORPHAN_ADDED.notify_one();
+  }
+}
+
+#[cfg(unix)]
+fn reap_orphans() {
+  let mut orphans = ORPHANS.lock().unwrap();
+  loop {
+    // keep waiting while it is still running, errors mean that it is gone
+    orphans.retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+    orphans = if orphans.is_empty() {
+      ORPHAN_ADDED.wait(orphans).unwrap()
+    } else {
+      // children were sent `SIGKILL`, so they exit shortly
+      ORPHAN_ADDED
+        .wait_timeout(orphans, Duration::from_millis(50))
+        .unwrap()
+        .0
+    };
+  }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn reaps_adopted_children() {
+    let child = tokio::process::Command::new("sleep")
+      .arg("60")
+      .spawn()
+      .unwrap();
+    let pid = child.id().unwrap() as libc::pid_t;
+    adopt(child);
+    for _ in 0..100 {
+      // a zombie can still be signaled, so this fails once it was reaped
+      // SAFETY: libc call
+      if unsafe { libc::kill(pid, 0) } == -1 {
+        return;
+      }
+      std::thread::sleep(Duration::from_millis(20));
+    }
+    panic!("the child was not reaped");
+  }
+}
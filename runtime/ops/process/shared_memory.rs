@@ -0,0 +1,625 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Named shared memory segments, exposed to JS as `SharedArrayBuffer`s.
+//!
+//! A segment is a POSIX shared memory object on unix and a file mapping
+//! backed by the paging file on Windows. Its memory starts with a header that
+//! identifies it as a segment created by Deno and holds its size, followed by
+//! the data that JS sees.
+//!
+//! Children spawned with a segment in the `sharedMemory` option of
+//! `Deno.Command` inherit it as an fd or handle, which is passed to them
+//! together with the name of the segment through [`ENV_VAR`]. Opening the
+//! segment by its name in the child uses the inherited fd, so that it works
+//! even after the parent closed the segment. Other processes can open a
+//! segment by its name while the process that created it keeps it open.
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use deno_core::op2;
+use deno_core::v8;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::UNSTABLE_FEATURE_NAME;
+
+/// The segments a child inherits, as `name:fd` pairs separated by commas.
+pub const ENV_VAR: &str = "DENO_SHARED_MEMORY";
+
+const NAME_PREFIX: &str = "deno-";
+/// The number of random hex digits in a name. macOS limits the names of
+/// shared memory objects to 31 characters, including the leading slash.
+const NAME_RANDOM_LEN: usize = 20;
+
+const MAGIC: &[u8; 8] = b"DENOSHM1";
+/// The size of the header, which keeps the data aligned for any typed array.
+const HEADER_SIZE: usize = 64;
+
+fn is_valid_name(name: &str) -> bool {
+  name.strip_prefix(NAME_PREFIX).is_some_and(|random| {
+    random.len() == NAME_RANDOM_LEN
+      && random
+        .bytes()
+        .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+  })
+}
+
+fn invalid_segment() -> AnyError {
+  type_error("The shared memory segment is invalid")
+}
+
+/// The segments this process inherited from its parent, keyed by name. Read
+/// from the environment the first time a segment is opened.
+fn inherited() -> &'static HashMap<String, i64> {
+  static INHERITED: OnceLock<HashMap<String, i64>> = OnceLock::new();
+  INHERITED.get_or_init(|| {
+    let Ok(value) = std::env::var(ENV_VAR) else {
+      return HashMap::new();
+    };
+    value
+      .split(',')
+      .filter_map(|entry| {
+        let (name, handle) = entry.split_once(':')?;
+        let handle = handle.parse().ok()?;
+        super::ipc::disable_inheritance(handle);
+        Some((name.to_string(), handle))
+      })
+      .collect()
+  })
+}
+
+/// The memory of a segment that is mapped into this process. Unmapped once
+/// the `SharedArrayBuffer`s that use it were collected.
+struct Mapping {
+  ptr: *mut u8,
+  len: usize,
+  /// The size of the data, once it was written to or read from the header.
+  size: usize,
+}
+
+impl Mapping {
+  fn header(&self) -> &[u8] {
+    // SAFETY: mappings are at least `HEADER_SIZE` bytes long
+    unsafe { std::slice::from_raw_parts(self.ptr, HEADER_SIZE) }
+  }
+
+  fn write_header(&mut self, size: usize) {
+    // SAFETY: mappings are at least `HEADER_SIZE` bytes long
+    let header =
+      unsafe { std::slice::from_raw_parts_mut(self.ptr, HEADER_SIZE) };
+    header[..8].copy_from_slice(MAGIC);
+    header[8..16].copy_from_slice(&(size as u64).to_le_bytes());
+    self.size = size;
+  }
+
+  /// Checks the header of a mapping of the whole segment, which might have
+  /// been rounded up to whole pages.
+  fn read_header(&mut self) -> Result<(), AnyError> {
+    let header = self.header();
+    if &header[..8] != MAGIC {
+      return Err(invalid_segment());
+    }
+    let size = u64::from_le_bytes(header[8..16].try_into().unwrap());
+    match usize::try_from(size) {
+      Ok(size) if size > 0 && size <= self.len - HEADER_SIZE => {
+        self.size = size;
+        Ok(())
+      }
+      _ => Err(invalid_segment()),
+    }
+  }
+}
+
+impl AsMut<[u8]> for Mapping {
+  fn as_mut(&mut self) -> &mut [u8] {
+    // SAFETY: the mapping stays valid until it is dropped
+    unsafe {
+      std::slice::from_raw_parts_mut(self.ptr.add(HEADER_SIZE), self.size)
+    }
+  }
+}
+
+pub struct SharedMemoryResource {
+  name: String,
+  segment: imp::Segment,
+  store: v8::SharedRef<v8::BackingStore>,
+  /// Set for the resource of the process that created the segment, which
+  /// removes its name once the resource is closed.
+  owner: bool,
+}
+
+impl Resource for SharedMemoryResource {
+  fn name(&self) -> Cow<str> {
+    "sharedMemory".into()
+  }
+}
+
+impl Drop for SharedMemoryResource {
+  fn drop(&mut self) {
+    if self.owner {
+      imp::unlink(&self.name);
+    }
+  }
+}
+
+impl SharedMemoryResource {
+  fn new(
+    name: String,
+    segment: imp::Segment,
+    mapping: Mapping,
+    owner: bool,
+  ) -> Self {
+    let store =
+      v8::SharedArrayBuffer::new_backing_store_from_bytes(Box::new(mapping))
+        .make_shared();
+    Self {
+      name,
+      segment,
+      store,
+      owner,
+    }
+  }
+}
+
+/// Creates a segment of `size` bytes, which are zeroed. Returns its resource
+/// and its name.
+#[op2]
+#[serde]
+pub fn op_shared_memory_create(
+  state: &mut OpState,
+  #[number] size: u64,
+) -> Result<(ResourceId, String), AnyError> {
+  super::super::check_unstable(
+    state,
+    UNSTABLE_FEATURE_NAME,
+    "Deno.createSharedMemory",
+  );
+  let size = match usize::try_from(size) {
+    Ok(size) if size > 0 && size <= isize::MAX as usize - HEADER_SIZE => size,
+    _ => return Err(type_error(format!("Invalid shared memory size: {size}"))),
+  };
+  let random = uuid::Uuid::new_v4().simple().to_string();
+  let name = format!("{NAME_PREFIX}{}", &random[..NAME_RANDOM_LEN]);
+  let (segment, mut mapping) = imp::create(&name, HEADER_SIZE + size)?;
+  mapping.write_header(size);
+  let resource =
+    SharedMemoryResource::new(name.clone(), segment, mapping, true);
+  Ok((state.resource_table.add(resource), name))
+}
+
+/// Opens the segment named `name`, which was either inherited from the parent
+/// or is kept open by another process.
+#[op2(fast)]
+#[smi]
+pub fn op_shared_memory_open(
+  state: &mut OpState,
+  #[string] name: String,
+) -> Result<ResourceId, AnyError> {
+  super::super::check_unstable(
+    state,
+    UNSTABLE_FEATURE_NAME,
+    "Deno.openSharedMemory",
+  );
+  // Only segments created by Deno can be opened, not arbitrary objects.
+  if !is_valid_name(&name) {
+    return Err(type_error(format!(
+      "Invalid shared memory segment name: {name}"
+    )));
+  }
+  let (segment, mut mapping) = match inherited().get(&name) {
+    Some(&handle) => imp::open_inherited(handle)?,
+    None => imp::open(&name)?,
+  };
+  mapping.read_header()?;
+  let resource = SharedMemoryResource::new(name, segment, mapping, false);
+  Ok(state.resource_table.add(resource))
+}
+
+/// A new `SharedArrayBuffer` for the data of a segment.
+#[op2]
+pub fn op_shared_memory_buffer<'scope>(
+  scope: &mut v8::HandleScope<'scope>,
+  state: &mut OpState,
+  #[smi] rid: ResourceId,
+) -> Result<v8::Local<'scope, v8::SharedArrayBuffer>, AnyError> {
+  let resource = state.resource_table.get::<SharedMemoryResource>(rid)?;
+  Ok(v8::SharedArrayBuffer::with_backing_store(
+    scope,
+    &resource.store,
+  ))
+}
+
+/// Prepares the segments `rids` to be inherited by a child. Returns the fds
+/// that are to be duplicated to the target fds in the child, which start at
+/// `first_fd`, and the value of [`ENV_VAR`]. The returned fds are duplicates
+/// of at least `min_fd`, which the caller closes once the child was spawned.
+#[cfg(unix)]
+pub fn inherit(
+  state: &OpState,
+  rids: &[ResourceId],
+  first_fd: i32,
+  min_fd: i32,
+) -> Result<(Vec<(i32, i32)>, String), AnyError> {
+  use std::os::fd::AsRawFd;
+  use std::os::fd::FromRawFd;
+  use std::os::fd::IntoRawFd;
+  use std::os::fd::OwnedFd;
+
+  // Owned until all fds were duplicated, so that they are closed on errors.
+  let mut fds = Vec::with_capacity(rids.len());
+  let mut entries = Vec::with_capacity(rids.len());
+  for (&rid, target) in rids.iter().zip(first_fd..) {
+    let resource = state.resource_table.get::<SharedMemoryResource>(rid)?;
+    // SAFETY: libc call, the fd is kept open by the resource
+    let fd = unsafe {
+      libc::fcntl(
+        resource.segment.fd.as_raw_fd(),
+        libc::F_DUPFD_CLOEXEC,
+        min_fd,
+      )
+    };
+    if fd == -1 {
+      return Err(std::io::Error::last_os_error().into());
+    }
+    // SAFETY: the fd was just duplicated
+    fds.push((unsafe { OwnedFd::from_raw_fd(fd) }, target));
+    entries.push(format!("{}:{target}", resource.name));
+  }
+  let fds = fds
+    .into_iter()
+    .map(|(fd, target)| (fd.into_raw_fd(), target))
+    .collect();
+  Ok((fds, entries.join(",")))
+}
+
+/// Prepares the segments `rids` to be inherited by a child. Returns the
+/// duplicates of their handles for the child, which the caller closes once it
+/// was spawned, and the value of [`ENV_VAR`].
+#[cfg(windows)]
+pub fn inherit(
+  state: &OpState,
+  rids: &[ResourceId],
+) -> Result<(Vec<std::os::windows::io::RawHandle>, String), AnyError> {
+  use std::os::windows::io::AsRawHandle;
+  use std::os::windows::io::IntoRawHandle;
+
+  let mut handles = Vec::with_capacity(rids.len());
+  let mut entries = Vec::with_capacity(rids.len());
+  for &rid in rids {
+    let resource = state.resource_table.get::<SharedMemoryResource>(rid)?;
+    let handle = imp::duplicate(resource.segment.handle.as_raw_handle())?;
+    entries.push(format!(
+      "{}:{}",
+      resource.name,
+      handle.as_raw_handle() as usize
+    ));
+    handles.push(handle);
+  }
+  let handles = handles
+    .into_iter()
+    .map(|handle| handle.into_raw_handle())
+    .collect();
+  Ok((handles, entries.join(",")))
+}
+
+#[cfg(unix)]
+mod imp {
+  use deno_core::error::AnyError;
+  use std::ffi::CString;
+  use std::io;
+  use std::os::fd::AsRawFd;
+  use std::os::fd::FromRawFd;
+  use std::os::fd::OwnedFd;
+
+  use super::Mapping;
+  use super::HEADER_SIZE;
+
+  pub struct Segment {
+    pub fd: OwnedFd,
+  }
+
+  fn path(name: &str) -> CString {
+    CString::new(format!("/{name}")).unwrap()
+  }
+
+  fn map(fd: &OwnedFd, len: usize) -> io::Result<Mapping> {
+    // SAFETY: libc call, the result is checked
+    let ptr = unsafe {
+      libc::mmap(
+        std::ptr::null_mut(),
+        len,
+        libc::PROT_READ | libc::PROT_WRITE,
+        libc::MAP_SHARED,
+        fd.as_raw_fd(),
+        0,
+      )
+    };
+    if ptr == libc::MAP_FAILED {
+      return Err(io::Error::last_os_error());
+    }
+    Ok(Mapping {
+      ptr: ptr as *mut u8,
+      len,
+      size: 0,
+    })
+  }
+
+  /// Maps the whole segment of `fd`.
+  fn map_all(fd: OwnedFd) -> Result<(Segment, Mapping), AnyError> {
+    // SAFETY: `stat` is written by `fstat`
+    let stat = unsafe {
+      let mut stat = std::mem::zeroed::<libc::stat>();
+      if libc::fstat(fd.as_raw_fd(), &mut stat) == -1 {
+        return Err(io::Error::last_os_error().into());
+      }
+      stat
+    };
+    match usize::try_from(stat.st_size) {
+      Ok(len) if len > HEADER_SIZE => {
+        let mapping = map(&fd, len)?;
+        Ok((Segment { fd }, mapping))
+      }
+      _ => Err(super::invalid_segment()),
+    }
+  }
+
+  pub fn create(
+    name: &str,
+    len: usize,
+  ) -> Result<(Segment, Mapping), AnyError> {
+    let path = path(name);
+    #[cfg(target_vendor = "apple")]
+    let mode = 0o600 as libc::c_uint;
+    #[cfg(not(target_vendor = "apple"))]
+    let mode = 0o600 as libc::mode_t;
+    // SAFETY: libc call, the result is checked. The new fd has `FD_CLOEXEC`
+    // set.
+    let fd = unsafe {
+      libc::shm_open(
+        path.as_ptr(),
+        libc::O_RDWR | libc::O_CREAT | libc::O_EXCL,
+        mode,
+      )
+    };
+    if fd == -1 {
+      return Err(io::Error::last_os_error().into());
+    }
+    // SAFETY: the fd was just opened
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    let result = (|| {
+      let size = libc::off_t::try_from(len)
+        .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+      // SAFETY: libc call, the result is checked
+      if unsafe { libc::ftruncate(fd.as_raw_fd(), size) } == -1 {
+        return Err(io::Error::last_os_error());
+      }
+      map(&fd, len)
+    })();
+    match result {
+      Ok(mapping) => Ok((Segment { fd }, mapping)),
+      Err(err) => {
+        unlink(name);
+        Err(err.into())
+      }
+    }
+  }
+
+  pub fn open(name: &str) -> Result<(Segment, Mapping), AnyError> {
+    let path = path(name);
+    // SAFETY: libc call, the result is checked
+    let fd = unsafe { libc::shm_open(path.as_ptr(), libc::O_RDWR, 0) };
+    if fd == -1 {
+      return Err(io::Error::last_os_error().into());
+    }
+    // SAFETY: the fd was just opened
+    map_all(unsafe { OwnedFd::from_raw_fd(fd) })
+  }
+
+  pub fn open_inherited(fd: i64) -> Result<(Segment, Mapping), AnyError> {
+    // The inherited fd is duplicated, so that the segment can be opened again
+    // after this resource was closed.
+    // SAFETY: libc call, an invalid fd is reported as an error
+    let fd = unsafe { libc::fcntl(fd as i32, libc::F_DUPFD_CLOEXEC, 0) };
+    if fd == -1 {
+      return Err(io::Error::last_os_error().into());
+    }
+    // SAFETY: the fd was just duplicated
+    map_all(unsafe { OwnedFd::from_raw_fd(fd) })
+  }
+
+  pub fn unlink(name: &str) {
+    let path = path(name);
+    // SAFETY: libc call
+    unsafe { libc::shm_unlink(path.as_ptr()) };
+  }
+
+  impl Drop for Mapping {
+    fn drop(&mut self) {
+      // SAFETY: the memory was mapped by `map`
+      unsafe { libc::munmap(self.ptr as *mut _, self.len) };
+    }
+  }
+}
+
+#[cfg(windows)]
+mod imp {
+  use deno_core::error::AnyError;
+  use std::io;
+  use std::os::windows::ffi::OsStrExt;
+  use std::os::windows::io::AsRawHandle;
+  use std::os::windows::io::FromRawHandle;
+  use std::os::windows::io::OwnedHandle;
+  use std::os::windows::io::RawHandle;
+  use winapi::shared::minwindef::FALSE;
+  use winapi::shared::winerror::ERROR_ALREADY_EXISTS;
+  use winapi::um::handleapi::DuplicateHandle;
+  use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+  use winapi::um::memoryapi::CreateFileMappingW;
+  use winapi::um::memoryapi::MapViewOfFile;
+  use winapi::um::memoryapi::OpenFileMappingW;
+  use winapi::um::memoryapi::UnmapViewOfFile;
+  use winapi::um::memoryapi::VirtualQuery;
+  use winapi::um::memoryapi::FILE_MAP_ALL_ACCESS;
+  use winapi::um::processthreadsapi::GetCurrentProcess;
+  use winapi::um::winnt::DUPLICATE_SAME_ACCESS;
+  use winapi::um::winnt::MEMORY_BASIC_INFORMATION;
+  use winapi::um::winnt::PAGE_READWRITE;
+
+  use super::Mapping;
+  use super::HEADER_SIZE;
+
+  pub struct Segment {
+    pub handle: OwnedHandle,
+  }
+
+  fn path(name: &str) -> Vec<u16> {
+    std::ffi::OsStr::new(&format!("Local\\{name}"))
+      .encode_wide()
+      .chain(Some(0))
+      .collect()
+  }
+
+  /// Maps the whole segment of `handle`.
+  fn map(handle: OwnedHandle) -> Result<(Segment, Mapping), AnyError> {
+    // SAFETY: winapi calls, the results are checked
+    let (ptr, len) = unsafe {
+      let ptr = MapViewOfFile(
+        handle.as_raw_handle() as _,
+        FILE_MAP_ALL_ACCESS,
+        0,
+        0,
+        0,
+      );
+      if ptr.is_null() {
+        return Err(io::Error::last_os_error().into());
+      }
+      let mut info = std::mem::zeroed::<MEMORY_BASIC_INFORMATION>();
+      let size = std::mem::size_of::<MEMORY_BASIC_INFORMATION>();
+      if VirtualQuery(ptr, &mut info, size) == 0 {
+        let err = io::Error::last_os_error();
+        UnmapViewOfFile(ptr);
+        return Err(err.into());
+      }
+      (ptr as *mut u8, info.RegionSize)
+    };
+    let mapping = Mapping { ptr, len, size: 0 };
+    if len <= HEADER_SIZE {
+      return Err(super::invalid_segment());
+    }
+    Ok((Segment { handle }, mapping))
+  }
+
+  pub fn create(
+    name: &str,
+    len: usize,
+  ) -> Result<(Segment, Mapping), AnyError> {
+    let path = path(name);
+    let len = len as u64;
+    // SAFETY: winapi calls, the result is checked
+    let handle = unsafe {
+      let handle = CreateFileMappingW(
+        INVALID_HANDLE_VALUE,
+        std::ptr::null_mut(),
+        PAGE_READWRITE,
+        (len >> 32) as u32,
+        len as u32,
+        path.as_ptr(),
+      );
+      if handle.is_null() {
+        return Err(io::Error::last_os_error().into());
+      }
+      let handle = OwnedHandle::from_raw_handle(handle as _);
+      // An existing mapping is opened instead, which is never wanted.
+      let err = io::Error::last_os_error();
+      if err.raw_os_error() == Some(ERROR_ALREADY_EXISTS as i32) {
+        return Err(io::Error::from(io::ErrorKind::AlreadyExists).into());
+      }
+      handle
+    };
+    map(handle)
+  }
+
+  pub fn open(name: &str) -> Result<(Segment, Mapping), AnyError> {
+    let path = path(name);
+    // SAFETY: winapi call, the result is checked
+    let handle =
+      unsafe { OpenFileMappingW(FILE_MAP_ALL_ACCESS, FALSE, path.as_ptr()) };
+    if handle.is_null() {
+      return Err(io::Error::last_os_error().into());
+    }
+    // SAFETY: the handle was just opened
+    map(unsafe { OwnedHandle::from_raw_handle(handle as _) })
+  }
+
+  pub fn open_inherited(handle: i64) -> Result<(Segment, Mapping), AnyError> {
+    // The inherited handle is duplicated, so that the segment can be opened
+    // again after this resource was closed.
+    map(duplicate(handle as _)?)
+  }
+
+  pub fn duplicate(handle: RawHandle) -> io::Result<OwnedHandle> {
+    let mut duplicate = std::ptr::null_mut();
+    // SAFETY: winapi calls, an invalid handle is reported as an error
+    let ok = unsafe {
+      let current = GetCurrentProcess();
+      DuplicateHandle(
+        current,
+        handle as _,
+        current,
+        &mut duplicate,
+        0,
+        FALSE,
+        DUPLICATE_SAME_ACCESS,
+      )
+    };
+    if ok == FALSE {
+      return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the handle was just duplicated
+    Ok(unsafe { OwnedHandle::from_raw_handle(duplicate as _) })
+  }
+
+  /// File mappings are removed once their last handle was closed.
+  pub fn unlink(_name: &str) {}
+
+  impl Drop for Mapping {
+    fn drop(&mut self) {
+      // SAFETY: the view was mapped by `map`
+      unsafe { UnmapViewOfFile(self.ptr as _) };
+    }
+  }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn names() {
+    assert!(is_valid_name("deno-0123456789abcdef0123"));
+    assert!(!is_valid_name("deno-0123456789abcdef012"));
+    assert!(!is_valid_name("deno-0123456789ABCDEF0123"));
+    assert!(!is_valid_name("/deno-0123456789abcdef0123"));
+  }
+
+  #[test]
+  fn create_and_open() {
+    let name = "deno-00000000000000000001";
+    let (_segment, mut created) = imp::create(name, HEADER_SIZE + 10).unwrap();
+    created.write_header(10);
+    created.as_mut()[9] = 7;
+    assert!(imp::create(name, HEADER_SIZE + 10).is_err());
+
+    let (_segment, mut opened) = imp::open(name).unwrap();
+    opened.read_header().unwrap();
+    assert_eq!(opened.as_mut().len(), 10);
+    assert_eq!(opened.as_mut()[9], 7);
+
+    imp::unlink(name);
+    assert!(imp::open(name).is_err());
+  }
+}
@@ -0,0 +1,575 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Running commands through a shell for `Deno.Command`'s `shell` option.
+//!
+//! With `shell: true` the command is run in-process by the shell of
+//! `deno task`, which behaves the same on every platform. The shell requires a
+//! `LocalSet`, so each command is executed on a thread of its own, and is
+//! represented by a `ShellChildResource` instead of a `ChildResource`.
+
+use super::check_command;
+use super::collect_output;
+use super::error::ProcessError;
+use super::shared_memory;
+use super::Child;
+use super::ChildPipes;
+use super::ChildStatus;
+use super::SpawnArgs;
+use super::SpawnOutput;
+use super::Stdio;
+use super::StdioOrRid;
+use deno_core::error::bad_resource_id;
+use deno_core::error::custom_error;
+use deno_core::error::generic_error;
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use deno_core::futures::executor::block_on;
+use deno_core::JsBuffer;
+use deno_core::OpState;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_io::fs::FileResource;
+use deno_io::PipeReadResource;
+use deno_io::PipeWriteResource;
+use deno_task_shell::parser::SequentialList;
+use deno_task_shell::ShellPipeReader;
+use deno_task_shell::ShellPipeWriter;
+use deno_task_shell::ShellState;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum ShellOption {
+  /// Use the built-in shell if `true`.
+  Builtin(bool),
+  /// The path to a shell to use instead, invoked with `-c`. `cmd.exe` is
+  /// invoked with `/d /s /c` instead.
+  Program(String),
+}
+
+impl ShellOption {
+  pub fn is_builtin(&self) -> bool {
+    matches!(self, ShellOption::Builtin(true))
+  }
+
+  /// Whether the shell is `cmd.exe`, which is passed its script as a raw
+  /// argument by [`command`].
+  #[cfg(windows)]
+  pub fn is_cmd(&self) -> bool {
+    matches!(self, ShellOption::Program(program) if is_cmd(program))
+  }
+}
+
+/// The command to spawn in order to run `cmd` with `args` appended through
+/// the shell at the given path, or `None` if no such shell should be used.
+pub fn command(
+  shell: &ShellOption,
+  cmd: &str,
+  args: &[String],
+) -> Option<std::process::Command> {
+  let ShellOption::Program(program) = shell else {
+    return None;
+  };
+  let mut command = std::process::Command::new(program);
+  #[cfg(windows)]
+  if is_cmd(program) {
+    use std::os::windows::process::CommandExt;
+    let script = script(cmd, args, quote_cmd);
+    command.raw_arg(format!("/d /s /c \"{script}\""));
+    return Some(command);
+  }
+  command.arg("-c").arg(script(cmd, args, quote));
+  Some(command)
+}
+
+/// Appends `args` to `cmd`, each quoted so that the shell passes it on as a
+/// single argument.
+fn script(cmd: &str, args: &[String], quote: fn(&str) -> String) -> String {
+  let mut script = cmd.to_string();
+  for arg in args {
+    script.push(' ');
+    script.push_str(&quote(arg));
+  }
+  script
+}
+
+/// Quotes `arg` for a POSIX shell, which is understood by the built-in shell
+/// as well.
+fn quote(arg: &str) -> String {
+  format!("'{}'", arg.replace('\'', r#"'"'"'"#))
+}
+
+#[cfg(windows)]
+fn quote_cmd(arg: &str) -> String {
+  format!("\"{}\"", arg.replace('"', "\"\""))
+}
+
+#[cfg(windows)]
+fn is_cmd(program: &str) -> bool {
+  std::path::Path::new(program)
+    .file_stem()
+    .is_some_and(|stem| stem.eq_ignore_ascii_case("cmd"))
+}
+
+/// A command run by the built-in shell. As it is run in-process, it has no
+/// pid of its own, it can only be terminated along with the processes it
+/// started, and there is no grace period when it is aborted.
+pub struct ShellChildResource {
+  /// Canceling it kills the processes started by the shell.
+  token: CancellationToken,
+  /// Set to the exit code once the command has completed.
+  status: watch::Receiver<Option<i32>>,
+  /// The command is killed by `wait` if it is still running at this point in
+  /// time.
+  deadline: Option<tokio::time::Instant>,
+  timed_out: Cell<bool>,
+}
+
+impl Resource for ShellChildResource {
+  fn name(&self) -> Cow<str> {
+    "child".into()
+  }
+}
+
+impl Drop for ShellChildResource {
+  fn drop(&mut self) {
+    self.token.cancel();
+  }
+}
+
+impl ShellChildResource {
+  /// Terminates the command. Only `SIGTERM` and `SIGKILL` are supported, as
+  /// there is no process to deliver any other signal to.
+  pub fn kill(&self, signal: &str) -> Result<(), AnyError> {
+    if !matches!(signal, "SIGTERM" | "SIGKILL") {
+      return Err(custom_error(
+        "NotSupported",
+        format!(
+          "Sending {signal} to a command run by the built-in shell is not supported"
+        ),
+      ));
+    }
+    self.cancel();
+    Ok(())
+  }
+
+  /// Kills the processes started by the shell.
+  pub fn cancel(&self) {
+    self.token.cancel();
+  }
+
+  pub async fn wait(&self) -> Result<ChildStatus, AnyError> {
+    let completed = exit_code(self.status.clone());
+    let code = match self.deadline {
+      Some(deadline) => {
+        tokio::pin!(completed);
+        match tokio::time::timeout_at(deadline, &mut completed).await {
+          Ok(code) => code?,
+          Err(_) => {
+            self.timed_out.set(true);
+            self.cancel();
+            completed.await?
+          }
+        }
+      }
+      None => completed.await?,
+    };
+    Ok(self.status(code))
+  }
+
+  pub fn try_wait(&self) -> Option<ChildStatus> {
+    let code = (*self.status.borrow())?;
+    Some(self.status(code))
+  }
+
+  fn status(&self, code: i32) -> ChildStatus {
+    ChildStatus {
+      success: code == 0,
+      code,
+      signal: None,
+      timed_out: self.timed_out.get(),
+      usage: None,
+    }
+  }
+}
+
+/// Waits for the exit code of a command started by `execute`.
+async fn exit_code(
+  mut status: watch::Receiver<Option<i32>>,
+) -> Result<i32, AnyError> {
+  // The sender is only dropped without sending the exit code if the thread
+  // running the command panicked.
+  let code = status.wait_for(Option::is_some).await.map_err(|_| {
+    generic_error("The built-in shell exited without completing the command")
+  })?;
+  // only returned once it is set
+  Ok((*code).unwrap_or_default())
+}
+
+/// Runs `args.cmd` with the built-in shell, for `op_spawn_child`.
+pub fn spawn(
+  state: &mut OpState,
+  args: SpawnArgs,
+  api_name: &str,
+  stdin_bytes: Option<JsBuffer>,
+) -> Result<Child, AnyError> {
+  let timeout = args.timeout_ms.map(std::time::Duration::from_millis);
+  let (list, env_vars, cwd) = prepare(state, &args, api_name)?;
+
+  let (stdin, stdin_pipe) = reader(state, args.stdio.stdin)?;
+  let (stdout, stdout_pipe) = writer(state, args.stdio.stdout, 1)?;
+  let (stderr, stderr_pipe) = writer(state, args.stdio.stderr, 2)?;
+  // converted before the execution starts, so that it can't fail afterwards
+  let stdin_pipe = stdin_pipe.map(|pipe| pipe.into_async()).transpose()?;
+  let stdout_pipe = stdout_pipe.map(|pipe| pipe.into_async()).transpose()?;
+  let stderr_pipe = stderr_pipe.map(|pipe| pipe.into_async()).transpose()?;
+
+  let (token, status) = execute(list, env_vars, cwd, stdin, stdout, stderr)?;
+  let start_time = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|elapsed| elapsed.as_secs_f64() * 1000.0)
+    .unwrap_or_default();
+
+  let stdin_rid = match (stdin_pipe, stdin_bytes) {
+    (Some(mut pipe), Some(bytes)) => {
+      deno_core::unsync::spawn(async move {
+        // the command is free to complete without reading all of its input
+        let _ = pipe.write_all(&bytes).await;
+      });
+      None
+    }
+    (pipe, _) => {
+      pipe.map(|pipe| state.resource_table.add(PipeWriteResource::from(pipe)))
+    }
+  };
+  let stdout_rid = stdout_pipe
+    .map(|pipe| state.resource_table.add(PipeReadResource::from(pipe)));
+  let stderr_rid = stderr_pipe
+    .map(|pipe| state.resource_table.add(PipeReadResource::from(pipe)));
+
+  let rid = state.resource_table.add(ShellChildResource {
+    token,
+    status,
+    deadline: timeout.map(|timeout| tokio::time::Instant::now() + timeout),
+    timed_out: Cell::new(false),
+  });
+  Ok(Child {
+    rid,
+    // the command is run by this process, which must not be signaled in its
+    // place
+    pid: None,
+    stdin_rid,
+    stdout_rid,
+    stderr_rid,
+    start_time,
+    pidfd_rid: None,
+    pipes: ChildPipes::default(),
+  })
+}
+
+/// Runs `args.cmd` with the built-in shell to completion, for
+/// `op_spawn_sync`.
+pub fn output(
+  state: &mut OpState,
+  args: SpawnArgs,
+  stdin_bytes: Option<JsBuffer>,
+) -> Result<SpawnOutput, AnyError> {
+  let max_buffer = args.max_buffer;
+  let (list, env_vars, cwd) =
+    prepare(state, &args, "Deno.Command().outputSync()")?;
+
+  let (stdin, stdin_pipe) = reader(state, args.stdio.stdin)?;
+  let (stdout, stdout_pipe) = writer(state, args.stdio.stdout, 1)?;
+  let (stderr, stderr_pipe) = writer(state, args.stdio.stderr, 2)?;
+  let (token, status) = execute(list, env_vars, cwd, stdin, stdout, stderr)?;
+
+  // Both pipes are drained concurrently, as the command could otherwise block
+  // on writing to one of them while we wait on the other. For the same reason
+  // the input is written from another thread.
+  let (stdout, stderr) = std::thread::scope(|scope| {
+    if let (Some(mut pipe), Some(bytes)) = (stdin_pipe, &stdin_bytes) {
+      scope.spawn(move || {
+        use std::io::Write;
+        // the command is free to complete without reading all of its input
+        let _ = pipe.write_all(bytes);
+      });
+    }
+    let kill = || token.cancel();
+    let stderr_thread = stderr_pipe.map(|pipe| {
+      scope.spawn(move || collect_output(pipe, max_buffer, kill, "stderr"))
+    });
+    let stdout = stdout_pipe
+      .map(|pipe| collect_output(pipe, max_buffer, kill, "stdout"))
+      .transpose();
+    let stderr = stderr_thread
+      .map(|thread| thread.join().expect("stderr reader thread panicked"))
+      .transpose();
+    (stdout, stderr)
+  });
+  let code = block_on(exit_code(status))?;
+  let stdout = stdout?;
+  let stderr = stderr?;
+
+  Ok(SpawnOutput {
+    pid: None,
+    status: ChildStatus {
+      success: code == 0,
+      code,
+      signal: None,
+      timed_out: false,
+      usage: None,
+    },
+    stdout: stdout.map(|buf| buf.into()),
+    stderr: stderr.map(|buf| buf.into()),
+  })
+}
+
+/// Checks the permissions and options of the command, and parses it. Returns
+/// the parsed script along with the variables and the directory it is run
+/// with.
+fn prepare(
+  state: &mut OpState,
+  args: &SpawnArgs,
+  api_name: &str,
+) -> Result<(SequentialList, HashMap<String, String>, PathBuf), AnyError> {
+  check_options(args)?;
+  let inherited_env = check_command(state, args, api_name)?;
+
+  let script = script(&args.cmd, &args.args, quote);
+  let list = deno_task_shell::parser::parse(&script)
+    .map_err(|err| type_error(format!("Invalid shell command: {err}")))?;
+
+  let mut env_vars: HashMap<String, String> = match inherited_env {
+    Some(inherited_env) => inherited_env.into_iter().collect(),
+    None if args.clear_env => HashMap::new(),
+    None => std::env::vars_os()
+      .filter_map(|(key, value)| {
+        Some((key.into_string().ok()?, value.into_string().ok()?))
+      })
+      .collect(),
+  };
+  env_vars.extend(args.env.iter().cloned());
+  // Segments are only inherited by the children they were passed to.
+  env_vars.remove(shared_memory::ENV_VAR);
+
+  // The shell requires an absolute path, which `join` keeps as is. Launching
+  // a sub process always depends on the real file system so using these
+  // methods directly is ok.
+  #[allow(clippy::disallowed_methods)]
+  let cwd = std::env::current_dir()?.join(args.cwd.as_deref().unwrap_or(""));
+  #[allow(clippy::disallowed_methods)]
+  if !cwd.exists() {
+    return Err(
+      ProcessError::CwdNotFound {
+        command: script,
+        cwd: cwd.to_string_lossy().into_owned(),
+      }
+      .into(),
+    );
+  }
+  #[allow(clippy::disallowed_methods)]
+  if !cwd.is_dir() {
+    return Err(
+      ProcessError::CwdNotDirectory {
+        command: script,
+        cwd: cwd.to_string_lossy().into_owned(),
+      }
+      .into(),
+    );
+  }
+
+  Ok((list, env_vars, cwd))
+}
+
+/// Rejects the options which only apply to spawning a process, as the
+/// built-in shell runs in-process.
+fn check_options(args: &SpawnArgs) -> Result<(), AnyError> {
+  let is_ipc = args.stdio.stdin.is_ipc()
+    || args.stdio.stdout.is_ipc()
+    || args.stdio.stderr.is_ipc()
+    || args.ipc.is_some_and(|ipc| ipc >= 0)
+    || args.ipc_channel.is_some();
+  let options = [
+    ("argv0", args.argv0.is_some()),
+    #[cfg(unix)]
+    ("gid", args.gid.is_some()),
+    #[cfg(unix)]
+    ("uid", args.uid.is_some()),
+    #[cfg(unix)]
+    ("umask", args.umask.is_some()),
+    #[cfg(windows)]
+    ("windowsRawArguments", args.windows_raw_arguments),
+    #[cfg(windows)]
+    ("creationFlags", args.creation_flags.is_some()),
+    ("processGroup", args.process_group),
+    ("priority", args.priority.is_some()),
+    ("cpuAffinity", args.cpu_affinity.is_some()),
+    ("chroot", args.chroot.is_some()),
+    ("unshare", args.unshare.is_some()),
+    ("pty", args.pty.is_some()),
+    ("ipc", is_ipc),
+    ("sharedMemory", !args.shared_memory.is_empty()),
+    (
+      "extraStdio",
+      args
+        .extra_stdio
+        .iter()
+        .any(|stdio| *stdio != StdioOrRid::Stdio(Stdio::Null)),
+    ),
+  ];
+  match options.iter().find(|(_, is_set)| *is_set) {
+    Some((name, _)) => Err(type_error(format!(
+      "The {name} option is not supported by the built-in shell"
+    ))),
+    None => Ok(()),
+  }
+}
+
+/// The stdin of the command, along with the parent's end of the pipe if it
+/// is piped.
+fn reader(
+  state: &OpState,
+  stdio: StdioOrRid,
+) -> Result<(ShellPipeReader, Option<deno_io::PipeWrite>), AnyError> {
+  match stdio {
+    StdioOrRid::Stdio(Stdio::Piped) => {
+      let (read, write) = deno_io::pipe()?;
+      Ok((ShellPipeReader::from_std(read.into()), Some(write)))
+    }
+    StdioOrRid::Stdio(Stdio::Null) => {
+      // a pipe without a writer reads as empty
+      let (read, _) = deno_io::pipe()?;
+      Ok((ShellPipeReader::from_std(read.into()), None))
+    }
+    StdioOrRid::Stdio(_) => {
+      Ok((ShellPipeReader::from_std(clone(state, 0)?), None))
+    }
+    StdioOrRid::Rid(rid) => {
+      Ok((ShellPipeReader::from_std(clone(state, rid)?), None))
+    }
+  }
+}
+
+/// The stdout or stderr of the command, along with the parent's end of the
+/// pipe if it is piped. `"inherit"` refers to the resource `rid`, like for a
+/// process.
+fn writer(
+  state: &OpState,
+  stdio: StdioOrRid,
+  rid: ResourceId,
+) -> Result<(ShellPipeWriter, Option<deno_io::PipeRead>), AnyError> {
+  match stdio {
+    StdioOrRid::Stdio(Stdio::Piped) => {
+      let (read, write) = deno_io::pipe()?;
+      Ok((ShellPipeWriter::from_std(write.into()), Some(read)))
+    }
+    StdioOrRid::Stdio(Stdio::Null) => Ok((ShellPipeWriter::null(), None)),
+    StdioOrRid::Stdio(_) => {
+      Ok((ShellPipeWriter::from_std(clone(state, rid)?), None))
+    }
+    StdioOrRid::Rid(rid) => {
+      Ok((ShellPipeWriter::from_std(clone(state, rid)?), None))
+    }
+  }
+}
+
+/// Duplicates the handle of the file resource `rid`.
+fn clone(state: &OpState, rid: ResourceId) -> Result<std::fs::File, AnyError> {
+  FileResource::with_file(state, rid, |file| {
+    let handle = file.backing_fd().ok_or_else(bad_resource_id)?;
+    #[cfg(unix)]
+    // SAFETY: the handle is kept open by the resource
+    let owned = unsafe { std::os::fd::BorrowedFd::borrow_raw(handle) }
+      .try_clone_to_owned()?;
+    #[cfg(windows)]
+    // SAFETY: the handle is kept open by the resource
+    let owned =
+      unsafe { std::os::windows::io::BorrowedHandle::borrow_raw(handle) }
+        .try_clone_to_owned()?;
+    Ok(owned.into())
+  })
+}
+
+/// Starts executing `list` on a thread of its own. Returns the token to kill
+/// it, and the receiver of its exit code.
+fn execute(
+  list: SequentialList,
+  env_vars: HashMap<String, String>,
+  cwd: PathBuf,
+  stdin: ShellPipeReader,
+  stdout: ShellPipeWriter,
+  stderr: ShellPipeWriter,
+) -> Result<(CancellationToken, watch::Receiver<Option<i32>>), AnyError> {
+  let runtime = tokio::runtime::Builder::new_current_thread()
+    .enable_all()
+    .build()?;
+  let token = CancellationToken::new();
+  let (status_tx, status_rx) = watch::channel(None);
+  let thread_token = token.clone();
+  std::thread::Builder::new()
+    .name("deno-shell".to_string())
+    .spawn(move || {
+      let local = tokio::task::LocalSet::new();
+      let code = local.block_on(&runtime, async move {
+        let state = ShellState::new(env_vars, &cwd, HashMap::new());
+        // the state only hands out its own token
+        let state_token = state.token().clone();
+        tokio::task::spawn_local(async move {
+          thread_token.cancelled().await;
+          state_token.cancel();
+        });
+        deno_task_shell::execute_with_pipes(list, state, stdin, stdout, stderr)
+          .await
+      });
+      let _ = status_tx.send(Some(code));
+    })?;
+  Ok((token, status_rx))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::Read;
+
+  fn run(script: &str) -> (i32, String) {
+    let list = deno_task_shell::parser::parse(script).unwrap();
+    let (stdin, _) = deno_io::pipe().unwrap();
+    let (mut read, write) = deno_io::pipe().unwrap();
+    let (token, mut status) = execute(
+      list,
+      HashMap::new(),
+      PathBuf::from(env!("CARGO_MANIFEST_DIR")),
+      ShellPipeReader::from_std(stdin.into()),
+      ShellPipeWriter::from_std(write.into()),
+      ShellPipeWriter::null(),
+    )
+    .unwrap();
+    if script.starts_with("sleep") {
+      token.cancel();
+    }
+    let mut output = String::new();
+    read.read_to_string(&mut output).unwrap();
+    let code = block_on(status.wait_for(Option::is_some)).unwrap().unwrap();
+    (code, output)
+  }
+
+  #[test]
+  fn quotes_args() {
+    let args = ["it's", "$FOO", "a  b", "&&", ""].map(String::from);
+    let script = script("echo $PWD_IS_UNSET", &args, quote);
+    assert_eq!(run(&script), (0, "it's $FOO a  b && \n".to_string()));
+  }
+
+  #[test]
+  fn kills_on_cancel() {
+    let (code, _) = run("sleep 60 && echo finished");
+    assert_ne!(code, 0);
+  }
+}
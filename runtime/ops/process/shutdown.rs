@@ -0,0 +1,80 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Shutting down the children spawned with `Deno.Command` that are still
+//! running when the process exits.
+//!
+//! Children are normally killed with `SIGKILL` once their resource is dropped,
+//! but that never happens when exiting through `std::process::exit()`, e.g. on
+//! `Deno.exit()`. This keeps track of the running children of all runtimes in
+//! the process, so they can be asked to exit first and only be killed if they
+//! don't do so in time.
+
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// How the children that are still running are shut down on exit.
+#[derive(Clone, Debug)]
+pub struct ChildShutdownPolicy {
+  /// The signal initially sent to every child.
+  pub signal: String,
+  /// How long to wait for the children to exit after `signal` was sent,
+  /// before killing them with `SIGKILL`.
+  pub grace_period: Duration,
+}
+
+impl Default for ChildShutdownPolicy {
+  fn default() -> Self {
+    Self {
+      signal: "SIGTERM".to_string(),
+      grace_period: Duration::from_secs(1),
+    }
+  }
+}
+
+static POLICY: Mutex<Option<ChildShutdownPolicy>> = Mutex::new(None);
+static LIVE_CHILDREN: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// Sets the policy used by [`shutdown_children`].
+pub fn set_child_shutdown_policy(policy: ChildShutdownPolicy) {
+  *POLICY.lock().unwrap() = Some(policy);
+}
+
+pub(super) fn register(pid: u32) {
+  LIVE_CHILDREN.lock().unwrap().push(pid);
+}
+
+/// Must be called once the child was reaped at the latest, as its pid may be
+/// reused afterwards.
+pub(super) fn unregister(pid: u32) {
+  LIVE_CHILDREN.lock().unwrap().retain(|&live| live != pid);
+}
+
+/// Shuts down all children that are still running according to the
+/// configured [`ChildShutdownPolicy`]. Blocks until they exited, or the grace
+/// period is over.
+pub fn shutdown_children() {
+  let mut children = std::mem::take(&mut *LIVE_CHILDREN.lock().unwrap());
+  if children.is_empty() {
+    return;
+  }
+  let policy = POLICY.lock().unwrap().clone().unwrap_or_default();
+
+  for &pid in &children {
+    let _ = super::deprecated::kill(pid as i32, &policy.signal);
+  }
+
+  let deadline = Instant::now() + policy.grace_period;
+  loop {
+    // Errors mean that the child is gone already.
+    children.retain(|&pid| matches!(super::peek_exit_status(pid), Ok(None)));
+    if children.is_empty() || Instant::now() >= deadline {
+      break;
+    }
+    std::thread::sleep(Duration::from_millis(10));
+  }
+
+  for pid in children {
+    let _ = super::deprecated::kill(pid as i32, "SIGKILL");
+  }
+}
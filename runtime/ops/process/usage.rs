@@ -0,0 +1,252 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Collecting the resource usage of a child process when waiting for it.
+//!
+//! The usage has to be read before the child is reaped, which is done by
+//! `tokio::process::Child::wait()`. On Linux the child's exit is observed
+//! with `waitid(WNOWAIT)`, which leaves the zombie in place but fills in its
+//! resource usage. On Windows the usage is queried through a separate process
+//! handle that stays valid after the child exited.
+
+use serde::Serialize;
+use std::process::ExitStatus;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+  /// CPU time spent in user mode, in microseconds.
+  user_cpu_time: u64,
+  /// CPU time spent in kernel mode, in microseconds.
+  system_cpu_time: u64,
+  /// Peak resident set size, in bytes.
+  max_rss: u64,
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+  use super::ResourceUsage;
+  use std::io;
+  use tokio::signal::unix::signal;
+  use tokio::signal::unix::SignalKind;
+
+  fn timeval_to_micros(tv: libc::timeval) -> u64 {
+    tv.tv_sec as u64 * 1_000_000 + tv.tv_usec as u64
+  }
+
+  /// Returns the resource usage of the child with the given pid if it has
+  /// exited, without reaping it.
+  pub fn zombie_usage(
+    pid: u32,
+    block: bool,
+  ) -> io::Result<Option<ResourceUsage>> {
+    let mut options = libc::WEXITED | libc::WNOWAIT;
+    if !block {
+      options |= libc::WNOHANG;
+    }
+    // SAFETY: zeroed is a valid representation for both structs
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    // SAFETY: see above
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+    // The raw syscall is used as the libc wrapper doesn't expose the
+    // `rusage` argument.
+    // SAFETY: libc call, both pointers are valid for the duration of the call
+    let ret = unsafe {
+      libc::syscall(
+        libc::SYS_waitid,
+        libc::P_PID,
+        pid,
+        &mut info as *mut libc::siginfo_t,
+        options,
+        &mut rusage as *mut libc::rusage,
+      )
+    };
+    if ret == -1 {
+      return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `si_pid` is set by a successful `waitid` call, and left zeroed
+    // by `WNOHANG` if the child is still running
+    if unsafe { info.si_pid() } == 0 {
+      return Ok(None);
+    }
+    Ok(Some(ResourceUsage {
+      user_cpu_time: timeval_to_micros(rusage.ru_utime),
+      system_cpu_time: timeval_to_micros(rusage.ru_stime),
+      // reported in kilobytes
+      max_rss: rusage.ru_maxrss as u64 * 1024,
+    }))
+  }
+
+  pub async fn wait_for_usage(pid: u32) -> io::Result<Option<ResourceUsage>> {
+    // Registered before checking the first time, so that an exit in between
+    // is not missed.
+    let mut sigchld = signal(SignalKind::child())?;
+    loop {
+      if let Some(usage) = zombie_usage(pid, false)? {
+        return Ok(Some(usage));
+      }
+      sigchld.recv().await;
+    }
+  }
+}
+
+#[cfg(windows)]
+mod imp {
+  use super::ResourceUsage;
+  use winapi::shared::minwindef::FALSE;
+  use winapi::shared::minwindef::FILETIME;
+  use winapi::um::handleapi::CloseHandle;
+  use winapi::um::processthreadsapi::GetProcessTimes;
+  use winapi::um::processthreadsapi::OpenProcess;
+  use winapi::um::psapi::GetProcessMemoryInfo;
+  use winapi::um::psapi::PROCESS_MEMORY_COUNTERS;
+  use winapi::um::winnt::HANDLE;
+  use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+  use winapi::um::winnt::PROCESS_VM_READ;
+
+  /// A process handle that can be used to query the usage of a process,
+  /// even after it exited.
+  pub struct UsageHandle(HANDLE);
+
+  impl UsageHandle {
+    /// Must be called before the last handle to the child is closed.
+    pub fn open(pid: u32) -> Option<Self> {
+      // SAFETY: winapi call
+      let handle = unsafe {
+        OpenProcess(
+          PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_VM_READ,
+          FALSE,
+          pid,
+        )
+      };
+      if handle.is_null() {
+        None
+      } else {
+        Some(Self(handle))
+      }
+    }
+
+    pub fn usage(&self) -> Option<ResourceUsage> {
+      fn filetime_to_micros(ft: FILETIME) -> u64 {
+        // FILETIME is in 100 nanosecond intervals
+        (((ft.dwHighDateTime as u64) << 32) | ft.dwLowDateTime as u64) / 10
+      }
+
+      // SAFETY: zeroed is a valid representation for these structs
+      let (mut creation, mut exit, mut kernel, mut user, mut memory) = unsafe {
+        (
+          std::mem::zeroed::<FILETIME>(),
+          std::mem::zeroed::<FILETIME>(),
+          std::mem::zeroed::<FILETIME>(),
+          std::mem::zeroed::<FILETIME>(),
+          std::mem::zeroed::<PROCESS_MEMORY_COUNTERS>(),
+        )
+      };
+      // SAFETY: winapi calls, all pointers are valid for the duration of
+      // the calls
+      unsafe {
+        if GetProcessTimes(
+          self.0,
+          &mut creation,
+          &mut exit,
+          &mut kernel,
+          &mut user,
+        ) == FALSE
+        {
+          return None;
+        }
+        if GetProcessMemoryInfo(
+          self.0,
+          &mut memory,
+          std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+        ) == FALSE
+        {
+          return None;
+        }
+      }
+      Some(ResourceUsage {
+        user_cpu_time: filetime_to_micros(user),
+        system_cpu_time: filetime_to_micros(kernel),
+        max_rss: memory.PeakWorkingSetSize as u64,
+      })
+    }
+  }
+
+  impl Drop for UsageHandle {
+    fn drop(&mut self) {
+      // SAFETY: winapi call
+      unsafe {
+        CloseHandle(self.0);
+      }
+    }
+  }
+}
+
+/// Waits for the child to exit, collecting its resource usage where it is
+/// supported.
+pub async fn wait(
+  child: &mut super::SpawnedChild,
+) -> std::io::Result<(ExitStatus, Option<ResourceUsage>)> {
+  let Some(pid) = child.id() else {
+    // already reaped
+    return Ok((child.wait().await?, None));
+  };
+
+  #[cfg(target_os = "linux")]
+  {
+    let usage = imp::wait_for_usage(pid).await.ok().flatten();
+    Ok((child.wait().await?, usage))
+  }
+  #[cfg(windows)]
+  {
+    let handle = imp::UsageHandle::open(pid);
+    let status = child.wait().await?;
+    Ok((status, handle.and_then(|handle| handle.usage())))
+  }
+  #[cfg(not(any(target_os = "linux", windows)))]
+  {
+    let _ = pid;
+    Ok((child.wait().await?, None))
+  }
+}
+
+/// Returns the resource usage of a child that has exited but hasn't been
+/// reaped yet.
+pub fn peek(pid: u32) -> Option<ResourceUsage> {
+  #[cfg(target_os = "linux")]
+  {
+    imp::zombie_usage(pid, false).ok().flatten()
+  }
+  #[cfg(windows)]
+  {
+    imp::UsageHandle::open(pid).and_then(|handle| handle.usage())
+  }
+  #[cfg(not(any(target_os = "linux", windows)))]
+  {
+    let _ = pid;
+    None
+  }
+}
+
+/// Synchronous version of [`wait`].
+pub fn wait_sync(
+  child: &mut super::SyncChild,
+) -> std::io::Result<(ExitStatus, Option<ResourceUsage>)> {
+  let pid = child.id();
+
+  #[cfg(target_os = "linux")]
+  {
+    let usage = imp::zombie_usage(pid, true).ok().flatten();
+    Ok((child.wait()?, usage))
+  }
+  #[cfg(windows)]
+  {
+    let handle = imp::UsageHandle::open(pid);
+    let status = child.wait()?;
+    Ok((status, handle.and_then(|handle| handle.usage())))
+  }
+  #[cfg(not(any(target_os = "linux", windows)))]
+  {
+    let _ = pid;
+    Ok((child.wait()?, None))
+  }
+}
@@ -9,6 +9,8 @@ use deno_core::OpState;
 use deno_core::RcRef;
 use deno_core::Resource;
 use deno_core::ResourceId;
+use deno_permissions::PermissionsContainer;
+use serde::Serialize;
 
 use std::borrow::Cow;
 use std::cell::RefCell;
@@ -34,10 +36,24 @@ use tokio::signal::windows::ctrl_c;
 use tokio::signal::windows::CtrlBreak;
 #[cfg(windows)]
 use tokio::signal::windows::CtrlC;
+#[cfg(windows)]
+use windows_sys::Win32::System::Console::CTRL_CLOSE_EVENT;
+#[cfg(windows)]
+use windows_sys::Win32::System::Console::CTRL_LOGOFF_EVENT;
+#[cfg(windows)]
+use windows_sys::Win32::System::Console::CTRL_SHUTDOWN_EVENT;
 
 deno_core::extension!(
   deno_signal,
-  ops = [op_signal_bind, op_signal_unbind, op_signal_poll],
+  ops = [
+    op_signal_bind,
+    op_signal_unbind,
+    op_signal_poll,
+    op_signal_info,
+    op_signal_queue,
+    op_signal_block,
+    op_signal_unblock
+  ],
   state = |state| {
     #[cfg(unix)]
     {
@@ -50,6 +66,15 @@ deno_core::extension!(
 #[derive(Default)]
 struct SignalState {
   enable_default_handlers: BTreeMap<libc::c_int, Arc<AtomicBool>>,
+  /// The number of listeners of each signal, which keep the default handler
+  /// disabled.
+  listeners: BTreeMap<libc::c_int, usize>,
+  /// The children which were waited for since the last `SIGCHLD` info, as
+  /// they can't be found by `exited_children()` anymore.
+  reaped_children: Vec<super::process::ExitedChild>,
+  /// The signals blocked with `op_signal_block`, with the handler which
+  /// records whether they were received meanwhile.
+  blocked: BTreeMap<libc::c_int, (signal_hook::SigId, Arc<AtomicBool>)>,
 }
 
 #[cfg(unix)]
@@ -63,6 +88,7 @@ impl SignalState {
   ) -> (Arc<AtomicBool>, bool) {
     use std::collections::btree_map::Entry;
 
+    *self.listeners.entry(signo).or_default() += 1;
     match self.enable_default_handlers.entry(signo) {
       Entry::Occupied(entry) => {
         let enable = entry.get();
@@ -76,6 +102,39 @@ impl SignalState {
       }
     }
   }
+
+  /// Like `disable_default_handler()`, but also registers the default
+  /// handler to be restored for signals that weren't listened to before.
+  fn add_listener(&mut self, signo: libc::c_int) {
+    let (enable_default_handler, has_default_handler) =
+      self.disable_default_handler(signo);
+    if !has_default_handler {
+      // restore default signal handler when the signal is unbound
+      // this can error if the signal is not supported, if so let's just leave it as is
+      let _ = signal_hook::flag::register_conditional_default(
+        signo,
+        enable_default_handler,
+      );
+    }
+  }
+
+  /// Enables the default signal handler again once the last listener of the
+  /// given signal is removed.
+  fn remove_listener(&mut self, signo: libc::c_int) {
+    let Some(count) = self.listeners.get_mut(&signo) else {
+      return;
+    };
+    *count -= 1;
+    if *count == 0 {
+      self.listeners.remove(&signo);
+      if signo == libc::SIGCHLD {
+        self.reaped_children.clear();
+      }
+      if let Some(enable) = self.enable_default_handlers.get(&signo) {
+        enable.store(true, std::sync::atomic::Ordering::Release);
+      }
+    }
+  }
 }
 
 #[cfg(unix)]
@@ -83,7 +142,7 @@ impl SignalState {
 /// The second element is the waker of polling future.
 struct SignalStreamResource {
   signal: AsyncRefCell<Signal>,
-  enable_default_handler: Arc<AtomicBool>,
+  signo: libc::c_int,
   cancel: CancelHandle,
 }
 
@@ -98,12 +157,15 @@ impl Resource for SignalStreamResource {
   }
 }
 
-// TODO: CtrlClose could be mapped to SIGHUP but that needs a
-// tokio::windows::signal::CtrlClose type, or something from a different crate
+/// The console control events. Closing the console is mapped to SIGHUP like
+/// libuv does, logging off and shutting down are only delivered to processes
+/// that aren't attached to an interactive session, e.g. services.
 #[cfg(windows)]
 enum WindowsSignal {
   Sigint(CtrlC),
   Sigbreak(CtrlBreak),
+  /// SIGHUP, SIGLOGOFF or SIGSHUTDOWN.
+  Terminating(console_events::ConsoleEvent),
 }
 
 #[cfg(windows)]
@@ -120,12 +182,143 @@ impl From<CtrlBreak> for WindowsSignal {
   }
 }
 
+#[cfg(windows)]
+impl From<console_events::ConsoleEvent> for WindowsSignal {
+  fn from(event: console_events::ConsoleEvent) -> Self {
+    WindowsSignal::Terminating(event)
+  }
+}
+
 #[cfg(windows)]
 impl WindowsSignal {
   pub async fn recv(&mut self) -> Option<()> {
     match self {
       WindowsSignal::Sigint(ctrl_c) => ctrl_c.recv().await,
       WindowsSignal::Sigbreak(ctrl_break) => ctrl_break.recv().await,
+      WindowsSignal::Terminating(event) => event.recv().await,
+    }
+  }
+}
+
+/// Windows terminates the process as soon as the handler of closing the
+/// console, logging off or shutting down returns. Unlike tokio, whose handler
+/// returns right after notifying the listeners, this handler waits until the
+/// listeners ran, i.e. until the next time their resource is polled.
+#[cfg(windows)]
+mod console_events {
+  use std::sync::Condvar;
+  use std::sync::Mutex;
+  use std::sync::OnceLock;
+  use std::time::Duration;
+
+  use tokio::sync::mpsc;
+  use windows_sys::Win32::Foundation::BOOL;
+  use windows_sys::Win32::Foundation::FALSE;
+  use windows_sys::Win32::Foundation::TRUE;
+  use windows_sys::Win32::System::Console::SetConsoleCtrlHandler;
+
+  /// Windows itself terminates the process a few seconds after these events,
+  /// this only bounds the wait in case it doesn't.
+  const LISTENERS_TIMEOUT: Duration = Duration::from_secs(30);
+
+  struct Listener {
+    id: u64,
+    event: u32,
+    sender: mpsc::UnboundedSender<()>,
+    /// Whether the listener was notified and didn't run yet.
+    pending: bool,
+  }
+
+  struct Listeners {
+    next_id: u64,
+    list: Vec<Listener>,
+  }
+
+  static LISTENERS: Mutex<Listeners> = Mutex::new(Listeners {
+    next_id: 0,
+    list: Vec::new(),
+  });
+  static LISTENERS_RAN: Condvar = Condvar::new();
+
+  unsafe extern "system" fn handler(event: u32) -> BOOL {
+    let mut listeners = LISTENERS.lock().unwrap();
+    let mut notified = false;
+    for listener in listeners.list.iter_mut().filter(|l| l.event == event) {
+      if listener.sender.send(()).is_ok() {
+        listener.pending = true;
+        notified = true;
+      }
+    }
+    if !notified {
+      // let the next handler, or the default one, handle the event
+      return FALSE;
+    }
+    // the handler runs on its own thread, so it can block
+    let _ = LISTENERS_RAN.wait_timeout_while(
+      listeners,
+      LISTENERS_TIMEOUT,
+      |listeners| listeners.list.iter().any(|l| l.pending),
+    );
+    TRUE
+  }
+
+  fn install_handler() -> std::io::Result<()> {
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+    // SAFETY: `handler` is a valid handler routine for the process lifetime
+    let installed = *INSTALLED.get_or_init(|| unsafe {
+      SetConsoleCtrlHandler(Some(handler), TRUE) != 0
+    });
+    if installed {
+      Ok(())
+    } else {
+      Err(std::io::Error::last_os_error())
+    }
+  }
+
+  fn set_ran(id: u64) {
+    let mut listeners = LISTENERS.lock().unwrap();
+    if let Some(listener) = listeners.list.iter_mut().find(|l| l.id == id) {
+      listener.pending = false;
+    }
+    LISTENERS_RAN.notify_all();
+  }
+
+  /// A stream of `CTRL_CLOSE_EVENT`, `CTRL_LOGOFF_EVENT` or
+  /// `CTRL_SHUTDOWN_EVENT`.
+  pub struct ConsoleEvent {
+    id: u64,
+    receiver: mpsc::UnboundedReceiver<()>,
+  }
+
+  impl ConsoleEvent {
+    pub fn new(event: u32) -> std::io::Result<Self> {
+      install_handler()?;
+      let (sender, receiver) = mpsc::unbounded_channel();
+      let mut listeners = LISTENERS.lock().unwrap();
+      let id = listeners.next_id;
+      listeners.next_id += 1;
+      listeners.list.push(Listener {
+        id,
+        event,
+        sender,
+        pending: false,
+      });
+      Ok(Self { id, receiver })
+    }
+
+    /// Waiting for the next event means the listeners of the previous one
+    /// ran, which lets its handler return.
+    pub async fn recv(&mut self) -> Option<()> {
+      set_ran(self.id);
+      self.receiver.recv().await
+    }
+  }
+
+  impl Drop for ConsoleEvent {
+    fn drop(&mut self) {
+      let mut listeners = LISTENERS.lock().unwrap();
+      listeners.list.retain(|l| l.id != self.id);
+      LISTENERS_RAN.notify_all();
     }
   }
 }
@@ -342,7 +535,8 @@ pub fn signal_str_to_int(s: &str) -> Result<libc::c_int, AnyError> {
     "SIGIO" | "SIGPOLL" => Ok(29),
     "SIGPWR" => Ok(30),
     "SIGSYS" | "SIGUNUSED" => Ok(31),
-    _ => Err(type_error(format!("Invalid signal : {s}"))),
+    _ => realtime_signal_str_to_int(s)
+      .ok_or_else(|| type_error(format!("Invalid signal : {s}"))),
   }
 }
 
@@ -380,7 +574,81 @@ pub fn signal_int_to_str(s: libc::c_int) -> Result<&'static str, AnyError> {
     29 => Ok("SIGIO"),
     30 => Ok("SIGPWR"),
     31 => Ok("SIGSYS"),
-    _ => Err(type_error(format!("Invalid signal : {s}"))),
+    _ => realtime_signal_int_to_str(s)
+      .ok_or_else(|| type_error(format!("Invalid signal : {s}"))),
+  }
+}
+
+/// Names of the realtime signals, relative to `SIGRTMIN`. The C library
+/// reserves a few of them, so `SIGRTMIN` is determined at runtime.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+const REALTIME_SIGNAL_NAMES: [&str; 32] = [
+  "SIGRTMIN",
+  "SIGRTMIN+1",
+  "SIGRTMIN+2",
+  "SIGRTMIN+3",
+  "SIGRTMIN+4",
+  "SIGRTMIN+5",
+  "SIGRTMIN+6",
+  "SIGRTMIN+7",
+  "SIGRTMIN+8",
+  "SIGRTMIN+9",
+  "SIGRTMIN+10",
+  "SIGRTMIN+11",
+  "SIGRTMIN+12",
+  "SIGRTMIN+13",
+  "SIGRTMIN+14",
+  "SIGRTMIN+15",
+  "SIGRTMIN+16",
+  "SIGRTMIN+17",
+  "SIGRTMIN+18",
+  "SIGRTMIN+19",
+  "SIGRTMIN+20",
+  "SIGRTMIN+21",
+  "SIGRTMIN+22",
+  "SIGRTMIN+23",
+  "SIGRTMIN+24",
+  "SIGRTMIN+25",
+  "SIGRTMIN+26",
+  "SIGRTMIN+27",
+  "SIGRTMIN+28",
+  "SIGRTMIN+29",
+  "SIGRTMIN+30",
+  "SIGRTMIN+31",
+];
+
+/// Parses `SIGRTMIN`, `SIGRTMIN+n`, `SIGRTMAX` and `SIGRTMAX-n`, like
+/// `kill -l` prints them.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn realtime_signal_str_to_int(s: &str) -> Option<libc::c_int> {
+  let (min, max) = (libc::SIGRTMIN(), libc::SIGRTMAX());
+  let signo = if let Some(rest) = s.strip_prefix("SIGRTMIN") {
+    match rest.strip_prefix('+') {
+      Some(offset) => min.checked_add(offset.parse().ok()?)?,
+      None if rest.is_empty() => min,
+      None => return None,
+    }
+  } else if let Some(rest) = s.strip_prefix("SIGRTMAX") {
+    match rest.strip_prefix('-') {
+      Some(offset) => max.checked_sub(offset.parse().ok()?)?,
+      None if rest.is_empty() => max,
+      None => return None,
+    }
+  } else {
+    return None;
+  };
+  (min..=max).contains(&signo).then_some(signo)
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn realtime_signal_int_to_str(s: libc::c_int) -> Option<&'static str> {
+  let (min, max) = (libc::SIGRTMIN(), libc::SIGRTMAX());
+  if s == max {
+    Some("SIGRTMAX")
+  } else if (min..max).contains(&s) {
+    REALTIME_SIGNAL_NAMES.get((s - min) as usize).copied()
+  } else {
+    None
   }
 }
 
@@ -558,25 +826,41 @@ pub fn signal_int_to_str(s: libc::c_int) -> Result<&'static str, AnyError> {
   }
 }
 
+#[cfg(target_os = "windows")]
+const WINDOWS_SIGNALS_MESSAGE: &str = concat!(
+  "Windows only supports ctrl-c (SIGINT), ctrl-break (SIGBREAK), ",
+  "closing the console (SIGHUP), logging off (SIGLOGOFF) and ",
+  "shutting down (SIGSHUTDOWN)."
+);
+
+/// The console control events without a unix counterpart get numbers which
+/// don't collide with any signal of the C runtime.
+#[cfg(target_os = "windows")]
+const SIGLOGOFF: libc::c_int = 65;
+#[cfg(target_os = "windows")]
+const SIGSHUTDOWN: libc::c_int = 66;
+
 #[cfg(target_os = "windows")]
 pub fn signal_str_to_int(s: &str) -> Result<libc::c_int, AnyError> {
   match s {
+    "SIGHUP" => Ok(1),
     "SIGINT" => Ok(2),
     "SIGBREAK" => Ok(21),
-    _ => Err(type_error(
-      "Windows only supports ctrl-c (SIGINT) and ctrl-break (SIGBREAK).",
-    )),
+    "SIGLOGOFF" => Ok(SIGLOGOFF),
+    "SIGSHUTDOWN" => Ok(SIGSHUTDOWN),
+    _ => Err(type_error(WINDOWS_SIGNALS_MESSAGE)),
   }
 }
 
 #[cfg(target_os = "windows")]
 pub fn signal_int_to_str(s: libc::c_int) -> Result<&'static str, AnyError> {
   match s {
+    1 => Ok("SIGHUP"),
     2 => Ok("SIGINT"),
     21 => Ok("SIGBREAK"),
-    _ => Err(type_error(
-      "Windows only supports ctrl-c (SIGINT) and ctrl-break (SIGBREAK).",
-    )),
+    SIGLOGOFF => Ok("SIGLOGOFF"),
+    SIGSHUTDOWN => Ok("SIGSHUTDOWN"),
+    _ => Err(type_error(WINDOWS_SIGNALS_MESSAGE)),
   }
 }
 
@@ -596,26 +880,15 @@ fn op_signal_bind(
 
   let signal = AsyncRefCell::new(signal(SignalKind::from_raw(signo))?);
 
-  let (enable_default_handler, has_default_handler) = state
-    .borrow_mut::<SignalState>()
-    .disable_default_handler(signo);
+  state.borrow_mut::<SignalState>().add_listener(signo);
 
   let resource = SignalStreamResource {
     signal,
+    signo,
     cancel: Default::default(),
-    enable_default_handler: enable_default_handler.clone(),
   };
   let rid = state.resource_table.add(resource);
 
-  if !has_default_handler {
-    // restore default signal handler when the signal is unbound
-    // this can error if the signal is not supported, if so let's just leave it as is
-    let _ = signal_hook::flag::register_conditional_default(
-      signo,
-      enable_default_handler,
-    );
-  }
-
   Ok(rid)
 }
 
@@ -627,6 +900,13 @@ fn op_signal_bind(
   #[string] sig: &str,
 ) -> Result<ResourceId, AnyError> {
   let signo = signal_str_to_int(sig)?;
+  if matches!(signo, SIGLOGOFF | SIGSHUTDOWN) {
+    super::check_unstable(
+      state,
+      super::process::UNSTABLE_FEATURE_NAME,
+      &format!("Deno.addSignalListener(\"{sig}\")"),
+    );
+  }
   let resource = SignalStreamResource {
     signal: AsyncRefCell::new(match signo {
       // SIGINT
@@ -637,6 +917,12 @@ fn op_signal_bind(
       21 => ctrl_break()
         .expect("There was an issue creating ctrl+break event stream.")
         .into(),
+      // SIGHUP
+      1 => console_events::ConsoleEvent::new(CTRL_CLOSE_EVENT)?.into(),
+      SIGLOGOFF => console_events::ConsoleEvent::new(CTRL_LOGOFF_EVENT)?.into(),
+      SIGSHUTDOWN => {
+        console_events::ConsoleEvent::new(CTRL_SHUTDOWN_EVENT)?.into()
+      }
       _ => unimplemented!(),
     }),
     cancel: Default::default(),
@@ -664,6 +950,68 @@ async fn op_signal_poll(
   }
 }
 
+/// Details about a signal that was received, which are passed to the
+/// listeners of signals that have any.
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SignalInfo {
+  signal: String,
+  /// For `SIGCHLD`, the children which exited.
+  #[cfg(unix)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  children: Option<Vec<super::process::ExitedChild>>,
+  /// For `SIGWINCH`, the new size of the terminal.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  columns: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  rows: Option<u32>,
+}
+
+#[op2]
+#[serde]
+fn op_signal_info(
+  state: &mut OpState,
+  #[string] sig: String,
+) -> Result<SignalInfo, AnyError> {
+  #[cfg(unix)]
+  {
+    let signo = signal_str_to_int(&sig)?;
+    let mut info = SignalInfo::default();
+    if signo == libc::SIGCHLD {
+      let mut children =
+        std::mem::take(&mut state.borrow_mut::<SignalState>().reaped_children);
+      for child in super::process::exited_children(state) {
+        if !children.iter().any(|reaped| reaped.pid == child.pid) {
+          children.push(child);
+        }
+      }
+      info.children = Some(children);
+    } else if signo == libc::SIGWINCH {
+      // stdio might be piped, so the first terminal among them is used
+      if let Some(size) = [0, 1, 2]
+        .into_iter()
+        .find_map(|fd| super::tty::console_size_from_fd(fd).ok())
+      {
+        info.columns = Some(size.cols);
+        info.rows = Some(size.rows);
+      }
+    }
+    Ok(SignalInfo {
+      signal: sig,
+      ..info
+    })
+  }
+  #[cfg(windows)]
+  {
+    let _ = state;
+    signal_str_to_int(&sig)?;
+    Ok(SignalInfo {
+      signal: sig,
+      ..Default::default()
+    })
+  }
+}
+
 #[op2(fast)]
 pub fn op_signal_unbind(
   state: &mut OpState,
@@ -673,11 +1021,250 @@ pub fn op_signal_unbind(
 
   #[cfg(unix)]
   {
-    resource
-      .enable_default_handler
-      .store(true, std::sync::atomic::Ordering::Release);
+    state
+      .borrow_mut::<SignalState>()
+      .remove_listener(resource.signo);
   }
 
   resource.close();
   Ok(())
 }
+
+/// Sends `sig` to `pid` together with `value`, which a handler installed with
+/// `SA_SIGINFO` can read from `si_value`. Unlike `kill()` the signal is
+/// queued, so realtime signals aren't merged if they are sent repeatedly.
+#[op2(fast)]
+fn op_signal_queue(
+  state: &mut OpState,
+  #[smi] pid: i32,
+  #[string] sig: &str,
+  value: i32,
+) -> Result<(), AnyError> {
+  super::check_unstable(
+    state,
+    super::process::UNSTABLE_FEATURE_NAME,
+    "Deno.signal.queue",
+  );
+  state
+    .borrow_mut::<PermissionsContainer>()
+    .check_run_all("Deno.signal.queue()")?;
+  #[cfg(target_os = "linux")]
+  {
+    extern "C" {
+      fn sigqueue(
+        pid: libc::pid_t,
+        sig: libc::c_int,
+        value: libc::sigval,
+      ) -> libc::c_int;
+    }
+
+    if pid <= 0 {
+      return Err(type_error("Invalid pid"));
+    }
+    let signo = signal_str_to_int(sig)?;
+    // `sigval` is a union of an int and a pointer, libc only exposes the latter
+    let value = libc::sigval {
+      sival_ptr: value as isize as *mut libc::c_void,
+    };
+    // SAFETY: libc call
+    let res = unsafe { sigqueue(pid, signo, value) };
+    nix::errno::Errno::result(res)?;
+    Ok(())
+  }
+  #[cfg(not(target_os = "linux"))]
+  {
+    let _ = (pid, sig, value);
+    Err(type_error("Deno.signal.queue() is only supported on Linux"))
+  }
+}
+
+/// Changes the signal mask of the current thread. Signals sent to the process
+/// are still delivered to other threads, so this alone doesn't defer them.
+#[cfg(unix)]
+fn set_thread_signal_mask(
+  how: libc::c_int,
+  signals: &[libc::c_int],
+) -> Result<(), AnyError> {
+  let mut set = std::mem::MaybeUninit::<libc::sigset_t>::uninit();
+  // SAFETY: libc calls, the set is initialized by `sigemptyset` first
+  let res = unsafe {
+    libc::sigemptyset(set.as_mut_ptr());
+    for signo in signals {
+      libc::sigaddset(set.as_mut_ptr(), *signo);
+    }
+    libc::pthread_sigmask(how, set.as_ptr(), std::ptr::null_mut())
+  };
+  if res != 0 {
+    return Err(std::io::Error::from_raw_os_error(res).into());
+  }
+  Ok(())
+}
+
+/// Defers the default action of the given signals, e.g. terminating the
+/// process, until `op_signal_unblock` is called for them. Listeners are still
+/// notified right away.
+#[cfg(unix)]
+#[op2]
+fn op_signal_block(
+  state: &mut OpState,
+  #[serde] signals: Vec<String>,
+) -> Result<(), AnyError> {
+  super::check_unstable(
+    state,
+    super::process::UNSTABLE_FEATURE_NAME,
+    "Deno.signal.block",
+  );
+  let signals = signals
+    .iter()
+    .map(|sig| {
+      let signo = signal_str_to_int(sig)?;
+      if signal_hook_registry::FORBIDDEN.contains(&signo) {
+        return Err(type_error(format!(
+          "Blocking signal '{sig}' is not allowed",
+        )));
+      }
+      Ok(signo)
+    })
+    .collect::<Result<Vec<_>, AnyError>>()?;
+
+  let signal_state = state.borrow_mut::<SignalState>();
+  for signo in &signals {
+    if signal_state.blocked.contains_key(signo) {
+      continue;
+    }
+    let pending = Arc::new(AtomicBool::new(false));
+    let id = signal_hook::flag::register(*signo, pending.clone())?;
+    signal_state.add_listener(*signo);
+    signal_state.blocked.insert(*signo, (id, pending));
+  }
+  set_thread_signal_mask(libc::SIG_BLOCK, &signals)
+}
+
+/// Unblocks the given signals, and raises those which were received while
+/// they were blocked again, unless they are listened to.
+#[cfg(unix)]
+#[op2]
+fn op_signal_unblock(
+  state: &mut OpState,
+  #[serde] signals: Vec<String>,
+) -> Result<(), AnyError> {
+  super::check_unstable(
+    state,
+    super::process::UNSTABLE_FEATURE_NAME,
+    "Deno.signal.unblock",
+  );
+  let signals = signals
+    .iter()
+    .map(|sig| signal_str_to_int(sig))
+    .collect::<Result<Vec<_>, AnyError>>()?;
+
+  set_thread_signal_mask(libc::SIG_UNBLOCK, &signals)?;
+  let signal_state = state.borrow_mut::<SignalState>();
+  let mut received = vec![];
+  for signo in signals {
+    let Some((id, pending)) = signal_state.blocked.remove(&signo) else {
+      continue;
+    };
+    signal_hook::low_level::unregister(id);
+    signal_state.remove_listener(signo);
+    if pending.load(std::sync::atomic::Ordering::Acquire)
+      && !signal_state.listeners.contains_key(&signo)
+    {
+      received.push(signo);
+    }
+  }
+  for signo in received {
+    // SAFETY: libc call, the default handler is enabled again
+    unsafe { libc::raise(signo) };
+  }
+  Ok(())
+}
+
+#[cfg(windows)]
+#[op2]
+fn op_signal_block(
+  state: &mut OpState,
+  #[serde] _signals: Vec<String>,
+) -> Result<(), AnyError> {
+  super::check_unstable(
+    state,
+    super::process::UNSTABLE_FEATURE_NAME,
+    "Deno.signal.block",
+  );
+  Err(type_error(
+    "Deno.signal.block() is not supported on Windows",
+  ))
+}
+
+#[cfg(windows)]
+#[op2]
+fn op_signal_unblock(
+  state: &mut OpState,
+  #[serde] _signals: Vec<String>,
+) -> Result<(), AnyError> {
+  super::check_unstable(
+    state,
+    super::process::UNSTABLE_FEATURE_NAME,
+    "Deno.signal.unblock",
+  );
+  Err(type_error(
+    "Deno.signal.unblock() is not supported on Windows",
+  ))
+}
+
+/// Keeps a child which was just waited for until the next `SIGCHLD` info, if
+/// there are any listeners.
+#[cfg(unix)]
+pub(crate) fn record_reaped_child(
+  state: &mut OpState,
+  child: super::process::ExitedChild,
+) {
+  let signal_state = state.borrow_mut::<SignalState>();
+  if signal_state.listeners.contains_key(&libc::SIGCHLD) {
+    signal_state.reaped_children.push(child);
+  }
+}
+
+/// Relays SIGINT and SIGTERM received by this process to the child `pid`
+/// until `exited` is canceled. The signals don't terminate this process
+/// meanwhile, so that it can wait for the child to exit in turn. JS listeners
+/// of the signals still run, but only after the child got the signal.
+#[cfg(unix)]
+pub(crate) async fn forward_signals(
+  state: Rc<RefCell<OpState>>,
+  pid: u32,
+  exited: RcRef<CancelHandle>,
+) -> Result<(), AnyError> {
+  const FORWARDED: [libc::c_int; 2] = [libc::SIGINT, libc::SIGTERM];
+
+  let mut sigint = signal(SignalKind::interrupt())?;
+  let mut sigterm = signal(SignalKind::terminate())?;
+  for signo in FORWARDED {
+    state
+      .borrow_mut()
+      .borrow_mut::<SignalState>()
+      .add_listener(signo);
+  }
+  let forward = async {
+    loop {
+      let signo = tokio::select! {
+        _ = sigint.recv() => libc::SIGINT,
+        _ = sigterm.recv() => libc::SIGTERM,
+      };
+      // SAFETY: libc call. `exited` is canceled right after the child is
+      // reaped, so its pid can't have been reused yet.
+      unsafe {
+        libc::kill(pid as libc::pid_t, signo);
+      }
+    }
+  };
+  // forwarding only stops once the child exited
+  let _ = forward.or_cancel(exited).await;
+  for signo in FORWARDED {
+    state
+      .borrow_mut()
+      .borrow_mut::<SignalState>()
+      .remove_listener(signo);
+  }
+  Ok(())
+}
@@ -12,6 +12,8 @@ use rustyline::Editor;
 use rustyline::KeyCode;
 use rustyline::KeyEvent;
 use rustyline::Modifiers;
+use serde::Deserialize;
+use serde::Serialize;
 
 #[cfg(windows)]
 use deno_core::parking_lot::Mutex;
@@ -55,9 +57,18 @@ use winapi::shared::minwindef::DWORD;
 #[cfg(windows)]
 use winapi::um::wincon;
 
+pub const UNSTABLE_FEATURE_NAME: &str = "tty";
+
 deno_core::extension!(
   deno_tty,
-  ops = [op_set_raw, op_console_size, op_read_line_prompt],
+  ops = [
+    op_set_raw,
+    op_tty_get_mode,
+    op_tty_set_mode,
+    op_stdin_info,
+    op_console_size,
+    op_read_line_prompt
+  ],
   state = |state| {
     #[cfg(unix)]
     state.put(TtyModeStore::default());
@@ -206,40 +217,6 @@ fn op_set_raw(
   }
   #[cfg(unix)]
   {
-    fn prepare_stdio() {
-      // SAFETY: Save current state of stdio and restore it when we exit.
-      unsafe {
-        use libc::atexit;
-        use libc::tcgetattr;
-        use libc::tcsetattr;
-        use libc::termios;
-        use once_cell::sync::OnceCell;
-
-        // Only save original state once.
-        static ORIG_TERMIOS: OnceCell<Option<termios>> = OnceCell::new();
-        ORIG_TERMIOS.get_or_init(|| {
-          let mut termios = std::mem::zeroed::<termios>();
-          if tcgetattr(libc::STDIN_FILENO, &mut termios) == 0 {
-            extern "C" fn reset_stdio() {
-              // SAFETY: Reset the stdio state.
-              unsafe {
-                tcsetattr(
-                  libc::STDIN_FILENO,
-                  0,
-                  &ORIG_TERMIOS.get().unwrap().unwrap(),
-                )
-              };
-            }
-
-            atexit(reset_stdio);
-            return Some(termios);
-          }
-
-          None
-        });
-      }
-    }
-
     prepare_stdio();
     let tty_mode_store = state.borrow::<TtyModeStore>().clone();
     let previous_mode = tty_mode_store.get(rid);
@@ -285,6 +262,356 @@ fn op_set_raw(
   }
 }
 
+/// Saves the original mode of stdin, and restores it when the process exits.
+#[cfg(unix)]
+fn prepare_stdio() {
+  // SAFETY: Save current state of stdio and restore it when we exit.
+  unsafe {
+    use libc::atexit;
+    use libc::tcgetattr;
+    use libc::tcsetattr;
+    use libc::termios;
+    use once_cell::sync::OnceCell;
+
+    // Only save original state once.
+    static ORIG_TERMIOS: OnceCell<Option<termios>> = OnceCell::new();
+    ORIG_TERMIOS.get_or_init(|| {
+      let mut termios = std::mem::zeroed::<termios>();
+      if tcgetattr(libc::STDIN_FILENO, &mut termios) == 0 {
+        extern "C" fn reset_stdio() {
+          // SAFETY: Reset the stdio state.
+          unsafe {
+            tcsetattr(
+              libc::STDIN_FILENO,
+              0,
+              &ORIG_TERMIOS.get().unwrap().unwrap(),
+            )
+          };
+        }
+
+        atexit(reset_stdio);
+        return Some(termios);
+      }
+
+      None
+    });
+  }
+}
+
+/// The terminal settings that raw mode changes, which can be read and changed
+/// individually with `op_tty_get_mode` and `op_tty_set_mode`. Fields that are
+/// `None` are left as they are. Some of them are only supported on unix, and
+/// the raw console mode only on Windows.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TtyMode {
+  /// `ECHO`, or `ENABLE_ECHO_INPUT` on Windows.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  echo: Option<bool>,
+  /// `ICANON`, or `ENABLE_LINE_INPUT` on Windows.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  canonical: Option<bool>,
+  /// `ISIG`, or `ENABLE_PROCESSED_INPUT` on Windows.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  signals: Option<bool>,
+  /// `IEXTEN`
+  #[serde(skip_serializing_if = "Option::is_none")]
+  extended: Option<bool>,
+  /// `ICRNL`
+  #[serde(skip_serializing_if = "Option::is_none")]
+  cr_to_nl: Option<bool>,
+  /// `IXON`
+  #[serde(skip_serializing_if = "Option::is_none")]
+  flow_control: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  vmin: Option<u8>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  vtime: Option<u8>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  console_mode: Option<u32>,
+}
+
+#[op2]
+#[serde]
+fn op_tty_get_mode(
+  state: &mut OpState,
+  #[smi] rid: u32,
+) -> Result<TtyMode, AnyError> {
+  super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.stdin.getMode");
+  let handle_or_fd = state.resource_table.get_fd(rid)?;
+
+  #[cfg(windows)]
+  {
+    use winapi::shared::minwindef::FALSE;
+    use winapi::um::consoleapi;
+
+    let mut mode: DWORD = 0;
+    // SAFETY: winapi call
+    if unsafe { consoleapi::GetConsoleMode(handle_or_fd, &mut mode) } == FALSE {
+      return Err(Error::last_os_error().into());
+    }
+    Ok(TtyMode {
+      echo: Some(mode & wincon::ENABLE_ECHO_INPUT != 0),
+      canonical: Some(mode & wincon::ENABLE_LINE_INPUT != 0),
+      signals: Some(mode & wincon::ENABLE_PROCESSED_INPUT != 0),
+      console_mode: Some(mode),
+      ..Default::default()
+    })
+  }
+  #[cfg(unix)]
+  {
+    use termios::LocalFlags;
+    use termios::SpecialCharacterIndices::VMIN;
+    use termios::SpecialCharacterIndices::VTIME;
+
+    let mode = termios::tcgetattr(handle_or_fd)?;
+    Ok(TtyMode {
+      echo: Some(mode.local_flags.contains(LocalFlags::ECHO)),
+      canonical: Some(mode.local_flags.contains(LocalFlags::ICANON)),
+      signals: Some(mode.local_flags.contains(LocalFlags::ISIG)),
+      extended: Some(mode.local_flags.contains(LocalFlags::IEXTEN)),
+      cr_to_nl: Some(mode.input_flags.contains(termios::InputFlags::ICRNL)),
+      flow_control: Some(mode.input_flags.contains(termios::InputFlags::IXON)),
+      vmin: Some(mode.control_chars[VMIN as usize]),
+      vtime: Some(mode.control_chars[VTIME as usize]),
+      console_mode: None,
+    })
+  }
+}
+
+#[op2]
+fn op_tty_set_mode(
+  state: &mut OpState,
+  #[smi] rid: u32,
+  #[serde] mode: TtyMode,
+) -> Result<(), AnyError> {
+  super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.stdin.setMode");
+  let handle_or_fd = state.resource_table.get_fd(rid)?;
+
+  #[cfg(windows)]
+  {
+    use winapi::shared::minwindef::FALSE;
+    use winapi::um::consoleapi;
+
+    if mode.extended.is_some()
+      || mode.cr_to_nl.is_some()
+      || mode.flow_control.is_some()
+      || mode.vmin.is_some()
+      || mode.vtime.is_some()
+    {
+      return Err(deno_core::error::not_supported());
+    }
+
+    let mut console_mode: DWORD = 0;
+    // SAFETY: winapi call
+    if unsafe { consoleapi::GetConsoleMode(handle_or_fd, &mut console_mode) }
+      == FALSE
+    {
+      return Err(Error::last_os_error().into());
+    }
+    let mut new_mode = mode.console_mode.unwrap_or(console_mode);
+    for (flag, enable) in [
+      (wincon::ENABLE_ECHO_INPUT, mode.echo),
+      (wincon::ENABLE_LINE_INPUT, mode.canonical),
+      (wincon::ENABLE_PROCESSED_INPUT, mode.signals),
+    ] {
+      match enable {
+        Some(true) => new_mode |= flag,
+        Some(false) => new_mode &= !flag,
+        None => {}
+      }
+    }
+    // SAFETY: winapi call
+    if unsafe { consoleapi::SetConsoleMode(handle_or_fd, new_mode) } == FALSE {
+      return Err(Error::last_os_error().into());
+    }
+    Ok(())
+  }
+  #[cfg(unix)]
+  {
+    use termios::InputFlags;
+    use termios::LocalFlags;
+    use termios::SpecialCharacterIndices::VMIN;
+    use termios::SpecialCharacterIndices::VTIME;
+
+    if mode.console_mode.is_some() {
+      return Err(deno_core::error::not_supported());
+    }
+
+    prepare_stdio();
+    let mut new_mode = termios::tcgetattr(handle_or_fd)?;
+    // `setRaw(false)` restores the mode from before any changes
+    let tty_mode_store = state.borrow::<TtyModeStore>();
+    if tty_mode_store.get(rid).is_none() {
+      tty_mode_store.set(rid, new_mode.clone());
+    }
+
+    let local_flags = [
+      (LocalFlags::ECHO, mode.echo),
+      (LocalFlags::ICANON, mode.canonical),
+      (LocalFlags::ISIG, mode.signals),
+      (LocalFlags::IEXTEN, mode.extended),
+    ];
+    for (flag, enable) in local_flags {
+      if let Some(enable) = enable {
+        new_mode.local_flags.set(flag, enable);
+      }
+    }
+    let input_flags = [
+      (InputFlags::ICRNL, mode.cr_to_nl),
+      (InputFlags::IXON, mode.flow_control),
+    ];
+    for (flag, enable) in input_flags {
+      if let Some(enable) = enable {
+        new_mode.input_flags.set(flag, enable);
+      }
+    }
+    if let Some(vmin) = mode.vmin {
+      new_mode.control_chars[VMIN as usize] = vmin;
+    }
+    if let Some(vtime) = mode.vtime {
+      new_mode.control_chars[VTIME as usize] = vtime;
+    }
+    termios::tcsetattr(handle_or_fd, termios::SetArg::TCSADRAIN, &new_mode)?;
+    Ok(())
+  }
+}
+
+/// What `stdin` is connected to, and whether a read returns right away.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StdinInfo {
+  /// `"terminal"`, `"pipe"`, `"file"` or `"other"`, e.g. for `/dev/null`.
+  /// Sockets count as pipes, as they are what some parents use for piping.
+  kind: &'static str,
+  /// The size of the file, only for files.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  size: Option<u64>,
+  has_data: bool,
+}
+
+#[op2]
+#[serde]
+fn op_stdin_info(
+  state: &mut OpState,
+  #[smi] rid: u32,
+) -> Result<StdinInfo, AnyError> {
+  super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Deno.stdin.info");
+  let handle_or_fd = state.resource_table.get_fd(rid)?;
+
+  #[cfg(windows)]
+  {
+    use std::os::windows::io::FromRawHandle;
+    use winapi::shared::minwindef::FALSE;
+    use winapi::um::consoleapi;
+    use winapi::um::fileapi;
+    use winapi::um::namedpipeapi;
+    use winapi::um::winbase;
+
+    // SAFETY: winapi call
+    let file_type = unsafe { fileapi::GetFileType(handle_or_fd) };
+    let mut mode: DWORD = 0;
+    // SAFETY: winapi call
+    let is_console =
+      unsafe { consoleapi::GetConsoleMode(handle_or_fd, &mut mode) } != FALSE;
+    let info = match file_type {
+      winbase::FILE_TYPE_CHAR if is_console => {
+        let mut events: DWORD = 0;
+        // SAFETY: winapi call
+        unsafe {
+          consoleapi::GetNumberOfConsoleInputEvents(handle_or_fd, &mut events)
+        };
+        StdinInfo {
+          kind: "terminal",
+          size: None,
+          // not every event is a key press, so this may be a false positive
+          has_data: events > 0,
+        }
+      }
+      winbase::FILE_TYPE_PIPE => {
+        let mut available: DWORD = 0;
+        // SAFETY: winapi call, only the number of available bytes is written
+        let res = unsafe {
+          namedpipeapi::PeekNamedPipe(
+            handle_or_fd,
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null_mut(),
+            &mut available,
+            std::ptr::null_mut(),
+          )
+        };
+        StdinInfo {
+          kind: "pipe",
+          size: None,
+          // fails once the other end is closed
+          has_data: res != FALSE && available > 0,
+        }
+      }
+      winbase::FILE_TYPE_DISK => {
+        // SAFETY: the resource owns the handle, which isn't closed by
+        // `ManuallyDrop`
+        let file = std::mem::ManuallyDrop::new(unsafe {
+          std::fs::File::from_raw_handle(handle_or_fd)
+        });
+        StdinInfo {
+          kind: "file",
+          size: Some(file.metadata()?.len()),
+          has_data: true,
+        }
+      }
+      _ => StdinInfo {
+        kind: "other",
+        size: None,
+        has_data: true,
+      },
+    };
+    Ok(info)
+  }
+  #[cfg(unix)]
+  {
+    use std::io::IsTerminal;
+    use std::os::fd::BorrowedFd;
+    use std::os::fd::FromRawFd;
+    use std::os::unix::fs::FileTypeExt;
+
+    // SAFETY: the resource owns the fd, which isn't closed by `ManuallyDrop`
+    let file = std::mem::ManuallyDrop::new(unsafe {
+      std::fs::File::from_raw_fd(handle_or_fd)
+    });
+    let metadata = file.metadata()?;
+    let file_type = metadata.file_type();
+    // SAFETY: the resource owns the fd
+    let fd = unsafe { BorrowedFd::borrow_raw(handle_or_fd) };
+    let (kind, size) = if file_type.is_file() {
+      ("file", Some(metadata.len()))
+    } else if file_type.is_fifo() || file_type.is_socket() {
+      ("pipe", None)
+    } else if fd.is_terminal() {
+      ("terminal", None)
+    } else {
+      ("other", None)
+    };
+
+    let mut pollfd = libc::pollfd {
+      fd: handle_or_fd,
+      events: libc::POLLIN,
+      revents: 0,
+    };
+    // SAFETY: libc call, `pollfd` is a single entry
+    if unsafe { libc::poll(&mut pollfd, 1, 0) } < 0 {
+      return Err(Error::last_os_error().into());
+    }
+    // a closed pipe without data left only reports `POLLHUP`
+    let has_data = pollfd.revents & libc::POLLIN != 0;
+    Ok(StdinInfo {
+      kind,
+      size,
+      has_data,
+    })
+  }
+}
+
 #[op2(fast)]
 fn op_console_size(
   state: &mut OpState,
@@ -369,7 +696,7 @@ fn console_size_from_fd(
 }
 
 #[cfg(not(windows))]
-fn console_size_from_fd(
+pub(crate) fn console_size_from_fd(
   fd: std::os::unix::prelude::RawFd,
 ) -> Result<ConsoleSize, std::io::Error> {
   // SAFETY: libc calls
@@ -8,8 +8,10 @@ use crate::web_worker::WebWorkerHandle;
 use crate::web_worker::WebWorkerType;
 use crate::web_worker::WorkerControlEvent;
 use crate::web_worker::WorkerId;
+use crate::web_worker::WorkerLimits;
 use crate::web_worker::WorkerMetadata;
 use crate::worker::FormatJsErrorFn;
+use deno_core::error::type_error;
 use deno_core::error::AnyError;
 use deno_core::op2;
 use deno_core::serde::Deserialize;
@@ -17,9 +19,16 @@ use deno_core::CancelFuture;
 use deno_core::CancelHandle;
 use deno_core::ModuleSpecifier;
 use deno_core::OpState;
+use deno_core::ResourceId;
+use deno_core::ToJsBuffer;
+use deno_io::PipeRead;
+use deno_io::PipeReadResource;
+use deno_io::StdioPipe;
 use deno_permissions::create_child_permissions;
+use deno_permissions::create_child_permissions_from_snapshot;
 use deno_permissions::ChildPermissionsArg;
 use deno_permissions::PermissionsContainer;
+use deno_permissions::PermissionsSnapshot;
 use deno_web::deserialize_js_transferables;
 use deno_web::JsMessageData;
 use log::debug;
@@ -39,6 +48,11 @@ pub struct CreateWebWorkerArgs {
   pub worker_type: WebWorkerType,
   pub close_on_idle: bool,
   pub maybe_worker_metadata: Option<WorkerMetadata>,
+  pub limits: WorkerLimits,
+  /// Replaces the stdout of the parent, to capture the output of the worker.
+  pub stdout: Option<StdioPipe>,
+  /// Replaces the stderr of the parent, to capture the output of the worker.
+  pub stderr: Option<StdioPipe>,
 }
 
 pub type CreateWebWorkerCb = dyn Fn(CreateWebWorkerArgs) -> (WebWorker, SendableWebWorkerHandle)
@@ -58,6 +72,7 @@ struct FormatJsErrorFnHolder(Option<Arc<FormatJsErrorFn>>);
 pub struct WorkerThread {
   worker_handle: WebWorkerHandle,
   cancel_handle: Rc<CancelHandle>,
+  stdio: WorkerStdio,
 
   // A WorkerThread that hasn't been explicitly terminated can only be removed
   // from the WorkersTable once close messages have been received for both the
@@ -82,6 +97,14 @@ impl Drop for WorkerThread {
 
 pub type WorkersTable = HashMap<WorkerId, WorkerThread>;
 
+/// The read ends of the pipes that a worker writes its captured stdout and
+/// stderr to.
+#[derive(Default)]
+pub(crate) struct WorkerStdio {
+  stdout: Option<PipeRead>,
+  stderr: Option<PipeRead>,
+}
+
 deno_core::extension!(
   deno_worker_host,
   ops = [
@@ -90,6 +113,10 @@ deno_core::extension!(
     op_host_post_message,
     op_host_recv_ctrl,
     op_host_recv_message,
+    op_host_take_worker_stdio,
+    op_host_worker_heap_snapshot,
+    super::worker_pool::op_worker_pool_create,
+    super::worker_pool::op_worker_pool_run,
   ],
   options = {
     create_web_worker_cb: Arc<CreateWebWorkerCb>,
@@ -113,10 +140,199 @@ pub struct CreateWorkerArgs {
   has_source_code: bool,
   name: Option<String>,
   permissions: Option<ChildPermissionsArg>,
+  permissions_snapshot: Option<PermissionsSnapshot>,
   source_code: String,
   specifier: String,
   worker_type: WebWorkerType,
   close_on_idle: bool,
+  limits: Option<WorkerLimits>,
+  /// Limits set with `resourceLimits` of `node:worker_threads`, which unlike
+  /// `limits` aren't unstable.
+  #[serde(default)]
+  resource_limits: Option<WorkerLimits>,
+  #[serde(default)]
+  capture_stdout: bool,
+  #[serde(default)]
+  capture_stderr: bool,
+}
+
+/// Everything needed to start the thread of a worker. Worker pools keep it
+/// to replace workers that crashed.
+#[derive(Clone)]
+pub(crate) struct WorkerSpawner {
+  create_web_worker_cb: CreateWebWorkerCbHolder,
+  format_js_error_fn: FormatJsErrorFnHolder,
+  parent_permissions: PermissionsContainer,
+  worker_permissions: PermissionsContainer,
+  module_specifier: ModuleSpecifier,
+  name: String,
+  worker_type: WebWorkerType,
+  close_on_idle: bool,
+  limits: WorkerLimits,
+  capture_stdout: bool,
+  capture_stderr: bool,
+  maybe_source_code: Option<String>,
+}
+
+impl WorkerSpawner {
+  pub(crate) fn new(
+    state: &mut OpState,
+    args: CreateWorkerArgs,
+  ) -> Result<Self, AnyError> {
+    let maybe_source_code = if args.has_source_code {
+      Some(args.source_code)
+    } else {
+      None
+    };
+    let worker_type = args.worker_type;
+    if let WebWorkerType::Classic = worker_type {
+      if let TestingFeaturesEnabled(false) = state.borrow() {
+        return Err(
+          deno_webstorage::DomExceptionNotSupportedError::new(
+            "Classic workers are not supported.",
+          )
+          .into(),
+        );
+      }
+    }
+
+    if args.permissions.is_some() {
+      super::check_unstable(
+        state,
+        UNSTABLE_FEATURE_NAME,
+        "Worker.deno.permissions",
+      );
+    }
+    if args.permissions_snapshot.is_some() {
+      super::check_unstable(
+        state,
+        UNSTABLE_FEATURE_NAME,
+        "Worker.deno.permissionsSnapshot",
+      );
+    }
+    if args.limits.is_some() {
+      super::check_unstable(state, UNSTABLE_FEATURE_NAME, "Worker.deno.limits");
+    }
+    let parent_permissions = state.borrow_mut::<PermissionsContainer>();
+    let worker_permissions = match (args.permissions, args.permissions_snapshot)
+    {
+      (Some(_), Some(_)) => {
+        return Err(type_error(
+          "Only one of \"permissions\" and \"permissionsSnapshot\" can be set",
+        ));
+      }
+      (Some(child_permissions_arg), None) => {
+        let perms = create_child_permissions(
+          &mut parent_permissions.0.lock(),
+          child_permissions_arg,
+        )?;
+        parent_permissions.new_child(perms)
+      }
+      (None, Some(snapshot)) => {
+        let perms = create_child_permissions_from_snapshot(
+          &mut parent_permissions.0.lock(),
+          &snapshot,
+        )?;
+        parent_permissions.new_child(perms)
+      }
+      (None, None) => parent_permissions.clone(),
+    };
+    let parent_permissions = parent_permissions.clone();
+
+    Ok(Self {
+      create_web_worker_cb: state.borrow::<CreateWebWorkerCbHolder>().clone(),
+      format_js_error_fn: state.borrow::<FormatJsErrorFnHolder>().clone(),
+      parent_permissions,
+      worker_permissions,
+      module_specifier: deno_core::resolve_url(&args.specifier)?,
+      name: args.name.unwrap_or_default(),
+      worker_type,
+      close_on_idle: args.close_on_idle,
+      limits: args.limits.or(args.resource_limits).unwrap_or_default(),
+      capture_stdout: args.capture_stdout,
+      capture_stderr: args.capture_stderr,
+      maybe_source_code,
+    })
+  }
+
+  /// Starts a new worker thread.
+  pub(crate) fn spawn(
+    &self,
+    maybe_worker_metadata: Option<WorkerMetadata>,
+  ) -> Result<(WorkerId, WebWorkerHandle, WorkerStdio), AnyError> {
+    let worker_id = WorkerId::new();
+    let spawner = self.clone();
+
+    let mut stdio = WorkerStdio::default();
+    let stdout = if self.capture_stdout {
+      let (read, write) = deno_io::pipe()?;
+      stdio.stdout = Some(read);
+      Some(StdioPipe::file(write))
+    } else {
+      None
+    };
+    let stderr = if self.capture_stderr {
+      let (read, write) = deno_io::pipe()?;
+      stdio.stderr = Some(read);
+      Some(StdioPipe::file(write))
+    } else {
+      None
+    };
+
+    let (handle_sender, handle_receiver) = std::sync::mpsc::sync_channel::<
+      Result<SendableWebWorkerHandle, AnyError>,
+    >(1);
+
+    // Setup new thread
+    let mut thread_builder =
+      std::thread::Builder::new().name(format!("{worker_id}"));
+    if let Some(stack_mb) = self.limits.stack_mb {
+      let size = usize::try_from(stack_mb.saturating_mul(1024 * 1024))
+        .unwrap_or(usize::MAX);
+      thread_builder = thread_builder.stack_size(size);
+    }
+    // Spawn it
+    thread_builder.spawn(move || {
+      // Any error inside this block is terminal:
+      // - JS worker is useless - meaning it throws an exception and can't do anything else,
+      //  all action done upon it should be noops
+      // - newly spawned thread exits
+
+      let (worker, external_handle) =
+        (spawner.create_web_worker_cb.0)(CreateWebWorkerArgs {
+          name: spawner.name,
+          worker_id,
+          parent_permissions: spawner.parent_permissions,
+          permissions: spawner.worker_permissions,
+          main_module: spawner.module_specifier.clone(),
+          worker_type: spawner.worker_type,
+          close_on_idle: spawner.close_on_idle,
+          maybe_worker_metadata,
+          limits: spawner.limits,
+          stdout,
+          stderr,
+        });
+
+      // Send thread safe handle from newly created worker to host thread
+      handle_sender.send(Ok(external_handle)).unwrap();
+      drop(handle_sender);
+
+      // At this point the only method of communication with host
+      // is using `worker.internal_channels`.
+      //
+      // Host can already push messages and interact with worker.
+      run_web_worker(
+        worker,
+        spawner.module_specifier,
+        spawner.maybe_source_code,
+        spawner.format_js_error_fn.0,
+      )
+    })?;
+
+    // Receive WebWorkerHandle from newly created worker
+    let worker_handle = handle_receiver.recv().unwrap()?;
+    Ok((worker_id, worker_handle.into(), stdio))
+  }
 }
 
 /// Create worker as the host
@@ -127,56 +343,7 @@ fn op_create_worker(
   #[serde] args: CreateWorkerArgs,
   #[serde] maybe_worker_metadata: Option<JsMessageData>,
 ) -> Result<WorkerId, AnyError> {
-  let specifier = args.specifier.clone();
-  let maybe_source_code = if args.has_source_code {
-    Some(args.source_code.clone())
-  } else {
-    None
-  };
-  let args_name = args.name;
-  let worker_type = args.worker_type;
-  if let WebWorkerType::Classic = worker_type {
-    if let TestingFeaturesEnabled(false) = state.borrow() {
-      return Err(
-        deno_webstorage::DomExceptionNotSupportedError::new(
-          "Classic workers are not supported.",
-        )
-        .into(),
-      );
-    }
-  }
-
-  if args.permissions.is_some() {
-    super::check_unstable(
-      state,
-      UNSTABLE_FEATURE_NAME,
-      "Worker.deno.permissions",
-    );
-  }
-  let parent_permissions = state.borrow_mut::<PermissionsContainer>();
-  let worker_permissions = if let Some(child_permissions_arg) = args.permissions
-  {
-    let mut parent_permissions = parent_permissions.0.lock();
-    let perms =
-      create_child_permissions(&mut parent_permissions, child_permissions_arg)?;
-    PermissionsContainer::new(perms)
-  } else {
-    parent_permissions.clone()
-  };
-  let parent_permissions = parent_permissions.clone();
-  let create_web_worker_cb = state.borrow::<CreateWebWorkerCbHolder>().clone();
-  let format_js_error_fn = state.borrow::<FormatJsErrorFnHolder>().clone();
-  let worker_id = WorkerId::new();
-
-  let module_specifier = deno_core::resolve_url(&specifier)?;
-  let worker_name = args_name.unwrap_or_default();
-
-  let (handle_sender, handle_receiver) = std::sync::mpsc::sync_channel::<
-    Result<SendableWebWorkerHandle, AnyError>,
-  >(1);
-
-  // Setup new thread
-  let thread_builder = std::thread::Builder::new().name(format!("{worker_id}"));
+  let spawner = WorkerSpawner::new(state, args)?;
   let maybe_worker_metadata = if let Some(data) = maybe_worker_metadata {
     let transferables =
       deserialize_js_transferables(state, data.transferables)?;
@@ -187,47 +354,13 @@ fn op_create_worker(
   } else {
     None
   };
-  // Spawn it
-  thread_builder.spawn(move || {
-    // Any error inside this block is terminal:
-    // - JS worker is useless - meaning it throws an exception and can't do anything else,
-    //  all action done upon it should be noops
-    // - newly spawned thread exits
-
-    let (worker, external_handle) =
-      (create_web_worker_cb.0)(CreateWebWorkerArgs {
-        name: worker_name,
-        worker_id,
-        parent_permissions,
-        permissions: worker_permissions,
-        main_module: module_specifier.clone(),
-        worker_type,
-        close_on_idle: args.close_on_idle,
-        maybe_worker_metadata,
-      });
-
-    // Send thread safe handle from newly created worker to host thread
-    handle_sender.send(Ok(external_handle)).unwrap();
-    drop(handle_sender);
-
-    // At this point the only method of communication with host
-    // is using `worker.internal_channels`.
-    //
-    // Host can already push messages and interact with worker.
-    run_web_worker(
-      worker,
-      module_specifier,
-      maybe_source_code,
-      format_js_error_fn.0,
-    )
-  })?;
-
-  // Receive WebWorkerHandle from newly created worker
-  let worker_handle = handle_receiver.recv().unwrap()?;
+  let (worker_id, worker_handle, stdio) =
+    spawner.spawn(maybe_worker_metadata)?;
 
   let worker_thread = WorkerThread {
-    worker_handle: worker_handle.into(),
+    worker_handle,
     cancel_handle: CancelHandle::new_rc(),
+    stdio,
     ctrl_closed: false,
     message_closed: false,
   };
@@ -388,3 +521,43 @@ fn op_host_post_message(
   }
   Ok(())
 }
+
+/// Takes the captured stdout and stderr of a worker as resources to read
+/// from.
+#[op2]
+#[serde]
+fn op_host_take_worker_stdio(
+  state: &mut OpState,
+  #[serde] id: WorkerId,
+) -> Result<(Option<ResourceId>, Option<ResourceId>), AnyError> {
+  let Some(worker_thread) = state.borrow_mut::<WorkersTable>().get_mut(&id)
+  else {
+    return Ok((None, None));
+  };
+  let stdio = std::mem::take(&mut worker_thread.stdio);
+  let mut add_resource = |pipe: Option<PipeRead>| -> Result<_, AnyError> {
+    let Some(pipe) = pipe else {
+      return Ok(None);
+    };
+    let resource = PipeReadResource::from(pipe.into_async()?);
+    Ok(Some(state.resource_table.add(resource)))
+  };
+  Ok((add_resource(stdio.stdout)?, add_resource(stdio.stderr)?))
+}
+
+/// Takes a heap snapshot of a worker as the host. Returns `None` if the worker
+/// isn't running anymore.
+#[op2(async)]
+#[serde]
+async fn op_host_worker_heap_snapshot(
+  state: Rc<RefCell<OpState>>,
+  #[serde] id: WorkerId,
+) -> Option<ToJsBuffer> {
+  let worker_handle = {
+    let state = state.borrow();
+    let worker_thread = state.borrow::<WorkersTable>().get(&id)?;
+    worker_thread.worker_handle.clone()
+  };
+  let snapshot = worker_handle.take_heap_snapshot().await?;
+  Some(snapshot.into())
+}
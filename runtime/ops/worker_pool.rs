@@ -0,0 +1,215 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! `Deno.WorkerPool`: a fixed number of module workers that run tasks.
+//!
+//! Every worker slot of a pool takes the next task from a queue that is shared
+//! by all slots, posts it to its worker and waits for the first message the
+//! worker posts back, which is the result of the task. Slots wait for the
+//! queue in turn, so tasks go round-robin to the workers that are idle.
+//!
+//! Workers are started when their slot gets its first task. A worker that
+//! exits, because of an uncaught error or a resource limit, fails the task it
+//! was running and is replaced when the slot gets its next task.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use deno_core::error::generic_error;
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use deno_core::error::JsError;
+use deno_core::op2;
+use deno_core::unsync::spawn;
+use deno_core::AsyncRefCell;
+use deno_core::CancelFuture;
+use deno_core::CancelHandle;
+use deno_core::OpState;
+use deno_core::RcRef;
+use deno_core::Resource;
+use deno_core::ResourceId;
+use deno_web::JsMessageData;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+
+use crate::ops::worker_host::CreateWorkerArgs;
+use crate::ops::worker_host::WorkerSpawner;
+use crate::web_worker::WebWorkerHandle;
+use crate::web_worker::WorkerControlEvent;
+
+struct Task {
+  data: JsMessageData,
+  result: oneshot::Sender<Result<JsMessageData, AnyError>>,
+}
+
+pub struct WorkerPool {
+  tasks: RefCell<Option<mpsc::UnboundedSender<Task>>>,
+  queue: AsyncRefCell<mpsc::UnboundedReceiver<Task>>,
+  spawner: WorkerSpawner,
+  cancel: CancelHandle,
+}
+
+impl Resource for WorkerPool {
+  fn name(&self) -> Cow<str> {
+    "workerPool".into()
+  }
+
+  fn close(self: Rc<Self>) {
+    self.tasks.borrow_mut().take();
+    self.cancel.cancel();
+  }
+}
+
+/// The worker of a slot, which is terminated when it is replaced or when the
+/// pool is closed.
+#[derive(Default)]
+struct Slot(Option<WebWorkerHandle>);
+
+impl Slot {
+  fn discard(&mut self) {
+    if let Some(handle) = self.0.take() {
+      handle.terminate();
+    }
+  }
+}
+
+impl Drop for Slot {
+  fn drop(&mut self) {
+    self.discard();
+  }
+}
+
+fn pool_terminated() -> AnyError {
+  generic_error("Worker pool was terminated")
+}
+
+/// The error a task fails with when its worker exits.
+fn exit_error(event: Result<Option<WorkerControlEvent>, AnyError>) -> AnyError {
+  match event {
+    Ok(Some(
+      WorkerControlEvent::TerminalError(err) | WorkerControlEvent::Error(err),
+    )) => match err.downcast_ref::<JsError>() {
+      Some(js_error) => generic_error(js_error.exception_message.clone()),
+      None => err,
+    },
+    Ok(_) => generic_error("Worker exited before finishing the task"),
+    Err(err) => err,
+  }
+}
+
+async fn run_task(
+  state: &Rc<RefCell<OpState>>,
+  handle: &WebWorkerHandle,
+  data: JsMessageData,
+) -> Result<JsMessageData, AnyError> {
+  handle.port.send(&mut state.borrow_mut(), data)?;
+  let message = tokio::select! {
+    biased;
+    message = handle.port.recv(state.clone()) => message?,
+    event = handle.get_control_event() => return Err(exit_error(event)),
+  };
+  match message {
+    Some(data) => Ok(data),
+    None => Err(exit_error(handle.get_control_event().await)),
+  }
+}
+
+async fn run_slot(state: Rc<RefCell<OpState>>, pool: Rc<WorkerPool>) {
+  let mut slot = Slot::default();
+  loop {
+    let next = {
+      let mut queue = RcRef::map(&pool, |p| &p.queue).borrow_mut().await;
+      match &slot.0 {
+        Some(handle) => tokio::select! {
+          biased;
+          task = queue.recv() => Some(task),
+          _ = handle.get_control_event() => None,
+        },
+        None => Some(queue.recv().await),
+      }
+    };
+    let task = match next {
+      Some(Some(task)) => task,
+      // The pool was closed.
+      Some(None) => return,
+      // A worker that exits while idle is dropped right away.
+      None => {
+        slot.discard();
+        continue;
+      }
+    };
+
+    if slot.0.is_none() {
+      match pool.spawner.spawn(None) {
+        Ok((_, handle, _)) => slot.0 = Some(handle),
+        Err(err) => {
+          _ = task.result.send(Err(err));
+          continue;
+        }
+      }
+    }
+    let handle = slot.0.clone().unwrap();
+    let result = run_task(&state, &handle, task.data).await;
+    if result.is_err() {
+      slot.discard();
+    }
+    _ = task.result.send(result);
+  }
+}
+
+/// Creates a pool of `size` module workers.
+#[op2]
+#[smi]
+pub fn op_worker_pool_create(
+  state: Rc<RefCell<OpState>>,
+  #[serde] args: CreateWorkerArgs,
+  #[smi] size: u32,
+) -> Result<ResourceId, AnyError> {
+  if size == 0 {
+    return Err(type_error("Worker pool size must be greater than 0"));
+  }
+  let spawner = WorkerSpawner::new(&mut state.borrow_mut(), args)?;
+  let (tasks, queue) = mpsc::unbounded_channel();
+  let pool = Rc::new(WorkerPool {
+    tasks: RefCell::new(Some(tasks)),
+    queue: AsyncRefCell::new(queue),
+    spawner,
+    cancel: CancelHandle::default(),
+  });
+  for _ in 0..size {
+    let state = state.clone();
+    let pool = pool.clone();
+    spawn(async move {
+      let cancel = RcRef::map(&pool, |p| &p.cancel);
+      _ = run_slot(state, pool.clone()).or_cancel(cancel).await;
+    });
+  }
+  let rid = state.borrow_mut().resource_table.add_rc(pool);
+  Ok(rid)
+}
+
+/// Runs a task on the next idle worker of a pool, resolving with the first
+/// message the worker posts back.
+#[op2(async)]
+#[serde]
+pub async fn op_worker_pool_run(
+  state: Rc<RefCell<OpState>>,
+  #[smi] rid: ResourceId,
+  #[serde] data: JsMessageData,
+) -> Result<JsMessageData, AnyError> {
+  // The pool may have been closed before the op was polled.
+  let pool = state
+    .borrow()
+    .resource_table
+    .get::<WorkerPool>(rid)
+    .map_err(|_| pool_terminated())?;
+  let (result, receiver) = oneshot::channel();
+  pool
+    .tasks
+    .borrow()
+    .as_ref()
+    .and_then(|tasks| tasks.send(Task { data, result }).ok())
+    .ok_or_else(pool_terminated)?;
+  drop(pool);
+  receiver.await.map_err(|_| pool_terminated())?
+}
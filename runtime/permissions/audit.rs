@@ -0,0 +1,123 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! An opt-in log of every permission check, written as one JSON object per
+//! line so that it can be processed by other tools. It is shared by all
+//! threads of the process, so checks of workers end up in it too.
+
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use deno_core::error::AnyError;
+use deno_core::parking_lot::Mutex;
+use deno_core::serde_json;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+static AUDIT_LOG: OnceCell<Mutex<Box<dyn Write + Send>>> = OnceCell::new();
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuditRecord<'a> {
+  /// Milliseconds since the unix epoch.
+  timestamp: u64,
+  permission: &'a str,
+  /// `None` for checks of the permission as a whole.
+  descriptor: Option<String>,
+  api_name: Option<&'a str>,
+  result: &'static str,
+}
+
+/// Appends a record of every following permission check to the file at
+/// `path`, which is created if it doesn't exist.
+pub fn open_audit_log(path: &Path) -> Result<(), AnyError> {
+  // the audit log is written by the runtime itself, not on behalf of the user
+  #[allow(clippy::disallowed_methods)]
+  let file = std::fs::OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(path)?;
+  set_audit_writer(Box::new(file))
+}
+
+/// Like `open_audit_log()`, but writes the records to `writer`. The audit
+/// log can only be set once per process.
+pub fn set_audit_writer(writer: Box<dyn Write + Send>) -> Result<(), AnyError> {
+  AUDIT_LOG
+    .set(Mutex::new(writer))
+    .map_err(|_| AnyError::msg("The permission audit log was already set"))
+}
+
+/// Records the outcome of a permission check and passes it through. The
+/// descriptor is only formatted if the audit log is enabled.
+#[inline(always)]
+pub(crate) fn audited(
+  permission: &str,
+  descriptor: impl FnOnce() -> Option<String>,
+  api_name: Option<&str>,
+  result: Result<(), AnyError>,
+) -> Result<(), AnyError> {
+  if let Some(log) = AUDIT_LOG.get() {
+    let granted = result.is_ok();
+    write_record(
+      &mut **log.lock(),
+      permission,
+      descriptor(),
+      api_name,
+      granted,
+    );
+  }
+  result
+}
+
+fn write_record(
+  writer: &mut dyn Write,
+  permission: &str,
+  descriptor: Option<String>,
+  api_name: Option<&str>,
+  granted: bool,
+) {
+  let timestamp = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or_default();
+  let record = AuditRecord {
+    timestamp,
+    permission,
+    descriptor,
+    api_name,
+    result: if granted { "granted" } else { "denied" },
+  };
+  let mut line = serde_json::to_vec(&record).unwrap();
+  line.push(b'\n');
+  // a single write keeps the lines of concurrent processes apart
+  if let Err(err) = writer.write_all(&line) {
+    log::warn!("Failed to write to the permission audit log: {err}");
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn record_is_a_json_line() {
+    let mut log = Vec::new();
+    write_record(&mut log, "run", Some("git".to_string()), Some("api"), false);
+    write_record(&mut log, "env", None, None, true);
+    let lines = String::from_utf8(log).unwrap();
+    let records = lines
+      .lines()
+      .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+      .collect::<Vec<_>>();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["permission"], "run");
+    assert_eq!(records[0]["descriptor"], "git");
+    assert_eq!(records[0]["apiName"], "api");
+    assert_eq!(records[0]["result"], "denied");
+    assert!(records[0]["timestamp"].as_u64().unwrap() > 0);
+    assert_eq!(records[1]["descriptor"], serde_json::Value::Null);
+    assert_eq!(records[1]["result"], "granted");
+  }
+}
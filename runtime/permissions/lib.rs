@@ -20,6 +20,7 @@ use deno_terminal::colors;
 use fqdn::FQDN;
 use once_cell::sync::Lazy;
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::ffi::OsStr;
 use std::fmt;
@@ -34,13 +35,19 @@ use std::string::ToString;
 use std::sync::Arc;
 use which::which;
 
+mod audit;
 pub mod prompter;
+mod tokens;
+use audit::audited;
 use prompter::permission_prompt;
 use prompter::PromptResponse;
 use prompter::PERMISSION_EMOJI;
 
+pub use audit::open_audit_log;
+pub use audit::set_audit_writer;
 pub use prompter::set_prompt_callbacks;
 pub use prompter::PromptCallback;
+pub use tokens::RunPermissionTokens;
 
 /// Fast exit from permission check routines if this permission
 /// is in the "fully-granted" state.
@@ -158,9 +165,22 @@ impl PermissionState {
     info: Option<&str>,
     prompt: bool,
   ) -> (Result<(), AnyError>, bool, bool) {
-    self.check2(name, api_name, || info.map(|s| s.to_string()), prompt)
+    let (result, response) = self.check2(
+      name,
+      api_name,
+      || info.map(|s| s.to_string()),
+      prompt,
+      false,
+    );
+    (
+      result,
+      response.is_some(),
+      response == Some(PromptResponse::AllowAll),
+    )
   }
 
+  /// Check the permission state. The response is `Some` if a prompt was
+  /// issued.
   #[inline]
   fn check2(
     self,
@@ -168,11 +188,12 @@ impl PermissionState {
     api_name: Option<&str>,
     info: impl Fn() -> Option<String>,
     prompt: bool,
-  ) -> (Result<(), AnyError>, bool, bool) {
+    allow_binary: bool,
+  ) -> (Result<(), AnyError>, Option<PromptResponse>) {
     match self {
       PermissionState::Granted => {
         Self::log_perm_access(name, info);
-        (Ok(()), false, false)
+        (Ok(()), None)
       }
       PermissionState::Prompt if prompt => {
         let msg = format!(
@@ -182,19 +203,17 @@ impl PermissionState {
             .map(|info| { format!(" to {info}") })
             .unwrap_or_default(),
         );
-        match permission_prompt(&msg, name, api_name, true) {
-          PromptResponse::Allow => {
-            Self::log_perm_access(name, info);
-            (Ok(()), true, false)
+        match permission_prompt(&msg, name, api_name, true, allow_binary) {
+          PromptResponse::Deny => {
+            (Err(Self::error(name, info)), Some(PromptResponse::Deny))
           }
-          PromptResponse::AllowAll => {
+          response => {
             Self::log_perm_access(name, info);
-            (Ok(()), true, true)
+            (Ok(()), Some(response))
           }
-          PromptResponse::Deny => (Err(Self::error(name, info)), true, false),
         }
       }
-      _ => (Err(Self::error(name, info)), false, false),
+      _ => (Err(Self::error(name, info)), None),
     }
   }
 }
@@ -231,6 +250,7 @@ impl UnitPermission {
           self.name,
           Some("Deno.permissions.query()"),
           false,
+          false,
         )
       {
         self.state = PermissionState::Granted;
@@ -336,6 +356,13 @@ pub trait Descriptor: Eq + Clone + Hash {
   fn aliases(&self) -> Vec<Self> {
     vec![]
   }
+  /// The descriptors by which restrictions on the arguments of this one are
+  /// looked up, see `UnaryPermission::granted_args`.
+  fn restriction_keys(&self) -> Vec<Self> {
+    let mut keys = self.aliases();
+    keys.insert(0, self.clone());
+    keys
+  }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -346,6 +373,9 @@ pub struct UnaryPermission<T: Descriptor + Hash> {
   flag_denied_list: HashSet<T>,
   prompt_denied_global: bool,
   prompt_denied_list: HashSet<T>,
+  /// The first arguments that granted descriptors are restricted to, which
+  /// only applies to run permissions given as `--allow-run=program:arg`.
+  granted_args: HashMap<T, HashSet<String>>,
   prompt: bool,
 }
 
@@ -358,6 +388,7 @@ impl<T: Descriptor + Hash> Default for UnaryPermission<T> {
       flag_denied_list: Default::default(),
       prompt_denied_global: Default::default(),
       prompt_denied_list: Default::default(),
+      granted_args: Default::default(),
       prompt: Default::default(),
     }
   }
@@ -391,9 +422,28 @@ impl<T: Descriptor + Hash> UnaryPermission<T> {
     assert_non_partial: bool,
     api_name: Option<&str>,
     get_display_name: impl Fn() -> Option<String>,
+  ) -> Result<(), AnyError> {
+    self.check_desc_or_binary(
+      desc,
+      None,
+      assert_non_partial,
+      api_name,
+      get_display_name,
+    )
+  }
+
+  /// Like `check_desc`, but if `binary` is given a prompt additionally offers
+  /// to allow all future runs of it, which grants `binary` instead of `desc`.
+  fn check_desc_or_binary(
+    &mut self,
+    desc: Option<&T>,
+    binary: Option<&T>,
+    assert_non_partial: bool,
+    api_name: Option<&str>,
+    get_display_name: impl Fn() -> Option<String>,
   ) -> Result<(), AnyError> {
     skip_check_if_is_permission_fully_granted!(self);
-    let (result, prompted, is_allow_all) = self
+    let (result, response) = self
       .query_desc(desc, AllowPartial::from(!assert_non_partial))
       .check2(
         T::flag_name(),
@@ -403,17 +453,16 @@ impl<T: Descriptor + Hash> UnaryPermission<T> {
           None => desc.map(|d| format!("\"{}\"", d.name())),
         },
         self.prompt,
+        binary.is_some(),
       );
-    if prompted {
-      if result.is_ok() {
-        if is_allow_all {
-          self.insert_granted(None);
-        } else {
-          self.insert_granted(desc.cloned());
-        }
-      } else {
-        self.insert_prompt_denied(desc.cloned());
+    match response {
+      Some(PromptResponse::Allow) => self.insert_granted(desc.cloned()),
+      Some(PromptResponse::AllowAll) => self.insert_granted(None),
+      Some(PromptResponse::AllowBinary) => {
+        self.insert_granted(binary.or(desc).cloned())
       }
+      Some(PromptResponse::Deny) => self.insert_prompt_denied(desc.cloned()),
+      None => {}
     }
     result
   }
@@ -492,8 +541,9 @@ impl<T: Descriptor + Hash> UnaryPermission<T> {
       T::flag_name(),
       Some("Deno.permissions.request()"),
       true,
+      false,
     ) {
-      PromptResponse::Allow => {
+      PromptResponse::Allow | PromptResponse::AllowBinary => {
         self.insert_granted(desc.cloned());
         PermissionState::Granted
       }
@@ -564,6 +614,17 @@ impl<T: Descriptor + Hash> UnaryPermission<T> {
   }
 
   fn insert_granted(&mut self, desc: Option<T>) {
+    // a grant from a prompt isn't restricted to any arguments
+    if !self.granted_args.is_empty() {
+      match &desc {
+        Some(desc) => {
+          for key in desc.restriction_keys() {
+            self.granted_args.remove(&key);
+          }
+        }
+        None => self.granted_args.clear(),
+      }
+    }
     Self::list_insert(desc, &mut self.granted_global, &mut self.granted_list);
   }
 
@@ -620,6 +681,13 @@ impl<T: Descriptor + Hash> UnaryPermission<T> {
         {
           return Err(escalation_error());
         }
+        // granting a descriptor can't lift the restrictions on its arguments
+        perms.granted_args = self
+          .granted_args
+          .iter()
+          .filter(|(desc, _)| perms.granted_list.contains(*desc))
+          .map(|(desc, args)| (desc.clone(), args.clone()))
+          .collect();
       }
     }
     perms.flag_denied_global = self.flag_denied_global;
@@ -632,6 +700,102 @@ impl<T: Descriptor + Hash> UnaryPermission<T> {
 
     Ok(perms)
   }
+
+  fn snapshot(&self) -> UnaryPermissionSnapshot {
+    UnaryPermissionSnapshot {
+      granted_global: self.granted_global,
+      granted: snapshot_names(&self.granted_list),
+      flag_denied_global: self.flag_denied_global,
+      flag_denied: snapshot_names(&self.flag_denied_list),
+      prompt_denied_global: self.prompt_denied_global,
+      prompt_denied: snapshot_names(&self.prompt_denied_list),
+      prompt: self.prompt,
+    }
+  }
+
+  /// Replays a snapshot, where `new` parses the allow and deny lists like
+  /// their flags.
+  fn from_snapshot(
+    snapshot: &UnaryPermissionSnapshot,
+    new: impl FnOnce(
+      &Option<Vec<T::Arg>>,
+      &Option<Vec<T::Arg>>,
+      bool,
+    ) -> Result<Self, AnyError>,
+  ) -> Result<Self, AnyError> {
+    fn flag_list<A: From<String>>(
+      global: bool,
+      list: &[String],
+    ) -> Option<Vec<A>> {
+      if global {
+        Some(vec![])
+      } else if list.is_empty() {
+        None
+      } else {
+        Some(list.iter().cloned().map(From::from).collect())
+      }
+    }
+
+    let mut perm = new(
+      &flag_list(snapshot.granted_global, &snapshot.granted),
+      &flag_list(snapshot.flag_denied_global, &snapshot.flag_denied),
+      snapshot.prompt,
+    )?;
+    perm.prompt_denied_global = snapshot.prompt_denied_global;
+    perm.prompt_denied_list = T::parse(&Some(
+      snapshot
+        .prompt_denied
+        .iter()
+        .cloned()
+        .map(From::from)
+        .collect(),
+    ))?;
+    Ok(perm)
+  }
+
+  /// Makes sure that permissions replayed from a snapshot for a child don't
+  /// exceed those of the parent, and carries over the parent's denials.
+  fn restrict_to_parent(&mut self, parent: &mut Self) -> Result<(), AnyError> {
+    if self.granted_global && parent.check_all_api(None).is_err() {
+      return Err(escalation_error());
+    }
+    if !self
+      .granted_list
+      .iter()
+      .all(|desc| desc.check_in_permission(parent, None).is_ok())
+    {
+      return Err(escalation_error());
+    }
+    for (desc, args) in &parent.granted_args {
+      if self.granted_global || self.granted_list.contains(desc) {
+        let args = match self.granted_args.get(desc) {
+          Some(child_args) => child_args.intersection(args).cloned().collect(),
+          None => args.clone(),
+        };
+        self.granted_args.insert(desc.clone(), args);
+      }
+    }
+    self.flag_denied_global |= parent.flag_denied_global;
+    self
+      .flag_denied_list
+      .extend(parent.flag_denied_list.iter().cloned());
+    self.prompt_denied_global |= parent.prompt_denied_global;
+    self
+      .prompt_denied_list
+      .extend(parent.prompt_denied_list.iter().cloned());
+    self.prompt &= parent.prompt;
+    Ok(())
+  }
+}
+
+fn snapshot_names<T: Descriptor>(list: &HashSet<T>) -> Vec<String> {
+  let mut names = list
+    .iter()
+    .map(|desc| desc.name().into_owned())
+    .collect::<Vec<_>>();
+  names.sort();
+  names.dedup();
+  names
 }
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
@@ -850,6 +1014,35 @@ impl EnvDescriptor {
   pub fn new(env: impl AsRef<str>) -> Self {
     Self(EnvVarName::new(env))
   }
+
+  /// Whether this is a pattern like `AWS_*`, in which `*` matches any
+  /// sequence of characters.
+  pub fn is_pattern(&self) -> bool {
+    self.0.as_ref().contains('*')
+  }
+}
+
+/// Matches `name` against a pattern in which `*` matches any sequence of
+/// characters.
+fn env_pattern_matches(pattern: &str, name: &str) -> bool {
+  let mut parts = pattern.split('*');
+  // there's always a first part
+  let first = parts.next().unwrap();
+  let Some(mut rest) = name.strip_prefix(first) else {
+    return false;
+  };
+  let mut parts = parts.collect::<Vec<_>>();
+  let Some(last) = parts.pop() else {
+    // no `*`, so the whole name has to match
+    return rest.is_empty();
+  };
+  for part in parts {
+    match rest.find(part) {
+      Some(index) => rest = &rest[index + part.len()..],
+      None => return false,
+    }
+  }
+  rest.len() >= last.len() && rest.ends_with(last)
 }
 
 impl Descriptor for EnvDescriptor {
@@ -875,6 +1068,14 @@ impl Descriptor for EnvDescriptor {
   fn name(&self) -> Cow<str> {
     Cow::from(self.0.as_ref())
   }
+
+  fn stronger_than(&self, other: &Self) -> bool {
+    if self.is_pattern() {
+      env_pattern_matches(self.0.as_ref(), other.0.as_ref())
+    } else {
+      self == other
+    }
+  }
 }
 
 impl AsRef<str> for EnvDescriptor {
@@ -926,6 +1127,31 @@ impl Descriptor for RunDescriptor {
       RunDescriptor::Path(_) => vec![],
     }
   }
+
+  fn restriction_keys(&self) -> Vec<Self> {
+    // restrictions apply no matter through which name, path or symlink the
+    // binary is run
+    let mut keys = vec![self.clone()];
+    keys.extend(self.aliases());
+    keys.extend(self.binary());
+    keys
+  }
+}
+
+impl RunDescriptor {
+  /// The canonical path of the binary that is executed when running this,
+  /// which is the same no matter through which name, path or symlink it is
+  /// run.
+  fn binary(&self) -> Option<Self> {
+    let path = match self {
+      RunDescriptor::Name(name) => which(name).ok()?,
+      RunDescriptor::Path(path) => path.clone(),
+    };
+    // spawning a binary always uses the real file system, which is what the
+    // permission applies to
+    #[allow(clippy::disallowed_methods)]
+    path.canonicalize().ok().map(Self::from)
+  }
 }
 
 impl From<String> for RunDescriptor {
@@ -1268,6 +1494,12 @@ impl UnaryPermission<EnvDescriptor> {
     skip_check_if_is_permission_fully_granted!(self);
     self.check_desc(None, false, None, || None)
   }
+
+  /// Whether access was granted to a namespace of variables with a pattern
+  /// like `--allow-env=AWS_*`.
+  pub fn has_granted_patterns(&self) -> bool {
+    self.granted_list.iter().any(EnvDescriptor::is_pattern)
+  }
 }
 
 impl UnaryPermission<SysDescriptor> {
@@ -1334,8 +1566,29 @@ impl UnaryPermission<RunDescriptor> {
     api_name: Option<&str>,
   ) -> Result<(), AnyError> {
     skip_check_if_is_permission_fully_granted!(self);
-    self.check_desc(
-      Some(&RunDescriptor::from(cmd.to_string())),
+    let desc = RunDescriptor::from(cmd.to_string());
+    let binary = desc.binary();
+    if self.is_flag_denied(binary.as_ref()) {
+      return Err(PermissionState::error(RunDescriptor::flag_name(), || {
+        Some(format!("\"{}\"", cmd))
+      }));
+    }
+    // A binary whose future runs were all allowed in a prompt is allowed by
+    // any name, as long as that name wasn't denied.
+    if let Some(binary) = &binary {
+      if self.is_granted(Some(binary))
+        && self.query_desc(Some(&desc), AllowPartial::TreatAsGranted)
+          != PermissionState::Denied
+      {
+        PermissionState::log_perm_access(RunDescriptor::flag_name(), || {
+          Some(format!("\"{}\"", cmd))
+        });
+        return Ok(());
+      }
+    }
+    self.check_desc_or_binary(
+      Some(&desc),
+      binary.as_ref(),
       false,
       api_name,
       || Some(format!("\"{}\"", cmd)),
@@ -1346,6 +1599,105 @@ impl UnaryPermission<RunDescriptor> {
     skip_check_if_is_permission_fully_granted!(self);
     self.check_desc(None, false, api_name, || None)
   }
+
+  /// Whether `cmd` was denied by `--deny-run`, by any name or path.
+  fn is_denied_by_flag(&self, cmd: &str) -> bool {
+    let desc = RunDescriptor::from(cmd.to_string());
+    self.is_flag_denied(Some(&desc))
+      || desc
+        .binary()
+        .is_some_and(|binary| self.is_flag_denied(Some(&binary)))
+  }
+
+  /// Checks that a token for running `cmd` may be minted. Unlike `check()`,
+  /// a prompt doesn't grant running `cmd` outside of the token.
+  fn check_for_token(
+    &mut self,
+    cmd: &str,
+    api_name: Option<&str>,
+  ) -> Result<(), AnyError> {
+    let desc = RunDescriptor::from(cmd.to_string());
+    let info = || Some(format!("\"{}\"", cmd));
+    if self.is_denied_by_flag(cmd) {
+      return Err(PermissionState::error(RunDescriptor::flag_name(), info));
+    }
+    match self.query_desc(Some(&desc), AllowPartial::TreatAsGranted) {
+      PermissionState::Granted => Ok(()),
+      PermissionState::Prompt if self.prompt => {
+        let msg = format!("run access to \"{cmd}\" for a permission token");
+        match permission_prompt(
+          &msg,
+          RunDescriptor::flag_name(),
+          api_name,
+          false,
+          false,
+        ) {
+          PromptResponse::Deny => {
+            Err(PermissionState::error(RunDescriptor::flag_name(), info))
+          }
+          _ => Ok(()),
+        }
+      }
+      _ => Err(PermissionState::error(RunDescriptor::flag_name(), info)),
+    }
+  }
+
+  /// Like the generic `snapshot()`, but keeps the restrictions on the first
+  /// argument as `program:arg` entries.
+  fn snapshot_run(&self) -> UnaryPermissionSnapshot {
+    let mut granted = self
+      .granted_list
+      .iter()
+      .flat_map(|desc| match self.granted_args.get(desc) {
+        Some(args) => args
+          .iter()
+          .map(|arg| format!("{}:{arg}", desc.name()))
+          .collect(),
+        None => vec![desc.name().into_owned()],
+      })
+      .collect::<Vec<_>>();
+    granted.sort();
+    granted.dedup();
+    UnaryPermissionSnapshot {
+      granted,
+      ..self.snapshot()
+    }
+  }
+
+  /// Checks that `cmd` is run with one of the first arguments it was
+  /// restricted to, if it was granted as `--allow-run=program:arg`. This is
+  /// in addition to `check()`.
+  pub fn check_args(&self, cmd: &str, args: &[String]) -> Result<(), AnyError> {
+    if self.granted_args.is_empty() {
+      return Ok(());
+    }
+    let desc = RunDescriptor::from(cmd.to_string());
+    let restrictions = desc
+      .restriction_keys()
+      .into_iter()
+      .filter_map(|key| self.granted_args.get(&key))
+      .collect::<Vec<_>>();
+    if restrictions.is_empty() {
+      return Ok(());
+    }
+    match args.first() {
+      Some(arg) if restrictions.iter().any(|allowed| allowed.contains(arg)) => {
+        Ok(())
+      }
+      Some(arg) => Err(custom_error(
+        "PermissionDenied",
+        format!(
+          "Requires run access to \"{cmd}\" with the argument \"{arg}\", run again with the --allow-run flag"
+        ),
+      )),
+      None => Err(custom_error(
+        "PermissionDenied",
+        format!(
+          "Requires run access to \"{cmd}\" without arguments, run again with the --allow-run flag"
+        ),
+      )),
+    }
+  }
 }
 
 impl UnaryPermission<FfiDescriptor> {
@@ -1416,6 +1768,7 @@ pub struct Permissions {
   pub run: UnaryPermission<RunDescriptor>,
   pub ffi: UnaryPermission<FfiDescriptor>,
   pub all: UnitPermission,
+  pub run_tokens: RunPermissionTokens,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Default, Serialize, Deserialize)]
@@ -1438,6 +1791,34 @@ pub struct PermissionsOptions {
   pub prompt: bool,
 }
 
+/// The full state of a set of permissions, which can be persisted as JSON and
+/// replayed with `Permissions::from_snapshot()`. Descriptors are kept in the
+/// form they are given on the command line.
+#[derive(Clone, Debug, Eq, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PermissionsSnapshot {
+  pub all: bool,
+  pub read: UnaryPermissionSnapshot,
+  pub write: UnaryPermissionSnapshot,
+  pub net: UnaryPermissionSnapshot,
+  pub env: UnaryPermissionSnapshot,
+  pub sys: UnaryPermissionSnapshot,
+  pub run: UnaryPermissionSnapshot,
+  pub ffi: UnaryPermissionSnapshot,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct UnaryPermissionSnapshot {
+  pub granted_global: bool,
+  pub granted: Vec<String>,
+  pub flag_denied_global: bool,
+  pub flag_denied: Vec<String>,
+  pub prompt_denied_global: bool,
+  pub prompt_denied: Vec<String>,
+  pub prompt: bool,
+}
+
 impl Permissions {
   pub fn new_unary<T>(
     allow_list: &Option<Vec<T::Arg>>,
@@ -1457,6 +1838,30 @@ impl Permissions {
     })
   }
 
+  /// Like `new_unary()`, but the allow list may restrict the first argument
+  /// of a program, as in `git:status`.
+  pub fn new_run(
+    allow_list: &Option<Vec<String>>,
+    deny_list: &Option<Vec<String>>,
+    prompt: bool,
+  ) -> Result<UnaryPermission<RunDescriptor>, AnyError> {
+    let (allow_list, granted_args) = parse_run_constraints(allow_list)?;
+    let mut perm = UnaryPermission {
+      granted_args,
+      ..Permissions::new_unary(&allow_list, deny_list, prompt)?
+    };
+    // Denied programs are also denied by their canonical binary, so that they
+    // can't be run through a symlink or another path. This happens regardless
+    // of the allow list, as denials always take precedence.
+    let binaries = perm
+      .flag_denied_list
+      .iter()
+      .filter_map(RunDescriptor::binary)
+      .collect::<Vec<_>>();
+    perm.flag_denied_list.extend(binaries);
+    Ok(perm)
+  }
+
   pub const fn new_all(allow_state: bool) -> UnitPermission {
     unit_permission_from_flag_bools(
       allow_state,
@@ -1494,17 +1899,61 @@ impl Permissions {
         &opts.deny_sys,
         opts.prompt,
       )?,
-      run: Permissions::new_unary(
-        &opts.allow_run,
-        &opts.deny_run,
-        opts.prompt,
-      )?,
+      run: Permissions::new_run(&opts.allow_run, &opts.deny_run, opts.prompt)?,
       ffi: Permissions::new_unary(
         &opts.allow_ffi,
         &opts.deny_ffi,
         opts.prompt,
       )?,
       all: Permissions::new_all(opts.allow_all),
+      run_tokens: Default::default(),
+    })
+  }
+
+  pub fn snapshot(&self) -> PermissionsSnapshot {
+    PermissionsSnapshot {
+      all: self.all.query() == PermissionState::Granted,
+      read: self.read.snapshot(),
+      write: self.write.snapshot(),
+      net: self.net.snapshot(),
+      env: self.env.snapshot(),
+      sys: self.sys.snapshot(),
+      run: self.run.snapshot_run(),
+      ffi: self.ffi.snapshot(),
+    }
+  }
+
+  pub fn from_snapshot(
+    snapshot: &PermissionsSnapshot,
+  ) -> Result<Self, AnyError> {
+    Ok(Self {
+      read: UnaryPermission::from_snapshot(
+        &snapshot.read,
+        Permissions::new_unary,
+      )?,
+      write: UnaryPermission::from_snapshot(
+        &snapshot.write,
+        Permissions::new_unary,
+      )?,
+      net: UnaryPermission::from_snapshot(
+        &snapshot.net,
+        Permissions::new_unary,
+      )?,
+      env: UnaryPermission::from_snapshot(
+        &snapshot.env,
+        Permissions::new_unary,
+      )?,
+      sys: UnaryPermission::from_snapshot(
+        &snapshot.sys,
+        Permissions::new_unary,
+      )?,
+      run: UnaryPermission::from_snapshot(&snapshot.run, Permissions::new_run)?,
+      ffi: UnaryPermission::from_snapshot(
+        &snapshot.ffi,
+        Permissions::new_unary,
+      )?,
+      all: Permissions::new_all(snapshot.all),
+      run_tokens: Default::default(),
     })
   }
 
@@ -1519,6 +1968,7 @@ impl Permissions {
       run: UnaryPermission::allow_all(),
       ffi: UnaryPermission::allow_all(),
       all: Permissions::new_all(true),
+      run_tokens: Default::default(),
     }
   }
 
@@ -1542,6 +1992,7 @@ impl Permissions {
       run: Permissions::new_unary(&None, &None, prompt).unwrap(),
       ffi: Permissions::new_unary(&None, &None, prompt).unwrap(),
       all: Permissions::new_all(false),
+      run_tokens: Default::default(),
     }
   }
 
@@ -1553,14 +2004,24 @@ impl Permissions {
   ) -> Result<(), AnyError> {
     match specifier.scheme() {
       "file" => match specifier.to_file_path() {
-        Ok(path) => self.read.check(&path, Some("import()")),
+        Ok(path) => audited(
+          "read",
+          || Some(path.display().to_string()),
+          Some("import()"),
+          self.read.check(&path, Some("import()")),
+        ),
         Err(_) => Err(uri_error(format!(
           "Invalid file path.\n  Specifier: {specifier}"
         ))),
       },
       "data" => Ok(()),
       "blob" => Ok(()),
-      _ => self.net.check_url(specifier, Some("import()")),
+      _ => audited(
+        "net",
+        || Some(specifier.to_string()),
+        Some("import()"),
+        self.net.check_url(specifier, Some("import()")),
+      ),
     }
   }
 }
@@ -1572,11 +2033,65 @@ impl Permissions {
 /// case might need to be mutated). Also for the Web Worker API we need a way
 /// to send permissions to a new thread.
 #[derive(Clone, Debug)]
-pub struct PermissionsContainer(pub Arc<Mutex<Permissions>>);
+pub struct PermissionsContainer(
+  pub Arc<Mutex<Permissions>>,
+  /// The permissions of workers that were created from these, which
+  /// revocations can be propagated to.
+  Arc<Mutex<Vec<WeakPermissionsContainer>>>,
+);
+
+/// A `PermissionsContainer` that doesn't keep the permissions of a worker
+/// alive after the worker terminated.
+#[derive(Debug)]
+struct WeakPermissionsContainer(
+  std::sync::Weak<Mutex<Permissions>>,
+  std::sync::Weak<Mutex<Vec<WeakPermissionsContainer>>>,
+);
+
+impl WeakPermissionsContainer {
+  fn upgrade(&self) -> Option<PermissionsContainer> {
+    Some(PermissionsContainer(self.0.upgrade()?, self.1.upgrade()?))
+  }
+}
 
 impl PermissionsContainer {
   pub fn new(perms: Permissions) -> Self {
-    Self(Arc::new(Mutex::new(perms)))
+    Self(Arc::new(Mutex::new(perms)), Default::default())
+  }
+
+  /// Like `new()`, but for the permissions of a worker that is created by the
+  /// owner of these permissions. They are then reached by
+  /// `for_each_descendant()`.
+  pub fn new_child(&self, perms: Permissions) -> Self {
+    let child = Self::new(perms);
+    let mut children = self.1.lock();
+    children.retain(|child| child.0.strong_count() > 0);
+    children.push(WeakPermissionsContainer(
+      Arc::downgrade(&child.0),
+      Arc::downgrade(&child.1),
+    ));
+    child
+  }
+
+  /// Calls `f` with the permissions of every worker which descends from
+  /// these permissions through `new_child()`, e.g. to propagate a revocation.
+  pub fn for_each_descendant(&self, mut f: impl FnMut(&mut Permissions)) {
+    let mut pending = self
+      .1
+      .lock()
+      .iter()
+      .filter_map(WeakPermissionsContainer::upgrade)
+      .collect::<Vec<_>>();
+    while let Some(container) = pending.pop() {
+      f(&mut container.0.lock());
+      pending.extend(
+        container
+          .1
+          .lock()
+          .iter()
+          .filter_map(WeakPermissionsContainer::upgrade),
+      );
+    }
   }
 
   pub fn allow_all() -> Self {
@@ -1597,7 +2112,12 @@ impl PermissionsContainer {
     path: &Path,
     api_name: &str,
   ) -> Result<(), AnyError> {
-    self.0.lock().read.check(path, Some(api_name))
+    audited(
+      "read",
+      || Some(path.display().to_string()),
+      Some(api_name),
+      self.0.lock().read.check(path, Some(api_name)),
+    )
   }
 
   #[inline(always)]
@@ -1606,7 +2126,12 @@ impl PermissionsContainer {
     path: &Path,
     api_name: Option<&str>,
   ) -> Result<(), AnyError> {
-    self.0.lock().read.check(path, api_name)
+    audited(
+      "read",
+      || Some(path.display().to_string()),
+      api_name,
+      self.0.lock().read.check(path, api_name),
+    )
   }
 
   #[inline(always)]
@@ -1616,12 +2141,22 @@ impl PermissionsContainer {
     display: &str,
     api_name: &str,
   ) -> Result<(), AnyError> {
-    self.0.lock().read.check_blind(path, display, api_name)
+    audited(
+      "read",
+      || Some(display.to_string()),
+      Some(api_name),
+      self.0.lock().read.check_blind(path, display, api_name),
+    )
   }
 
   #[inline(always)]
   pub fn check_read_all(&mut self, api_name: &str) -> Result<(), AnyError> {
-    self.0.lock().read.check_all(Some(api_name))
+    audited(
+      "read",
+      || None,
+      Some(api_name),
+      self.0.lock().read.check_all(Some(api_name)),
+    )
   }
 
   #[inline(always)]
@@ -1630,7 +2165,12 @@ impl PermissionsContainer {
     path: &Path,
     api_name: &str,
   ) -> Result<(), AnyError> {
-    self.0.lock().write.check(path, Some(api_name))
+    audited(
+      "write",
+      || Some(path.display().to_string()),
+      Some(api_name),
+      self.0.lock().write.check(path, Some(api_name)),
+    )
   }
 
   #[inline(always)]
@@ -1639,12 +2179,22 @@ impl PermissionsContainer {
     path: &Path,
     api_name: Option<&str>,
   ) -> Result<(), AnyError> {
-    self.0.lock().write.check(path, api_name)
+    audited(
+      "write",
+      || Some(path.display().to_string()),
+      api_name,
+      self.0.lock().write.check(path, api_name),
+    )
   }
 
   #[inline(always)]
   pub fn check_write_all(&mut self, api_name: &str) -> Result<(), AnyError> {
-    self.0.lock().write.check_all(Some(api_name))
+    audited(
+      "write",
+      || None,
+      Some(api_name),
+      self.0.lock().write.check_all(Some(api_name)),
+    )
   }
 
   #[inline(always)]
@@ -1654,7 +2204,12 @@ impl PermissionsContainer {
     display: &str,
     api_name: &str,
   ) -> Result<(), AnyError> {
-    self.0.lock().write.check_blind(path, display, api_name)
+    audited(
+      "write",
+      || Some(display.to_string()),
+      Some(api_name),
+      self.0.lock().write.check_blind(path, display, api_name),
+    )
   }
 
   #[inline(always)]
@@ -1663,53 +2218,127 @@ impl PermissionsContainer {
     path: &Path,
     api_name: &str,
   ) -> Result<(), AnyError> {
-    self.0.lock().write.check_partial(path, Some(api_name))
+    audited(
+      "write",
+      || Some(path.display().to_string()),
+      Some(api_name),
+      self.0.lock().write.check_partial(path, Some(api_name)),
+    )
   }
 
   #[inline(always)]
   pub fn check_run(
     &mut self,
     cmd: &str,
+    args: &[String],
+    api_name: &str,
+  ) -> Result<(), AnyError> {
+    let mut perms = self.0.lock();
+    let result = perms
+      .run
+      .check(cmd, Some(api_name))
+      .and_then(|_| perms.run.check_args(cmd, args));
+    audited("run", || Some(cmd.to_string()), Some(api_name), result)
+  }
+
+  /// Like `check_run()`, but a `token` which was minted for `cmd` takes the
+  /// place of run permission. Programs denied by `--deny-run` can't be run
+  /// with a token either, and the arguments stay restricted as by
+  /// `--allow-run=program:arg`.
+  pub fn check_run_with_token(
+    &mut self,
+    token: Option<&str>,
+    cmd: &str,
+    args: &[String],
     api_name: &str,
   ) -> Result<(), AnyError> {
-    self.0.lock().run.check(cmd, Some(api_name))
+    let Some(token) = token else {
+      return self.check_run(cmd, args, api_name);
+    };
+    let perms = self.0.lock();
+    let result = if perms.run.is_denied_by_flag(cmd) {
+      Err(PermissionState::error(RunDescriptor::flag_name(), || {
+        Some(format!("\"{}\"", cmd))
+      }))
+    } else if perms.run_tokens.grants(token, cmd) {
+      // a token doesn't lift the restrictions on the arguments of `cmd`
+      perms.run.check_args(cmd, args)
+    } else {
+      Err(custom_error(
+        "PermissionDenied",
+        format!("The permission token doesn't grant run access to \"{cmd}\""),
+      ))
+    };
+    audited("run", || Some(cmd.to_string()), Some(api_name), result)
+  }
+
+  /// Mints a token for running `cmd`, see `RunPermissionTokens`. Unless `cmd`
+  /// may already be run, the user is prompted without granting run access
+  /// for anything but the token.
+  pub fn mint_run_token(
+    &self,
+    cmd: &str,
+    api_name: &str,
+  ) -> Result<String, AnyError> {
+    let mut perms = self.0.lock();
+    perms.run.check_for_token(cmd, Some(api_name))?;
+    Ok(perms.run_tokens.mint(cmd))
+  }
+
+  pub fn revoke_run_token(&self, token: &str) -> bool {
+    self.0.lock().run_tokens.revoke(token)
   }
 
   #[inline(always)]
   pub fn check_run_all(&mut self, api_name: &str) -> Result<(), AnyError> {
-    self.0.lock().run.check_all(Some(api_name))
+    audited(
+      "run",
+      || None,
+      Some(api_name),
+      self.0.lock().run.check_all(Some(api_name)),
+    )
   }
 
   #[inline(always)]
   pub fn check_sys(&self, kind: &str, api_name: &str) -> Result<(), AnyError> {
-    self.0.lock().sys.check(kind, Some(api_name))
+    audited(
+      "sys",
+      || Some(kind.to_string()),
+      Some(api_name),
+      self.0.lock().sys.check(kind, Some(api_name)),
+    )
   }
 
   #[inline(always)]
   pub fn check_env(&mut self, var: &str) -> Result<(), AnyError> {
-    self.0.lock().env.check(var, None)
+    audited(
+      "env",
+      || Some(var.to_string()),
+      None,
+      self.0.lock().env.check(var, None),
+    )
   }
 
   #[inline(always)]
   pub fn check_env_all(&mut self) -> Result<(), AnyError> {
-    self.0.lock().env.check_all()
+    audited("env", || None, None, self.0.lock().env.check_all())
   }
 
   #[inline(always)]
   pub fn check_sys_all(&mut self) -> Result<(), AnyError> {
-    self.0.lock().sys.check_all()
+    audited("sys", || None, None, self.0.lock().sys.check_all())
   }
 
   #[inline(always)]
   pub fn check_ffi_all(&mut self) -> Result<(), AnyError> {
-    self.0.lock().ffi.check_all()
+    audited("ffi", || None, None, self.0.lock().ffi.check_all())
   }
 
   /// This checks to see if the allow-all flag was passed, not whether all
   /// permissions are enabled!
   #[inline(always)]
   pub fn check_was_allow_all_flag_passed(&mut self) -> Result<(), AnyError> {
-    self.0.lock().all.check()
+    audited("all", || None, None, self.0.lock().all.check())
   }
 
   /// Checks special file access, returning the failed permission type if
@@ -1822,7 +2451,12 @@ impl PermissionsContainer {
     url: &Url,
     api_name: &str,
   ) -> Result<(), AnyError> {
-    self.0.lock().net.check_url(url, Some(api_name))
+    audited(
+      "net",
+      || Some(url.to_string()),
+      Some(api_name),
+      self.0.lock().net.check_url(url, Some(api_name)),
+    )
   }
 
   #[inline(always)]
@@ -1833,12 +2467,22 @@ impl PermissionsContainer {
   ) -> Result<(), AnyError> {
     let hostname = host.0.as_ref().parse::<Host>()?;
     let descriptor = NetDescriptor(hostname, host.1);
-    self.0.lock().net.check(&descriptor, Some(api_name))
+    audited(
+      "net",
+      || Some(descriptor.to_string()),
+      Some(api_name),
+      self.0.lock().net.check(&descriptor, Some(api_name)),
+    )
   }
 
   #[inline(always)]
   pub fn check_ffi(&mut self, path: Option<&Path>) -> Result<(), AnyError> {
-    self.0.lock().ffi.check(path.unwrap(), None)
+    audited(
+      "ffi",
+      || path.map(|path| path.display().to_string()),
+      None,
+      self.0.lock().ffi.check(path.unwrap(), None),
+    )
   }
 
   #[inline(always)]
@@ -1846,7 +2490,12 @@ impl PermissionsContainer {
     &mut self,
     path: Option<&Path>,
   ) -> Result<(), AnyError> {
-    self.0.lock().ffi.check_partial(path)
+    audited(
+      "ffi",
+      || path.map(|path| path.display().to_string()),
+      None,
+      self.0.lock().ffi.check_partial(path),
+    )
   }
 }
 
@@ -1961,6 +2610,91 @@ fn parse_run_list(
   Ok(result)
 }
 
+/// Splits an `--allow-run` entry like `git:status` into the program and the
+/// first argument it is restricted to. The entry is split on the first colon,
+/// so the argument may contain colons itself, as in `docker:image:ls` or
+/// `curl:https://deno.land`. A colon following a drive letter belongs to the
+/// program, as in `C:\git.exe:status`, but programs can't contain colons
+/// otherwise.
+fn split_run_constraint(entry: &str) -> (&str, Option<&str>) {
+  let drive_len = match entry.as_bytes() {
+    [letter, b':', rest @ ..]
+      if letter.is_ascii_alphabetic()
+        && matches!(rest.first(), None | Some(b'/' | b'\\')) =>
+    {
+      2
+    }
+    _ => 0,
+  };
+  match entry[drive_len..].find(':') {
+    Some(index) if drive_len + index > 0 => {
+      let (program, arg) = entry.split_at(drive_len + index);
+      (program, Some(&arg[1..]))
+    }
+    _ => (entry, None),
+  }
+}
+
+type RunConstraints =
+  (Option<Vec<String>>, HashMap<RunDescriptor, HashSet<String>>);
+
+/// Separates the programs of a run allow list from the first arguments they
+/// are restricted to. A program which is also listed without an argument
+/// isn't restricted.
+fn parse_run_constraints(
+  list: &Option<Vec<String>>,
+) -> Result<RunConstraints, AnyError> {
+  let Some(list) = list else {
+    return Ok((None, HashMap::new()));
+  };
+  let mut programs = Vec::with_capacity(list.len());
+  let mut constraints: HashMap<String, HashSet<String>> = HashMap::new();
+  let mut unrestricted = HashSet::new();
+  for entry in list {
+    match split_run_constraint(entry) {
+      (_, Some("")) => {
+        return Err(AnyError::msg(format!(
+          "Empty argument is not allowed in \"{entry}\""
+        )));
+      }
+      (program, Some(arg)) => {
+        constraints
+          .entry(program.to_string())
+          .or_default()
+          .insert(arg.to_string());
+        programs.push(program.to_string());
+      }
+      (program, None) => {
+        unrestricted.insert(program);
+        programs.push(program.to_string());
+      }
+    }
+  }
+  // a program is unrestricted if it is also listed without an argument, by
+  // any name or path
+  let unrestricted = unrestricted
+    .into_iter()
+    .flat_map(|program| {
+      RunDescriptor::from(program.to_string()).restriction_keys()
+    })
+    .collect::<HashSet<_>>();
+  let mut granted_args: HashMap<RunDescriptor, HashSet<String>> =
+    HashMap::new();
+  for (program, args) in constraints {
+    let keys = RunDescriptor::from(program).restriction_keys();
+    if keys.iter().any(|key| unrestricted.contains(key)) {
+      continue;
+    }
+    for key in keys {
+      granted_args
+        .entry(key)
+        .or_default()
+        .extend(args.iter().cloned());
+    }
+  }
+  Ok((Some(programs), granted_args))
+}
+
 fn escalation_error() -> AnyError {
   custom_error(
     "PermissionDenied",
@@ -2276,6 +3010,31 @@ pub fn create_child_permissions(
   Ok(worker_perms)
 }
 
+/// Like `create_child_permissions()`, but replays the permissions of the child
+/// from a snapshot. A snapshot can't grant more than the parent has, and the
+/// parent's denials still apply to the child.
+pub fn create_child_permissions_from_snapshot(
+  main_perms: &mut Permissions,
+  snapshot: &PermissionsSnapshot,
+) -> Result<Permissions, AnyError> {
+  let mut worker_perms = Permissions::from_snapshot(snapshot)?;
+  if worker_perms.all.query() == PermissionState::Granted
+    && main_perms.all.query() != PermissionState::Granted
+  {
+    return Err(escalation_error());
+  }
+  worker_perms.read.restrict_to_parent(&mut main_perms.read)?;
+  worker_perms
+    .write
+    .restrict_to_parent(&mut main_perms.write)?;
+  worker_perms.net.restrict_to_parent(&mut main_perms.net)?;
+  worker_perms.env.restrict_to_parent(&mut main_perms.env)?;
+  worker_perms.sys.restrict_to_parent(&mut main_perms.sys)?;
+  worker_perms.run.restrict_to_parent(&mut main_perms.run)?;
+  worker_perms.ffi.restrict_to_parent(&mut main_perms.ffi)?;
+  Ok(worker_perms)
+}
+
 static IS_STANDALONE: AtomicFlag = AtomicFlag::lowered();
 
 pub fn mark_standalone() {
@@ -2703,6 +3462,7 @@ mod tests {
         .unwrap(),
       run: Permissions::new_unary(&Some(svec!["deno"]), &None, false).unwrap(),
       all: Permissions::new_all(false),
+      run_tokens: Default::default(),
     };
     let perms3 = Permissions {
       read: Permissions::new_unary(
@@ -2730,6 +3490,7 @@ mod tests {
         .unwrap(),
       run: Permissions::new_unary(&None, &Some(svec!["deno"]), false).unwrap(),
       all: Permissions::new_all(false),
+      run_tokens: Default::default(),
     };
     let perms4 = Permissions {
       read: Permissions::new_unary(
@@ -2767,6 +3528,7 @@ mod tests {
       run: Permissions::new_unary(&Some(vec![]), &Some(svec!["deno"]), false)
         .unwrap(),
       all: Permissions::new_all(false),
+      run_tokens: Default::default(),
     };
     #[rustfmt::skip]
     {
@@ -2922,6 +3684,7 @@ mod tests {
         .unwrap(),
       run: Permissions::new_unary(&Some(svec!["deno"]), &None, false).unwrap(),
       all: Permissions::new_all(false),
+      run_tokens: Default::default(),
     };
     #[rustfmt::skip]
     {
@@ -3171,6 +3934,185 @@ mod tests {
     assert!(perms.write.check(Path::new("/foo"), None).is_err());
   }
 
+  #[test]
+  fn test_check_env_patterns() {
+    let mut perms = Permissions {
+      env: Permissions::new_unary(
+        &Some(svec!["AWS_*", "*_DEBUG", "HOME"]),
+        &Some(svec!["AWS_SECRET_*"]),
+        false,
+      )
+      .unwrap(),
+      ..Permissions::none_without_prompt()
+    };
+
+    assert!(perms.env.has_granted_patterns());
+    assert!(perms.env.check("AWS_REGION", None).is_ok());
+    assert!(perms.env.check("AWS_", None).is_ok());
+    assert!(perms.env.check("NODE_DEBUG", None).is_ok());
+    assert!(perms.env.check("HOME", None).is_ok());
+    assert!(perms.env.check("AWS_SECRET_ACCESS_KEY", None).is_err());
+    assert!(perms.env.check("MY_AWS_REGION", None).is_err());
+    assert!(perms.env.check("PATH", None).is_err());
+    assert!(perms.env.check_all().is_err());
+
+    assert!(env_pattern_matches("A*B*C", "AxxBC"));
+    assert!(!env_pattern_matches("A*B*C", "AxxC"));
+    assert!(!env_pattern_matches("AB*BA", "ABA"));
+  }
+
+  #[test]
+  fn test_check_run_args() {
+    let mut perms = Permissions {
+      run: Permissions::new_run(
+        &Some(svec!["curl", "git:status", "git:log", "ls", "ls:-l"]),
+        &None,
+        false,
+      )
+      .unwrap(),
+      ..Permissions::none_without_prompt()
+    };
+
+    assert!(perms.run.check("git", None).is_ok());
+    assert!(perms.run.check_args("git", &["status".to_string()]).is_ok());
+    assert!(perms
+      .run
+      .check_args("git", &["log".to_string(), "-1".to_string()])
+      .is_ok());
+    assert!(perms.run.check_args("git", &["push".to_string()]).is_err());
+    assert!(perms.run.check_args("git", &[]).is_err());
+    // a program which is also listed without an argument isn't restricted
+    assert!(perms.run.check_args("ls", &["-a".to_string()]).is_ok());
+    assert!(perms.run.check_args("curl", &["-I".to_string()]).is_ok());
+
+    assert!(Permissions::new_run(&Some(svec!["git:"]), &None, false).is_err());
+  }
+
+  #[test]
+  fn test_check_run_args_by_path() {
+    let perms = Permissions {
+      run: Permissions::new_run(&Some(svec!["ls:-l"]), &None, false).unwrap(),
+      ..Permissions::none_without_prompt()
+    };
+    let args = |arg: &str| [arg.to_string()];
+
+    // the restriction applies however the binary is run
+    let ls = which("ls").unwrap();
+    #[allow(clippy::disallowed_methods)]
+    let binary = ls.canonicalize().unwrap();
+    for path in [&ls, &binary] {
+      let path = path.to_string_lossy();
+      assert!(perms.run.check_args(&path, &args("-l")).is_ok());
+      assert!(perms.run.check_args(&path, &args("-a")).is_err());
+    }
+    #[cfg(unix)]
+    {
+      #[allow(clippy::disallowed_methods)]
+      let link = std::env::temp_dir()
+        .join(format!("deno_test_run_args_{}", std::process::id()));
+      std::os::unix::fs::symlink(&binary, &link).unwrap();
+      let link_str = link.to_string_lossy();
+      let allowed = perms.run.check_args(&link_str, &args("-l"));
+      let denied = perms.run.check_args(&link_str, &args("-a"));
+      #[allow(clippy::disallowed_methods)]
+      std::fs::remove_file(&link).unwrap();
+      assert!(allowed.is_ok());
+      assert!(denied.is_err());
+    }
+
+    // listing the binary without an argument by another path lifts it
+    let perms = Permissions::new_run(
+      &Some(vec![
+        "ls:-l".to_string(),
+        binary.to_string_lossy().into_owned(),
+      ]),
+      &None,
+      false,
+    )
+    .unwrap();
+    assert!(perms.check_args("ls", &args("-a")).is_ok());
+  }
+
+  #[test]
+  fn test_check_run_with_token_args() {
+    let mut perms = PermissionsContainer::new(Permissions {
+      run: Permissions::new_run(&Some(svec!["ls:-l"]), &None, false).unwrap(),
+      ..Permissions::none_without_prompt()
+    });
+    let token = perms.mint_run_token("ls", "test").unwrap();
+    let token = Some(token.as_str());
+    assert!(perms
+      .check_run_with_token(token, "ls", &["-l".to_string()], "test")
+      .is_ok());
+    assert!(perms
+      .check_run_with_token(token, "ls", &["-a".to_string()], "test")
+      .is_err());
+    assert!(perms
+      .check_run_with_token(token, "ls", &[], "test")
+      .is_err());
+  }
+
+  #[test]
+  fn test_check_run_denied() {
+    let mut perms = Permissions {
+      run: Permissions::new_run(&Some(vec![]), &Some(svec!["ls"]), false)
+        .unwrap(),
+      ..Permissions::none_without_prompt()
+    };
+    assert!(perms.run.check("ls", None).is_err());
+    assert!(perms.run.check("sh", None).is_ok());
+
+    // the binary is denied by any path leading to it
+    #[allow(clippy::disallowed_methods)]
+    let ls = which("ls").unwrap().canonicalize().unwrap();
+    assert!(perms.run.check(&ls.to_string_lossy(), None).is_err());
+    #[cfg(unix)]
+    {
+      #[allow(clippy::disallowed_methods)]
+      let link = std::env::temp_dir()
+        .join(format!("deno_test_run_denied_{}", std::process::id()));
+      std::os::unix::fs::symlink(&ls, &link).unwrap();
+      let result = perms.run.check(&link.to_string_lossy(), None);
+      #[allow(clippy::disallowed_methods)]
+      std::fs::remove_file(&link).unwrap();
+      assert!(result.is_err());
+    }
+  }
+
+  #[test]
+  fn test_split_run_constraint() {
+    assert_eq!(split_run_constraint("git"), ("git", None));
+    assert_eq!(split_run_constraint("git:status"), ("git", Some("status")));
+    assert_eq!(split_run_constraint("C:\\git.exe"), ("C:\\git.exe", None));
+    assert_eq!(
+      split_run_constraint("C:\\git.exe:status"),
+      ("C:\\git.exe", Some("status"))
+    );
+    assert_eq!(
+      split_run_constraint("C:/Git/git.exe:status"),
+      ("C:/Git/git.exe", Some("status"))
+    );
+    assert_eq!(split_run_constraint("C:"), ("C:", None));
+    assert_eq!(split_run_constraint("x:status"), ("x", Some("status")));
+    assert_eq!(
+      split_run_constraint("docker:image:ls"),
+      ("docker", Some("image:ls"))
+    );
+    assert_eq!(
+      split_run_constraint("curl:https://deno.land"),
+      ("curl", Some("https://deno.land"))
+    );
+    assert_eq!(
+      split_run_constraint("C:\\curl.exe:C:\\out.txt"),
+      ("C:\\curl.exe", Some("C:\\out.txt"))
+    );
+    assert_eq!(
+      split_run_constraint("/usr/bin/git:status"),
+      ("/usr/bin/git", Some("status"))
+    );
+    assert_eq!(split_run_constraint(":status"), (":status", None));
+  }
+
   #[test]
   fn test_net_fully_qualified_domain_name() {
     let mut perms = Permissions {
@@ -3389,6 +4331,135 @@ mod tests {
     .is_err());
   }
 
+  #[test]
+  fn test_permissions_snapshot() {
+    set_prompter(Box::new(TestPrompter));
+    let mut perms = Permissions::from_options(&PermissionsOptions {
+      allow_env: Some(vec![]),
+      deny_env: Some(svec!["SECRET"]),
+      allow_net: Some(svec!["deno.land", "127.0.0.1:8000"]),
+      allow_read: Some(vec![PathBuf::from("/a")]),
+      allow_run: Some(svec!["git:status", "git:log", "deno"]),
+      prompt: true,
+      ..Default::default()
+    })
+    .unwrap();
+    perms
+      .write
+      .insert_prompt_denied(Some(WriteDescriptor("/b".into())));
+
+    let snapshot = perms.snapshot();
+    assert!(snapshot.env.granted_global);
+    assert_eq!(snapshot.env.flag_denied, svec!["SECRET"]);
+    assert_eq!(snapshot.net.granted, svec!["127.0.0.1:8000", "deno.land"]);
+    assert!(snapshot.run.granted.contains(&"git:log".to_string()));
+    assert!(snapshot.run.granted.contains(&"git:status".to_string()));
+    assert!(snapshot.run.granted.contains(&"deno".to_string()));
+    assert!(!snapshot.run.granted.contains(&"git".to_string()));
+    assert_eq!(snapshot.write.prompt_denied, svec!["/b"]);
+    assert!(snapshot.read.prompt);
+
+    let json = serde_json::to_value(&snapshot).unwrap();
+    let replayed = Permissions::from_snapshot(
+      &serde_json::from_value::<PermissionsSnapshot>(json).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(replayed.snapshot(), snapshot);
+    assert_eq!(replayed.read, perms.read);
+    assert_eq!(replayed.write, perms.write);
+    assert_eq!(replayed.env, perms.env);
+
+    // missing permissions are not granted
+    let replayed = Permissions::from_snapshot(
+      &serde_json::from_value::<PermissionsSnapshot>(
+        json!({ "read": { "granted": ["/a"] } }),
+      )
+      .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+      replayed.read.query(Some(Path::new("/a"))),
+      PermissionState::Granted
+    );
+    assert_eq!(replayed.net.query(None), PermissionState::Prompt);
+  }
+
+  #[test]
+  fn test_create_child_permissions_from_snapshot() {
+    set_prompter(Box::new(TestPrompter));
+    let mut main_perms = Permissions::from_options(&PermissionsOptions {
+      allow_env: Some(vec![]),
+      deny_env: Some(svec!["SECRET"]),
+      allow_net: Some(svec!["foo", "bar"]),
+      allow_run: Some(svec!["git:status"]),
+      ..Default::default()
+    })
+    .unwrap();
+    let snapshot = PermissionsSnapshot {
+      env: UnaryPermissionSnapshot {
+        granted_global: true,
+        ..Default::default()
+      },
+      net: UnaryPermissionSnapshot {
+        granted: svec!["foo"],
+        ..Default::default()
+      },
+      run: UnaryPermissionSnapshot {
+        granted: svec!["git"],
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    let mut worker_perms =
+      create_child_permissions_from_snapshot(&mut main_perms, &snapshot)
+        .unwrap();
+    assert_eq!(
+      worker_perms.env.query(Some("HOME")),
+      PermissionState::Granted
+    );
+    assert_eq!(
+      worker_perms.env.query(Some("SECRET")),
+      PermissionState::Denied
+    );
+    let foo = NetDescriptor("foo".parse().unwrap(), None);
+    let bar = NetDescriptor("bar".parse().unwrap(), None);
+    assert_eq!(worker_perms.net.query(Some(&foo)), PermissionState::Granted);
+    assert_eq!(worker_perms.net.query(Some(&bar)), PermissionState::Prompt);
+    // the restrictions on the arguments of the parent are kept
+    assert!(worker_perms.run.check("git", None).is_ok());
+    assert!(worker_perms
+      .run
+      .check_args("git", &["push".to_string()])
+      .is_err());
+
+    for snapshot in [
+      PermissionsSnapshot {
+        net: UnaryPermissionSnapshot {
+          granted_global: true,
+          ..Default::default()
+        },
+        ..Default::default()
+      },
+      PermissionsSnapshot {
+        net: UnaryPermissionSnapshot {
+          granted: svec!["foo", "baz"],
+          ..Default::default()
+        },
+        ..Default::default()
+      },
+      PermissionsSnapshot {
+        all: true,
+        ..Default::default()
+      },
+    ] {
+      assert!(create_child_permissions_from_snapshot(
+        &mut main_perms,
+        &snapshot
+      )
+      .is_err());
+    }
+  }
+
   #[test]
   fn test_create_child_permissions_with_prompt() {
     set_prompter(Box::new(TestPrompter));
@@ -3440,6 +4511,26 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_for_each_descendant() {
+    let main = PermissionsContainer::allow_all();
+    let child = main.new_child(Permissions::allow_all());
+    let grandchild = child.new_child(Permissions::allow_all());
+    let terminated = main.new_child(Permissions::allow_all());
+    drop(terminated);
+
+    main.for_each_descendant(|perms| {
+      perms.env.revoke(None);
+    });
+    assert_eq!(main.0.lock().env.query(None), PermissionState::Granted);
+    assert_eq!(child.0.lock().env.query(None), PermissionState::Prompt);
+    assert_eq!(grandchild.0.lock().env.query(None), PermissionState::Prompt);
+
+    let mut count = 0;
+    main.for_each_descendant(|_| count += 1);
+    assert_eq!(count, 2);
+  }
+
   #[test]
   fn test_handle_empty_value() {
     set_prompter(Box::new(TestPrompter));
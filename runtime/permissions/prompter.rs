@@ -38,11 +38,14 @@ pub const PERMISSION_EMOJI: &str = "⚠️";
 // 10kB of permission prompting should be enough for anyone
 const MAX_PERMISSION_PROMPT_LENGTH: usize = 10 * 1024;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum PromptResponse {
   Allow,
   Deny,
   AllowAll,
+  /// Allow all future uses of the binary that was requested to be run,
+  /// regardless of the name or path it is run by.
+  AllowBinary,
 }
 
 static PERMISSION_PROMPTER: Lazy<Mutex<Box<dyn PermissionPrompter>>> =
@@ -59,13 +62,18 @@ pub fn permission_prompt(
   flag: &str,
   api_name: Option<&str>,
   is_unary: bool,
+  allow_binary: bool,
 ) -> PromptResponse {
   if let Some(before_callback) = MAYBE_BEFORE_PROMPT_CALLBACK.lock().as_mut() {
     before_callback();
   }
-  let r = PERMISSION_PROMPTER
-    .lock()
-    .prompt(message, flag, api_name, is_unary);
+  let r = PERMISSION_PROMPTER.lock().prompt(
+    message,
+    flag,
+    api_name,
+    is_unary,
+    allow_binary,
+  );
   if let Some(after_callback) = MAYBE_AFTER_PROMPT_CALLBACK.lock().as_mut() {
     after_callback();
   }
@@ -83,12 +91,15 @@ pub fn set_prompt_callbacks(
 pub type PromptCallback = Box<dyn FnMut() + Send + Sync>;
 
 pub trait PermissionPrompter: Send + Sync {
+  /// `allow_binary` is whether [`PromptResponse::AllowBinary`] may be
+  /// returned, which is only the case for requests to run a specific binary.
   fn prompt(
     &mut self,
     message: &str,
     name: &str,
     api_name: Option<&str>,
     is_unary: bool,
+    allow_binary: bool,
   ) -> PromptResponse;
 }
 
@@ -277,6 +288,7 @@ impl PermissionPrompter for TtyPrompter {
     name: &str,
     api_name: Option<&str>,
     is_unary: bool,
+    allow_binary: bool,
   ) -> PromptResponse {
     if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
       return PromptResponse::Deny;
@@ -312,7 +324,9 @@ impl PermissionPrompter for TtyPrompter {
     let api_name = api_name.map(escape_control_characters);
 
     // print to stderr so that if stdout is piped this is still displayed.
-    let opts: String = if is_unary {
+    let opts: String = if allow_binary {
+      format!("[y/n/b/A] (y = yes, allow; n = no, deny; b = allow all future runs of this binary; A = allow all {name} permissions)")
+    } else if is_unary {
       format!("[y/n/A] (y = yes, allow; n = no, deny; A = allow all {name} permissions)")
     } else {
       "[y/n] (y = yes, allow; n = no, deny)".to_string()
@@ -389,6 +403,16 @@ impl PermissionPrompter for TtyPrompter {
           writeln!(stderr_lock, "❌ {}", colors::bold(&msg)).unwrap();
           break PromptResponse::Deny;
         }
+        'b' if allow_binary => {
+          clear_n_lines(
+            &mut stderr_lock,
+            if api_name.is_some() { 5 } else { 4 },
+          );
+          let msg =
+            format!("Granted {message} for all future runs of this binary.");
+          writeln!(stderr_lock, "✅ {}", colors::bold(&msg)).unwrap();
+          break PromptResponse::AllowBinary;
+        }
         'A' if is_unary => {
           clear_n_lines(
             &mut stderr_lock,
@@ -454,6 +478,7 @@ pub mod tests {
       _name: &str,
       _api_name: Option<&str>,
       _is_unary: bool,
+      _allow_binary: bool,
     ) -> PromptResponse {
       if STUB_PROMPT_VALUE.load(Ordering::SeqCst) {
         PromptResponse::Allow
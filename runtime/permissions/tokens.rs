@@ -0,0 +1,66 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Tokens which each grant run access to a single program. They are minted by
+//! the embedder or the main module and handed to code, e.g. a library, which
+//! should be able to run a vetted program without run permission of its own.
+//! Tokens are random, so they can't be guessed by code they weren't given to.
+
+use std::collections::HashMap;
+
+use crate::Descriptor;
+use crate::RunDescriptor;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RunPermissionTokens(HashMap<String, RunDescriptor>);
+
+impl RunPermissionTokens {
+  /// Mints a token for running `cmd`. Whether the minter may run `cmd` is up
+  /// to the caller to check.
+  pub fn mint(&mut self, cmd: &str) -> String {
+    let token = format!("{:032x}", rand::random::<u128>());
+    self
+      .0
+      .insert(token.clone(), RunDescriptor::from(cmd.to_string()));
+    token
+  }
+
+  /// Returns whether `token` was valid.
+  pub fn revoke(&mut self, token: &str) -> bool {
+    self.0.remove(token).is_some()
+  }
+
+  /// Whether `token` grants running `cmd`, by the name or path the token was
+  /// minted for or by the binary it resolves to.
+  pub(crate) fn grants(&self, token: &str, cmd: &str) -> bool {
+    let Some(granted) = self.0.get(token) else {
+      return false;
+    };
+    let desc = RunDescriptor::from(cmd.to_string());
+    if *granted == desc || granted.aliases().contains(&desc) {
+      return true;
+    }
+    match (granted.binary(), desc.binary()) {
+      (Some(granted), Some(binary)) => granted == binary,
+      _ => false,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn mint_and_revoke() {
+    let mut tokens = RunPermissionTokens::default();
+    let token = tokens.mint("ls");
+    assert_eq!(token.len(), 32);
+    assert_ne!(tokens.mint("ls"), token);
+    assert!(tokens.grants(&token, "ls"));
+    assert!(!tokens.grants(&token, "cat"));
+    assert!(!tokens.grants("0", "ls"));
+    assert!(tokens.revoke(&token));
+    assert!(!tokens.revoke(&token));
+    assert!(!tokens.grants(&token, "ls"));
+  }
+}
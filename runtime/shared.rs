@@ -31,7 +31,9 @@ extension!(runtime,
     deno_napi,
     deno_http,
     deno_io,
-    deno_fs
+    deno_fs,
+    deno_archive,
+    deno_quic
   ],
   esm_entry_point = "ext:runtime/90_deno_ns.js",
   esm = [
@@ -210,6 +210,24 @@ impl deno_kv::sqlite::SqliteDbHandlerPermissions for Permissions {
   }
 }
 
+impl deno_archive::ArchivePermissions for Permissions {
+  fn check_read(
+    &mut self,
+    _path: &Path,
+    _api_name: &str,
+  ) -> Result<(), AnyError> {
+    unreachable!("snapshotting!")
+  }
+
+  fn check_write(
+    &mut self,
+    _path: &Path,
+    _api_name: &str,
+  ) -> Result<(), AnyError> {
+    unreachable!("snapshotting!")
+  }
+}
+
 pub fn create_runtime_snapshot(
   snapshot_path: PathBuf,
   snapshot_options: SnapshotOptions,
@@ -251,6 +269,8 @@ pub fn create_runtime_snapshot(
     deno_cron::deno_cron::init_ops_and_esm(
       deno_cron::local::LocalCronHandler::new(),
     ),
+    deno_archive::deno_archive::init_ops_and_esm::<Permissions>(),
+    deno_quic::deno_quic::init_ops_and_esm::<Permissions>(),
     deno_napi::deno_napi::init_ops_and_esm::<Permissions>(),
     deno_http::deno_http::init_ops_and_esm::<DefaultHttpPropertyExtractor>(),
     deno_io::deno_io::init_ops_and_esm(Default::default()),
@@ -0,0 +1,188 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! The CPU time of a thread, as read from another thread. Used by the CPU
+//! watchdog of workers with a `cpuMs` limit.
+
+use std::time::Duration;
+
+pub struct ThreadCpuClock(imp::Clock);
+
+impl ThreadCpuClock {
+  /// The clock of the current thread, if the platform supports it.
+  pub fn current() -> Option<Self> {
+    imp::Clock::current().map(Self)
+  }
+
+  /// The CPU time used by the thread so far. Returns `None` once the thread
+  /// exited.
+  pub fn elapsed(&self) -> Option<Duration> {
+    self.0.elapsed()
+  }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+mod imp {
+  use std::time::Duration;
+
+  pub struct Clock(libc::clockid_t);
+
+  impl Clock {
+    pub fn current() -> Option<Self> {
+      let mut clock_id: libc::clockid_t = 0;
+      // SAFETY: `clock_id` is written by `pthread_getcpuclockid`.
+      let res = unsafe {
+        libc::pthread_getcpuclockid(libc::pthread_self(), &mut clock_id)
+      };
+      (res == 0).then_some(Self(clock_id))
+    }
+
+    pub fn elapsed(&self) -> Option<Duration> {
+      // SAFETY: `time` is written by `clock_gettime`, which fails for the
+      // clock of a thread that exited.
+      let time = unsafe {
+        let mut time = std::mem::zeroed::<libc::timespec>();
+        if libc::clock_gettime(self.0, &mut time) != 0 {
+          return None;
+        }
+        time
+      };
+      Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+    }
+  }
+}
+
+#[cfg(target_vendor = "apple")]
+mod imp {
+  use std::time::Duration;
+
+  pub struct Clock(libc::mach_port_t);
+
+  impl Clock {
+    pub fn current() -> Option<Self> {
+      // SAFETY: libc call
+      Some(Self(unsafe {
+        libc::pthread_mach_thread_np(libc::pthread_self())
+      }))
+    }
+
+    pub fn elapsed(&self) -> Option<Duration> {
+      // SAFETY: `info` is written by `thread_info`, which fails for a thread
+      // that exited.
+      let info = unsafe {
+        let mut info = std::mem::zeroed::<libc::thread_basic_info>();
+        let mut count = libc::THREAD_BASIC_INFO_COUNT;
+        let res = libc::thread_info(
+          self.0,
+          libc::THREAD_BASIC_INFO as libc::thread_flavor_t,
+          &mut info as *mut _ as libc::thread_info_t,
+          &mut count,
+        );
+        if res != libc::KERN_SUCCESS {
+          return None;
+        }
+        info
+      };
+      let micros = |time: libc::time_value_t| {
+        time.seconds as u64 * 1_000_000 + time.microseconds as u64
+      };
+      Some(Duration::from_micros(
+        micros(info.user_time) + micros(info.system_time),
+      ))
+    }
+  }
+}
+
+#[cfg(windows)]
+mod imp {
+  use std::time::Duration;
+
+  use winapi::shared::minwindef::FALSE;
+  use winapi::shared::minwindef::FILETIME;
+  use winapi::um::handleapi::CloseHandle;
+  use winapi::um::handleapi::DuplicateHandle;
+  use winapi::um::processthreadsapi::GetCurrentProcess;
+  use winapi::um::processthreadsapi::GetCurrentThread;
+  use winapi::um::processthreadsapi::GetThreadTimes;
+  use winapi::um::winnt::HANDLE;
+  use winapi::um::winnt::THREAD_QUERY_LIMITED_INFORMATION;
+
+  pub struct Clock(HANDLE);
+
+  // SAFETY: thread handles can be used from any thread.
+  unsafe impl Send for Clock {}
+
+  impl Clock {
+    pub fn current() -> Option<Self> {
+      let mut handle: HANDLE = std::ptr::null_mut();
+      // SAFETY: `GetCurrentThread` returns a pseudo handle, which is
+      // duplicated into one that other threads can use.
+      let res = unsafe {
+        DuplicateHandle(
+          GetCurrentProcess(),
+          GetCurrentThread(),
+          GetCurrentProcess(),
+          &mut handle,
+          THREAD_QUERY_LIMITED_INFORMATION,
+          FALSE,
+          0,
+        )
+      };
+      (res != FALSE).then_some(Self(handle))
+    }
+
+    pub fn elapsed(&self) -> Option<Duration> {
+      // SAFETY: winapi calls
+      unsafe {
+        let mut creation_time = std::mem::zeroed::<FILETIME>();
+        let mut exit_time = std::mem::zeroed::<FILETIME>();
+        let mut kernel_time = std::mem::zeroed::<FILETIME>();
+        let mut user_time = std::mem::zeroed::<FILETIME>();
+        if GetThreadTimes(
+          self.0,
+          &mut creation_time,
+          &mut exit_time,
+          &mut kernel_time,
+          &mut user_time,
+        ) == FALSE
+        {
+          return None;
+        }
+        // `FILETIME`s count 100 nanosecond intervals
+        let nanos = |time: FILETIME| {
+          ((time.dwHighDateTime as u64) << 32 | time.dwLowDateTime as u64) * 100
+        };
+        Some(Duration::from_nanos(nanos(kernel_time) + nanos(user_time)))
+      }
+    }
+  }
+
+  impl Drop for Clock {
+    fn drop(&mut self) {
+      // SAFETY: the handle was duplicated by `current`.
+      unsafe { CloseHandle(self.0) };
+    }
+  }
+}
+
+#[cfg(not(any(
+  target_os = "linux",
+  target_os = "android",
+  target_os = "freebsd",
+  target_vendor = "apple",
+  windows
+)))]
+mod imp {
+  use std::time::Duration;
+
+  pub struct Clock;
+
+  impl Clock {
+    pub fn current() -> Option<Self> {
+      None
+    }
+
+    pub fn elapsed(&self) -> Option<Duration> {
+      None
+    }
+  }
+}
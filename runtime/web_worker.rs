@@ -4,6 +4,7 @@ use crate::ops;
 use crate::ops::worker_host::WorkersTable;
 use crate::shared::maybe_transpile_source;
 use crate::shared::runtime;
+use crate::thread_cpu_clock::ThreadCpuClock;
 use crate::tokio_util::create_and_run_current_thread;
 use crate::worker::create_op_metrics;
 use crate::worker::import_meta_resolve_callback;
@@ -13,9 +14,11 @@ use crate::BootstrapOptions;
 use deno_broadcast_channel::InMemoryBroadcastChannel;
 use deno_cache::CreateCache;
 use deno_cache::SqliteBackedCache;
+use deno_core::error::generic_error;
 use deno_core::error::AnyError;
 use deno_core::error::JsError;
 use deno_core::futures::channel::mpsc;
+use deno_core::futures::channel::oneshot;
 use deno_core::futures::future::poll_fn;
 use deno_core::futures::stream::StreamExt;
 use deno_core::futures::task::AtomicWaker;
@@ -64,6 +67,7 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::task::Context;
 use std::task::Poll;
+use std::time::Duration;
 
 pub struct WorkerMetadata {
   pub buffer: DetachedBuffer,
@@ -91,6 +95,19 @@ impl Default for WorkerId {
   }
 }
 
+/// Resource limits of a worker, set with `deno.limits` of `new Worker()` or
+/// `resourceLimits` of `node:worker_threads`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerLimits {
+  /// The maximum size of the V8 heap, in MiB.
+  pub heap_mb: Option<u64>,
+  /// The CPU time the worker thread may use, in milliseconds.
+  pub cpu_ms: Option<u64>,
+  /// The size of the stack of the worker thread, in MiB.
+  pub stack_mb: Option<u64>,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum WebWorkerType {
@@ -215,8 +232,76 @@ impl WebWorkerInternalHandle {
     // Wake parent by closing the channel
     self.sender.close_channel();
   }
+
+  /// Terminates the worker with a terminal error once its thread used more
+  /// than `limit` of CPU time. Must be called on the worker thread; the
+  /// watchdog stops when the returned guard is dropped.
+  fn start_cpu_watchdog(&self, limit: Duration) -> Option<CpuWatchdogGuard> {
+    let Some(clock) = ThreadCpuClock::current() else {
+      debug!("CPU time limits of workers are not supported on this platform");
+      return None;
+    };
+    // Bootstrapping the worker doesn't count towards its limit.
+    let start = clock.elapsed()?;
+    let done = Arc::new(AtomicBool::new(false));
+    let interval = (limit / 10).clamp(Duration::from_millis(1), MAX_INTERVAL);
+    let mut sender = self.sender.clone();
+    let termination_signal = self.termination_signal.clone();
+    let has_terminated = self.has_terminated.clone();
+    let terminate_waker = self.terminate_waker.clone();
+    let isolate_handle = self.isolate_handle.clone();
+    let watchdog_done = done.clone();
+    let spawned = std::thread::Builder::new()
+      .name(format!("{}-cpu-watchdog", self.name))
+      .spawn(move || loop {
+        std::thread::sleep(interval);
+        if watchdog_done.load(Ordering::SeqCst)
+          || has_terminated.load(Ordering::SeqCst)
+        {
+          return;
+        }
+        let Some(elapsed) = clock.elapsed() else {
+          return;
+        };
+        if elapsed.saturating_sub(start) < limit {
+          continue;
+        }
+        _ = sender.try_send(WorkerControlEvent::TerminalError(generic_error(
+          format!(
+            "Worker exceeded its CPU time limit of {} ms",
+            limit.as_millis()
+          ),
+        )));
+        termination_signal.store(true, Ordering::SeqCst);
+        terminate_waker.wake();
+        if !has_terminated.swap(true, Ordering::SeqCst) {
+          isolate_handle.terminate_execution();
+        }
+        return;
+      });
+    if let Err(err) = spawned {
+      debug!("failed to start the CPU watchdog of a worker: {err}");
+      return None;
+    }
+    Some(CpuWatchdogGuard(done))
+  }
 }
 
+/// The longest time between two checks of the CPU watchdog.
+const MAX_INTERVAL: Duration = Duration::from_millis(100);
+
+struct CpuWatchdogGuard(Arc<AtomicBool>);
+
+impl Drop for CpuWatchdogGuard {
+  fn drop(&mut self) {
+    self.0.store(true, Ordering::SeqCst);
+  }
+}
+
+/// Requests for heap snapshots of a worker, each answered with the JSON of
+/// the snapshot.
+type HeapSnapshotRequest = oneshot::Sender<Vec<u8>>;
+
 pub struct SendableWebWorkerHandle {
   port: MessagePort,
   receiver: mpsc::Receiver<WorkerControlEvent>,
@@ -224,6 +309,7 @@ pub struct SendableWebWorkerHandle {
   has_terminated: Arc<AtomicBool>,
   terminate_waker: Arc<AtomicWaker>,
   isolate_handle: v8::IsolateHandle,
+  heap_snapshot_requests: mpsc::UnboundedSender<HeapSnapshotRequest>,
 }
 
 impl From<SendableWebWorkerHandle> for WebWorkerHandle {
@@ -235,6 +321,7 @@ impl From<SendableWebWorkerHandle> for WebWorkerHandle {
       has_terminated: handle.has_terminated,
       terminate_waker: handle.terminate_waker,
       isolate_handle: handle.isolate_handle,
+      heap_snapshot_requests: handle.heap_snapshot_requests,
     }
   }
 }
@@ -254,6 +341,7 @@ pub struct WebWorkerHandle {
   has_terminated: Arc<AtomicBool>,
   terminate_waker: Arc<AtomicWaker>,
   isolate_handle: v8::IsolateHandle,
+  heap_snapshot_requests: mpsc::UnboundedSender<HeapSnapshotRequest>,
 }
 
 impl WebWorkerHandle {
@@ -267,6 +355,14 @@ impl WebWorkerHandle {
     Ok(receiver.next().await)
   }
 
+  /// Takes a heap snapshot of the worker's isolate once its event loop is
+  /// polled. Returns `None` if the worker isn't running anymore.
+  pub async fn take_heap_snapshot(&self) -> Option<Vec<u8>> {
+    let (sender, receiver) = oneshot::channel();
+    self.heap_snapshot_requests.unbounded_send(sender).ok()?;
+    receiver.await.ok()
+  }
+
   /// Terminate the worker
   /// This function will set the termination signal, close the message channel,
   /// and schedule to terminate the isolate after two seconds.
@@ -307,6 +403,7 @@ fn create_handles(
   isolate_handle: v8::IsolateHandle,
   name: String,
   worker_type: WebWorkerType,
+  heap_snapshot_requests: mpsc::UnboundedSender<HeapSnapshotRequest>,
 ) -> (WebWorkerInternalHandle, SendableWebWorkerHandle) {
   let (parent_port, worker_port) = create_entangled_message_port();
   let (ctrl_tx, ctrl_rx) = mpsc::channel::<WorkerControlEvent>(1);
@@ -331,6 +428,7 @@ fn create_handles(
     has_terminated,
     terminate_waker,
     isolate_handle,
+    heap_snapshot_requests,
   };
   (internal_handle, external_handle)
 }
@@ -353,6 +451,8 @@ pub struct WebWorker {
   bootstrap_fn_global: Option<v8::Global<v8::Function>>,
   // Consumed when `bootstrap_fn` is called
   maybe_worker_metadata: Option<WorkerMetadata>,
+  limits: WorkerLimits,
+  heap_snapshot_requests: mpsc::UnboundedReceiver<HeapSnapshotRequest>,
 }
 
 pub struct WebWorkerOptions {
@@ -380,6 +480,7 @@ pub struct WebWorkerOptions {
   pub strace_ops: Option<Vec<String>>,
   pub close_on_idle: bool,
   pub maybe_worker_metadata: Option<WorkerMetadata>,
+  pub limits: WorkerLimits,
 }
 
 impl WebWorker {
@@ -483,6 +584,8 @@ impl WebWorker {
         deno_kv::KvConfig::builder().build(),
       ),
       deno_cron::deno_cron::init_ops_and_esm(LocalCronHandler::new()),
+      deno_archive::deno_archive::init_ops_and_esm::<PermissionsContainer>(),
+      deno_quic::deno_quic::init_ops_and_esm::<PermissionsContainer>(),
       deno_napi::deno_napi::init_ops_and_esm::<PermissionsContainer>(),
       deno_http::deno_http::init_ops_and_esm::<DefaultHttpPropertyExtractor>(),
       deno_io::deno_io::init_ops_and_esm(Some(options.stdio)),
@@ -553,9 +656,16 @@ impl WebWorker {
       ))
     };
 
+    let create_params = options.limits.heap_mb.map(|heap_mb| {
+      let max = usize::try_from(heap_mb.saturating_mul(1024 * 1024))
+        .unwrap_or(usize::MAX);
+      v8::CreateParams::default().heap_limits(0, max)
+    });
+
     let mut js_runtime = JsRuntime::new(RuntimeOptions {
       module_loader: Some(options.module_loader.clone()),
       startup_snapshot: options.startup_snapshot,
+      create_params,
       get_error_class_fn: options.get_error_class_fn,
       shared_array_buffer_store: options.shared_array_buffer_store.clone(),
       compiled_wasm_module_store: options.compiled_wasm_module_store.clone(),
@@ -594,16 +704,38 @@ impl WebWorker {
       op_state.borrow_mut().put(inspector);
     }
 
+    let (heap_snapshot_sender, heap_snapshot_requests) = mpsc::unbounded();
     let (internal_handle, external_handle) = {
       let handle = js_runtime.v8_isolate().thread_safe_handle();
-      let (internal_handle, external_handle) =
-        create_handles(handle, name.clone(), options.worker_type);
+      let (internal_handle, external_handle) = create_handles(
+        handle,
+        name.clone(),
+        options.worker_type,
+        heap_snapshot_sender,
+      );
       let op_state = js_runtime.op_state();
       let mut op_state = op_state.borrow_mut();
       op_state.put(internal_handle.clone());
       (internal_handle, external_handle)
     };
 
+    if let Some(heap_mb) = options.limits.heap_mb {
+      // Terminate the worker instead of letting V8 abort the process.
+      let mut handle = internal_handle.clone();
+      js_runtime.add_near_heap_limit_callback(move |current_limit, _| {
+        if !handle.is_terminated() {
+          _ = handle.post_event(WorkerControlEvent::TerminalError(
+            generic_error(format!(
+              "Worker exceeded its heap limit of {heap_mb} MB"
+            )),
+          ));
+          handle.terminate();
+        }
+        // Leave room for the stack to unwind after the termination.
+        current_limit * 2
+      });
+    }
+
     let bootstrap_fn_global = {
       let context = js_runtime.main_context();
       let scope = &mut js_runtime.handle_scope();
@@ -640,6 +772,8 @@ impl WebWorker {
         close_on_idle: options.close_on_idle,
         has_executed_main_module: false,
         maybe_worker_metadata: options.maybe_worker_metadata,
+        limits: options.limits,
+        heap_snapshot_requests,
       },
       external_handle,
     )
@@ -804,6 +938,17 @@ impl WebWorker {
 
     self.internal_handle.terminate_waker.register(cx.waker());
 
+    while let Poll::Ready(Some(request)) =
+      self.heap_snapshot_requests.poll_next_unpin(cx)
+    {
+      let mut snapshot = Vec::new();
+      self.js_runtime.v8_isolate().take_heap_snapshot(|chunk| {
+        snapshot.extend_from_slice(chunk);
+        true
+      });
+      _ = request.send(snapshot);
+    }
+
     match self.js_runtime.poll_event_loop(cx, poll_options) {
       Poll::Ready(r) => {
         // If js ended because we are terminating, just return Ok
@@ -931,6 +1076,9 @@ pub fn run_web_worker(
 
   let fut = async move {
     let internal_handle = worker.internal_handle.clone();
+    let _cpu_watchdog = worker.limits.cpu_ms.and_then(|cpu_ms| {
+      internal_handle.start_cpu_watchdog(Duration::from_millis(cpu_ms))
+    });
 
     // Execute provided source code immediately
     let result = if let Some(source_code) = maybe_source_code.take() {
@@ -407,6 +407,8 @@ impl MainWorker {
         deno_kv::KvConfig::builder().build(),
       ),
       deno_cron::deno_cron::init_ops_and_esm(LocalCronHandler::new()),
+      deno_archive::deno_archive::init_ops_and_esm::<PermissionsContainer>(),
+      deno_quic::deno_quic::init_ops_and_esm::<PermissionsContainer>(),
       deno_napi::deno_napi::init_ops_and_esm::<PermissionsContainer>(),
       deno_http::deno_http::init_ops_and_esm::<DefaultHttpPropertyExtractor>(),
       deno_io::deno_io::init_ops_and_esm(Some(options.stdio)),
@@ -682,6 +684,9 @@ impl MainWorker {
       if let Some(node_ipc_fd) = options.node_ipc_fd {
         state.put(deno_node::ChildPipeFd(node_ipc_fd));
       }
+      if let Some(deno_ipc) = options.deno_ipc.clone() {
+        state.put(deno_ipc);
+      }
     }
 
     let scope = &mut self.js_runtime.handle_scope();
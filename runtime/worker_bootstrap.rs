@@ -1,5 +1,6 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
+use crate::ops::process::ParentIpcChannel;
 use deno_core::v8;
 use deno_core::ModuleSpecifier;
 use serde::Serialize;
@@ -116,6 +117,9 @@ pub struct BootstrapOptions {
   pub argv0: Option<String>,
   pub node_debug: Option<String>,
   pub node_ipc_fd: Option<i64>,
+  /// The channel to the parent, if spawned with `Deno.Command`'s `ipc`
+  /// option.
+  pub deno_ipc: Option<ParentIpcChannel>,
   pub future: bool,
   pub mode: WorkerExecutionMode,
   // Used by `deno serve`
@@ -153,6 +157,7 @@ impl Default for BootstrapOptions {
       argv0: None,
       node_debug: None,
       node_ipc_fd: None,
+      deno_ipc: None,
       future: false,
       mode: WorkerExecutionMode::None,
       serve_port: Default::default(),
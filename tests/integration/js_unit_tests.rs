@@ -12,6 +12,7 @@ util::unit_test_factory!(
   "*.ts",
   [
     abort_controller_test,
+    archive_test,
     blob_test,
     body_test,
     broadcast_channel_test,
@@ -40,6 +41,7 @@ util::unit_test_factory!(
     files_test,
     fs_events_test,
     get_random_values_test,
+    glob_test,
     globals_test,
     headers_test,
     http_test,
@@ -67,6 +69,7 @@ util::unit_test_factory!(
     process_test,
     progressevent_test,
     promise_hooks_test,
+    quic_test,
     read_dir_test,
     read_file_test,
     read_link_test,
@@ -110,6 +113,7 @@ util::unit_test_factory!(
     worker_test,
     write_file_test,
     write_text_file_test,
+    xattr_test,
   ]
 );
 
@@ -761,6 +761,64 @@ fn permissions_cache() {
     });
 }
 
+#[cfg(unix)]
+#[test]
+fn permissions_prompt_allow_binary() {
+  TestContext::default()
+    .new_command()
+    .args_vec(["run", "--quiet", "run/permissions_prompt_allow_binary.ts"])
+    .with_pty(|mut console| {
+      console.expect(concat!(
+        "┏ ⚠️  Deno requests run access to \"sh\".\r\n",
+        "┠─ Requested by `Deno.Command().spawn()` API.\r\n",
+        "┠─ Learn more at: https://docs.deno.com/go/--allow-run\r\n",
+        "┠─ Run again with --allow-run to bypass this prompt.\r\n",
+        "┗ Allow? [y/n/b/A] (y = yes, allow; n = no, deny; b = allow all future runs of this binary; A = allow all run permissions)",
+      ));
+      console.human_delay();
+      console.write_line_raw("b");
+      console.expect("✅ Granted run access to \"sh\" for all future runs of this binary.");
+      console.expect("first");
+      // "/bin/sh" is the same binary, so it doesn't prompt again
+      console.expect("second");
+      console.expect("prompt");
+    });
+}
+
+#[test]
+fn permission_audit() {
+  let context = TestContextBuilder::new().use_temp_cwd().build();
+  let temp_dir = context.temp_dir();
+  temp_dir.write(
+    "main.ts",
+    "Deno.env.get('HOME');\ntry { Deno.env.get('PATH'); } catch {}\n",
+  );
+  context
+    .new_command()
+    .args(
+      "run --no-prompt --allow-env=HOME --permission-audit=audit.jsonl main.ts",
+    )
+    .run()
+    .skip_output_check()
+    .assert_exit_code(0);
+  let records = temp_dir
+    .read_to_string("audit.jsonl")
+    .lines()
+    .map(|line| deno_core::serde_json::from_str(line).unwrap())
+    .filter(|record: &deno_core::serde_json::Value| {
+      record["permission"] == "env"
+    })
+    .map(|record| (record["descriptor"].clone(), record["result"].clone()))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    records,
+    vec![
+      (json!("HOME"), json!("granted")),
+      (json!("PATH"), json!("denied")),
+    ]
+  );
+}
+
 itest!(env_file {
   args: "run --env=env --allow-env run/env_file.ts",
   output: "run/env_file.out",
@@ -4053,6 +4111,36 @@ async fn test_resolve_dns() {
     assert_eq!(expected, out);
   }
 
+  // Pass: a resolver of its own with `--unstable-net`
+  {
+    let output = util::deno_cmd()
+      .current_dir(util::testdata_path())
+      .env("NO_COLOR", "1")
+      .arg("run")
+      .arg("--check")
+      .arg("--unstable-net")
+      .arg("--allow-net=127.0.0.1:4553")
+      .arg("run/resolve_dns_resolver.ts")
+      .piped_output()
+      .spawn()
+      .unwrap()
+      .wait_with_output()
+      .unwrap();
+    let err = String::from_utf8_lossy(&output.stderr);
+    let out = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+      eprintln!("stderr: {err}");
+    }
+    assert!(output.status.success());
+    assert!(err.starts_with("Check file"));
+
+    let expected = std::fs::read_to_string(
+      util::testdata_path().join("run/resolve_dns_resolver.ts.out"),
+    )
+    .unwrap();
+    assert_eq!(expected, out);
+  }
+
   // Permission error: `--allow-net=deno.land`
   {
     let output = util::deno_cmd()
@@ -4182,6 +4270,300 @@ fn fsfile_set_raw_should_not_panic_on_no_tty() {
   );
 }
 
+#[cfg(not(windows))]
+#[test]
+fn stdin_set_mode() {
+  TestContext::default()
+    .new_command()
+    .args_vec(["run", "--quiet", "--unstable-tty", "run/stdin_set_mode.ts"])
+    .with_pty(|mut console| {
+      console.expect("echo: true canonical: true");
+      console.expect("echo: false canonical: false signals: true vmin: 1");
+      console.write_raw("x");
+      console.expect("read: x");
+      console.expect("restored echo: true canonical: true");
+    });
+}
+
+#[cfg(not(windows))]
+#[test]
+fn stdin_get_mode_should_not_panic_on_no_tty() {
+  let output = util::deno_cmd()
+    .arg("eval")
+    .arg("--unstable-tty")
+    .arg("Deno.stdin.getMode()")
+    .stdin(std::process::Stdio::piped())
+    .stderr_piped()
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(!output.status.success());
+  let stderr = std::str::from_utf8(&output.stderr).unwrap().trim();
+  assert!(stderr.contains("BadResource"), "{stderr}");
+}
+
+#[test]
+fn stdin_get_mode_requires_unstable_flag() {
+  let output = util::deno_cmd()
+    .arg("eval")
+    .arg("Deno.stdin.getMode()")
+    .stdin(std::process::Stdio::piped())
+    .stderr_piped()
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(!output.status.success());
+  let stderr = std::str::from_utf8(&output.stderr).unwrap().trim();
+  assert!(stderr.contains("--unstable-tty"), "{stderr}");
+}
+
+#[test]
+fn stdin_info_pipe() {
+  use std::io::BufRead;
+
+  let mut child = util::deno_cmd()
+    .current_dir(util::testdata_path())
+    .args("run --quiet --unstable-tty run/stdin_info.ts")
+    .stdin_piped()
+    .stdout_piped()
+    .spawn()
+    .unwrap();
+  let mut stdout = BufReader::new(child.stdout.take().unwrap());
+  let mut line = String::new();
+  stdout.read_line(&mut line).unwrap();
+  assert_eq!(line, "pipe undefined false\n");
+
+  let mut stdin = child.stdin.take().unwrap();
+  stdin.write_all(b"hello").unwrap();
+  line.clear();
+  stdout.read_line(&mut line).unwrap();
+  assert_eq!(line, "has data\n");
+  drop(stdin);
+  assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn stdin_info_file() {
+  let path = util::testdata_path().join("run/stdin_info.ts");
+  let size = path.read_to_string().len();
+  let output = util::deno_cmd()
+    .current_dir(util::testdata_path())
+    .args("run --quiet --unstable-tty run/stdin_info.ts")
+    .stdin(std::fs::File::open(&path).unwrap())
+    .stdout_piped()
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(output.status.success());
+  let stdout = std::str::from_utf8(&output.stdout).unwrap();
+  assert_eq!(stdout, format!("file {size} true\n"));
+}
+
+#[test]
+fn unstable_os_apis_require_flag() {
+  let code = concat!(
+    "console.log(typeof Deno.systemCpuUsage, typeof Deno.diskUsage, ",
+    "typeof Deno.processMetrics)"
+  );
+  let output = util::deno_cmd()
+    .arg("eval")
+    .arg(code)
+    .stdout_piped()
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(output.status.success());
+  assert_eq!(
+    std::str::from_utf8(&output.stdout).unwrap(),
+    "undefined undefined undefined\n"
+  );
+
+  let output = util::deno_cmd()
+    .arg("eval")
+    .arg("--unstable-os")
+    .arg(code)
+    .stdout_piped()
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(output.status.success());
+  assert_eq!(
+    std::str::from_utf8(&output.stdout).unwrap(),
+    "function function function\n"
+  );
+}
+
+#[cfg(windows)]
+#[test]
+fn windows_session_signals_require_unstable_flag() {
+  assert_eval_requires_unstable_flag(
+    "Deno.addSignalListener('SIGLOGOFF', () => {})",
+    "process",
+  );
+}
+
+#[test]
+fn signal_namespace_requires_unstable_flag() {
+  let output = util::deno_cmd()
+    .arg("eval")
+    .arg("console.log(typeof Deno.signal)")
+    .stdout_piped()
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(output.status.success());
+  assert_eq!(std::str::from_utf8(&output.stdout).unwrap(), "undefined\n");
+
+  let output = util::deno_cmd()
+    .arg("eval")
+    .arg("--unstable-process")
+    .arg("console.log(typeof Deno.signal.block)")
+    .stdout_piped()
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(output.status.success());
+  assert_eq!(std::str::from_utf8(&output.stdout).unwrap(), "function\n");
+}
+
+/// Asserts that evaluating `code` fails with an error pointing to the
+/// `--unstable-<feature>` flag.
+fn assert_eval_requires_unstable_flag(code: &str, feature: &str) {
+  let output = util::deno_cmd()
+    .arg("eval")
+    .arg("--allow-all")
+    .arg(code)
+    .stderr_piped()
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(!output.status.success());
+  let stderr = std::str::from_utf8(&output.stderr).unwrap().trim();
+  assert!(
+    stderr.contains(&format!("--unstable-{feature}")),
+    "{stderr}"
+  );
+}
+
+#[test]
+fn command_pty_requires_unstable_flag() {
+  assert_eval_requires_unstable_flag(
+    "new Deno.Command(Deno.execPath(), { pty: { rows: 24, cols: 80 } }).spawn()",
+    "process",
+  );
+}
+
+#[test]
+fn command_ipc_requires_unstable_flag() {
+  assert_eval_requires_unstable_flag(
+    "new Deno.Command(Deno.execPath(), { ipc: true }).spawn()",
+    "process",
+  );
+
+  let output = util::deno_cmd()
+    .arg("eval")
+    .arg("console.log(Deno.ipc)")
+    .stdout_piped()
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(output.status.success());
+  assert_eq!(std::str::from_utf8(&output.stdout).unwrap(), "undefined\n");
+}
+
+#[test]
+fn command_shell_requires_unstable_flag() {
+  assert_eval_requires_unstable_flag(
+    "new Deno.Command('echo', { shell: true }).outputSync()",
+    "process",
+  );
+}
+
+#[test]
+fn permissions_snapshot_requires_unstable_flag() {
+  assert_eval_requires_unstable_flag(
+    "Deno.permissions.snapshot()",
+    "permissions",
+  );
+}
+
+#[test]
+fn permission_tokens_require_unstable_flag() {
+  assert_eval_requires_unstable_flag(
+    "Deno.permissions.mintToken({ name: 'run', command: 'deno' })",
+    "permissions",
+  );
+  assert_eval_requires_unstable_flag(
+    "new Deno.Command('deno', { args: ['--version'], permissionToken: 'x' }).outputSync()",
+    "permissions",
+  );
+}
+
+#[test]
+fn fs_file_try_lock_requires_unstable_flag() {
+  assert_eval_requires_unstable_flag(
+    "Deno.openSync(Deno.execPath()).tryLockSync()",
+    "fs",
+  );
+}
+
+#[test]
+fn copy_file_reflink_requires_unstable_flag() {
+  assert_eval_requires_unstable_flag(
+    "Deno.copyFileSync(Deno.execPath(), Deno.makeTempDirSync() + '/deno', { reflink: 'never' })",
+    "fs",
+  );
+}
+
+#[test]
+fn read_dir_stat_requires_unstable_flag() {
+  assert_eval_requires_unstable_flag(
+    "Deno.readDirSync('.', { stat: true })",
+    "fs",
+  );
+}
+
+#[test]
+fn make_temp_file_unnamed_requires_unstable_flag() {
+  assert_eval_requires_unstable_flag(
+    "Deno.makeTempFileSync({ unnamed: true })",
+    "fs",
+  );
+}
+
+#[test]
+fn env_change_events_require_unstable_flag() {
+  assert_eval_requires_unstable_flag(
+    "Deno.env.addEventListener('change', () => {})",
+    "os",
+  );
+}
+
+#[test]
+fn env_diff_requires_unstable_flag() {
+  let output = util::deno_cmd()
+    .arg("eval")
+    .arg("--allow-env")
+    .arg("Deno.env.diff({})")
+    .stderr_piped()
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(!output.status.success());
+  let stderr = std::str::from_utf8(&output.stderr).unwrap().trim();
+  assert!(stderr.contains("--unstable-os"), "{stderr}");
+}
+
 #[test]
 fn timeout_clear() {
   // https://github.com/denoland/deno/issues/7599
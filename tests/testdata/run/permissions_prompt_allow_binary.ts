@@ -0,0 +1,7 @@
+new Deno.Command("sh", { args: ["-c", "echo first"] }).spawn(); // b
+await new Promise((resolve) => setTimeout(resolve, 100));
+const { stdout } = await new Deno.Command("/bin/sh", {
+  args: ["-c", "echo second"],
+}).output();
+console.log(new TextDecoder().decode(stdout).trim());
+console.log((await Deno.permissions.query({ name: "run" })).state);
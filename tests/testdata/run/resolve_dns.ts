@@ -1,6 +1,6 @@
 const nameServer = { nameServer: { ipAddr: "127.0.0.1", port: 4553 } };
 
-const [a, aaaa, aname, caa, cname, mx, naptr, ns, ptr, soa, srv, txt] =
+const [a, aaaa, aname, caa, cname, https, mx, naptr, ns, ptr, soa, srv, txt] =
   await Promise
     .all([
       Deno.resolveDns("www.example.com", "A", nameServer),
@@ -8,6 +8,7 @@ const [a, aaaa, aname, caa, cname, mx, naptr, ns, ptr, soa, srv, txt] =
       Deno.resolveDns("www.example.com", "ANAME", nameServer),
       Deno.resolveDns("example.com", "CAA", nameServer),
       Deno.resolveDns("alias.example.com", "CNAME", nameServer),
+      Deno.resolveDns("example.com", "HTTPS", nameServer),
       Deno.resolveDns("example.com", "MX", nameServer),
       Deno.resolveDns("example.com", "NAPTR", nameServer),
       Deno.resolveDns("example.com", "NS", nameServer),
@@ -32,6 +33,9 @@ console.log(JSON.stringify(caa));
 console.log("CNAME");
 console.log(JSON.stringify(cname));
 
+console.log("HTTPS");
+console.log(JSON.stringify(https));
+
 console.log("MX");
 console.log(JSON.stringify(mx));
 
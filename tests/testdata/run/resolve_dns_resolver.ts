@@ -0,0 +1,28 @@
+const nameServer = { ipAddr: "127.0.0.1", port: 4553 };
+
+using udpResolver = Deno.createDnsResolver({ nameServers: [nameServer] });
+using tcpResolver = Deno.createDnsResolver({
+  nameServers: [{ ...nameServer, protocol: "tcp" }],
+  cacheSize: 0,
+});
+
+for (const resolver of [udpResolver, tcpResolver]) {
+  // the second query of the UDP resolver is answered by its cache
+  for (let i = 0; i < 2; i++) {
+    const a = await Deno.resolveDns("www.example.com", "A", { resolver });
+    console.log(JSON.stringify(a));
+  }
+  const https = await Deno.resolveDns("example.com", "HTTPS", { resolver });
+  console.log(JSON.stringify(https));
+  resolver.clearCache();
+
+  try {
+    await Deno.resolveDns("missing.example.com", "A", { resolver });
+  } catch (e) {
+    console.log(
+      `Error ${
+        e instanceof Error ? e.name : "[non-error]"
+      } thrown for missing.example.com`,
+    );
+  }
+}
@@ -0,0 +1,9 @@
+const { kind, size, hasData } = Deno.stdin.info();
+console.log(kind, size, hasData);
+
+if (kind === "pipe") {
+  while (!Deno.stdin.info().hasData) {
+    await new Promise((resolve) => setTimeout(resolve, 10));
+  }
+  console.log("has data");
+}
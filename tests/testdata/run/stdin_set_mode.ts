@@ -0,0 +1,16 @@
+let mode = Deno.stdin.getMode();
+console.log(`echo: ${mode.echo} canonical: ${mode.canonical}`);
+
+Deno.stdin.setMode({ echo: false, canonical: false, vmin: 1, vtime: 0 });
+mode = Deno.stdin.getMode();
+console.log(
+  `echo: ${mode.echo} canonical: ${mode.canonical} signals: ${mode.signals} vmin: ${mode.vmin}`,
+);
+
+const buf = new Uint8Array(1);
+await Deno.stdin.read(buf);
+console.log(`read: ${new TextDecoder().decode(buf)}`);
+
+Deno.stdin.setRaw(false);
+mode = Deno.stdin.getMode();
+console.log(`restored echo: ${mode.echo} canonical: ${mode.canonical}`);
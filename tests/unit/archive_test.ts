@@ -0,0 +1,127 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import {
+  assertEquals,
+  assertRejects,
+  assertThrows,
+  makeTempTree,
+} from "./test_util.ts";
+
+function makeTree(): string {
+  return makeTempTree({
+    "mod.ts": "export * from './src/a.ts';\n",
+    "src/a.ts": "export const a = 1;\n",
+    "src/sub/data.bin": new Uint8Array(200_000),
+  });
+}
+
+function assertTree(dir: string) {
+  assertEquals(
+    Deno.readTextFileSync(`${dir}/mod.ts`),
+    "export * from './src/a.ts';\n",
+  );
+  assertEquals(
+    Deno.readTextFileSync(`${dir}/src/a.ts`),
+    "export const a = 1;\n",
+  );
+  assertEquals(Deno.readFileSync(`${dir}/src/sub/data.bin`).length, 200_000);
+}
+
+/** A tar archive with a single file at `name`, which isn't validated. */
+function tarOf(name: string, content: string): Uint8Array {
+  const encoder = new TextEncoder();
+  const data = encoder.encode(content);
+  const header = new Uint8Array(512);
+  const field = (offset: number, value: string) =>
+    header.set(encoder.encode(value), offset);
+  field(0, name);
+  field(100, "0000644\0");
+  field(108, "0000000\0");
+  field(116, "0000000\0");
+  field(124, data.length.toString(8).padStart(11, "0") + "\0");
+  field(136, "00000000000\0");
+  field(148, "        ");
+  field(156, "0");
+  field(257, "ustar\x0000");
+  const checksum = header.reduce((sum, byte) => sum + byte, 0);
+  field(148, checksum.toString(8).padStart(6, "0") + "\0 ");
+  const padded = Math.ceil(data.length / 512) * 512;
+  const archive = new Uint8Array(512 + padded + 1024);
+  archive.set(header);
+  archive.set(data, 512);
+  return archive;
+}
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function archiveRoundTrip() {
+    const root = makeTree();
+    for (const compression of ["none", "gzip", "zstd"] as const) {
+      const dest = Deno.makeTempDirSync();
+      await Deno.extractArchive(
+        Deno.createArchive(root, { compression }),
+        dest,
+      );
+      assertTree(dest);
+    }
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function archiveFromFile() {
+    const root = makeTree();
+    const archive = `${Deno.makeTempDirSync()}/archive.tar.zst`;
+    using file = await Deno.create(archive);
+    await Deno.createArchive(root, { compression: "zstd", prefix: "package" })
+      .pipeTo(file.writable);
+
+    const dest = Deno.makeTempDirSync();
+    await Deno.extractArchive(archive, dest);
+    assertTree(`${dest}/package`);
+
+    const stripped = Deno.makeTempDirSync();
+    await Deno.extractArchive(archive, stripped, { stripComponents: 1 });
+    assertTree(stripped);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function archiveLeavesOutParentDirs() {
+    const dir = Deno.makeTempDirSync();
+    const dest = `${dir}/dest`;
+    for (const archive of [tarOf("../evil", ""), tarOf("ok.txt", "ok")]) {
+      await Deno.extractArchive(ReadableStream.from([archive]), dest);
+    }
+    assertEquals([...Deno.readDirSync(dir)].map((e) => e.name), ["dest"]);
+    assertEquals(Deno.readTextFileSync(`${dest}/ok.txt`), "ok");
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function archiveInvalid() {
+    const dest = Deno.makeTempDirSync();
+    const data = new TextEncoder().encode("not an archive".repeat(100));
+    await assertRejects(() =>
+      Deno.extractArchive(ReadableStream.from([data]), dest)
+    );
+    await assertRejects(
+      () => Deno.extractArchive(`${dest}/missing.tar`, dest),
+      Deno.errors.NotFound,
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { read: false, write: false } },
+  async function archivePerm() {
+    assertThrows(() => {
+      Deno.createArchive(".");
+    }, Deno.errors.PermissionDenied);
+    await assertRejects(
+      () => Deno.extractArchive(ReadableStream.from([]), "."),
+      Deno.errors.PermissionDenied,
+    );
+  },
+);
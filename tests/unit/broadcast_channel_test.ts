@@ -32,3 +32,40 @@ Deno.test("BroadcastChannel immediate close after post", () => {
   bc.postMessage("New listening connected!");
   bc.close();
 });
+
+Deno.test(
+  { permissions: { run: true, read: true, write: true } },
+  async function broadcastChannelIpc() {
+    const dir = await Deno.makeTempDir();
+    const args = (code: string) => [
+      "eval",
+      "--unstable-broadcast-channel",
+      `--broadcast-channel-ipc=${dir}/bc.sock`,
+      code,
+    ];
+    const responder = new Deno.Command(Deno.execPath(), {
+      args: args(`
+        const bc = new BroadcastChannel("ipc");
+        bc.onmessage = (e) => bc.postMessage(e.data + " pong");
+      `),
+    }).spawn();
+    try {
+      const { stdout } = await new Deno.Command(Deno.execPath(), {
+        args: args(`
+          const bc = new BroadcastChannel("ipc");
+          const id = setInterval(() => bc.postMessage("ping"), 50);
+          bc.onmessage = (e) => {
+            console.log(e.data);
+            clearInterval(id);
+            bc.close();
+          };
+        `),
+      }).output();
+      assertEquals(new TextDecoder().decode(stdout), "ping pong\n");
+    } finally {
+      responder.kill();
+      await responder.status;
+      await Deno.remove(dir, { recursive: true });
+    }
+  },
+);
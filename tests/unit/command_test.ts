@@ -348,6 +348,124 @@ Deno.test(
   },
 );
 
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandKillTree() {
+    const command = new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        `
+        new Deno.Command(Deno.execPath(), {
+          args: ["eval", "setTimeout(() => {}, 10000)"],
+          stdout: "inherit",
+        }).spawn();
+        console.log("ready");
+        setTimeout(() => {}, 10000);
+        `,
+      ],
+      stdout: "piped",
+      stderr: "null",
+      processGroup: true,
+    });
+    const child = command.spawn();
+    const reader = child.stdout.getReader();
+    await reader.read();
+
+    child.kill("SIGKILL", { tree: true });
+    // stdout is only closed once the grandchild, which inherited it, has
+    // exited as well
+    while (!(await reader.read()).done);
+    const status = await child.status;
+
+    assertEquals(status.success, false);
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os !== "windows",
+  },
+  function commandProcessGroupSyncWindows() {
+    // the child is spawned suspended, and has to be resumed without a job
+    const { success, stdout } = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "console.log('hello')"],
+      processGroup: true,
+    }).outputSync();
+    assert(success);
+    assertEquals(new TextDecoder().decode(stdout), "hello\n");
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandKillTreeWithoutProcessGroup() {
+    const command = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "setTimeout(() => {}, 10000)"],
+      stdout: "null",
+      stderr: "null",
+    });
+    const child = command.spawn();
+
+    assertThrows(
+      () => child.kill("SIGTERM", { tree: true }),
+      TypeError,
+      "Child process was not spawned in its own process group.",
+    );
+
+    child.kill();
+    await child.status;
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandPty() {
+    const command = new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        `
+        const { rows, columns } = Deno.consoleSize();
+        console.log(Deno.stdin.isTerminal(), rows, columns);
+        const buf = new Uint8Array(5);
+        await Deno.stdin.read(buf);
+        `,
+      ],
+      pty: { rows: 24, cols: 80 },
+    });
+    const child = command.spawn();
+    assertThrows(() => child.stdout, TypeError, "stdout is not piped");
+
+    const writer = child.pty.writable.getWriter();
+    // a terminal translates the carriage return of the enter key to a newline
+    await writer.write(new TextEncoder().encode("hello\r"));
+    writer.releaseLock();
+
+    let output = "";
+    for await (const chunk of child.pty.readable) {
+      output += new TextDecoder().decode(chunk);
+    }
+    const status = await child.status;
+
+    assert(status.success);
+    assertStringIncludes(output, "true 24 80");
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandPtyNotEnabled() {
+    const command = new Deno.Command(Deno.execPath(), {
+      args: ["eval", ""],
+      stdout: "null",
+      stderr: "null",
+    });
+    const child = command.spawn();
+    assertThrows(() => child.pty, TypeError, "pty is not enabled");
+    await child.status;
+  },
+);
+
 Deno.test(
   { permissions: { run: true, read: true } },
   async function commandAbort() {
@@ -355,23 +473,751 @@ Deno.test(
     const command = new Deno.Command(Deno.execPath(), {
       args: [
         "eval",
-        "setTimeout(console.log, 1e8)",
+        "setTimeout(console.log, 1e8)",
+      ],
+      signal: ac.signal,
+      stdout: "null",
+      stderr: "null",
+    });
+    const child = command.spawn();
+    queueMicrotask(() => ac.abort());
+    const status = await child.status;
+    assertEquals(status.success, false);
+    if (Deno.build.os === "windows") {
+      assertEquals(status.code, 1);
+      assertEquals(status.signal, null);
+    } else {
+      assertEquals(status.success, false);
+      assertEquals(status.code, 143);
+    }
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os === "windows",
+  },
+  async function commandAbortGracePeriod() {
+    const ac = new AbortController();
+    const command = new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        `
+        Deno.addSignalListener("SIGTERM", () => {});
+        console.log("ready");
+        setTimeout(() => {}, 1e8);
+        `,
+      ],
+      signal: ac.signal,
+      abortGracePeriod: 100,
+      stdout: "piped",
+      stderr: "null",
+    });
+    const child = command.spawn();
+    const reader = child.stdout.getReader();
+    await reader.read();
+    reader.releaseLock();
+
+    ac.abort();
+    const status = await child.status;
+    assertEquals(status.success, false);
+    assertEquals(status.code, 137);
+    assertEquals(status.signal, "SIGKILL");
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandTimeout() {
+    const command = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "setTimeout(() => {}, 1e8)"],
+      stdout: "null",
+      stderr: "null",
+      timeout: 100,
+    });
+    const status = await command.spawn().status;
+    assertEquals(status.success, false);
+    assertEquals(status.timedOut, true);
+    if (Deno.build.os !== "windows") {
+      assertEquals(status.signal, "SIGTERM");
+    }
+
+    const output = await new Deno.Command(Deno.execPath(), {
+      args: ["eval", ""],
+      timeout: 60_000,
+    }).output();
+    assert(output.success);
+    assertEquals(output.timedOut, false);
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os === "windows",
+  },
+  async function commandTimeoutKillsAfterGracePeriod() {
+    const status = await new Deno.Command("sh", {
+      args: ["-c", "trap '' TERM; exec sleep 60"],
+      timeout: 100,
+    }).spawn().status;
+    assertEquals(status.timedOut, true);
+    assertEquals(status.signal, "SIGKILL");
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os !== "linux",
+  },
+  async function commandUnshareNet() {
+    const { success, stdout } = await new Deno.Command("cat", {
+      args: ["/proc/net/dev"],
+      unshare: { user: true, net: true },
+    }).output();
+    assert(success);
+    // the first two lines are headers
+    const interfaces = new TextDecoder().decode(stdout).trim().split("\n")
+      .slice(2).map((line) => line.split(":")[0].trim());
+    assertEquals(interfaces, ["lo"]);
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os !== "linux",
+  },
+  async function commandUnshareUserRunsAsOverflowUid() {
+    const { success, stdout } = await new Deno.Command("id", {
+      args: ["-u"],
+      unshare: { user: true },
+    }).output();
+    assert(success);
+    const overflowUid = await Deno.readTextFile(
+      "/proc/sys/kernel/overflowuid",
+    );
+    assertEquals(new TextDecoder().decode(stdout).trim(), overflowUid.trim());
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  function commandChrootRequiresAllowAll() {
+    assertThrows(
+      () =>
+        new Deno.Command(Deno.execPath(), {
+          args: ["eval", ""],
+          chroot: "/",
+        }).spawn(),
+      Deno.errors.PermissionDenied,
+      "Requires --allow-all permissions to spawn subprocess with chroot.",
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandPriority() {
+    const { success, stdout } = await new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        "import os from 'node:os'; console.log(os.getPriority())",
+      ],
+      priority: 10,
+    }).output();
+    assert(success);
+    assertEquals(new TextDecoder().decode(stdout), "10\n");
+
+    assertThrows(
+      () =>
+        new Deno.Command(Deno.execPath(), {
+          args: ["eval", ""],
+          priority: 20,
+        }).spawn(),
+      TypeError,
+      "Invalid priority: 20",
+    );
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os !== "linux",
+  },
+  async function commandCpuAffinity() {
+    const { success, stdout } = await new Deno.Command("grep", {
+      args: ["Cpus_allowed_list", "/proc/self/status"],
+      cpuAffinity: [0],
+    }).output();
+    assert(success);
+    assertEquals(new TextDecoder().decode(stdout), "Cpus_allowed_list:\t0\n");
+
+    assertThrows(
+      () => new Deno.Command("true", { cpuAffinity: [100000] }).spawn(),
+      TypeError,
+      "Invalid CPU: 100000",
+    );
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os !== "windows",
+  },
+  async function commandCpuAffinityWindows() {
+    const { success, stdout } = await new Deno.Command("powershell.exe", {
+      args: [
+        "-NoProfile",
+        "-Command",
+        "[System.Diagnostics.Process]::GetCurrentProcess().ProcessorAffinity",
+      ],
+      cpuAffinity: [0],
+    }).output();
+    assert(success);
+    assertEquals(new TextDecoder().decode(stdout).trim(), "1");
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true, write: true },
+    ignore: Deno.build.os === "windows",
+  },
+  async function commandExitShutsDownChildren() {
+    const marker = `${await Deno.makeTempDir()}/terminated`;
+    const childCode = `
+      Deno.addSignalListener("SIGTERM", () => {
+        Deno.writeTextFileSync(${JSON.stringify(marker)}, "SIGTERM");
+        Deno.exit(0);
+      });
+      console.log("ready");
+      setTimeout(() => {}, 10000);
+    `;
+    const parentCode = `
+      const child = new Deno.Command(Deno.execPath(), {
+        args: ["eval", ${JSON.stringify(childCode)}],
+        stdout: "piped",
+      }).spawn();
+      // wait for the signal listener to be installed
+      await child.stdout.getReader().read();
+      Deno.exit(0);
+    `;
+    const { success } = await new Deno.Command(Deno.execPath(), {
+      args: ["eval", parentCode],
+    }).output();
+    assert(success);
+    assertEquals(await Deno.readTextFile(marker), "SIGTERM");
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true, write: true },
+    ignore: Deno.build.os === "windows",
+  },
+  async function commandExitShutsDownChildrenWithConfiguredSignal() {
+    const marker = `${await Deno.makeTempDir()}/terminated`;
+    const childCode = `
+      Deno.addSignalListener("SIGINT", () => {
+        Deno.writeTextFileSync(${JSON.stringify(marker)}, "SIGINT");
+        Deno.exit(0);
+      });
+      console.log("ready");
+      setTimeout(() => {}, 10000);
+    `;
+    const parentCode = `
+      const child = new Deno.Command(Deno.execPath(), {
+        args: ["eval", ${JSON.stringify(childCode)}],
+        stdout: "piped",
+      }).spawn();
+      await child.stdout.getReader().read();
+      Deno.exit(0);
+    `;
+    const { success } = await new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        "--child-shutdown-signal=SIGINT",
+        "--child-shutdown-timeout=5000",
+        parentCode,
+      ],
+    }).output();
+    assert(success);
+    assertEquals(await Deno.readTextFile(marker), "SIGINT");
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandListChildren() {
+    const { listChildren } = Deno[Deno.internal];
+    const child = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "setTimeout(() => {}, 10000)"],
+    }).spawn();
+    const children = listChildren();
+    assertEquals(children.length, 1);
+    assertEquals(children[0].pid, child.pid);
+    assertEquals(children[0].command, Deno.execPath());
+
+    child.kill("SIGKILL");
+    await child.status;
+    assertEquals(listChildren(), []);
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os !== "windows",
+  },
+  async function commandCreationFlags() {
+    const CREATE_NO_WINDOW = 0x08000000;
+    const { success, stdout } = await new Deno.Command(Deno.execPath(), {
+      args: ["eval", "console.log('hello')"],
+      creationFlags: CREATE_NO_WINDOW,
+      processGroup: true,
+    }).output();
+    assert(success);
+    assertEquals(new TextDecoder().decode(stdout), "hello\n");
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os !== "windows",
+  },
+  async function commandKillSigbreakWindows() {
+    const child = new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        `Deno.addSignalListener("SIGBREAK", () => Deno.exit(3));
+        console.log("ready");
+        setTimeout(() => {}, 10000);`,
+      ],
+      stdout: "piped",
+      processGroup: true,
+    }).spawn();
+    const reader = child.stdout.getReader();
+    await reader.read();
+    reader.releaseLock();
+
+    child.kill("SIGBREAK");
+    const status = await child.status;
+    assertEquals(status.code, 3);
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os !== "windows",
+  },
+  async function commandKillConsoleEventsRejectedWindows() {
+    const child = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "setTimeout(() => {}, 10000)"],
+    }).spawn();
+    assertThrows(
+      () => child.kill("SIGBREAK"),
+      TypeError,
+      "SIGBREAK can only be sent to child processes spawned with processGroup: true",
+    );
+    assertThrows(
+      () => child.kill("SIGINT"),
+      TypeError,
+      "Invalid signal: SIGINT",
+    );
+    child.kill();
+    await child.status;
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandTeeStdout() {
+    const child = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "console.log('x'.repeat(200000))"],
+      stdout: "piped",
+    }).spawn();
+    const streams = child.teeStdout(3);
+    assertEquals(streams.length, 3);
+    assertThrows(() => child.stdout, TypeError, "teeStdout()");
+    // a canceled stream doesn't hold back the others
+    await streams[2].cancel();
+
+    const [first, second] = await Promise.all([
+      new Response(streams[0]).text(),
+      new Response(streams[1]).text(),
+    ]);
+    assertEquals(first, "x".repeat(200000) + "\n");
+    assertEquals(second, first);
+    assert((await child.status).success);
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true, write: true } },
+  async function commandSpliceStdoutToFile() {
+    const path = Deno.makeTempFileSync();
+    const child = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "console.log('x'.repeat(200000))"],
+      stdout: "piped",
+    }).spawn();
+    {
+      using file = Deno.openSync(path, { write: true });
+      assertEquals(await child.spliceStdout(file), 200001);
+    }
+    assertThrows(() => child.stdout, TypeError, "spliceStdout()");
+    assertEquals(Deno.readTextFileSync(path), "x".repeat(200000) + "\n");
+    assert((await child.status).success);
+    Deno.removeSync(path);
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true, net: true } },
+  async function commandSpliceStdoutToConn() {
+    using listener = Deno.listen({ hostname: "127.0.0.1", port: 0 });
+    const accepted = listener.accept();
+    const conn = await Deno.connect(listener.addr as Deno.NetAddr);
+    const child = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "console.log('x'.repeat(200000))"],
+      stdout: "piped",
+    }).spawn();
+
+    const spliced = child.spliceStdout(conn).then(() => conn.close());
+    const received = await new Response((await accepted).readable).text();
+    await spliced;
+    assertEquals(received, "x".repeat(200000) + "\n");
+    assert((await child.status).success);
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandLines() {
+    const child = new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        `await Deno.stdout.write(new TextEncoder().encode("a\\r\\n\\nb\\nc"));
+        console.error("error");`,
       ],
-      signal: ac.signal,
-      stdout: "null",
-      stderr: "null",
+      stdout: "piped",
+      stderr: "piped",
+    }).spawn();
+    const stdout = child.lines();
+    assertThrows(() => child.stdout, TypeError, "lines()");
+    assertThrows(() => child.lines("stdout"), TypeError, "lines()");
+
+    const lines = [];
+    for await (const line of stdout) {
+      lines.push(line);
+    }
+    assertEquals(lines, ["a", "", "b", "c"]);
+    assertEquals(await Array.fromAsync(child.lines("stderr")), ["error"]);
+    assert((await child.status).success);
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandTryStatus() {
+    const child = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "setTimeout(() => {}, 10000)"],
+    }).spawn();
+    assertEquals(child.tryStatus(), null);
+
+    child.kill("SIGKILL");
+    let status = child.tryStatus();
+    while (status === null) {
+      await new Promise((resolve) => setTimeout(resolve, 10));
+      status = child.tryStatus();
+    }
+    assertEquals(status.success, false);
+    assertEquals(status, await child.status);
+    assertEquals(child.tryStatus(), status);
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandTryStatusAfterStatus() {
+    const child = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "Deno.exit(3)"],
+    }).spawn();
+    const status = await child.status;
+    assertEquals(status.code, 3);
+    assertEquals(child.tryStatus(), status);
+    assertEquals(child.tryStatus(), status);
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os !== "linux",
+  },
+  async function commandReapsChildrenOfTerminatedWorker() {
+    const code = `
+      const child = new Deno.Command("sleep", { args: ["60"] }).spawn();
+      postMessage(child.pid);
+    `;
+    const worker = new Worker(
+      `data:application/javascript,${encodeURIComponent(code)}`,
+      { type: "module", deno: { permissions: "inherit" } },
+    );
+    const { promise, resolve } = Promise.withResolvers<number>();
+    worker.onmessage = (e) => resolve(e.data);
+    const pid = await promise;
+    worker.terminate();
+
+    // a zombie is still listed in /proc until it is reaped
+    let reaped = false;
+    for (let i = 0; i < 100 && !reaped; i++) {
+      await new Promise((resolve) => setTimeout(resolve, 50));
+      reaped = await Deno.stat(`/proc/${pid}`).then(() => false, () => true);
+    }
+    assert(reaped);
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os !== "linux" && Deno.build.os !== "windows",
+  },
+  async function commandResourceUsage() {
+    const command = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "const a = new Uint8Array(64 * 1024 * 1024).fill(1);"],
     });
-    const child = command.spawn();
-    queueMicrotask(() => ac.abort());
+    const { usage } = await command.spawn().status;
+    assert(usage !== null);
+    assert(usage.userCpuTime + usage.systemCpuTime > 0);
+    assert(usage.maxRss >= 64 * 1024 * 1024);
+
+    const output = command.outputSync();
+    assert(output.usage !== null);
+    assert(output.usage.maxRss >= 64 * 1024 * 1024);
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandIpc() {
+    for (const serialization of ["json", "advanced"] as const) {
+      const child = new Deno.Command(Deno.execPath(), {
+        args: [
+          "eval",
+          "--unstable-process",
+          `
+          Deno.ipc.onmessage = async ({ data }) => {
+            await Deno.ipc.send({ echo: data, serialization: Deno.ipc.serialization });
+            Deno.ipc.close();
+          };
+          `,
+        ],
+        ipc: true,
+        serialization,
+      }).spawn();
+
+      const message = Promise.withResolvers<unknown>();
+      child.onmessage = ({ data }) => message.resolve(data);
+      const data = serialization === "advanced"
+        ? new Map([["date", new Date(0)]])
+        : { hello: [1, 2, 3] };
+      await child.send(data);
+
+      assertEquals(await message.promise, { echo: data, serialization });
+      assert((await child.status).success);
+    }
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandIpcNotEnabled() {
+    const child = new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        "--unstable-process",
+        "if (Deno.ipc !== null) Deno.exit(1)",
+      ],
+    }).spawn();
+    assertThrows(() => child.send("hello"), TypeError, "ipc is not enabled");
+    assert((await child.status).success);
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandShell() {
+    const { stdout } = await new Deno.Command("echo $FOO | cat", {
+      env: { FOO: "bar" },
+      shell: true,
+    }).output();
+    assertEquals(new TextDecoder().decode(stdout), "bar\n");
+
+    const output = new Deno.Command("echo", {
+      args: ["it's", "$FOO", "&&", "a  b"],
+      env: { FOO: "bar" },
+      shell: true,
+    }).outputSync();
+    assertEquals(
+      new TextDecoder().decode(output.stdout),
+      "it's $FOO && a  b\n",
+    );
+
+    assertThrows(
+      () => new Deno.Command("echo (", { shell: true }).spawn(),
+      TypeError,
+      "Invalid shell command",
+    );
+    assertThrows(
+      () =>
+        new Deno.Command("echo", { shell: true, processGroup: true }).spawn(),
+      TypeError,
+      "The processGroup option is not supported by the built-in shell",
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandShellKill() {
+    const child = new Deno.Command("sleep 10", { shell: true }).spawn();
+    assertThrows(() => child.pid, TypeError, "pid is not available");
+    assertThrows(
+      () => child.kill("SIGINT"),
+      Deno.errors.NotSupported,
+      "Sending SIGINT to a command run by the built-in shell is not supported",
+    );
+    child.kill();
     const status = await child.status;
     assertEquals(status.success, false);
-    if (Deno.build.os === "windows") {
-      assertEquals(status.code, 1);
-      assertEquals(status.signal, null);
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os === "windows",
+  },
+  async function commandShellProgram() {
+    const { stdout } = await new Deno.Command("echo hello | tr a-z A-Z", {
+      shell: "/bin/sh",
+    }).output();
+    assertEquals(new TextDecoder().decode(stdout), "HELLO\n");
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os === "windows",
+  },
+  async function commandArgv0() {
+    // without further arguments, `$0` is the shell's own `argv[0]`
+    const { stdout } = await new Deno.Command("sh", {
+      args: ["-c", "echo $0"],
+      argv0: "custom",
+    }).output();
+    assertEquals(new TextDecoder().decode(stdout), "custom\n");
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandStdinBytes() {
+    // larger than a pipe buffer, so that writing it all at once would block
+    const bytes = new Uint8Array(1024 * 1024).fill(97);
+    const { success, stdout } = await new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        "await Deno.stdin.readable.pipeTo(Deno.stdout.writable);",
+      ],
+      stdin: { bytes },
+    }).output();
+    assert(success);
+    assertEquals(stdout, bytes);
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  function commandSyncStdinBytes() {
+    const bytes = new Uint8Array(1024 * 1024).fill(97);
+    const { success, stdout } = new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        "await Deno.stdin.readable.pipeTo(Deno.stdout.writable);",
+      ],
+      stdin: { bytes },
+    }).outputSync();
+    assert(success);
+    assertEquals(stdout, bytes);
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  function commandStdinBytesInvalid() {
+    assertThrows(
+      () =>
+        new Deno.Command(Deno.execPath(), {
+          // @ts-expect-error a string isn't allowed
+          stdin: { bytes: "hello" },
+        }).outputSync(),
+      TypeError,
+      "stdin.bytes must be a Uint8Array",
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandStartTimeAndPidfd() {
+    const before = Date.now();
+    const child = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "setTimeout(() => {}, 100)"],
+    }).spawn();
+    // `Date.now()` is rounded down to whole milliseconds
+    assert(child.startTime >= before && child.startTime < Date.now() + 1);
+    if (Deno.build.os === "linux") {
+      assert(typeof child.pidfd === "number");
     } else {
-      assertEquals(status.success, false);
-      assertEquals(status.code, 143);
+      assertEquals(child.pidfd, null);
     }
+    await child.status;
+  },
+);
+
+Deno.test(
+  { permissions: { run: true }, ignore: Deno.build.os === "windows" },
+  function commandUmask() {
+    const { stdout } = new Deno.Command("sh", {
+      args: ["-c", "umask"],
+      umask: 0o077,
+    }).outputSync();
+    assertEquals(new TextDecoder().decode(stdout), "0077\n");
+    assertThrows(
+      () => new Deno.Command("sh", { umask: 0o1000 }).outputSync(),
+      TypeError,
+      "Invalid umask",
+    );
+  },
+);
+
+Deno.test(
+  { permissions: { run: ["echo"], read: true } },
+  function commandShellRequiresAllowRunAll() {
+    assertThrows(
+      () => new Deno.Command("echo", { shell: true }).spawn(),
+      Deno.errors.PermissionDenied,
+    );
   },
 );
 
@@ -479,11 +1325,26 @@ Deno.test({ permissions: { run: true, read: true } }, function cwdNotFound() {
       new Deno.Command(Deno.execPath(), {
         cwd: Deno.cwd() + "/non-existent-directory",
       }).output(),
-    Deno.errors.NotFound,
+    Deno.errors.CwdNotFound,
     "No such cwd",
   );
 });
 
+Deno.test(
+  { permissions: { run: true, read: true } },
+  function cwdNotFoundSync() {
+    const error = assertThrows(
+      () =>
+        new Deno.Command(Deno.execPath(), {
+          cwd: Deno.cwd() + "/non-existent-directory",
+        }).outputSync(),
+      Deno.errors.CwdNotFound,
+      "No such cwd",
+    );
+    assert(error instanceof Deno.errors.NotFound);
+  },
+);
+
 Deno.test(
   { permissions: { run: true, read: true } },
   function cwdNotDirectory() {
@@ -492,7 +1353,7 @@ Deno.test(
         new Deno.Command(Deno.execPath(), {
           cwd: Deno.execPath(),
         }).output(),
-      Deno.errors.NotFound,
+      Deno.errors.CwdNotDirectory,
       "cwd is not a directory",
     );
   },
@@ -590,6 +1451,27 @@ Deno.test(
   },
 );
 
+Deno.test(
+  { permissions: { run: true, read: true } },
+  function commandSyncMaxBuffer() {
+    const command = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "console.log('a'.repeat(1024))"],
+      maxBuffer: 10,
+    });
+    assertThrows(
+      () => command.outputSync(),
+      Deno.errors.MaxBufferExceeded,
+      "stdout maxBuffer length of 10 bytes exceeded",
+    );
+
+    const { stdout } = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "console.log('a'.repeat(1024))"],
+      maxBuffer: 1025,
+    }).outputSync();
+    assertEquals(stdout.length, 1025);
+  },
+);
+
 Deno.test(
   { permissions: { run: true, read: true } },
   async function commandStderrOutput() {
@@ -706,6 +1588,40 @@ Deno.test(
   },
 );
 
+Deno.test(
+  { permissions: { run: true, read: true, env: true } },
+  async function commandEnvInherit() {
+    Deno.env.set("DENO_TEST_SECRET", "secret");
+    Deno.env.set("DENO_TEST_LC_ALL", "C");
+    try {
+      const { stdout } = await new Deno.Command(Deno.execPath(), {
+        args: ["eval", "-p", "JSON.stringify(Deno.env.toObject())"],
+        envInherit: ["PATH", /^deno_test_lc_/i],
+        env: { FOO: "23147" },
+      }).output();
+
+      const obj = JSON.parse(new TextDecoder().decode(stdout));
+      assertEquals(obj.PATH, Deno.env.get("PATH"));
+      assertEquals(obj.DENO_TEST_LC_ALL, "C");
+      assertEquals(obj.FOO, "23147");
+      assert(!("DENO_TEST_SECRET" in obj));
+    } finally {
+      Deno.env.delete("DENO_TEST_SECRET");
+      Deno.env.delete("DENO_TEST_LC_ALL");
+    }
+
+    assertThrows(
+      () =>
+        new Deno.Command(Deno.execPath(), {
+          clearEnv: true,
+          envInherit: ["PATH"],
+        }).spawn(),
+      TypeError,
+      "can't be combined",
+    );
+  },
+);
+
 Deno.test(
   {
     permissions: { run: true, read: true },
@@ -975,3 +1891,147 @@ Deno.test(
     );
   },
 );
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandPermissionToken() {
+    const token = Deno.permissions.mintToken({
+      name: "run",
+      command: Deno.execPath(),
+    });
+    Deno.permissions.revokeSync({ name: "run" });
+
+    const options = { args: ["eval", "console.log('hello world')"] };
+    await assertRejects(
+      () => new Deno.Command(Deno.execPath(), options).output(),
+      Deno.errors.PermissionDenied,
+    );
+    const { success, stdout } = await new Deno.Command(Deno.execPath(), {
+      ...options,
+      permissionToken: token,
+    }).output();
+    assert(success);
+    assertEquals(new TextDecoder().decode(stdout), "hello world\n");
+
+    // a token only grants the command it was minted for
+    assertThrows(
+      () => new Deno.Command("echo", { permissionToken: token }).outputSync(),
+      Deno.errors.PermissionDenied,
+    );
+    assertThrows(
+      () => Deno.permissions.mintToken({ name: "run", command: "echo" }),
+      Deno.errors.PermissionDenied,
+    );
+
+    assert(Deno.permissions.revokeToken(token));
+    assert(!Deno.permissions.revokeToken(token));
+    await assertRejects(
+      () =>
+        new Deno.Command(Deno.execPath(), {
+          ...options,
+          permissionToken: token,
+        }).output(),
+      Deno.errors.PermissionDenied,
+    );
+  },
+);
+
+Deno.test(
+  {
+    permissions: { run: true, read: true },
+    ignore: Deno.build.os === "windows",
+  },
+  async function commandForwardSignals() {
+    const child = new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        `Deno.addSignalListener("SIGTERM", () => {
+          console.log("got SIGTERM");
+          Deno.exit(3);
+        });
+        console.log("ready");
+        setInterval(() => {}, 1000);`,
+      ],
+      stdout: "piped",
+      forwardSignals: true,
+    }).spawn();
+    const reader = child.stdout.pipeThrough(new TextDecoderStream())
+      .getReader();
+    let output = "";
+    while (!output.includes("ready")) {
+      output += (await reader.read()).value;
+    }
+
+    // doesn't terminate this process, as the signal is forwarded
+    Deno.kill(Deno.pid, "SIGTERM");
+    const status = await child.status;
+    for (let r = await reader.read(); !r.done; r = await reader.read()) {
+      output += r.value;
+    }
+    assertEquals(status.code, 3);
+    assertStringIncludes(output, "got SIGTERM");
+  },
+);
+
+Deno.test(
+  { permissions: { run: true, read: true } },
+  async function commandSharedMemory() {
+    const memory = Deno.createSharedMemory(16);
+    const bytes = new Int32Array(memory.buffer);
+    bytes[0] = 41;
+    const child = new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        "--unstable-process",
+        `using memory = Deno.openSharedMemory(Deno.args[0]);
+        const bytes = new Int32Array(memory.buffer);
+        Atomics.add(bytes, 0, 1);`,
+        memory.name,
+      ],
+      sharedMemory: [memory],
+    }).spawn();
+    // the inherited segment can still be opened by the child
+    memory.close();
+    const status = await child.status;
+    assertEquals(status.code, 0);
+    assertEquals(Atomics.load(bytes, 0), 42);
+  },
+);
+
+Deno.test(function sharedMemoryOpen() {
+  using memory = Deno.createSharedMemory(100);
+  assertEquals(memory.buffer.byteLength, 100);
+  assert(memory.buffer instanceof SharedArrayBuffer);
+  using opened = Deno.openSharedMemory(memory.name);
+  assertEquals(opened.name, memory.name);
+  assertEquals(opened.buffer.byteLength, 100);
+  new Uint8Array(memory.buffer)[99] = 7;
+  assertEquals(new Uint8Array(opened.buffer)[99], 7);
+});
+
+Deno.test(function sharedMemoryInvalid() {
+  assertThrows(() => Deno.createSharedMemory(0), RangeError);
+  assertThrows(() => Deno.createSharedMemory(1.5), RangeError);
+  assertThrows(
+    () => Deno.openSharedMemory("/etc/passwd"),
+    TypeError,
+    "Invalid shared memory segment name",
+  );
+  const memory = Deno.createSharedMemory(1);
+  const name = memory.name;
+  memory.close();
+  // the name is removed once the segment is closed by its creator, while
+  // Windows keeps it until the buffer was collected
+  if (Deno.build.os !== "windows") {
+    assertThrows(() => Deno.openSharedMemory(name), Deno.errors.NotFound);
+  }
+  assertThrows(
+    () =>
+      new Deno.Command(Deno.execPath(), {
+        // @ts-expect-error: only segments can be inherited
+        sharedMemory: [{ name }],
+      }).spawn(),
+    TypeError,
+    "sharedMemory must only contain Deno.SharedMemory",
+  );
+});
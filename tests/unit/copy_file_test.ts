@@ -1,5 +1,10 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
-import { assertEquals, assertRejects, assertThrows } from "./test_util.ts";
+import {
+  assert,
+  assertEquals,
+  assertRejects,
+  assertThrows,
+} from "./test_util.ts";
 
 function readFileString(filename: string | URL): string {
   const dataRead = Deno.readFileSync(filename);
@@ -247,3 +252,43 @@ Deno.test(
     }, TypeError);
   },
 );
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function copyFileReflinkNever() {
+    const tempDir = Deno.makeTempDirSync();
+    const fromFilename = tempDir + "/from.txt";
+    const toFilename = tempDir + "/to.txt";
+    writeFileString(fromFilename, "Hello world!".repeat(128 * 1024));
+    writeFileString(toFilename, "Goodbye!");
+    await Deno.copyFile(fromFilename, toFilename, { reflink: "never" });
+    assertSameContent(fromFilename, toFilename);
+    if (Deno.build.os !== "windows") {
+      assertEquals(
+        Deno.statSync(toFilename).mode,
+        Deno.statSync(fromFilename).mode,
+      );
+    }
+
+    Deno.removeSync(tempDir, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function copyFileSyncReflinkAlways() {
+    const tempDir = Deno.makeTempDirSync();
+    const fromFilename = tempDir + "/from.txt";
+    const toFilename = tempDir + "/to.txt";
+    writeFileString(fromFilename, "Hello world!");
+    // whether cloning is supported depends on the file system of the tempdir
+    try {
+      Deno.copyFileSync(fromFilename, toFilename, { reflink: "always" });
+      assertSameContent(fromFilename, toFilename);
+    } catch (error) {
+      assert(error instanceof Deno.errors.NotSupported);
+    }
+
+    Deno.removeSync(tempDir, { recursive: true });
+  },
+);
@@ -77,7 +77,12 @@ Deno.test(function invalidScheduleTest() {
     "Invalid cron schedule",
   );
   assertThrows(
-    () => Deno.cron("abc", "* * * * * *", () => {}),
+    () => Deno.cron("abc", "* * * * * * *", () => {}),
+    TypeError,
+    "Invalid cron schedule",
+  );
+  assertThrows(
+    () => Deno.cron("abc", "60 * * * * *", () => {}),
     TypeError,
     "Invalid cron schedule",
   );
@@ -113,6 +118,29 @@ Deno.test(function invalidBackoffScheduleTest() {
   );
 });
 
+Deno.test(function invalidJitterTest() {
+  assertThrows(
+    () => Deno.cron("abc", "*/1 * * * *", { jitter: 3600001 }, () => {}),
+    TypeError,
+    "Invalid jitter",
+  );
+});
+
+Deno.test(function invalidOverlapTest() {
+  assertThrows(
+    () =>
+      Deno.cron(
+        "abc",
+        "*/1 * * * *",
+        // @ts-expect-error invalid overlap policy
+        { overlap: "bogus" },
+        () => {},
+      ),
+    TypeError,
+    "Invalid overlap policy",
+  );
+});
+
 Deno.test(async function tooManyCrons() {
   const crons: Promise<void>[] = [];
   const ac = new AbortController();
@@ -268,6 +296,72 @@ Deno.test(async function overlappingExecutions() {
   assertEquals(count, 1);
 });
 
+Deno.test(async function overlappingExecutionsQueue() {
+  Deno.env.set("DENO_CRON_TEST_SCHEDULE_OFFSET", "1000");
+
+  let count = 0;
+  const { promise: promise0, resolve: resolve0 } = Promise.withResolvers<
+    void
+  >();
+  const { promise: promise1, resolve: resolve1 } = Promise.withResolvers<
+    void
+  >();
+  const ac = new AbortController();
+  const c = Deno.cron(
+    "abc",
+    "*/20 * * * *",
+    { signal: ac.signal, overlap: "queue" },
+    async () => {
+      count++;
+      if (count === 1) {
+        resolve0();
+        await promise1;
+      }
+    },
+  );
+  try {
+    await promise0;
+    // The second execution is due while the first one is running.
+    await sleep(1500);
+    assertEquals(count, 1);
+    resolve1();
+    // It runs right away, instead of at the next scheduled time.
+    await sleep(200);
+    assertEquals(count, 2);
+  } finally {
+    resolve1();
+    ac.abort();
+    await c;
+  }
+});
+
+Deno.test(async function overlappingExecutionsKill() {
+  Deno.env.set("DENO_CRON_TEST_SCHEDULE_OFFSET", "100");
+
+  let count = 0;
+  const { promise, resolve } = Promise.withResolvers<void>();
+  const ac = new AbortController();
+  const c = Deno.cron(
+    "abc",
+    "*/20 * * * *",
+    { signal: ac.signal, overlap: "kill" },
+    () => {
+      count++;
+      if (count > 2) {
+        resolve();
+      }
+      // Never finishes, so each execution is killed by the next one.
+      return new Promise<void>(() => {});
+    },
+  );
+  try {
+    await promise;
+  } finally {
+    ac.abort();
+    await c;
+  }
+});
+
 Deno.test(async function retriesWithBackoffSchedule() {
   Deno.env.set("DENO_CRON_TEST_SCHEDULE_OFFSET", "5000");
 
@@ -457,4 +551,24 @@ Deno.test("Parse test", () => {
     }),
     "*/5 * * */2 *",
   );
+  assertEquals(
+    parseScheduleToString({
+      second: { every: 10 },
+    }),
+    "*/10 * * * * *",
+  );
+  assertEquals(
+    parseScheduleToString({
+      second: 30,
+      minute: 5,
+    }),
+    "30 5 * * * *",
+  );
+  assertEquals(
+    parseScheduleToString({
+      second: 15,
+      hour: { every: 2 },
+    }),
+    "15 0 */2 * * *",
+  );
 });
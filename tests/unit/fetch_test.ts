@@ -1176,6 +1176,118 @@ Deno.test(
   },
 );
 
+Deno.test(
+  { permissions: { net: true } },
+  async function fetchCustomClientConnections() {
+    const client = Deno.createHttpClient({ poolMaxConnectionsPerHost: 1 });
+    assertEquals(client.connections(), []);
+    const responses = await Promise.all([
+      fetch("http://localhost:4545/echo_server", { client }),
+      fetch("http://localhost:4545/echo_server", { client }),
+    ]);
+    await Promise.all(responses.map((response) => response.text()));
+
+    const connections = client.connections();
+    assertEquals(connections.length, 1);
+    assertEquals(connections[0].origin, "http://localhost:4545");
+    assertEquals(connections[0].protocol, "http/1.1");
+    assertEquals(connections[0].proxied, false);
+    assertEquals(connections[0].remoteAddr?.port, 4545);
+    client.close();
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function fetchProgressHooks() {
+    const data = new Uint8Array(256 * 1024).fill(1);
+    const uploads: Deno.FetchProgress[] = [];
+    const downloads: Deno.FetchProgress[] = [];
+    const { promise: downloaded, resolve } = Promise.withResolvers<void>();
+    const response = await fetch("http://localhost:4545/echo_server", {
+      method: "POST",
+      body: data,
+      onUploadProgress: (progress) => uploads.push(progress),
+      onDownloadProgress: (progress) => {
+        downloads.push(progress);
+        if (progress.loaded === data.length) resolve();
+      },
+    });
+    assertEquals((await response.arrayBuffer()).byteLength, data.length);
+    await downloaded;
+
+    assertEquals(uploads.at(-1), { loaded: data.length, total: data.length });
+    assertEquals(downloads.at(-1), {
+      loaded: data.length,
+      total: data.length,
+    });
+    for (let i = 1; i < downloads.length; i++) {
+      assert(downloads[i].loaded >= downloads[i - 1].loaded);
+    }
+  },
+);
+
+Deno.test(function fetchProgressHookMustBeFunction() {
+  assertThrows(
+    () =>
+      new Request("http://localhost:4545", {
+        // @ts-expect-error invalid hook
+        onUploadProgress: "nope",
+      }),
+    TypeError,
+    "`onUploadProgress` must be a function",
+  );
+});
+
+Deno.test(function createHttpClientRejectsZeroMaxConnections() {
+  assertThrows(
+    () => Deno.createHttpClient({ poolMaxConnectionsPerHost: 0 }),
+    TypeError,
+    "`poolMaxConnectionsPerHost` must be greater than 0",
+  );
+});
+
+Deno.test(
+  { permissions: { net: true } },
+  async function fetchRequestNoProxyBypassesProxy() {
+    // Nothing listens on port 1, so the request only succeeds if it is sent
+    // to the server directly.
+    const response = await fetch("http://localhost:4545/echo_server", {
+      method: "POST",
+      body: "hello",
+      proxy: { url: "http://localhost:1" },
+      noProxy: ["example.com", "localhost"],
+    });
+    assertEquals(await response.text(), "hello");
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function fetchRequestProxyUnreachable() {
+    await assertRejects(
+      () =>
+        fetch("http://localhost:4545/echo_server", {
+          proxy: { url: "http://localhost:1" },
+        }),
+      TypeError,
+    );
+  },
+);
+
+Deno.test(function fetchRequestProxyWithClientThrows() {
+  using client = Deno.createHttpClient({});
+  assertThrows(
+    () =>
+      new Request("http://localhost:4545", {
+        client,
+        proxy: { url: "http://localhost:1" },
+      }),
+    TypeError,
+    "`proxy` and `noProxy` can't be used together with `client`",
+  );
+});
+
 Deno.test(
   { permissions: { net: true } },
   async function fetchCustomClientUserAgent(): Promise<
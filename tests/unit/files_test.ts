@@ -795,6 +795,27 @@ Deno.test(
   },
 );
 
+Deno.test(
+  { permissions: { read: true } },
+  async function fsFileTryLock() {
+    // locks of different files for the same path conflict, even in the same
+    // process
+    const path = "tests/testdata/assets/lock_target.txt";
+    using first = Deno.openSync(path);
+    using second = Deno.openSync(path);
+
+    assert(first.tryLockSync(true));
+    assert(!second.tryLockSync(false));
+    assert(!(await second.tryLock(true)));
+    first.unlockSync();
+
+    assert(first.tryLockSync(false));
+    assert(await second.tryLock(false));
+    first.unlockSync();
+    second.unlockSync();
+  },
+);
+
 async function runFlockTests(opts: { sync: boolean }) {
   assertEquals(
     await checkFirstBlocksSecond({
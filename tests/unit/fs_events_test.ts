@@ -90,6 +90,60 @@ Deno.test(
   },
 );
 
+Deno.test(
+  {
+    ignore: Deno.build.os !== "linux" && Deno.build.os !== "windows",
+    permissions: { read: true, write: true },
+  },
+  async function watchFsCorrelateRenames() {
+    const testDir = await makeTempDir();
+    const file = testDir + "/file.txt";
+    await Deno.writeTextFile(file, "hello");
+    const watcher = Deno.watchFs(testDir, { correlateRenames: true });
+    async function waitForRename() {
+      for await (const event of watcher) {
+        if (event.kind === "rename") {
+          return event;
+        }
+      }
+    }
+    const eventPromise = waitForRename();
+    await Deno.rename(file, testDir + "/file2.txt");
+    const event = await eventPromise;
+    watcher.close();
+    assertEquals(event!.paths.length, 2);
+    assert(event!.paths[0].endsWith("file.txt"));
+    assert(event!.paths[1].endsWith("file2.txt"));
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function watchFsDebounce() {
+    const testDir = await makeTempDir();
+    const file = testDir + "/file.txt";
+    await Deno.writeTextFile(file, "");
+    await delay(100);
+    const watcher = Deno.watchFs(testDir, { debounce: 500 });
+    for (let i = 0; i < 10; i++) {
+      await Deno.writeTextFile(file, `${i}`);
+    }
+    const events: Deno.FsEvent[] = [];
+    setTimeout(() => watcher.close(), 1000);
+    for await (const event of watcher) {
+      events.push(event);
+    }
+    const unique = new Set(events.map((e) => JSON.stringify(e)));
+    assert(events.length > 0);
+    assertEquals(unique.size, events.length);
+  },
+);
+
+Deno.test({ permissions: { read: true } }, function watchFsInvalidDebounce() {
+  assertThrows(() => Deno.watchFs(".", { debounce: -1 }), RangeError);
+  assertThrows(() => Deno.watchFs(".", { debounce: NaN }), TypeError);
+});
+
 // TODO(kt3k): This test is for the backward compatibility of `.return` method.
 // This should be removed at 2.0
 Deno.test(
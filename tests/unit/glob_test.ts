@@ -0,0 +1,109 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assertEquals, assertThrows, makeTempTree } from "./test_util.ts";
+
+function makeTree(): string {
+  return makeTempTree({
+    "mod.ts": "",
+    "types.d.ts": "",
+    "src/a.ts": "",
+    "src/b.js": "",
+    "src/sub/c.ts": "",
+    "src/generated.ts": "",
+    "src/.gitignore": "generated.ts\n",
+    "node_modules/pkg/index.ts": "",
+  });
+}
+
+function normalize(paths: Iterable<string>): string[] {
+  return [...paths].map((path) => path.replaceAll("\\", "/")).sort();
+}
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function globSyncPatterns() {
+    const root = makeTree();
+    assertEquals(
+      normalize(Deno.globSync(["*.ts", "!*.d.ts"], { root })),
+      [
+        "mod.ts",
+        "node_modules/pkg/index.ts",
+        "src/a.ts",
+        "src/generated.ts",
+        "src/sub/c.ts",
+      ],
+    );
+    // a pattern with a slash is anchored at the root
+    assertEquals(normalize(Deno.globSync("src/*.ts", { root })), [
+      "src/a.ts",
+      "src/generated.ts",
+    ]);
+    Deno.removeSync(root, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function globExcludeAndGitignore() {
+    const root = makeTree();
+    const paths = [];
+    for await (
+      const path of Deno.glob("**/*.ts", {
+        root,
+        exclude: ["node_modules", "*.d.ts"],
+        gitignore: true,
+      })
+    ) {
+      paths.push(path);
+    }
+    assertEquals(normalize(paths), ["mod.ts", "src/a.ts", "src/sub/c.ts"]);
+    Deno.removeSync(root, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function globSyncIncludeDirs() {
+    const root = makeTree();
+    assertEquals(
+      normalize(Deno.globSync("sub", { root, includeDirs: true })),
+      ["src/sub"],
+    );
+    assertEquals(normalize(Deno.globSync("sub", { root })), []);
+    Deno.removeSync(root, { recursive: true });
+  },
+);
+
+Deno.test(
+  {
+    permissions: { read: true, write: true },
+    ignore: Deno.build.os === "windows",
+  },
+  function globSyncFollowSymlinks() {
+    const root = makeTree();
+    // a cycle, which is only walked once
+    Deno.symlinkSync(`${root}/src`, `${root}/src/sub/loop`);
+    assertEquals(normalize(Deno.globSync("c.ts", { root })), ["src/sub/c.ts"]);
+    assertEquals(
+      normalize(Deno.globSync("c.ts", { root, followSymlinks: true })),
+      ["src/sub/c.ts"],
+    );
+    assertEquals(normalize(Deno.globSync("loop", { root })), [
+      "src/sub/loop",
+    ]);
+    Deno.removeSync(root, { recursive: true });
+  },
+);
+
+Deno.test({ permissions: { read: true } }, function globSyncInvalidPattern() {
+  assertThrows(
+    () => [...Deno.globSync("[", { root: "tests/testdata" })],
+    TypeError,
+    "Invalid glob pattern",
+  );
+});
+
+Deno.test({ permissions: { read: false } }, function globSyncPerm() {
+  assertThrows(() => {
+    [...Deno.globSync("*.ts")];
+  }, Deno.errors.PermissionDenied);
+});
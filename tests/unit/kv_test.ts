@@ -1669,6 +1669,83 @@ queueTest("queue retries with backoffSchedule", async (db) => {
   assertEquals(2, count);
 });
 
+queueTest("queue priority", async (db) => {
+  const dequeued: unknown[] = [];
+  const { promise, resolve } = Promise.withResolvers<void>();
+  await db.enqueue("low");
+  await db.enqueue("high", { priority: 9 });
+  await db.enqueue("medium", { priority: 5 });
+  const listener = db.listenQueue((msg) => {
+    dequeued.push(msg);
+    if (dequeued.length === 3) {
+      resolve();
+    }
+  });
+  try {
+    await promise;
+    assertEquals(dequeued, ["high", "medium", "low"]);
+  } finally {
+    db.close();
+    await listener;
+  }
+});
+
+queueTest("queue visibility timeout", async (db) => {
+  let count = 0;
+  const { promise: promise0, resolve: resolve0 } = Promise.withResolvers<
+    void
+  >();
+  const { promise: promise1, resolve: resolve1 } = Promise.withResolvers<
+    void
+  >();
+  const listener = db.listenQueue(async (_msg) => {
+    count += 1;
+    if (count === 1) {
+      // Finishes after the visibility timeout, so the delivery is retried.
+      await sleep(500);
+      resolve0();
+    } else {
+      resolve1();
+    }
+  });
+  try {
+    await db.enqueue("test", { visibilityTimeout: 100, backoffSchedule: [1] });
+    await promise0;
+    await promise1;
+  } finally {
+    db.close();
+    await listener;
+  }
+  assertEquals(2, count);
+});
+
+queueTest("queue dead letter", async (db) => {
+  let count = 0;
+  const listener = db.listenQueue((_msg) => {
+    count += 1;
+    throw new TypeError("dequeue error");
+  });
+  try {
+    await db.enqueue("test", {
+      backoffSchedule: [1],
+      maxDeliveries: 3,
+      deadLetterPrefix: ["dead"],
+      keysIfUndelivered: [["undelivered"]],
+    });
+    await sleep(2000);
+    const entries = await collect(db.list({ prefix: ["dead"] }));
+    assertEquals(entries.length, 1);
+    assertEquals(entries[0].value, "test");
+    assertEquals((await db.get(["undelivered"])).value, "test");
+  } finally {
+    db.close();
+    await listener;
+  }
+
+  // There should have been 3 deliveries, the last delay being repeated
+  assertEquals(3, count);
+});
+
 queueTest("multiple listenQueues", async (db) => {
   const numListens = 10;
   let count = 0;
@@ -1962,6 +2039,37 @@ dbTest("invalid backoffSchedule", async (db) => {
   );
 });
 
+dbTest("invalid enqueue delivery options", async (db) => {
+  await assertRejects(
+    async () => {
+      await db.enqueue("foo", { priority: 10 });
+    },
+    TypeError,
+    "priority must be an integer between 0 and 9",
+  );
+  await assertRejects(
+    async () => {
+      await db.enqueue("foo", { priority: 1, delay: 1000 });
+    },
+    TypeError,
+    "priority cannot be combined with delay",
+  );
+  await assertRejects(
+    async () => {
+      await db.enqueue("foo", { visibilityTimeout: 0 });
+    },
+    TypeError,
+    "visibilityTimeout must be an integer between 1 and 3600000",
+  );
+  assertThrows(
+    () => {
+      db.atomic().enqueue("foo", { maxDeliveries: 101 });
+    },
+    TypeError,
+    "maxDeliveries must be an integer between 1 and 100",
+  );
+});
+
 dbTest("atomic operation is exposed", (db) => {
   assert(Deno.AtomicOperation);
   const ao = db.atomic();
@@ -2268,6 +2376,208 @@ dbTest("key watch", async (db) => {
   await reader.cancel();
 });
 
+dbTest("prefix watch", async (db) => {
+  await db.set(["a", 1], "x");
+  const reader = db.watch<string>({ prefix: ["a"] }).getReader();
+
+  const first = await reader.read();
+  assert(!first.done);
+  assertEquals(first.value.map((entry) => entry.key), [["a", 1]]);
+
+  const { versionstamp } = await db.set(["a", 2], "y");
+  const second = await reader.read();
+  assert(!second.done);
+  assertEquals(second.value.length, 2);
+  assertEquals(second.value[1], { key: ["a", 2], value: "y", versionstamp });
+
+  await db.delete(["a", 1]);
+  const third = await reader.read();
+  assert(!third.done);
+  assertEquals(third.value.map((entry) => entry.key), [["a", 2]]);
+
+  await reader.cancel();
+});
+
+dbTest("range watch ignores writes outside the range", async (db) => {
+  const reader = db.watch({ start: ["b"], end: ["d"] }).getReader();
+
+  const first = await reader.read();
+  assert(!first.done);
+  assertEquals(first.value, []);
+
+  await db.set(["a"], 1);
+  await db.set(["d"], 1);
+  await db.set(["c"], 1);
+  const second = await reader.read();
+  assert(!second.done);
+  assertEquals(second.value.map((entry) => entry.key), [["c"]]);
+
+  await reader.cancel();
+});
+
+dbTest("range watch with limit", async (db) => {
+  await db.set(["a", 1], 1);
+  await db.set(["a", 2], 2);
+  const reader = db.watch({ prefix: ["a"] }, { limit: 1 }).getReader();
+
+  const first = await reader.read();
+  assert(!first.done);
+  assertEquals(first.value.map((entry) => entry.key), [["a", 1]]);
+
+  await db.set(["a", 0], 0);
+  const second = await reader.read();
+  assert(!second.done);
+  assertEquals(second.value.map((entry) => entry.key), [["a", 0]]);
+
+  await reader.cancel();
+
+  assertThrows(
+    () => db.watch({ prefix: ["a"] }, { limit: 0 }),
+    Error,
+    "limit must be positive",
+  );
+  assertThrows(
+    () => db.watch({ prefix: ["a"] }, { limit: 1001 }),
+    TypeError,
+    "too many entries (max 1000)",
+  );
+});
+
+dbTest("raw range watch", async (db) => {
+  await db.set(["a"], 1);
+  const reader = db.watch({ prefix: [] }, { raw: true }).getReader();
+
+  const first = await reader.read();
+  assert(!first.done);
+  assertEquals(first.value.length, 1);
+
+  // Deleting a missing key doesn't change the range, but touches it.
+  await db.delete(["b"]);
+  const second = await reader.read();
+  assert(!second.done);
+  assertEquals(second.value.length, 1);
+
+  await reader.cancel();
+});
+
+dbTest("export and import", async (db) => {
+  await db.set(["a"], "x".repeat(2000));
+  await db.set(["b", 1n], new Uint8Array([1, 2, 3]));
+  await db.set(["c"], new Deno.KvU64(7n));
+  for (let i = 0; i < 100; i++) {
+    await db.set(["d", i], "y".repeat(2000));
+  }
+  const entries = await Array.fromAsync(db.list({ prefix: [] }));
+
+  const chunks = await Array.fromAsync(db.export());
+  assert(chunks.length > 1);
+  // Writes while the export is read are not part of it.
+  const reader = db.export().getReader();
+  const exported = [(await reader.read()).value];
+  await db.set(["e"], 1);
+  while (true) {
+    const { value, done } = await reader.read();
+    if (done) break;
+    exported.push(value);
+  }
+  assertEquals(exported, chunks);
+
+  using db2 = await Deno.openKv(":memory:");
+  const count = await db2.import(ReadableStream.from(chunks));
+  assertEquals(count, entries.length);
+  const imported = await Array.fromAsync(db2.list({ prefix: [] }));
+  assertEquals(
+    imported.map(({ key, value }) => ({ key, value })),
+    entries.map(({ key, value }) => ({ key, value })),
+  );
+
+  // Importing again overwrites the entries.
+  const bytes = new Uint8Array(await new Blob(chunks).arrayBuffer());
+  assertEquals(await db2.import(bytes), entries.length);
+  const reimported = await Array.fromAsync(db2.list({ prefix: [] }));
+  assertEquals(reimported.length, entries.length);
+});
+
+dbTest("import invalid backup", async (db) => {
+  await assertRejects(
+    () => db.import(new Uint8Array([1, 2, 3, 4, 5, 6, 7, 8])),
+    TypeError,
+    "Invalid Deno KV backup",
+  );
+
+  await db.set(["a"], 1);
+  const bytes = new Uint8Array(await new Response(db.export()).arrayBuffer());
+  using db2 = await Deno.openKv(":memory:");
+  await assertRejects(
+    () => db2.import(bytes.subarray(0, bytes.length - 1)),
+    TypeError,
+    "Invalid Deno KV backup",
+  );
+  assertEquals(await db2.import(bytes.subarray(0, 8)), 0);
+});
+
+dbTest("secondary indexes", async (db) => {
+  const alice = { name: "Alice", email: "alice@example.com" };
+  const res = await db.atomic()
+    .set(["users", 1], alice, { indexes: [["users_by_email", alice.email]] })
+    .commit();
+  assert(res.ok);
+  assertEquals((await db.get(["users_by_email", alice.email])).value, alice);
+
+  // Changing the index keys deletes the ones that are no longer declared.
+  const bob = { name: "Bob", email: "bob@example.com" };
+  await db.set(["users", 1], bob, {
+    indexes: [["users_by_email", bob.email], ["users_by_name", bob.name]],
+  });
+  assertEquals((await db.get(["users_by_email", alice.email])).value, null);
+  assertEquals((await db.get(["users_by_email", bob.email])).value, bob);
+  assertEquals((await db.get(["users_by_name", bob.name])).value, bob);
+
+  // Index records are not visible to list.
+  const keys = (await Array.fromAsync(db.list({ prefix: [] })))
+    .map((entry) => entry.key);
+  assertEquals(keys, [
+    ["users", 1],
+    ["users_by_email", bob.email],
+    ["users_by_name", bob.name],
+  ]);
+
+  await db.atomic().delete(["users", 1], { indexes: true }).commit();
+  assertEquals(await Array.fromAsync(db.list({ prefix: [] })), []);
+});
+
+dbTest("secondary indexes with checks", async (db) => {
+  const { versionstamp } = await db.set(["a"], 1, { indexes: [["b", 1]] });
+
+  const failed = await db.atomic()
+    .check({ key: ["a"], versionstamp: null })
+    .set(["a"], 2, { indexes: [["b", 2]] })
+    .commit();
+  assert(!failed.ok);
+  assertEquals((await db.get(["b", 1])).value, 1);
+  assertEquals((await db.get(["b", 2])).value, null);
+
+  const res = await db.atomic()
+    .check({ key: ["a"], versionstamp })
+    .mutate({ type: "set", key: ["a"], value: 2, indexes: [["b", 2]] })
+    .commit();
+  assert(res.ok);
+  assertEquals((await db.get(["b", 1])).value, null);
+  assertEquals((await db.get(["b", 2])).value, 2);
+
+  await db.delete(["a"], { indexes: true });
+  assertEquals((await db.get(["b", 2])).value, null);
+});
+
+dbTest("secondary indexes are exported", async (db) => {
+  await db.set(["a"], 1, { indexes: [["b"]] });
+  using db2 = await Deno.openKv(":memory:");
+  await db2.import(db.export());
+  await db2.set(["a"], 2, { indexes: [["c"]] });
+  assertEquals((await db2.get(["b"])).value, null);
+  assertEquals((await db2.get(["c"])).value, 2);
+});
+
 dbTest("set with key versionstamp suffix", async (db) => {
   const result1 = await Array.fromAsync(db.list({ prefix: ["a"] }));
   assertEquals(result1, []);
@@ -177,3 +177,48 @@ Deno.test(
     }
   },
 );
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function makeTempFileSyncUnnamed() {
+    const dir = Deno.makeTempDirSync();
+    const file = Deno.makeTempFileSync({ dir, unnamed: true });
+    assert(file instanceof Deno.FsFile);
+    file.writeSync(new TextEncoder().encode("hello"));
+    file.seekSync(0, Deno.SeekMode.Start);
+    const buf = new Uint8Array(5);
+    assertEquals(file.readSync(buf), 5);
+    assertEquals(new TextDecoder().decode(buf), "hello");
+    if (Deno.build.os !== "windows") {
+      assertEquals([...Deno.readDirSync(dir)], []);
+    }
+    file.close();
+    assertEquals([...Deno.readDirSync(dir)], []);
+    Deno.removeSync(dir);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function makeTempFileUnnamed() {
+    const dir = await Deno.makeTempDir();
+    const file = await Deno.makeTempFile({ dir, unnamed: true });
+    await file.write(new TextEncoder().encode("hello"));
+    assertEquals((await file.stat()).size, 5);
+    file.close();
+    assertEquals([...Deno.readDirSync(dir)], []);
+    await Deno.remove(dir);
+  },
+);
+
+Deno.test(
+  { permissions: { write: false } },
+  async function makeTempFileUnnamedPerm() {
+    assertThrows(() => {
+      Deno.makeTempFileSync({ unnamed: true });
+    }, Deno.errors.PermissionDenied);
+    await assertRejects(async () => {
+      await Deno.makeTempFile({ unnamed: true });
+    }, Deno.errors.PermissionDenied);
+  },
+);
@@ -33,6 +33,61 @@ Deno.test({ permissions: { env: true } }, function hasEnv() {
   assert(!Deno.env.has("TEST_VAR"));
 });
 
+Deno.test({ permissions: { env: true } }, function envDiff() {
+  Deno.env.set("TEST_VAR_CHANGED", "A");
+  Deno.env.set("TEST_VAR_REMOVED", "A");
+  const snapshot = Deno.env.toObject();
+  Deno.env.set("TEST_VAR_ADDED", "B");
+  Deno.env.set("TEST_VAR_CHANGED", "B");
+  Deno.env.delete("TEST_VAR_REMOVED");
+  try {
+    assertEquals(Deno.env.diff(snapshot), {
+      added: { TEST_VAR_ADDED: "B" },
+      changed: { TEST_VAR_CHANGED: "B" },
+      removed: ["TEST_VAR_REMOVED"],
+    });
+    assertEquals(Deno.env.diff(Deno.env.toObject()), {
+      added: {},
+      changed: {},
+      removed: [],
+    });
+  } finally {
+    Deno.env.delete("TEST_VAR_ADDED");
+    Deno.env.delete("TEST_VAR_CHANGED");
+  }
+});
+
+Deno.test({ permissions: { env: true } }, function envChangeEvent() {
+  Deno.env.delete("TEST_VAR");
+  const changes: [string, string | undefined, string | undefined][] = [];
+  const listener = (e: Deno.EnvChangeEvent) => {
+    changes.push([e.key, e.oldValue, e.newValue]);
+  };
+  Deno.env.addEventListener("change", listener);
+  try {
+    Deno.env.set("TEST_VAR", "A");
+    Deno.env.set("TEST_VAR", "A");
+    Deno.env.set("TEST_VAR", "B");
+    Deno.env.delete("TEST_VAR");
+    Deno.env.delete("TEST_VAR");
+  } finally {
+    Deno.env.removeEventListener("change", listener);
+  }
+  Deno.env.set("TEST_VAR", "C");
+  Deno.env.delete("TEST_VAR");
+  assertEquals(changes, [
+    ["TEST_VAR", undefined, "A"],
+    ["TEST_VAR", "A", "B"],
+    ["TEST_VAR", "B", undefined],
+  ]);
+});
+
+Deno.test({ permissions: { env: false } }, function envDiffPermissionDenied() {
+  assertThrows(() => {
+    Deno.env.diff({});
+  }, Deno.errors.PermissionDenied);
+});
+
 Deno.test({ permissions: { env: true } }, function avoidEmptyNamedEnv() {
   assertThrows(() => Deno.env.set("", "v"), TypeError);
   assertThrows(() => Deno.env.set("a=a", "v"), TypeError);
@@ -60,6 +115,22 @@ Deno.test({ permissions: { env: false } }, function envPermissionDenied2() {
   }, Deno.errors.PermissionDenied);
 });
 
+Deno.test(
+  { permissions: { env: ["DENO_TEST_PATTERN_*"] } },
+  function envPattern() {
+    Deno.env.set("DENO_TEST_PATTERN_A", "A");
+    try {
+      assertEquals(Deno.env.get("DENO_TEST_PATTERN_A"), "A");
+      assertEquals(Deno.env.toObject(), { DENO_TEST_PATTERN_A: "A" });
+      assertThrows(() => {
+        Deno.env.get("PATH");
+      }, Deno.errors.PermissionDenied);
+    } finally {
+      Deno.env.delete("DENO_TEST_PATTERN_A");
+    }
+  },
+);
+
 // This test verifies that on Windows, environment variables are
 // case-insensitive. Case normalization needs be done using the collation
 // that Windows uses, rather than naively using String.toLowerCase().
@@ -295,6 +366,53 @@ Deno.test(
   },
 );
 
+Deno.test(
+  { permissions: { sys: ["systemMemoryInfo"] } },
+  function systemMemoryInfoInBytes() {
+    const info = Deno.systemMemoryInfo();
+    assert(info.available <= info.total);
+    assert(info.free <= info.total);
+    assert(info.swapFree <= info.swapTotal);
+  },
+);
+
+Deno.test({ permissions: { sys: ["cpus"] } }, function systemCpuUsage() {
+  const usage = Deno.systemCpuUsage();
+  assert(usage.length > 0);
+  for (const core of [...usage, ...Deno.systemCpuUsage()]) {
+    assert(core >= 0 && core <= 1);
+  }
+});
+
+Deno.test({ permissions: { sys: false } }, function systemCpuUsagePerm() {
+  assertThrows(() => {
+    Deno.systemCpuUsage();
+  }, Deno.errors.PermissionDenied);
+});
+
+Deno.test(
+  { permissions: { read: true, sys: ["statfs"] } },
+  function diskUsage() {
+    const usage = Deno.diskUsage(".");
+    assert(usage.total > 0);
+    assert(usage.free <= usage.total);
+    assert(usage.available <= usage.free);
+    assert(Deno.diskUsage(new URL(".", import.meta.url)).total > 0);
+    assertThrows(() => {
+      Deno.diskUsage("/does/not/exist");
+    }, Deno.errors.NotFound);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, sys: false } },
+  function diskUsagePerm() {
+    assertThrows(() => {
+      Deno.diskUsage(".");
+    }, Deno.errors.PermissionDenied);
+  },
+);
+
 Deno.test({ permissions: { sys: ["uid"] } }, function getUid() {
   if (Deno.build.os === "windows") {
     assertEquals(Deno.uid(), null);
@@ -324,6 +442,22 @@ Deno.test(function memoryUsage() {
   assert(mem.rss >= mem.heapTotal);
 });
 
+Deno.test({ permissions: { read: true } }, function processMetrics() {
+  const before = Deno.processMetrics();
+  assert(before.rss > 0);
+  assert(before.maxRss >= before.rss);
+  assert(before.userCpuTime > 0);
+  assert(before.systemCpuTime >= 0);
+
+  using _file = Deno.openSync("tests/testdata/assets/hello.txt");
+  const after = Deno.processMetrics();
+  assertEquals(after.resources, before.resources + 1);
+  if (before.openFds !== null) {
+    assert(after.openFds! > before.openFds);
+  }
+  assert(after.userCpuTime >= before.userCpuTime);
+});
+
 Deno.test("Deno.exitCode getter and setter", () => {
   // Initial value is 0
   assertEquals(Deno.exitCode, 0);
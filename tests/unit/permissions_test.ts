@@ -202,3 +202,33 @@ Deno.test(function permissionStatusObjectsNotEqualSync() {
       Deno.permissions.querySync({ name: "env", variable: "B" }),
   );
 });
+
+Deno.test(
+  { permissions: { run: ["ls"] } },
+  function permissionQueryRunInvocation() {
+    assertEquals(
+      Deno.permissions.querySync({ name: "run", command: "cat", args: [] })
+        .state,
+      "prompt",
+    );
+    // LD_* env vars require all run permissions
+    assertEquals(
+      Deno.permissions.querySync({
+        name: "run",
+        command: "ls",
+        args: ["-l"],
+        env: { LD_PRELOAD: "./libfoo.so" },
+      }).state,
+      "prompt",
+    );
+    assertEquals(
+      Deno.permissions.querySync({
+        name: "run",
+        command: "ls",
+        env: { LD_PRELOAD: "" },
+      }).state,
+      Deno.permissions.querySync({ name: "run", command: "ls", args: [] })
+        .state,
+    );
+  },
+);
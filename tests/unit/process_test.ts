@@ -522,12 +522,25 @@ Deno.test({ permissions: { run: false } }, function killPermissions() {
   }, Deno.errors.PermissionDenied);
 });
 
+Deno.test({ permissions: { run: true } }, function minusOnePidInvalid() {
+  assertThrows(() => {
+    Deno.kill(-1, "SIGTERM");
+  }, TypeError);
+});
+
 Deno.test(
-  { ignore: Deno.build.os !== "windows", permissions: { run: true } },
-  function negativePidInvalidWindows() {
-    assertThrows(() => {
-      Deno.kill(-1, "SIGTERM");
-    }, TypeError);
+  { permissions: { run: true, read: true } },
+  async function killNegativePid() {
+    const child = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "setTimeout(() => {}, 10000)"],
+      processGroup: true,
+    }).spawn();
+    Deno.kill(-child.pid, "SIGKILL");
+    const status = await child.status;
+    assertEquals(status.success, false);
+    if (Deno.build.os !== "windows") {
+      assertEquals(status.signal, "SIGKILL");
+    }
   },
 );
 
@@ -0,0 +1,177 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assert, assertEquals, assertRejects } from "./test_util.ts";
+
+const cert = Deno.readTextFileSync("tests/testdata/tls/localhost.crt");
+const key = Deno.readTextFileSync("tests/testdata/tls/localhost.key");
+const caCerts = [Deno.readTextFileSync("tests/testdata/tls/RootCA.pem")];
+
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+function listen(): { listener: Deno.QuicListener; port: number } {
+  const listener = Deno.listenQuic({
+    hostname: "localhost",
+    port: 0,
+    cert,
+    key,
+    alpnProtocols: ["deno-test"],
+  });
+  return { listener, port: listener.addr.port };
+}
+
+async function connectPair(): Promise<
+  [Deno.QuicListener, Deno.QuicConn, Deno.QuicConn]
+> {
+  const { listener, port } = listen();
+  const [client, server] = await Promise.all([
+    Deno.connectQuic({
+      hostname: "localhost",
+      port,
+      caCerts,
+      alpnProtocols: ["deno-test"],
+    }),
+    listener.accept(),
+  ]);
+  return [listener, client, server];
+}
+
+async function readAll(readable: ReadableStream<Uint8Array>): Promise<string> {
+  let text = "";
+  for await (const chunk of readable) {
+    text += decoder.decode(chunk, { stream: true });
+  }
+  return text;
+}
+
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function quicConnectAndAccept() {
+    const [listener, client, server] = await connectPair();
+    assertEquals(listener.addr.transport, "udp");
+    assertEquals(client.protocol, "deno-test");
+    assertEquals(server.protocol, "deno-test");
+    assertEquals(client.remoteAddr.port, listener.addr.port);
+    assertEquals(server.remoteAddr.port, client.localAddr.port);
+
+    client.close({ closeCode: 42, reason: "bye" });
+    assertEquals(await server.closed, { closeCode: 42, reason: "bye" });
+    assertEquals(await client.closed, { closeCode: 42, reason: "bye" });
+    server.close();
+    listener.close();
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function quicBidirectionalStream() {
+    const [listener, client, server] = await connectPair();
+
+    const stream = await client.createBidirectionalStream();
+    const writer = stream.writable.getWriter();
+    await writer.write(encoder.encode("ping"));
+    await writer.close();
+
+    const incoming = server.incomingBidirectionalStreams.getReader();
+    const { value: serverStream } = await incoming.read();
+    assert(serverStream);
+    assertEquals(await readAll(serverStream.readable), "ping");
+    const serverWriter = serverStream.writable.getWriter();
+    await serverWriter.write(encoder.encode("pong"));
+    await serverWriter.close();
+    assertEquals(await readAll(stream.readable), "pong");
+
+    client.close();
+    assertEquals(await incoming.read(), { value: undefined, done: true });
+    server.close();
+    listener.close();
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function quicUnidirectionalStream() {
+    const [listener, client, server] = await connectPair();
+
+    const writable = await client.createUnidirectionalStream();
+    await ReadableStream.from([encoder.encode("hello")]).pipeTo(writable);
+
+    const incoming = server.incomingUnidirectionalStreams.getReader();
+    const { value: readable } = await incoming.read();
+    assert(readable);
+    assertEquals(await readAll(readable), "hello");
+
+    incoming.releaseLock();
+    client.close();
+    server.close();
+    listener.close();
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function quicDatagrams() {
+    const [listener, client, server] = await connectPair();
+    assert(client.maxDatagramSize > 0);
+
+    client.sendDatagram(encoder.encode("datagram"));
+    const data = await server.readDatagram();
+    assert(data);
+    assertEquals(decoder.decode(data), "datagram");
+
+    server.close();
+    assertEquals(await client.readDatagram(), null);
+    client.close();
+    listener.close();
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function quicListenerAsyncIterator() {
+    const { listener, port } = listen();
+    const [client, server] = await Promise.all([
+      Deno.connectQuic({
+        hostname: "localhost",
+        port,
+        caCerts,
+        alpnProtocols: ["deno-test"],
+      }),
+      (async () => {
+        // returning closes the listener
+        for await (const conn of listener) {
+          return conn;
+        }
+      })(),
+    ]);
+    assert(server);
+    assertEquals(server.remoteAddr.port, client.localAddr.port);
+
+    client.close();
+    server.close();
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function quicConnectWithoutCaCerts() {
+    const { listener, port } = listen();
+    await assertRejects(() =>
+      Deno.connectQuic({
+        hostname: "localhost",
+        port,
+        alpnProtocols: ["deno-test"],
+      })
+    );
+    listener.close();
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, net: false } },
+  async function quicConnectPermission() {
+    await assertRejects(
+      () => Deno.connectQuic({ port: 4433, alpnProtocols: ["deno-test"] }),
+      Deno.errors.PermissionDenied,
+    );
+  },
+);
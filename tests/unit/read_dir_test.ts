@@ -111,3 +111,72 @@ Deno.test({ permissions: { read: true } }, async function readDirNotFound() {
     `readdir 'bad_dir_name'`,
   );
 });
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function readDirSyncWithStat() {
+    const dir = Deno.makeTempDirSync();
+    // more than fit in one batch
+    for (let i = 0; i < 300; ++i) {
+      Deno.writeTextFileSync(`${dir}/${i}.txt`, "x".repeat(i));
+    }
+    Deno.mkdirSync(`${dir}/subdir`);
+    const entries = [...Deno.readDirSync(dir, { stat: true })];
+    assertEquals(entries.length, 301);
+    for (const entry of entries) {
+      const info = Deno.lstatSync(`${dir}/${entry.name}`);
+      assertEquals(entry.isFile, info.isFile);
+      assertEquals(entry.isDirectory, info.isDirectory);
+      assertEquals(entry.info.isDirectory, info.isDirectory);
+      assertEquals(entry.info.size, info.size);
+      assertEquals(entry.info.mtime, info.mtime);
+      assertEquals(entry.info.ino, info.ino);
+    }
+    Deno.removeSync(dir, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function readDirWithStat() {
+    const dir = await Deno.makeTempDir();
+    await Deno.writeTextFile(`${dir}/file.txt`, "hello");
+    await Deno.symlink(`${dir}/file.txt`, `${dir}/link`);
+    const entries = new Map<string, Deno.DirEntryWithInfo>();
+    for await (const entry of Deno.readDir(dir, { stat: true })) {
+      entries.set(entry.name, entry);
+    }
+    assertEquals(entries.size, 2);
+    assert(entries.get("file.txt")!.isFile);
+    assertEquals(entries.get("file.txt")!.info.size, 5);
+    // the symlink itself isn't followed
+    assert(entries.get("link")!.isSymlink);
+    assert(entries.get("link")!.info.isSymlink);
+    await Deno.remove(dir, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true } },
+  async function readDirWithStatNotFound() {
+    assertThrows(
+      () => Deno.readDirSync("bad_dir_name", { stat: true }),
+      Deno.errors.NotFound,
+      `readdir 'bad_dir_name'`,
+    );
+    await assertRejects(
+      async () => {
+        await Deno.readDir("bad_dir_name", { stat: true })
+          [Symbol.asyncIterator]().next();
+      },
+      Deno.errors.NotFound,
+      `readdir 'bad_dir_name'`,
+    );
+  },
+);
+
+Deno.test({ permissions: { read: false } }, function readDirWithStatPerm() {
+  assertThrows(() => {
+    Deno.readDirSync("tests/", { stat: true });
+  }, Deno.errors.PermissionDenied);
+});
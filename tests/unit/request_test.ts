@@ -1,5 +1,9 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
-import { assertEquals, assertStringIncludes } from "./test_util.ts";
+import {
+  assertEquals,
+  assertStringIncludes,
+  assertThrows,
+} from "./test_util.ts";
 
 Deno.test(async function fromInit() {
   const req = new Request("http://foo/", {
@@ -75,3 +79,23 @@ Deno.test(function requestConstructorTakeURLObjectAsParameter() {
     "http://foo/",
   );
 });
+
+Deno.test(function requestDuplex() {
+  assertEquals(new Request("http://foo/").duplex, "half");
+  const request = new Request("http://foo/", {
+    method: "POST",
+    body: new ReadableStream(),
+    duplex: "full",
+  });
+  assertEquals(request.duplex, "full");
+  assertEquals(request.clone().duplex, "full");
+  assertEquals(new Request(request).duplex, "full");
+  assertThrows(
+    () =>
+      new Request("http://foo/", {
+        // @ts-expect-error invalid duplex mode
+        duplex: "both",
+      }),
+    TypeError,
+  );
+});
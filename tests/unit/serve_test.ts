@@ -333,6 +333,124 @@ Deno.test(
   },
 );
 
+Deno.test(
+  { permissions: { net: true } },
+  async function httpServerShutdownStats() {
+    const waitForRequest = Promise.withResolvers<void>();
+    const waitForResponse = Promise.withResolvers<void>();
+    const listeningDeferred = Promise.withResolvers<void>();
+    const server = Deno.serve({
+      port: servePort,
+      onListen: onListen(listeningDeferred.resolve),
+    }, async () => {
+      waitForRequest.resolve();
+      await waitForResponse.promise;
+      return new Response("ok");
+    });
+    await listeningDeferred.promise;
+    assertEquals(server.stats(), { connections: 0, requests: 0 });
+
+    // An idle connection, which has not sent anything
+    const idle = await Deno.connect({ port: servePort });
+    const f = fetch(`http://localhost:${servePort}`);
+    await waitForRequest.promise;
+    assertEquals(server.stats(), { connections: 2, requests: 1 });
+
+    const shutdown = server.shutdown();
+    // The idle connection is closed right away
+    assertEquals(await idle.read(new Uint8Array(1)), null);
+    idle.close();
+    assertEquals(server.stats(), { connections: 1, requests: 1 });
+
+    waitForResponse.resolve();
+    assertEquals(await (await f).text(), "ok");
+    await shutdown;
+    assertEquals(server.stats(), { connections: 0, requests: 0 });
+    await server.finished;
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function httpServerShutdownTimeout() {
+    const waitForRequest = Promise.withResolvers<void>();
+    const waitForResponse = Promise.withResolvers<void>();
+    const listeningDeferred = Promise.withResolvers<void>();
+    const server = Deno.serve({
+      port: servePort,
+      onListen: onListen(listeningDeferred.resolve),
+    }, async () => {
+      waitForRequest.resolve();
+      await waitForResponse.promise;
+      return new Response("too late");
+    });
+    await listeningDeferred.promise;
+    await assertRejects(
+      () => server.shutdown({ timeout: NaN }),
+      TypeError,
+      "The timeout must be a finite number.",
+    );
+
+    const f = fetch(`http://localhost:${servePort}`);
+    await waitForRequest.promise;
+    // The request does not finish in time, so its connection is closed
+    await server.shutdown({ timeout: 50 });
+    await assertRejects(() => f);
+    assertEquals(server.stats(), { connections: 0, requests: 0 });
+
+    waitForResponse.resolve();
+    await server.finished;
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function httpServerOnExistingListener() {
+    const listener = Deno.listen({ hostname: "127.0.0.1", port: servePort });
+    const listeningDeferred = Promise.withResolvers<Deno.NetAddr>();
+    const server = Deno.serve({
+      listener,
+      onListen: (addr) => listeningDeferred.resolve(addr as Deno.NetAddr),
+    }, () => new Response("adopted"));
+
+    const addr = await listeningDeferred.promise;
+    assertEquals(addr.port, servePort);
+    assertEquals(server.addr, listener.addr);
+    const resp = await fetch(`http://127.0.0.1:${servePort}/`);
+    assertEquals(await resp.text(), "adopted");
+
+    await server.shutdown();
+    // The server owns the listener, so the port is free again
+    Deno.listen({ hostname: "127.0.0.1", port: servePort }).close();
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  function httpServerInvalidFd() {
+    const handler = () => new Response();
+    assertThrows(
+      () => Deno.serve({ fd: -1 }, handler),
+      TypeError,
+      "Invalid file descriptor: -1",
+    );
+    assertThrows(
+      () => Deno.serve({ fd: 1.5 }, handler),
+      TypeError,
+      "Invalid file descriptor: 1.5",
+    );
+    const listener = Deno.listen({ port: servePort });
+    assertThrows(
+      () => Deno.serve({ fd: 0, listener }, handler),
+      TypeError,
+      "Only one of 'listener' and 'fd' may be provided.",
+    );
+    listener.close();
+    // Not a socket, let alone a listening one
+    assertThrows(() => Deno.serve({ fd: 100000 }, handler));
+  },
+);
+
 Deno.test(
   { permissions: { net: true, write: true, read: true } },
   async function httpServerExplicitResourceManagement() {
@@ -2853,6 +2971,85 @@ for (const testCase of compressionTestCases) {
   );
 }
 
+Deno.test(
+  { permissions: { net: true } },
+  async function httpServerCompressionZstdAndDictionaries() {
+    const listeningDeferred = Promise.withResolvers<void>();
+    const ac = new AbortController();
+    const dictionary = new TextEncoder().encode(
+      "The quick brown fox jumps over the lazy dog. ".repeat(10),
+    );
+    const hash = new Uint8Array(
+      await crypto.subtle.digest("SHA-256", dictionary),
+    );
+    const availableDictionary = `:${btoa(String.fromCharCode(...hash))}:`;
+    const text = "The quick brown fox jumps over the lazy cat. ".repeat(10);
+    const server = Deno.serve({
+      handler: () =>
+        new Response(text, { headers: { "content-type": "text/plain" } }),
+      port: servePort,
+      signal: ac.signal,
+      compressionDictionaries: [dictionary],
+      onListen: onListen(listeningDeferred.resolve),
+      onError: createOnErrorCb(ac),
+    });
+
+    async function request(headers: Record<string, string>) {
+      const resp = await fetch(`http://127.0.0.1:${servePort}/`, { headers });
+      const body = await resp.bytes();
+      return { encoding: resp.headers.get("content-encoding"), resp, body };
+    }
+
+    try {
+      await listeningDeferred.promise;
+
+      const zstd = await request({ "Accept-Encoding": "zstd" });
+      assertEquals(zstd.encoding, "zstd");
+      assertEquals(
+        zstd.body.subarray(0, 4),
+        new Uint8Array([0x28, 0xb5, 0x2f, 0xfd]),
+      );
+
+      const dcz = await request({
+        "Accept-Encoding": "gzip, deflate, br, zstd, dcb, dcz",
+        "Available-Dictionary": availableDictionary,
+      });
+      assertEquals(dcz.encoding, "dcz");
+      assertEquals(
+        dcz.resp.headers.get("vary"),
+        "Accept-Encoding, Available-Dictionary",
+      );
+      assertEquals(
+        dcz.body.subarray(0, 8),
+        new Uint8Array([0x5e, 0x2a, 0x4d, 0x18, 0x20, 0, 0, 0]),
+      );
+      assertEquals(dcz.body.subarray(8, 40), hash);
+      assert(dcz.body.byteLength < zstd.body.byteLength);
+
+      const dcb = await request({
+        "Accept-Encoding": "dcb",
+        "Available-Dictionary": availableDictionary,
+      });
+      assertEquals(dcb.encoding, "dcb");
+      assertEquals(
+        dcb.body.subarray(0, 4),
+        new Uint8Array([0xff, 0x44, 0x43, 0x42]),
+      );
+      assertEquals(dcb.body.subarray(4, 36), hash);
+
+      // Dictionaries the server doesn't hold are ignored
+      const unknown = await request({
+        "Accept-Encoding": "zstd, dcz",
+        "Available-Dictionary": `:${btoa("x".repeat(32))}:`,
+      });
+      assertEquals(unknown.encoding, "zstd");
+    } finally {
+      ac.abort();
+      await server.finished;
+    }
+  },
+);
+
 Deno.test(
   { permissions: { net: true, write: true, read: true } },
   async function httpServerPostFile() {
@@ -3116,6 +3313,81 @@ Deno.test(
   },
 );
 
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function httpServerWithClientCertificates() {
+    const cert = Deno.readTextFileSync("tests/testdata/tls/localhost.crt");
+    const key = Deno.readTextFileSync("tests/testdata/tls/localhost.key");
+    const caCert = Deno.readTextFileSync("tests/testdata/tls/RootCA.pem");
+    const otherCaCert = Deno.readTextFileSync(
+      "tests/testdata/tls/localhost_ecc.crt",
+    );
+
+    const serve = async (
+      options: { clientCaCerts: string[]; requireClientCert?: boolean },
+      clientOptions: { cert?: string; key?: string },
+    ) => {
+      const { promise, resolve } = Promise.withResolvers<void>();
+      const server = Deno.serve({
+        ...options,
+        handler: (_req, info) => Response.json(info.clientCertificate),
+        hostname: "127.0.0.1",
+        port: servePort,
+        onListen: onListen(resolve),
+        cert,
+        key,
+      });
+      await promise;
+      const client = Deno.createHttpClient({
+        caCerts: [caCert],
+        ...clientOptions,
+      });
+      try {
+        const resp = await fetch(`https://localhost:${servePort}/`, {
+          client,
+        });
+        return await resp.json();
+      } finally {
+        client.close();
+        await server.shutdown();
+      }
+    };
+
+    const verified = await serve({ clientCaCerts: [caCert] }, { cert, key });
+    assertEquals(verified.verified, true);
+    assertEquals(verified.error, null);
+    assertEquals(verified.chain.length, 1);
+    assertStringIncludes(verified.chain[0], "-----BEGIN CERTIFICATE-----");
+    assertEquals(
+      verified.spkiSha256,
+      "LUoAIoic1GZuPrXMkihRhgG8Jz8PmEo5I5EfXR+b/eo=",
+    );
+
+    assertEquals(await serve({ clientCaCerts: [caCert] }, {}), null);
+
+    const unverified = await serve({ clientCaCerts: [otherCaCert] }, {
+      cert,
+      key,
+    });
+    assertEquals(unverified.verified, false);
+    assert(unverified.error);
+    assertEquals(
+      unverified.spkiSha256,
+      "LUoAIoic1GZuPrXMkihRhgG8Jz8PmEo5I5EfXR+b/eo=",
+    );
+
+    await assertRejects(() =>
+      serve({ clientCaCerts: [caCert], requireClientCert: true }, {})
+    );
+    await assertRejects(() =>
+      serve({ clientCaCerts: [otherCaCert], requireClientCert: true }, {
+        cert,
+        key,
+      })
+    );
+  },
+);
+
 Deno.test(
   { permissions: { net: true, write: true, read: true } },
   async function httpServerRequestCLTE() {
@@ -3911,7 +4183,6 @@ Deno.test(
   },
 );
 
-// TODO(mmastrac): This test should eventually use fetch, when we support trailers there.
 // This test is ignored because it's flaky and relies on cURL's verbose output.
 Deno.test(
   { permissions: { net: true, run: true, read: true }, ignore: true },
@@ -3950,6 +4221,81 @@ Deno.test(
   },
 );
 
+Deno.test(
+  { permissions: { net: true } },
+  async function httpServerTrailersFetch() {
+    const ac = new AbortController();
+    const { promise, resolve } = Promise.withResolvers<void>();
+
+    const server = Deno.serve({
+      handler: () => {
+        const response = new Response("Hello World", {
+          headers: { "trailer": "grpc-status" },
+        });
+        addTrailers(response, [["grpc-status", "0"]]);
+        return response;
+      },
+      port: servePort,
+      signal: ac.signal,
+      onListen: onListen(resolve),
+      onError: createOnErrorCb(ac),
+    });
+
+    await promise;
+    const client = Deno.createHttpClient({ http1: false, http2: true });
+    const resp = await fetch(`http://127.0.0.1:${servePort}/`, { client });
+    const trailers = resp.trailers;
+    assertEquals(await resp.text(), "Hello World");
+    assertEquals((await trailers).get("grpc-status"), "0");
+
+    assertEquals([...(await new Response("Hello World").trailers)], []);
+
+    client.close();
+    ac.abort();
+    await server.finished;
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function httpServerFullDuplexFetch() {
+    const ac = new AbortController();
+    const { promise, resolve } = Promise.withResolvers<void>();
+
+    const server = Deno.serve({
+      handler: (req) => new Response(req.body),
+      port: servePort,
+      signal: ac.signal,
+      onListen: onListen(resolve),
+      onError: createOnErrorCb(ac),
+    });
+
+    await promise;
+    const client = Deno.createHttpClient({ http1: false, http2: true });
+    const { readable, writable } = new TransformStream<Uint8Array>();
+    const writer = writable.getWriter();
+    const resp = await fetch(`http://127.0.0.1:${servePort}/`, {
+      method: "POST",
+      body: readable,
+      duplex: "full",
+      client,
+    });
+    const reader = resp.body!.getReader();
+    const decoder = new TextDecoder();
+    for (const message of ["ping", "pong"]) {
+      await writer.write(new TextEncoder().encode(message));
+      const { value } = await reader.read();
+      assertEquals(decoder.decode(value), message);
+    }
+    await writer.close();
+    assert((await reader.read()).done);
+
+    client.close();
+    ac.abort();
+    await server.finished;
+  },
+);
+
 // TODO(mmastrac): curl on CI stopped supporting --http2?
 Deno.test(
   {
@@ -5,7 +5,7 @@ Deno.test(
   { ignore: Deno.build.os !== "windows" },
   function signalsNotImplemented() {
     const msg =
-      "Windows only supports ctrl-c (SIGINT) and ctrl-break (SIGBREAK).";
+      "Windows only supports ctrl-c (SIGINT), ctrl-break (SIGBREAK), closing the console (SIGHUP), logging off (SIGLOGOFF) and shutting down (SIGSHUTDOWN).";
     assertThrows(
       () => {
         Deno.addSignalListener("SIGALRM", () => {});
@@ -20,13 +20,6 @@ Deno.test(
       Error,
       msg,
     );
-    assertThrows(
-      () => {
-        Deno.addSignalListener("SIGHUP", () => {});
-      },
-      Error,
-      msg,
-    );
     assertThrows(
       () => {
         Deno.addSignalListener("SIGIO", () => {});
@@ -104,6 +97,82 @@ Deno.test(
   },
 );
 
+Deno.test(
+  { ignore: Deno.build.os !== "windows" },
+  function signalListenerConsoleControlEvents() {
+    const listener = () => {};
+    Deno.addSignalListener("SIGHUP", listener);
+    Deno.removeSignalListener("SIGHUP", listener);
+    for (const signal of ["SIGLOGOFF", "SIGSHUTDOWN"] as const) {
+      Deno.addSignalListener(signal, listener);
+      Deno.removeSignalListener(signal, listener);
+    }
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os !== "windows",
+    permissions: { run: true, read: true, write: true },
+  },
+  async function signalListenerRunsBeforeConsoleCloses() {
+    const CREATE_NEW_CONSOLE = 0x10;
+    const marker = await Deno.makeTempFile();
+    // the child gets its own console, which is closed below
+    const child = new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        `Deno.addSignalListener("SIGHUP", () => {
+          Deno.writeTextFileSync(${JSON.stringify(marker)}, "SIGHUP");
+        });
+        console.log("ready");
+        setTimeout(() => {}, 60000);`,
+      ],
+      stdout: "piped",
+      creationFlags: CREATE_NEW_CONSOLE,
+    }).spawn();
+    const reader = child.stdout.getReader();
+    await reader.read();
+    reader.releaseLock();
+
+    // closing the console window makes Windows send CTRL_CLOSE_EVENT to the
+    // processes attached to it, which is only possible from one of them
+    const WM_CLOSE = 0x10;
+    await new Deno.Command(Deno.execPath(), {
+      args: [
+        "eval",
+        "--unstable-ffi",
+        `const kernel32 = Deno.dlopen("kernel32.dll", {
+          FreeConsole: { parameters: [], result: "i32" },
+          AttachConsole: { parameters: ["u32"], result: "i32" },
+          GetConsoleWindow: { parameters: [], result: "pointer" },
+        });
+        const user32 = Deno.dlopen("user32.dll", {
+          PostMessageW: {
+            parameters: ["pointer", "u32", "usize", "isize"],
+            result: "i32",
+          },
+        });
+        kernel32.symbols.FreeConsole();
+        kernel32.symbols.AttachConsole(${child.pid});
+        user32.symbols.PostMessageW(
+          kernel32.symbols.GetConsoleWindow(),
+          ${WM_CLOSE},
+          0,
+          0,
+        );`,
+      ],
+      stdout: "null",
+      stderr: "null",
+    }).output();
+
+    await child.status;
+    await child.stdout.cancel();
+    assertEquals(await Deno.readTextFile(marker), "SIGHUP");
+    await Deno.remove(marker);
+  },
+);
+
 Deno.test(
   {
     ignore: Deno.build.os === "windows",
@@ -200,6 +269,132 @@ Deno.test(
   },
 );
 
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { run: true, read: true },
+  },
+  async function signalListenerSigchldInfo() {
+    const { promise, resolve } = Promise.withResolvers<Deno.SignalInfo>();
+    let pid: number | undefined;
+    const listener = (info?: Deno.SignalInfo) => {
+      if (info?.children?.some((child) => child.pid === pid)) {
+        resolve(info);
+      }
+    };
+    Deno.addSignalListener("SIGCHLD", listener);
+    const child = new Deno.Command(Deno.execPath(), {
+      args: ["eval", "Deno.exit(4)"],
+      stdout: "null",
+      stderr: "null",
+    }).spawn();
+    pid = child.pid;
+
+    const info = await promise;
+    Deno.removeSignalListener("SIGCHLD", listener);
+    assertEquals(info.signal, "SIGCHLD");
+    const exited = info.children!.find((child) => child.pid === pid)!;
+    assertEquals(exited.status.code, 4);
+    assertEquals((await child.status).code, 4);
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { run: true },
+  },
+  async function signalListenerSigwinchInfo() {
+    const { promise, resolve } = Promise.withResolvers<Deno.SignalInfo>();
+    const listener = (info?: Deno.SignalInfo) => resolve(info!);
+    Deno.addSignalListener("SIGWINCH", listener);
+    Deno.kill(Deno.pid, "SIGWINCH");
+    const info = await promise;
+    Deno.removeSignalListener("SIGWINCH", listener);
+    assertEquals(info.signal, "SIGWINCH");
+    // the size is only known if a terminal is attached
+    assertEquals(typeof info.columns, typeof info.rows);
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os !== "linux",
+    permissions: { run: true },
+  },
+  async function signalListenerRealtimeSignal() {
+    let c = 0;
+    const listener = () => {
+      c += 1;
+    };
+    Deno.addSignalListener("SIGRTMIN+1", listener);
+    Deno.kill(Deno.pid, "SIGRTMIN+1");
+    while (c < 1) {
+      await delay(20);
+    }
+    Deno.signal.queue(Deno.pid, "SIGRTMIN+1", 7);
+    while (c < 2) {
+      await delay(20);
+    }
+    Deno.removeSignalListener("SIGRTMIN+1", listener);
+    assertThrows(
+      () => Deno.kill(Deno.pid, "SIGRTMIN+100" as Deno.Signal),
+      TypeError,
+    );
+  },
+);
+
+Deno.test(
+  { ignore: Deno.build.os === "linux", permissions: { run: true } },
+  function signalQueueNotSupported() {
+    assertThrows(
+      () => Deno.signal.queue(Deno.pid, "SIGUSR1", 1),
+      TypeError,
+    );
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { run: true, read: true },
+  },
+  async function signalBlockDefersSignal() {
+    const { success, signal, stdout } = await new Deno.Command(
+      Deno.execPath(),
+      {
+        args: [
+          "eval",
+          `
+          Deno.signal.block(["SIGTERM"]);
+          Deno.kill(Deno.pid, "SIGTERM");
+          await new Promise((resolve) => setTimeout(resolve, 100));
+          console.log("flushed");
+          Deno.signal.unblock(["SIGTERM"]);
+          await new Promise((resolve) => setTimeout(resolve, 1000));
+          console.log("not terminated");
+          `,
+        ],
+        stdout: "piped",
+      },
+    ).output();
+    assertEquals(success, false);
+    assertEquals(signal, "SIGTERM");
+    assertEquals(new TextDecoder().decode(stdout), "flushed\n");
+  },
+);
+
+Deno.test(
+  { ignore: Deno.build.os === "windows" },
+  function signalBlockForbidden() {
+    assertThrows(
+      () => Deno.signal.block(["SIGKILL"]),
+      TypeError,
+      "Blocking signal 'SIGKILL' is not allowed",
+    );
+  },
+);
+
 // This tests that pending op_signal_poll doesn't block the runtime from exiting the process.
 Deno.test(
   {
@@ -3,7 +3,7 @@
 import * as colors from "@std/fmt/colors";
 import { assert } from "@std/assert";
 export { colors };
-import { join, resolve } from "@std/path";
+import { dirname, join, resolve } from "@std/path";
 export {
   assert,
   assertEquals,
@@ -84,6 +84,23 @@ export function execCode2(code: string) {
   return execCode3(Deno.execPath(), ["eval", "--unstable", "--no-check", code]);
 }
 
+/** Creates a temporary directory with the given files, keyed by their path
+ * relative to it, and returns its path. */
+export function makeTempTree(
+  files: Record<string, string | Uint8Array>,
+): string {
+  const root = Deno.makeTempDirSync();
+  for (const [path, content] of Object.entries(files)) {
+    Deno.mkdirSync(dirname(join(root, path)), { recursive: true });
+    if (typeof content === "string") {
+      Deno.writeTextFileSync(join(root, path), content);
+    } else {
+      Deno.writeFileSync(join(root, path), content);
+    }
+  }
+  return root;
+}
+
 export function tmpUnixSocketPath(): string {
   const folder = Deno.makeTempDirSync();
   return join(folder, "socket");
@@ -1648,3 +1648,143 @@ Deno.test(
     listener.close();
   },
 );
+
+// `openssl x509 -pubkey -noout | openssl pkey -pubin -outform der |
+// openssl dgst -sha256 -binary | base64` of the certificates
+const localhostPublicKeyPin = "LUoAIoic1GZuPrXMkihRhgG8Jz8PmEo5I5EfXR+b/eo=";
+const rootCAPublicKeyPin = "rCZhu/dePzdoNw3S5Br47UF6PM6fPNxAicc5U6w9jrU=";
+
+Deno.test(
+  { permissions: { read: true, net: true } },
+  async function connectTlsPinnedPublicKeys() {
+    const { listener, port, hostname } = listenTls();
+    const accepted = (async () => {
+      for await (const conn of listener) {
+        await conn.handshake().catch(() => {});
+        conn.close();
+      }
+    })();
+
+    for (const pin of [localhostPublicKeyPin, rootCAPublicKeyPin]) {
+      const conn = await Deno.connectTls({
+        hostname,
+        port,
+        caCertFiles: ["tests/testdata/tls/RootCA.pem"],
+        pinnedPublicKeys: [pin],
+      });
+      await conn.handshake();
+      conn.close();
+    }
+
+    const conn = await Deno.connectTls({
+      hostname,
+      port,
+      caCerts,
+      pinnedPublicKeys: [btoa("\0".repeat(32))],
+    });
+    await assertRejects(
+      () => conn.handshake(),
+      Deno.errors.InvalidData,
+      "invalid peer certificate: ApplicationVerificationFailure",
+    );
+    conn.close();
+
+    await assertRejects(
+      () =>
+        Deno.connectTls({ hostname, port, pinnedPublicKeys: ["not a pin"] }),
+      TypeError,
+      "Invalid pinned public key: not a pin",
+    );
+    listener.close();
+    await accepted;
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function connectTlsCaCertFilesPermission() {
+    const { listener, port, hostname } = listenTls();
+    await assertRejects(
+      () =>
+        Deno.connectTls({
+          hostname,
+          port,
+          caCertFiles: ["tests/testdata/tls/RootCA.pem"],
+        }),
+      Deno.errors.PermissionDenied,
+    );
+    listener.close();
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function startTlsExportKeyingMaterial() {
+    const { listener, port, hostname } = listenTls();
+    const accepted = listener.accept();
+    const tcpConn = await Deno.connect({ hostname, port });
+    const conn = await Deno.startTls(tcpConn, { hostname, caCerts });
+    const serverConn = await accepted;
+
+    const [material] = await Promise.all([
+      conn.exportKeyingMaterial(32, "label"),
+      serverConn.handshake(),
+    ]);
+    assertEquals(material.length, 32);
+    assertEquals(await conn.exportKeyingMaterial(32, "label"), material);
+    assertEquals(
+      await conn.exportKeyingMaterial(32, "label", new Uint8Array()),
+      material,
+    );
+    assertNotEquals(
+      await conn.exportKeyingMaterial(32, "label", new Uint8Array([1])),
+      material,
+    );
+    assertNotEquals(await conn.exportKeyingMaterial(32, "other"), material);
+    assertEquals(
+      (await conn.exportKeyingMaterial(100, "label")).length,
+      100,
+    );
+    await assertRejects(
+      () => serverConn.exportKeyingMaterial(32, "label"),
+      Deno.errors.NotSupported,
+    );
+
+    conn.close();
+    serverConn.close();
+    listener.close();
+  },
+);
+
+Deno.test(
+  { permissions: { net: true } },
+  async function connectTlsSessionCache() {
+    const { listener, port, hostname } = listenTls();
+    const accepted = (async () => {
+      for await (const conn of listener) {
+        await conn.handshake().catch(() => {});
+        conn.close();
+      }
+    })();
+
+    using sessionCache = Deno.createTlsSessionCache({ capacity: 4 });
+    for (let i = 0; i < 3; i++) {
+      const conn = await Deno.connectTls({
+        hostname,
+        port,
+        caCerts,
+        sessionCache,
+      });
+      await conn.handshake();
+      conn.close();
+    }
+
+    sessionCache.close();
+    await assertRejects(
+      () => Deno.connectTls({ hostname, port, caCerts, sessionCache }),
+      Deno.errors.BadResource,
+    );
+    listener.close();
+    await accepted;
+  },
+);
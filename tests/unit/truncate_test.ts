@@ -112,3 +112,81 @@ Deno.test(
     );
   },
 );
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function fallocateSyncSuccess() {
+    const filename = Deno.makeTempDirSync() + "/test_fallocateSync.txt";
+    using file = Deno.openSync(filename, {
+      create: true,
+      read: true,
+      write: true,
+    });
+
+    file.writeSync(new TextEncoder().encode("hello"));
+    file.allocateSync(4096);
+    assertEquals(file.statSync().size, 4096);
+    file.allocateSync(1024, { offset: 8192, mode: "sparse" });
+    assertEquals(file.statSync().size, 9216);
+    // the data which was there is kept
+    file.allocateSync(16);
+    assertEquals(file.statSync().size, 9216);
+    const data = Deno.readFileSync(filename);
+    assertEquals(new TextDecoder().decode(data.subarray(0, 5)), "hello");
+    assertEquals(data.subarray(5).every((b) => b === 0), true);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function fallocatePunchHole() {
+    const filename = Deno.makeTempDirSync() + "/test_fallocate.txt";
+    using file = await Deno.open(filename, {
+      create: true,
+      read: true,
+      write: true,
+    });
+
+    await file.write(new Uint8Array(8192).fill(1));
+    try {
+      await file.allocate(4096, { offset: 1024, mode: "punch-hole" });
+    } catch (err) {
+      if (err instanceof Deno.errors.NotSupported) {
+        return;
+      }
+      throw err;
+    }
+    assertEquals((await file.stat()).size, 8192);
+    const data = await Deno.readFile(filename);
+    assertEquals(data.subarray(0, 1024).every((b) => b === 1), true);
+    assertEquals(data.subarray(1024, 5120).every((b) => b === 0), true);
+    assertEquals(data.subarray(5120).every((b) => b === 1), true);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function fallocateInvalidMode() {
+    const filename = Deno.makeTempDirSync() + "/test_fallocate_mode.txt";
+    using file = await Deno.open(filename, { create: true, write: true });
+
+    assertThrows(
+      () =>
+        file.allocateSync(16, {
+          // @ts-expect-error invalid mode
+          mode: "compress",
+        }),
+      TypeError,
+      "Invalid allocate mode: compress",
+    );
+    await assertRejects(
+      () =>
+        file.allocate(16, {
+          // @ts-expect-error invalid mode
+          mode: "compress",
+        }),
+      TypeError,
+      "Invalid allocate mode: compress",
+    );
+  },
+);
@@ -1,5 +1,5 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
-import { assertEquals } from "./test_util.ts";
+import { assertEquals, assertThrows } from "./test_util.ts";
 
 Deno.test(
   {
@@ -13,3 +13,108 @@ Deno.test(
     assertEquals(finalMask, prevMask);
   },
 );
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { read: true, write: true },
+  },
+  function umaskScopedSync() {
+    const mask = Deno.umask();
+    const dir = Deno.makeTempDirSync();
+    const path = `${dir}/file.txt`;
+    const result = Deno.umask(0o077, () => {
+      assertEquals(Deno.umask(), 0o077);
+      Deno.writeTextFileSync(path, "hello");
+      return "done";
+    });
+    assertEquals(result, "done");
+    assertEquals(Deno.umask(), mask);
+    assertEquals(Deno.statSync(path).mode! & 0o777, 0o600);
+    Deno.removeSync(dir, { recursive: true });
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { read: true, write: true, run: true },
+  },
+  async function umaskScopedAsyncOperations() {
+    const mask = Deno.umask();
+    const dir = await Deno.makeTempDir();
+    const [created, child] = Deno.umask(0o027, () => [
+      Deno.mkdir(`${dir}/sub`),
+      new Deno.Command("sh", { args: ["-c", "umask"], stdout: "piped" })
+        .spawn(),
+    ]);
+    assertEquals(Deno.umask(), mask);
+    await created;
+    const { stdout } = await child.output();
+    assertEquals(new TextDecoder().decode(stdout).trim(), "0027");
+    assertEquals((await Deno.stat(`${dir}/sub`)).mode! & 0o777, 0o750);
+    await Deno.remove(dir, { recursive: true });
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+  },
+  function umaskScopedRejectsPromises() {
+    const mask = Deno.umask();
+    assertThrows(
+      () => Deno.umask(0o077, async () => {}),
+      TypeError,
+      "must not return a promise",
+    );
+    assertThrows(
+      // deno-lint-ignore no-explicit-any
+      () => Deno.umask(0o077, () => ({ then() {} }) as any),
+      TypeError,
+      "must not return a promise",
+    );
+    assertEquals(Deno.umask(), mask);
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { read: true, write: true },
+  },
+  async function umaskScopedWriteFileAtomicAsync() {
+    const dir = await Deno.makeTempDir();
+    const path = `${dir}/file.txt`;
+    // wrapped, as the callback must not return a promise
+    const [written] = Deno.umask(0o077, () => [
+      Deno.writeFileAtomic(path, "hello"),
+    ]);
+    await written;
+    assertEquals((await Deno.stat(path)).mode! & 0o777, 0o600);
+    await Deno.remove(dir, { recursive: true });
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+  },
+  function umaskScopedRestoredOnThrow() {
+    const mask = Deno.umask();
+    assertThrows(
+      () =>
+        Deno.umask(0o077, () => {
+          throw new Error("boom");
+        }),
+      Error,
+      "boom",
+    );
+    assertEquals(Deno.umask(), mask);
+    assertThrows(
+      () => Deno.umask(0o1000, () => {}),
+      TypeError,
+      "Invalid umask",
+    );
+  },
+);
@@ -805,3 +805,191 @@ Deno.test("Close connection", async () => {
   await server.finished;
   conn.close();
 });
+
+Deno.test(async function websocketDeflateEcho() {
+  const ac = new AbortController();
+  const listeningDeferred = Promise.withResolvers<void>();
+  const serverExtensions = Promise.withResolvers<string>();
+
+  const server = Deno.serve({
+    handler: (req) => {
+      const { socket, response } = Deno.upgradeWebSocket(req, {
+        deflate: { clientNoContextTakeover: true },
+      });
+      socket.onopen = () => serverExtensions.resolve(socket.extensions);
+      socket.onmessage = (e) => socket.send(e.data);
+      socket.onclose = () => ac.abort();
+      socket.onerror = () => fail();
+      return response;
+    },
+    signal: ac.signal,
+    onListen: () => listeningDeferred.resolve(),
+    hostname: "localhost",
+    port: servePort,
+  });
+
+  await listeningDeferred.promise;
+  const wss = new WebSocketStream(serveUrl, { deflate: true });
+  const { readable, writable, extensions } = await wss.opened;
+  assertEquals(extensions, "permessage-deflate; client_no_context_takeover");
+  assertEquals(
+    await serverExtensions.promise,
+    "permessage-deflate; client_no_context_takeover",
+  );
+
+  const text = "Hello, deflate! ".repeat(10_000);
+  const binary = new Uint8Array(100_000).fill(42);
+  const writer = writable.getWriter();
+  const reader = readable.getReader();
+  for (const message of [text, binary, "", text]) {
+    await writer.write(message);
+    assertEquals((await reader.read()).value, message);
+  }
+  wss.close();
+  await wss.closed;
+  await server.finished;
+});
+
+Deno.test(async function websocketDeflateRawFrame() {
+  const ac = new AbortController();
+  const listeningDeferred = Promise.withResolvers<void>();
+  const message = Promise.withResolvers<string>();
+
+  const server = Deno.serve({
+    handler: (req) => {
+      const { socket, response } = Deno.upgradeWebSocket(req, {
+        deflate: true,
+      });
+      socket.onmessage = (e) => {
+        message.resolve(e.data);
+        socket.close();
+      };
+      socket.onclose = () => ac.abort();
+      return response;
+    },
+    signal: ac.signal,
+    onListen: () => listeningDeferred.resolve(),
+    hostname: "localhost",
+    port: servePort,
+  });
+
+  await listeningDeferred.promise;
+  const conn = await Deno.connect({ port: servePort, hostname: "localhost" });
+  await conn.write(
+    new TextEncoder().encode(
+      "GET / HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Extensions: permessage-deflate; server_max_window_bits=10, permessage-deflate; client_max_window_bits\r\n\r\n",
+    ),
+  );
+  const buf = new Uint8Array(1024);
+  const n = await conn.read(buf);
+  const head = new TextDecoder().decode(buf.subarray(0, n!));
+  // The first offer asks for a smaller window than the server compresses with.
+  assert(
+    head.includes("sec-websocket-extensions: permessage-deflate\r\n"),
+    head,
+  );
+
+  // "Hello" as a compressed text frame, from RFC 7692 section 7.2.3.1.
+  await conn.write(
+    new Uint8Array([0xc1, 0x07, 0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00]),
+  );
+  assertEquals(await message.promise, "Hello");
+
+  await server.finished;
+  conn.close();
+});
+
+Deno.test(async function websocketMaxMessageSize() {
+  const ac = new AbortController();
+  const listeningDeferred = Promise.withResolvers<void>();
+
+  const server = Deno.serve({
+    handler: (req) => {
+      const { socket, response } = Deno.upgradeWebSocket(req, {
+        maxMessageSize: 4,
+      });
+      socket.onmessage = () => fail();
+      socket.onclose = () => ac.abort();
+      return response;
+    },
+    signal: ac.signal,
+    onListen: () => listeningDeferred.resolve(),
+    hostname: "localhost",
+    port: servePort,
+  });
+
+  await listeningDeferred.promise;
+  const { promise, resolve } = Promise.withResolvers<CloseEvent>();
+  const ws = new WebSocket(serveUrl);
+  ws.onopen = () => ws.send("Hello");
+  ws.onclose = resolve;
+  const event = await promise;
+  assertEquals(event.code, 1009);
+  await server.finished;
+});
+
+Deno.test(function websocketExtensionOptionsValidation() {
+  assertThrows(
+    () =>
+      new WebSocketStream(serveUrl, {
+        deflate: { clientMaxWindowBits: 10 },
+      }),
+    TypeError,
+    "'clientMaxWindowBits' can only be requested by servers",
+  );
+  assertThrows(
+    () =>
+      new WebSocketStream(serveUrl, {
+        deflate: { serverMaxWindowBits: 16 },
+      }),
+    RangeError,
+  );
+  assertThrows(
+    () => new WebSocketStream(serveUrl, { maxFrameSize: 0 }),
+    RangeError,
+    "'maxFrameSize' must be greater than 0",
+  );
+});
+
+Deno.test(async function websocketStreamBackpressure() {
+  const ac = new AbortController();
+  const listeningDeferred = Promise.withResolvers<void>();
+
+  const server = Deno.serve({
+    handler: (req) => {
+      const { socket, response } = Deno.upgradeWebSocket(req);
+      socket.onmessage = (e) => socket.send(e.data);
+      socket.onclose = () => ac.abort();
+      socket.onerror = () => fail();
+      return response;
+    },
+    signal: ac.signal,
+    onListen: () => listeningDeferred.resolve(),
+    hostname: "localhost",
+    port: servePort,
+  });
+
+  await listeningDeferred.promise;
+  const wss = new WebSocketStream(serveUrl);
+  const { readable, writable } = await wss.opened;
+  const writer = writable.getWriter();
+  const reader = readable.getReader();
+
+  // Write more than the socket buffers in both directions before reading.
+  const count = 64;
+  const writes = [];
+  for (let i = 0; i < count; i++) {
+    writes.push(writer.write(new Uint8Array(32 * 1024).fill(i)));
+  }
+  await Promise.all(writes);
+  for (let i = 0; i < count; i++) {
+    const { value } = await reader.read();
+    assert(value instanceof Uint8Array);
+    assertEquals(value.byteLength, 32 * 1024);
+    assertEquals(value[0], i);
+    assertEquals(value[value.byteLength - 1], i);
+  }
+  wss.close();
+  await wss.closed;
+  await server.finished;
+});
@@ -32,3 +32,76 @@ Deno.test(
     }
   },
 );
+
+Deno.test(
+  { permissions: { env: true, read: true } },
+  async function workerPermissionsRevokePropagated() {
+    const { promise, resolve } = Promise.withResolvers<
+      Record<string, string>
+    >();
+
+    const worker = new Worker(
+      import.meta.resolve("../testdata/workers/permission_echo.js"),
+      {
+        type: "module",
+        deno: { permissions: { env: true, read: true } },
+      },
+    );
+
+    worker.onmessage = ({ data }) => {
+      resolve(data);
+    };
+
+    Deno.permissions.revokeSync({ name: "read" });
+    const status = Deno.permissions.revokeSync({ name: "env" }, {
+      workers: true,
+    });
+    assertEquals(status.state, "prompt");
+
+    worker.postMessage(null);
+    const permissions = await promise;
+    worker.terminate();
+
+    assertEquals(permissions.env, "prompt");
+    // only revoked in this thread
+    assertEquals(permissions.read, "granted");
+  },
+);
+
+Deno.test(
+  { permissions: { env: true, read: true } },
+  async function workerPermissionsSnapshot() {
+    const snapshot = Deno.permissions.snapshot();
+    assertEquals(snapshot.env.grantedGlobal, true);
+    assertEquals(snapshot.net.grantedGlobal, false);
+
+    const { promise, resolve } = Promise.withResolvers<
+      Record<string, string>
+    >();
+
+    const worker = new Worker(
+      import.meta.resolve("../testdata/workers/permission_echo.js"),
+      {
+        type: "module",
+        deno: {
+          permissionsSnapshot: JSON.parse(JSON.stringify({
+            ...snapshot,
+            read: { ...snapshot.read, grantedGlobal: false },
+          })),
+        },
+      },
+    );
+
+    worker.onmessage = ({ data }) => {
+      resolve(data);
+    };
+
+    worker.postMessage(null);
+    const permissions = await promise;
+    worker.terminate();
+
+    assertEquals(permissions.env, "granted");
+    assertEquals(permissions.read, "prompt");
+    assertEquals(permissions.net, "prompt");
+  },
+);
@@ -4,7 +4,13 @@
 
 // Requires to be run with `--allow-net` flag
 
-import { assert, assertEquals, assertMatch, assertThrows } from "@std/assert";
+import {
+  assert,
+  assertEquals,
+  assertMatch,
+  assertRejects,
+  assertThrows,
+} from "@std/assert";
 
 function resolveWorker(worker: string): string {
   return import.meta.resolve(`../testdata/workers/${worker}`);
@@ -833,3 +839,174 @@ Deno.test({
     w.terminate();
   },
 });
+
+Deno.test({
+  name: "worker cpu limit",
+  fn: async function () {
+    const worker = new Worker(
+      `data:application/javascript,${encodeURIComponent("while (true) {}")}`,
+      { type: "module", deno: { limits: { cpuMs: 100 } } },
+    );
+    const { promise, resolve } = Promise.withResolvers<string>();
+    worker.onerror = (e) => {
+      e.preventDefault();
+      resolve(e.message);
+    };
+    assertEquals(
+      await promise,
+      "Worker exceeded its CPU time limit of 100 ms",
+    );
+    worker.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker heap limit",
+  fn: async function () {
+    const worker = new Worker(
+      `data:application/javascript,${
+        encodeURIComponent(
+          "const a = []; while (true) a.push(new Array(1e5).fill({}));",
+        )
+      }`,
+      { type: "module", deno: { limits: { heapMb: 64 } } },
+    );
+    const { promise, resolve } = Promise.withResolvers<string>();
+    worker.onerror = (e) => {
+      e.preventDefault();
+      resolve(e.message);
+    };
+    assertEquals(await promise, "Worker exceeded its heap limit of 64 MB");
+    worker.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker limits are validated",
+  fn: function () {
+    const url = resolveWorker("test_worker.ts");
+    assertThrows(
+      () =>
+        new Worker(url, { type: "module", deno: { limits: { cpuMs: 0 } } }),
+      RangeError,
+      "'cpuMs' of 'WorkerLimits' ('deno.limits') must be greater than 0",
+    );
+    assertThrows(
+      () =>
+        new Worker(url, { type: "module", deno: { limits: { heapMb: -1 } } }),
+      TypeError,
+    );
+  },
+});
+
+function workerPoolModule(source: string): string {
+  return `data:application/javascript,${encodeURIComponent(source)}`;
+}
+
+Deno.test({
+  name: "worker pool runs tasks",
+  fn: async function () {
+    using pool = new Deno.WorkerPool(
+      workerPoolModule(
+        "self.onmessage = (e) => self.postMessage([e.data, self.name]);",
+      ),
+      { size: 2, name: "pool" },
+    );
+    assertEquals(pool.size, 2);
+    const results = await Promise.all(
+      Array.from({ length: 8 }, (_, i) => pool.run(i)),
+    );
+    assertEquals(
+      results,
+      Array.from({ length: 8 }, (_, i) => [i, "pool"]),
+    );
+  },
+});
+
+Deno.test({
+  name: "worker pool transfers buffers",
+  fn: async function () {
+    using pool = new Deno.WorkerPool(
+      workerPoolModule(
+        "self.onmessage = (e) => self.postMessage(e.data.byteLength);",
+      ),
+      { size: 1 },
+    );
+    const buffer = new ArrayBuffer(16);
+    assertEquals(await pool.run(buffer, [buffer]), 16);
+    assertEquals(buffer.byteLength, 0);
+  },
+});
+
+Deno.test({
+  name: "worker pool replaces crashed workers",
+  fn: async function () {
+    using pool = new Deno.WorkerPool(
+      workerPoolModule(`
+        self.onmessage = (e) => {
+          if (e.data === "crash") throw new Error("boom");
+          self.postMessage(e.data);
+        };
+      `),
+      { size: 1 },
+    );
+    assertEquals(await pool.run("a"), "a");
+    await assertRejects(() => pool.run("crash"), Error, "boom");
+    assertEquals(await pool.run("b"), "b");
+  },
+});
+
+Deno.test({
+  name: "worker pool enforces limits",
+  fn: async function () {
+    using pool = new Deno.WorkerPool(
+      workerPoolModule(`
+        self.onmessage = (e) => {
+          while (e.data) {}
+          self.postMessage(1);
+        };
+      `),
+      { size: 1, deno: { limits: { cpuMs: 100 } } },
+    );
+    await assertRejects(
+      () => pool.run(true),
+      Error,
+      "Worker exceeded its CPU time limit of 100 ms",
+    );
+    assertEquals(await pool.run(false), 1);
+  },
+});
+
+Deno.test({
+  name: "worker pool terminate rejects tasks",
+  fn: async function () {
+    const pool = new Deno.WorkerPool(
+      workerPoolModule("self.onmessage = () => {};"),
+      { size: 1 },
+    );
+    const task = pool.run(null);
+    pool.terminate();
+    await assertRejects(() => task, Error, "Worker pool was terminated");
+    await assertRejects(
+      () => pool.run(null),
+      Error,
+      "Worker pool was terminated",
+    );
+  },
+});
+
+Deno.test({
+  name: "worker pool options are validated",
+  fn: function () {
+    const specifier = workerPoolModule("");
+    assertThrows(
+      () => new Deno.WorkerPool(specifier, { size: 0 }),
+      RangeError,
+      "'size' must be greater than 0",
+    );
+    assertThrows(
+      () => new Deno.WorkerPool(specifier, { size: -1 }),
+      TypeError,
+    );
+  },
+});
@@ -443,3 +443,72 @@ Deno.test(
     assertEquals(Deno.readFileSync(filename), new Uint8Array([1, 2]));
   },
 );
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function writeFileAtomicSuccess() {
+    const tempDir = Deno.makeTempDirSync();
+    const filename = tempDir + "/config.json";
+    await Deno.writeFileAtomic(filename, new Uint8Array([1, 2, 3]));
+    assertEquals(Deno.readFileSync(filename), new Uint8Array([1, 2, 3]));
+    await Deno.writeFileAtomic(filename, "hello", { syncDir: true });
+    assertEquals(Deno.readTextFileSync(filename), "hello");
+    // the temporary file is gone
+    const names = Array.from(Deno.readDirSync(tempDir), (e) => e.name);
+    assertEquals(names, ["config.json"]);
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { read: true, write: true },
+  },
+  function writeFileAtomicSyncMode() {
+    const tempDir = Deno.makeTempDirSync();
+    const filename = tempDir + "/config.json";
+    Deno.writeFileSync(filename, new Uint8Array([1]));
+    Deno.chmodSync(filename, 0o640);
+    Deno.writeFileAtomicSync(filename, "existing mode");
+    assertEquals(Deno.statSync(filename).mode! & 0o777, 0o640);
+    Deno.writeFileAtomicSync(filename, "given mode", { mode: 0o600 });
+    assertEquals(Deno.statSync(filename).mode! & 0o777, 0o600);
+    assertEquals(Deno.readTextFileSync(filename), "given mode");
+  },
+);
+
+Deno.test(
+  {
+    ignore: Deno.build.os === "windows",
+    permissions: { read: true, write: true },
+  },
+  function writeFileAtomicSyncSymlink() {
+    const tempDir = Deno.makeTempDirSync();
+    const filename = tempDir + "/config.json";
+    const link = tempDir + "/link.json";
+    Deno.writeTextFileSync(filename, "old");
+    Deno.symlinkSync(filename, link);
+    Deno.writeFileAtomicSync(link, "new");
+    assert(Deno.lstatSync(link).isSymlink);
+    assertEquals(Deno.readTextFileSync(filename), "new");
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function writeFileAtomicSyncNotFound() {
+    const filename = "/baddir/config.json";
+    assertThrows(() => {
+      Deno.writeFileAtomicSync(filename, "hello");
+    }, Deno.errors.NotFound);
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: false } },
+  async function writeFileAtomicPerm() {
+    await assertRejects(async () => {
+      await Deno.writeFileAtomic("/config.json", "hello");
+    }, Deno.errors.PermissionDenied);
+  },
+);
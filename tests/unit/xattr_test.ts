@@ -0,0 +1,80 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+import { assertEquals, assertRejects, assertThrows } from "./test_util.ts";
+
+// Linux only allows unprivileged users to set attributes in the `user.`
+// namespace, other platforms take any name.
+const NAME = "user.deno_test";
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  function xattrSync() {
+    const dir = Deno.makeTempDirSync();
+    const path = `${dir}/file.txt`;
+    Deno.writeTextFileSync(path, "hello");
+
+    assertEquals(Deno.getXattrSync(path, NAME), null);
+    assertEquals(Deno.listXattrsSync(path).includes(NAME), false);
+
+    Deno.setXattrSync(path, NAME, "world");
+    assertEquals(
+      Deno.getXattrSync(path, NAME),
+      new TextEncoder().encode("world"),
+    );
+    assertEquals(Deno.listXattrsSync(path).includes(NAME), true);
+
+    Deno.setXattrSync(path, NAME, new Uint8Array([1, 2, 3]));
+    assertEquals(Deno.getXattrSync(path, NAME), new Uint8Array([1, 2, 3]));
+    // the contents of the file are untouched
+    assertEquals(Deno.readTextFileSync(path), "hello");
+    Deno.removeSync(dir, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function xattr() {
+    const dir = await Deno.makeTempDir();
+    const path = `${dir}/file.txt`;
+    await Deno.writeTextFile(path, "hello");
+
+    assertEquals(await Deno.getXattr(path, NAME), null);
+    await Deno.setXattr(path, NAME, "world");
+    assertEquals(
+      await Deno.getXattr(path, NAME),
+      new TextEncoder().encode("world"),
+    );
+    assertEquals((await Deno.listXattrs(path)).includes(NAME), true);
+    await Deno.remove(dir, { recursive: true });
+  },
+);
+
+Deno.test(
+  { permissions: { read: true, write: true } },
+  async function xattrNotFound() {
+    const path = `${Deno.makeTempDirSync()}/missing.txt`;
+    assertThrows(() => Deno.getXattrSync(path, NAME), Deno.errors.NotFound);
+    assertThrows(
+      () => Deno.setXattrSync(path, NAME, "world"),
+      Deno.errors.NotFound,
+    );
+    await assertRejects(() => Deno.listXattrs(path), Deno.errors.NotFound);
+  },
+);
+
+Deno.test(
+  { permissions: { read: false, write: false } },
+  async function xattrPerm() {
+    assertThrows(
+      () => Deno.getXattrSync("file.txt", NAME),
+      Deno.errors.PermissionDenied,
+    );
+    assertThrows(
+      () => Deno.listXattrsSync("file.txt"),
+      Deno.errors.PermissionDenied,
+    );
+    await assertRejects(
+      () => Deno.setXattr("file.txt", NAME, "world"),
+      Deno.errors.PermissionDenied,
+    );
+  },
+);
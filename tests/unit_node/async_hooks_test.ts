@@ -1,7 +1,8 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 import { AsyncLocalStorage, AsyncResource } from "node:async_hooks";
+import http from "node:http";
 import process from "node:process";
-import { setImmediate } from "node:timers";
+import { setImmediate, setTimeout as setNodeTimeout } from "node:timers";
 import { assert, assertEquals } from "@std/assert";
 
 Deno.test(async function foo() {
@@ -160,3 +161,115 @@ Deno.test(async function worksWithAsyncAPIs() {
     test();
   });
 });
+
+Deno.test(function exit() {
+  const store = new AsyncLocalStorage();
+  const other = new AsyncLocalStorage();
+  store.run("data", () => {
+    other.run("other", () => {
+      const result = store.exit((arg) => {
+        assertEquals(store.getStore(), undefined);
+        assertEquals(other.getStore(), "other");
+        return arg;
+      }, "arg");
+      assertEquals(result, "arg");
+      assertEquals(store.getStore(), "data");
+    });
+  });
+});
+
+Deno.test(async function exitPropagatesToCallbacks() {
+  const store = new AsyncLocalStorage();
+  const deferred = Promise.withResolvers();
+  store.run("data", () => {
+    store.exit(() => {
+      setTimeout(() => deferred.resolve(store.getStore()), 0);
+    });
+  });
+  assertEquals(await deferred.promise, undefined);
+});
+
+Deno.test(async function timerRefreshKeepsContext() {
+  const store = new AsyncLocalStorage();
+  const deferred = Promise.withResolvers();
+  const timer = store.run("created", () => {
+    return setNodeTimeout(() => deferred.resolve(store.getStore()), 10);
+  });
+  store.run("refreshed", () => timer.refresh());
+  assertEquals(await deferred.promise, "created");
+});
+
+Deno.test(async function propagatesThroughOpsAndFetch() {
+  const store = new AsyncLocalStorage();
+  const ac = new AbortController();
+  const { promise: listening, resolve } = Promise.withResolvers<number>();
+  const server = Deno.serve({
+    port: 0,
+    signal: ac.signal,
+    onListen: ({ port }) => resolve(port),
+  }, () => new Response("ok"));
+  const port = await listening;
+
+  await Promise.all(["a", "b"].map((id) =>
+    store.run(id, async () => {
+      await Deno.readTextFile(import.meta.filename!);
+      assertEquals(store.getStore(), id);
+      const res = await fetch(`http://127.0.0.1:${port}/`);
+      assertEquals(store.getStore(), id);
+      assertEquals(await res.text(), "ok");
+      assertEquals(store.getStore(), id);
+    })
+  ));
+
+  ac.abort();
+  await server.finished;
+});
+
+Deno.test(async function doesNotLeakAcrossRequests() {
+  const store = new AsyncLocalStorage();
+  const seen: unknown[] = [];
+  const server = http.createServer((req, res) => {
+    seen.push(store.getStore());
+    store.enterWith(req.url);
+    setTimeout(() => res.end(store.getStore()), 0);
+  });
+  const { promise, resolve } = Promise.withResolvers<void>();
+  server.listen(0, "127.0.0.1", () => resolve());
+  await promise;
+  // deno-lint-ignore no-explicit-any
+  const { port } = server.address() as any;
+
+  for (const path of ["/a", "/b"]) {
+    const res = await fetch(`http://127.0.0.1:${port}${path}`);
+    assertEquals(await res.text(), path);
+  }
+  assertEquals(seen, [undefined, undefined]);
+
+  const closed = Promise.withResolvers<void>();
+  server.close(() => closed.resolve());
+  await closed.promise;
+});
+
+Deno.test(async function clientResponseInRequestContext() {
+  const store = new AsyncLocalStorage();
+  const server = http.createServer((_req, res) => res.end("ok"));
+  const { promise, resolve } = Promise.withResolvers<void>();
+  server.listen(0, "127.0.0.1", () => resolve());
+  await promise;
+  // deno-lint-ignore no-explicit-any
+  const { port } = server.address() as any;
+
+  const deferred = Promise.withResolvers<unknown>();
+  const req = store.run("request", () => {
+    return http.request(`http://127.0.0.1:${port}/`, (res) => {
+      res.resume();
+      res.on("end", () => deferred.resolve(store.getStore()));
+    });
+  });
+  store.run("end", () => req.end());
+  assertEquals(await deferred.promise, "request");
+
+  const closed = Promise.withResolvers<void>();
+  server.close(() => closed.resolve());
+  await closed.promise;
+});
@@ -2,6 +2,10 @@
 
 import CP from "node:child_process";
 import { Buffer } from "node:buffer";
+import fs from "node:fs";
+import net from "node:net";
+import process from "node:process";
+import type { Writable } from "node:stream";
 import {
   assert,
   assertEquals,
@@ -1046,3 +1050,265 @@ Deno.test(async function sendAfterClosedThrows() {
 
   await timeout.promise;
 });
+
+Deno.test({
+  name: "[node/child_process spawn] passes file descriptors as extra stdio",
+  ignore: Deno.build.os === "windows",
+  async fn() {
+    const file = await Deno.makeTempFile();
+    const fd = fs.openSync(file, "w");
+    const timeout = withTimeout<number | null>();
+    const child = spawn("sh", ["-c", "echo hello >&3"], {
+      stdio: ["inherit", "inherit", "inherit", fd],
+    });
+    child.on("close", (code) => timeout.resolve(code));
+    assertEquals(await timeout.promise, 0);
+    fs.closeSync(fd);
+    assertEquals(await Deno.readTextFile(file), "hello\n");
+  },
+});
+
+Deno.test({
+  name: "[node/child_process spawn] writes corked chunks to extra stdio pipes",
+  ignore: Deno.build.os === "windows",
+  async fn() {
+    const timeout = withTimeout<number | null>();
+    const child = spawn("sh", ["-c", "head -c 10 <&3"], {
+      stdio: ["inherit", "pipe", "inherit", "pipe"],
+    });
+    let stdout = "";
+    child.stdout?.on("data", (data) => {
+      stdout += data;
+    });
+    child.on("close", (code) => timeout.resolve(code));
+
+    const pipe = child.stdio[3] as Writable;
+    pipe.cork();
+    pipe.write("hello");
+    pipe.write("world");
+    pipe.uncork();
+
+    assertEquals(await timeout.promise, 0);
+    assertEquals(stdout, "helloworld");
+  },
+});
+
+Deno.test({
+  name: "[node/child_process spawn] Deno children write to extra stdio pipes",
+  // On unix the child would have to open /dev/fd/3 instead
+  ignore: Deno.build.os !== "windows",
+  async fn() {
+    const timeout = withTimeout<number | null>();
+    const child = spawn(Deno.execPath(), [
+      "eval",
+      "import fs from 'node:fs'; fs.writeSync(3, 'hello');",
+    ], {
+      stdio: ["inherit", "inherit", "inherit", "pipe"],
+    });
+    let output = "";
+    child.stdio[3]?.on("data", (data) => {
+      output += data;
+    });
+    child.on("close", (code) => timeout.resolve(code));
+
+    assertEquals(await timeout.promise, 0);
+    assertEquals(output, "hello");
+  },
+});
+
+Deno.test({
+  name: "[node/child_process spawnSync] writes input to stdin",
+  ignore: Deno.build.os === "windows",
+  fn() {
+    const result = spawnSync("cat", [], { input: "hello" });
+    assertEquals(result.stdout.toString(), "hello");
+  },
+});
+
+Deno.test({
+  name: "[node/child_process spawnSync] supports argv0",
+  ignore: Deno.build.os === "windows",
+  fn() {
+    const result = spawnSync("sh", ["-c", "echo $0"], { argv0: "custom" });
+    assertEquals(result.stdout.toString(), "custom\n");
+  },
+});
+
+Deno.test({
+  name: "[node/child_process spawnSync] result has the same shape as Node",
+  ignore: Deno.build.os === "windows",
+  fn() {
+    const result = spawnSync("cat", [], {
+      input: new Uint8Array([104, 105]),
+      encoding: "utf8",
+    });
+    assertEquals(Object.keys(result), [
+      "status",
+      "signal",
+      "output",
+      "pid",
+      "stdout",
+      "stderr",
+    ]);
+    assertEquals(result.status, 0);
+    assertEquals(result.signal, null);
+    assertEquals(result.output, [null, "hi", ""]);
+    assert(result.pid > 0);
+
+    const killed = spawnSync("sh", ["-c", "kill -9 $$"]);
+    assertEquals(killed.status, null);
+    assertEquals(killed.signal, "SIGKILL");
+
+    const missing = spawnSync("does-not-exist");
+    assertEquals(missing.error?.message, "spawnSync does-not-exist ENOENT");
+    assertEquals(missing.status, null);
+    assertEquals(missing.output, null);
+    assertEquals(missing.pid, 0);
+
+    assertThrows(
+      // deno-lint-ignore no-explicit-any
+      () => spawnSync("cat", [], { input: 5 as any }),
+      TypeError,
+      'The "options.stdio[0]" property must be of type string',
+    );
+  },
+});
+
+Deno.test({
+  name: "[node/child_process] process.binding('spawn_sync').spawn",
+  ignore: Deno.build.os === "windows",
+  fn() {
+    // @ts-ignore: untyped internal binding, not actually supposed to be
+    // used by userland modules in Node.js
+    const { spawn } = process.binding("spawn_sync");
+    const result = spawn({
+      file: "sh",
+      args: ["sh", "-c", "cat; echo $FOO >&2"],
+      envPairs: ["FOO=bar"],
+      stdio: [
+        { type: "pipe", readable: true, input: Buffer.from("hello") },
+        { type: "pipe", writable: true },
+        { type: "pipe", writable: true },
+      ],
+    });
+    assertEquals(result.status, 0);
+    assertEquals(result.output[0], null);
+    assertEquals(result.output[1].toString(), "hello");
+    assertEquals(result.output[2].toString(), "bar\n");
+
+    const missing = spawn({
+      file: "does-not-exist",
+      args: ["does-not-exist"],
+      envPairs: [],
+      stdio: [{ type: "ignore" }, { type: "ignore" }, { type: "ignore" }],
+    });
+    assertEquals(missing, {
+      error: -2,
+      status: null,
+      signal: null,
+      output: null,
+      pid: 0,
+    });
+  },
+});
+
+Deno.test(async function ipcAdvancedSerialization() {
+  const timeout = withTimeout<void>();
+  const script = `
+    process.on("message", (message) => {
+      process.send({ echo: message, map: new Map([["a", 1n]]) });
+      process.channel.unref();
+    });
+  `;
+  const file = await Deno.makeTempFile();
+  await Deno.writeTextFile(file, script);
+  const child = CP.fork(file, [], {
+    stdio: ["inherit", "inherit", "inherit", "ipc"],
+    serialization: "advanced",
+  });
+  const message = {
+    date: new Date(0),
+    bytes: new Uint8Array([1, 2, 3]),
+    set: new Set([undefined, NaN]),
+  };
+  let received;
+  child.on("message", (message) => {
+    received = message;
+  });
+  child.on("close", () => timeout.resolve());
+  child.send(message);
+  await timeout.promise;
+  assertEquals(received, { echo: message, map: new Map([["a", 1n]]) });
+});
+
+Deno.test(function invalidIpcSerialization() {
+  assertThrows(
+    () =>
+      CP.fork("./foo.js", [], {
+        // deno-lint-ignore no-explicit-any
+        serialization: "yaml" as any,
+      }),
+    TypeError,
+    'The property \'options.serialization\' must be "json" or "advanced"',
+  );
+});
+
+Deno.test({
+  name: "[node/child_process fork] sends sockets to the child",
+  async fn() {
+    const script = `
+      process.on("message", (message, socket) => {
+        socket.end(message + " from child");
+        process.channel.unref();
+      });
+    `;
+    const file = await Deno.makeTempFile();
+    await Deno.writeTextFile(file, script);
+    const child = CP.fork(file, [], {
+      stdio: ["inherit", "inherit", "inherit", "ipc"],
+    });
+    const childClosed = withTimeout<void>();
+    child.on("close", () => childClosed.resolve());
+
+    const server = net.createServer({ pauseOnConnect: true }, (socket) => {
+      child.send("hello", socket);
+    });
+    const listening = Promise.withResolvers<void>();
+    server.listen(0, "127.0.0.1", () => listening.resolve());
+    await listening.promise;
+    const { port } = server.address() as { port: number };
+
+    const received = withTimeout<string>();
+    const client = net.connect(port, "127.0.0.1");
+    let data = "";
+    client.on("data", (chunk) => {
+      data += chunk;
+    });
+    client.on("end", () => received.resolve(data));
+    assertEquals(await received.promise, "hello from child");
+
+    client.destroy();
+    server.close();
+    await childClosed.promise;
+  },
+});
+
+Deno.test({
+  name: "[node/child_process fork] throws for handles that can't be sent",
+  async fn() {
+    const file = await Deno.makeTempFile();
+    await Deno.writeTextFile(file, "");
+    const childClosed = withTimeout<void>();
+    const child = CP.fork(file, [], {
+      stdio: ["inherit", "inherit", "inherit", "ipc"],
+    });
+    child.on("close", () => childClosed.resolve());
+    assertThrows(
+      // deno-lint-ignore no-explicit-any
+      () => child.send("hello", {} as any),
+      TypeError,
+      "This handle type cannot be sent",
+    );
+    await childClosed.promise;
+  },
+});
@@ -1,5 +1,6 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 import { assertEquals } from "@std/assert";
+import { fromFileUrl } from "@std/path";
 import cluster from "node:cluster";
 import * as clusterNamed from "node:cluster";
 
@@ -41,3 +42,26 @@ Deno.test("[node/cluster] has all node exports", () => {
   // @ts-ignore Our @types/node version is too old
   assertEquals(cluster.isMaster, clusterNamed.isMaster);
 });
+
+for (const kind of ["net", "http", "dgram"]) {
+  for (const policy of ["rr", "none"]) {
+    Deno.test(
+      `[node/cluster] workers share a ${kind} server (${policy})`,
+      async () => {
+        const script = fromFileUrl(
+          new URL("./testdata/cluster_server.js", import.meta.url),
+        );
+        const { code, stdout, stderr } = await new Deno.Command(
+          Deno.execPath(),
+          {
+            args: ["run", "-A", script, kind],
+            env: { NODE_CLUSTER_SCHED_POLICY: policy },
+          },
+        ).output();
+        const decoder = new TextDecoder();
+        assertEquals(code, 0, decoder.decode(stderr));
+        assertEquals(decoder.decode(stdout).trim(), "ok");
+      },
+    );
+  }
+}
@@ -1,6 +1,6 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
-import { assertEquals } from "@std/assert";
+import { assertEquals, assertThrows } from "@std/assert";
 import { execCode } from "../unit/test_util.ts";
 import { createSocket } from "node:dgram";
 
@@ -57,3 +57,86 @@ Deno.test("[node/dgram] udp unref", {
     `);
   assertEquals(statusCode, 0);
 });
+
+Deno.test("[node/dgram] multicast messages", {
+  permissions: { read: true, run: true, net: true },
+}, async () => {
+  const { promise, resolve } = Promise.withResolvers<string>();
+  const group = "239.255.0.1";
+
+  const receiver = createSocket({ type: "udp4", reuseAddr: true });
+  const sender = createSocket("udp4");
+  receiver.on("message", (msg) => resolve(`${msg}`));
+  await new Promise<void>((resolve) => receiver.bind(0, resolve));
+  receiver.addMembership(group, "127.0.0.1");
+
+  await new Promise<void>((resolve) => sender.bind(0, "127.0.0.1", resolve));
+  sender.setMulticastInterface("127.0.0.1");
+  sender.setMulticastTTL(1);
+  sender.setMulticastLoopback(true);
+  sender.send("hello", receiver.address().port, group);
+
+  assertEquals(await promise, "hello");
+  receiver.dropMembership(group, "127.0.0.1");
+  // the membership has been dropped already
+  assertThrows(
+    () => receiver.dropMembership(group, "127.0.0.1"),
+    Error,
+    "dropMembership EADDRNOTAVAIL",
+  );
+  assertThrows(
+    () => receiver.addMembership("nope"),
+    Error,
+    "addMembership EINVAL",
+  );
+  receiver.close();
+  sender.close();
+});
+
+Deno.test("[node/dgram] socket options", {
+  permissions: { read: true, run: true, net: true },
+}, async () => {
+  const socket = createSocket("udp4");
+  assertThrows(() => socket.setTTL(16), Error, "setTTL EBADF");
+
+  await new Promise<void>((resolve) => socket.bind(0, "127.0.0.1", resolve));
+  assertEquals(socket.setTTL(16), 16);
+  assertEquals(socket.setMulticastTTL(0), 0);
+  socket.setBroadcast(true);
+  socket.setMulticastInterface("0.0.0.0");
+  assertThrows(() => socket.setTTL(256), Error, "setTTL EINVAL");
+  assertThrows(
+    () => socket.setMulticastTTL(256),
+    Error,
+    "setMulticastTTL EINVAL",
+  );
+  assertThrows(
+    () => socket.setMulticastInterface("nope"),
+    Error,
+    "setMulticastInterface EINVAL",
+  );
+  socket.close();
+});
+
+Deno.test("[node/dgram] reuseAddr", {
+  permissions: { read: true, run: true, net: true },
+}, async () => {
+  const first = createSocket({ type: "udp4", reuseAddr: true });
+  await new Promise<void>((resolve) => first.bind(0, resolve));
+  const { port } = first.address();
+
+  const second = createSocket({ type: "udp4", reuseAddr: true });
+  await new Promise<void>((resolve) => second.bind(port, resolve));
+  assertEquals(second.address().port, port);
+
+  // sockets without `reuseAddr` can't bind to the port
+  const third = createSocket("udp4");
+  const { promise, resolve } = Promise.withResolvers<Error>();
+  third.on("error", resolve);
+  third.bind(port);
+  assertEquals((await promise as Error & { code: string }).code, "EADDRINUSE");
+
+  third.close();
+  second.close();
+  first.close();
+});
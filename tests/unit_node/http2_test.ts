@@ -7,7 +7,7 @@ import { Buffer } from "node:buffer";
 import { readFile } from "node:fs/promises";
 import { join } from "node:path";
 import * as net from "node:net";
-import { assert, assertEquals } from "@std/assert";
+import { assert, assertEquals, assertThrows } from "@std/assert";
 import { curlRequest } from "../unit/test_util.ts";
 
 for (const url of ["http://127.0.0.1:4246", "https://127.0.0.1:4247"]) {
@@ -378,3 +378,166 @@ Deno.test("[node/http2 client] connection states", async () => {
 
   assertEquals(actual, expected);
 });
+
+// Resolves with the port once `server` listens, and closes it with all of its
+// sessions when the returned `close()` is called.
+function listen(server: net.Server) {
+  const sessions: Promise<void>[] = [];
+  server.on("session", (session) => {
+    const { promise, resolve } = Promise.withResolvers<void>();
+    session.on("close", () => resolve());
+    sessions.push(promise);
+  });
+  const { promise, resolve } = Promise.withResolvers<number>();
+  server.listen(0, () => resolve((<net.AddressInfo> server.address()).port));
+  return {
+    port: promise,
+    async close() {
+      server.close();
+      await Promise.all(sessions);
+    },
+  };
+}
+
+Deno.test("[node/http2 server] streams with trailers", {
+  ignore: Deno.build.os === "windows",
+}, async () => {
+  const server = http2.createServer();
+  let body = "";
+  server.on("stream", (stream, headers) => {
+    assertEquals(headers[":method"], "POST");
+    assertEquals(headers[":path"], "/echo?foo=bar");
+    assertEquals(headers["x-request"], "1");
+    assertEquals(stream.id, 1);
+    assertEquals(stream.session?.socket.remoteAddress, "127.0.0.1");
+    stream.setEncoding("utf8");
+    stream.on("data", (chunk) => body += chunk);
+    stream.on("end", () => {
+      stream.respond({
+        ":status": 201,
+        "content-type": "text/plain",
+        "x-response": "1",
+      }, { waitForTrailers: true });
+      stream.on("wantTrailers", () => {
+        stream.sendTrailers({ "grpc-status": "0" });
+      });
+      stream.end(body.toUpperCase());
+    });
+  });
+  const { port, close } = listen(server);
+
+  const client = http2.connect(`http://127.0.0.1:${await port}`);
+  const req = client.request({
+    ":method": "POST",
+    ":path": "/echo?foo=bar",
+    "x-request": "1",
+  });
+  let headers;
+  let trailers;
+  let data = "";
+  req.on("response", (h) => headers = h);
+  req.on("trailers", (t) => trailers = t);
+  req.setEncoding("utf8");
+  req.on("data", (chunk) => data += chunk);
+  const ended = Promise.withResolvers<void>();
+  req.on("end", () => ended.resolve());
+  req.write("hello ");
+  req.end("world");
+  await ended.promise;
+  client.close();
+  await close();
+
+  assertEquals(body, "hello world");
+  assertEquals(data, "HELLO WORLD");
+  assertEquals(headers[":status"], 201);
+  assertEquals(headers["content-type"], "text/plain");
+  assertEquals(headers["x-response"], "1");
+  assertEquals(trailers, { "grpc-status": "0" });
+});
+
+Deno.test("[node/http2 server] respondWithFile and push streams", {
+  ignore: Deno.build.os === "windows",
+}, async () => {
+  const file = import.meta.filename!;
+  const server = http2.createServer((req, res) => {
+    assertEquals(req.httpVersion, "2.0");
+    assertEquals(res.stream.pushAllowed, false);
+    assertThrows(
+      () => res.stream.pushStream({ ":path": "/pushed" }, () => {}),
+      Error,
+      "HTTP/2 client has disabled push streams",
+    );
+    res.stream.respondWithFile(file, { "content-type": "text/plain" }, {
+      offset: 3,
+      length: 8,
+    });
+  });
+  const { port, close } = listen(server);
+
+  const client = http2.connect(`http://127.0.0.1:${await port}`);
+  const req = client.request({ ":path": "/" });
+  let headers;
+  let data = "";
+  req.on("response", (h) => headers = h);
+  req.setEncoding("utf8");
+  req.on("data", (chunk) => data += chunk);
+  const ended = Promise.withResolvers<void>();
+  req.on("end", () => ended.resolve());
+  req.end();
+  await ended.promise;
+  client.close();
+  await close();
+
+  const contents = await readFile(file, "utf8");
+  assertEquals(data, contents.slice(3, 11));
+  assertEquals(headers["content-length"], "8");
+});
+
+Deno.test("[node/http2 server] stream close resets the client stream", {
+  ignore: Deno.build.os === "windows",
+}, async () => {
+  const server = http2.createServer();
+  const closed = Promise.withResolvers<number>();
+  server.on("stream", (stream) => {
+    stream.on("close", () => closed.resolve(stream.rstCode));
+    stream.close(http2.constants.NGHTTP2_CANCEL);
+  });
+  const { port, close } = listen(server);
+
+  const client = http2.connect(`http://127.0.0.1:${await port}`);
+  const req = client.request({ ":path": "/" });
+  const reqClosed = Promise.withResolvers<void>();
+  req.on("error", () => {});
+  req.on("close", () => reqClosed.resolve());
+  req.resume();
+  req.end();
+  assertEquals(await closed.promise, http2.constants.NGHTTP2_CANCEL);
+  await reqClosed.promise;
+  client.close();
+  await close();
+});
+
+Deno.test("[node/http2.createSecureServer()]", {
+  ignore: Deno.build.os === "windows",
+}, async () => {
+  const tlsDir = join(import.meta.dirname!, "..", "testdata", "tls");
+  const server = http2.createSecureServer({
+    key: await readFile(join(tlsDir, "localhost.key")),
+    cert: await readFile(join(tlsDir, "localhost.crt")),
+  }, (req, res) => {
+    res.setHeader("content-type", "text/plain");
+    res.end(`${req.httpVersion} ${req.scheme} ${req.url}`);
+  });
+  const { port, close } = listen(server);
+
+  const client = Deno.createHttpClient({
+    caCerts: [await readFile(join(tlsDir, "RootCA.pem"), "utf8")],
+  });
+  const res = await fetch(`https://localhost:${await port}/secure`, {
+    client,
+  });
+  assertEquals(res.headers.get("content-type"), "text/plain");
+  assertEquals(await res.text(), "2.0 https /secure");
+  client.close();
+  await close();
+});
@@ -0,0 +1,83 @@
+import cluster from "node:cluster";
+import dgram from "node:dgram";
+import http from "node:http";
+import net from "node:net";
+import process from "node:process";
+
+const kind = process.argv[2];
+const numWorkers = 2;
+const numRequests = 4;
+
+function request(port) {
+  if (kind === "dgram") {
+    return new Promise((resolve, reject) => {
+      const socket = dgram.createSocket("udp4");
+      socket.on("message", (msg) => {
+        socket.close();
+        resolve(`${msg}`);
+      });
+      socket.on("error", reject);
+      socket.send("ping", port, "127.0.0.1");
+    });
+  }
+  if (kind === "http") {
+    return fetch(`http://127.0.0.1:${port}/`).then((res) => res.text());
+  }
+  return new Promise((resolve, reject) => {
+    let data = "";
+    const conn = net.connect(port, "127.0.0.1");
+    conn.setEncoding("utf8");
+    conn.on("data", (chunk) => data += chunk);
+    conn.on("end", () => resolve(data));
+    conn.on("error", reject);
+  });
+}
+
+if (cluster.isPrimary) {
+  const ports = [];
+  let exited = 0;
+
+  cluster.on("listening", async (_worker, address) => {
+    ports.push(address.port);
+    if (ports.length < numWorkers) {
+      return;
+    }
+    if (ports[0] !== ports[1]) {
+      throw new Error(`workers listen on different ports: ${ports}`);
+    }
+    for (let i = 0; i < numRequests; i++) {
+      const id = Number(await request(ports[0]));
+      if (!cluster.workers[id]) {
+        throw new Error(`unexpected response: ${id}`);
+      }
+    }
+    cluster.disconnect();
+  });
+
+  cluster.on("exit", (worker, code) => {
+    if (code !== 0 || !worker.exitedAfterDisconnect) {
+      throw new Error(`worker ${worker.id} exited with code ${code}`);
+    }
+    if (++exited === numWorkers) {
+      console.log("ok");
+    }
+  });
+
+  for (let i = 0; i < numWorkers; i++) {
+    cluster.fork();
+  }
+} else {
+  const id = `${cluster.worker.id}`;
+  if (kind === "dgram") {
+    const socket = dgram.createSocket("udp4");
+    socket.on("message", (_msg, rinfo) => {
+      socket.send(id, rinfo.port, rinfo.address);
+    });
+    socket.bind(0, "127.0.0.1");
+  } else {
+    const server = kind === "http"
+      ? http.createServer((_req, res) => res.end(id))
+      : net.createServer((conn) => conn.end(id));
+    server.listen(0, "127.0.0.1");
+  }
+}
@@ -1,5 +1,5 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
-import { assertEquals, assertThrows } from "@std/assert";
+import { assert, assertEquals, assertThrows } from "@std/assert";
 import {
   createContext,
   isContext,
@@ -179,3 +179,59 @@ Deno.test({
     assertEquals(result, 1);
   },
 });
+
+Deno.test({
+  name: "vm runInContext timeout",
+  fn() {
+    const context = createContext({ count: 0 });
+    assertThrows(
+      () => runInContext("while (true) count++", context, { timeout: 10 }),
+      Error,
+      "Script execution timed out after 10ms",
+    );
+    assert(context.count > 0);
+
+    // The context keeps working, and a timeout that races with the end of a
+    // script doesn't terminate the code that follows it.
+    for (let i = 0; i < 100; i++) {
+      try {
+        runInContext("count = 1", context, { timeout: 1 });
+      } catch (error) {
+        assertEquals(
+          (error as { code: string }).code,
+          "ERR_SCRIPT_EXECUTION_TIMEOUT",
+        );
+      }
+    }
+    assertEquals(runInThisContext("1 + 1"), 2);
+  },
+});
+
+Deno.test({
+  name: "vm createContext microtaskMode",
+  fn() {
+    const code = "Promise.resolve().then(() => resolved = true)";
+
+    const context = createContext({ resolved: false });
+    runInContext(code, context);
+    assertEquals(context.resolved, false);
+
+    const isolated = createContext({ resolved: false }, {
+      microtaskMode: "afterEvaluate",
+    });
+    runInContext(code, isolated);
+    assertEquals(isolated.resolved, true);
+
+    // The timeout also covers the microtasks run after the evaluation.
+    assertThrows(
+      () =>
+        runInContext(
+          "Promise.resolve().then(() => { while (true); })",
+          isolated,
+          { timeout: 10 },
+        ),
+      Error,
+      "Script execution timed out after 10ms",
+    );
+  },
+});
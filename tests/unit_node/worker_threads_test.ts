@@ -11,6 +11,7 @@ import { fromFileUrl, relative, SEPARATOR } from "@std/path";
 import * as workerThreads from "node:worker_threads";
 import { EventEmitter, once } from "node:events";
 import process from "node:process";
+import type { Readable } from "node:stream";
 
 Deno.test("[node/worker_threads] BroadcastChannel is exported", () => {
   assertEquals<unknown>(workerThreads.BroadcastChannel, BroadcastChannel);
@@ -621,3 +622,80 @@ Deno.test({
     worker.terminate();
   },
 });
+
+Deno.test({
+  name: "[node/worker_threads] Worker resourceLimits",
+  async fn() {
+    const worker = new workerThreads.Worker(
+      `
+      import { parentPort, resourceLimits } from "node:worker_threads";
+      parentPort.postMessage(resourceLimits);
+      `,
+      {
+        eval: true,
+        resourceLimits: { maxOldGenerationSizeMb: 64, stackSizeMb: 8 },
+      },
+    );
+    const expected = {
+      maxYoungGenerationSizeMb: -1,
+      maxOldGenerationSizeMb: 64,
+      codeRangeSizeMb: -1,
+      stackSizeMb: 8,
+    };
+    assertEquals(worker.resourceLimits, expected);
+    assertEquals((await once(worker, "message"))[0], expected);
+    worker.terminate();
+  },
+});
+
+Deno.test({
+  name: "[node/worker_threads] Worker stdout and stderr capture",
+  async fn() {
+    const worker = new workerThreads.Worker(
+      `
+      import process from "node:process";
+      console.log("hello");
+      process.stdout.write("from the worker\\n");
+      console.error("oops");
+      `,
+      { eval: true, stdout: true, stderr: true },
+    );
+    const read = async (stream: Readable) => {
+      let data = "";
+      for await (const chunk of stream) {
+        data += chunk;
+      }
+      return data;
+    };
+    const [stdout, stderr] = await Promise.all([
+      read(worker.stdout),
+      read(worker.stderr),
+    ]);
+    assertEquals(stdout, "hello\nfrom the worker\n");
+    assertEquals(stderr, "oops\n");
+  },
+});
+
+Deno.test({
+  name: "[node/worker_threads] Worker.getHeapSnapshot",
+  async fn() {
+    const worker = new workerThreads.Worker(
+      `
+      import { parentPort } from "node:worker_threads";
+      parentPort.on("message", () => parentPort.close());
+      parentPort.postMessage("ready");
+      `,
+      { eval: true },
+    );
+    await once(worker, "message");
+    const stream = await worker.getHeapSnapshot();
+    let data = "";
+    for await (const chunk of stream) {
+      data += chunk;
+    }
+    const snapshot = JSON.parse(data);
+    assert(Array.isArray(snapshot.nodes));
+    assert(snapshot.snapshot.node_count > 0);
+    worker.terminate();
+  },
+});